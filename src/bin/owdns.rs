@@ -4,10 +4,27 @@ use std::process::exit;
 use std::time::Duration;
 use mimalloc::MiMalloc;
 use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 use tracing::{debug, error, info};
-use tracing_subscriber::{prelude::*, EnvFilter, fmt};
-use oxide_wdns::server::args::CliArgs;
-use oxide_wdns::server::config::ServerConfig;
+use tracing_subscriber::{prelude::*, reload};
+use oxide_wdns::common::consts::{PROXY_PROTOCOL_LISTENER_DOH, SHUTDOWN_CLEANUP_TIMEOUT_SECS, SHUTDOWN_FLUSH_TIMEOUT_SECS};
+use oxide_wdns::server::args::{CliArgs, Command};
+use oxide_wdns::server::acme::AcmeManager;
+use oxide_wdns::server::config::{AcmeChallengeType, LoggingConfig, ServerConfig};
+use oxide_wdns::server::config_template::generate_default_config;
+use oxide_wdns::server::dns_server::bind_dns_server;
+use oxide_wdns::server::dot_server::bind_dot_server;
+use oxide_wdns::server::http3::{bind_h3, serve_h3, shutdown_h3};
+use oxide_wdns::server::log_level::LogLevelControl;
+use oxide_wdns::server::proxy_protocol::ProxyProtocol;
+use oxide_wdns::server::logging::{build_filter, format_layer};
+use oxide_wdns::server::routing::Router;
+use oxide_wdns::server::route_test::{build_router, read_domains, route_test};
+use oxide_wdns::server::tls::{serve_tls, TlsContext};
+use oxide_wdns::server::http_conn::{serve_plain, ConnectionLimits};
+#[cfg(unix)]
+use oxide_wdns::server::unix::{bind_unix, serve_unix};
 use oxide_wdns::server::DoHServer;
 use std::sync::Arc;
 use clap::Parser;
@@ -17,67 +34,251 @@ use tokio_graceful_shutdown::{Toplevel, SubsystemHandle};
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-// 初始化日志系统
-fn init_logging(args: &CliArgs) {
-    // 从环境变量获取日志级别，或根据调试参数设置
-    let filter = if let Ok(filter) = EnvFilter::try_from_default_env() {
-        filter
-    } else if args.debug {
-        // 启用调试模式，显示更详细的日志
-        EnvFilter::new("oxide_wdns=debug,tower_http=debug,owdns=debug,info")
-    } else {
-        // 正常模式，仅显示 info 级别及以上
-        EnvFilter::new("oxide_wdns=info,owdns=info,tokio_graceful_shutdown=info")
-    };
+// 初始化日志系统，返回运行时修改过滤规则的控制句柄
+fn init_logging(args: &CliArgs, config: &LoggingConfig) -> LogLevelControl {
+    // 通过 reload 层包装过滤规则，以便运行时修改
+    let (filter, handle) = reload::Layer::new(build_filter(config, args.debug));
     
-    // 创建日志格式化器
-    let fmt_layer = fmt::layer()
-        .with_target(true)
-        .with_level(true)
-        .with_ansi(false); // 关闭彩色输出
-        
     // 注册日志订阅器
     tracing_subscriber::registry()
         .with(filter)
-        .with(fmt_layer)
+        .with(format_layer(config, std::io::stdout))
         .init();
     
     // 如果启用调试模式，输出调试信息
     if args.debug {
         debug!("Debug logging enabled - verbose output mode active");
     }
-} 
+    
+    LogLevelControl::new(handle)
+}
 
 // 定义 owdns 服务子系统
 async fn owdns_server_subsystem(
     subsys: SubsystemHandle,
     config: ServerConfig,
     doh_server: Arc<DoHServer>,
+    tls: Option<Arc<TlsContext>>,
+    acme: Option<Arc<AcmeManager>>,
 ) -> Result<(), anyhow::Error> {
-    let (app_router, dns_cache) =
+    let components =
         doh_server.build_application_components().await.map_err(|e| {
             error!("Failed to build application components: {}", e);
             anyhow::anyhow!("Failed to build application components: {}", e)
         })?;
 
-    let addr = config.http.listen_addr;
-    let listener = TcpListener::bind(addr).await.map_err(|e| {
-        error!("Failed to bind to address {}: {}", addr, e);
-        anyhow::anyhow!("Failed to bind to address {}: {}", addr, e)
+    // 先绑定所有 DoH 监听地址，任一地址绑定失败时直接退出
+    let mut listeners = Vec::with_capacity(config.http.listen_addr.len());
+    for addr in config.http.listen_addr.iter().copied() {
+        let listener = TcpListener::bind(addr).await.map_err(|e| {
+            error!("Failed to bind to address {}: {}", addr, e);
+            anyhow::anyhow!("Failed to bind to address {}: {}", addr, e)
+        })?;
+        info!("DoH server listening on: {} ({})", addr, if tls.is_some() { "HTTPS" } else { "HTTP" });
+        listeners.push((addr, listener));
+    }
+
+    // 配置了 listen_unix 时绑定 Unix 域套接字监听
+    #[cfg(unix)]
+    let unix_listener = match &config.http.listen_unix {
+        Some(path) => {
+            let listener = bind_unix(path, &config.http.unix_socket).map_err(|e| {
+                error!("{}", e);
+                anyhow::anyhow!("{}", e)
+            })?;
+            info!("DoH server listening on unix socket: {}", path.display());
+            Some(listener)
+        },
+        None => None,
+    };
+
+    // 配置了经典 DNS 监听时绑定 UDP 与 TCP 端口
+    let (dns_udp_socket, dns_tcp_listener) = bind_dns_server(&config.dns_server).await.map_err(|e| {
+        error!("{}", e);
+        anyhow::anyhow!("{}", e)
     })?;
-    info!("DoH server listening on: {}", addr);
+    let dot_listener = bind_dot_server(&config.dot_server).await.map_err(|e| {
+        error!("{}", e);
+        anyhow::anyhow!("{}", e)
+    })?;
+
+    // ACME 使用 HTTP-01 验证时，在独立端口上提供挑战响应
+    let http01_listen_addr = config.http.tls.as_ref()
+        .filter(|tls| tls.acme.enabled && tls.acme.challenge == AcmeChallengeType::Http01)
+        .and_then(|tls| tls.acme.http01_listen_addr);
+    let mut http01_app = acme.as_ref().map(|acme| acme.http01_routes());
+
+    // HTTPS 重定向与 ACME HTTP-01 共用同一地址时合并为一个监听，挑战路径优先于重定向
+    let redirect_listen_addr = config.http.https_redirect_listen_addr();
+    let mut redirect_app = components.redirect_app;
+    if redirect_listen_addr.is_some() && redirect_listen_addr == http01_listen_addr {
+        if let (Some(redirect), Some(http01)) = (redirect_app.take(), http01_app.take()) {
+            redirect_app = Some(http01.merge(redirect));
+        }
+    }
 
-    let server_future = axum::serve(
-        listener,
-        app_router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
-    );
+    // 绑定独立的管理、指标与明文 HTTP 监听（如已配置）
+    let mut auxiliary_servers = JoinSet::new();
+    let auxiliary_listeners = [
+        ("Admin", config.http.admin_listen_addr, components.admin_app),
+        ("Metrics", config.http.metrics_listen_addr, components.metrics_app),
+        ("ACME HTTP-01", http01_listen_addr, http01_app),
+        ("HTTPS redirect", redirect_listen_addr, redirect_app),
+    ];
+    for (name, listen_addr, router) in auxiliary_listeners {
+        let (Some(listen_addr), Some(router)) = (listen_addr, router) else {
+            continue;
+        };
+        let auxiliary_listener = TcpListener::bind(listen_addr).await.map_err(|e| {
+            error!("Failed to bind {} listener to address {}: {}", name, listen_addr, e);
+            anyhow::anyhow!("Failed to bind {} listener to address {}: {}", name, listen_addr, e)
+        })?;
+        info!("{} server listening on: {}", name, listen_addr);
+        auxiliary_servers.spawn(async move {
+            axum::serve(
+                auxiliary_listener,
+                router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            ).await.map_err(|e| anyhow::anyhow!("{} server error: {}", name, e))
+        });
+    }
+
+    // 启用 HTTP/3 时在 UDP 上绑定 QUIC 监听，与 TCP 监听共享证书与应用状态
+    let h3_endpoint = match (config.http.http3_listen_addr(), &tls, components.h3_app) {
+        (Some(h3_addr), Some(tls), Some(h3_app)) => {
+            let endpoint = bind_h3(h3_addr, tls).map_err(|e| {
+                error!("{}", e);
+                anyhow::anyhow!("{}", e)
+            })?;
+            info!("HTTP/3 server listening on: {} (UDP)", h3_addr);
+            let h3_server = endpoint.clone();
+            auxiliary_servers.spawn(async move {
+                serve_h3(h3_server, h3_app).await
+                    .map_err(|e| anyhow::anyhow!("HTTP/3 server error: {}", e))
+            });
+            Some(endpoint)
+        },
+        _ => None,
+    };
+
+    // 每个监听地址运行独立的接受循环，共享同一应用与服务器状态
+    // 配置了 TLS 时由 TLS 监听直接提供 HTTPS，否则使用明文 HTTP
+    // 收到 SIGHUP 时重新加载 HTTPS 与 DoT 证书并重新扫描规则目录
+    let reloadable_tls = tls.iter().cloned()
+        .chain(components.dot_server.as_ref().map(|dot_server| dot_server.tls()))
+        .collect();
+    spawn_reload_task(reloadable_tls, components.router.clone());
+    // 所有 DoH 监听使用相同的请求头读取超时、空闲 keep-alive 超时与单连接请求数限制
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let limits = ConnectionLimits::from_config(&config.http);
+    let enable_h2c = config.http.enable_h2c;
+    // 启用 PROXY 协议时，可信代理连接的客户端地址取自 PROXY 头
+    let proxy_protocol = config.http.proxy_protocol
+        .then(|| Arc::new(ProxyProtocol::from_config(&config.http, PROXY_PROTOCOL_LISTENER_DOH)));
+    let mut doh_servers = JoinSet::new();
+    for (addr, listener) in listeners {
+        let app = components.app.clone();
+        let tls = tls.clone();
+        let proxy_protocol = proxy_protocol.clone();
+        let mut shutdown_rx = shutdown_rx.clone();
+        doh_servers.spawn(async move {
+            let result = match tls {
+                Some(tls) => tokio::select! {
+                    result = serve_tls(listener, app, tls, limits, proxy_protocol) => result,
+                    _ = shutdown_rx.wait_for(|shutdown| *shutdown) => Ok(()),
+                },
+                None => tokio::select! {
+                    result = serve_plain(listener, app, limits, enable_h2c, proxy_protocol) => result,
+                    _ = shutdown_rx.wait_for(|shutdown| *shutdown) => Ok(()),
+                },
+            };
+            result.map_err(|e| anyhow::anyhow!("DoH server on {} error: {}", addr, e))
+        });
+    }
+
+    #[cfg(unix)]
+    if let (Some(listener), Some(unix_app)) = (unix_listener, components.unix_app.clone()) {
+        let mut shutdown_rx = shutdown_rx.clone();
+        doh_servers.spawn(async move {
+            tokio::select! {
+                result = serve_unix(listener, unix_app, limits) => {
+                    result.map_err(|e| anyhow::anyhow!("DoH server on unix socket error: {}", e))
+                },
+                _ = shutdown_rx.wait_for(|shutdown| *shutdown) => Ok(()),
+            }
+        });
+    }
+
+    // 经典 DNS 与 DoT 监听与 DoH 监听一同停止，在关闭前退出同样视为错误
+    if let Some(dns_server) = &components.dns_server {
+        if let Some(socket) = dns_udp_socket {
+            let dns_server = dns_server.clone();
+            let mut shutdown_rx = shutdown_rx.clone();
+            doh_servers.spawn(async move {
+                tokio::select! {
+                    result = dns_server.serve_udp(socket) => {
+                        result.map_err(|e| anyhow::anyhow!("DNS UDP server error: {}", e))
+                    },
+                    _ = shutdown_rx.wait_for(|shutdown| *shutdown) => Ok(()),
+                }
+            });
+        }
+        if let Some(listener) = dns_tcp_listener {
+            let dns_server = dns_server.clone();
+            let mut shutdown_rx = shutdown_rx.clone();
+            doh_servers.spawn(async move {
+                tokio::select! {
+                    result = dns_server.serve_tcp(listener) => {
+                        result.map_err(|e| anyhow::anyhow!("DNS TCP server error: {}", e))
+                    },
+                    _ = shutdown_rx.wait_for(|shutdown| *shutdown) => Ok(()),
+                }
+            });
+        }
+    }
+
+    if let (Some(dot_server), Some(listener)) = (components.dot_server.clone(), dot_listener) {
+        let mut shutdown_rx = shutdown_rx.clone();
+        doh_servers.spawn(async move {
+            tokio::select! {
+                result = dot_server.serve(listener) => {
+                    result.map_err(|e| anyhow::anyhow!("DoT server error: {}", e))
+                },
+                _ = shutdown_rx.wait_for(|shutdown| *shutdown) => Ok(()),
+            }
+        });
+    }
+
+    // 任一 DoH 监听在关闭前退出即视为错误
+    let server_future = async {
+        match doh_servers.join_next().await {
+            Some(Ok(Ok(()))) => Err::<(), _>(anyhow::anyhow!("DoH server stopped unexpectedly")),
+            Some(Ok(Err(e))) => Err(e),
+            Some(Err(e)) => Err(anyhow::anyhow!("DoH server task failed: {}", e)),
+            None => Err(anyhow::anyhow!("No DoH listen address configured")),
+        }
+    };
+
+    // 任一独立监听退出时结束；未配置独立监听时，该 future 永不完成
+    let auxiliary_future = async move {
+        match auxiliary_servers.join_next().await {
+            Some(Ok(result)) => result,
+            Some(Err(e)) => Err(anyhow::anyhow!("Auxiliary server task failed: {}", e)),
+            None => std::future::pending().await,
+        }
+    };
 
     // 将 axum 服务器与子系统的关闭信号集成
     tokio::select! {
         result = server_future => {
             if let Err(e) = result {
-                error!("Axum server error: {}", e);
-                return Err(anyhow::anyhow!("Axum server error: {}", e));
+                error!("{}", e);
+                return Err(e);
+            }
+        }
+        result = auxiliary_future => {
+            if let Err(e) = result {
+                error!("{}", e);
+                return Err(e);
             }
         }
         _ = subsys.on_shutdown_requested() => {
@@ -85,10 +286,50 @@ async fn owdns_server_subsystem(
         }
     };
 
+    // 先拒绝新请求并等待进行中的查询完成，超过宽限期后仍未完成的查询返回 503
+    let dropped = components.drain.drain().await;
+
+    // 通知规则更新与上游发现等后台任务退出
+    components.router.stop_background_tasks();
+    components.upstream.stop_background_tasks();
+
+    // 通知所有 DoH 与经典 DNS 监听停止接受新连接，并等待全部退出；
+    // 宽限期结束时只等待 503 响应发送完毕，之后强制关闭剩余连接
+    let _ = shutdown_tx.send(true);
+    let join_servers = async {
+        while let Some(result) = doh_servers.join_next().await {
+            match result {
+                Ok(Err(e)) => error!("{}", e),
+                Err(e) if !e.is_cancelled() => error!("DoH server task failed: {}", e),
+                _ => {},
+            }
+        }
+    };
+    if dropped > 0 {
+        if tokio::time::timeout(Duration::from_secs(SHUTDOWN_FLUSH_TIMEOUT_SECS), join_servers).await.is_err() {
+            doh_servers.abort_all();
+        }
+    } else {
+        join_servers.await;
+    }
+
+    // 移除 Unix 域套接字文件
+    #[cfg(unix)]
+    if let Some(path) = &config.http.listen_unix {
+        if let Err(e) = std::fs::remove_file(path) {
+            debug!("Failed to remove unix socket {}: {}", path.display(), e);
+        }
+    }
+
+    // 关闭 HTTP/3 监听，通知客户端连接关闭
+    if let Some(endpoint) = h3_endpoint {
+        shutdown_h3(&endpoint).await;
+    }
+
     info!("HTTP server shutdown successfully.");
     
     // 关闭 DNS 缓存
-    if let Err(e) = dns_cache.shutdown().await {
+    if let Err(e) = components.cache.shutdown().await {
         error!("Failed to shutdown DNS cache: {}", e);
     } else {
         info!("DNS cache shutdown successfully.");
@@ -97,23 +338,98 @@ async fn owdns_server_subsystem(
     Ok(())
 }
 
+// 监听 SIGHUP 信号，重新加载 TLS 证书并重新扫描规则目录，加载失败时继续使用当前证书与规则
+#[cfg(unix)]
+fn spawn_reload_task(tls_contexts: Vec<Arc<TlsContext>>, router: Arc<Router>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            error!("Failed to install SIGHUP handler, hot-reload disabled: {}", e);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            for tls in &tls_contexts {
+                info!("Received SIGHUP, reloading TLS certificate...");
+                if let Err(e) = tls.reload() {
+                    error!(error = %e, "Failed to reload TLS certificate, keeping current certificate");
+                }
+            }
+            router.reload_rule_dirs().await;
+        }
+    });
+}
+
+// 非 Unix 平台不支持 SIGHUP 热重载
+#[cfg(not(unix))]
+fn spawn_reload_task(_tls_contexts: Vec<Arc<TlsContext>>, _router: Arc<Router>) {}
+
+// 执行 route-test 子命令，返回进程退出码
+async fn run_route_test(config: &ServerConfig, domains: &[String], client_ip: Option<std::net::IpAddr>) -> i32 {
+    // 未指定域名或指定为 "-" 时从标准输入读取
+    let read_stdin = domains.is_empty() || domains.iter().any(|domain| domain == "-");
+    let mut domains = domains.iter().filter(|domain| domain.as_str() != "-").cloned().collect::<Vec<_>>();
+    if read_stdin {
+        match read_domains(std::io::stdin().lock()) {
+            Ok(stdin_domains) => domains.extend(stdin_domains),
+            Err(e) => {
+                error!(error = %e, "Failed to read domains from stdin");
+                return 1;
+            }
+        }
+    }
+
+    let router = match build_router(config).await {
+        Ok(router) => router,
+        Err(e) => {
+            error!(error = %e, "Failed to build router");
+            return 1;
+        }
+    };
+    for domain in &domains {
+        println!("{}", route_test(&router, domain, client_ip).await);
+    }
+    router.stop_background_tasks();
+    0
+}
+
 // 使用 tokio::main 宏让tokio自动决定线程数量
 #[tokio::main]
 async fn main() {
     // 解析命令行参数
     let args = CliArgs::parse();
     
+    // 输出默认配置后退出，不需要配置文件
+    if args.generate_config {
+        match generate_default_config() {
+            Ok(config) => {
+                print!("{}", config);
+                exit(0);
+            },
+            Err(e) => {
+                eprintln!("Failed to generate default configuration: {}", e);
+                exit(1);
+            }
+        }
+    }
+    
     // 验证命令行参数
     if let Err(e) = args.validate() {
         eprintln!("Parameter validation error: {}", e);
         exit(1);
     }
     
-    // 初始化日志
-    init_logging(&args);
+    // 先解析配置以按 logging 配置初始化日志，解析失败时使用默认日志配置输出错误
+    let config = ServerConfig::parse_file(&args.config);
+    let logging = config.as_ref().map(|config| config.logging.clone()).unwrap_or_default();
+    let log_level = Arc::new(init_logging(&args, &logging));
     
-    // 加载配置
-    let config = match ServerConfig::from_file(&args.config) {
+    // 验证配置
+    let config = match config.and_then(|config| config.test().map(|_| config)) {
         Ok(config) => {
             info!(
                 config_path = ?args.config,
@@ -145,10 +461,52 @@ async fn main() {
         }
     }
 
+    // 路由测试：输出每个域名的路由结果后退出
+    if let Some(Command::RouteTest { domains, client_ip }) = &args.command {
+        exit(run_route_test(&config, domains, *client_ip).await);
+    }
+
     info!("Initializing Oxide WDNS server...");
     
+    // 配置了 TLS 时预先加载证书，证书无效或与私钥不匹配时立即退出
+    let tls = match &config.http.tls {
+        Some(tls_config) => match TlsContext::new(tls_config) {
+            Ok(tls) => Some(Arc::new(tls)),
+            Err(e) => {
+                error!(error = %e, "Failed to initialize TLS");
+                exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // 启用 ACME 时启动证书签发与续期任务
+    let acme = match (&config.http.tls, &tls) {
+        (Some(tls_config), Some(tls)) if tls_config.acme.enabled => {
+            match AcmeManager::new(&tls_config.acme, tls.clone()) {
+                Ok(manager) => {
+                    let manager = Arc::new(manager);
+                    manager.spawn();
+                    Some(manager)
+                },
+                Err(e) => {
+                    error!(error = %e, "Failed to initialize ACME");
+                    exit(1);
+                }
+            }
+        },
+        _ => None,
+    };
+    
     // 创建 DoHServer 实例，传入debug参数
-    let doh_server = Arc::new(DoHServer::new(config.clone(), args.debug));
+    let mut doh_server = DoHServer::new(config.clone(), args.debug).with_log_level(log_level);
+    if let Some(acme) = &acme {
+        doh_server = doh_server.with_acme(acme.clone());
+    }
+    let doh_server = Arc::new(doh_server);
+    
+    // 关闭超时包含排空宽限期与之后的清理时间
+    let shutdown_timeout = Duration::from_secs(config.http.shutdown_grace_period_secs + SHUTDOWN_CLEANUP_TIMEOUT_SECS);
 
     // 使用 tokio-graceful-shutdown 设置顶层关闭处理
     // 创建并运行顶层控制器
@@ -156,14 +514,16 @@ async fn main() {
             // 克隆 Arc<DoHServer> 和 config
             let server_clone = doh_server.clone();
             let config_clone = config.clone();
+            let tls_clone = tls.clone();
+            let acme_clone = acme.clone();
             async move {
-                if let Err(e) = owdns_server_subsystem(subsys, config_clone, server_clone).await {
+                if let Err(e) = owdns_server_subsystem(subsys, config_clone, server_clone, tls_clone, acme_clone).await {
                     error!("Oxide WDNS server subsystem error: {:#}", e);
                 }
             }
         })
         .catch_signals()
-        .handle_shutdown_requests(Duration::from_secs(10))
+        .handle_shutdown_requests(shutdown_timeout)
         .await
     {
         error!("Oxide WDNS server shut down with error: {:#}", e);
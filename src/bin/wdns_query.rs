@@ -0,0 +1,40 @@
+// src/bin/wdns_query.rs
+
+//! oxide-wdns 解析器测试工具
+//!
+//! 这个工具用于测试 DoH 解析器的行为与性能，也可作为集成测试的驱动程序。
+//! 支持以下主要功能：
+//! - 以 Wireformat 或 JSON 格式发送 DNS 查询
+//! - 以表格形式输出应答记录（名称、TTL、类型、数据）
+//! - 通过 --repeat 统计 P50/P95/P99 延迟
+//! - 通过 --parallel 进行并发负载测试
+//! - 通过 --trace 输出响应中的 X-Request-ID
+
+use mimalloc::MiMalloc;
+use clap::Parser;
+use oxide_wdns::client::{QueryToolArgs, run_query_tool, print_error};
+
+// 使用 mimalloc 作为全局内存分配器
+#[global_allocator]
+static GLOBAL: MiMalloc = MiMalloc;
+
+#[tokio::main]
+async fn main() {
+    // 解析命令行参数
+    let args = QueryToolArgs::parse();
+
+    // 初始化全局颜色控制
+    colored::control::set_override(!args.no_color);
+
+    // 验证命令行参数
+    if let Err(err) = args.validate() {
+        eprintln!("Argument error: {}", err);
+        std::process::exit(1);
+    }
+
+    // 执行查询
+    if let Err(err) = run_query_tool(args).await {
+        print_error(&err);
+        std::process::exit(1);
+    }
+}
@@ -307,7 +307,7 @@ fn validate_response(response: &DohResponse, conditions: &[ValidationCondition])
 }
 
 // 构建配置好的 HTTP 客户端
-fn build_http_client(args: &CliArgs) -> ClientResult<Client> {
+pub(crate) fn build_http_client(args: &CliArgs) -> ClientResult<Client> {
     let mut client_builder = Client::builder()
         .timeout(Duration::from_secs(DEFAULT_HTTP_CLIENT_TIMEOUT));
     
@@ -6,9 +6,11 @@ pub mod error;
 pub mod request;
 pub mod response;
 pub mod core;
+pub mod query_tool;
 
 // 重新导出关键类型，方便外部使用
 pub use args::CliArgs;
 pub use error::{ClientError, ClientResult};
 pub use response::DohResponse;
-pub use core::{run_query, print_error}; 
\ No newline at end of file
+pub use core::{run_query, print_error};
+pub use query_tool::{QueryToolArgs, run_query_tool}; 
\ No newline at end of file
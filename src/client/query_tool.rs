@@ -0,0 +1,298 @@
+// src/client/query_tool.rs
+
+// wdns-query 测试工具的核心逻辑。
+//
+// 与 owdns-cli 共用请求构建与响应解析模块，在此基础上提供：
+// 1. 以表格形式 (名称、TTL、类型、数据) 输出应答记录。
+// 2. `--repeat N` 重复发送 N 次查询，并输出 P50/P95/P99 延迟。
+// 3. `--parallel N` 以 N 个并发工作任务发送查询，用于负载测试。
+// 4. `--trace` 输出响应头中的 `X-Request-ID`，便于与服务端日志关联。
+
+use crate::client::args::{CliArgs, DohFormat};
+use crate::client::core::build_http_client;
+use crate::client::error::{ClientError, ClientResult};
+use crate::client::response::DohResponse;
+use crate::client::{request, response};
+use crate::common::consts::DEFAULT_REQUEST_ID_HEADER;
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+use colored::Colorize;
+use hickory_proto::op::Message;
+use reqwest::Client;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+// wdns-query 命令行参数
+#[derive(Parser, Debug, Clone)]
+#[command(
+    name = "wdns-query",
+    author,
+    version,
+    about = "A DoH query tool for testing resolver behavior, latency and concurrency.\n\n\
+             Examples:\n\
+             - wdns-query --server https://localhost:8053/dns-query --name example.com --type A\n\
+             - wdns-query --server https://localhost:8053/dns-query --name example.com --repeat 100 --parallel 10"
+)]
+pub struct QueryToolArgs {
+    // DoH 服务器完整 URL
+    #[arg(long, required = true, help = "Full URL of the DoH server endpoint (e.g., https://localhost:8053/dns-query)")]
+    pub server: String,
+
+    // 要查询的域名
+    #[arg(long, required = true, help = "Domain name to query (e.g., example.com)")]
+    pub name: String,
+
+    // DNS 记录类型
+    #[arg(long = "type", default_value = "A", help = "DNS record type to query (e.g., A, AAAA, MX, TXT)")]
+    pub record_type: String,
+
+    // DoH 请求格式 (json 或 wire)
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DohFormat::Wire,
+        help = "DoH request format: 'wire' (application/dns-message) or 'json' (application/dns-json)"
+    )]
+    pub format: DohFormat,
+
+    // 查询总次数，大于 1 时输出延迟分位数
+    #[arg(long, default_value_t = 1, help = "Number of queries to send; prints P50/P95/P99 latency when greater than 1")]
+    pub repeat: u32,
+
+    // 并发工作任务数
+    #[arg(long, default_value_t = 1, help = "Number of concurrent workers used to send the queries")]
+    pub parallel: u32,
+
+    // 输出响应头中的请求 ID
+    #[arg(long, action = ArgAction::SetTrue, help = "Print the X-Request-ID header of each response")]
+    pub trace: bool,
+
+    // 跳过 TLS 证书验证
+    #[arg(short = 'k', long, action = ArgAction::SetTrue, help = "Skip TLS certificate verification (use with caution)")]
+    pub insecure: bool,
+
+    // 禁用终端中的彩色输出
+    #[arg(long, action = ArgAction::SetTrue, help = "Disable colored output in the terminal")]
+    pub no_color: bool,
+}
+
+impl QueryToolArgs {
+    // 验证命令行参数
+    pub fn validate(&self) -> Result<()> {
+        if self.repeat == 0 {
+            return Err(anyhow::anyhow!("--repeat must be at least 1"));
+        }
+        if self.parallel == 0 {
+            return Err(anyhow::anyhow!("--parallel must be at least 1"));
+        }
+        // URL 与记录类型沿用 owdns-cli 的校验规则
+        self.to_cli_args().validate()
+    }
+
+    // 转换为 owdns-cli 参数，以复用请求构建逻辑
+    pub fn to_cli_args(&self) -> CliArgs {
+        CliArgs {
+            server_url: self.server.clone(),
+            domain: self.name.clone(),
+            record_type: self.record_type.clone(),
+            format: self.format,
+            method: None,
+            http_version: None,
+            dnssec: false,
+            payload: None,
+            validate: None,
+            insecure: self.insecure,
+            verbose: 0,
+            no_color: self.no_color,
+        }
+    }
+}
+
+// 延迟分位数统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+impl LatencySummary {
+    // 使用最近秩 (nearest-rank) 方法计算分位数，样本为空时返回 None
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let percentile = |p: usize| {
+            let rank = (p * sorted.len()).div_ceil(100).max(1);
+            sorted[rank - 1]
+        };
+        Some(Self {
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+        })
+    }
+}
+
+// 单次查询的结果
+#[derive(Debug)]
+pub struct QueryOutcome {
+    // 查询序号 (从 1 开始)
+    pub index: u32,
+    // 查询耗时
+    pub duration: Duration,
+    // 响应或错误
+    pub result: ClientResult<DohResponse>,
+}
+
+impl QueryOutcome {
+    // 响应头中的请求 ID
+    pub fn request_id(&self) -> Option<&str> {
+        self.result.as_ref().ok()?
+            .headers
+            .get(DEFAULT_REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+    }
+}
+
+// 将应答部分格式化为表格 (NAME / TTL / TYPE / DATA)
+pub fn format_answer_table(message: &Message) -> String {
+    let rows: Vec<[String; 4]> = message.answers().iter()
+        .map(|record| [
+            record.name().to_string(),
+            record.ttl().to_string(),
+            record.record_type().to_string(),
+            record.data().map(|data| data.to_string()).unwrap_or_default(),
+        ])
+        .collect();
+
+    let header = ["NAME", "TTL", "TYPE", "DATA"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: [&str; 4]| {
+        format!("{:<w0$}  {:>w1$}  {:<w2$}  {}",
+            cells[0], cells[1], cells[2], cells[3],
+            w0 = widths[0], w1 = widths[1], w2 = widths[2])
+            .trim_end()
+            .to_string()
+    };
+
+    let mut table = format_row(header);
+    for row in &rows {
+        table.push('\n');
+        table.push_str(&format_row([&row[0], &row[1], &row[2], &row[3]]));
+    }
+    table
+}
+
+// 发送一次查询并记录耗时
+async fn send_query(client: &Client, args: &CliArgs) -> ClientResult<DohResponse> {
+    let request = request::build_doh_request(args, client).await?;
+    let start_time = Instant::now();
+    let http_response = client.execute(request).await?;
+    let mut doh_response = response::parse_doh_response(http_response).await?;
+    doh_response.duration = start_time.elapsed();
+    Ok(doh_response)
+}
+
+// 以指定并发度发送全部查询，结果按序号排序返回
+pub async fn execute_queries(args: &QueryToolArgs) -> ClientResult<Vec<QueryOutcome>> {
+    let cli_args = Arc::new(args.to_cli_args());
+    let client = build_http_client(&cli_args)?;
+    let next_index = Arc::new(AtomicU32::new(0));
+
+    // 每个工作任务循环领取序号，直到发送完 repeat 次查询
+    let mut workers = JoinSet::new();
+    for _ in 0..args.parallel.min(args.repeat) {
+        let client = client.clone();
+        let cli_args = Arc::clone(&cli_args);
+        let next_index = Arc::clone(&next_index);
+        let total = args.repeat;
+        workers.spawn(async move {
+            let mut outcomes = Vec::new();
+            loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                if index >= total {
+                    break;
+                }
+                let start_time = Instant::now();
+                let result = send_query(&client, &cli_args).await;
+                outcomes.push(QueryOutcome {
+                    index: index + 1,
+                    duration: start_time.elapsed(),
+                    result,
+                });
+            }
+            outcomes
+        });
+    }
+
+    let mut outcomes = Vec::with_capacity(args.repeat as usize);
+    while let Some(worker) = workers.join_next().await {
+        let worker_outcomes = worker
+            .map_err(|e| ClientError::Other(format!("Query worker failed: {}", e)))?;
+        outcomes.extend(worker_outcomes);
+    }
+    outcomes.sort_by_key(|outcome| outcome.index);
+    Ok(outcomes)
+}
+
+// 执行 wdns-query 并输出结果
+pub async fn run_query_tool(args: QueryToolArgs) -> ClientResult<()> {
+    let total_start = Instant::now();
+    let outcomes = execute_queries(&args).await?;
+    let total_elapsed = total_start.elapsed();
+
+    // 输出第一个成功响应的应答表格
+    let first_response = outcomes.iter().find_map(|outcome| outcome.result.as_ref().ok());
+    if let Some(response) = first_response {
+        println!("{} {}, {} {}",
+            ";; status:".bold(), response.message.response_code(),
+            "answers:".bold(), response.message.answers().len());
+        println!("{}", format_answer_table(&response.message));
+    }
+
+    // 输出每个查询的请求 ID 与错误
+    for outcome in &outcomes {
+        if args.trace {
+            println!("{} #{} {} {:?}",
+                ";; query".bold(), outcome.index,
+                outcome.request_id().unwrap_or("-"), outcome.duration);
+        }
+        if let Err(err) = &outcome.result {
+            eprintln!("{} #{}: {}", ";; query failed".red().bold(), outcome.index, err);
+        }
+    }
+
+    let failures = outcomes.iter().filter(|outcome| outcome.result.is_err()).count();
+    if args.repeat > 1 {
+        let samples: Vec<Duration> = outcomes.iter()
+            .filter(|outcome| outcome.result.is_ok())
+            .map(|outcome| outcome.duration)
+            .collect();
+        println!("\n{} {} queries, {} failed, {} workers, {:?} total",
+            ";; Summary:".bold(), outcomes.len(), failures, args.parallel, total_elapsed);
+        if let Some(summary) = LatencySummary::from_samples(&samples) {
+            println!("{} P50 {:?}, P95 {:?}, P99 {:?}",
+                ";; Latency:".bold(), summary.p50, summary.p95, summary.p99);
+        }
+    }
+
+    // 全部查询失败时返回第一个错误
+    if first_response.is_none() {
+        if let Some(QueryOutcome { result: Err(err), .. }) = outcomes.into_iter().next() {
+            return Err(err);
+        }
+    }
+
+    Ok(())
+}
@@ -21,11 +21,159 @@ pub fn default_listen_addr() -> SocketAddr {
     "0.0.0.0:3053".parse().unwrap()
 }
 
-// 默认服务器连接超时
-pub const DEFAULT_LISTEN_TIMEOUT: u64 = 120;
+// Unix 域套接字连接没有客户端 IP，统一视为来自本地回环地址
+pub fn unix_socket_peer_addr() -> SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+// 默认请求处理超时（秒），包含上游查询在内的端到端时限
+pub const DEFAULT_REQUEST_TIMEOUT: u64 = 120;
+
+// 默认请求头读取超时（秒）
+pub const DEFAULT_READ_HEADER_TIMEOUT: u64 = 10;
+
+// 默认空闲 keep-alive 连接的保持时间（秒）
+pub const DEFAULT_IDLE_KEEPALIVE_TIMEOUT: u64 = 60;
+
+// HTTP 服务器各项超时的上限（秒）
+pub const MAX_HTTP_SERVER_TIMEOUT_SECS: u64 = 3600;
+
+// 经典 DNS 监听 TCP 连接的默认空闲超时（秒）
+pub const DEFAULT_DNS_SERVER_TCP_IDLE_TIMEOUT: u64 = 10;
+
+// 经典 DNS 监听 TCP 连接空闲超时的上限（秒）
+pub const MAX_DNS_SERVER_TCP_IDLE_TIMEOUT: u64 = 3600;
+
+// DoT 监听连接的默认空闲超时（秒）
+pub const DEFAULT_DOT_SERVER_IDLE_TIMEOUT: u64 = 30;
+
+// DoT 监听连接空闲超时的上限（秒）
+pub const MAX_DOT_SERVER_IDLE_TIMEOUT: u64 = 3600;
+
+// DoT 单个连接上同时处理的查询数上限，超过时暂停读取后续查询
+pub const DOT_MAX_IN_FLIGHT_PER_CONNECTION: usize = 100;
 
-// 最大请求大小
-pub const MAX_REQUEST_SIZE: usize = 16 * 1024; // 16KB
+// 请求体大小上限的最大值（DNS 消息的最大长度）
+pub const MAX_REQUEST_BODY_SIZE: usize = 65535;
+
+// 请求体大小上限的最小值
+pub const MIN_REQUEST_BODY_SIZE: usize = 512;
+
+// 默认请求体大小上限
+pub const DEFAULT_MAX_REQUEST_BODY_SIZE: usize = MAX_REQUEST_BODY_SIZE;
+
+// DNS 消息头长度（字节），更短的消息不可能是有效查询
+pub const DNS_MESSAGE_HEADER_SIZE: usize = 12;
+
+// 默认 SERVFAIL 响应的 Retry-After（秒）
+pub const DEFAULT_SERVFAIL_RETRY_AFTER_SECS: u64 = 5;
+
+// 默认请求 ID 头名称
+pub const DEFAULT_REQUEST_ID_HEADER: &str = "X-Request-ID";
+
+// 默认的上游组覆盖请求头名称
+pub const DEFAULT_UPSTREAM_OVERRIDE_HEADER: &str = "X-Upstream-Group";
+
+// 客户端提供的请求 ID 最大长度，超过时重新生成
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+// Unix 域套接字默认文件权限（八进制）
+pub const DEFAULT_UNIX_SOCKET_MODE: &str = "0660";
+
+//
+// TLS 常量
+//
+
+// TLS 握手超时（秒）
+pub const TLS_HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+
+// TLS 记录层握手消息类型（ClientHello 的首字节）
+pub const TLS_HANDSHAKE_RECORD_TYPE: u8 = 0x16;
+
+// TLS 监听支持的 ALPN 协议（按优先级排序）
+pub const TLS_ALPN_PROTOCOLS: [&[u8]; 2] = [b"h2", b"http/1.1"];
+
+// HTTP/3 监听使用的 ALPN 协议
+pub const HTTP3_ALPN_PROTOCOL: &[u8] = b"h3";
+
+// DoT 监听使用的 ALPN 协议（RFC 7858）
+pub const DOT_ALPN_PROTOCOL: &[u8] = b"dot";
+
+//
+// PROXY 协议常量
+//
+
+// PROXY 协议拒绝指标中 DoH 监听的标签
+pub const PROXY_PROTOCOL_LISTENER_DOH: &str = "doh";
+
+// 读取 PROXY 协议头的超时（秒）
+pub const PROXY_PROTOCOL_HEADER_TIMEOUT_SECS: u64 = 5;
+
+// PROXY 协议 v1（文本格式）头前缀
+pub const PROXY_PROTOCOL_V1_PREFIX: &[u8] = b"PROXY ";
+
+// PROXY 协议 v1 头的最大长度（包括结尾的 CRLF）
+pub const PROXY_PROTOCOL_V1_MAX_LEN: usize = 107;
+
+// PROXY 协议 v2（二进制格式）头签名
+pub const PROXY_PROTOCOL_V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+// Alt-Svc 头中 HTTP/3 服务的有效期（秒）
+pub const HTTP3_ALT_SVC_MAX_AGE_SECS: u64 = 86400;
+
+// HTTP/3 连接关闭时等待进行中请求完成的最长时间（秒）
+pub const HTTP3_SHUTDOWN_TIMEOUT_SECS: u64 = 5;
+
+// 关闭时等待进行中查询完成的默认宽限期（秒）
+pub const DEFAULT_SHUTDOWN_GRACE_PERIOD_SECS: u64 = 30;
+// 关闭宽限期上限（秒）
+pub const MAX_SHUTDOWN_GRACE_PERIOD_SECS: u64 = 3600;
+// 宽限期之后留给监听关闭与缓存持久化的时间（秒）
+pub const SHUTDOWN_CLEANUP_TIMEOUT_SECS: u64 = 10;
+
+// 宽限期结束后等待 503 响应发送完毕的时间（秒），之后强制关闭剩余连接
+pub const SHUTDOWN_FLUSH_TIMEOUT_SECS: u64 = 2;
+// 排空期间可跟踪的最大进行中查询数
+pub const MAX_TRACKED_IN_FLIGHT_QUERIES: u32 = 1 << 24;
+// 并发请求数达到上限时 503 响应的 Retry-After 值（秒）
+pub const LOAD_SHED_RETRY_AFTER_SECS: u64 = 1;
+
+//
+// ACME 常量
+//
+
+// 默认 ACME 目录地址（Let's Encrypt 生产环境）
+pub const DEFAULT_ACME_DIRECTORY_URL: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+// 默认 ACME 缓存目录
+pub const DEFAULT_ACME_CACHE_DIR: &str = "./acme";
+
+// ACME 缓存目录中的证书、私钥与账户私钥文件名
+pub const ACME_CERT_FILE: &str = "cert.pem";
+pub const ACME_KEY_FILE: &str = "key.pem";
+pub const ACME_ACCOUNT_KEY_FILE: &str = "account.key";
+
+// 默认在证书到期前多少天开始续期
+pub const DEFAULT_ACME_RENEW_BEFORE_DAYS: u64 = 30;
+
+// 续期失败且证书在该天数内到期时，健康检查报告异常
+pub const ACME_HEALTH_WARNING_DAYS: u64 = 14;
+
+// 证书有效期检查间隔（秒）
+pub const ACME_CHECK_INTERVAL_SECS: u64 = 12 * 3600; // 12小时
+
+// 续期失败后的重试间隔（秒）
+pub const ACME_RETRY_INTERVAL_SECS: u64 = 3600; // 1小时
+
+// ACME 订单/授权状态轮询间隔（秒）与最大轮询次数
+pub const ACME_POLL_INTERVAL_SECS: u64 = 2;
+pub const ACME_MAX_POLL_ATTEMPTS: u32 = 30;
+
+// TLS-ALPN-01 挑战使用的 ALPN 协议标识（RFC 8737）
+pub const ACME_TLS_ALPN_PROTOCOL: &[u8] = b"acme-tls/1";
+
+// HTTP-01 挑战路径（RFC 8555）
+pub const ACME_HTTP01_CHALLENGE_PATH: &str = "/.well-known/acme-challenge/{token}";
 
 //
 // DNS 常量
@@ -34,12 +182,22 @@ pub const MAX_REQUEST_SIZE: usize = 16 * 1024; // 16KB
 // 默认记录类型 (A 记录)
 pub const DNS_RECORD_TYPE_A: u16 = 1;
 
+// DNAME 记录类型（RFC 6672），hickory 未提供对应的枚举值
+pub const DNS_RECORD_TYPE_DNAME: u16 = 39;
+
 // 默认 DNS 类 (IN 类)
 pub const DNS_CLASS_IN: u16 = 1;
 
 // DNS 分流特殊上游组名称 - 黑洞（阻止）
 pub const BLACKHOLE_UPSTREAM_GROUP_NAME: &str = "__blackhole__";
 
+// DNS 分流特殊上游组名称 - 全局上游：规则指向该名称时使用全局上游，
+// route-test 输出中也以此表示未命中规则且没有默认上游组的查询
+pub const GLOBAL_UPSTREAM_ROUTE_TARGET: &str = "__default__";
+
+// 单条规则可按顺序回退的最大上游组数量
+pub const MAX_RULE_UPSTREAM_GROUPS: usize = 4;
+
 //
 // EDNS 客户端子网 (ECS) 常量
 //
@@ -47,6 +205,21 @@ pub const BLACKHOLE_UPSTREAM_GROUP_NAME: &str = "__blackhole__";
 // EDNS 客户端子网 Option Code（RFC 7871）
 pub const EDNS_CLIENT_SUBNET_OPTION_CODE: u16 = 8;
 
+// EDNS 填充 Option Code（RFC 7830）
+pub const EDNS_PADDING_OPTION_CODE: u16 = 12;
+
+// EDNS TCP Keepalive Option Code（RFC 7828），超时以 100 毫秒为单位
+pub const EDNS_TCP_KEEPALIVE_OPTION_CODE: u16 = 11;
+
+// 查询填充块大小（RFC 8467 推荐值）
+pub const EDNS_QUERY_PADDING_BLOCK_SIZE: usize = 128;
+
+// 响应填充块大小（RFC 8467 推荐值）
+pub const EDNS_RESPONSE_PADDING_BLOCK_SIZE: usize = 468;
+
+// 响应填充块大小上限
+pub const MAX_RESPONSE_PADDING_BLOCK_SIZE: usize = 4096;
+
 // ECS 策略：剥离
 pub const ECS_POLICY_STRIP: &str = "strip";
 
@@ -84,11 +257,84 @@ pub const DEFAULT_MAX_TTL: u32 = 86400; // 1 天
 // 默认负缓存 TTL（秒）
 pub const DEFAULT_NEGATIVE_TTL: u32 = 300; // 5 分钟
 
+// 默认等待后台缓存未命中解析结果的超时时间（毫秒）
+pub const DEFAULT_ASYNC_MISS_RESOLUTION_TIMEOUT_MS: u64 = 2000;
+
+// 默认不缓存的响应码
+pub const DEFAULT_NO_CACHE_RCODES: [&str; 4] = ["SERVFAIL", "FORMERR", "NOTIMP", "REFUSED"];
+
+// 默认缓存响应的最大应答记录数
+pub const DEFAULT_CACHE_MAX_ANSWER_RECORDS: usize = 20;
+
 // 缓存文件魔数，用于识别缓存文件
 pub const CACHE_FILE_MAGIC: &str = "OXIDEWDNS_CACHE";
 
 // 缓存文件版本号
-pub const CACHE_FILE_VERSION: u64 = 1;
+pub const CACHE_FILE_VERSION: u64 = 2;
+
+// 按客户端子网隔离缓存时使用的 IPv4 前缀长度
+pub const CACHE_KEY_SCOPE_IPV4_PREFIX_LENGTH: u8 = 24;
+
+// 按客户端子网隔离缓存时使用的 IPv6 前缀长度
+pub const CACHE_KEY_SCOPE_IPV6_PREFIX_LENGTH: u8 = 56;
+
+//
+// 查询统计常量
+//
+
+// 默认统计滑动窗口大小（秒）
+pub const DEFAULT_STATS_WINDOW_SECS: u64 = 300; // 5 分钟
+
+// 统计滑动窗口的最小值（秒）
+pub const MIN_STATS_WINDOW_SECS: u64 = 10;
+
+// 统计滑动窗口的最大值（秒）
+pub const MAX_STATS_WINDOW_SECS: u64 = 86400; // 1 天
+
+// 统计滑动窗口划分的桶数
+pub const STATS_WINDOW_BUCKETS: usize = 10;
+
+// 热门域名查询默认返回数量
+pub const DEFAULT_TOP_DOMAINS_LIMIT: usize = 100;
+
+// 热门域名查询最大返回数量
+pub const MAX_TOP_DOMAINS_LIMIT: usize = 1000;
+
+// 热门域名统计 API 路径
+pub const STATS_TOP_DOMAINS_PATH: &str = "/admin/stats/top-domains";
+
+// 默认最多跟踪的域名数
+pub const DEFAULT_STATS_MAX_TRACKED_DOMAINS: usize = 10000;
+
+// 缓存分析 API 路径
+pub const CACHE_TOP_PATH: &str = "/admin/cache/top";
+
+// 缓存分析默认返回数量
+pub const DEFAULT_CACHE_TOP_LIMIT: usize = 50;
+
+// 缓存分析的时间桶大小（秒）
+pub const CACHE_ANALYTICS_BUCKET_SECS: u64 = 60;
+
+// 缓存分析保留的时间桶数（覆盖最大的 1 小时窗口）
+pub const CACHE_ANALYTICS_BUCKETS: usize = 60;
+
+// 运行时修改日志过滤规则的管理端点路径
+pub const LOG_LEVEL_PATH: &str = "/log-level";
+
+// 调试抓包记录的管理端点路径
+pub const DEBUG_CAPTURES_PATH: &str = "/api/debug/captures";
+
+// 默认调试抓包缓冲区容量（条）
+pub const DEFAULT_DEBUG_CAPTURE_SIZE: usize = 256;
+
+// 调试抓包缓冲区容量上限（条）
+pub const MAX_DEBUG_CAPTURE_SIZE: usize = 4096;
+
+// 版本信息管理端点路径
+pub const VERSION_PATH: &str = "/version";
+
+// 服务器信息页面路径
+pub const ROOT_INFO_PATH: &str = "/";
 
 //
 // 速率限制常量
@@ -112,6 +358,12 @@ pub const MIN_PER_IP_CONCURRENT: u32 = 1;
 // 单个 IP 的并发请求数限制的最大值
 pub const MAX_PER_IP_CONCURRENT: u32 = 65535; 
 
+// 未匹配任何覆盖时使用的速率限制桶名称
+pub const DEFAULT_RATE_LIMIT_BUCKET: &str = "default";
+
+// 429 响应中标明拒绝请求的速率限制桶的头名称
+pub const RATE_LIMIT_BUCKET_HEADER: &str = "X-RateLimit-Bucket";
+
 //
 // 上游服务器常量
 //
@@ -119,6 +371,97 @@ pub const MAX_PER_IP_CONCURRENT: u32 = 65535;
 // 默认查询超时时间（秒）
 pub const DEFAULT_QUERY_TIMEOUT: u64 = 30;
 
+// 生成默认配置时使用的上游 DNS 解析器（UDP）
+pub const DEFAULT_UPSTREAM_RESOLVERS: [&str; 2] = ["1.1.1.1:53", "8.8.8.8:53"];
+
+//
+// 递归解析常量
+//
+
+// 递归模式默认使用的根服务器地址（IANA 根服务器 a-m 的 IPv4 地址）
+pub const DEFAULT_ROOT_HINTS: [&str; 13] = [
+    "198.41.0.4", "170.247.170.2", "192.33.4.12", "199.7.91.13", "192.203.230.10",
+    "192.5.5.241", "192.112.36.4", "198.97.190.53", "192.36.148.17", "192.58.128.30",
+    "193.0.14.129", "199.7.83.42", "202.12.27.33",
+];
+// 名称服务器的 DNS 端口
+pub const DNS_NAMESERVER_PORT: u16 = 53;
+// 单次解析最多跟随的推荐（referral）次数
+pub const RECURSIVE_MAX_REFERRALS: usize = 16;
+// CNAME 链与无粘合记录的名称服务器地址解析的最大嵌套深度
+pub const RECURSIVE_MAX_DEPTH: usize = 8;
+// 单个名称服务器的查询超时（毫秒），超时后尝试下一个名称服务器
+pub const RECURSIVE_SERVER_TIMEOUT_MS: u64 = 1500;
+// 迭代查询通告的 EDNS UDP 负载大小
+pub const RECURSIVE_EDNS_PAYLOAD_SIZE: u16 = 1232;
+// 区域委派与 DNSKEY/DS 响应缓存的最大条目数
+pub const RECURSIVE_CACHE_MAX_ENTRIES: usize = 10_000;
+// 区域委派与 DNSKEY/DS 响应缓存的最大 TTL（秒）
+pub const RECURSIVE_CACHE_MAX_TTL_SECS: u32 = 86_400;
+
+//
+// mDNS 转发常量
+//
+
+// 默认通过 mDNS 解析的域名后缀
+pub const DEFAULT_MDNS_DOMAINS: [&str; 2] = [".local", ".local."];
+
+// 默认 mDNS 组播地址（RFC 6762）
+pub fn default_mdns_multicast_addr() -> SocketAddr {
+    "224.0.0.251:5353".parse().unwrap()
+}
+
+// 默认 mDNS 响应收集超时时间（毫秒）
+pub const DEFAULT_MDNS_RESPONSE_TIMEOUT_MS: u64 = 1000;
+
+// mDNS 响应收集超时时间上限（毫秒）
+pub const MAX_MDNS_RESPONSE_TIMEOUT_MS: u64 = 10000;
+
+// 默认 mDNS 无响应时的负缓存 TTL（秒）
+pub const DEFAULT_MDNS_NEGATIVE_TTL_SECS: u32 = 30;
+
+// 本地合成的 SVCB/HTTPS 记录的默认 TTL（秒）
+pub const DEFAULT_SVCB_HINT_TTL: u32 = 300;
+
+// 本地合成的 SVCB/HTTPS 记录的优先级（ServiceMode）
+pub const SVCB_HINT_PRIORITY: u16 = 1;
+
+// 默认 SRV 上游发现刷新间隔（秒）
+pub const DEFAULT_SRV_REFRESH_INTERVAL_SECS: u64 = 300;
+
+// SRV 上游发现的最小刷新间隔（秒）
+pub const MIN_SRV_REFRESH_INTERVAL_SECS: u64 = 10;
+
+// 通过 SRV 发现的 DoH 上游使用的请求路径
+pub const SRV_DISCOVERED_DOH_PATH: &str = "/dns-query";
+
+// 默认 Consul 上游发现刷新间隔（秒）
+pub const DEFAULT_CONSUL_REFRESH_INTERVAL_SECS: u64 = 30;
+
+// Consul 上游发现的最小刷新间隔（秒）
+pub const MIN_CONSUL_REFRESH_INTERVAL_SECS: u64 = 5;
+
+// Consul 健康检查接口路径前缀，后接服务名称
+pub const CONSUL_HEALTH_SERVICE_PATH: &str = "/v1/health/service/";
+
+// Consul 接口请求超时时间（秒）
+pub const CONSUL_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+// 默认上游失败率统计窗口（秒）
+pub const DEFAULT_FAILURE_RATIO_WINDOW_SECS: u64 = 60;
+
+// 上游失败率统计窗口上限（秒）
+pub const MAX_FAILURE_RATIO_WINDOW_SECS: u64 = 3600;
+
+// 窗口内查询数达到该值后才可能将上游标记为降级，避免少量失败导致误判
+pub const MIN_FAILURE_RATIO_SAMPLES: u64 = 10;
+
+// 默认的 DoH 上游 HTTP/2 PING 间隔（秒）
+pub const DEFAULT_HTTP2_KEEPALIVE_INTERVAL_SECS: u64 = 30;
+
+// DoH 上游 HTTP/2 PING 间隔上限（秒）
+pub const MAX_HTTP2_KEEPALIVE_INTERVAL_SECS: u64 = 3600;
+
 //
 // HTTP 相关常量
 //
@@ -126,12 +469,30 @@ pub const DEFAULT_QUERY_TIMEOUT: u64 = 30;
 // 默认 HTTP 客户端超时时间（秒）
 pub const DEFAULT_HTTP_CLIENT_TIMEOUT: u64 = 120;
 
+// 默认 HTTP 客户端 TCP 连接超时时间（秒）
+pub const DEFAULT_HTTP_CLIENT_CONNECT_TIMEOUT: u64 = 2;
+
+// 默认建立连接时为 TLS 握手额外预留的时间（秒）
+pub const DEFAULT_HTTP_CLIENT_TLS_HANDSHAKE_ALLOWANCE: u64 = 3;
+
 // 默认 HTTP 客户端连接池空闲超时时间（秒）
 pub const DEFAULT_HTTP_CLIENT_POOL_IDLE_TIMEOUT: u64 = 30;
 
 // 默认 HTTP 客户端连接池最大空闲连接数
 pub const DEFAULT_HTTP_CLIENT_POOL_MAX_IDLE_CONNECTIONS: u32 = 10;
 
+// 默认 HTTP 客户端连接池最大并发连接数（0 表示不限制）
+pub const DEFAULT_HTTP_CLIENT_POOL_MAX_CONNECTIONS: u32 = 0;
+
+// 默认 DoH 上游按状态码重试的最大次数
+pub const DEFAULT_HTTP_CLIENT_MAX_RETRIES: u32 = 2;
+
+// DoH 上游按状态码重试的次数上限
+pub const MAX_HTTP_CLIENT_MAX_RETRIES: u32 = 10;
+
+// DoH 上游重试的初始退避时间（毫秒），每次重试翻倍
+pub const DEFAULT_HTTP_CLIENT_RETRY_BACKOFF_MS: u64 = 100;
+
 // 默认 HTTP 客户端 Agent
 pub const DEFAULT_HTTP_CLIENT_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/135.0.0.0 Safari/537.36";
 
@@ -144,6 +505,9 @@ pub const CONTENT_TYPE_DNS_JSON: &str = "application/dns-json";
 // DoH 二进制消息内容类型
 pub const CONTENT_TYPE_DNS_MESSAGE: &str = "application/dns-message";
 
+// RFC 8484 草案阶段使用的二进制消息内容类型，部分旧客户端仍在使用，按 application/dns-message 处理
+pub const CONTENT_TYPE_DNS_UDPWIREFORMAT: &str = "application/dns-udpwireformat";
+
 // IP 代理头字段名
 pub const IP_HEADER_NAMES: [&str; 3] = [
     "X-Forwarded-For", 
@@ -167,6 +531,80 @@ pub const DOH_FORMAT_JSON: &str = "json";
 // DoH 二进制格式标识
 pub const DOH_FORMAT_WIRE: &str = "wire"; 
 
+// Oblivious DoH 格式标识
+pub const DOH_FORMAT_ODOH: &str = "odoh";
+
+// DNS over WebSocket 格式标识
+pub const DOH_FORMAT_WEBSOCKET: &str = "websocket";
+
+// DNS over WebSocket 端点路径
+pub const DNS_WEBSOCKET_PATH: &str = "/dns-ws";
+
+//
+// Oblivious DoH (RFC 9230) 常量
+//
+
+// Oblivious DoH 消息内容类型
+pub const CONTENT_TYPE_ODOH_MESSAGE: &str = "application/oblivious-dns-message";
+
+// ODoH 目标公钥配置路径
+pub const ODOH_CONFIGS_PATH: &str = "/.well-known/odohconfigs";
+
+// 默认 ODoH 查询路径
+pub const DEFAULT_ODOH_PATH: &str = "/odoh-query";
+
+// 默认 ODoH 密钥目录
+pub const DEFAULT_ODOH_KEY_DIR: &str = "./odoh";
+
+// 默认 ODoH 密钥轮换间隔（秒）：7 天
+pub const DEFAULT_ODOH_KEY_ROTATION_SECS: u64 = 7 * 24 * 60 * 60;
+
+// 最小 ODoH 密钥轮换间隔（秒）
+pub const MIN_ODOH_KEY_ROTATION_SECS: u64 = 3600;
+
+// ODoH 当前私钥文件名
+pub const ODOH_CURRENT_KEY_FILE: &str = "current.key";
+
+// ODoH 上一个私钥文件名（轮换后继续接受使用旧公钥加密的查询）
+pub const ODOH_PREVIOUS_KEY_FILE: &str = "previous.key";
+
+//
+// CORS 常量
+//
+
+// 允许任意来源的通配符
+pub const CORS_ANY_ORIGIN: &str = "*";
+
+// 默认预检结果缓存时间（秒）
+pub const DEFAULT_CORS_MAX_AGE_SECS: u64 = 600;
+
+// 最大预检结果缓存时间（秒）：1 天
+pub const MAX_CORS_MAX_AGE_SECS: u64 = 86400;
+
+// DoH 端点支持的 HTTP 方法（OPTIONS 与 405 响应的 Allow 头）
+pub const DOH_ALLOWED_METHODS: &str = "GET, HEAD, POST, OPTIONS";
+
+// 跨域请求允许的方法
+pub const CORS_ALLOWED_METHODS: &str = "GET, POST, OPTIONS";
+
+// 跨域请求允许的请求头
+pub const CORS_ALLOWED_HEADERS: &str = "Content-Type, Accept";
+
+// 默认的响应压缩最小大小（字节），更小的响应压缩后收益有限
+pub const DEFAULT_COMPRESSION_MIN_SIZE: u16 = 256;
+
+// 可压缩的响应内容类型：JSON API 与 Prometheus 文本格式指标
+pub const COMPRESSIBLE_CONTENT_TYPES: &[&str] = &["application/json", "application/dns-json", "text/plain"];
+
+// 查询日志写入队列的容量（行），队列满时丢弃新的日志行
+pub const QUERY_LOG_CHANNEL_CAPACITY: usize = 8192;
+
+// 客户端 IP 摘要输出的字节数（十六进制编码后长度加倍）
+pub const QUERY_LOG_CLIENT_IP_HASH_BYTES: usize = 16;
+
+// 查询日志默认采样率：记录全部查询
+pub const DEFAULT_QUERY_LOG_SAMPLE_RATE: f64 = 1.0;
+
 //
 // URL规则周期性更新常量
 //
@@ -179,3 +617,22 @@ pub const MIN_URL_RULE_UPDATE_INTERVAL_SECS: u64 = 30; // 30秒
 
 // URL规则更新间隔的最大值（秒）
 pub const MAX_URL_RULE_UPDATE_INTERVAL_SECS: u64 = 86400 * 7; // 7天
+
+// gzip 压缩的规则列表解压后的最大大小（字节），防止解压炸弹耗尽内存
+pub const MAX_DECOMPRESSED_RULE_LIST_SIZE: u64 = 256 * 1024 * 1024; // 256MB
+
+// 上游超时错误原因中包含的文本，用于区分超时与其他上游不可达错误
+pub const UPSTREAM_TIMEOUT_REASON: &str = "timed out";
+
+// 上游响应未通过校验时错误原因中包含的文本
+pub const UPSTREAM_VALIDATION_FAILED_REASON: &str = "failed validation";
+
+// 拒绝 PTR 查询的私有地址段：RFC 1918、RFC 4193（唯一本地地址）与链路本地地址
+pub const PRIVATE_PTR_NETWORKS: &[&str] = &[
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "169.254.0.0/16",
+    "fc00::/7",
+    "fe80::/10",
+];
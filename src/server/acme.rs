@@ -0,0 +1,601 @@
+// src/server/acme.rs
+
+// 该模块实现内置的 ACME（RFC 8555）证书签发与续期。
+//
+// 账户私钥与签发的证书保存在 cache_dir 中，后台任务定期检查证书有效期，
+// 在到期前 renew_before_days 天内自动续期，并通过 TlsContext 热替换证书，
+// 已建立的连接不受影响。验证方式支持 TLS-ALPN-01（在 DoH 的 TLS 监听上完成）
+// 和 HTTP-01（在独立的 HTTP 端口上完成）。
+// 续期失败会记录到指标中，并在证书临近到期时反映到健康检查结果里。
+
+use std::fs;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL};
+use dashmap::DashMap;
+use reqwest::{Client, Response as HttpResponse};
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair as _, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tokio_rustls::rustls::crypto::ring::default_provider;
+use tokio_rustls::rustls::pki_types::PrivateKeyDer;
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tracing::{debug, error, info, warn};
+use crate::common::consts::{
+    ACME_ACCOUNT_KEY_FILE, ACME_CHECK_INTERVAL_SECS, ACME_HEALTH_WARNING_DAYS,
+    ACME_HTTP01_CHALLENGE_PATH, ACME_MAX_POLL_ATTEMPTS, ACME_POLL_INTERVAL_SECS,
+    ACME_RETRY_INTERVAL_SECS, DEFAULT_HTTP_CLIENT_AGENT,
+};
+use crate::server::config::{AcmeChallengeType, AcmeConfig};
+use crate::server::error::{Result, ServerError};
+use crate::server::metrics::METRICS;
+use crate::server::tls::TlsContext;
+
+// ACME 请求内容类型
+const CONTENT_TYPE_JOSE_JSON: &str = "application/jose+json";
+
+// ACME 响应头
+const HEADER_REPLAY_NONCE: &str = "Replay-Nonce";
+
+// ACME 对象状态
+const STATUS_VALID: &str = "valid";
+const STATUS_INVALID: &str = "invalid";
+
+// 无效 nonce 错误类型，遇到时使用新 nonce 重试一次
+const ERROR_BAD_NONCE: &str = "urn:ietf:params:acme:error:badNonce";
+
+// 续期结果指标标签
+const RENEWAL_RESULT_SUCCESS: &str = "success";
+const RENEWAL_RESULT_FAILURE: &str = "failure";
+
+// 每天的秒数
+const SECS_PER_DAY: i64 = 86400;
+
+// ACME 目录
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AcmeDirectory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+// ACME 订单
+#[derive(Debug, Deserialize)]
+struct AcmeOrder {
+    status: String,
+    #[serde(default)]
+    authorizations: Vec<String>,
+    finalize: String,
+    #[serde(default)]
+    certificate: Option<String>,
+}
+
+// ACME 授权
+#[derive(Debug, Deserialize)]
+struct AcmeAuthorization {
+    status: String,
+    identifier: AcmeIdentifier,
+    #[serde(default)]
+    challenges: Vec<AcmeChallenge>,
+}
+
+// ACME 标识符
+#[derive(Debug, Deserialize)]
+struct AcmeIdentifier {
+    value: String,
+}
+
+// ACME 挑战
+#[derive(Debug, Deserialize)]
+struct AcmeChallenge {
+    #[serde(rename = "type")]
+    type_: String,
+    url: String,
+    token: String,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+// 单次签发流程中的 ACME 会话（负责 JWS 签名与 nonce 管理）
+struct AcmeSession {
+    client: Client,
+    directory: AcmeDirectory,
+    account_key: EcdsaKeyPair,
+    rng: SystemRandom,
+    jwk: Value,
+    nonce: Option<String>,
+    kid: Option<String>,
+}
+
+impl AcmeSession {
+    // 获取 ACME 目录并创建会话
+    async fn connect(client: Client, directory_url: &str, account_key: EcdsaKeyPair) -> Result<Self> {
+        let directory = client.get(directory_url)
+            .send()
+            .await
+            .and_then(HttpResponse::error_for_status)
+            .map_err(|e| ServerError::Acme(format!("Failed to fetch ACME directory {}: {}", directory_url, e)))?
+            .json::<AcmeDirectory>()
+            .await
+            .map_err(|e| ServerError::Acme(format!("Invalid ACME directory {}: {}", directory_url, e)))?;
+
+        let jwk = account_jwk(&account_key);
+
+        Ok(Self {
+            client,
+            directory,
+            account_key,
+            rng: SystemRandom::new(),
+            jwk,
+            nonce: None,
+            kid: None,
+        })
+    }
+
+    // JWK 指纹（RFC 7638），用于构建 key authorization
+    fn thumbprint(&self) -> String {
+        // 成员必须按字典序排列且不含空白
+        let canonical = format!(
+            r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+            self.jwk["x"].as_str().unwrap_or_default(),
+            self.jwk["y"].as_str().unwrap_or_default(),
+        );
+        BASE64_URL.encode(digest(&SHA256, canonical.as_bytes()))
+    }
+
+    // 获取新的 nonce
+    async fn fetch_nonce(&mut self) -> Result<String> {
+        let response = self.client.head(&self.directory.new_nonce)
+            .send()
+            .await
+            .map_err(|e| ServerError::Acme(format!("Failed to fetch ACME nonce: {}", e)))?;
+
+        response.headers()
+            .get(HEADER_REPLAY_NONCE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| ServerError::Acme("ACME server returned no Replay-Nonce".to_string()))
+    }
+
+    // 发送签名请求，payload 为 None 时为 POST-as-GET
+    async fn post(&mut self, url: &str, payload: Option<&Value>) -> Result<HttpResponse> {
+        let mut retried = false;
+        loop {
+            let nonce = match self.nonce.take() {
+                Some(nonce) => nonce,
+                None => self.fetch_nonce().await?,
+            };
+            let body = self.sign(url, &nonce, payload)?;
+
+            let response = self.client.post(url)
+                .header(header::CONTENT_TYPE, CONTENT_TYPE_JOSE_JSON)
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| ServerError::Acme(format!("ACME request to {} failed: {}", url, e)))?;
+
+            self.nonce = response.headers()
+                .get(HEADER_REPLAY_NONCE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+
+            if response.status().is_success() {
+                return Ok(response);
+            }
+
+            let status = response.status();
+            let problem: Value = response.json().await.unwrap_or_default();
+            if !retried && problem["type"] == ERROR_BAD_NONCE {
+                debug!(url = url, "ACME server rejected nonce, retrying");
+                retried = true;
+                continue;
+            }
+
+            return Err(ServerError::Acme(format!(
+                "ACME request to {} failed with {}: {}",
+                url, status, problem["detail"].as_str().unwrap_or("no detail")
+            )));
+        }
+    }
+
+    // 发送签名请求并解析 JSON 响应
+    async fn post_json<T: for<'de> Deserialize<'de>>(&mut self, url: &str, payload: Option<&Value>) -> Result<T> {
+        self.post(url, payload).await?
+            .json::<T>()
+            .await
+            .map_err(|e| ServerError::Acme(format!("Invalid ACME response from {}: {}", url, e)))
+    }
+
+    // 构建 JWS（RFC 7515 Flattened JSON 序列化）
+    fn sign(&self, url: &str, nonce: &str, payload: Option<&Value>) -> Result<String> {
+        let mut protected = json!({
+            "alg": "ES256",
+            "nonce": nonce,
+            "url": url,
+        });
+        match &self.kid {
+            Some(kid) => protected["kid"] = json!(kid),
+            None => protected["jwk"] = self.jwk.clone(),
+        }
+
+        let protected = BASE64_URL.encode(protected.to_string());
+        let payload = payload.map(|p| BASE64_URL.encode(p.to_string())).unwrap_or_default();
+        let signing_input = format!("{}.{}", protected, payload);
+        let signature = self.account_key.sign(&self.rng, signing_input.as_bytes())
+            .map_err(|_| ServerError::Acme("Failed to sign ACME request".to_string()))?;
+
+        Ok(json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": BASE64_URL.encode(signature.as_ref()),
+        }).to_string())
+    }
+
+    // 注册（或找回已有的）账户
+    async fn register_account(&mut self, contact: Option<&str>) -> Result<()> {
+        let mut payload = json!({ "termsOfServiceAgreed": true });
+        if let Some(contact) = contact {
+            let contact = if contact.contains(':') { contact.to_string() } else { format!("mailto:{}", contact) };
+            payload["contact"] = json!([contact]);
+        }
+
+        let url = self.directory.new_account.clone();
+        let response = self.post(&url, Some(&payload)).await?;
+        let kid = location(&response)?;
+        debug!(account = %kid, "ACME account ready");
+        self.kid = Some(kid);
+        Ok(())
+    }
+
+    // 创建订单，返回订单地址与订单内容
+    async fn new_order(&mut self, domains: &[String]) -> Result<(String, AcmeOrder)> {
+        let identifiers: Vec<Value> = domains.iter()
+            .map(|d| json!({ "type": "dns", "value": d }))
+            .collect();
+
+        let url = self.directory.new_order.clone();
+        let response = self.post(&url, Some(&json!({ "identifiers": identifiers }))).await?;
+        let order_url = location(&response)?;
+        let order = response.json::<AcmeOrder>()
+            .await
+            .map_err(|e| ServerError::Acme(format!("Invalid ACME order: {}", e)))?;
+        Ok((order_url, order))
+    }
+}
+
+// ACME 证书管理器
+pub struct AcmeManager {
+    // ACME 配置
+    config: AcmeConfig,
+    // TLS 上下文（用于挑战证书与证书替换）
+    tls: Arc<TlsContext>,
+    // ACME HTTP 客户端
+    client: Client,
+    // HTTP-01 挑战：token -> key authorization
+    http01_tokens: Arc<DashMap<String, String>>,
+    // 最近一次签发/续期失败的原因
+    last_error: RwLock<Option<String>>,
+}
+
+impl AcmeManager {
+    // 创建 ACME 证书管理器
+    pub fn new(config: &AcmeConfig, tls: Arc<TlsContext>) -> Result<Self> {
+        let client = Client::builder()
+            .user_agent(DEFAULT_HTTP_CLIENT_AGENT)
+            .build()
+            .map_err(|e| ServerError::Acme(format!("Failed to create ACME HTTP client: {}", e)))?;
+
+        Ok(Self {
+            config: config.clone(),
+            tls,
+            client,
+            http01_tokens: Arc::new(DashMap::new()),
+            last_error: RwLock::new(None),
+        })
+    }
+
+    // 启动后台续期任务
+    pub fn spawn(self: &Arc<Self>) -> JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let next_check = match manager.renew_if_needed().await {
+                    Ok(_) => ACME_CHECK_INTERVAL_SECS,
+                    Err(e) => {
+                        error!(error = %e, "ACME certificate renewal failed");
+                        ACME_RETRY_INTERVAL_SECS
+                    }
+                };
+                sleep(Duration::from_secs(next_check)).await;
+            }
+        })
+    }
+
+    // 证书缺失或即将到期时签发新证书，返回是否进行了签发
+    pub async fn renew_if_needed(&self) -> Result<bool> {
+        if let Some(remaining) = self.remaining_secs() {
+            if remaining > self.config.renew_before_days as i64 * SECS_PER_DAY {
+                debug!(remaining_days = remaining / SECS_PER_DAY, "TLS certificate does not need renewal yet");
+                return Ok(false);
+            }
+        }
+
+        match self.issue_certificate().await {
+            Ok(()) => {
+                METRICS.acme_renewals_total().with_label_values(&[RENEWAL_RESULT_SUCCESS]).inc();
+                if let Ok(mut last_error) = self.last_error.write() {
+                    *last_error = None;
+                }
+                Ok(true)
+            },
+            Err(e) => {
+                METRICS.acme_renewals_total().with_label_values(&[RENEWAL_RESULT_FAILURE]).inc();
+                if let Ok(mut last_error) = self.last_error.write() {
+                    *last_error = Some(e.to_string());
+                }
+                Err(e)
+            },
+        }
+    }
+
+    // 执行完整的 ACME 签发流程并安装证书
+    pub async fn issue_certificate(&self) -> Result<()> {
+        info!(domains = ?self.config.domains, directory = %self.config.directory_url, "Requesting certificate via ACME");
+
+        let account_key = self.load_or_create_account_key()?;
+        let mut session = AcmeSession::connect(self.client.clone(), &self.config.directory_url, account_key).await?;
+        session.register_account(self.config.contact.as_deref()).await?;
+
+        let (order_url, order) = session.new_order(&self.config.domains).await?;
+
+        // 完成所有授权验证，无论成功与否都清理挑战
+        let mut prepared = Vec::new();
+        let result = self.complete_authorizations(&mut session, &order.authorizations, &mut prepared).await;
+        for (domain, token) in prepared {
+            self.clear_challenge(&domain, &token);
+        }
+        result?;
+
+        // 生成证书私钥与 CSR，提交订单
+        let cert_key = rcgen::KeyPair::generate()
+            .map_err(|e| ServerError::Acme(format!("Failed to generate certificate key: {}", e)))?;
+        let csr = rcgen::CertificateParams::new(self.config.domains.clone())
+            .and_then(|params| params.serialize_request(&cert_key))
+            .map_err(|e| ServerError::Acme(format!("Failed to create CSR: {}", e)))?;
+        let payload = json!({ "csr": BASE64_URL.encode(csr.der()) });
+        let mut order: AcmeOrder = session.post_json(&order.finalize, Some(&payload)).await?;
+
+        // 等待订单完成
+        let mut attempts = 0;
+        while order.status != STATUS_VALID {
+            if order.status == STATUS_INVALID {
+                return Err(ServerError::Acme(format!("ACME order {} became invalid", order_url)));
+            }
+            attempts += 1;
+            if attempts > ACME_MAX_POLL_ATTEMPTS {
+                return Err(ServerError::Acme(format!("Timed out waiting for ACME order {}", order_url)));
+            }
+            sleep(Duration::from_secs(ACME_POLL_INTERVAL_SECS)).await;
+            order = session.post_json(&order_url, None).await?;
+        }
+
+        // 下载证书并安装
+        let certificate_url = order.certificate
+            .ok_or_else(|| ServerError::Acme("ACME order is valid but has no certificate URL".to_string()))?;
+        let cert_pem = session.post(&certificate_url, None).await?
+            .text()
+            .await
+            .map_err(|e| ServerError::Acme(format!("Failed to download certificate: {}", e)))?;
+
+        self.tls.install_certificate(&cert_pem, &cert_key.serialize_pem())?;
+        info!(domains = ?self.config.domains, "ACME certificate issued and installed");
+        Ok(())
+    }
+
+    // 健康问题描述：续期失败且证书临近到期（或尚未签发）时返回
+    pub fn health_issue(&self) -> Option<String> {
+        let last_error = self.last_error.read().ok()?.clone()?;
+
+        match self.remaining_secs() {
+            None => Some(format!("ACME certificate not issued yet: {}", last_error)),
+            Some(remaining) if remaining < ACME_HEALTH_WARNING_DAYS as i64 * SECS_PER_DAY => Some(format!(
+                "ACME renewal failing, certificate expires in {} days: {}",
+                remaining.max(0) / SECS_PER_DAY, last_error
+            )),
+            Some(_) => None,
+        }
+    }
+
+    // HTTP-01 挑战路由
+    pub fn http01_routes(&self) -> Router {
+        Router::new()
+            .route(ACME_HTTP01_CHALLENGE_PATH, get(handle_http01_challenge))
+            .with_state(self.http01_tokens.clone())
+    }
+
+    // 依次完成订单中的授权验证
+    async fn complete_authorizations(
+        &self,
+        session: &mut AcmeSession,
+        authorizations: &[String],
+        prepared: &mut Vec<(String, String)>,
+    ) -> Result<()> {
+        let challenge_type = match self.config.challenge {
+            AcmeChallengeType::TlsAlpn01 => "tls-alpn-01",
+            AcmeChallengeType::Http01 => "http-01",
+        };
+
+        for authz_url in authorizations {
+            let authz: AcmeAuthorization = session.post_json(authz_url, None).await?;
+            if authz.status == STATUS_VALID {
+                continue;
+            }
+
+            let domain = authz.identifier.value.clone();
+            let challenge = authz.challenges.iter()
+                .find(|c| c.type_ == challenge_type)
+                .ok_or_else(|| ServerError::Acme(format!(
+                    "ACME server offered no {} challenge for {}", challenge_type, domain
+                )))?;
+
+            let key_authorization = format!("{}.{}", challenge.token, session.thumbprint());
+            self.prepare_challenge(&domain, &challenge.token, &key_authorization)?;
+            prepared.push((domain.clone(), challenge.token.clone()));
+
+            // 通知服务器开始验证，然后轮询授权状态
+            session.post(&challenge.url, Some(&json!({}))).await?;
+
+            let mut attempts = 0;
+            loop {
+                let authz: AcmeAuthorization = session.post_json(authz_url, None).await?;
+                match authz.status.as_str() {
+                    STATUS_VALID => break,
+                    STATUS_INVALID => {
+                        let detail = authz.challenges.iter()
+                            .find(|c| c.type_ == challenge_type)
+                            .and_then(|c| c.error.as_ref())
+                            .and_then(|e| e["detail"].as_str())
+                            .unwrap_or("no detail")
+                            .to_string();
+                        return Err(ServerError::Acme(format!(
+                            "ACME {} validation failed for {}: {}", challenge_type, domain, detail
+                        )));
+                    },
+                    _ => {},
+                }
+                attempts += 1;
+                if attempts > ACME_MAX_POLL_ATTEMPTS {
+                    return Err(ServerError::Acme(format!("Timed out validating {}", domain)));
+                }
+                sleep(Duration::from_secs(ACME_POLL_INTERVAL_SECS)).await;
+            }
+
+            info!(domain = %domain, challenge = challenge_type, "ACME authorization validated");
+        }
+
+        Ok(())
+    }
+
+    // 布置挑战响应
+    fn prepare_challenge(&self, domain: &str, token: &str, key_authorization: &str) -> Result<()> {
+        match self.config.challenge {
+            AcmeChallengeType::Http01 => {
+                self.http01_tokens.insert(token.to_string(), key_authorization.to_string());
+            },
+            AcmeChallengeType::TlsAlpn01 => {
+                let challenge = alpn_challenge_certificate(domain, key_authorization)?;
+                self.tls.set_alpn_challenge(domain, Some(challenge));
+            },
+        }
+        Ok(())
+    }
+
+    // 清理挑战响应
+    fn clear_challenge(&self, domain: &str, token: &str) {
+        match self.config.challenge {
+            AcmeChallengeType::Http01 => {
+                self.http01_tokens.remove(token);
+            },
+            AcmeChallengeType::TlsAlpn01 => self.tls.set_alpn_challenge(domain, None),
+        }
+    }
+
+    // 加载账户私钥，不存在时生成并保存
+    fn load_or_create_account_key(&self) -> Result<EcdsaKeyPair> {
+        let path = self.config.cache_dir.join(ACME_ACCOUNT_KEY_FILE);
+
+        let key_pair = if path.exists() {
+            let pem = fs::read_to_string(&path)?;
+            rcgen::KeyPair::from_pem(&pem)
+                .map_err(|e| ServerError::Acme(format!("Invalid ACME account key {}: {}", path.display(), e)))?
+        } else {
+            let key_pair = rcgen::KeyPair::generate_for(&rcgen::PKCS_ECDSA_P256_SHA256)
+                .map_err(|e| ServerError::Acme(format!("Failed to generate ACME account key: {}", e)))?;
+            fs::create_dir_all(&self.config.cache_dir)?;
+            fs::write(&path, key_pair.serialize_pem())?;
+            info!(path = %path.display(), "Generated new ACME account key");
+            key_pair
+        };
+
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &key_pair.serialize_der(), &SystemRandom::new())
+            .map_err(|e| ServerError::Acme(format!("Unsupported ACME account key {}: {}", path.display(), e)))
+    }
+
+    // 证书剩余有效时间（秒），尚未签发时返回 None
+    fn remaining_secs(&self) -> Option<i64> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+        self.tls.certificate_not_after().map(|not_after| not_after - now)
+    }
+}
+
+// 处理 HTTP-01 挑战请求
+async fn handle_http01_challenge(
+    State(tokens): State<Arc<DashMap<String, String>>>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    match tokens.get(&token) {
+        Some(key_authorization) => {
+            debug!(token = %token, "Serving ACME HTTP-01 challenge");
+            (StatusCode::OK, key_authorization.clone()).into_response()
+        },
+        None => {
+            warn!(token = %token, "Unknown ACME HTTP-01 challenge token");
+            StatusCode::NOT_FOUND.into_response()
+        },
+    }
+}
+
+// 账户公钥的 JWK 表示（P-256）
+fn account_jwk(account_key: &EcdsaKeyPair) -> Value {
+    // 未压缩点格式：0x04 || x || y
+    let public_key = account_key.public_key().as_ref();
+    let (x, y) = public_key[1..].split_at(32);
+    json!({
+        "crv": "P-256",
+        "kty": "EC",
+        "x": BASE64_URL.encode(x),
+        "y": BASE64_URL.encode(y),
+    })
+}
+
+// 生成 TLS-ALPN-01 挑战证书（RFC 8737）
+fn alpn_challenge_certificate(domain: &str, key_authorization: &str) -> Result<CertifiedKey> {
+    let key_pair = rcgen::KeyPair::generate()
+        .map_err(|e| ServerError::Acme(format!("Failed to generate challenge key: {}", e)))?;
+
+    let mut params = rcgen::CertificateParams::new(vec![domain.to_string()])
+        .map_err(|e| ServerError::Acme(format!("Invalid challenge domain {}: {}", domain, e)))?;
+    let key_authorization_digest = digest(&SHA256, key_authorization.as_bytes());
+    params.custom_extensions = vec![
+        rcgen::CustomExtension::new_acme_identifier(key_authorization_digest.as_ref()),
+    ];
+    let cert = params.self_signed(&key_pair)
+        .map_err(|e| ServerError::Acme(format!("Failed to create challenge certificate: {}", e)))?;
+
+    CertifiedKey::from_der(
+        vec![cert.der().clone()],
+        PrivateKeyDer::Pkcs8(key_pair.serialize_der().into()),
+        &default_provider(),
+    ).map_err(|e| ServerError::Acme(format!("Invalid challenge certificate: {}", e)))
+}
+
+// 从响应的 Location 头获取对象地址
+fn location(response: &HttpResponse) -> Result<String> {
+    response.headers()
+        .get(header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .ok_or_else(|| ServerError::Acme("ACME server response is missing the Location header".to_string()))
+}
@@ -1,8 +1,9 @@
 // src/server/args.rs
 
+use std::net::IpAddr;
 use std::path::PathBuf;
 use anyhow::Result;
-use clap::{Parser, ArgAction};
+use clap::{Parser, Subcommand, ArgAction};
 use crate::common::consts::DEFAULT_CONFIG_PATH;
 
 // Oxide WDNS 命令行参数
@@ -53,6 +54,38 @@ pub struct CliArgs {
         help = "Enable debug level logging for detailed output"
     )]
     pub debug: bool,
+    
+    // 输出带注释的默认配置
+    #[arg(
+        long = "generate-config",
+        action = ArgAction::SetTrue,
+        help = "Print a fully-commented default configuration (YAML) to stdout and exit"
+    )]
+    pub generate_config: bool,
+    
+    // 子命令（未指定时启动服务器）
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// 子命令
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    // 测试域名的路由结果，不查询上游、不绑定端口
+    #[command(
+        name = "route-test",
+        about = "Print which routing rule matches each domain and which upstream group would handle it, \
+                 without contacting upstreams or binding ports"
+    )]
+    RouteTest {
+        // 待测试的域名，未指定或为 "-" 时从标准输入读取（每行一个）
+        #[arg(help = "Domains to test; reads one domain per line from stdin when omitted or \"-\"")]
+        domains: Vec<String>,
+        
+        // 模拟的客户端 IP（用于客户端 IP 规则）
+        #[arg(long = "client-ip", help = "Client IP address used to evaluate client_ip rules")]
+        client_ip: Option<IpAddr>,
+    },
 }
 
 impl CliArgs {
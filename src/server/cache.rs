@@ -8,10 +8,14 @@ use std::path::Path;
 use std::io::{BufReader, BufWriter};
 use std::io::Write;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::future::Future;
+use std::time::Duration;
+use dashmap::DashMap;
+use ipnet::IpNet;
 use moka::future::Cache;
-use hickory_proto::op::{Message};
+use hickory_proto::op::{Message, ResponseCode};
 use hickory_proto::rr::{DNSClass, Name, RecordType};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tokio::time::{interval, Instant};
 use tracing::{debug, warn, error, info};
 use serde::{Serialize, Deserialize};
@@ -19,14 +23,22 @@ use tokio::task;
 use crate::server::error::{Result, ServerError};
 use crate::server::config::{CacheConfig, PersistenceCacheConfig};
 use crate::server::ecs::{EcsData};
-use crate::common::consts::{CACHE_FILE_MAGIC, CACHE_FILE_VERSION};
+use crate::common::consts::{
+    CACHE_FILE_MAGIC, CACHE_FILE_VERSION,
+    CACHE_KEY_SCOPE_IPV4_PREFIX_LENGTH, CACHE_KEY_SCOPE_IPV6_PREFIX_LENGTH,
+};
 use crate::server::metrics::METRICS;
+use crate::server::request_id::current_request_id;
 
 // 缓存操作标签常量
 const CACHE_OP_HIT: &str = "hit";
 const CACHE_OP_MISS: &str = "miss";
 const CACHE_OP_INSERT: &str = "insert";
 const CACHE_OP_CLEAR: &str = "clear";
+const CACHE_OP_ASYNC_MISS_STARTED: &str = "async_miss_started";
+const CACHE_OP_ASYNC_MISS_JOINED: &str = "async_miss_joined";
+const CACHE_OP_ASYNC_MISS_TIMEOUT: &str = "async_miss_timeout";
+const CACHE_OP_SKIP: &str = "skip";
 
 // 持久化操作标签常量
 const PERSIST_OP_LOAD: &str = "load";
@@ -65,6 +77,8 @@ struct PersistableCacheKey {
     ecs_network: Option<String>,
     // ECS 作用域前缀长度（可选）
     ecs_scope_prefix_length: Option<u8>,
+    // 客户端子网（可选）
+    client_subnet: Option<String>,
 }
 
 // 持久化文件版本信息
@@ -99,6 +113,8 @@ pub struct CacheEntry {
     pub message: Arc<Message>,
     // 过期时间（Unix 时间戳，秒）
     pub expires_at: u64,
+    // 写入缓存的时间（Unix 时间戳，秒），用于计算 HTTP Age
+    pub inserted_at: u64,
     // 访问次数，使用原子类型实现无锁更新
     pub access_count: Arc<AtomicU64>,
     // 最后访问时间（Unix 时间戳，秒），使用原子类型实现无锁更新
@@ -107,6 +123,43 @@ pub struct CacheEntry {
     pub ecs_data: Option<EcsData>,
 }
 
+impl CacheEntry {
+    // 条目写入缓存后经过的时间（秒）
+    pub fn age(&self) -> u64 {
+        DnsCache::get_system_time_secs().saturating_sub(self.inserted_at)
+    }
+}
+
+// 后台解析中的缓存未命中
+pub enum PendingMiss {
+    // 当前请求启动了后台解析
+    Started(broadcast::Receiver<Option<Message>>),
+    // 已有相同查询在后台解析，当前请求加入等待
+    Joined(broadcast::Receiver<Option<Message>>),
+}
+
+impl PendingMiss {
+    // 等待后台解析结果，超时或解析失败时返回 None
+    pub async fn wait(self, timeout: Option<Duration>) -> Option<Message> {
+        let mut receiver = match self {
+            PendingMiss::Started(receiver) | PendingMiss::Joined(receiver) => receiver,
+        };
+        
+        let result = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, receiver.recv()).await {
+                Ok(result) => result,
+                Err(_) => {
+                    METRICS.cache_operations_total().with_label_values(&[CACHE_OP_ASYNC_MISS_TIMEOUT]).inc();
+                    return None;
+                },
+            },
+            None => receiver.recv().await,
+        };
+        
+        result.ok().flatten()
+    }
+}
+
 // DNS 响应缓存
 pub struct DnsCache {
     // 内部 Moka LRU 缓存
@@ -117,6 +170,10 @@ pub struct DnsCache {
     periodic_save_cancel: Option<Arc<RwLock<bool>>>,
     // 周期性缓存条目计数任务取消标记
     metrics_task_cancel: Option<Arc<RwLock<bool>>>,
+    // 正在后台解析的缓存未命中，等待者通过广播接收解析结果
+    pending_misses: Arc<DashMap<CacheKey, broadcast::Sender<Option<Message>>>>,
+    // 不缓存的响应码
+    no_cache_rcodes: Vec<ResponseCode>,
 }
 
 // 缓存键
@@ -132,19 +189,26 @@ pub struct CacheKey {
     pub ecs_network: Option<Arc<String>>,
     // ECS 作用域前缀长度（可选）
     pub ecs_scope_prefix_length: Option<u8>,
+    // 客户端子网（可选），仅在 key_scope 为 per_client_subnet 时设置
+    pub client_subnet: Option<Arc<String>>,
 }
 
 impl CacheKey {
+    // 规范化查询名：域名不区分大小写（RFC 1035 §2.3.3），统一转为小写
+    fn normalize_name(name: &Name) -> String {
+        name.to_lowercase().to_utf8()
+    }
+    
     // 创建新的缓存键
     pub fn new(name: Name, record_type: RecordType, record_class: DNSClass) -> Self {
-        // 直接从Name获取内部的utf8字符串，避免to_string()的间接转换开销
-        let name_str = name.to_utf8();
+        let name_str = Self::normalize_name(&name);
         Self {
             name: Arc::new(name_str),
             record_type: record_type.into(),
             record_class: record_class.into(),
             ecs_network: None,
             ecs_scope_prefix_length: None,
+            client_subnet: None,
         }
     }
     
@@ -157,7 +221,7 @@ impl CacheKey {
     ) -> Self {
         // 预先格式化ECS网络地址，避免重复计算
         // 使用预先估计的容量初始化字符串，避免多次重新分配
-        let name_str = name.to_utf8();
+        let name_str = Self::normalize_name(&name);
         
         // 提前计算网络字符串的大致长度 (IP + '/' + prefix数字)
         let ecs_str_len = ecs_data.address.to_string().len() + 1 + 3;
@@ -172,9 +236,24 @@ impl CacheKey {
             record_class: record_class.into(),
             ecs_network: Some(Arc::new(network_str)),
             ecs_scope_prefix_length: Some(ecs_data.scope_prefix_length),
+            client_subnet: None,
         }
     }
     
+    // 将缓存键限定到客户端所在的 /24（IPv4）或 /56（IPv6）子网
+    pub fn with_client_subnet(mut self, client_ip: IpAddr) -> Self {
+        let prefix_length = match client_ip {
+            IpAddr::V4(_) => CACHE_KEY_SCOPE_IPV4_PREFIX_LENGTH,
+            IpAddr::V6(_) => CACHE_KEY_SCOPE_IPV6_PREFIX_LENGTH,
+        };
+        // 前缀长度为常量且不超过地址位数，构造不会失败
+        let subnet = IpNet::new(client_ip, prefix_length)
+            .map(|network| network.trunc().to_string())
+            .unwrap_or_else(|_| client_ip.to_string());
+        self.client_subnet = Some(Arc::new(subnet));
+        self
+    }
+    
     // 创建缓存查找键，用于匹配客户端查询
     pub fn create_lookup_key(
         name: Name, 
@@ -189,7 +268,7 @@ impl CacheKey {
         }
     }
     
-    // 获取基础键（不包含 ECS 信息）
+    // 获取基础键（不包含 ECS 信息，保留客户端子网）
     pub fn get_base_key(&self) -> Self {
         Self {
             name: Arc::clone(&self.name),
@@ -197,6 +276,7 @@ impl CacheKey {
             record_class: self.record_class,
             ecs_network: None,
             ecs_scope_prefix_length: None,
+            client_subnet: self.client_subnet.clone(),
         }
     }
     
@@ -205,7 +285,8 @@ impl CacheKey {
         // 基本字段必须匹配
         if self.name != query_key.name || 
            self.record_type != query_key.record_type || 
-           self.record_class != query_key.record_class {
+           self.record_class != query_key.record_class ||
+           self.client_subnet != query_key.client_subnet {
             return false;
         }
         
@@ -368,11 +449,19 @@ impl DnsCache {
             .time_to_idle(std::time::Duration::from_secs(300)) // 5分钟内未使用的条目将被移除
             .build();
         
+        // 配置已在启动时校验，这里解析失败时回退到默认列表
+        let no_cache_rcodes = config.parsed_no_cache_rcodes().unwrap_or_else(|e| {
+            warn!("{}, falling back to default no_cache_rcodes", e);
+            CacheConfig::default().parsed_no_cache_rcodes().unwrap_or_default()
+        });
+        
         let mut dns_cache = DnsCache { 
             cache, 
             config: config.clone(), 
             periodic_save_cancel: None,
             metrics_task_cancel: None,
+            pending_misses: Arc::new(DashMap::new()),
+            no_cache_rcodes,
         };
         
         // 记录缓存初始状态指标
@@ -401,7 +490,7 @@ impl DnsCache {
                     let load_fut = async move {
                         let entry_count = entries.len();
                         
-                        for (i, (key, entry)) in keys.into_iter().zip(entries.into_iter()).enumerate() {
+                        for (i, (key, entry)) in keys.into_iter().zip(entries).enumerate() {
                             cache_clone.insert(key, entry).await;
                             
                             // 更新缓存条目计数指标
@@ -525,7 +614,12 @@ impl DnsCache {
     }
     
     // 基于客户端 ECS 信息查找缓存条目
-    pub async fn get_with_ecs(&self, key: &CacheKey, _client_ecs: Option<&EcsData>) -> Option<Message> {
+    pub async fn get_with_ecs(&self, key: &CacheKey, client_ecs: Option<&EcsData>) -> Option<Message> {
+        self.get_with_ecs_and_age(key, client_ecs).await.map(|(message, _)| message)
+    }
+    
+    // 查找缓存条目，同时返回条目的存在时间（秒），支持 ECS
+    pub async fn get_with_ecs_and_age(&self, key: &CacheKey, _client_ecs: Option<&EcsData>) -> Option<(Message, u64)> {
         // 检查缓存是否启用
         if !self.is_enabled() {
             return None;
@@ -546,8 +640,8 @@ impl DnsCache {
                     .with_label_values(&[CACHE_OP_HIT])
                     .inc();
                     
-                debug!("Cache hit for key: {:?}", key);
-                return Some(entry.message.as_ref().clone());
+                debug!(request_id = ?current_request_id(), "Cache hit for key: {:?}", key);
+                return Some((entry.message.as_ref().clone(), entry.age()));
             }
         }
         
@@ -571,8 +665,8 @@ impl DnsCache {
                             .with_label_values(&[CACHE_OP_HIT])
                             .inc();
                         
-                        debug!("Cache hit for base key (non-ECS): {:?}", base_key);
-                        return Some(base_entry.message.as_ref().clone());
+                        debug!(request_id = ?current_request_id(), "Cache hit for base key (non-ECS): {:?}", base_key);
+                        return Some((base_entry.message.as_ref().clone(), base_entry.age()));
                     }
                 }
             }
@@ -584,6 +678,7 @@ impl DnsCache {
                 .with_label_values(&[CACHE_OP_MISS])
                 .inc();
         }
+        debug!(request_id = ?current_request_id(), "Cache miss for key: {:?}", key);
         None
     }
     
@@ -600,16 +695,49 @@ impl DnsCache {
             return Ok(());
         }
         
+        // 截断或错误响应不进入缓存
+        if !self.is_cacheable(message) {
+            METRICS.cache_operations_total()
+                .with_label_values(&[CACHE_OP_SKIP])
+                .inc();
+            debug!(
+                request_id = ?current_request_id(),
+                truncated = message.truncated(),
+                response_code = ?message.response_code(),
+                "Skipping cache insert for key: {:?}", key
+            );
+            return Ok(());
+        }
+        
+        // 应答记录过多时截断应答部分并设置 TC 位，避免单个条目占用过多缓存
+        let mut message = message.clone();
+        if let Some(max_answer_records) = self.config.max_answer_records {
+            let answer_records = message.answers().len();
+            if answer_records > max_answer_records {
+                message.answers_mut().truncate(max_answer_records);
+                let mut header = *message.header();
+                header.set_truncated(true).set_answer_count(max_answer_records as u16);
+                message.set_header(header);
+                debug!(
+                    request_id = ?current_request_id(),
+                    answer_records,
+                    max_answer_records,
+                    "Truncated oversized answer section before caching key: {:?}", key
+                );
+            }
+        }
+        
         // 当前时间（秒）
         let now = Self::get_system_time_secs();
         
         // 计算过期时间
         let expires_at = now + ttl as u64;
         
-        // 创建缓存条目（尽量减少克隆操作）
+        // 创建缓存条目
         let entry = CacheEntry {
-            message: Arc::new(message.clone()),
+            message: Arc::new(message),
             expires_at,
+            inserted_at: now,
             access_count: Arc::new(AtomicU64::new(1)),
             last_accessed: Arc::new(AtomicU64::new(now)),
             ecs_data: client_ecs.cloned(),
@@ -624,6 +752,7 @@ impl DnsCache {
         
         // 插入到缓存
         self.cache.insert(key.clone(), entry).await;
+        debug!(request_id = ?current_request_id(), ttl, "Cache insert for key: {:?}", key);
         
         Ok(())
     }
@@ -686,6 +815,11 @@ impl DnsCache {
         min_ttl
     }
     
+    // 判断响应是否可以缓存：设置 TC 位的截断响应与配置的响应码不缓存
+    pub fn is_cacheable(&self, message: &Message) -> bool {
+        !message.truncated() && !self.no_cache_rcodes.contains(&message.response_code())
+    }
+    
     // 获取负缓存TTL
     pub fn negative_ttl(&self) -> u32 {
         self.config.ttl.negative
@@ -696,6 +830,45 @@ impl DnsCache {
         self.config.enabled
     }
     
+    // 缓存未命中时在后台执行解析，同一缓存键的并发未命中共享一次解析
+    //
+    // resolve 负责查询上游并写入缓存，返回 None 表示解析失败。
+    // 没有进行中的解析时启动新的后台任务，否则加入已有的解析等待结果。
+    pub fn prefetch_on_miss<F>(&self, key: &CacheKey, resolve: F) -> PendingMiss
+    where
+        F: Future<Output = Option<Message>> + Send + 'static,
+    {
+        let sender = match self.pending_misses.entry(key.clone()) {
+            dashmap::mapref::entry::Entry::Occupied(entry) => {
+                METRICS.cache_operations_total().with_label_values(&[CACHE_OP_ASYNC_MISS_JOINED]).inc();
+                return PendingMiss::Joined(entry.get().subscribe());
+            },
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                let (sender, _) = broadcast::channel(1);
+                entry.insert(sender.clone());
+                sender
+            },
+        };
+        
+        METRICS.cache_operations_total().with_label_values(&[CACHE_OP_ASYNC_MISS_STARTED]).inc();
+        let receiver = sender.subscribe();
+        let pending_misses = self.pending_misses.clone();
+        let key = key.clone();
+        tokio::spawn(async move {
+            let result = resolve.await;
+            // 先移除再广播，保证所有已订阅的等待者都能收到结果
+            pending_misses.remove(&key);
+            let _ = sender.send(result);
+        });
+        
+        PendingMiss::Started(receiver)
+    }
+    
+    // 当前正在后台解析的缓存未命中数量
+    pub fn pending_miss_count(&self) -> usize {
+        self.pending_misses.len()
+    }
+    
     // 清除所有缓存条目
     pub async fn clear(&self) {
         self.cache.invalidate_all();
@@ -836,6 +1009,7 @@ impl DnsCache {
                     record_class: item.key.record_class,
                     ecs_network: item.key.ecs_network.as_ref().map(|s| (**s).clone()),
                     ecs_scope_prefix_length: item.key.ecs_scope_prefix_length,
+                    client_subnet: item.key.client_subnet.as_ref().map(|s| (**s).clone()),
                 };
                 
                 let persistable_entry = PersistableCacheEntry {
@@ -945,7 +1119,7 @@ impl DnsCache {
         let mut entries = Vec::with_capacity(persistable_entries.len());
         
         for (persistable_key, persistable_entry) in persistable_keys.into_iter()
-            .zip(persistable_entries.into_iter()) 
+            .zip(persistable_entries) 
         {
             // 检查是否过期
             if config.skip_expired_on_load && persistable_entry.expires_at <= now {
@@ -962,17 +1136,21 @@ impl DnsCache {
             };
             
             // 创建缓存键和条目
+            // 旧版本保存的键可能包含大写字母，加载时统一规范化
             let key = CacheKey {
-                name: Arc::new(persistable_key.name),
+                name: Arc::new(persistable_key.name.to_ascii_lowercase()),
                 record_type: persistable_key.record_type,
                 record_class: persistable_key.record_class,
                 ecs_network: persistable_key.ecs_network.map(Arc::new),
                 ecs_scope_prefix_length: persistable_key.ecs_scope_prefix_length,
+                client_subnet: persistable_key.client_subnet.map(Arc::new),
             };
             
             let entry = CacheEntry {
                 message: Arc::new(message),
                 expires_at: persistable_entry.expires_at,
+                // 持久化文件未记录写入时间，以保存时间近似
+                inserted_at: persistable_entry.stored_at,
                 access_count: Arc::new(AtomicU64::new(persistable_entry.access_count)),
                 last_accessed: Arc::new(AtomicU64::new(persistable_entry.last_accessed)),
                 ecs_data: None,
@@ -1052,11 +1230,12 @@ impl From<&Message> for CacheKey {
         // 仅使用第一个查询作为缓存键
         if let Some(query) = message.queries().first() {
             CacheKey {
-                name: Arc::new(query.name().to_string()),
+                name: Arc::new(CacheKey::normalize_name(query.name())),
                 record_type: query.query_type().into(),
                 record_class: query.query_class().into(),
                 ecs_network: None,
                 ecs_scope_prefix_length: None,
+                client_subnet: None,
             }
         } else {
             // 创建一个空键，实际上不应该发生
@@ -1066,6 +1245,7 @@ impl From<&Message> for CacheKey {
                 record_class: 0,
                 ecs_network: None,
                 ecs_scope_prefix_length: None,
+                client_subnet: None,
             }
         }
     }
@@ -0,0 +1,210 @@
+// src/server/capture.rs
+
+// 该模块实现调试抓包（debug.capture）：在内存中保留最近的查询/响应报文，便于排查问题时回看，
+// 而无需事先开启完整日志。
+//
+// 缓冲区为固定容量的环形队列，写满后丢弃最旧的记录，因此内存占用有上限。
+// 每条记录保存时间、客户端 IP、协议、原始查询与响应报文、命中的路由规则、应答的上游与耗时，
+// 通过 GET /api/debug/captures（可按 qname 过滤）读取，DELETE 同一路径清空。
+// 端点只在配置了 admin_token 时提供，并需要 Bearer 令牌认证。
+
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use crate::common::consts::DEBUG_CAPTURES_PATH;
+use crate::server::query_log::QueryRecord;
+use crate::server::routing::normalize_query_domain;
+
+// 一条抓取的查询
+#[derive(Debug, Clone)]
+struct CapturedQuery {
+    timestamp: String,
+    client_ip: Option<IpAddr>,
+    protocol: String,
+    qname: String,
+    qtype: String,
+    rcode: String,
+    cached: bool,
+    query: Vec<u8>,
+    response: Vec<u8>,
+    rule: Option<String>,
+    upstream_group: Option<String>,
+    resolver: Option<String>,
+    latency_ms: f64,
+}
+
+// 抓取记录的 JSON 表示，报文使用标准 Base64 编码
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaptureEntry {
+    pub timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<IpAddr>,
+    pub protocol: String,
+    pub qname: String,
+    pub qtype: String,
+    pub rcode: String,
+    pub cached: bool,
+    pub query: String,
+    pub response: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolver: Option<String>,
+    pub latency_ms: f64,
+}
+
+impl From<&CapturedQuery> for CaptureEntry {
+    fn from(captured: &CapturedQuery) -> Self {
+        Self {
+            timestamp: captured.timestamp.clone(),
+            client_ip: captured.client_ip,
+            protocol: captured.protocol.clone(),
+            qname: captured.qname.clone(),
+            qtype: captured.qtype.clone(),
+            rcode: captured.rcode.clone(),
+            cached: captured.cached,
+            query: BASE64_STANDARD.encode(&captured.query),
+            response: BASE64_STANDARD.encode(&captured.response),
+            rule: captured.rule.clone(),
+            upstream_group: captured.upstream_group.clone(),
+            resolver: captured.resolver.clone(),
+            latency_ms: captured.latency_ms,
+        }
+    }
+}
+
+// 最近查询的环形缓冲区
+pub struct QueryCapture {
+    // 缓冲区容量（条）
+    size: usize,
+    entries: Mutex<VecDeque<CapturedQuery>>,
+}
+
+impl QueryCapture {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            entries: Mutex::new(VecDeque::with_capacity(size)),
+        }
+    }
+    
+    // 缓冲区容量（条）
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    
+    // 记录一条已完成的查询，缓冲区已满时丢弃最旧的记录
+    pub fn record(&self, record: &QueryRecord<'_>) {
+        let question = record.query.queries().first();
+        let captured = CapturedQuery {
+            timestamp: timestamp(),
+            client_ip: record.client_ip,
+            protocol: record.protocol.to_string(),
+            qname: question.map(|q| q.name().to_ascii()).unwrap_or_default(),
+            qtype: question.map(|q| q.query_type().to_string()).unwrap_or_default(),
+            rcode: format!("{:?}", record.response.response_code()),
+            cached: record.cached,
+            query: record.query.to_vec().unwrap_or_default(),
+            response: record.response.to_vec().unwrap_or_default(),
+            rule: record.trace.rule.clone(),
+            upstream_group: record.trace.upstream_group.clone(),
+            resolver: record.trace.resolver.clone(),
+            latency_ms: record.latency.as_secs_f64() * 1000.0,
+        };
+        
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.size {
+            entries.pop_front();
+        }
+        entries.push_back(captured);
+    }
+    
+    // 按时间倒序（最新的在前）列出记录；指定 qname 时只返回该查询名（不区分大小写，忽略尾部的点，
+    // 国际化域名按 punycode 比较）的记录
+    pub fn entries(&self, qname: Option<&str>) -> Vec<CaptureEntry> {
+        let qname = qname.map(|qname| normalize_query_domain(qname.trim()));
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.iter()
+            .rev()
+            .filter(|captured| qname.as_ref().is_none_or(|qname| normalize_query_domain(&captured.qname) == *qname))
+            .map(CaptureEntry::from)
+            .collect()
+    }
+    
+    // 清空缓冲区，返回清除的记录数
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let cleared = entries.len();
+        entries.clear();
+        cleared
+    }
+}
+
+// 与查询日志相同格式的当前时间
+fn timestamp() -> String {
+    let mut timestamp = String::new();
+    let _ = SystemTime.format_time(&mut Writer::new(&mut timestamp));
+    timestamp
+}
+
+// 读取抓取记录的查询参数
+#[derive(Debug, Deserialize)]
+struct CapturesParams {
+    qname: Option<String>,
+}
+
+// 读取抓取记录的响应
+#[derive(Debug, Serialize)]
+struct CapturesResponse {
+    size: usize,
+    count: usize,
+    captures: Vec<CaptureEntry>,
+}
+
+// 清空抓取记录的响应
+#[derive(Debug, Serialize)]
+struct ClearCapturesResponse {
+    cleared: usize,
+}
+
+// 创建抓取记录的路由（需由调用方添加认证）
+pub fn capture_routes(capture: Arc<QueryCapture>) -> Router {
+    Router::new()
+        .route(DEBUG_CAPTURES_PATH, get(handle_list_captures).delete(handle_clear_captures))
+        .with_state(capture)
+}
+
+// 处理读取抓取记录请求
+async fn handle_list_captures(
+    State(capture): State<Arc<QueryCapture>>,
+    Query(params): Query<CapturesParams>,
+) -> impl IntoResponse {
+    let captures = capture.entries(params.qname.as_deref());
+    let response = CapturesResponse {
+        size: capture.size(),
+        count: captures.len(),
+        captures,
+    };
+    (StatusCode::OK, Json(response))
+}
+
+// 处理清空抓取记录请求
+async fn handle_clear_captures(State(capture): State<Arc<QueryCapture>>) -> impl IntoResponse {
+    let cleared = capture.clear();
+    info!(cleared, "Debug capture buffer cleared");
+    (StatusCode::OK, Json(ClearCapturesResponse { cleared }))
+}
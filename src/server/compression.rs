@@ -0,0 +1,105 @@
+// src/server/compression.rs
+
+// 该模块负责 JSON API 与指标端点的响应压缩，以及 DoH 请求体的解压。
+//
+// 仅压缩 JSON 与 Prometheus 文本格式的响应；application/dns-message 响应体很小，
+// 且部分客户端无法处理压缩后的二进制响应，因此按内容类型白名单判断，其余响应保持原样。
+// 压缩前后的响应字节数计入 owdns_response_compression_bytes_total，两者之比即压缩率。
+
+use axum::{
+    body::{Body, HttpBody},
+    http::{header, Extensions, HeaderMap, Request, StatusCode, Version},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower_http::compression::{
+    predicate::{Predicate, SizeAbove},
+    CompressionLayer,
+};
+use tower_http::decompression::RequestDecompressionLayer;
+use tracing::{debug, info};
+use crate::common::consts::COMPRESSIBLE_CONTENT_TYPES;
+use crate::server::config::{CompressionAlgorithm, CompressionConfig};
+use crate::server::metrics::METRICS;
+
+// 压缩字节数指标的阶段标签
+const COMPRESSION_STAGE_UNCOMPRESSED: &str = "uncompressed";
+const COMPRESSION_STAGE_COMPRESSED: &str = "compressed";
+
+// 压缩前的响应体大小，由压缩层内侧记录到响应扩展中
+#[derive(Clone, Copy)]
+struct UncompressedSize(u64);
+
+// 为路由添加响应压缩，按客户端 Accept-Encoding 协商算法
+pub fn apply_compression(routes: Router, config: &CompressionConfig) -> Router {
+    info!(
+        algorithms = ?config.algorithms,
+        min_size = config.min_size,
+        "Response compression enabled"
+    );
+    
+    let predicate = SizeAbove::new(config.min_size).and(is_compressible);
+    let layer = CompressionLayer::new()
+        .gzip(config.algorithms.contains(&CompressionAlgorithm::Gzip))
+        .br(config.algorithms.contains(&CompressionAlgorithm::Brotli))
+        .compress_when(predicate);
+    routes
+        .layer(middleware::from_fn(record_uncompressed_size))
+        .layer(layer)
+        .layer(middleware::from_fn(record_compression_savings))
+}
+
+// 为路由添加请求体解压，支持 gzip 与 br；不支持的 Content-Encoding 返回 415
+pub fn apply_request_decompression<S: Clone + Send + Sync + 'static>(routes: Router<S>) -> Router<S> {
+    info!("Request body decompression enabled");
+    routes.layer(RequestDecompressionLayer::new())
+}
+
+// 记录处理器生成的响应体大小
+async fn record_uncompressed_size(req: Request<Body>, next: Next) -> Response {
+    let mut response = next.run(req).await;
+    if let Some(size) = response.body().size_hint().exact() {
+        response.extensions_mut().insert(UncompressedSize(size));
+    }
+    response
+}
+
+// 响应被压缩时记录压缩前后的字节数
+//
+// 压缩后的大小需要读取完整响应体才能得知；被压缩的只有 JSON 与指标响应，处理器本就一次性生成完整响应体。
+async fn record_compression_savings(req: Request<Body>, next: Next) -> Response {
+    let response = next.run(req).await;
+    let Some(UncompressedSize(uncompressed)) = response.extensions().get::<UncompressedSize>().copied() else {
+        return response;
+    };
+    if !response.headers().contains_key(header::CONTENT_ENCODING) {
+        return response;
+    }
+    
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            debug!(error = %e, "Failed to read compressed response body");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        },
+    };
+    METRICS.response_compression_bytes_total()
+        .with_label_values(&[COMPRESSION_STAGE_UNCOMPRESSED])
+        .inc_by(uncompressed);
+    METRICS.response_compression_bytes_total()
+        .with_label_values(&[COMPRESSION_STAGE_COMPRESSED])
+        .inc_by(bytes.len() as u64);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+// 响应内容类型是否在可压缩白名单中（忽略 charset 等参数）
+fn is_compressible(_status: StatusCode, _version: Version, headers: &HeaderMap, _extensions: &Extensions) -> bool {
+    headers.get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| {
+            COMPRESSIBLE_CONTENT_TYPES.iter().any(|compressible| media_type.trim().eq_ignore_ascii_case(compressible))
+        })
+}
@@ -1,26 +1,45 @@
 // src/server/config.rs
 
 use std::fs;
-use std::net::SocketAddr;
-use std::path::Path;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration;
+use axum::http::HeaderName;
+use hickory_proto::op::ResponseCode;
+use hickory_proto::rr::{Name, RecordType};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
+use tracing_subscriber::EnvFilter;
 use crate::server::error::{ServerError, Result};
+use crate::server::tls::{load_ca_cert_pem, load_certified_key, load_client_identity_pem, load_client_verifier, validate_tls_protocol};
+use crate::server::ip_set::{parse_network, parse_network_list};
+use crate::server::routing::{normalize_rule_domain, normalize_wildcard_pattern};
 use crate::common::consts::{
     // 服务器配置相关常量
-    default_listen_addr, DEFAULT_LISTEN_TIMEOUT,
+    default_listen_addr, DEFAULT_REQUEST_TIMEOUT, DEFAULT_READ_HEADER_TIMEOUT, DEFAULT_IDLE_KEEPALIVE_TIMEOUT, MAX_HTTP_SERVER_TIMEOUT_SECS, DEFAULT_DNS_SERVER_TCP_IDLE_TIMEOUT, MAX_DNS_SERVER_TCP_IDLE_TIMEOUT, DEFAULT_DOT_SERVER_IDLE_TIMEOUT, MAX_DOT_SERVER_IDLE_TIMEOUT, DEFAULT_SHUTDOWN_GRACE_PERIOD_SECS, MAX_SHUTDOWN_GRACE_PERIOD_SECS, DEFAULT_MAX_REQUEST_BODY_SIZE, MIN_REQUEST_BODY_SIZE, MAX_REQUEST_BODY_SIZE, DEFAULT_UNIX_SOCKET_MODE, DEFAULT_SERVFAIL_RETRY_AFTER_SECS, DEFAULT_REQUEST_ID_HEADER, DEFAULT_UPSTREAM_OVERRIDE_HEADER,
     // 上游服务器相关常量
-    DEFAULT_QUERY_TIMEOUT,
+    DEFAULT_QUERY_TIMEOUT, DEFAULT_UPSTREAM_RESOLVERS, DEFAULT_SRV_REFRESH_INTERVAL_SECS,
+    DEFAULT_ROOT_HINTS, DNS_NAMESERVER_PORT,
+    DEFAULT_MDNS_DOMAINS, default_mdns_multicast_addr, DEFAULT_MDNS_RESPONSE_TIMEOUT_MS,
+    MAX_MDNS_RESPONSE_TIMEOUT_MS, DEFAULT_MDNS_NEGATIVE_TTL_SECS, MIN_SRV_REFRESH_INTERVAL_SECS,
+    DEFAULT_CONSUL_REFRESH_INTERVAL_SECS, MIN_CONSUL_REFRESH_INTERVAL_SECS,
+    DEFAULT_SVCB_HINT_TTL,
+    DEFAULT_FAILURE_RATIO_WINDOW_SECS, MAX_FAILURE_RATIO_WINDOW_SECS,
+    DEFAULT_HTTP2_KEEPALIVE_INTERVAL_SECS, MAX_HTTP2_KEEPALIVE_INTERVAL_SECS,
     // 缓存相关常量
     DEFAULT_CACHE_SIZE, DEFAULT_MIN_TTL, 
-    DEFAULT_MAX_TTL, DEFAULT_NEGATIVE_TTL,
+    DEFAULT_MAX_TTL, DEFAULT_NEGATIVE_TTL, DEFAULT_ASYNC_MISS_RESOLUTION_TIMEOUT_MS, DEFAULT_NO_CACHE_RCODES,
+    DEFAULT_CACHE_MAX_ANSWER_RECORDS,
     // 速率限制相关常量
     DEFAULT_PER_IP_RATE, DEFAULT_PER_IP_CONCURRENT,
     // HTTP 客户端相关常量
     DEFAULT_HTTP_CLIENT_TIMEOUT, DEFAULT_HTTP_CLIENT_POOL_IDLE_TIMEOUT,
-    DEFAULT_HTTP_CLIENT_POOL_MAX_IDLE_CONNECTIONS, DEFAULT_HTTP_CLIENT_AGENT,
+    DEFAULT_HTTP_CLIENT_CONNECT_TIMEOUT, DEFAULT_HTTP_CLIENT_TLS_HANDSHAKE_ALLOWANCE,
+    DEFAULT_HTTP_CLIENT_POOL_MAX_IDLE_CONNECTIONS, DEFAULT_HTTP_CLIENT_POOL_MAX_CONNECTIONS, DEFAULT_HTTP_CLIENT_AGENT,
+    DEFAULT_HTTP_CLIENT_MAX_RETRIES, MAX_HTTP_CLIENT_MAX_RETRIES,
     // 分流相关常量
-    BLACKHOLE_UPSTREAM_GROUP_NAME,
+    BLACKHOLE_UPSTREAM_GROUP_NAME, GLOBAL_UPSTREAM_ROUTE_TARGET, MAX_RULE_UPSTREAM_GROUPS,
     // ECS 相关常量
     ECS_POLICY_STRIP, ECS_POLICY_FORWARD, ECS_POLICY_ANONYMIZE,
     DEFAULT_IPV4_PREFIX_LENGTH, DEFAULT_IPV6_PREFIX_LENGTH,
@@ -30,12 +49,33 @@ use crate::common::consts::{
     MAX_PER_IP_RATE,
     MIN_PER_IP_CONCURRENT,
     MAX_PER_IP_CONCURRENT,
+    DEFAULT_RATE_LIMIT_BUCKET,
+    // ACME 相关常量
+    ACME_CERT_FILE, ACME_KEY_FILE, DEFAULT_ACME_CACHE_DIR,
+    DEFAULT_ACME_DIRECTORY_URL, DEFAULT_ACME_RENEW_BEFORE_DAYS,
+    // 查询统计相关常量
+    DEFAULT_STATS_WINDOW_SECS, MIN_STATS_WINDOW_SECS, MAX_STATS_WINDOW_SECS,
+    DEFAULT_STATS_MAX_TRACKED_DOMAINS,
+    DEFAULT_DEBUG_CAPTURE_SIZE, MAX_DEBUG_CAPTURE_SIZE,
     // URL规则周期性更新相关常量
     DEFAULT_URL_RULE_UPDATE_INTERVAL_SECS,
     MIN_URL_RULE_UPDATE_INTERVAL_SECS,
     MAX_URL_RULE_UPDATE_INTERVAL_SECS,
+    // Oblivious DoH 相关常量
+    DEFAULT_ODOH_PATH, DEFAULT_ODOH_KEY_DIR, DEFAULT_ODOH_KEY_ROTATION_SECS,
+    MIN_ODOH_KEY_ROTATION_SECS, ODOH_CONFIGS_PATH, DOH_STANDARD_PATH, DOH_JSON_API_PATH,
+    STATS_TOP_DOMAINS_PATH, DNS_WEBSOCKET_PATH,
+    // CORS 相关常量
+    CORS_ANY_ORIGIN, DEFAULT_CORS_MAX_AGE_SECS, MAX_CORS_MAX_AGE_SECS, DEFAULT_COMPRESSION_MIN_SIZE,
+    // 响应填充相关常量
+    EDNS_RESPONSE_PADDING_BLOCK_SIZE, MAX_RESPONSE_PADDING_BLOCK_SIZE,
+    // 查询日志相关常量
+    DEFAULT_QUERY_LOG_SAMPLE_RATE,
 };
 
+// 内置路由路径，DoH 与 ODoH 路径不能与之冲突
+const RESERVED_HTTP_PATHS: [&str; 6] = ["/health", "/metrics", "/scalar", STATS_TOP_DOMAINS_PATH, ODOH_CONFIGS_PATH, DNS_WEBSOCKET_PATH];
+
 // 服务器配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
@@ -46,649 +86,3789 @@ pub struct ServerConfig {
     // DNS 解析器配置
     #[serde(rename = "dns_resolver")]
     pub dns: DnsResolverConfig,
+    
+    // 经典 DNS（UDP/TCP）监听配置
+    #[serde(default)]
+    pub dns_server: DnsServerConfig,
+    
+    // DNS-over-TLS 监听配置
+    #[serde(default)]
+    pub dot_server: DotServerConfig,
+    
+    // 查询统计配置
+    #[serde(default)]
+    pub stats: StatsConfig,
+    
+    // 安全相关配置
+    #[serde(default)]
+    pub security: SecurityConfig,
+    
+    // 日志输出配置
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    
+    // 调试功能配置
+    #[serde(default)]
+    pub debug: DebugConfig,
 }
 
 // HTTP 服务器配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpServerConfig {
-    // 服务器监听地址
-    #[serde(default = "default_listen_addr")]
-    pub listen_addr: SocketAddr,
+    // 服务器监听地址（单个地址或地址列表）
+    #[serde(default = "default_listen_addrs")]
+    pub listen_addr: ListenAddrs,
     
-    // 服务器连接超时（秒）
-    #[serde(default = "default_listen_timeout")]
-    pub timeout: u64,
+    // Unix 域套接字监听路径（设置后在该套接字上提供与 TCP 监听相同的应用）
+    #[serde(default)]
+    pub listen_unix: Option<PathBuf>,
+    
+    // Unix 域套接字监听配置
+    #[serde(default)]
+    pub unix_socket: UnixSocketConfig,
+    
+    // 单个请求的处理时限（秒），包含上游查询在内，超时返回 504
+    #[serde(default = "default_request_timeout", alias = "timeout")]
+    pub request_timeout: u64,
+    
+    // 读取请求头的时限（秒），超时关闭连接
+    #[serde(default = "default_read_header_timeout")]
+    pub read_header_timeout: u64,
+    
+    // 空闲 keep-alive 连接的保持时间（秒），0 表示不保持连接
+    #[serde(default = "default_idle_keepalive_timeout")]
+    pub idle_keepalive_timeout: u64,
+    
+    // 单个连接上处理的最大请求数，达到后关闭连接，0 表示不限制
+    #[serde(default)]
+    pub max_requests_per_connection: u64,
+    
+    // 同时处理的最大 DoH 请求数，超出时直接返回 503，0 表示不限制（不作用于健康检查等管理路由）
+    #[serde(default)]
+    pub max_concurrent_requests: usize,
+    
+    // 明文监听是否接受 HTTP/2 先验知识（h2c）连接，供以 h2c 连接后端的反向代理使用
+    #[serde(default = "default_disable")]
+    pub enable_h2c: bool,
+    
+    // 关闭时等待进行中查询完成的宽限期（秒），超时后仍未完成的查询返回 503
+    #[serde(default = "default_shutdown_grace_period_secs", alias = "shutdown_timeout")]
+    pub shutdown_grace_period_secs: u64,
+    
+    // 提供 DoH 查询的路径列表，也可通过 doh_path 配置单个路径
+    #[serde(default = "default_doh_paths", alias = "doh_path", deserialize_with = "deserialize_doh_paths")]
+    pub doh_paths: Vec<String>,
     
     // 速率限制配置
     #[serde(default)]
     pub rate_limit: RateLimitConfig,
-}
-
-// DNS 解析器配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DnsResolverConfig {
-    // 上游 DNS 服务器配置（全局/默认）
-    pub upstream: UpstreamConfig,
     
-    // HTTP 客户端配置
+    // 客户端 IP 访问控制（在速率限制之前执行）
     #[serde(default)]
-    pub http_client: HttpClientConfig,
+    pub access_control: AccessControlConfig,
     
-    // 缓存配置
+    // 请求体大小上限（字节），GET 请求的 dns 参数按解码后的长度限制
+    #[serde(default = "default_max_request_body_size")]
+    pub max_request_body_size: usize,
+    
+    // SERVFAIL 响应的 Retry-After 头（秒）；上游全部降级时改用预计的恢复时间，此值作为后备
+    #[serde(default = "default_servfail_retry_after_secs")]
+    pub servfail_retry_after_secs: u64,
+    
+    // 上游解析失败时的响应方式：dns_rcode 返回 200 与 SERVFAIL 消息，http_status 返回对应的 HTTP 错误状态码
     #[serde(default)]
-    pub cache: CacheConfig,
+    pub error_mode: ErrorMode,
     
-    // 路由配置（DNS分流）
+    // 是否在 wireformat 响应中添加 Cache-Control 头（RFC 8484）
+    #[serde(default = "default_emit_cache_headers")]
+    pub emit_cache_headers: bool,
+    
+    // DNS 响应超过该大小（字节）时输出警告日志，用于发现放大攻击或配置问题
     #[serde(default)]
-    pub routing: RoutingConfig,
+    pub alert_on_response_larger_than_bytes: Option<usize>,
     
-    // EDNS 客户端子网配置
+    // 指标端点认证配置（未设置时 /metrics 保持开放）
     #[serde(default)]
-    pub ecs_policy: EcsPolicyConfig,
+    pub metrics_auth: Option<MetricsAuthConfig>,
+    
+    // 管理端点令牌（Bearer），设置后启用需要认证的管理端点（如 PUT /log-level）
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    
+    // 指标端点独立监听地址（未设置时随管理路由一起提供）
+    #[serde(default)]
+    pub metrics_listen_addr: Option<SocketAddr>,
+    
+    // 管理端点（健康检查、指标、统计）独立监听地址（未设置时与 DoH 共用监听地址）
+    #[serde(default)]
+    pub admin_listen_addr: Option<SocketAddr>,
+    
+    // TLS 配置（设置后 DoH 监听直接提供 HTTPS）
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    
+    // 服务器对外的主机名（客户端 TLS SNI 使用的名称），用作 HTTPS 重定向的目标主机
+    // 未设置时使用 tls.acme.domains 中的第一个域名
+    #[serde(default)]
+    pub server_name: String,
+    
+    // 明文 HTTP 监听地址（启用 http_to_https_redirect 时使用）
+    #[serde(default)]
+    pub http_listen_addr: Option<SocketAddr>,
+    
+    // 是否在 http_listen_addr 上将所有请求 301 重定向到 HTTPS 监听
+    #[serde(default)]
+    pub http_to_https_redirect: bool,
+    
+    // HTTP/3 (QUIC) 监听配置
+    #[serde(default)]
+    pub http3: Http3Config,
+    
+    // 请求 ID 头名称（读取客户端提供的 ID，并在响应中回显）
+    #[serde(default = "default_request_id_header")]
+    pub request_id_header: String,
+    
+    // 是否在发往 DoH 上游的请求中携带请求 ID
+    #[serde(default)]
+    pub propagate_request_id_upstream: bool,
+    
+    // 允许提供请求 ID 的代理 IP 或网段（按连接源地址判断），为空时接受任意客户端提供的请求 ID
+    #[serde(default)]
+    pub request_id_trusted_proxies: Vec<String>,
+    
+    // 是否在 DoH 监听（明文 HTTP 与 TLS）接受的连接上解析 PROXY 协议头（v1/v2），以获得负载均衡器之后的真实客户端地址
+    #[serde(default)]
+    pub proxy_protocol: bool,
+    
+    // 受信任的代理 IP 或网段（按连接源地址判断）：仅这些连接的 PROXY 头与 X-Forwarded-For 等转发头被采信，其他连接按直连客户端处理
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    
+    // Oblivious DoH 目标配置
+    #[serde(default)]
+    pub odoh: OdohConfig,
+    
+    // 跨域资源共享 (CORS) 配置
+    #[serde(default)]
+    pub cors: CorsConfig,
+    
+    // JSON API 与指标端点的响应压缩配置
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    
+    // 是否解压带有 Content-Encoding（gzip、br）的 DoH 请求体，解压后的大小同样受 max_request_body_size 限制
+    #[serde(default = "default_disable")]
+    pub request_decompression: bool,
+    
+    // 允许通过请求头指定上游组的客户端 IP 或网段，为空时禁用该功能
+    #[serde(default)]
+    pub trusted_override_ips: Vec<String>,
+    
+    // 指定上游组的请求头名称
+    #[serde(default = "default_upstream_override_header")]
+    pub upstream_override_header: String,
+    
+    // DoH 端点令牌认证配置
+    #[serde(default)]
+    pub auth: AuthConfig,
+    
+    // 是否启用 DNS over WebSocket 端点 (/dns-ws)
+    #[serde(default)]
+    pub websocket_enabled: bool,
+    
+    // 是否在根路径 (/) 提供服务器信息页面
+    #[serde(default = "default_root_info_enabled")]
+    pub root_info_enabled: bool,
 }
 
-// 上游 DNS 服务器配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UpstreamConfig {
-    // 上游 DNS 服务器列表
-    pub resolvers: Vec<ResolverConfig>,
+// DoH 端点令牌认证配置，令牌可通过 Bearer 头或路径 (/dns-query/<token>) 提供
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    // 是否启用令牌认证
+    #[serde(default)]
+    pub enabled: bool,
     
-    // 是否启用 DNSSEC
+    // 内联令牌列表
     #[serde(default)]
-    pub enable_dnssec: bool,
+    pub tokens: Vec<AuthTokenConfig>,
     
-    // 查询超时时间（秒）
-    #[serde(default = "default_query_timeout")]
-    pub query_timeout: u64,
+    // 令牌文件路径，每行一个 "name:token"，空行与 '#' 开头的行被忽略
+    #[serde(default)]
+    pub token_file: Option<PathBuf>,
+    
+    // 是否同样要求 /metrics 提供令牌（与 metrics_auth 互斥）
+    #[serde(default)]
+    pub protect_metrics: bool,
 }
 
-// DNS 解析器配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResolverConfig {
-    // 解析器地址（IP:端口 或 URL）
-    pub address: String,
+// 具名访问令牌，名称用于日志与指标标签
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuthTokenConfig {
+    // 令牌名称（例如用户名）
+    pub name: String,
     
-    // 解析器协议类型
-    #[serde(default = "default_resolver_protocol")]
-    pub protocol: ResolverProtocol,
+    // 令牌值
+    pub token: String,
 }
 
-// DNS 解析器协议类型
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum ResolverProtocol {
-    // UDP 协议
-    Udp,
-    // TCP 协议
-    Tcp,
-    // DNS-over-TLS
-    Dot,
-    // DNS-over-HTTPS
-    Doh,
+impl AuthConfig {
+    // 合并内联令牌与令牌文件中的令牌
+    pub fn load_tokens(&self) -> Result<Vec<AuthTokenConfig>> {
+        let mut tokens = self.tokens.clone();
+        
+        if let Some(path) = &self.token_file {
+            let content = fs::read_to_string(path).map_err(|e| ServerError::Config(format!(
+                "Failed to read auth token_file '{}': {}",
+                path.display(), e
+            )))?;
+            
+            for (line_number, line) in content.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (name, token) = line.split_once(':').ok_or_else(|| ServerError::Config(format!(
+                    "Invalid auth token_file line {}: expected 'name:token'",
+                    line_number + 1
+                )))?;
+                tokens.push(AuthTokenConfig {
+                    name: name.trim().to_string(),
+                    token: token.trim().to_string(),
+                });
+            }
+        }
+        
+        Ok(tokens)
+    }
 }
 
-// 缓存配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CacheConfig {
-    // 是否启用缓存
-    #[serde(default = "default_disable")]
-    pub enabled: bool,
+// 客户端 IP 访问控制配置，allow 与 deny 均为空时不启用
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccessControlConfig {
+    // 允许的客户端 IP 或网段
+    #[serde(default)]
+    pub allow: Vec<String>,
     
-    // 缓存大小（条目数）
-    #[serde(default = "default_cache_size")]
-    pub size: usize,
+    // 拒绝的客户端 IP 或网段
+    #[serde(default)]
+    pub deny: Vec<String>,
     
-    // TTL 配置
+    // 两个列表的匹配顺序
     #[serde(default)]
-    pub ttl: TtlConfig,
-
-    // 持久化缓存配置
+    pub order: AccessControlOrder,
+    
+    // 拒绝请求的方式
     #[serde(default)]
-    pub persistence: PersistenceCacheConfig,
+    pub action: AccessControlAction,
 }
 
-// TTL 配置
+// 访问控制列表的匹配顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessControlOrder {
+    // 先匹配 deny（命中即拒绝），allow 非空时其余未命中 allow 的客户端同样拒绝
+    #[default]
+    DenyThenAllow,
+    // 先匹配 allow（命中即放行），其余命中 deny 的客户端拒绝
+    AllowThenDeny,
+}
+
+// 被拒绝请求的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessControlAction {
+    // 返回 403 Forbidden
+    #[default]
+    Forbidden,
+    // 不发送响应，直接中止请求（HTTP/1.1 关闭连接，HTTP/2 重置流）
+    Drop,
+}
+
+impl AccessControlConfig {
+    // 是否配置了任一列表
+    pub fn is_enabled(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty()
+    }
+}
+
+// 跨域资源共享 (CORS) 配置，供浏览器中的 DoH 客户端使用
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TtlConfig {
-    // 最小 TTL（秒）
-    #[serde(default = "default_min_ttl")]
-    pub min: u32,
+pub struct CorsConfig {
+    // 是否启用 CORS
+    #[serde(default)]
+    pub enabled: bool,
     
-    // 最大 TTL（秒）
-    #[serde(default = "default_max_ttl")]
-    pub max: u32,
+    // 允许的来源列表，"*" 表示允许任意来源
+    #[serde(default = "default_cors_allowed_origins")]
+    pub allowed_origins: Vec<String>,
     
-    // 负缓存 TTL（秒）
-    #[serde(default = "default_negative_ttl")]
-    pub negative: u32,
+    // 预检结果缓存时间（秒）
+    #[serde(default = "default_cors_max_age")]
+    pub max_age: u64,
+    
+    // 是否同时对管理路由（健康检查、指标、统计）应用 CORS
+    #[serde(default)]
+    pub include_admin_routes: bool,
 }
 
-// 速率限制配置
+impl CorsConfig {
+    // 是否允许任意来源
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == CORS_ANY_ORIGIN)
+    }
+}
+
+// 响应压缩配置，仅作用于 JSON API 与指标端点，application/dns-message 响应始终不压缩
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RateLimitConfig {
-    // 是否启用速率限制
-    #[serde(default = "default_disable")]
+pub struct CompressionConfig {
+    // 是否启用响应压缩
+    #[serde(default)]
     pub enabled: bool,
     
-    // 每个 IP 每秒最大请求数
-    #[serde(default = "default_per_ip_rate")]
-    pub per_ip_rate: u32,
+    // 可用的压缩算法，按客户端 Accept-Encoding 协商
+    #[serde(default = "default_compression_algorithms")]
+    pub algorithms: Vec<CompressionAlgorithm>,
     
-    // 单个 IP 的并发请求数限制
-    #[serde(default = "default_per_ip_concurrent")]
-    pub per_ip_concurrent: u32,
+    // 小于该大小（字节）的响应不压缩
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: u16,
 }
 
-// HTTP 客户端配置
+// 响应压缩算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompressionAlgorithm {
+    // gzip
+    #[serde(rename = "gzip")]
+    Gzip,
+    // Brotli
+    #[serde(rename = "br")]
+    Brotli,
+}
+
+// Unix 域套接字监听配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HttpClientConfig {
-    // HTTP 客户端超时时间（秒）
-    #[serde(default = "default_http_client_timeout")]
-    pub timeout: u64,
+pub struct UnixSocketConfig {
+    // 套接字文件权限（八进制字符串，如 "0660"）
+    #[serde(default = "default_unix_socket_mode")]
+    pub mode: String,
     
-    // 连接池配置
+    // 套接字文件所有者 UID（未设置时保持当前用户）
     #[serde(default)]
-    pub pool: PoolConfig,
+    pub owner: Option<u32>,
     
-    // HTTP 请求相关配置
+    // 套接字文件所属组 GID（未设置时保持当前用户组）
     #[serde(default)]
-    pub request: RequestConfig,
-}
-
-// 连接池配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PoolConfig {
-    // 连接池空闲超时时间（秒）
-    #[serde(default = "default_http_client_pool_idle_timeout")]
-    pub idle_timeout: u64,
+    pub group: Option<u32>,
     
-    // 连接池最大空闲连接数
-    #[serde(default = "default_http_client_pool_max_idle_connections")]
-    pub max_idle_connections: u32,
+    // 是否对该监听应用速率限制（所有连接共享同一个限速键）
+    #[serde(default)]
+    pub rate_limit: bool,
 }
 
-// HTTP 请求配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RequestConfig {
-    // HTTP 客户端 User-Agent
-    #[serde(default = "default_http_client_agent")]
-    pub user_agent: String,
-    
-    // 使用的 IP 代理头字段名列表
-    #[serde(default = "default_ip_header_names")]
-    pub ip_header_names: Vec<String>,
+impl UnixSocketConfig {
+    // 解析套接字文件权限
+    pub fn parsed_mode(&self) -> Result<u32> {
+        let digits = self.mode.trim().trim_start_matches("0o");
+        match u32::from_str_radix(digits, 8) {
+            Ok(mode) if mode <= 0o777 => Ok(mode),
+            _ => Err(ServerError::Config(format!(
+                "Invalid unix_socket.mode: '{}' (must be an octal permission such as \"0660\")",
+                self.mode
+            ))),
+        }
+    }
 }
 
-// 路由配置（DNS分流）
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[derive(Default)]
-pub struct RoutingConfig {
-    // 是否启用DNS分流
-    #[serde(default = "default_disable")]
-    pub enabled: bool,
-    
-    // 上游DNS服务器组
-    #[serde(default)]
-    pub upstream_groups: Vec<UpstreamGroup>,
-    
-    // 分流规则
+// HTTP/3 (QUIC) 监听配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Http3Config {
+    // 是否启用 HTTP/3（需要同时配置 tls）
     #[serde(default)]
-    pub rules: Vec<Rule>,
+    pub enabled: bool,
     
-    // 默认上游组名称（如果未匹配任何规则）
+    // QUIC 监听地址（UDP，未设置时与 listen_addr 相同）
     #[serde(default)]
-    pub default_upstream_group: Option<String>,
+    pub listen_addr: Option<SocketAddr>,
 }
 
-// 上游DNS服务器组
+// Oblivious DoH (RFC 9230) 目标配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UpstreamGroup {
-    // 组名称
-    pub name: String,
-    
-    // 是否启用DNSSEC（覆盖全局设置）
-    pub enable_dnssec: Option<bool>,
+pub struct OdohConfig {
+    // 是否启用 ODoH 目标
+    #[serde(default)]
+    pub enabled: bool,
     
-    // 查询超时时间（覆盖全局设置）
-    pub query_timeout: Option<u64>,
+    // 接收 application/oblivious-dns-message 查询的路径
+    #[serde(default = "default_odoh_path")]
+    pub path: String,
     
-    // 解析器列表
-    pub resolvers: Vec<ResolverConfig>,
+    // HPKE 私钥保存目录
+    #[serde(default = "default_odoh_key_dir")]
+    pub key_dir: PathBuf,
     
-    // 上游组级别的 ECS 策略配置（覆盖全局设置）
-    #[serde(default)]
-    pub ecs_policy: Option<EcsPolicyConfig>,
+    // 密钥轮换间隔（秒），0 表示不轮换
+    #[serde(default = "default_odoh_key_rotation_secs")]
+    pub key_rotation_secs: u64,
 }
 
-// 分流规则
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Rule {
-    // 匹配条件
-    #[serde(rename = "match")]
-    pub match_: MatchCondition,
+impl HttpServerConfig {
+    // HTTP/3 实际使用的监听地址（默认使用第一个 listen_addr），未启用时返回 None
+    pub fn http3_listen_addr(&self) -> Option<SocketAddr> {
+        self.http3.enabled.then(|| self.http3.listen_addr.unwrap_or(self.listen_addr.primary()))
+    }
     
-    // 目标上游组名称
-    pub upstream_group: String,
+    // HTTPS 重定向的监听地址，未启用或未配置 TLS 时返回 None
+    pub fn https_redirect_listen_addr(&self) -> Option<SocketAddr> {
+        self.http_listen_addr.filter(|_| self.http_to_https_redirect && self.tls.is_some())
+    }
+    
+    // 服务器对外的主机名：优先使用 server_name，其次使用第一个 ACME 域名
+    pub fn effective_server_name(&self) -> Option<&str> {
+        let server_name = self.server_name.trim();
+        if !server_name.is_empty() {
+            return Some(server_name);
+        }
+        self.tls.as_ref()
+            .filter(|tls| tls.acme.enabled)
+            .and_then(|tls| tls.acme.domains.first())
+            .map(|domain| domain.trim())
+            .filter(|domain| !domain.is_empty())
+    }
 }
 
-// 匹配条件
+// DoH 路径的配置表示形式，可写为单个路径或路径列表
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DohPathsRepr {
+    One(String),
+    Many(Vec<String>),
+}
+
+fn deserialize_doh_paths<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match DohPathsRepr::deserialize(deserializer)? {
+        DohPathsRepr::One(path) => vec![path],
+        DohPathsRepr::Many(paths) => paths,
+    })
+}
+
+// DoH 服务监听地址列表，配置中可写为单个地址或地址列表
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "ListenAddrsRepr", into = "ListenAddrsRepr")]
+pub struct ListenAddrs(Vec<SocketAddr>);
+
+// 监听地址的配置表示形式
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum ListenAddrsRepr {
+    One(SocketAddr),
+    Many(Vec<SocketAddr>),
+}
+
+impl ListenAddrs {
+    // 第一个监听地址
+    pub fn primary(&self) -> SocketAddr {
+        self.0.first().copied().unwrap_or_else(default_listen_addr)
+    }
+
+    // 是否包含指定地址
+    pub fn contains(&self, addr: &SocketAddr) -> bool {
+        self.0.contains(addr)
+    }
+
+    // 遍历所有监听地址
+    pub fn iter(&self) -> impl Iterator<Item = &SocketAddr> {
+        self.0.iter()
+    }
+
+    // 监听地址数量
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    // 是否未配置任何监听地址
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<SocketAddr> for ListenAddrs {
+    fn from(addr: SocketAddr) -> Self {
+        Self(vec![addr])
+    }
+}
+
+impl From<Vec<SocketAddr>> for ListenAddrs {
+    fn from(addrs: Vec<SocketAddr>) -> Self {
+        Self(addrs)
+    }
+}
+
+impl From<ListenAddrsRepr> for ListenAddrs {
+    fn from(repr: ListenAddrsRepr) -> Self {
+        match repr {
+            ListenAddrsRepr::One(addr) => Self(vec![addr]),
+            ListenAddrsRepr::Many(addrs) => Self(addrs),
+        }
+    }
+}
+
+impl From<ListenAddrs> for ListenAddrsRepr {
+    fn from(addrs: ListenAddrs) -> Self {
+        match addrs.0.as_slice() {
+            [addr] => ListenAddrsRepr::One(*addr),
+            _ => ListenAddrsRepr::Many(addrs.0),
+        }
+    }
+}
+
+impl std::fmt::Display for ListenAddrs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let addrs: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        write!(f, "{}", addrs.join(", "))
+    }
+}
+
+// TLS 证书配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MatchCondition {
-    // 匹配类型
-    #[serde(rename = "type")]
-    pub type_: MatchType,
+pub struct TlsConfig {
+    // PEM 格式证书链文件路径（启用 ACME 且未设置时位于 cache_dir 中）
+    #[serde(default)]
+    pub cert: Option<PathBuf>,
     
-    // 匹配值（根据类型可能是域名列表、路径或URL）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub values: Option<Vec<String>>,
+    // PEM 格式私钥文件路径（启用 ACME 且未设置时位于 cache_dir 中）
+    #[serde(default)]
+    pub key: Option<PathBuf>,
     
-    // 文件路径（用于file类型）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub path: Option<String>,
+    // ACME 自动证书配置
+    #[serde(default)]
+    pub acme: AcmeConfig,
     
-    // URL（用于url类型）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub url: Option<String>,
+    // 客户端证书认证（mTLS）配置，未设置时不要求客户端证书
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_auth: Option<ClientAuthConfig>,
     
-    // 周期性更新配置（用于url类型）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub periodic: Option<PeriodicUpdateConfig>,
+    // 接受的最低 TLS 版本，低于该版本的握手被拒绝
+    #[serde(default)]
+    pub min_version: TlsMinVersion,
+    
+    // 允许的密码套件（rustls 名称，如 TLS13_AES_256_GCM_SHA384），未设置时使用默认套件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cipher_suites: Option<Vec<String>>,
+    
+    // 通过 ALPN 协商的协议（h2、http/1.1，按优先级排序），未设置时两者均提供
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpn: Option<Vec<String>>,
 }
 
-// 匹配类型
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+// 接受的最低 TLS 版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TlsMinVersion {
+    // 接受 TLS 1.2 与 TLS 1.3
+    #[default]
+    #[serde(rename = "1.2")]
+    Tls12,
+    // 只接受 TLS 1.3
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+// 客户端证书认证（mTLS）配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientAuthConfig {
+    // 签发客户端证书的 CA 证书文件（PEM，可包含多个证书）
+    pub ca_cert: PathBuf,
+    
+    // 认证模式
+    #[serde(default)]
+    pub mode: ClientAuthMode,
+}
+
+// 客户端证书认证模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
-#[derive(Default)]
-pub enum MatchType {
-    // 精确匹配
+pub enum ClientAuthMode {
+    // 必须提供有效的客户端证书
     #[default]
-    Exact,
-    // 正则表达式匹配
-    Regex,
-    // 通配符匹配
-    Wildcard,
-    // 文件匹配
-    File,
-    // URL匹配
-    Url,
+    Required,
+    // 客户端证书可选，提供时必须有效
+    Optional,
 }
 
-// 持久化缓存配置
+impl TlsConfig {
+    // 证书链文件的实际路径
+    pub fn cert_path(&self) -> PathBuf {
+        self.cert.clone().unwrap_or_else(|| self.acme.cache_dir.join(ACME_CERT_FILE))
+    }
+    
+    // 私钥文件的实际路径
+    pub fn key_path(&self) -> PathBuf {
+        self.key.clone().unwrap_or_else(|| self.acme.cache_dir.join(ACME_KEY_FILE))
+    }
+}
+
+// ACME 自动证书配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PersistenceCacheConfig {
-    // 是否启用缓存持久化功能
-    #[serde(default = "default_disable")]
+pub struct AcmeConfig {
+    // 是否启用 ACME
+    #[serde(default)]
     pub enabled: bool,
     
-    // 缓存文件的存储路径
-    #[serde(default = "default_cache_persistence_path")]
-    pub path: String,
-    
-    // 服务启动时是否自动加载缓存
-    #[serde(default = "default_cache_load_on_startup")]
-    pub load_on_startup: bool,
+    // 申请证书的域名列表
+    #[serde(default)]
+    pub domains: Vec<String>,
     
-    // 保存到磁盘的最大缓存条目数
+    // 账户联系邮箱
     #[serde(default)]
-    pub max_items_to_save: usize,
+    pub contact: Option<String>,
     
-    // 加载时是否跳过已过期条目
-    #[serde(default = "default_cache_skip_expired_on_load")]
-    pub skip_expired_on_load: bool,
+    // 账户私钥与证书的存储目录
+    #[serde(default = "default_acme_cache_dir")]
+    pub cache_dir: PathBuf,
     
-    // 关机时保存缓存的超时时间（秒）
-    #[serde(default = "default_cache_shutdown_save_timeout")]
-    pub shutdown_save_timeout_secs: u64,
+    // ACME 目录地址
+    #[serde(default = "default_acme_directory_url")]
+    pub directory_url: String,
     
-    // 周期性保存配置
+    // 验证方式
     #[serde(default)]
-    pub periodic: PeriodicSaveConfig,
+    pub challenge: AcmeChallengeType,
+    
+    // HTTP-01 验证的监听地址（challenge 为 http-01 时必需）
+    #[serde(default)]
+    pub http01_listen_addr: Option<SocketAddr>,
+    
+    // 证书到期前多少天开始续期
+    #[serde(default = "default_acme_renew_before_days")]
+    pub renew_before_days: u64,
 }
 
-// 周期性保存配置
+// ACME 验证方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum AcmeChallengeType {
+    // 在 TLS 监听上通过 ALPN 完成验证（RFC 8737）
+    #[default]
+    #[serde(rename = "tls-alpn-01")]
+    TlsAlpn01,
+    // 在独立的 HTTP 端口上完成验证
+    #[serde(rename = "http-01")]
+    Http01,
+}
+
+// 指标端点认证配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PeriodicSaveConfig {
-    // 是否启用周期性保存
-    #[serde(default = "default_disable")]
-    pub enabled: bool,
+pub struct MetricsAuthConfig {
+    // 认证类型
+    #[serde(rename = "type")]
+    pub type_: MetricsAuthType,
     
-    // 保存间隔（秒）
-    #[serde(default = "default_cache_periodic_interval_secs")]
-    pub interval_secs: u64,
+    // Basic 认证用户名
+    #[serde(default)]
+    pub username: Option<String>,
+    
+    // Basic 认证密码
+    #[serde(default)]
+    pub password: Option<String>,
+    
+    // Bearer 认证令牌
+    #[serde(default)]
+    pub token: Option<String>,
 }
 
-// EDNS 客户端子网策略配置
+// 指标端点认证类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsAuthType {
+    // HTTP Basic 认证
+    Basic,
+    // Bearer 令牌认证
+    Bearer,
+}
+
+// DNS 解析器配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EcsPolicyConfig {
-    // 是否启用 ECS 处理策略
+pub struct DnsResolverConfig {
+    // 上游 DNS 服务器配置（全局/默认）
+    pub upstream: UpstreamConfig,
+    
+    // HTTP 客户端配置
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+    
+    // 缓存配置
+    #[serde(default)]
+    pub cache: CacheConfig,
+    
+    // 路由配置（DNS分流）
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    
+    // EDNS 客户端子网配置
+    #[serde(default)]
+    pub ecs_policy: EcsPolicyConfig,
+    
+    // 返回给客户端的记录 TTL 下限（秒），不影响缓存内部使用的 TTL
+    #[serde(default)]
+    pub min_response_ttl_override: Option<u64>,
+    
+    // 返回给客户端的记录 TTL 上限（秒），不影响缓存内部使用的 TTL
+    #[serde(default, alias = "max_serve_ttl")]
+    pub max_response_ttl_override: Option<u64>,
+    
+    // 从返回给客户端的应答部分中移除的记录类型（如 HTTPS、SVCB）
+    #[serde(default)]
+    pub strip_record_types: Vec<String>,
+    
+    // A/AAAA 应答沿 CNAME 链展平：按链的顺序整理记录并去除重复记录，仅处理应答中已有的记录
     #[serde(default = "default_disable")]
-    pub enabled: bool,
+    pub flatten_cname: bool,
     
-    // 全局 ECS 处理策略
-    #[serde(default = "default_ecs_strategy")]
-    pub strategy: String,
+    // 展平时移除中间的 CNAME，只保留所有者改写为查询名称的最终地址记录
+    #[serde(default = "default_flatten_cname_strip_chain")]
+    pub flatten_cname_strip_chain: bool,
     
-    // 匿名化配置
+    // 缓存与返回前去除上游响应中各部分之间重复的记录（按名称、类型与记录数据判断）
+    #[serde(default = "default_disable")]
+    pub response_deduplication: bool,
+    
+    // mDNS 转发配置（.local 等本地域名通过组播查询解析）
     #[serde(default)]
-    pub anonymization: EcsAnonymizationConfig,
+    pub mdns_forwarder: MdnsForwarderConfig,
+    
+    // 本地合成 SVCB/HTTPS 应答的名称（名称 -> SvcParams），这些名称的其他查询类型仍发送到上游
+    #[serde(default)]
+    pub svcb_hints: std::collections::HashMap<String, SvcbHintConfig>,
 }
 
-// EDNS 客户端子网匿名化配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EcsAnonymizationConfig {
-    // IPv4 地址匿名化保留前缀长度
-    #[serde(default = "default_ipv4_prefix_length")]
-    pub ipv4_prefix_length: u8,
+// 本地合成的 SVCB/HTTPS 记录参数（RFC 9460），记录以 ServiceMode 指向名称本身
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SvcbHintConfig {
+    // 支持的应用层协议（如 h3、h2）
+    #[serde(default)]
+    pub alpn: Vec<String>,
     
-    // IPv6 地址匿名化保留前缀长度
-    #[serde(default = "default_ipv6_prefix_length")]
-    pub ipv6_prefix_length: u8,
+    // 替代端口
+    #[serde(default)]
+    pub port: Option<u16>,
+    
+    // IPv4 地址提示
+    #[serde(default)]
+    pub ipv4hint: Vec<Ipv4Addr>,
+    
+    // IPv6 地址提示
+    #[serde(default)]
+    pub ipv6hint: Vec<Ipv6Addr>,
+    
+    // 记录 TTL（秒）
+    #[serde(default = "default_svcb_hint_ttl")]
+    pub ttl: u32,
 }
 
-// URL规则周期性更新配置
+// mDNS 转发配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PeriodicUpdateConfig {
-    // 是否启用周期性更新
-    #[serde(default)]
+pub struct MdnsForwarderConfig {
+    // 是否启用 mDNS 转发
+    #[serde(default = "default_disable")]
     pub enabled: bool,
     
-    // 更新间隔（秒）
-    #[serde(default = "default_url_rule_update_interval")]
-    pub interval_secs: u64,
-}
-
-// 默认值函数 - 使用 consts 中定义的常量
-fn default_resolver_protocol() -> ResolverProtocol {
-    ResolverProtocol::Udp
+    // 通过 mDNS 解析的域名后缀
+    #[serde(default = "default_mdns_domains")]
+    pub domains: Vec<String>,
+    
+    // 组播查询的目标地址
+    #[serde(default = "default_mdns_multicast_addr")]
+    pub multicast_addr: SocketAddr,
+    
+    // 收集 mDNS 响应的超时时间（毫秒）
+    #[serde(default = "default_mdns_response_timeout_ms")]
+    pub mdns_response_timeout_ms: u64,
+    
+    // 超时无响应时负结果的缓存时间（秒），0 表示不缓存
+    #[serde(default = "default_mdns_negative_ttl_secs")]
+    pub mdns_negative_ttl_secs: u32,
 }
 
-fn default_query_timeout() -> u64 {
-    DEFAULT_QUERY_TIMEOUT
+impl DnsResolverConfig {
+    // 解析需要移除的记录类型，支持类型名称（不区分大小写）与数字编号
+    pub fn parsed_strip_record_types(&self) -> Result<Vec<RecordType>> {
+        self.strip_record_types.iter()
+            .map(|value| {
+                let record_type = match value.parse::<u16>() {
+                    Ok(num) => RecordType::from(num),
+                    Err(_) => RecordType::from_str(&value.to_ascii_uppercase())
+                        .unwrap_or(RecordType::Unknown(0)),
+                };
+                match record_type {
+                    RecordType::Unknown(_) => Err(ServerError::Config(format!(
+                        "Invalid dns_resolver.strip_record_types entry: '{}'",
+                        value
+                    ))),
+                    record_type => Ok(record_type),
+                }
+            })
+            .collect()
+    }
 }
 
-fn default_disable() -> bool {
-    false
+// 上游 DNS 服务器配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamConfig {
+    // 上游 DNS 服务器列表（递归模式下全局上游不使用，可配置为空列表）
+    pub resolvers: Vec<ResolverConfig>,
+    
+    // 解析模式：forwarding 转发到 resolvers，recursive 从根服务器开始迭代解析（仅全局上游）
+    #[serde(default)]
+    pub mode: UpstreamMode,
+    
+    // 递归模式的根服务器地址（IP 或 IP:端口），默认为 IANA 根服务器
+    #[serde(default = "default_root_hints")]
+    pub root_hints: Vec<String>,
+    
+    // 是否启用 DNSSEC
+    #[serde(default)]
+    pub enable_dnssec: bool,
+    
+    // 查询超时时间（秒）
+    #[serde(default = "default_query_timeout")]
+    pub query_timeout: u64,
+    
+    // 是否启用 EDNS 填充（RFC 7830），对上游查询及 TLS 下的响应进行填充
+    #[serde(default)]
+    pub edns_padding: bool,
+    
+    // 是否对 TCP/DoT 上游复用连接并发送 EDNS TCP Keepalive 选项（RFC 7828），
+    // 空闲连接的保持时间使用上游通告的超时，未通告时使用连接池的 idle_timeout
+    #[serde(default)]
+    pub tcp_keepalive: bool,
+    
+    // 是否通过 SRV 记录发现 DoH 上游（仅全局上游，resolvers 作为引导解析器）
+    #[serde(default)]
+    pub discover_via_srv: bool,
+    
+    // 用于发现上游的 SRV 名称，如 _dns-query._tcp.example.com
+    #[serde(default)]
+    pub srv_name: String,
+    
+    // SRV 记录刷新间隔（秒）
+    #[serde(default = "default_srv_refresh_interval_secs")]
+    pub srv_refresh_interval_secs: u64,
+    
+    // 通过 Consul 健康检查接口发现 DoH 上游（上游组不继承全局设置，需在组内单独配置）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consul_upstream_discovery: Option<ConsulUpstreamDiscoveryConfig>,
+    
+    // DoH 上游失败率超过该值（0-1）时标记为降级并降低其选择优先级，未设置时不跟踪
+    #[serde(default)]
+    pub failure_ratio_threshold: Option<f64>,
+    
+    // 降级上游的失败率低于该值时恢复优先级，未设置时为 failure_ratio_threshold 的一半
+    #[serde(default)]
+    pub recovery_threshold: Option<f64>,
+    
+    // 失败率统计的滑动窗口（秒）
+    #[serde(default = "default_rolling_window_secs")]
+    pub rolling_window_secs: u64,
+    
+    // DoH 上游是否优先使用 HTTP/2，使并发查询复用同一连接
+    #[serde(default = "default_prefer_http2")]
+    pub prefer_http2: bool,
+    
+    // DoH 上游 HTTP/2 连接的 PING 间隔（秒），0 表示不发送
+    #[serde(default = "default_http2_keepalive_interval_secs")]
+    pub http2_keepalive_interval_secs: u64,
+    
+    // 上游查询使用的本地源地址（多出口主机按策略路由选择出口），未设置时由系统选择
+    #[serde(default)]
+    pub bind_addr: Option<IpAddr>,
+    
+    // 是否校验上游响应与查询一致（问题、应答名称链、权威否定应答、DoH 来源），丢弃疑似投毒的响应
+    #[serde(default = "default_strict_response_validation")]
+    pub strict_response_validation: bool,
+    
+    // 是否以 REFUSED 拒绝私有地址（RFC 1918、RFC 4193、链路本地）的 PTR 查询，不转发到上游
+    #[serde(default = "default_disable")]
+    pub refused_types_for_private_ips: bool,
+    
+    // 无条件以 REFUSED 拒绝的记录类型（如 AXFR、IXFR），支持类型名称或数值
+    #[serde(default)]
+    pub refused_types: Vec<String>,
 }
 
-fn default_cache_size() -> usize {
-    DEFAULT_CACHE_SIZE
+impl UpstreamConfig {
+    // 解析根服务器地址，未指定端口时使用 53
+    pub fn parsed_root_hints(&self) -> Result<Vec<SocketAddr>> {
+        self.root_hints.iter()
+            .map(|hint| {
+                hint.parse::<SocketAddr>()
+                    .or_else(|_| hint.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, DNS_NAMESERVER_PORT)))
+                    .map_err(|_| ServerError::Config(format!(
+                        "Invalid upstream.root_hints entry: '{}' (must be an IP address or IP:port)",
+                        hint
+                    )))
+            })
+            .collect()
+    }
+    
+    // 解析需要拒绝的记录类型
+    pub fn parsed_refused_types(&self) -> Result<Vec<RecordType>> {
+        self.refused_types.iter()
+            .map(|value| {
+                let record_type = match (value.parse::<u16>(), value.to_ascii_uppercase()) {
+                    (Ok(num), _) => RecordType::from(num),
+                    // hickory 不支持按名称解析 IXFR
+                    (Err(_), name) if name == "IXFR" => RecordType::IXFR,
+                    (Err(_), name) => RecordType::from_str(&name).unwrap_or(RecordType::Unknown(0)),
+                };
+                match record_type {
+                    RecordType::Unknown(_) => Err(ServerError::Config(format!(
+                        "Invalid dns_resolver.upstream.refused_types entry: '{}'",
+                        value
+                    ))),
+                    record_type => Ok(record_type),
+                }
+            })
+            .collect()
+    }
+    
+    // 降级上游恢复优先级的失败率阈值
+    pub fn effective_recovery_threshold(&self) -> Option<f64> {
+        self.failure_ratio_threshold
+            .map(|threshold| self.recovery_threshold.unwrap_or(threshold / 2.0))
+    }
 }
 
-fn default_min_ttl() -> u32 {
-    DEFAULT_MIN_TTL
+// DNS 解析器配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolverConfig {
+    // 解析器地址（IP:端口 或 URL）
+    pub address: String,
+    
+    // 解析器协议类型
+    #[serde(default = "default_resolver_protocol")]
+    pub protocol: ResolverProtocol,
+    
+    // DoH 解析器的 URL 路径，与 address 中的主机部分组合
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    
+    // 上游要求双向 TLS 时出示的客户端证书（PEM，仅 DoT/DoH）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert_path: Option<PathBuf>,
+    
+    // 客户端证书的私钥（PKCS#8 PEM）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<PathBuf>,
+    
+    // 验证上游服务器证书的额外 CA 证书（PEM），未配置时只使用系统信任库
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_path: Option<PathBuf>,
 }
 
-fn default_max_ttl() -> u32 {
-    DEFAULT_MAX_TTL
-}
+impl ResolverConfig {
+    // 是否配置了客户端证书或 CA 等 TLS 客户端设置
+    pub fn has_tls_client_settings(&self) -> bool {
+        self.client_cert_path.is_some() || self.client_key_path.is_some() || self.ca_cert_path.is_some()
+    }
+    
+    // 加载客户端证书与私钥（PEM），未配置时返回 None，只配置其中之一时返回错误
+    pub fn client_identity_pem(&self) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        match (&self.client_cert_path, &self.client_key_path) {
+            (None, None) => Ok(None),
+            (Some(cert), Some(key)) => load_client_identity_pem(cert, key).map(Some),
+            _ => Err(ServerError::Config(format!(
+                "Resolver {} requires both client_cert_path and client_key_path for mutual TLS",
+                self.address
+            ))),
+        }
+    }
+    
+    // 加载验证上游服务器证书的 CA 证书（PEM），未配置时返回 None
+    pub fn ca_cert_pem(&self) -> Result<Option<Vec<u8>>> {
+        self.ca_cert_path.as_deref().map(load_ca_cert_pem).transpose()
+    }
+    
+    // DoH 解析器的完整 URL
+    //
+    // address 为完整 URL 时原样使用（兼容旧配置）；address 只有主机部分时补全 https:// 前缀，
+    // 并拼接 path（未配置 path 时使用 /dns-query）
+    pub fn doh_url(&self) -> String {
+        let address = self.address.trim();
+        let (base, has_scheme) = match address.split_once("://") {
+            Some(_) => (address.to_string(), true),
+            None => (format!("https://{}", address), false),
+        };
+        match &self.path {
+            Some(path) => format!("{}{}", base.trim_end_matches('/'), path),
+            None if !has_scheme => format!("{}{}", base.trim_end_matches('/'), DOH_STANDARD_PATH),
+            None => base,
+        }
+    }
+    
 
-fn default_negative_ttl() -> u32 {
-    DEFAULT_NEGATIVE_TTL
+    // 解析 UDP/TCP/DoT 解析器的目标地址，返回 (DoT 的 TLS 域名, socket 地址)
+    // 支持 "ip:port" 与 "[ipv6]:port"，DoT 格式为 "domain@ip:port"
+    pub fn parse_socket_addr(&self) -> Result<(Option<String>, SocketAddr)> {
+        match self.protocol {
+            ResolverProtocol::Dot => {
+                let Some((domain, addr)) = self.address.split_once('@') else {
+                    return Err(ServerError::Config(format!(
+                        "DoT resolver address must be in format 'domain@ip:port' (IPv6: 'domain@[ipv6]:port'): {}",
+                        self.address
+                    )));
+                };
+                if domain.trim().is_empty() {
+                    return Err(ServerError::Config(format!(
+                        "DoT resolver address is missing the TLS server name before '@': {}",
+                        self.address
+                    )));
+                }
+                Ok((Some(domain.to_string()), parse_resolver_socket_addr(addr)?))
+            },
+            ResolverProtocol::Udp | ResolverProtocol::Tcp => {
+                Ok((None, parse_resolver_socket_addr(&self.address)?))
+            },
+            ResolverProtocol::Doh => Err(ServerError::Config(format!(
+                "DoH resolver address is a URL, not a socket address: {}",
+                self.address
+            ))),
+        }
+    }
 }
 
-fn default_per_ip_rate() -> u32 {
-    DEFAULT_PER_IP_RATE
+// 解析解析器 socket 地址，对常见的书写错误给出明确提示
+fn parse_resolver_socket_addr(addr: &str) -> Result<SocketAddr> {
+    let addr = addr.trim();
+    if let Ok(socket_addr) = addr.parse::<SocketAddr>() {
+        return Ok(socket_addr);
+    }
+    
+    // 未加方括号的 IPv6 地址（无法区分地址与端口）
+    if !addr.starts_with('[') && addr.matches(':').count() > 1 {
+        return Err(ServerError::Config(format!(
+            "IPv6 resolver address '{}' must be bracketed and include a port, e.g. '[2001:4860:4860::8888]:53'",
+            addr
+        )));
+    }
+    
+    // 缺少端口的 IP 地址
+    if let Ok(ip) = addr.trim_start_matches('[').trim_end_matches(']').parse::<std::net::IpAddr>() {
+        let hint = if ip.is_ipv6() { format!("[{}]:53", ip) } else { format!("{}:53", ip) };
+        return Err(ServerError::Config(format!(
+            "Resolver address '{}' is missing a port, e.g. '{}'",
+            addr, hint
+        )));
+    }
+    
+    Err(ServerError::Config(format!(
+        "Invalid resolver address '{}', expected 'ip:port' or '[ipv6]:port'",
+        addr
+    )))
 }
 
-fn default_per_ip_concurrent() -> u32 {
-    DEFAULT_PER_IP_CONCURRENT
+// DNS 解析器协议类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResolverProtocol {
+    // UDP 协议
+    Udp,
+    // TCP 协议
+    Tcp,
+    // DNS-over-TLS
+    Dot,
+    // DNS-over-HTTPS
+    Doh,
 }
 
-fn default_listen_timeout() -> u64 {
-    DEFAULT_LISTEN_TIMEOUT
-}
+// 缓存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    // 是否启用缓存
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 缓存大小（条目数）
+    #[serde(default = "default_cache_size")]
+    pub size: usize,
+    
+    // TTL 配置
+    #[serde(default)]
+    pub ttl: TtlConfig,
 
-fn default_http_client_timeout() -> u64 {
-    DEFAULT_HTTP_CLIENT_TIMEOUT
-}
+    // 持久化缓存配置
+    #[serde(default)]
+    pub persistence: PersistenceCacheConfig,
 
-fn default_http_client_pool_idle_timeout() -> u64 {
-    DEFAULT_HTTP_CLIENT_POOL_IDLE_TIMEOUT
-}
+    // 缓存未命中时在后台解析，同一查询的并发请求等待共享的解析结果
+    #[serde(default)]
+    pub async_miss_resolution: bool,
 
-fn default_http_client_pool_max_idle_connections() -> u32 {
-    DEFAULT_HTTP_CLIENT_POOL_MAX_IDLE_CONNECTIONS
-}
+    // 后台解析时是否立即向首个请求返回 SERVFAIL（需启用 async_miss_resolution）
+    #[serde(default)]
+    pub return_servfail_on_miss: bool,
 
-fn default_http_client_agent() -> String {
-    DEFAULT_HTTP_CLIENT_AGENT.to_string()
-}
+    // 后续请求等待后台解析结果的超时时间（毫秒），超时后返回 SERVFAIL
+    #[serde(default = "default_async_miss_resolution_timeout_ms")]
+    pub async_miss_resolution_timeout_ms: u64,
 
-fn default_ip_header_names() -> Vec<String> {
-    crate::common::consts::IP_HEADER_NAMES.iter().map(|&s| s.to_string()).collect()
-}
+    // 不缓存的响应码（助记符如 SERVFAIL 或数值），设置 TC 位的响应始终不缓存
+    #[serde(default = "default_no_cache_rcodes")]
+    pub no_cache_rcodes: Vec<String>,
 
-// 默认缓存持久化路径
-fn default_cache_persistence_path() -> String {
-    "./cache.dat".to_string()
-}
+    // 缓存响应的最大应答记录数，超出时截断应答部分并设置 TC 位后缓存，None 表示不限制
+    #[serde(default = "default_max_answer_records")]
+    pub max_answer_records: Option<usize>,
 
-// 默认启动时加载缓存
-fn default_cache_load_on_startup() -> bool {
-    true
+    // 缓存键的作用域：global 为所有客户端共享，per_client_subnet 按客户端子网隔离
+    #[serde(default)]
+    pub key_scope: CacheKeyScope,
 }
 
-// 默认加载时跳过已过期条目
-fn default_cache_skip_expired_on_load() -> bool {
-    true
+// 缓存键作用域
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheKeyScope {
+    // 所有客户端共享缓存条目
+    #[default]
+    Global,
+    // 缓存键包含客户端所在的 /24（IPv4）或 /56（IPv6）子网，不同子网的客户端互不共享缓存条目
+    PerClientSubnet,
 }
 
-// 默认周期性保存间隔
-fn default_cache_periodic_interval_secs() -> u64 {
-    3600  // 1小时
+impl CacheConfig {
+    // 解析不缓存的响应码列表
+    pub fn parsed_no_cache_rcodes(&self) -> Result<Vec<ResponseCode>> {
+        self.no_cache_rcodes.iter()
+            .map(|value| parse_response_code(value).ok_or_else(|| ServerError::Config(format!(
+                "Invalid dns_resolver.cache.no_cache_rcodes entry: '{}'",
+                value
+            ))))
+            .collect()
+    }
 }
 
-// 默认关机保存超时
-fn default_cache_shutdown_save_timeout() -> u64 {
-    30  // 30秒
+// 解析响应码，支持助记符（不区分大小写）与 0-4095 的数值
+fn parse_response_code(value: &str) -> Option<ResponseCode> {
+    let value = value.trim();
+    if let Ok(code) = value.parse::<u16>() {
+        return (code <= 0x0FFF).then(|| code.into());
+    }
+    let code = match value.to_ascii_uppercase().as_str() {
+        "NOERROR" => ResponseCode::NoError,
+        "FORMERR" => ResponseCode::FormErr,
+        "SERVFAIL" => ResponseCode::ServFail,
+        "NXDOMAIN" => ResponseCode::NXDomain,
+        "NOTIMP" => ResponseCode::NotImp,
+        "REFUSED" => ResponseCode::Refused,
+        "YXDOMAIN" => ResponseCode::YXDomain,
+        "YXRRSET" => ResponseCode::YXRRSet,
+        "NXRRSET" => ResponseCode::NXRRSet,
+        "NOTAUTH" => ResponseCode::NotAuth,
+        "NOTZONE" => ResponseCode::NotZone,
+        "BADVERS" => ResponseCode::BADVERS,
+        _ => return None,
+    };
+    Some(code)
 }
 
-// 默认 ECS 策略为剥离
-fn default_ecs_strategy() -> String {
-    ECS_POLICY_STRIP.to_string()
+// TTL 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtlConfig {
+    // 最小 TTL（秒）
+    #[serde(default = "default_min_ttl")]
+    pub min: u32,
+    
+    // 最大 TTL（秒）
+    #[serde(default = "default_max_ttl")]
+    pub max: u32,
+    
+    // 负缓存 TTL（秒）
+    #[serde(default = "default_negative_ttl")]
+    pub negative: u32,
 }
 
-// 默认 IPv4 匿名化前缀长度
-fn default_ipv4_prefix_length() -> u8 {
-    DEFAULT_IPV4_PREFIX_LENGTH
+// 速率限制配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    // 是否启用速率限制
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 每个 IP 每秒最大请求数
+    #[serde(default = "default_per_ip_rate")]
+    pub per_ip_rate: u32,
+    
+    // 单个 IP 的并发请求数限制
+    #[serde(default = "default_per_ip_concurrent")]
+    pub per_ip_concurrent: u32,
+    
+    // 按认证令牌名称或客户端网段覆盖的速率限制，令牌匹配优先于网段，同类按顺序取第一个匹配项
+    #[serde(default)]
+    pub overrides: Vec<RateLimitOverrideConfig>,
+    
+    // 缓存命中是否计入速率限制；为 false 时仅在请求实际需要查询上游时才消耗配额
+    #[serde(default = "default_count_cache_hits")]
+    pub count_cache_hits: bool,
 }
 
-// 默认 IPv6 匿名化前缀长度
-fn default_ipv6_prefix_length() -> u8 {
-    DEFAULT_IPV6_PREFIX_LENGTH
+// 速率限制覆盖配置
+// 令牌匹配的覆盖按令牌名称计数，网段匹配的覆盖按客户端 IP 计数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitOverrideConfig {
+    // 覆盖名称，出现在 429 响应的 X-RateLimit-Bucket 头中
+    pub name: String,
+    
+    // 匹配的客户端 IP 或 CIDR 网段
+    #[serde(default)]
+    pub cidrs: Vec<String>,
+    
+    // 匹配的认证令牌名称（需启用 http_server.auth）
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    
+    // 每秒最大请求数（未设置时使用 per_ip_rate）
+    #[serde(default)]
+    pub rate: Option<u32>,
+    
+    // 突发请求数（未设置时使用 per_ip_concurrent）
+    #[serde(default)]
+    pub burst: Option<u32>,
+    
+    // 是否完全跳过速率限制
+    #[serde(default)]
+    pub unlimited: bool,
 }
 
-// 默认URL规则更新间隔
-fn default_url_rule_update_interval() -> u64 {
-    DEFAULT_URL_RULE_UPDATE_INTERVAL_SECS
+// HTTP 客户端配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpClientConfig {
+    // HTTP 客户端超时时间（秒）
+    #[serde(default = "default_http_client_timeout")]
+    pub timeout: u64,
+    
+    // TCP 连接超时时间（秒）
+    #[serde(default = "default_http_client_connect_timeout")]
+    pub connect_timeout: u64,
+    
+    // 建立连接时为 TLS 握手额外预留的时间（秒）。
+    // reqwest 不单独限制 TLS 握手，TCP 连接与 TLS 握手共用 connect_timeout + tls_handshake_allowance 的总时限
+    #[serde(default = "default_http_client_tls_handshake_allowance")]
+    pub tls_handshake_allowance: u64,
+    
+    // 连接池配置
+    #[serde(default)]
+    pub pool: PoolConfig,
+    
+    // HTTP 请求相关配置
+    #[serde(default)]
+    pub request: RequestConfig,
+    
+    // DoH 上游返回这些状态码时在同一上游上重试（如 429、502、503）
+    #[serde(default)]
+    pub retry_status: Vec<u16>,
+    
+    // 按状态码重试的最大次数，总耗时不超过 query_timeout
+    #[serde(default = "default_http_client_max_retries")]
+    pub max_retries: u32,
 }
 
-impl ServerConfig {
-    // 从配置文件加载配置
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let config_str = fs::read_to_string(path)
-            .map_err(|e| ServerError::Config(format!("Failed to read config file: {}", e)))?;
-            
-        let config: ServerConfig = serde_yaml::from_str(&config_str)
-            .map_err(|e| ServerError::Config(format!("Failed to parse config: {}", e)))?;
-            
-        // 验证配置
-        config.test()?;
-        
-        Ok(config)
-    }
+// 连接池配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolConfig {
+    // 连接池空闲超时时间（秒）
+    #[serde(default = "default_http_client_pool_idle_timeout")]
+    pub idle_timeout: u64,
     
-    // 获取服务器监听超时时间
-    pub fn listen_timeout(&self) -> Duration {
-        Duration::from_secs(self.http.timeout)
-    }
+    // 连接池最大空闲连接数
+    #[serde(default = "default_http_client_pool_max_idle_connections")]
+    pub max_idle_connections: u32,
     
-    // 获取上游查询超时时间
-    pub fn query_timeout(&self) -> Duration {
-        Duration::from_secs(self.dns.upstream.query_timeout)
-    }
+    // 同时进行中的上游请求（连接）数上限，0 表示不限制
+    #[serde(default = "default_http_client_pool_max_connections")]
+    pub max_connections: u32,
+}
+
+// 上游组级别的连接池配置，未设置的字段继承 http_client.pool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionPoolConfig {
+    // 连接池最大空闲连接数
+    #[serde(default)]
+    pub max_idle_connections: Option<u32>,
     
-    // 获取 HTTP 客户端超时时间
-    pub fn http_client_timeout(&self) -> Duration {
-        Duration::from_secs(self.dns.http_client.timeout)
-    }
+    // 连接池空闲超时时间（秒）
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
     
-    // 获取 HTTP 客户端连接池空闲超时时间
-    pub fn http_client_pool_idle_timeout(&self) -> Duration {
-        Duration::from_secs(self.dns.http_client.pool.idle_timeout)
-    }
+    // 同时进行中的上游请求（连接）数上限，0 表示不限制
+    #[serde(default)]
+    pub max_connections: Option<u32>,
+}
+
+// HTTP 请求配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestConfig {
+    // HTTP 客户端 User-Agent
+    #[serde(default = "default_http_client_agent")]
+    pub user_agent: String,
     
-    // 获取上游组的有效配置（包含继承和覆盖）
-    pub fn get_effective_upstream_config(&self, group_name: &str) -> Result<UpstreamConfig> {
-        // 如果是黑洞，返回错误，因为黑洞不应该被用于实际查询
-        if group_name == BLACKHOLE_UPSTREAM_GROUP_NAME {
-            return Err(ServerError::UpstreamGroupNotFound(format!(
-                "Cannot get effective config for blackhole group: {}", 
-                BLACKHOLE_UPSTREAM_GROUP_NAME
+    // 使用的 IP 代理头字段名列表
+    #[serde(default = "default_ip_header_names")]
+    pub ip_header_names: Vec<String>,
+}
+
+// 路由配置（DNS分流）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    // 是否启用DNS分流
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 上游DNS服务器组
+    #[serde(default)]
+    pub upstream_groups: Vec<UpstreamGroup>,
+    
+    // 分流规则
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    
+    // 默认上游组名称（如果未匹配任何规则）
+    #[serde(default)]
+    pub default_upstream_group: Option<String>,
+    
+    // 远程规则集地址（YAML 格式的规则列表，与 rules 结构相同，在本地规则之后匹配）
+    #[serde(default)]
+    pub rules_url: Option<String>,
+    
+    // 远程规则集重新加载间隔（秒），0 表示仅在启动时加载
+    #[serde(default = "default_url_rule_update_interval")]
+    pub rules_reload_interval_secs: u64,
+    
+    // 多条规则同时命中时，选择健康评分最高的上游组，而不是第一条命中的规则
+    #[serde(default)]
+    pub load_balance_across_groups: bool,
+}
+
+// 上游DNS服务器组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamGroup {
+    // 组名称
+    pub name: String,
+    
+    // 是否启用DNSSEC（覆盖全局设置）
+    pub enable_dnssec: Option<bool>,
+    
+    // 查询超时时间（覆盖全局设置）
+    pub query_timeout: Option<u64>,
+    
+    // 解析器列表
+    pub resolvers: Vec<ResolverConfig>,
+    
+    // 上游组级别的 ECS 策略配置（覆盖全局设置）
+    #[serde(default)]
+    pub ecs_policy: Option<EcsPolicyConfig>,
+    
+    // 上游组独立的连接池配置（覆盖全局 http_client.pool）
+    #[serde(default)]
+    pub connection_pool: Option<ConnectionPoolConfig>,
+    
+    // 通过 Consul 发现该组的 DoH 上游，resolvers 作为全部发现的上游不健康时的后备
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consul_upstream_discovery: Option<ConsulUpstreamDiscoveryConfig>,
+}
+
+// Consul 上游发现配置
+//
+// 启动时及之后定期查询 /v1/health/service/<service_name>?passing，
+// 以通过健康检查的实例替换发现的上游；没有健康实例时回退到静态配置的解析器。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsulUpstreamDiscoveryConfig {
+    // Consul HTTP API 地址，如 http://127.0.0.1:8500
+    pub consul_addr: String,
+    
+    // 注册 DoH 上游的服务名称
+    pub service_name: String,
+    
+    // 发现的上游使用的协议，目前仅支持 doh
+    #[serde(default = "default_consul_protocol")]
+    pub protocol: ResolverProtocol,
+    
+    // 刷新间隔（秒）
+    #[serde(default = "default_consul_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+    
+    // 只使用带有该标签的服务实例
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check_tag: Option<String>,
+}
+
+// 分流规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    // 匹配条件
+    #[serde(rename = "match")]
+    pub match_: MatchCondition,
+    
+    // 目标上游组名称
+    #[serde(default)]
+    pub upstream_group: String,
+    
+    // 按顺序尝试的上游组列表（与 upstream_group 二选一），前一组传输失败时回退到下一组
+    #[serde(default)]
+    pub upstream_groups: Vec<String>,
+    
+    // 故障转移链：目标上游组查询失败（包括超时）或返回 SERVFAIL 时按顺序改用的上游组
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failover_chain: Vec<String>,
+}
+
+impl Rule {
+    // 规则的目标上游组：配置了 upstream_groups 时按列表顺序，否则为单个 upstream_group
+    pub fn target_groups(&self) -> Vec<String> {
+        if self.upstream_groups.is_empty() {
+            vec![self.upstream_group.clone()]
+        } else {
+            self.upstream_groups.clone()
+        }
+    }
+    
+    // 校验目标上游组，返回错误描述
+    pub fn check_target_groups(&self, group_names: &std::collections::HashSet<String>) -> std::result::Result<(), String> {
+        if !self.upstream_group.is_empty() && !self.upstream_groups.is_empty() {
+            return Err("cannot set both upstream_group and upstream_groups".to_string());
+        }
+        
+        let groups = self.target_groups();
+        if groups.len() > MAX_RULE_UPSTREAM_GROUPS {
+            return Err(format!(
+                "lists {} upstream groups, at most {} are allowed",
+                groups.len(), MAX_RULE_UPSTREAM_GROUPS
+            ));
+        }
+        
+        for (i, group) in groups.iter().enumerate() {
+            if group.is_empty() {
+                return Err("has no upstream group".to_string());
+            }
+            if is_special_route_target(group) {
+                // 黑洞与全局上游不属于上游组，不能作为回退列表的一部分
+                if groups.len() > 1 {
+                    return Err(format!("cannot use {} in upstream_groups", group));
+                }
+                continue;
+            }
+            if !group_names.contains(group) {
+                return Err(format!("references unknown upstream group: {}", group));
+            }
+            if groups[..i].contains(group) {
+                return Err(format!("lists upstream group {} more than once", group));
+            }
+        }
+        
+        if self.failover_chain.is_empty() {
+            return Ok(());
+        }
+        if let Some(group) = groups.iter().find(|group| is_special_route_target(group)) {
+            return Err(format!("cannot set failover_chain for {}", group));
+        }
+        if self.failover_chain.len() > MAX_RULE_UPSTREAM_GROUPS {
+            return Err(format!(
+                "lists {} failover_chain groups, at most {} are allowed",
+                self.failover_chain.len(), MAX_RULE_UPSTREAM_GROUPS
+            ));
+        }
+        for (i, group) in self.failover_chain.iter().enumerate() {
+            if is_special_route_target(group) {
+                return Err(format!("cannot use {} in failover_chain", group));
+            }
+            if !group_names.contains(group) {
+                return Err(format!("references unknown failover_chain group: {}", group));
+            }
+            if groups.contains(group) || self.failover_chain[..i].contains(group) {
+                return Err(format!("lists upstream group {} more than once", group));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // 校验规则引用的上游组（包括规则目录的文件映射），错误消息包含规则序号（从 1 开始）与规则描述
+    pub fn validate_targets(&self, rule_index: usize, group_names: &std::collections::HashSet<String>) -> Result<()> {
+        self.check_target_groups(group_names)
+            .map_err(|e| ServerError::Config(format!("Rule #{} ({}) {}", rule_index, self.describe(), e)))?;
+        
+        for (file, group) in self.match_.source.iter().flat_map(|source| &source.groups) {
+            if !is_special_route_target(group) && !group_names.contains(group) {
+                return Err(ServerError::Config(format!(
+                    "Rule #{} ({}) rule file '{}' maps to unknown upstream group: {}",
+                    rule_index, self.describe(), file, group
+                )));
+            }
+        }
+        Ok(())
+    }
+    
+    // 规则的简要描述：匹配类型与匹配值（最多列出 3 个）、文件路径、URL 或目录
+    pub fn describe(&self) -> String {
+        let match_ = &self.match_;
+        let (kind, detail) = match match_.type_ {
+            MatchType::File => ("file", match_.path.clone()),
+            MatchType::Url => ("url", match_.url.clone()),
+            MatchType::Dir => ("dir", match_.source.as_ref().map(|source| source.dir.clone())),
+            ref other => {
+                let kind = match other {
+                    MatchType::Regex => "regex",
+                    MatchType::Wildcard => "wildcard",
+                    MatchType::ClientIp => "client_ip",
+                    _ => "exact",
+                };
+                let values = match_.values.as_ref().map(|values| {
+                    let shown = values.iter().take(3).cloned().collect::<Vec<_>>().join(", ");
+                    match values.len() > 3 {
+                        true => format!("{}, ...", shown),
+                        false => shown,
+                    }
+                });
+                (kind, values)
+            },
+        };
+        format!("{}: {}", kind, detail.unwrap_or_default())
+    }
+}
+
+// 是否为不需要在 upstream_groups 中定义的特殊路由目标：黑洞或全局上游
+pub fn is_special_route_target(group: &str) -> bool {
+    group == BLACKHOLE_UPSTREAM_GROUP_NAME || group == GLOBAL_UPSTREAM_ROUTE_TARGET
+}
+
+// 匹配条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchCondition {
+    // 匹配类型
+    #[serde(rename = "type")]
+    pub type_: MatchType,
+    
+    // 匹配值（根据类型可能是域名列表或网段列表）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<String>>,
+    
+    // 文件路径（用于file与client_ip类型）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    
+    // URL（用于url与client_ip类型）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    
+    // 周期性更新配置（用于url与client_ip类型）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub periodic: Option<PeriodicUpdateConfig>,
+    
+    // 规则目录来源（用于dir类型）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<RuleSourceConfig>,
+}
+
+// 规则目录来源配置：目录下的每个规则文件合并加载，收到 SIGHUP 时重新扫描
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSourceConfig {
+    // 规则文件所在目录
+    pub dir: String,
+    
+    // 规则文件格式
+    #[serde(default)]
+    pub format: RuleFileFormat,
+    
+    // 文件名（不含扩展名）到上游组的映射，未映射的文件使用规则的目标上游组
+    #[serde(default)]
+    pub groups: std::collections::HashMap<String, String>,
+}
+
+// 规则文件格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleFileFormat {
+    // 每行一条域名规则，支持 regex: 与 wildcard: 前缀，与 file 类型的文件格式相同
+    #[default]
+    DomainList,
+}
+
+// 匹配类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum MatchType {
+    // 精确匹配
+    #[default]
+    Exact,
+    // 正则表达式匹配
+    Regex,
+    // 通配符匹配
+    Wildcard,
+    // 文件匹配
+    File,
+    // URL匹配
+    Url,
+    // 客户端 IP 匹配（按客户端源地址所属网段路由）
+    #[serde(rename = "client_ip")]
+    ClientIp,
+    // 目录匹配（合并目录下的所有规则文件）
+    Dir,
+}
+
+// 持久化缓存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistenceCacheConfig {
+    // 是否启用缓存持久化功能
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 缓存文件的存储路径
+    #[serde(default = "default_cache_persistence_path")]
+    pub path: String,
+    
+    // 服务启动时是否自动加载缓存
+    #[serde(default = "default_cache_load_on_startup")]
+    pub load_on_startup: bool,
+    
+    // 保存到磁盘的最大缓存条目数
+    #[serde(default)]
+    pub max_items_to_save: usize,
+    
+    // 加载时是否跳过已过期条目
+    #[serde(default = "default_cache_skip_expired_on_load")]
+    pub skip_expired_on_load: bool,
+    
+    // 关机时保存缓存的超时时间（秒）
+    #[serde(default = "default_cache_shutdown_save_timeout")]
+    pub shutdown_save_timeout_secs: u64,
+    
+    // 周期性保存配置
+    #[serde(default)]
+    pub periodic: PeriodicSaveConfig,
+}
+
+// 周期性保存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodicSaveConfig {
+    // 是否启用周期性保存
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 保存间隔（秒）
+    #[serde(default = "default_cache_periodic_interval_secs")]
+    pub interval_secs: u64,
+}
+
+// EDNS 客户端子网策略配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcsPolicyConfig {
+    // 是否启用 ECS 处理策略
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 全局 ECS 处理策略
+    #[serde(default = "default_ecs_strategy")]
+    pub strategy: String,
+    
+    // 匿名化配置
+    #[serde(default)]
+    pub anonymization: EcsAnonymizationConfig,
+}
+
+// EDNS 客户端子网匿名化配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcsAnonymizationConfig {
+    // IPv4 地址匿名化保留前缀长度
+    #[serde(default = "default_ipv4_prefix_length")]
+    pub ipv4_prefix_length: u8,
+    
+    // IPv6 地址匿名化保留前缀长度
+    #[serde(default = "default_ipv6_prefix_length")]
+    pub ipv6_prefix_length: u8,
+}
+
+// URL规则周期性更新配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodicUpdateConfig {
+    // 是否启用周期性更新
+    #[serde(default)]
+    pub enabled: bool,
+    
+    // 更新间隔（秒）
+    #[serde(default = "default_url_rule_update_interval")]
+    pub interval_secs: u64,
+}
+
+// 经典 DNS（UDP/TCP）监听配置，查询与 DoH 共用缓存、路由与上游
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsServerConfig {
+    // UDP 监听地址（未设置时不监听 UDP）
+    #[serde(default)]
+    pub listen_udp: Option<SocketAddr>,
+    
+    // TCP 监听地址（未设置时不监听 TCP）
+    #[serde(default)]
+    pub listen_tcp: Option<SocketAddr>,
+    
+    // TCP 连接的空闲超时（秒），超时未收到下一个查询时关闭连接
+    #[serde(default = "default_dns_server_tcp_idle_timeout")]
+    pub tcp_idle_timeout: u64,
+    
+    // 是否在 TCP 监听接受的连接上解析 PROXY 协议头，可信代理由 http_server.trusted_proxies 指定
+    #[serde(default)]
+    pub proxy_protocol: bool,
+}
+
+impl DnsServerConfig {
+    // 是否配置了任一经典 DNS 监听
+    pub fn is_enabled(&self) -> bool {
+        self.listen_udp.is_some() || self.listen_tcp.is_some()
+    }
+}
+
+// DNS-over-TLS（RFC 7858）监听配置，查询与 DoH 共用缓存、路由与上游
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DotServerConfig {
+    // TLS 监听地址（未设置时不启用 DoT）
+    #[serde(default)]
+    pub listen_addr: Option<SocketAddr>,
+    
+    // PEM 格式证书链文件路径，收到 SIGHUP 时与 HTTPS 证书一同重新加载
+    #[serde(default)]
+    pub cert: Option<PathBuf>,
+    
+    // PEM 格式私钥文件路径
+    #[serde(default)]
+    pub key: Option<PathBuf>,
+    
+    // 连接的空闲超时（秒），没有进行中的查询且超时未收到新查询时关闭连接
+    #[serde(default = "default_dot_server_idle_timeout")]
+    pub idle_timeout: u64,
+    
+    // 是否在 TLS 握手之前解析 PROXY 协议头，可信代理由 http_server.trusted_proxies 指定
+    #[serde(default)]
+    pub proxy_protocol: bool,
+}
+
+impl DotServerConfig {
+    // 证书配置，供 TLS 上下文加载与热替换
+    pub fn tls_config(&self) -> TlsConfig {
+        TlsConfig {
+            cert: self.cert.clone(),
+            key: self.key.clone(),
+            acme: AcmeConfig::default(),
+            client_auth: None,
+            min_version: TlsMinVersion::default(),
+            cipher_suites: None,
+            alpn: None,
+        }
+    }
+}
+
+// 查询统计配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsConfig {
+    // 是否启用按域名的查询统计
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 热门域名统计的滑动窗口大小（秒）
+    #[serde(default = "default_stats_window_secs")]
+    pub top_domains_window_secs: u64,
+    
+    // 最多跟踪的域名数，达到上限后不再跟踪新域名
+    #[serde(default = "default_stats_max_tracked_domains")]
+    pub max_tracked_domains: usize,
+}
+
+// 调试功能配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugConfig {
+    // 最近查询的抓包缓冲区
+    #[serde(default)]
+    pub capture: DebugCaptureConfig,
+}
+
+// 调试抓包配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugCaptureConfig {
+    // 是否启用（需要配置 admin_token）
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 缓冲区容量（条），写满后丢弃最旧的记录
+    #[serde(default = "default_debug_capture_size")]
+    pub size: usize,
+}
+
+// 日志输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    // 单行文本
+    #[default]
+    Text,
+    // 每行一个 JSON 对象，便于 ELK、Loki 等日志系统采集
+    Json,
+    // 多行易读格式，适合本地调试
+    Pretty,
+}
+
+// 日志配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    // 日志输出格式
+    #[serde(default)]
+    pub format: LogFormat,
+    
+    // 日志过滤规则（EnvFilter 语法），未设置时使用内置规则；RUST_LOG 环境变量与 --debug 参数优先
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    
+    // 是否输出产生日志的源文件与行号
+    #[serde(default = "default_disable")]
+    pub add_source_location: bool,
+    
+    // DNS 查询访问日志配置
+    #[serde(default)]
+    pub query_log: QueryLogConfig,
+}
+
+// 上游解析失败时的响应方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorMode {
+    // 返回 200 与 SERVFAIL 消息
+    #[default]
+    DnsRcode,
+    // 按失败类别返回 HTTP 错误状态码：超时 504，不可达 502，校验失败 500
+    HttpStatus,
+}
+
+// 上游解析模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpstreamMode {
+    // 转发到配置的上游解析器
+    #[default]
+    Forwarding,
+    // 从根服务器开始迭代解析
+    Recursive,
+}
+
+// 查询日志输出目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryLogTarget {
+    // 标准输出
+    #[default]
+    Stdout,
+    // 追加写入 path 指定的文件
+    File,
+}
+
+// 查询日志格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryLogFormat {
+    // 每条查询一行 JSON 对象
+    #[default]
+    Json,
+}
+
+// 查询日志过滤器动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryLogFilterAction {
+    // 始终记录，不受采样率影响
+    Log,
+    // 不记录
+    Skip,
+}
+
+// 查询日志过滤器匹配条件，查询名与查询类型须同时匹配
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryLogMatchConfig {
+    // 匹配类型，仅支持 exact 与 regex
+    #[serde(rename = "type", default)]
+    pub type_: MatchType,
+    
+    // 匹配的查询名（不含末尾的点，不区分大小写），为空时匹配所有查询名
+    #[serde(default)]
+    pub values: Vec<String>,
+    
+    // 匹配的查询类型（如 AAAA），为空时匹配所有类型
+    #[serde(default)]
+    pub query_types: Vec<String>,
+}
+
+// 查询日志过滤器，按顺序求值，首个匹配的过滤器生效
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryLogFilterConfig {
+    // 匹配条件
+    #[serde(rename = "match")]
+    pub match_: QueryLogMatchConfig,
+    
+    // 匹配时的动作
+    pub action: QueryLogFilterAction,
+}
+
+// DNS 查询访问日志配置
+//
+// 每条查询输出一行，由独立的写入线程写出，不阻塞请求处理。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryLogConfig {
+    // 是否启用查询日志
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 输出目标
+    #[serde(default)]
+    pub target: QueryLogTarget,
+    
+    // 日志文件路径（target 为 file 时必需）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    
+    // 输出格式
+    #[serde(default)]
+    pub format: QueryLogFormat,
+    
+    // 是否以 HMAC-SHA256 摘要代替客户端 IP 输出
+    #[serde(default = "default_disable")]
+    pub hash_client_ip: bool,
+    
+    // 客户端 IP 摘要的密钥，未设置时每次启动随机生成（摘要无法跨重启关联）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_key: Option<String>,
+    
+    // 采样率（0.0-1.0），未匹配任何过滤器的查询按该概率记录
+    #[serde(default = "default_query_log_sample_rate")]
+    pub sample_rate: f64,
+    
+    // 查询日志过滤器，在采样之前求值
+    #[serde(default)]
+    pub filters: Vec<QueryLogFilterConfig>,
+}
+
+// 安全相关配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecurityConfig {
+    // 响应填充配置
+    #[serde(default)]
+    pub padding: PaddingConfig,
+}
+
+// 响应 EDNS 填充配置（RFC 8467）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaddingConfig {
+    // 是否对包含 OPT 记录的查询的 wireformat 响应进行填充
+    #[serde(default = "default_disable")]
+    pub enabled: bool,
+    
+    // 填充块大小（字节），响应长度填充到该值的整数倍
+    #[serde(default = "default_padding_block_size")]
+    pub block_size: usize,
+}
+
+// 默认值函数 - 使用 consts 中定义的常量
+fn default_resolver_protocol() -> ResolverProtocol {
+    ResolverProtocol::Udp
+}
+
+fn default_query_timeout() -> u64 {
+    DEFAULT_QUERY_TIMEOUT
+}
+
+fn default_padding_block_size() -> usize {
+    EDNS_RESPONSE_PADDING_BLOCK_SIZE
+}
+
+fn default_mdns_domains() -> Vec<String> {
+    DEFAULT_MDNS_DOMAINS.iter().map(|domain| domain.to_string()).collect()
+}
+
+fn default_mdns_response_timeout_ms() -> u64 {
+    DEFAULT_MDNS_RESPONSE_TIMEOUT_MS
+}
+
+fn default_mdns_negative_ttl_secs() -> u32 {
+    DEFAULT_MDNS_NEGATIVE_TTL_SECS
+}
+
+fn default_srv_refresh_interval_secs() -> u64 {
+    DEFAULT_SRV_REFRESH_INTERVAL_SECS
+}
+
+fn default_root_hints() -> Vec<String> {
+    DEFAULT_ROOT_HINTS.iter().map(|hint| hint.to_string()).collect()
+}
+
+fn default_rolling_window_secs() -> u64 {
+    DEFAULT_FAILURE_RATIO_WINDOW_SECS
+}
+
+fn default_disable() -> bool {
+    false
+}
+
+fn default_emit_cache_headers() -> bool {
+    true
+}
+
+fn default_root_info_enabled() -> bool {
+    true
+}
+
+fn default_prefer_http2() -> bool {
+    true
+}
+
+fn default_count_cache_hits() -> bool {
+    true
+}
+
+fn default_consul_protocol() -> ResolverProtocol {
+    ResolverProtocol::Doh
+}
+
+fn default_consul_refresh_interval_secs() -> u64 {
+    DEFAULT_CONSUL_REFRESH_INTERVAL_SECS
+}
+
+fn default_query_log_sample_rate() -> f64 {
+    DEFAULT_QUERY_LOG_SAMPLE_RATE
+}
+
+fn default_strict_response_validation() -> bool {
+    true
+}
+
+fn default_flatten_cname_strip_chain() -> bool {
+    true
+}
+
+fn default_http2_keepalive_interval_secs() -> u64 {
+    DEFAULT_HTTP2_KEEPALIVE_INTERVAL_SECS
+}
+
+fn default_cache_size() -> usize {
+    DEFAULT_CACHE_SIZE
+}
+
+fn default_min_ttl() -> u32 {
+    DEFAULT_MIN_TTL
+}
+
+fn default_max_ttl() -> u32 {
+    DEFAULT_MAX_TTL
+}
+
+fn default_negative_ttl() -> u32 {
+    DEFAULT_NEGATIVE_TTL
+}
+
+fn default_per_ip_rate() -> u32 {
+    DEFAULT_PER_IP_RATE
+}
+
+fn default_per_ip_concurrent() -> u32 {
+    DEFAULT_PER_IP_CONCURRENT
+}
+
+fn default_http_client_max_retries() -> u32 {
+    DEFAULT_HTTP_CLIENT_MAX_RETRIES
+}
+
+fn default_async_miss_resolution_timeout_ms() -> u64 {
+    DEFAULT_ASYNC_MISS_RESOLUTION_TIMEOUT_MS
+}
+
+fn default_no_cache_rcodes() -> Vec<String> {
+    DEFAULT_NO_CACHE_RCODES.iter().map(|rcode| rcode.to_string()).collect()
+}
+
+fn default_max_answer_records() -> Option<usize> {
+    Some(DEFAULT_CACHE_MAX_ANSWER_RECORDS)
+}
+
+fn default_unix_socket_mode() -> String {
+    DEFAULT_UNIX_SOCKET_MODE.to_string()
+}
+
+fn default_listen_addrs() -> ListenAddrs {
+    ListenAddrs::from(default_listen_addr())
+}
+
+fn default_dns_server_tcp_idle_timeout() -> u64 {
+    DEFAULT_DNS_SERVER_TCP_IDLE_TIMEOUT
+}
+
+fn default_dot_server_idle_timeout() -> u64 {
+    DEFAULT_DOT_SERVER_IDLE_TIMEOUT
+}
+
+fn default_request_timeout() -> u64 {
+    DEFAULT_REQUEST_TIMEOUT
+}
+
+fn default_read_header_timeout() -> u64 {
+    DEFAULT_READ_HEADER_TIMEOUT
+}
+
+fn default_idle_keepalive_timeout() -> u64 {
+    DEFAULT_IDLE_KEEPALIVE_TIMEOUT
+}
+
+fn default_shutdown_grace_period_secs() -> u64 {
+    DEFAULT_SHUTDOWN_GRACE_PERIOD_SECS
+}
+
+fn default_acme_cache_dir() -> PathBuf {
+    PathBuf::from(DEFAULT_ACME_CACHE_DIR)
+}
+
+fn default_acme_directory_url() -> String {
+    DEFAULT_ACME_DIRECTORY_URL.to_string()
+}
+
+fn default_acme_renew_before_days() -> u64 {
+    DEFAULT_ACME_RENEW_BEFORE_DAYS
+}
+
+fn default_debug_capture_size() -> usize {
+    DEFAULT_DEBUG_CAPTURE_SIZE
+}
+
+fn default_servfail_retry_after_secs() -> u64 {
+    DEFAULT_SERVFAIL_RETRY_AFTER_SECS
+}
+
+fn default_svcb_hint_ttl() -> u32 {
+    DEFAULT_SVCB_HINT_TTL
+}
+
+fn default_doh_paths() -> Vec<String> {
+    vec![DOH_STANDARD_PATH.to_string(), DOH_JSON_API_PATH.to_string()]
+}
+
+fn default_max_request_body_size() -> usize {
+    DEFAULT_MAX_REQUEST_BODY_SIZE
+}
+
+fn default_request_id_header() -> String {
+    DEFAULT_REQUEST_ID_HEADER.to_string()
+}
+
+fn default_upstream_override_header() -> String {
+    DEFAULT_UPSTREAM_OVERRIDE_HEADER.to_string()
+}
+
+fn default_odoh_path() -> String {
+    DEFAULT_ODOH_PATH.to_string()
+}
+
+fn default_cors_allowed_origins() -> Vec<String> {
+    vec![CORS_ANY_ORIGIN.to_string()]
+}
+
+fn default_cors_max_age() -> u64 {
+    DEFAULT_CORS_MAX_AGE_SECS
+}
+
+fn default_compression_algorithms() -> Vec<CompressionAlgorithm> {
+    vec![CompressionAlgorithm::Gzip, CompressionAlgorithm::Brotli]
+}
+
+fn default_compression_min_size() -> u16 {
+    DEFAULT_COMPRESSION_MIN_SIZE
+}
+
+fn default_odoh_key_dir() -> PathBuf {
+    PathBuf::from(DEFAULT_ODOH_KEY_DIR)
+}
+
+fn default_odoh_key_rotation_secs() -> u64 {
+    DEFAULT_ODOH_KEY_ROTATION_SECS
+}
+
+fn default_http_client_timeout() -> u64 {
+    DEFAULT_HTTP_CLIENT_TIMEOUT
+}
+
+fn default_http_client_connect_timeout() -> u64 {
+    DEFAULT_HTTP_CLIENT_CONNECT_TIMEOUT
+}
+
+fn default_http_client_tls_handshake_allowance() -> u64 {
+    DEFAULT_HTTP_CLIENT_TLS_HANDSHAKE_ALLOWANCE
+}
+
+fn default_http_client_pool_idle_timeout() -> u64 {
+    DEFAULT_HTTP_CLIENT_POOL_IDLE_TIMEOUT
+}
+
+fn default_http_client_pool_max_idle_connections() -> u32 {
+    DEFAULT_HTTP_CLIENT_POOL_MAX_IDLE_CONNECTIONS
+}
+
+fn default_http_client_pool_max_connections() -> u32 {
+    DEFAULT_HTTP_CLIENT_POOL_MAX_CONNECTIONS
+}
+
+fn default_http_client_agent() -> String {
+    DEFAULT_HTTP_CLIENT_AGENT.to_string()
+}
+
+fn default_ip_header_names() -> Vec<String> {
+    crate::common::consts::IP_HEADER_NAMES.iter().map(|&s| s.to_string()).collect()
+}
+
+// 默认缓存持久化路径
+fn default_cache_persistence_path() -> String {
+    "./cache.dat".to_string()
+}
+
+// 默认启动时加载缓存
+fn default_cache_load_on_startup() -> bool {
+    true
+}
+
+// 默认加载时跳过已过期条目
+fn default_cache_skip_expired_on_load() -> bool {
+    true
+}
+
+// 默认周期性保存间隔
+fn default_cache_periodic_interval_secs() -> u64 {
+    3600  // 1小时
+}
+
+// 默认关机保存超时
+fn default_cache_shutdown_save_timeout() -> u64 {
+    30  // 30秒
+}
+
+// 默认 ECS 策略为剥离
+fn default_ecs_strategy() -> String {
+    ECS_POLICY_STRIP.to_string()
+}
+
+// 默认 IPv4 匿名化前缀长度
+fn default_ipv4_prefix_length() -> u8 {
+    DEFAULT_IPV4_PREFIX_LENGTH
+}
+
+// 默认 IPv6 匿名化前缀长度
+fn default_ipv6_prefix_length() -> u8 {
+    DEFAULT_IPV6_PREFIX_LENGTH
+}
+
+// 默认URL规则更新间隔
+fn default_url_rule_update_interval() -> u64 {
+    DEFAULT_URL_RULE_UPDATE_INTERVAL_SECS
+}
+
+// 默认查询统计窗口与最多跟踪的域名数
+fn default_stats_window_secs() -> u64 {
+    DEFAULT_STATS_WINDOW_SECS
+}
+
+fn default_stats_max_tracked_domains() -> usize {
+    DEFAULT_STATS_MAX_TRACKED_DOMAINS
+}
+
+impl ServerConfig {
+    // 从配置文件加载配置
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let config = Self::parse_file(path)?;
+            
+        // 验证配置
+        config.test()?;
+        
+        Ok(config)
+    }
+    
+    // 从文件读取并解析配置，不做验证
+    pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let config_str = fs::read_to_string(path)
+            .map_err(|e| ServerError::Config(format!("Failed to read config file: {}", e)))?;
+            
+        serde_yaml::from_str(&config_str)
+            .map_err(|e| ServerError::Config(format!("Failed to parse config: {}", e)))
+    }
+    
+    // 获取请求处理超时时间
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.http.request_timeout)
+    }
+    
+    // 获取上游查询超时时间
+    pub fn query_timeout(&self) -> Duration {
+        Duration::from_secs(self.dns.upstream.query_timeout)
+    }
+    
+    // 获取 HTTP 客户端超时时间
+    pub fn http_client_timeout(&self) -> Duration {
+        Duration::from_secs(self.dns.http_client.timeout)
+    }
+    
+    // 获取 HTTP 客户端建立连接（TCP 连接与 TLS 握手）的总时限
+    pub fn http_client_connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.dns.http_client.connect_timeout + self.dns.http_client.tls_handshake_allowance)
+    }
+    
+    // 获取 HTTP 客户端连接池空闲超时时间
+    pub fn http_client_pool_idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.dns.http_client.pool.idle_timeout)
+    }
+    
+    // 获取上游组的有效配置（包含继承和覆盖）
+    pub fn get_effective_upstream_config(&self, group_name: &str) -> Result<UpstreamConfig> {
+        // 如果是黑洞，返回错误，因为黑洞不应该被用于实际查询
+        if group_name == BLACKHOLE_UPSTREAM_GROUP_NAME {
+            return Err(ServerError::UpstreamGroupNotFound(format!(
+                "Cannot get effective config for blackhole group: {}", 
+                BLACKHOLE_UPSTREAM_GROUP_NAME
+            )));
+        }
+        
+        // 查找指定的上游组
+        if let Some(group) = self.dns.routing.upstream_groups.iter().find(|g| g.name == group_name) {
+            // 创建新的配置，继承全局配置并应用组特定的覆盖
+            let mut config = self.dns.upstream.clone();
+            
+            // 覆盖解析器列表
+            config.resolvers = group.resolvers.clone();
+            
+            // SRV 发现只作用于全局上游，上游组不继承
+            config.discover_via_srv = false;
+            
+            // Consul 发现按组配置，不继承全局设置
+            config.consul_upstream_discovery = group.consul_upstream_discovery.clone();
+            
+            // 递归模式只作用于全局上游，上游组转发到各自的解析器
+            config.mode = UpstreamMode::Forwarding;
+            
+            // 可选地覆盖其他设置
+            if let Some(enable_dnssec) = group.enable_dnssec {
+                config.enable_dnssec = enable_dnssec;
+            }
+            
+            if let Some(query_timeout) = group.query_timeout {
+                config.query_timeout = query_timeout;
+            }
+            
+            Ok(config)
+        } else {
+            Err(ServerError::UpstreamGroupNotFound(format!(
+                "Upstream group not found: {}", 
+                group_name
+            )))
+        }
+    }
+    
+    // 客户端 IP 是否允许通过请求头指定上游组
+    pub fn is_trusted_override_ip(&self, client_ip: std::net::IpAddr) -> bool {
+        let client_ip = client_ip.to_canonical();
+        self.http.trusted_override_ips.iter()
+            .filter_map(|entry| parse_network(entry).ok())
+            .any(|network| network.contains(&client_ip))
+    }
+    
+    // 获取上游组的有效连接池配置（组内未设置的字段继承 http_client.pool）
+    pub fn get_effective_connection_pool(&self, group_name: &str) -> PoolConfig {
+        let mut pool = self.dns.http_client.pool.clone();
+        
+        let group_pool = self.dns.routing.upstream_groups.iter()
+            .find(|g| g.name == group_name)
+            .and_then(|g| g.connection_pool.as_ref());
+        if let Some(group_pool) = group_pool {
+            if let Some(max_idle_connections) = group_pool.max_idle_connections {
+                pool.max_idle_connections = max_idle_connections;
+            }
+            if let Some(idle_timeout) = group_pool.idle_timeout_secs {
+                pool.idle_timeout = idle_timeout;
+            }
+            if let Some(max_connections) = group_pool.max_connections {
+                pool.max_connections = max_connections;
+            }
+        }
+        
+        pool
+    }
+    
+    // 获取特定上游组的有效 ECS 策略配置
+    pub fn get_effective_ecs_policy(&self, group_name: &str) -> Result<EcsPolicyConfig> {
+        // 如果指定了组名，尝试查找该组
+        if !group_name.is_empty() && group_name != BLACKHOLE_UPSTREAM_GROUP_NAME {
+            if let Some(group) = self.dns.routing.upstream_groups
+                .iter()
+                .find(|g| g.name == group_name) {
+                // 如果组存在且指定了 ECS 策略，则使用组策略
+                if let Some(ecs_policy) = &group.ecs_policy {
+                    return Ok(ecs_policy.clone());
+                }
+            }
+        }
+        
+        // 否则使用全局 ECS 策略
+        Ok(self.dns.ecs_policy.clone())
+    }
+    
+    // 验证配置有效性
+    pub fn test(&self) -> Result<()> {
+        // 验证速率限制配置
+        self.validate_rate_limit()?;
+        
+        // 验证 DoH 监听地址
+        self.validate_listen_addrs()?;
+        
+        // 验证 Unix 域套接字监听
+        self.validate_unix_socket()?;
+        
+        // 验证关闭宽限期
+        self.validate_shutdown_grace_period()?;
+        
+        // 验证 HTTP 服务器超时
+        self.validate_http_server_timeouts()?;
+        
+        // 验证管理监听地址
+        self.validate_admin_listener()?;
+        
+        // 验证管理端点令牌
+        self.validate_admin_token()?;
+        
+        // 验证指标端点配置
+        self.validate_metrics_endpoint()?;
+        
+        // 验证 TLS 证书与私钥
+        self.validate_tls()?;
+        
+        // 验证 HTTP/3 配置
+        self.validate_http3()?;
+        
+        // 验证 HTTPS 重定向配置
+        self.validate_https_redirect()?;
+        
+        // 验证 DoH 路径
+        self.validate_doh_paths()?;
+        
+        // 验证请求体大小上限
+        self.validate_max_request_body_size()?;
+        
+        // 验证请求 ID 头名称
+        self.validate_request_id_header()?;
+        
+        // 验证 PROXY 协议配置
+        self.validate_proxy_protocol()?;
+        
+        // 验证 Oblivious DoH 配置
+        self.validate_odoh()?;
+        
+        // 验证 CORS 配置
+        self.validate_cors()?;
+        
+        // 验证响应压缩配置
+        self.validate_compression()?;
+        
+        // 验证客户端 IP 访问控制配置
+        self.validate_access_control()?;
+        
+        // 验证上游组覆盖配置
+        self.validate_upstream_override()?;
+        
+        // 验证 DoH 令牌认证配置
+        self.validate_auth()?;
+        
+        // 验证 HTTP 客户端超时与重试配置
+        self.validate_http_client()?;
+        
+        // 验证响应 TTL 覆盖配置
+        self.validate_response_ttl_overrides()?;
+        
+        // 验证需要移除与需要拒绝的记录类型
+        self.dns.parsed_strip_record_types()?;
+        self.dns.upstream.parsed_refused_types()?;
+        
+        // 验证不缓存的响应码
+        self.dns.cache.parsed_no_cache_rcodes()?;
+        
+        // 验证缓存持久化依赖链
+        self.validate_cache_dependencies()?;
+        
+        // 验证全局解析器地址
+        self.validate_resolvers(&self.dns.upstream.resolvers)?;
+        
+        // 验证 SRV 上游发现配置
+        self.validate_srv_discovery()?;
+        
+        // 验证 Consul 上游发现配置
+        self.validate_consul_discovery()?;
+        
+        // 验证递归解析配置
+        self.validate_recursive_mode()?;
+        
+        // 验证上游失败率降级配置
+        self.validate_failure_ratio()?;
+        
+        // 验证 DoH 上游 HTTP/2 配置
+        self.validate_upstream_http2()?;
+        
+        // 验证上游查询的本地源地址
+        self.validate_upstream_bind_addr()?;
+        
+        // 验证上游组 ECS 策略与路由功能的依赖关系
+        self.validate_routing_ecs_dependencies()?;
+        
+        // 验证路由配置
+        self.validate_routing()?;
+        
+        // 验证 ECS 策略配置
+        self.validate_ecs_policy()?;
+        
+        // 验证经典 DNS 监听配置
+        self.validate_dns_server()?;
+        
+        // 验证 DoT 监听配置
+        self.validate_dot_server()?;
+        
+        // 验证查询统计配置
+        self.validate_stats()?;
+        self.validate_debug_capture()?;
+        
+        // 验证日志配置
+        self.validate_logging()?;
+        
+        // 验证响应填充配置
+        self.validate_padding()?;
+        
+        // 验证 mDNS 转发配置
+        self.validate_mdns_forwarder()?;
+        
+        // 验证本地合成的 SVCB/HTTPS 记录
+        self.validate_svcb_hints()?;
+        
+        // 验证响应大小告警阈值
+        self.validate_response_size_alert()?;
+        
+        Ok(())
+    }
+    
+    // 验证 PROXY 协议配置：启用任一监听的 PROXY 协议时必须指定可信代理，否则任何客户端都能伪造来源地址
+    fn validate_proxy_protocol(&self) -> Result<()> {
+        for entry in &self.http.trusted_proxies {
+            parse_network(entry).map_err(|_| ServerError::Config(format!(
+                "Invalid trusted_proxies entry: '{}' (must be an IP address or CIDR network)",
+                entry
+            )))?;
+        }
+        
+        let listeners = [
+            ("http_server.proxy_protocol", self.http.proxy_protocol),
+            ("dns_server.proxy_protocol", self.dns_server.proxy_protocol),
+            ("dot_server.proxy_protocol", self.dot_server.proxy_protocol),
+        ];
+        for (option, enabled) in listeners {
+            if enabled && self.http.trusted_proxies.is_empty() {
+                return Err(ServerError::Config(format!(
+                    "{} requires http_server.trusted_proxies to list the load balancer addresses",
+                    option
+                )));
+            }
+        }
+        
+        if self.dns_server.proxy_protocol && self.dns_server.listen_tcp.is_none() {
+            return Err(ServerError::Config(
+                "dns_server.proxy_protocol requires dns_server.listen_tcp (PROXY protocol is not supported over UDP)".to_string()
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证经典 DNS 监听配置：TCP 地址不能与 HTTP 监听冲突
+    fn validate_dns_server(&self) -> Result<()> {
+        let dns_server = &self.dns_server;
+        if let Some(tcp_addr) = dns_server.listen_tcp {
+            let http_addrs = self.http.listen_addr.iter().copied()
+                .chain(self.http.admin_listen_addr)
+                .chain(self.http.metrics_listen_addr);
+            for http_addr in http_addrs {
+                if http_addr == tcp_addr {
+                    return Err(ServerError::Config(format!(
+                        "dns_server.listen_tcp {} conflicts with an HTTP listen address",
+                        tcp_addr
+                    )));
+                }
+            }
+        }
+        
+        if !(1..=MAX_DNS_SERVER_TCP_IDLE_TIMEOUT).contains(&dns_server.tcp_idle_timeout) {
+            return Err(ServerError::Config(format!(
+                "Invalid dns_server.tcp_idle_timeout: {} (must be between 1 and {})",
+                dns_server.tcp_idle_timeout, MAX_DNS_SERVER_TCP_IDLE_TIMEOUT
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 DoT 监听配置：需要证书与私钥，监听地址不能与其他 TCP 监听冲突
+    fn validate_dot_server(&self) -> Result<()> {
+        let dot_server = &self.dot_server;
+        let Some(dot_addr) = dot_server.listen_addr else {
+            return Ok(());
+        };
+        
+        let tcp_addrs = self.http.listen_addr.iter().copied()
+            .chain(self.http.admin_listen_addr)
+            .chain(self.http.metrics_listen_addr)
+            .chain(self.dns_server.listen_tcp);
+        for tcp_addr in tcp_addrs {
+            if tcp_addr == dot_addr {
+                return Err(ServerError::Config(format!(
+                    "dot_server.listen_addr {} conflicts with another TCP listen address",
+                    dot_addr
+                )));
+            }
+        }
+        
+        if !(1..=MAX_DOT_SERVER_IDLE_TIMEOUT).contains(&dot_server.idle_timeout) {
+            return Err(ServerError::Config(format!(
+                "Invalid dot_server.idle_timeout: {} (must be between 1 and {})",
+                dot_server.idle_timeout, MAX_DOT_SERVER_IDLE_TIMEOUT
+            )));
+        }
+        
+        if dot_server.cert.is_none() || dot_server.key.is_none() {
+            return Err(ServerError::Config(
+                "dot_server requires both 'cert' and 'key'".to_string()
+            ));
+        }
+        load_certified_key(&dot_server.tls_config())?;
+        
+        Ok(())
+    }
+    
+    // 验证查询统计配置
+    fn validate_stats(&self) -> Result<()> {
+        if self.stats.enabled {
+            let window = self.stats.top_domains_window_secs;
+            if !(MIN_STATS_WINDOW_SECS..=MAX_STATS_WINDOW_SECS).contains(&window) {
+                return Err(ServerError::Config(format!(
+                    "Invalid stats.top_domains_window_secs: {} (must be between {} and {})",
+                    window, MIN_STATS_WINDOW_SECS, MAX_STATS_WINDOW_SECS
+                )));
+            }
+            if self.stats.max_tracked_domains == 0 {
+                return Err(ServerError::Config(
+                    "Invalid stats.max_tracked_domains: must be greater than 0".to_string()
+                ));
+            }
+        }
+        Ok(())
+    }
+    
+    // 验证调试抓包配置：抓包端点需要管理令牌认证
+    fn validate_debug_capture(&self) -> Result<()> {
+        let capture = &self.debug.capture;
+        if !capture.enabled {
+            return Ok(());
+        }
+        if !(1..=MAX_DEBUG_CAPTURE_SIZE).contains(&capture.size) {
+            return Err(ServerError::Config(format!(
+                "Invalid debug.capture.size: {} (must be between 1 and {})",
+                capture.size, MAX_DEBUG_CAPTURE_SIZE
+            )));
+        }
+        if self.http.admin_token.is_none() {
+            return Err(ServerError::Config(
+                "debug.capture requires http_server.admin_token to protect the capture endpoint".to_string()
+            ));
+        }
+        Ok(())
+    }
+    
+    // 验证日志过滤规则
+    fn validate_logging(&self) -> Result<()> {
+        if let Some(level) = &self.logging.level {
+            if level.trim().is_empty() {
+                return Err(ServerError::Config("logging.level must not be empty".to_string()));
+            }
+            EnvFilter::try_new(level).map_err(|e| ServerError::Config(format!(
+                "Invalid logging.level '{}': {}", level, e
+            )))?;
+        }
+        
+        let query_log = &self.logging.query_log;
+        if query_log.enabled && query_log.target == QueryLogTarget::File
+            && query_log.path.as_deref().is_none_or(|path| path.trim().is_empty())
+        {
+            return Err(ServerError::Config(
+                "logging.query_log.path is required when target is file".to_string()
+            ));
+        }
+        if query_log.hash_key.as_deref().is_some_and(str::is_empty) {
+            return Err(ServerError::Config("logging.query_log.hash_key must not be empty".to_string()));
+        }
+        if !(0.0..=1.0).contains(&query_log.sample_rate) {
+            return Err(ServerError::Config(format!(
+                "logging.query_log.sample_rate must be between 0.0 and 1.0, got {}", query_log.sample_rate
+            )));
+        }
+        for (index, filter) in query_log.filters.iter().enumerate() {
+            let match_ = &filter.match_;
+            match match_.type_ {
+                MatchType::Exact => {},
+                MatchType::Regex => {
+                    for pattern in &match_.values {
+                        regex::Regex::new(pattern).map_err(|e| ServerError::Config(format!(
+                            "logging.query_log.filters[{}]: regex pattern '{}' is invalid: {}", index, pattern, e
+                        )))?;
+                    }
+                },
+                ref other => return Err(ServerError::Config(format!(
+                    "logging.query_log.filters[{}]: unsupported match type {:?}, expected exact or regex", index, other
+                ))),
+            }
+            for query_type in &match_.query_types {
+                RecordType::from_str(&query_type.to_uppercase()).map_err(|_| ServerError::Config(format!(
+                    "logging.query_log.filters[{}]: unknown query type '{}'", index, query_type
+                )))?;
+            }
+        }
+        Ok(())
+    }
+    
+    // 验证响应大小告警阈值
+    fn validate_response_size_alert(&self) -> Result<()> {
+        if self.http.alert_on_response_larger_than_bytes == Some(0) {
+            return Err(ServerError::Config(
+                "http_server.alert_on_response_larger_than_bytes must be greater than 0".to_string()
+            ));
+        }
+        Ok(())
+    }
+    
+    // 验证响应填充块大小
+    fn validate_padding(&self) -> Result<()> {
+        let block_size = self.security.padding.block_size;
+        if !(1..=MAX_RESPONSE_PADDING_BLOCK_SIZE).contains(&block_size) {
+            return Err(ServerError::Config(format!(
+                "Invalid security.padding.block_size: {} (must be between 1 and {})",
+                block_size, MAX_RESPONSE_PADDING_BLOCK_SIZE
+            )));
+        }
+        Ok(())
+    }
+    
+    // 验证 mDNS 转发配置：域名后缀以 '.' 开头，响应超时在允许范围内
+    fn validate_mdns_forwarder(&self) -> Result<()> {
+        let mdns = &self.dns.mdns_forwarder;
+        if !mdns.enabled {
+            return Ok(());
+        }
+        
+        if mdns.domains.is_empty() {
+            return Err(ServerError::Config(
+                "dns_resolver.mdns_forwarder.domains must not be empty when mDNS forwarding is enabled".to_string()
+            ));
+        }
+        for domain in &mdns.domains {
+            if !domain.starts_with('.') || domain.trim_matches('.').is_empty() {
+                return Err(ServerError::Config(format!(
+                    "Invalid dns_resolver.mdns_forwarder.domains entry: '{}' (must be a suffix such as '.local')",
+                    domain
+                )));
+            }
+        }
+        
+        if !(1..=MAX_MDNS_RESPONSE_TIMEOUT_MS).contains(&mdns.mdns_response_timeout_ms) {
+            return Err(ServerError::Config(format!(
+                "Invalid dns_resolver.mdns_forwarder.mdns_response_timeout_ms: {} (must be between 1 and {})",
+                mdns.mdns_response_timeout_ms, MAX_MDNS_RESPONSE_TIMEOUT_MS
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 SVCB/HTTPS 提示：名称有效，ALPN 协议标识非空且不超过 255 字节
+    fn validate_svcb_hints(&self) -> Result<()> {
+        for (name, hint) in &self.dns.svcb_hints {
+            if name.trim().is_empty() || Name::from_str(name).is_err() {
+                return Err(ServerError::Config(format!(
+                    "Invalid dns_resolver.svcb_hints name: '{}'", name
+                )));
+            }
+            
+            if let Some(alpn) = hint.alpn.iter().find(|alpn| alpn.is_empty() || alpn.len() > 255) {
+                return Err(ServerError::Config(format!(
+                    "Invalid dns_resolver.svcb_hints.{}.alpn entry: '{}' (must be 1-255 bytes)", name, alpn
+                )));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // 验证响应 TTL 覆盖：不超过 u32 范围，且下限不大于上限
+    fn validate_response_ttl_overrides(&self) -> Result<()> {
+        let min = self.dns.min_response_ttl_override;
+        let max = self.dns.max_response_ttl_override;
+        
+        for (name, value) in [("min_response_ttl_override", min), ("max_response_ttl_override", max)] {
+            if let Some(value) = value {
+                if value > u32::MAX as u64 {
+                    return Err(ServerError::Config(format!(
+                        "Invalid dns_resolver.{}: {} (must not exceed {})",
+                        name, value, u32::MAX
+                    )));
+                }
+            }
+        }
+        
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                return Err(ServerError::Config(format!(
+                    "Invalid response TTL overrides: min_response_ttl_override ({}) is greater than max_response_ttl_override ({})",
+                    min, max
+                )));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // 验证速率限制配置
+    fn validate_rate_limit(&self) -> Result<()> {
+        if self.http.rate_limit.enabled {
+            // 验证每个 IP 每秒最大请求数
+            if self.http.rate_limit.per_ip_rate < MIN_PER_IP_RATE || self.http.rate_limit.per_ip_rate > MAX_PER_IP_RATE {
+                return Err(ServerError::Config(format!(
+                    "Invalid per_ip_rate: {} (must be between {} and {})",
+                    self.http.rate_limit.per_ip_rate, MIN_PER_IP_RATE, MAX_PER_IP_RATE
+                )));
+            }
+            
+            // 验证单个 IP 的并发请求数限制
+            if self.http.rate_limit.per_ip_concurrent < MIN_PER_IP_CONCURRENT || self.http.rate_limit.per_ip_concurrent > MAX_PER_IP_CONCURRENT {
+                return Err(ServerError::Config(format!(
+                    "Invalid per_ip_concurrent: {} (must be between {} and {})",
+                    self.http.rate_limit.per_ip_concurrent, MIN_PER_IP_CONCURRENT, MAX_PER_IP_CONCURRENT
+                )));
+            }
+            
+            self.validate_rate_limit_overrides()?;
+        }
+        Ok(())
+    }
+    
+    // 验证速率限制覆盖：名称唯一，至少匹配一个网段或令牌，速率与突发数在有效范围内
+    fn validate_rate_limit_overrides(&self) -> Result<()> {
+        let overrides = &self.http.rate_limit.overrides;
+        for (index, entry) in overrides.iter().enumerate() {
+            if entry.name.trim().is_empty() || entry.name == DEFAULT_RATE_LIMIT_BUCKET {
+                return Err(ServerError::Config(format!(
+                    "Invalid rate_limit.overrides name '{}': must be non-empty and not '{}'",
+                    entry.name, DEFAULT_RATE_LIMIT_BUCKET
+                )));
+            }
+            
+            if overrides[..index].iter().any(|other| other.name == entry.name) {
+                return Err(ServerError::Config(format!(
+                    "Duplicate rate_limit.overrides name '{}'",
+                    entry.name
+                )));
+            }
+            
+            if entry.cidrs.is_empty() && entry.tokens.is_empty() {
+                return Err(ServerError::Config(format!(
+                    "rate_limit.overrides '{}' must match at least one CIDR or token name",
+                    entry.name
+                )));
+            }
+            
+            for cidr in &entry.cidrs {
+                parse_network(cidr).map_err(|_| ServerError::Config(format!(
+                    "Invalid CIDR '{}' in rate_limit.overrides '{}' (must be an IP address or CIDR network)",
+                    cidr, entry.name
+                )))?;
+            }
+            
+            if !entry.tokens.is_empty() && !self.http.auth.enabled {
+                return Err(ServerError::Config(format!(
+                    "rate_limit.overrides '{}' matches token names but http_server.auth is disabled",
+                    entry.name
+                )));
+            }
+            
+            if entry.unlimited {
+                if entry.rate.is_some() || entry.burst.is_some() {
+                    return Err(ServerError::Config(format!(
+                        "rate_limit.overrides '{}' is unlimited and cannot set rate or burst",
+                        entry.name
+                    )));
+                }
+                continue;
+            }
+            
+            if let Some(rate) = entry.rate.filter(|rate| !(MIN_PER_IP_RATE..=MAX_PER_IP_RATE).contains(rate)) {
+                return Err(ServerError::Config(format!(
+                    "Invalid rate {} in rate_limit.overrides '{}' (must be between {} and {})",
+                    rate, entry.name, MIN_PER_IP_RATE, MAX_PER_IP_RATE
+                )));
+            }
+            
+            if let Some(burst) = entry.burst.filter(|burst| !(MIN_PER_IP_CONCURRENT..=MAX_PER_IP_CONCURRENT).contains(burst)) {
+                return Err(ServerError::Config(format!(
+                    "Invalid burst {} in rate_limit.overrides '{}' (must be between {} and {})",
+                    burst, entry.name, MIN_PER_IP_CONCURRENT, MAX_PER_IP_CONCURRENT
+                )));
+            }
+        }
+        Ok(())
+    }
+    
+    // 验证 DoH 监听地址列表非空且不重复
+    fn validate_listen_addrs(&self) -> Result<()> {
+        if self.http.listen_addr.is_empty() {
+            return Err(ServerError::Config("listen_addr must contain at least one address".to_string()));
+        }
+        
+        let mut seen = std::collections::HashSet::new();
+        for addr in self.http.listen_addr.iter() {
+            if !seen.insert(addr) {
+                return Err(ServerError::Config(format!("Duplicate listen_addr: {}", addr)));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 Unix 域套接字监听配置
+    fn validate_unix_socket(&self) -> Result<()> {
+        let Some(path) = &self.http.listen_unix else {
+            return Ok(());
+        };
+        
+        if cfg!(not(unix)) {
+            return Err(ServerError::Config(
+                "listen_unix is only supported on Unix platforms".to_string()
+            ));
+        }
+        
+        if path.as_os_str().is_empty() {
+            return Err(ServerError::Config("listen_unix must not be empty".to_string()));
+        }
+        
+        self.http.unix_socket.parsed_mode()?;
+        
+        Ok(())
+    }
+    
+    // 验证管理监听地址不与其他监听地址冲突
+    fn validate_admin_listener(&self) -> Result<()> {
+        let Some(admin_addr) = self.http.admin_listen_addr else {
+            return Ok(());
+        };
+        
+        if self.http.listen_addr.contains(&admin_addr) {
+            return Err(ServerError::Config(format!(
+                "admin_listen_addr {} must differ from listen_addr",
+                admin_addr
+            )));
+        }
+        
+        if self.http.metrics_listen_addr == Some(admin_addr) {
+            return Err(ServerError::Config(format!(
+                "admin_listen_addr {} must differ from metrics_listen_addr",
+                admin_addr
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证管理端点令牌
+    fn validate_admin_token(&self) -> Result<()> {
+        if self.http.admin_token.as_deref().is_some_and(|token| token.trim().is_empty()) {
+            return Err(ServerError::Config("admin_token must not be empty when set".to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证指标端点配置（独立监听地址与认证）
+    fn validate_metrics_endpoint(&self) -> Result<()> {
+        if let Some(metrics_addr) = self.http.metrics_listen_addr.filter(|addr| self.http.listen_addr.contains(addr)) {
+            return Err(ServerError::Config(format!(
+                "metrics_listen_addr {} must differ from listen_addr",
+                metrics_addr
+            )));
+        }
+        
+        let Some(auth) = &self.http.metrics_auth else {
+            return Ok(());
+        };
+        
+        let is_blank = |value: &Option<String>| value.as_deref().is_none_or(|v| v.is_empty());
+        
+        match auth.type_ {
+            MetricsAuthType::Basic => {
+                if is_blank(&auth.username) || is_blank(&auth.password) {
+                    return Err(ServerError::Config(
+                        "metrics_auth type 'basic' requires non-empty 'username' and 'password'".to_string()
+                    ));
+                }
+            },
+            MetricsAuthType::Bearer => {
+                if is_blank(&auth.token) {
+                    return Err(ServerError::Config(
+                        "metrics_auth type 'bearer' requires a non-empty 'token'".to_string()
+                    ));
+                }
+            },
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 HTTP/3 依赖 TLS 配置
+    fn validate_http3(&self) -> Result<()> {
+        if self.http.http3.enabled && self.http.tls.is_none() {
+            return Err(ServerError::Config(
+                "http3 requires 'http_server.tls' because QUIC is always encrypted".to_string()
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 DoH 路径：以 '/' 开头、不含通配符、不重复且不与内置路由冲突
+    fn validate_doh_paths(&self) -> Result<()> {
+        if self.http.doh_paths.is_empty() {
+            return Err(ServerError::Config("http_server.doh_paths must contain at least one path".to_string()));
+        }
+        
+        for (index, path) in self.http.doh_paths.iter().enumerate() {
+            if !path.starts_with('/') {
+                return Err(ServerError::Config(format!(
+                    "Invalid doh_paths entry '{}': must start with '/'",
+                    path
+                )));
+            }
+            
+            if path.contains(['*', '{', '}', ':', '?', '#']) || path.chars().any(char::is_whitespace) {
+                return Err(ServerError::Config(format!(
+                    "Invalid doh_paths entry '{}': wildcards, parameters and query strings are not allowed",
+                    path
+                )));
+            }
+            
+            if RESERVED_HTTP_PATHS.contains(&path.as_str()) {
+                return Err(ServerError::Config(format!(
+                    "Invalid doh_paths entry '{}': conflicts with a built-in route",
+                    path
+                )));
+            }
+            
+            if self.http.doh_paths[..index].contains(path) {
+                return Err(ServerError::Config(format!(
+                    "Duplicate doh_paths entry '{}'",
+                    path
+                )));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // 验证请求 ID 头名称是合法的 HTTP 头，以及受信任的代理网段
+    fn validate_request_id_header(&self) -> Result<()> {
+        if HeaderName::from_bytes(self.http.request_id_header.as_bytes()).is_err() {
+            return Err(ServerError::Config(format!(
+                "Invalid request_id_header: '{}' is not a valid HTTP header name",
+                self.http.request_id_header
+            )));
+        }
+        
+        for entry in &self.http.request_id_trusted_proxies {
+            parse_network(entry).map_err(|_| ServerError::Config(format!(
+                "Invalid request_id_trusted_proxies entry: '{}' (must be an IP address or CIDR network)",
+                entry
+            )))?;
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 DoH 令牌认证：至少一个令牌，名称与令牌非空且不重复，令牌可嵌入 URL 路径
+    fn validate_auth(&self) -> Result<()> {
+        let auth = &self.http.auth;
+        if !auth.enabled {
+            return Ok(());
+        }
+        
+        if auth.protect_metrics && self.http.metrics_auth.is_some() {
+            return Err(ServerError::Config(
+                "auth.protect_metrics cannot be combined with metrics_auth".to_string()
+            ));
+        }
+        
+        let tokens = auth.load_tokens()?;
+        if tokens.is_empty() {
+            return Err(ServerError::Config(
+                "auth requires at least one token in 'tokens' or 'token_file'".to_string()
+            ));
+        }
+        
+        for (index, entry) in tokens.iter().enumerate() {
+            if entry.name.is_empty() {
+                return Err(ServerError::Config("auth token names must not be empty".to_string()));
+            }
+            if entry.token.is_empty() || !entry.token.bytes().all(|b| b.is_ascii_graphic() && b != b'/') {
+                return Err(ServerError::Config(format!(
+                    "Invalid auth token for '{}': must be non-empty visible ASCII without '/'",
+                    entry.name
+                )));
+            }
+            if tokens[..index].iter().any(|other| other.name == entry.name) {
+                return Err(ServerError::Config(format!("Duplicate auth token name '{}'", entry.name)));
+            }
+            if tokens[..index].iter().any(|other| other.token == entry.token) {
+                return Err(ServerError::Config(format!("Duplicate auth token value for '{}'", entry.name)));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // 验证上游组覆盖请求头名称与受信任的客户端网段
+    fn validate_upstream_override(&self) -> Result<()> {
+        if HeaderName::from_bytes(self.http.upstream_override_header.as_bytes()).is_err() {
+            return Err(ServerError::Config(format!(
+                "Invalid upstream_override_header: '{}' is not a valid HTTP header name",
+                self.http.upstream_override_header
+            )));
+        }
+        
+        for entry in &self.http.trusted_override_ips {
+            parse_network(entry).map_err(|_| ServerError::Config(format!(
+                "Invalid trusted_override_ips entry: '{}' (must be an IP address or CIDR network)",
+                entry
+            )))?;
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 Oblivious DoH 路径与密钥轮换间隔
+    fn validate_odoh(&self) -> Result<()> {
+        let odoh = &self.http.odoh;
+        if !odoh.enabled {
+            return Ok(());
+        }
+        
+        if !odoh.path.starts_with('/') {
+            return Err(ServerError::Config(format!(
+                "Invalid odoh.path: '{}' (must start with '/')",
+                odoh.path
+            )));
+        }
+        
+        if RESERVED_HTTP_PATHS.contains(&odoh.path.as_str()) || self.http.doh_paths.contains(&odoh.path) {
+            return Err(ServerError::Config(format!(
+                "Invalid odoh.path: '{}' conflicts with a built-in route",
+                odoh.path
+            )));
+        }
+        
+        if odoh.key_rotation_secs != 0 && odoh.key_rotation_secs < MIN_ODOH_KEY_ROTATION_SECS {
+            return Err(ServerError::Config(format!(
+                "Invalid odoh.key_rotation_secs: {} (must be 0 or at least {})",
+                odoh.key_rotation_secs, MIN_ODOH_KEY_ROTATION_SECS
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证请求体大小上限
+    fn validate_max_request_body_size(&self) -> Result<()> {
+        let size = self.http.max_request_body_size;
+        if !(MIN_REQUEST_BODY_SIZE..=MAX_REQUEST_BODY_SIZE).contains(&size) {
+            return Err(ServerError::Config(format!(
+                "Invalid max_request_body_size: {} (must be between {} and {})",
+                size, MIN_REQUEST_BODY_SIZE, MAX_REQUEST_BODY_SIZE
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 HTTPS 重定向：需要 TLS、独立的明文监听地址与有效的服务器主机名
+    fn validate_https_redirect(&self) -> Result<()> {
+        let http = &self.http;
+        if !http.server_name.trim().is_empty() && url::Host::parse(http.server_name.trim()).is_err() {
+            return Err(ServerError::Config(format!(
+                "Invalid server_name: '{}'",
+                http.server_name
+            )));
+        }
+        
+        if !http.http_to_https_redirect {
+            return Ok(());
+        }
+        
+        if http.tls.is_none() {
+            return Err(ServerError::Config("http_to_https_redirect requires tls to be configured".to_string()));
+        }
+        
+        let Some(http_addr) = http.http_listen_addr else {
+            return Err(ServerError::Config("http_to_https_redirect requires 'http_listen_addr'".to_string()));
+        };
+        
+        if http.listen_addr.contains(&http_addr) || http.admin_listen_addr == Some(http_addr) || http.metrics_listen_addr == Some(http_addr) {
+            return Err(ServerError::Config(format!(
+                "http_listen_addr {} must differ from listen_addr, admin_listen_addr and metrics_listen_addr",
+                http_addr
             )));
         }
         
-        // 查找指定的上游组
-        if let Some(group) = self.dns.routing.upstream_groups.iter().find(|g| g.name == group_name) {
-            // 创建新的配置，继承全局配置并应用组特定的覆盖
-            let mut config = self.dns.upstream.clone();
-            
-            // 覆盖解析器列表
-            config.resolvers = group.resolvers.clone();
-            
-            // 可选地覆盖其他设置
-            if let Some(enable_dnssec) = group.enable_dnssec {
-                config.enable_dnssec = enable_dnssec;
+        if http.effective_server_name().is_none() {
+            return Err(ServerError::Config(
+                "http_to_https_redirect requires 'server_name' (or an acme domain)".to_string()
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证关闭宽限期
+    fn validate_shutdown_grace_period(&self) -> Result<()> {
+        let grace_period = self.http.shutdown_grace_period_secs;
+        if grace_period > MAX_SHUTDOWN_GRACE_PERIOD_SECS {
+            return Err(ServerError::Config(format!(
+                "Invalid shutdown_grace_period_secs: {} (must be at most {})",
+                grace_period, MAX_SHUTDOWN_GRACE_PERIOD_SECS
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 HTTP 服务器超时；请求时限小于上游查询超时时只输出警告
+    fn validate_http_server_timeouts(&self) -> Result<()> {
+        let timeouts = [
+            ("request_timeout", self.http.request_timeout, 1),
+            ("read_header_timeout", self.http.read_header_timeout, 1),
+            ("idle_keepalive_timeout", self.http.idle_keepalive_timeout, 0),
+        ];
+        for (name, value, min) in timeouts {
+            if !(min..=MAX_HTTP_SERVER_TIMEOUT_SECS).contains(&value) {
+                return Err(ServerError::Config(format!(
+                    "Invalid http_server.{}: {} (must be between {} and {})",
+                    name, value, min, MAX_HTTP_SERVER_TIMEOUT_SECS
+                )));
             }
-            
-            if let Some(query_timeout) = group.query_timeout {
-                config.query_timeout = query_timeout;
+        }
+        
+        let query_timeout = self.dns.routing.upstream_groups.iter()
+            .filter_map(|group| group.query_timeout)
+            .fold(self.dns.upstream.query_timeout, u64::max);
+        if self.http.request_timeout < query_timeout {
+            warn!(
+                request_timeout = self.http.request_timeout,
+                query_timeout,
+                "http_server.request_timeout is smaller than the upstream query_timeout, slow upstream queries will be answered with 504"
+            );
+        }
+        
+        Ok(())
+    }
+    
+    // 验证访问控制列表中的网段
+    fn validate_access_control(&self) -> Result<()> {
+        let access_control = &self.http.access_control;
+        for (list, entries) in [("allow", &access_control.allow), ("deny", &access_control.deny)] {
+            for entry in entries {
+                parse_network(entry).map_err(|_| ServerError::Config(format!(
+                    "Invalid access_control.{} entry '{}' (must be an IP address or CIDR network)",
+                    list, entry
+                )))?;
             }
-            
-            Ok(config)
-        } else {
-            Err(ServerError::UpstreamGroupNotFound(format!(
-                "Upstream group not found: {}", 
-                group_name
-            )))
         }
+        
+        Ok(())
     }
     
-    // 获取特定上游组的有效 ECS 策略配置
-    pub fn get_effective_ecs_policy(&self, group_name: &str) -> Result<EcsPolicyConfig> {
-        // 如果指定了组名，尝试查找该组
-        if !group_name.is_empty() && group_name != BLACKHOLE_UPSTREAM_GROUP_NAME {
-            if let Some(group) = self.dns.routing.upstream_groups
-                .iter()
-                .find(|g| g.name == group_name) {
-                // 如果组存在且指定了 ECS 策略，则使用组策略
-                if let Some(ecs_policy) = &group.ecs_policy {
-                    return Ok(ecs_policy.clone());
+    // 验证 CORS 来源列表与预检缓存时间
+    fn validate_cors(&self) -> Result<()> {
+        let cors = &self.http.cors;
+        if !cors.enabled {
+            return Ok(());
+        }
+        
+        if cors.allowed_origins.is_empty() {
+            return Err(ServerError::Config("cors.allowed_origins must not be empty when CORS is enabled".to_string()));
+        }
+        
+        if cors.allows_any_origin() && cors.allowed_origins.len() > 1 {
+            return Err(ServerError::Config(
+                "cors.allowed_origins: '*' cannot be combined with explicit origins".to_string()
+            ));
+        }
+        
+        // 显式来源必须是不带路径的 "scheme://host[:port]"
+        for origin in cors.allowed_origins.iter().filter(|origin| *origin != CORS_ANY_ORIGIN) {
+            let valid = match url::Url::parse(origin) {
+                Ok(url) => matches!(url.scheme(), "http" | "https")
+                    && url.host_str().is_some()
+                    && url.origin().ascii_serialization() == *origin,
+                Err(_) => false,
+            };
+            if !valid {
+                return Err(ServerError::Config(format!(
+                    "Invalid cors.allowed_origins entry: '{}' (expected an origin such as \"https://example.com\")",
+                    origin
+                )));
+            }
+        }
+        
+        if cors.max_age > MAX_CORS_MAX_AGE_SECS {
+            return Err(ServerError::Config(format!(
+                "Invalid cors.max_age: {} (must be at most {})",
+                cors.max_age, MAX_CORS_MAX_AGE_SECS
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证响应压缩配置
+    fn validate_compression(&self) -> Result<()> {
+        let compression = &self.http.compression;
+        if compression.enabled && compression.algorithms.is_empty() {
+            return Err(ServerError::Config(
+                "compression.algorithms must not be empty when compression is enabled".to_string()
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 TLS 证书与私钥可加载且相互匹配
+    fn validate_tls(&self) -> Result<()> {
+        let Some(tls) = &self.http.tls else {
+            return Ok(());
+        };
+        
+        // 最低版本、密码套件与 ALPN 的组合必须可用，HTTP/3 还需要 TLS 1.3 套件
+        validate_tls_protocol(tls, self.http.http3.enabled)?;
+        
+        if self.http.enable_h2c {
+            warn!("http_server.enable_h2c has no effect when TLS is configured, HTTP/2 is negotiated via ALPN");
+        }
+        
+        if let Some(client_auth) = &tls.client_auth {
+            // TLS-ALPN-01 验证方不会提供客户端证书
+            if client_auth.mode == ClientAuthMode::Required
+                && tls.acme.enabled
+                && tls.acme.challenge == AcmeChallengeType::TlsAlpn01
+            {
+                return Err(ServerError::Config(
+                    "tls.client_auth mode 'required' cannot be combined with the acme 'tls-alpn-01' challenge".to_string()
+                ));
+            }
+            load_client_verifier(client_auth)?;
+        }
+        
+        if !tls.acme.enabled {
+            if tls.cert.is_none() || tls.key.is_none() {
+                return Err(ServerError::Config(
+                    "tls requires both 'cert' and 'key' unless acme is enabled".to_string()
+                ));
+            }
+            load_certified_key(tls)?;
+            return Ok(());
+        }
+        
+        let acme = &tls.acme;
+        if acme.domains.is_empty() || acme.domains.iter().any(|d| d.trim().is_empty()) {
+            return Err(ServerError::Config(
+                "tls.acme requires at least one non-empty domain".to_string()
+            ));
+        }
+        
+        if acme.cache_dir.as_os_str().is_empty() {
+            return Err(ServerError::Config("tls.acme.cache_dir must not be empty".to_string()));
+        }
+        
+        if acme.renew_before_days == 0 {
+            return Err(ServerError::Config("tls.acme.renew_before_days must be greater than 0".to_string()));
+        }
+        
+        if acme.challenge == AcmeChallengeType::Http01 && acme.http01_listen_addr.is_none() {
+            return Err(ServerError::Config(
+                "tls.acme challenge 'http-01' requires 'http01_listen_addr'".to_string()
+            ));
+        }
+        
+        if let Some(http01_addr) = acme.http01_listen_addr.filter(|addr| self.http.listen_addr.contains(addr)) {
+            return Err(ServerError::Config(format!(
+                "tls.acme.http01_listen_addr {} must differ from listen_addr",
+                http01_addr
+            )));
+        }
+        
+        // 已有缓存证书时校验其有效性，首次启动时证书尚未签发
+        if tls.cert_path().exists() && tls.key_path().exists() {
+            load_certified_key(tls)?;
+        }
+        
+        Ok(())
+    }
+    
+    // 验证 HTTP 客户端超时不超过请求总超时，以及按状态码重试的配置
+    fn validate_http_client(&self) -> Result<()> {
+        let http_client = &self.dns.http_client;
+        
+        if http_client.connect_timeout == 0 || http_client.connect_timeout > http_client.timeout {
+            return Err(ServerError::Config(format!(
+                "Invalid http_client.connect_timeout: {} (must be between 1 and http_client.timeout {})",
+                http_client.connect_timeout, http_client.timeout
+            )));
+        }
+        
+        // 建立连接的总时限不能超过请求总超时
+        if http_client.connect_timeout + http_client.tls_handshake_allowance > http_client.timeout {
+            return Err(ServerError::Config(format!(
+                "Invalid http_client.tls_handshake_allowance: {} (connect_timeout + tls_handshake_allowance must not exceed http_client.timeout {})",
+                http_client.tls_handshake_allowance, http_client.timeout
+            )));
+        }
+        
+        // 只允许对 4xx/5xx 错误状态码重试
+        if let Some(status) = http_client.retry_status.iter().find(|status| !(400..=599).contains(*status)) {
+            return Err(ServerError::Config(format!(
+                "Invalid http_client.retry_status: {} (must be an HTTP error status between 400 and 599)",
+                status
+            )));
+        }
+        
+        if http_client.max_retries > MAX_HTTP_CLIENT_MAX_RETRIES {
+            return Err(ServerError::Config(format!(
+                "Invalid http_client.max_retries: {} (must be at most {})",
+                http_client.max_retries, MAX_HTTP_CLIENT_MAX_RETRIES
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证缓存持久化依赖链
+    fn validate_cache_dependencies(&self) -> Result<()> {
+        // 验证持久化缓存依赖于缓存本身
+        if self.dns.cache.persistence.enabled && !self.dns.cache.enabled {
+            return Err(ServerError::Config(
+                "Cache persistence is enabled but cache itself is disabled. Enable cache first.".to_string()
+            ));
+        }
+        
+        // 验证周期性保存依赖于持久化缓存
+        if self.dns.cache.persistence.periodic.enabled && !self.dns.cache.persistence.enabled {
+            return Err(ServerError::Config(
+                "Periodic cache persistence is enabled but persistence itself is disabled. Enable persistence first.".to_string()
+            ));
+        }
+        
+        // 验证后台未命中解析依赖于缓存本身
+        if self.dns.cache.async_miss_resolution && !self.dns.cache.enabled {
+            return Err(ServerError::Config(
+                "Async cache miss resolution is enabled but cache itself is disabled. Enable cache first.".to_string()
+            ));
+        }
+        
+        // 验证未命中时返回 SERVFAIL 依赖于后台未命中解析
+        if self.dns.cache.return_servfail_on_miss && !self.dns.cache.async_miss_resolution {
+            return Err(ServerError::Config(
+                "return_servfail_on_miss requires async_miss_resolution to be enabled".to_string()
+            ));
+        }
+        
+        if self.dns.cache.async_miss_resolution && self.dns.cache.async_miss_resolution_timeout_ms == 0 {
+            return Err(ServerError::Config(
+                "async_miss_resolution_timeout_ms must be greater than 0".to_string()
+            ));
+        }
+        
+        if self.dns.cache.max_answer_records == Some(0) {
+            return Err(ServerError::Config(
+                "max_answer_records must be greater than 0".to_string()
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    // 验证解析器地址配置
+    fn validate_resolvers(&self, resolvers: &[ResolverConfig]) -> Result<()> {
+        for resolver in resolvers {
+            match resolver.protocol {
+                ResolverProtocol::Doh => {
+                    // 验证 DoH 地址是有效的 URL
+                    if !resolver.doh_url().starts_with("https://") {
+                        return Err(ServerError::Config(format!(
+                            "DoH resolver address must start with 'https://': {}", 
+                            resolver.address
+                        )));
+                    }
+                    
+                    if let Some(path) = &resolver.path {
+                        if !path.starts_with('/') || path.contains(['?', '#']) || path.chars().any(char::is_whitespace) {
+                            return Err(ServerError::Config(format!(
+                                "Invalid DoH resolver path '{}': must start with '/' and contain no query string",
+                                path
+                            )));
+                        }
+                        
+                        // address 已包含路径时不能再单独配置 path
+                        let host_and_path = resolver.address.split_once("://")
+                            .map_or(resolver.address.as_str(), |(_, rest)| rest);
+                        if host_and_path.split_once('/').is_some_and(|(_, address_path)| !address_path.is_empty()) {
+                            return Err(ServerError::Config(format!(
+                                "DoH resolver address '{}' already contains a path, remove it or the separate path '{}'",
+                                resolver.address, path
+                            )));
+                        }
+                    }
+                },
+                _ => {
+                    if resolver.path.is_some() {
+                        return Err(ServerError::Config(format!(
+                            "Resolver path is only supported for DoH resolvers: {}",
+                            resolver.address
+                        )));
+                    }
+                    
+                    if resolver.has_tls_client_settings() && resolver.protocol != ResolverProtocol::Dot {
+                        return Err(ServerError::Config(format!(
+                            "client_cert_path, client_key_path and ca_cert_path are only supported for DoT and DoH resolvers: {}",
+                            resolver.address
+                        )));
+                    }
+                    
+                    // 验证 UDP/TCP/DoT 地址格式 (IP:端口、[IPv6]:端口，DoT 需带 域名@ 前缀)
+                    resolver.parse_socket_addr()?;
                 }
             }
+            
+            // 启动时加载双向 TLS 使用的证书，缺失或无效时给出明确错误
+            resolver.client_identity_pem()?;
+            resolver.ca_cert_pem()?;
+        }
+        Ok(())
+    }
+    
+    // 验证 SRV 上游发现配置
+    fn validate_srv_discovery(&self) -> Result<()> {
+        let upstream = &self.dns.upstream;
+        if !upstream.discover_via_srv {
+            return Ok(());
         }
         
-        // 否则使用全局 ECS 策略
-        Ok(self.dns.ecs_policy.clone())
+        if upstream.srv_name.trim().is_empty() {
+            return Err(ServerError::Config(
+                "upstream.srv_name is required when discover_via_srv is enabled".to_string()
+            ));
+        }
+        
+        if Name::from_ascii(upstream.srv_name.trim()).is_err() {
+            return Err(ServerError::Config(format!(
+                "Invalid upstream.srv_name: {}", upstream.srv_name
+            )));
+        }
+        
+        // SRV 查询需要通过静态解析器引导
+        if upstream.resolvers.is_empty() {
+            return Err(ServerError::Config(
+                "upstream.resolvers must contain at least one bootstrap resolver when discover_via_srv is enabled".to_string()
+            ));
+        }
+        
+        if upstream.srv_refresh_interval_secs < MIN_SRV_REFRESH_INTERVAL_SECS {
+            return Err(ServerError::Config(format!(
+                "Invalid upstream.srv_refresh_interval_secs: {} (must be at least {})",
+                upstream.srv_refresh_interval_secs, MIN_SRV_REFRESH_INTERVAL_SECS
+            )));
+        }
+        
+        Ok(())
     }
     
-    // 验证配置有效性
-    pub fn test(&self) -> Result<()> {
-        // 验证速率限制配置
-        self.validate_rate_limit()?;
+    // 验证全局上游与各上游组的 Consul 上游发现配置
+    fn validate_consul_discovery(&self) -> Result<()> {
+        let upstream = &self.dns.upstream;
+        if let Some(consul) = &upstream.consul_upstream_discovery {
+            Self::validate_consul_discovery_config(consul, "upstream.consul_upstream_discovery")?;
+            // 全局上游发现的 DoH 上游只能来自一个来源
+            if upstream.discover_via_srv {
+                return Err(ServerError::Config(
+                    "upstream.consul_upstream_discovery cannot be combined with discover_via_srv".to_string()
+                ));
+            }
+            if upstream.mode == UpstreamMode::Recursive {
+                return Err(ServerError::Config(
+                    "upstream.consul_upstream_discovery cannot be used when mode is recursive".to_string()
+                ));
+            }
+        }
+        
+        for group in &self.dns.routing.upstream_groups {
+            if let Some(consul) = &group.consul_upstream_discovery {
+                Self::validate_consul_discovery_config(
+                    consul,
+                    &format!("upstream group '{}' consul_upstream_discovery", group.name),
+                )?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn validate_consul_discovery_config(consul: &ConsulUpstreamDiscoveryConfig, context: &str) -> Result<()> {
+        match url::Url::parse(&consul.consul_addr) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {},
+            _ => return Err(ServerError::Config(format!(
+                "Invalid {}.consul_addr '{}': must be an http or https URL", context, consul.consul_addr
+            ))),
+        }
+        
+        if consul.service_name.trim().is_empty() || consul.service_name.contains(['/', '?', '#']) {
+            return Err(ServerError::Config(format!(
+                "Invalid {}.service_name '{}'", context, consul.service_name
+            )));
+        }
+        
+        if consul.protocol != ResolverProtocol::Doh {
+            return Err(ServerError::Config(format!(
+                "Unsupported {}.protocol {:?}: only doh is supported", context, consul.protocol
+            )));
+        }
         
-        // 验证缓存持久化依赖链
-        self.validate_cache_dependencies()?;
+        if consul.refresh_interval_secs < MIN_CONSUL_REFRESH_INTERVAL_SECS {
+            return Err(ServerError::Config(format!(
+                "Invalid {}.refresh_interval_secs: {} (must be at least {})",
+                context, consul.refresh_interval_secs, MIN_CONSUL_REFRESH_INTERVAL_SECS
+            )));
+        }
         
-        // 验证全局解析器地址
-        self.validate_resolvers(&self.dns.upstream.resolvers)?;
+        if consul.health_check_tag.as_deref().is_some_and(|tag| tag.trim().is_empty()) {
+            return Err(ServerError::Config(format!("{}.health_check_tag must not be empty", context)));
+        }
         
-        // 验证上游组 ECS 策略与路由功能的依赖关系
-        self.validate_routing_ecs_dependencies()?;
+        Ok(())
+    }
+    
+    // 验证递归解析配置：根服务器地址有效，且不与 SRV 上游发现同时使用
+    fn validate_recursive_mode(&self) -> Result<()> {
+        let upstream = &self.dns.upstream;
+        if upstream.mode != UpstreamMode::Recursive {
+            return Ok(());
+        }
         
-        // 验证路由配置
-        self.validate_routing()?;
+        if upstream.root_hints.is_empty() {
+            return Err(ServerError::Config(
+                "upstream.root_hints must contain at least one address when mode is recursive".to_string()
+            ));
+        }
+        upstream.parsed_root_hints()?;
         
-        // 验证 ECS 策略配置
-        self.validate_ecs_policy()?;
+        if upstream.discover_via_srv {
+            return Err(ServerError::Config(
+                "upstream.discover_via_srv cannot be used when mode is recursive".to_string()
+            ));
+        }
         
         Ok(())
     }
     
-    // 验证速率限制配置
-    fn validate_rate_limit(&self) -> Result<()> {
-        if self.http.rate_limit.enabled {
-            // 验证每个 IP 每秒最大请求数
-            if self.http.rate_limit.per_ip_rate < MIN_PER_IP_RATE || self.http.rate_limit.per_ip_rate > MAX_PER_IP_RATE {
-                return Err(ServerError::Config(format!(
-                    "Invalid per_ip_rate: {} (must be between {} and {})",
-                    self.http.rate_limit.per_ip_rate, MIN_PER_IP_RATE, MAX_PER_IP_RATE
-                )));
-            }
-            
-            // 验证单个 IP 的并发请求数限制
-            if self.http.rate_limit.per_ip_concurrent < MIN_PER_IP_CONCURRENT || self.http.rate_limit.per_ip_concurrent > MAX_PER_IP_CONCURRENT {
+    // 验证上游失败率降级配置：阈值在 (0, 1] 内，恢复阈值低于降级阈值
+    fn validate_failure_ratio(&self) -> Result<()> {
+        let upstream = &self.dns.upstream;
+        let Some(threshold) = upstream.failure_ratio_threshold else {
+            return Ok(());
+        };
+        
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(ServerError::Config(format!(
+                "Invalid upstream.failure_ratio_threshold: {} (must be greater than 0 and at most 1)",
+                threshold
+            )));
+        }
+        
+        if let Some(recovery) = upstream.recovery_threshold {
+            if !(0.0..threshold).contains(&recovery) {
                 return Err(ServerError::Config(format!(
-                    "Invalid per_ip_concurrent: {} (must be between {} and {})",
-                    self.http.rate_limit.per_ip_concurrent, MIN_PER_IP_CONCURRENT, MAX_PER_IP_CONCURRENT
+                    "Invalid upstream.recovery_threshold: {} (must be at least 0 and below failure_ratio_threshold {})",
+                    recovery, threshold
                 )));
             }
         }
+        
+        if !(1..=MAX_FAILURE_RATIO_WINDOW_SECS).contains(&upstream.rolling_window_secs) {
+            return Err(ServerError::Config(format!(
+                "Invalid upstream.rolling_window_secs: {} (must be between 1 and {})",
+                upstream.rolling_window_secs, MAX_FAILURE_RATIO_WINDOW_SECS
+            )));
+        }
+        
         Ok(())
     }
     
-    // 验证缓存持久化依赖链
-    fn validate_cache_dependencies(&self) -> Result<()> {
-        // 验证持久化缓存依赖于缓存本身
-        if self.dns.cache.persistence.enabled && !self.dns.cache.enabled {
-            return Err(ServerError::Config(
-                "Cache persistence is enabled but cache itself is disabled. Enable cache first.".to_string()
-            ));
+    // 验证上游查询的本地源地址：必须是本机地址，且与 UDP/TCP/DoT 上游的地址族一致
+    fn validate_upstream_bind_addr(&self) -> Result<()> {
+        let Some(bind_addr) = self.dns.upstream.bind_addr else {
+            return Ok(());
+        };
+        
+        if let Err(e) = std::net::UdpSocket::bind(SocketAddr::new(bind_addr, 0)) {
+            return Err(ServerError::Config(format!(
+                "Invalid upstream.bind_addr: {} cannot be bound on this host: {}",
+                bind_addr, e
+            )));
         }
         
-        // 验证周期性保存依赖于持久化缓存
-        if self.dns.cache.persistence.periodic.enabled && !self.dns.cache.persistence.enabled {
-            return Err(ServerError::Config(
-                "Periodic cache persistence is enabled but persistence itself is disabled. Enable persistence first.".to_string()
-            ));
+        let upstreams = self.dns.routing.upstream_groups.iter()
+            .flat_map(|group| &group.resolvers)
+            .chain(&self.dns.upstream.resolvers)
+            .filter(|resolver| resolver.protocol != ResolverProtocol::Doh);
+        for resolver in upstreams {
+            let (_, socket_addr) = resolver.parse_socket_addr()?;
+            if socket_addr.is_ipv4() != bind_addr.is_ipv4() {
+                return Err(ServerError::Config(format!(
+                    "Invalid upstream.bind_addr: {} does not match the address family of upstream resolver {}",
+                    bind_addr, resolver.address
+                )));
+            }
         }
         
         Ok(())
     }
     
-    // 验证解析器地址配置
-    fn validate_resolvers(&self, resolvers: &[ResolverConfig]) -> Result<()> {
-        for resolver in resolvers {
-            match resolver.protocol {
-                ResolverProtocol::Doh => {
-                    // 验证 DoH 地址是有效的 URL
-                    if !resolver.address.starts_with("https://") {
-                        return Err(ServerError::Config(format!(
-                            "DoH resolver address must start with 'https://': {}", 
-                            resolver.address
-                        )));
-                    }
-                },
-                ResolverProtocol::Dot => {
-                    // 验证 DoT 地址格式 (域名@IP:端口)
-                    if !resolver.address.contains('@') || !resolver.address.contains(':') {
-                        return Err(ServerError::Config(format!(
-                            "DoT resolver address must be in format 'domain@ip:port': {}", 
-                            resolver.address
-                        )));
-                    }
-                },
-                _ => {
-                    // 验证 UDP/TCP 地址格式 (IP:端口)
-                    if !resolver.address.contains(':') {
-                        return Err(ServerError::Config(format!(
-                            "Resolver address must be in format 'ip:port': {}", 
-                            resolver.address
-                        )));
-                    }
-                }
-            }
+    // 验证 DoH 上游 HTTP/2 配置
+    fn validate_upstream_http2(&self) -> Result<()> {
+        let interval = self.dns.upstream.http2_keepalive_interval_secs;
+        if interval > MAX_HTTP2_KEEPALIVE_INTERVAL_SECS {
+            return Err(ServerError::Config(format!(
+                "Invalid upstream.http2_keepalive_interval_secs: {} (must be at most {})",
+                interval, MAX_HTTP2_KEEPALIVE_INTERVAL_SECS
+            )));
         }
+        
         Ok(())
     }
     
@@ -729,6 +3909,9 @@ impl ServerConfig {
         // 验证默认上游组
         self.validate_default_upstream_group(&group_names)?;
         
+        // 验证远程规则集配置
+        self.validate_remote_rules()?;
+        
         Ok(())
     }
     
@@ -750,8 +3933,8 @@ impl ServerConfig {
                 )));
             }
             
-            // 检查组中至少有一个解析器
-            if group.resolvers.is_empty() {
+            // 检查组中至少有一个解析器（通过 Consul 发现上游的组可以不配置静态解析器）
+            if group.resolvers.is_empty() && group.consul_upstream_discovery.is_none() {
                 return Err(ServerError::Config(format!(
                     "Upstream group '{}' must have at least one resolver", 
                     group.name
@@ -760,6 +3943,14 @@ impl ServerConfig {
             
             // 验证解析器配置
             self.validate_resolvers(&group.resolvers)?;
+            
+            // 连接池空闲超时必须大于 0
+            if group.connection_pool.as_ref().and_then(|pool| pool.idle_timeout_secs) == Some(0) {
+                return Err(ServerError::Config(format!(
+                    "Invalid connection_pool.idle_timeout_secs for upstream group '{}': must be greater than 0",
+                    group.name
+                )));
+            }
         }
         
         Ok(group_names)
@@ -771,14 +3962,8 @@ impl ServerConfig {
             // 获取规则索引（从1开始，用于错误消息）
             let rule_index = i + 1;
             
-            // 验证上游组名称存在于上游组列表中或为黑洞特殊值
-            if rule.upstream_group != BLACKHOLE_UPSTREAM_GROUP_NAME && !group_names.contains(&rule.upstream_group) {
-                return Err(ServerError::Config(format!(
-                    "Rule #{} references unknown upstream group: {}", 
-                    rule_index,
-                    rule.upstream_group
-                )));
-            }
+            // 验证上游组名称存在于上游组列表中或为黑洞、全局上游特殊值，包括规则目录的文件到上游组映射
+            rule.validate_targets(rule_index, group_names)?;
             
             // 验证匹配条件
             self.validate_match_condition(&rule.match_, rule_index)?;
@@ -797,6 +3982,15 @@ impl ServerConfig {
                         rule_index
                     )));
                 }
+                // 验证域名可以被规范化（大小写、末尾的点与国际化域名）
+                for (i, domain) in match_.values.iter().flatten().enumerate() {
+                    if let Err(e) = normalize_rule_domain(domain) {
+                        return Err(ServerError::Config(format!(
+                            "Rule [{}]: Exact domain [{}] '{}' is invalid: {}",
+                            rule_index, i, domain, e
+                        )));
+                    }
+                }
             }
             MatchType::Regex => {
                 if match_.values.is_none() {
@@ -824,6 +4018,14 @@ impl ServerConfig {
                         rule_index
                     )));
                 }
+                for (i, pattern) in match_.values.iter().flatten().enumerate() {
+                    if let Err(e) = normalize_wildcard_pattern(pattern) {
+                        return Err(ServerError::Config(format!(
+                            "Rule [{}]: Wildcard pattern [{}] '{}' is invalid: {}",
+                            rule_index, i, pattern, e
+                        )));
+                    }
+                }
             }
             MatchType::File => {
                 if match_.path.is_none() {
@@ -892,6 +4094,98 @@ impl ServerConfig {
                     }
                 }
             }
+            MatchType::Dir => {
+                let Some(source) = &match_.source else {
+                    return Err(ServerError::Config(format!(
+                        "Rule [{}]: Dir match type requires 'source' with a 'dir' path",
+                        rule_index
+                    )));
+                };
+                // 目录中无法读取的文件在加载时跳过，此处只检查目录本身
+                if !Path::new(&source.dir).is_dir() {
+                    return Err(ServerError::Config(format!(
+                        "Rule [{}]: Dir type source '{}' is not a directory",
+                        rule_index, source.dir
+                    )));
+                }
+            }
+            MatchType::ClientIp => {
+                if match_.values.is_none() && match_.path.is_none() && match_.url.is_none() {
+                    return Err(ServerError::Config(format!(
+                        "Rule [{}]: Client IP match type requires 'values', 'path' or 'url'",
+                        rule_index
+                    )));
+                }
+                // 验证内联网段
+                for value in match_.values.iter().flatten() {
+                    if let Err(e) = parse_network(value) {
+                        return Err(ServerError::Config(format!("Rule [{}]: {}", rule_index, e)));
+                    }
+                }
+                // 验证网段文件可读且格式正确
+                if let Some(ref path) = match_.path {
+                    let content = fs::read_to_string(path).map_err(|e| ServerError::Config(format!(
+                        "Rule [{}]: Cannot read client IP file '{}': {}",
+                        rule_index, path, e
+                    )))?;
+                    if let Err(e) = parse_network_list(&content) {
+                        return Err(ServerError::Config(format!(
+                            "Rule [{}]: client IP file '{}': {}",
+                            rule_index, path, e
+                        )));
+                    }
+                }
+                if let Some(ref url) = match_.url {
+                    if let Err(e) = url::Url::parse(url) {
+                        return Err(ServerError::Config(format!(
+                            "Rule [{}]: Client IP URL '{}' is invalid: {}",
+                            rule_index, url, e
+                        )));
+                    }
+                }
+                if let Some(ref periodic) = match_.periodic {
+                    if periodic.enabled && !(MIN_URL_RULE_UPDATE_INTERVAL_SECS..=MAX_URL_RULE_UPDATE_INTERVAL_SECS).contains(&periodic.interval_secs) {
+                        return Err(ServerError::Config(format!(
+                            "Rule [{}]: Client IP periodic update interval {} seconds must be between {} and {} seconds",
+                            rule_index, periodic.interval_secs, MIN_URL_RULE_UPDATE_INTERVAL_SECS, MAX_URL_RULE_UPDATE_INTERVAL_SECS
+                        )));
+                    }
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // 验证远程规则集配置
+    fn validate_remote_rules(&self) -> Result<()> {
+        let routing = &self.dns.routing;
+        let Some(rules_url) = &routing.rules_url else {
+            return Ok(());
+        };
+        
+        match url::Url::parse(rules_url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {},
+            Ok(url) => {
+                return Err(ServerError::Config(format!(
+                    "routing.rules_url '{}' must use http or https, got '{}'",
+                    rules_url, url.scheme()
+                )));
+            },
+            Err(e) => {
+                return Err(ServerError::Config(format!(
+                    "routing.rules_url '{}' is invalid: {}",
+                    rules_url, e
+                )));
+            },
+        }
+        
+        let interval = routing.rules_reload_interval_secs;
+        if interval != 0 && !(MIN_URL_RULE_UPDATE_INTERVAL_SECS..=MAX_URL_RULE_UPDATE_INTERVAL_SECS).contains(&interval) {
+            return Err(ServerError::Config(format!(
+                "routing.rules_reload_interval_secs {} must be 0 or between {} and {} seconds",
+                interval, MIN_URL_RULE_UPDATE_INTERVAL_SECS, MAX_URL_RULE_UPDATE_INTERVAL_SECS
+            )));
         }
         
         Ok(())
@@ -962,6 +4256,46 @@ impl ServerConfig {
     }
 }
 
+impl Default for MdnsForwarderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            domains: default_mdns_domains(),
+            multicast_addr: default_mdns_multicast_addr(),
+            mdns_response_timeout_ms: DEFAULT_MDNS_RESPONSE_TIMEOUT_MS,
+            mdns_negative_ttl_secs: DEFAULT_MDNS_NEGATIVE_TTL_SECS,
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    // 规范的默认配置，使用公共 DNS 作为全局上游，可通过 --generate-config 输出
+    fn default() -> Self {
+        let mut dns = DnsResolverConfig::default();
+        dns.upstream.resolvers = DEFAULT_UPSTREAM_RESOLVERS.iter()
+            .map(|address| ResolverConfig {
+                address: address.to_string(),
+                protocol: default_resolver_protocol(),
+                path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                ca_cert_path: None,
+            })
+            .collect();
+        
+        Self {
+            http: HttpServerConfig::default(),
+            dns,
+            dns_server: DnsServerConfig::default(),
+            dot_server: DotServerConfig::default(),
+            stats: StatsConfig::default(),
+            security: SecurityConfig::default(),
+            logging: LoggingConfig::default(),
+            debug: DebugConfig::default(),
+        }
+    }
+}
+
 impl Default for TtlConfig {
     fn default() -> Self {
         Self {
@@ -979,6 +4313,12 @@ impl Default for CacheConfig {
             size: DEFAULT_CACHE_SIZE,
             ttl: TtlConfig::default(),
             persistence: PersistenceCacheConfig::default(),
+            async_miss_resolution: false,
+            return_servfail_on_miss: false,
+            async_miss_resolution_timeout_ms: DEFAULT_ASYNC_MISS_RESOLUTION_TIMEOUT_MS,
+            no_cache_rcodes: default_no_cache_rcodes(),
+            max_answer_records: default_max_answer_records(),
+            key_scope: CacheKeyScope::default(),
         }
     }
 }
@@ -989,6 +4329,8 @@ impl Default for RateLimitConfig {
             enabled: false,
             per_ip_rate: DEFAULT_PER_IP_RATE,
             per_ip_concurrent: DEFAULT_PER_IP_CONCURRENT,
+            overrides: Vec::new(),
+            count_cache_hits: true,
         }
     }
 }
@@ -998,6 +4340,7 @@ impl Default for PoolConfig {
         Self {
             idle_timeout: DEFAULT_HTTP_CLIENT_POOL_IDLE_TIMEOUT,
             max_idle_connections: DEFAULT_HTTP_CLIENT_POOL_MAX_IDLE_CONNECTIONS,
+            max_connections: DEFAULT_HTTP_CLIENT_POOL_MAX_CONNECTIONS,
         }
     }
 }
@@ -1015,8 +4358,23 @@ impl Default for HttpClientConfig {
     fn default() -> Self {
         Self {
             timeout: DEFAULT_HTTP_CLIENT_TIMEOUT,
+            connect_timeout: DEFAULT_HTTP_CLIENT_CONNECT_TIMEOUT,
+            tls_handshake_allowance: DEFAULT_HTTP_CLIENT_TLS_HANDSHAKE_ALLOWANCE,
             pool: PoolConfig::default(),
             request: RequestConfig::default(),
+            retry_status: Vec::new(),
+            max_retries: DEFAULT_HTTP_CLIENT_MAX_RETRIES,
+        }
+    }
+}
+
+impl Default for UnixSocketConfig {
+    fn default() -> Self {
+        Self {
+            mode: default_unix_socket_mode(),
+            owner: None,
+            group: None,
+            rate_limit: false,
         }
     }
 }
@@ -1024,9 +4382,79 @@ impl Default for HttpClientConfig {
 impl Default for HttpServerConfig {
     fn default() -> Self {
         Self {
-            listen_addr: default_listen_addr(),
-            timeout: DEFAULT_LISTEN_TIMEOUT,
+            listen_addr: default_listen_addrs(),
+            listen_unix: None,
+            unix_socket: UnixSocketConfig::default(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            read_header_timeout: DEFAULT_READ_HEADER_TIMEOUT,
+            idle_keepalive_timeout: DEFAULT_IDLE_KEEPALIVE_TIMEOUT,
+            max_requests_per_connection: 0,
+            max_concurrent_requests: 0,
+            enable_h2c: false,
+            shutdown_grace_period_secs: DEFAULT_SHUTDOWN_GRACE_PERIOD_SECS,
+            doh_paths: default_doh_paths(),
             rate_limit: RateLimitConfig::default(),
+            access_control: AccessControlConfig::default(),
+            max_request_body_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+            servfail_retry_after_secs: DEFAULT_SERVFAIL_RETRY_AFTER_SECS,
+            error_mode: ErrorMode::default(),
+            metrics_auth: None,
+            admin_token: None,
+            metrics_listen_addr: None,
+            admin_listen_addr: None,
+            tls: None,
+            server_name: String::new(),
+            http_listen_addr: None,
+            http_to_https_redirect: false,
+            http3: Http3Config::default(),
+            request_id_header: default_request_id_header(),
+            propagate_request_id_upstream: false,
+            request_id_trusted_proxies: Vec::new(),
+            proxy_protocol: false,
+            trusted_proxies: Vec::new(),
+            odoh: OdohConfig::default(),
+            cors: CorsConfig::default(),
+            compression: CompressionConfig::default(),
+            request_decompression: false,
+            trusted_override_ips: Vec::new(),
+            upstream_override_header: default_upstream_override_header(),
+            auth: AuthConfig::default(),
+            websocket_enabled: false,
+            root_info_enabled: default_root_info_enabled(),
+            emit_cache_headers: default_emit_cache_headers(),
+            alert_on_response_larger_than_bytes: None,
+        }
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            algorithms: default_compression_algorithms(),
+            min_size: DEFAULT_COMPRESSION_MIN_SIZE,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_origins: default_cors_allowed_origins(),
+            max_age: DEFAULT_CORS_MAX_AGE_SECS,
+            include_admin_routes: false,
+        }
+    }
+}
+
+impl Default for OdohConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_odoh_path(),
+            key_dir: default_odoh_key_dir(),
+            key_rotation_secs: DEFAULT_ODOH_KEY_ROTATION_SECS,
         }
     }
 }
@@ -1036,13 +4464,38 @@ impl Default for DnsResolverConfig {
         Self {
             upstream: UpstreamConfig {
                 resolvers: Vec::new(),
+                mode: UpstreamMode::Forwarding,
+                root_hints: default_root_hints(),
                 enable_dnssec: false,
                 query_timeout: DEFAULT_QUERY_TIMEOUT,
+                edns_padding: false,
+                tcp_keepalive: false,
+                discover_via_srv: false,
+                srv_name: String::new(),
+                srv_refresh_interval_secs: DEFAULT_SRV_REFRESH_INTERVAL_SECS,
+                consul_upstream_discovery: None,
+                failure_ratio_threshold: None,
+                recovery_threshold: None,
+                rolling_window_secs: DEFAULT_FAILURE_RATIO_WINDOW_SECS,
+                prefer_http2: true,
+                http2_keepalive_interval_secs: DEFAULT_HTTP2_KEEPALIVE_INTERVAL_SECS,
+                bind_addr: None,
+                strict_response_validation: true,
+                refused_types_for_private_ips: false,
+                refused_types: Vec::new(),
             },
             http_client: HttpClientConfig::default(),
             cache: CacheConfig::default(),
             routing: RoutingConfig::default(),
             ecs_policy: EcsPolicyConfig::default(),
+            min_response_ttl_override: None,
+            max_response_ttl_override: None,
+            strip_record_types: Vec::new(),
+            flatten_cname: false,
+            flatten_cname_strip_chain: true,
+            response_deduplication: false,
+            mdns_forwarder: MdnsForwarderConfig::default(),
+            svcb_hints: std::collections::HashMap::new(),
         }
     }
 }
@@ -1098,3 +4551,97 @@ impl Default for PeriodicUpdateConfig {
     }
 }
 
+impl Default for AcmeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            domains: Vec::new(),
+            contact: None,
+            cache_dir: default_acme_cache_dir(),
+            directory_url: default_acme_directory_url(),
+            challenge: AcmeChallengeType::default(),
+            http01_listen_addr: None,
+            renew_before_days: DEFAULT_ACME_RENEW_BEFORE_DAYS,
+        }
+    }
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            upstream_groups: Vec::new(),
+            rules: Vec::new(),
+            default_upstream_group: None,
+            rules_url: None,
+            rules_reload_interval_secs: DEFAULT_URL_RULE_UPDATE_INTERVAL_SECS,
+            load_balance_across_groups: false,
+        }
+    }
+}
+
+impl Default for QueryLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: QueryLogTarget::default(),
+            path: None,
+            format: QueryLogFormat::default(),
+            hash_client_ip: false,
+            hash_key: None,
+            sample_rate: DEFAULT_QUERY_LOG_SAMPLE_RATE,
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl Default for PaddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            block_size: EDNS_RESPONSE_PADDING_BLOCK_SIZE,
+        }
+    }
+}
+
+impl Default for DnsServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_udp: None,
+            listen_tcp: None,
+            tcp_idle_timeout: DEFAULT_DNS_SERVER_TCP_IDLE_TIMEOUT,
+            proxy_protocol: false,
+        }
+    }
+}
+
+impl Default for DotServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: None,
+            cert: None,
+            key: None,
+            idle_timeout: DEFAULT_DOT_SERVER_IDLE_TIMEOUT,
+            proxy_protocol: false,
+        }
+    }
+}
+
+impl Default for DebugCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            size: DEFAULT_DEBUG_CAPTURE_SIZE,
+        }
+    }
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            top_domains_window_secs: DEFAULT_STATS_WINDOW_SECS,
+            max_tracked_domains: DEFAULT_STATS_MAX_TRACKED_DOMAINS,
+        }
+    }
+}
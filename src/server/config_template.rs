@@ -0,0 +1,221 @@
+// src/server/config_template.rs
+
+use std::collections::HashMap;
+use serde_yaml::{Mapping, Value};
+use crate::server::config::ServerConfig;
+use crate::server::error::{ServerError, Result};
+
+// 带注释的示例配置，作为生成配置时的注释来源
+const CONFIG_TEMPLATE: &str = include_str!("../../config.default.yaml");
+
+// 生成配置的文件头
+const GENERATED_CONFIG_HEADER: &str = "\
+# Oxide WDNS 默认配置文件（由 --generate-config 生成）
+# 所有取值均为内置默认值，注释来自 config.default.yaml；被注释的配置项默认未设置。
+";
+
+// YAML 每级缩进的空格数
+const INDENT_WIDTH: usize = 2;
+
+// 生成带注释的默认配置 YAML
+//
+// 配置项与取值来自 ServerConfig::default() 的序列化结果，因此始终与结构体保持同步；
+// 注释按配置路径从 config.default.yaml 中提取，缺少注释的配置项仅输出取值。
+pub fn generate_default_config() -> Result<String> {
+    let value = serde_yaml::to_value(ServerConfig::default())
+        .map_err(|e| ServerError::Config(format!("Failed to serialize default config: {}", e)))?;
+    let Value::Mapping(root) = value else {
+        return Err(ServerError::Config("Default config is not a mapping".to_string()));
+    };
+
+    let comments = extract_comments(CONFIG_TEMPLATE);
+    let mut output = String::from(GENERATED_CONFIG_HEADER);
+    write_mapping(&mut output, &root, "", 0, &comments)?;
+    Ok(output)
+}
+
+// 按配置路径（如 "dns_resolver.upstream.resolvers[].address"）提取配置项前的注释
+fn extract_comments(template: &str) -> HashMap<String, Vec<String>> {
+    let mut comments: HashMap<String, Vec<String>> = HashMap::new();
+    // 当前路径栈：(缩进, 路径段)，列表项使用 "[]" 路径段
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+
+    for line in template.lines() {
+        let Some(line) = parse_template_line(line) else {
+            pending.clear();
+            continue;
+        };
+
+        let (indent, key) = match line {
+            TemplateLine::Comment(text) => {
+                pending.push(text);
+                continue;
+            },
+            TemplateLine::Value => {
+                pending.clear();
+                continue;
+            },
+            TemplateLine::Key { indent, key, list_item } => {
+                if list_item {
+                    // 列表项的键比 "- " 多缩进两格
+                    stack.retain(|(i, _)| *i <= indent);
+                    if !matches!(stack.last(), Some((i, segment)) if *i == indent && segment == "[]") {
+                        stack.push((indent, "[]".to_string()));
+                    }
+                    (indent + INDENT_WIDTH, key)
+                } else {
+                    (indent, key)
+                }
+            },
+        };
+
+        stack.retain(|(i, _)| *i < indent);
+        stack.push((indent, key));
+
+        let path = join_path(stack.iter().map(|(_, segment)| segment.as_str()));
+        let collected = std::mem::take(&mut pending);
+        if !collected.is_empty() {
+            comments.entry(path).or_insert(collected);
+        }
+    }
+
+    comments
+}
+
+// 模板中的一行
+enum TemplateLine {
+    // 注释文本
+    Comment(String),
+    // 配置项（包括被注释掉的配置项）
+    Key { indent: usize, key: String, list_item: bool },
+    // 其他内容（如列表中的标量值）
+    Value,
+}
+
+// 解析模板行，空行返回 None；被注释掉的配置项按其在注释内的缩进解析为配置项
+fn parse_template_line(line: &str) -> Option<TemplateLine> {
+    let content = line.trim_start();
+    if content.is_empty() {
+        return None;
+    }
+    let indent = line.len() - content.len();
+
+    if let Some(text) = content.strip_prefix('#') {
+        // 注释内容按 "#" 之后的缩进解析：可能是被注释掉的配置项，或注释块内的注释
+        let text = text.strip_prefix(' ').unwrap_or(text);
+        let inner = format!("{}{}", " ".repeat(indent), text);
+        return match parse_template_line(&inner) {
+            parsed @ Some(TemplateLine::Key { .. }) => parsed,
+            parsed @ Some(TemplateLine::Comment(_)) => parsed,
+            _ => Some(TemplateLine::Comment(text.trim().to_string())),
+        };
+    }
+
+    let (content, list_item) = match content.strip_prefix("- ") {
+        Some(rest) => (rest, true),
+        None => (content, false),
+    };
+    match parse_key(content) {
+        Some(key) => Some(TemplateLine::Key { indent, key: key.to_string(), list_item }),
+        None => Some(TemplateLine::Value),
+    }
+}
+
+// 解析 "key:" 或 "key: value" 形式的配置项名称
+fn parse_key(content: &str) -> Option<&str> {
+    let (key, rest) = content.split_once(':')?;
+    let is_key = !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && (rest.is_empty() || rest.starts_with(' '));
+    is_key.then_some(key)
+}
+
+// 拼接配置路径，列表项路径段直接附加在父路径之后
+fn join_path<'a>(segments: impl Iterator<Item = &'a str>) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        if segment != "[]" && !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(segment);
+    }
+    path
+}
+
+// 输出映射，每个配置项前附带注释
+fn write_mapping(
+    output: &mut String,
+    mapping: &Mapping,
+    parent: &str,
+    depth: usize,
+    comments: &HashMap<String, Vec<String>>,
+) -> Result<()> {
+    let indent = " ".repeat(depth * INDENT_WIDTH);
+
+    for (key, value) in mapping {
+        let key = key.as_str()
+            .ok_or_else(|| ServerError::Config("Config keys must be strings".to_string()))?;
+        let path = join_path([parent, key].into_iter().filter(|segment| !segment.is_empty()));
+
+        // 顶层配置段之间空一行
+        if depth == 0 {
+            output.push('\n');
+        }
+        for comment in comments.get(&path).into_iter().flatten() {
+            output.push_str(&format!("{}# {}\n", indent, comment));
+        }
+
+        match value {
+            // 未设置的可选项以注释形式给出
+            Value::Null => output.push_str(&format!("{}# {}:\n", indent, key)),
+            Value::Mapping(child) if !child.is_empty() => {
+                output.push_str(&format!("{}{}:\n", indent, key));
+                write_mapping(output, child, &path, depth + 1, comments)?;
+            },
+            Value::Sequence(items) if items.iter().any(|item| item.is_mapping()) => {
+                output.push_str(&format!("{}{}:\n", indent, key));
+                write_sequence_of_mappings(output, items, depth + 1)?;
+            },
+            value => output.push_str(&format!("{}{}: {}\n", indent, key, inline_value(value)?)),
+        }
+    }
+
+    Ok(())
+}
+
+// 输出元素为映射的列表
+fn write_sequence_of_mappings(output: &mut String, items: &[Value], depth: usize) -> Result<()> {
+    let indent = " ".repeat(depth * INDENT_WIDTH);
+
+    for item in items {
+        let mut entry = serde_yaml::to_string(item)
+            .map_err(|e| ServerError::Config(format!("Failed to serialize config value: {}", e)))?;
+        entry = entry.lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let prefix = if i == 0 { "- " } else { "  " };
+                format!("{}{}{}\n", indent, prefix, line)
+            })
+            .collect();
+        output.push_str(&entry);
+    }
+
+    Ok(())
+}
+
+// 将标量、空映射与元素均为标量的列表输出为单行
+fn inline_value(value: &Value) -> Result<String> {
+    let serialize = |value: &Value| serde_yaml::to_string(value)
+        .map(|s| s.trim_end().to_string())
+        .map_err(|e| ServerError::Config(format!("Failed to serialize config value: {}", e)));
+
+    match value {
+        Value::Mapping(mapping) if mapping.is_empty() => Ok("{}".to_string()),
+        Value::Sequence(items) => {
+            let items = items.iter().map(serialize).collect::<Result<Vec<_>>>()?;
+            Ok(format!("[{}]", items.join(", ")))
+        },
+        value => serialize(value),
+    }
+}
@@ -0,0 +1,97 @@
+// src/server/consul.rs
+
+// 该模块实现 Consul 上游发现（consul_upstream_discovery）的接口访问。
+//
+// 查询 Consul 健康检查接口 /v1/health/service/<service_name>?passing，只返回通过全部健康检查的实例；
+// 配置了 health_check_tag 时附加 tag 参数，只使用带有该标签的实例。
+// 每个实例优先使用服务地址（Service.Address），未设置时使用节点地址（Node.Address），
+// 构建为 https://<address>:<port>/dns-query 形式的 DoH 上游 URL。
+
+use std::net::IpAddr;
+use reqwest::Client;
+use serde::Deserialize;
+use url::Url;
+use crate::common::consts::{CONSUL_HEALTH_SERVICE_PATH, SRV_DISCOVERED_DOH_PATH};
+use crate::server::config::ConsulUpstreamDiscoveryConfig;
+use crate::server::error::{Result, ServerError};
+
+// 健康检查接口返回的一个服务实例
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ServiceEntry {
+    node: NodeInfo,
+    service: ServiceInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NodeInfo {
+    #[serde(default)]
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ServiceInfo {
+    #[serde(default)]
+    address: String,
+    port: u16,
+}
+
+impl ServiceEntry {
+    // 实例的 DoH 上游 URL，没有可用地址时返回 None
+    fn doh_url(&self) -> Option<String> {
+        let address = match self.service.address.trim() {
+            "" => self.node.address.trim(),
+            address => address,
+        };
+        if address.is_empty() || self.service.port == 0 {
+            return None;
+        }
+        let host = match address.parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) => format!("[{}]", ip),
+            _ => address.to_string(),
+        };
+        Some(format!("https://{}:{}{}", host, self.service.port, SRV_DISCOVERED_DOH_PATH))
+    }
+}
+
+// 构建健康检查接口 URL，服务名称作为单独的路径段进行百分号编码，避免其中的特殊字符改变请求路径
+pub fn health_service_url(config: &ConsulUpstreamDiscoveryConfig) -> Result<Url> {
+    let mut url = Url::parse(&config.consul_addr)
+        .map_err(|e| ServerError::Config(format!("Invalid consul_addr '{}': {}", config.consul_addr, e)))?;
+    url.path_segments_mut()
+        .map_err(|_| ServerError::Config(format!("Invalid consul_addr '{}': cannot be a base URL", config.consul_addr)))?
+        .pop_if_empty()
+        .extend(CONSUL_HEALTH_SERVICE_PATH.split('/').filter(|segment| !segment.is_empty()))
+        .push(config.service_name.trim());
+    Ok(url)
+}
+
+// 查询 Consul 中通过健康检查的服务实例，按 Consul 返回的顺序返回去重后的 DoH 上游 URL
+// 请求失败或响应无法解析时返回错误；没有健康实例时返回空列表
+pub async fn fetch_healthy_upstreams(client: &Client, config: &ConsulUpstreamDiscoveryConfig) -> Result<Vec<String>> {
+    let url = health_service_url(config)?;
+    let mut request = client.get(url.clone()).query(&[("passing", "true")]);
+    if let Some(tag) = &config.health_check_tag {
+        request = request.query(&[("tag", tag.as_str())]);
+    }
+    
+    let response = request.send().await
+        .map_err(|e| ServerError::Upstream(format!("Consul request to {} failed: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(ServerError::Upstream(format!(
+            "Consul request to {} returned {}", url, response.status()
+        )));
+    }
+    let entries: Vec<ServiceEntry> = response.json().await
+        .map_err(|e| ServerError::Upstream(format!("Invalid Consul response from {}: {}", url, e)))?;
+    
+    let mut urls: Vec<String> = Vec::with_capacity(entries.len());
+    for url in entries.iter().filter_map(ServiceEntry::doh_url) {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    Ok(urls)
+}
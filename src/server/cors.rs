@@ -0,0 +1,108 @@
+// src/server/cors.rs
+
+// 该模块负责跨域资源共享 (CORS)。
+//
+// 浏览器中的 DoH 客户端与面板需要 CORS 才能读取响应。中间件应答预检 OPTIONS 请求，
+// 并仅对配置允许的来源回显 Access-Control-Allow-Origin；未携带 Origin 的请求不受影响。
+
+use std::sync::Arc;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, Response, StatusCode},
+    middleware::{self, Next},
+    Router,
+};
+use tracing::{debug, info};
+use crate::common::consts::{CORS_ALLOWED_HEADERS, CORS_ALLOWED_METHODS, CORS_ANY_ORIGIN};
+use crate::server::config::CorsConfig;
+
+// 为路由添加 CORS 中间件
+pub fn apply_cors(routes: Router, config: &CorsConfig) -> Router {
+    let policy = Arc::new(CorsPolicy::new(config));
+    info!(
+        origins = ?config.allowed_origins,
+        max_age = config.max_age,
+        "CORS enabled"
+    );
+
+    routes.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let policy = policy.clone();
+        async move { policy.handle(req, next).await }
+    }))
+}
+
+// 预先计算的 CORS 策略
+struct CorsPolicy {
+    // 是否允许任意来源
+    any_origin: bool,
+    // 允许的来源（显式列表）
+    allowed_origins: Vec<String>,
+    // 预检结果缓存时间
+    max_age: HeaderValue,
+}
+
+impl CorsPolicy {
+    fn new(config: &CorsConfig) -> Self {
+        Self {
+            any_origin: config.allows_any_origin(),
+            allowed_origins: config.allowed_origins.clone(),
+            max_age: HeaderValue::from(config.max_age),
+        }
+    }
+
+    async fn handle(&self, req: Request, next: Next) -> Response<Body> {
+        let Some(origin) = req.headers().get(header::ORIGIN).cloned() else {
+            return next.run(req).await;
+        };
+        let allowed_origin = self.allowed_origin(&origin);
+        let is_preflight = req.method() == Method::OPTIONS
+            && req.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+        if is_preflight {
+            let Some(allowed_origin) = allowed_origin else {
+                debug!(origin = ?origin, "Rejected CORS preflight from disallowed origin");
+                return Response::builder()
+                    .status(StatusCode::FORBIDDEN)
+                    .body(Body::from("CORS origin not allowed"))
+                    .unwrap();
+            };
+
+            let mut response = Response::builder()
+                .status(StatusCode::NO_CONTENT)
+                .body(Body::empty())
+                .unwrap();
+            let headers = response.headers_mut();
+            self.insert_origin_headers(headers, allowed_origin);
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static(CORS_ALLOWED_METHODS));
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static(CORS_ALLOWED_HEADERS));
+            headers.insert(header::ACCESS_CONTROL_MAX_AGE, self.max_age.clone());
+            return response;
+        }
+
+        let mut response = next.run(req).await;
+        if let Some(allowed_origin) = allowed_origin {
+            self.insert_origin_headers(response.headers_mut(), allowed_origin);
+        }
+        response
+    }
+
+    // 返回应回显的 Access-Control-Allow-Origin 值，来源不被允许时返回 None
+    fn allowed_origin(&self, origin: &HeaderValue) -> Option<HeaderValue> {
+        if self.any_origin {
+            return Some(HeaderValue::from_static(CORS_ANY_ORIGIN));
+        }
+        let origin_str = origin.to_str().ok()?;
+        self.allowed_origins.iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(origin_str))
+            .then(|| origin.clone())
+    }
+
+    // 添加来源相关的响应头；回显显式来源时需要 Vary: Origin，避免共享缓存混用响应
+    fn insert_origin_headers(&self, headers: &mut HeaderMap, allowed_origin: HeaderValue) {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allowed_origin);
+        if !self.any_origin {
+            headers.append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+    }
+}
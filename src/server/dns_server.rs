@@ -0,0 +1,303 @@
+// src/server/dns_server.rs
+
+// 该模块提供经典 DNS（UDP/TCP）监听，供局域网内不支持 DoH 的客户端使用。
+//
+// 查询通过 ServerState::resolve 进入与 DoH 相同的缓存、路由与上游流程。
+// UDP 响应超过客户端 EDNS 缓冲区大小（无 EDNS 时为 512 字节）时截断并设置 TC 位，
+// 客户端随后改用 TCP 重试；TCP 连接上的消息带两字节长度前缀（RFC 1035 4.2.2）。
+// 查询计入关闭排空：排空期间不再处理新查询，宽限期结束时放弃进行中的查询。
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use hickory_proto::op::{Message, MessageType, OpCode, ResponseCode};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tracing::{debug, info, warn};
+use crate::common::consts::{DNS_MESSAGE_HEADER_SIZE, MAX_REQUEST_BODY_SIZE};
+use crate::server::config::DnsServerConfig;
+use crate::server::doh_handler::{ResolveOptions, ServerState};
+use crate::server::drain::DrainController;
+use crate::server::health::HealthSource;
+use crate::server::metrics::METRICS;
+use crate::server::proxy_protocol::{client_addr, ProxyProtocol};
+
+// 监听标签
+const LISTENER_UDP: &str = "udp";
+const LISTENER_TCP: &str = "tcp";
+
+// 查询处理结果标签
+const OUTCOME_ANSWERED: &str = "answered";
+const OUTCOME_TRUNCATED: &str = "truncated";
+const OUTCOME_FORMAT_ERROR: &str = "format_error";
+const OUTCOME_DROPPED: &str = "dropped";
+
+// 经典 DNS 监听
+pub struct DnsServer {
+    // 与 DoH 处理器共享的服务器状态
+    state: ServerState,
+    // 进行中查询跟踪（关闭时排空）
+    drain: Arc<DrainController>,
+    // 监听配置
+    config: DnsServerConfig,
+    // TCP 监听的 PROXY 协议解析器（未启用时为 None）
+    proxy_protocol: Option<ProxyProtocol>,
+    // UDP 监听是否正在运行
+    udp_running: AtomicBool,
+    // TCP 监听是否正在运行
+    tcp_running: AtomicBool,
+}
+
+// 监听退出时清除运行标记
+pub(crate) struct RunningGuard<'a>(&'a AtomicBool);
+
+impl<'a> RunningGuard<'a> {
+    pub(crate) fn new(running: &'a AtomicBool) -> Self {
+        running.store(true, Ordering::Relaxed);
+        Self(running)
+    }
+}
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+impl DnsServer {
+    pub fn new(state: ServerState, drain: Arc<DrainController>, config: DnsServerConfig) -> Self {
+        let proxy_protocol = config.proxy_protocol
+            .then(|| ProxyProtocol::from_config(&state.config.http, LISTENER_TCP));
+        Self {
+            state,
+            drain,
+            config,
+            proxy_protocol,
+            udp_running: AtomicBool::new(false),
+            tcp_running: AtomicBool::new(false),
+        }
+    }
+
+    // 在 UDP 套接字上提供服务，每个查询在独立任务中处理
+    pub async fn serve_udp(self: Arc<Self>, socket: UdpSocket) -> io::Result<()> {
+        let _running = RunningGuard::new(&self.udp_running);
+        let socket = Arc::new(socket);
+        let mut buf = vec![0u8; MAX_REQUEST_BODY_SIZE];
+
+        loop {
+            let (len, peer) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    // ICMP 端口不可达等错误只影响单个报文
+                    debug!("Failed to receive DNS UDP datagram: {}", e);
+                    continue;
+                }
+            };
+
+            let wire = buf[..len].to_vec();
+            let server = self.clone();
+            let socket = socket.clone();
+            tokio::spawn(async move {
+                let Some((response, max_payload)) = server.answer(&wire, peer.ip(), LISTENER_UDP).await else {
+                    return;
+                };
+                let Some(bytes) = encode_udp_response(&response, max_payload) else {
+                    return;
+                };
+                if let Err(e) = socket.send_to(&bytes, peer).await {
+                    debug!(client_ip = %peer.ip(), "Failed to send DNS UDP response: {}", e);
+                }
+            });
+        }
+    }
+
+    // 在 TCP 监听上提供服务，每个连接按顺序处理其上的查询
+    pub async fn serve_tcp(self: Arc<Self>, listener: TcpListener) -> io::Result<()> {
+        let _running = RunningGuard::new(&self.tcp_running);
+
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept DNS TCP connection: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            let server = self.clone();
+            tokio::spawn(async move {
+                METRICS.dns_server_tcp_connections().inc();
+                if let Err(e) = server.serve_tcp_connection(stream, peer).await {
+                    debug!(client_ip = %peer.ip(), "DNS TCP connection closed with error: {}", e);
+                }
+                METRICS.dns_server_tcp_connections().dec();
+            });
+        }
+    }
+
+    // 处理单个 TCP 连接，空闲超时、排空或对端关闭时结束
+    async fn serve_tcp_connection(&self, mut stream: TcpStream, peer: SocketAddr) -> io::Result<()> {
+        let peer = client_addr(self.proxy_protocol.as_ref(), &mut stream, peer).await?;
+        let idle_timeout = Duration::from_secs(self.config.tcp_idle_timeout);
+
+        loop {
+            let len = match tokio::time::timeout(idle_timeout, stream.read_u16()).await {
+                Ok(Ok(len)) => len as usize,
+                Ok(Err(e)) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Ok(Err(e)) => return Err(e),
+                Err(_) => {
+                    debug!(client_ip = %peer.ip(), "Closing idle DNS TCP connection");
+                    return Ok(());
+                }
+            };
+
+            let mut wire = vec![0u8; len];
+            tokio::time::timeout(idle_timeout, stream.read_exact(&mut wire)).await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "timed out reading DNS message"))??;
+
+            let Some((response, _)) = self.answer(&wire, peer.ip(), LISTENER_TCP).await else {
+                return Ok(());
+            };
+            stream.write_all(&encode_tcp_response(&response)?).await?;
+        }
+    }
+
+    async fn answer(&self, wire: &[u8], client_ip: IpAddr, listener: &str) -> Option<(Message, u16)> {
+        answer_query(&self.state, &self.drain, wire, client_ip, listener).await
+    }
+}
+
+// 解析并回答一个查询，返回响应与客户端可接收的 UDP 响应大小
+//
+// 排空期间、宽限期结束或收到的不是查询时返回 None，不发送响应。
+// 经典 DNS 与 DoT 监听共用该流程，listener 为指标中的监听标签。
+pub(crate) async fn answer_query(
+    state: &ServerState,
+    drain: &DrainController,
+    wire: &[u8],
+    client_ip: IpAddr,
+    listener: &str,
+) -> Option<(Message, u16)> {
+    let Some(_permit) = drain.begin_query() else {
+        METRICS.dns_server_queries_total().with_label_values(&[listener, OUTCOME_DROPPED]).inc();
+        return None;
+    };
+
+    let query = match Message::from_vec(wire) {
+        Ok(query) if query.message_type() == MessageType::Query => query,
+        Ok(_) => {
+            METRICS.dns_server_queries_total().with_label_values(&[listener, OUTCOME_DROPPED]).inc();
+            return None;
+        },
+        Err(e) => {
+            debug!(client_ip = %client_ip, listener, "Failed to parse DNS query: {}", e);
+            METRICS.dns_server_queries_total().with_label_values(&[listener, OUTCOME_FORMAT_ERROR]).inc();
+            // 消息头完整时回复 FORMERR，否则无法得知查询 ID
+            if wire.len() < DNS_MESSAGE_HEADER_SIZE {
+                return None;
+            }
+            let id = u16::from_be_bytes([wire[0], wire[1]]);
+            return Some((Message::error_msg(id, OpCode::Query, ResponseCode::FormErr), 0));
+        }
+    };
+
+    let max_payload = query.max_payload();
+    let response = tokio::select! {
+        resolution = state.resolve_with_protocol(&query, Some(client_ip), listener, ResolveOptions::default()) => resolution.message,
+        _ = drain.expired() => {
+            METRICS.dns_server_queries_total().with_label_values(&[listener, OUTCOME_DROPPED]).inc();
+            return None;
+        },
+    };
+
+    // UDP 响应可能被截断，由 encode_udp_response 在编码后记录 answered 或 truncated，保证每个查询只计入一种结果
+    if listener != LISTENER_UDP {
+        METRICS.dns_server_queries_total().with_label_values(&[listener, OUTCOME_ANSWERED]).inc();
+    }
+    Some((response, max_payload))
+}
+
+// 序列化流式传输的响应，并加上两字节长度前缀
+pub(crate) fn encode_tcp_response(response: &Message) -> io::Result<Vec<u8>> {
+    let bytes = response.to_vec().map_err(io::Error::other)?;
+    let len = u16::try_from(bytes.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "DNS response exceeds 65535 bytes"))?;
+
+    let mut framed = Vec::with_capacity(bytes.len() + 2);
+    framed.extend_from_slice(&len.to_be_bytes());
+    framed.extend_from_slice(&bytes);
+    Ok(framed)
+}
+
+impl HealthSource for DnsServer {
+    // 配置的监听未在运行时报告问题
+    fn health_issue(&self) -> Option<String> {
+        let listeners = [
+            (LISTENER_UDP, self.config.listen_udp, &self.udp_running),
+            (LISTENER_TCP, self.config.listen_tcp, &self.tcp_running),
+        ];
+        let stopped: Vec<String> = listeners.into_iter()
+            .filter_map(|(name, addr, running)| {
+                addr.filter(|_| !running.load(Ordering::Relaxed))
+                    .map(|addr| format!("DNS {} listener on {} is not running", name, addr))
+            })
+            .collect();
+        (!stopped.is_empty()).then(|| stopped.join("; "))
+    }
+}
+
+// 绑定配置的经典 DNS 监听，返回 (UDP 套接字, TCP 监听)
+pub async fn bind_dns_server(config: &DnsServerConfig) -> io::Result<(Option<UdpSocket>, Option<TcpListener>)> {
+    let udp = match config.listen_udp {
+        Some(addr) => {
+            let socket = UdpSocket::bind(addr).await.map_err(|e| io::Error::new(
+                e.kind(), format!("Failed to bind DNS UDP listener to {}: {}", addr, e)
+            ))?;
+            info!("DNS server listening on: {} (UDP)", addr);
+            Some(socket)
+        },
+        None => None,
+    };
+    let tcp = match config.listen_tcp {
+        Some(addr) => {
+            let listener = TcpListener::bind(addr).await.map_err(|e| io::Error::new(
+                e.kind(), format!("Failed to bind DNS TCP listener to {}: {}", addr, e)
+            ))?;
+            info!("DNS server listening on: {} (TCP)", addr);
+            Some(listener)
+        },
+        None => None,
+    };
+    Ok((udp, tcp))
+}
+
+// 序列化 UDP 响应，超过客户端可接收的大小时只保留问题部分与 EDNS 并设置 TC 位，
+// 并按是否截断记录 answered 或 truncated 结果
+fn encode_udp_response(response: &Message, max_payload: u16) -> Option<Vec<u8>> {
+    let bytes = match response.to_vec() {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to serialize DNS UDP response: {}", e);
+            return None;
+        }
+    };
+    // max_payload 为 0 表示 FORMERR 等不含记录的响应，已在解析时计入 format_error
+    if max_payload == 0 {
+        return Some(bytes);
+    }
+    if bytes.len() <= max_payload as usize {
+        METRICS.dns_server_queries_total().with_label_values(&[LISTENER_UDP, OUTCOME_ANSWERED]).inc();
+        return Some(bytes);
+    }
+
+    let mut truncated = response.clone();
+    truncated.take_answers();
+    truncated.take_name_servers();
+    truncated.take_additionals();
+    truncated.set_truncated(true);
+    METRICS.dns_server_queries_total().with_label_values(&[LISTENER_UDP, OUTCOME_TRUNCATED]).inc();
+    truncated.to_vec().ok()
+}
@@ -1,47 +1,84 @@
 // src/server/doh_handler.rs
 
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::net::IpAddr;
+use std::fmt;
+use std::pin::Pin;
+use std::time::Duration;
 use std::sync::Arc;
 use axum::{
-    extract::{Query, State},
-    http::{header, StatusCode, Request},
-    response::IntoResponse,
-    routing::{get, post},
-    Router as AxumRouter, Json,
+    extract::{MatchedPath, Query, State},
+    http::{header, HeaderValue, StatusCode, Request},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, MethodRouter},
+    Router as AxumRouter,
 };
-use axum::body::to_bytes;
-use serde::{Deserialize, Serialize};
+use axum::body::{Body, HttpBody};
+use axum::extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade};
+use bytes::{Bytes, BytesMut};
+use dashmap::DashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::sync::broadcast;
+use tokio::task::JoinSet;
 use tokio::time::Instant;
-use hickory_proto::op::{Message, MessageType, OpCode, ResponseCode};
-use hickory_proto::rr::{DNSClass, Name, RecordType};
-use tracing::{debug, info};
+use std::str::FromStr;
+use hickory_proto::op::{Edns, Message, MessageType, OpCode, ResponseCode};
+use hickory_proto::rr::{DNSClass, Name, RData, Record, RecordType};
+use hickory_proto::rr::dnssec::rdata::DNSSECRData;
+use tracing::{debug, info, warn, Instrument, Span};
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_ENGINE};
-use crate::server::error::{ServerError, Result};
+use crate::server::error::{ServerError, Result, UpstreamFailureKind};
 use crate::common::consts::{
     CONTENT_TYPE_DNS_JSON, 
     CONTENT_TYPE_DNS_MESSAGE,
+    CONTENT_TYPE_JSON,
+    CONTENT_TYPE_DNS_UDPWIREFORMAT,
     DNS_RECORD_TYPE_A, DNS_CLASS_IN, IP_HEADER_NAMES,
-    MAX_REQUEST_SIZE,
-    DOH_JSON_API_PATH, DOH_STANDARD_PATH,
-    DOH_FORMAT_JSON, DOH_FORMAT_WIRE,
+    DOH_FORMAT_JSON, DOH_FORMAT_WIRE, DOH_FORMAT_ODOH, DNS_MESSAGE_HEADER_SIZE,
+    CONTENT_TYPE_ODOH_MESSAGE, ODOH_CONFIGS_PATH, DOH_FORMAT_WEBSOCKET, DNS_WEBSOCKET_PATH,
+    DOH_ALLOWED_METHODS,
 };
-use crate::server::cache::{CacheKey, DnsCache};
-use crate::server::config::ServerConfig;
-use crate::server::routing::{RouteDecision, Router as DnsRouter};
+use crate::server::cache::{CacheKey, DnsCache, PendingMiss};
+use crate::server::capture::QueryCapture;
+use crate::server::compression::apply_request_decompression;
+use crate::server::config::{CacheKeyScope, DnsResolverConfig, ErrorMode, ServerConfig};
+use crate::server::refusal::{refusal_reason, refused_response};
+use crate::server::routing::{normalize_query_domain, RouteDecision, Router as DnsRouter};
 use crate::server::upstream::{UpstreamManager, UpstreamSelection};
-use crate::server::ecs::{EcsProcessor};
+use crate::server::ecs::{EcsData, EcsProcessor};
+use crate::server::ip_set::IpRangeSet;
+use crate::server::mdns::MdnsForwarder;
+use crate::server::svcb::synthesize_svcb_response;
 use crate::server::metrics::METRICS;
+use crate::server::stats::QueryStats;
+use crate::server::odoh::OdohTarget;
+use crate::server::padding::pad_message;
+use crate::server::query_log::{
+    self, QueryLogger, QueryRecord, QueryTrace,
+    QUERY_PROTOCOL_DOH_GET, QUERY_PROTOCOL_DOH_POST, QUERY_PROTOCOL_IN_PROCESS,
+    QUERY_PROTOCOL_JSON, QUERY_PROTOCOL_ODOH, QUERY_PROTOCOL_WEBSOCKET,
+};
+use crate::server::request_id::{current_request_id, with_request_id};
+use crate::server::security::check_deferred_rate_limit;
 
 // HTTP 方法常量
 const HTTP_METHOD_GET: &str = "GET";
 const HTTP_METHOD_POST: &str = "POST";
 
+// 不可缓存响应的 Cache-Control 值
+const CACHE_CONTROL_NO_STORE: &str = "max-age=0, no-store";
+
 // DNS 事件类型常量
 const DNS_EVENT_RECEIVED: &str = "received";
 const DNS_EVENT_PARAMETER_ERROR: &str = "parameter_error";
 const DNS_EVENT_PROCESSING_FAILED: &str = "processing_failed";
 const DNS_EVENT_PARSE_ERROR: &str = "parse_error";
 const DNS_EVENT_BASE64_DECODE_ERROR: &str = "base64_decode_error";
+const DNS_EVENT_DECRYPT_ERROR: &str = "decrypt_error";
 
 // DNS 查询类型常量
 const DNS_QUERY_TYPE_UNKNOWN: &str = "Unknown";
@@ -54,13 +91,27 @@ const ROUTE_RESULT_RULE_MATCH: &str = "rule_match";
 const ROUTE_RESULT_BLACKHOLE: &str = "blackhole";  
 const ROUTE_RESULT_DEFAULT: &str = "default";
 
+// 上游组覆盖结果常量
+const UPSTREAM_OVERRIDE_HONORED: &str = "honored";
+const UPSTREAM_OVERRIDE_IGNORED: &str = "ignored";
+const UPSTREAM_OVERRIDE_UNKNOWN_GROUP: &str = "unknown_group";
+
 // 错误消息常量
 const ERROR_INVALID_DNS_MESSAGE: &str = "Invalid DNS message format";
-const ERROR_INVALID_BASE64: &str = "Invalid base64 encoding";
 const ERROR_SERIALIZE_RESPONSE: &str = "Failed to serialize DNS response";
 const ERROR_INVALID_CONTENT_TYPE: &str = "Invalid content type";
 const ERROR_REQUEST_TOO_LARGE: &str = "Request body too large";
+const ERROR_REQUEST_TIMEOUT: &str = "Request processing timed out";
 const ERROR_READ_REQUEST_BODY: &str = "Failed to read request body";
+const ERROR_INVALID_JSON_REQUEST: &str = "Invalid JSON DNS request";
+const ERROR_UNKNOWN_UPSTREAM_GROUP: &str = "Unknown upstream group";
+const ERROR_NOT_ACCEPTABLE: &str = "Not acceptable: supported response types are application/dns-message and application/dns-json";
+const ERROR_UNKNOWN_ODOH_KEY: &str = "Unknown ODoH key id";
+const ERROR_INVALID_ODOH_MESSAGE: &str = "Invalid ODoH message";
+const ERROR_ENCRYPT_RESPONSE: &str = "Failed to encrypt ODoH response";
+
+// ODoH 公钥配置的内容类型
+const CONTENT_TYPE_OCTET_STREAM: &str = "application/octet-stream";
 
 // 共享的服务器状态
 #[derive(Clone)]
@@ -73,25 +124,284 @@ pub struct ServerState {
     pub router: Arc<DnsRouter>,
     // DNS 缓存
     pub cache: Arc<DnsCache>,
+    // 查询统计聚合器（未启用时为 None）
+    pub stats: Option<Arc<QueryStats>>,
+    // 查询访问日志（未启用时为 None）
+    pub query_log: Option<Arc<QueryLogger>>,
+    // 调试抓包缓冲区（未启用时为 None）
+    pub capture: Option<Arc<QueryCapture>>,
+    // 进行中的 wireformat 请求，相同的并发请求共享一次解析
+    pub pending_requests: Arc<PendingRequests>,
+    // 服务器启动时间（用于 /version 报告运行时间）
+    pub started_at: Instant,
+}
+
+impl ServerState {
+    // 在进程内解析一条 DNS 查询，不经过 HTTP 层
+    //
+    // 与 DoH 处理器共用同一解析流程：缓存 → 路由 → 上游 → 响应后处理，并记录查询指标与统计。
+    // client_ip 用于匹配 client_ip 路由规则，并在上游启用 ECS 时生成客户端子网；
+    // 为 None 时仅按域名路由，且不根据客户端地址生成 ECS。
+    // 处理失败时返回 SERVFAIL 响应。响应不做 EDNS 填充，由具体传输层决定。
+    pub async fn resolve(&self, query: Message, client_ip: Option<IpAddr>) -> Message {
+        self.resolve_with_protocol(&query, client_ip, QUERY_PROTOCOL_IN_PROCESS, ResolveOptions::default()).await.message
+    }
+    
+    // 所有传输层共用的解析流程：记录查询指标、域名统计与查询日志，protocol 为查询日志中记录的查询协议。
+    // 处理失败时响应为 SERVFAIL，失败原因保留在 Resolution::error 中，由 DoH 处理器按 error_mode 决定 HTTP 响应
+    pub async fn resolve_with_protocol(
+        &self,
+        query: &Message,
+        client_ip: Option<IpAddr>,
+        protocol: &str,
+        options: ResolveOptions<'_>,
+    ) -> Resolution {
+        let start = Instant::now();
+        let query_type = query.queries().first()
+            .map_or_else(|| DNS_QUERY_TYPE_UNKNOWN.to_string(), |q| format!("{:?}", q.query_type()));
+        
+        METRICS.dns_queries_total()
+            .with_label_values(&[&query_type, DNS_EVENT_RECEIVED])
+            .inc();
+        METRICS.dns_query_type_total()
+            .with_label_values(&[&query_type])
+            .inc();
+        
+        let (result, trace) = self.traced(async {
+            match options.dedup_wire {
+                Some(query_wire) => process_query_deduplicated(self, query, query_wire, client_ip, options.upstream_override).await,
+                None => process_query(self, query, client_ip, options.upstream_override).await,
+            }
+        }).await;
+        let (message, cache_age, error) = match result {
+            Ok((message, cache_age)) => (message, cache_age, None),
+            // 由速率限制中间件替换为带 Retry-After 的 429 响应，不计入查询统计
+            Err(e @ ServerError::RateLimited(_)) => {
+                return Resolution { message: build_servfail_response(query), cache_age: None, error: Some(e) };
+            },
+            Err(e) => {
+                info!(
+                    client_ip = ?client_ip,
+                    protocol,
+                    error = %e,
+                    "DNS query processing failed"
+                );
+                METRICS.dns_queries_total()
+                    .with_label_values(&[&query_type, DNS_EVENT_PROCESSING_FAILED])
+                    .inc();
+                (build_servfail_response(query), None, Some(e))
+            }
+        };
+        
+        let duration = start.elapsed();
+        let is_cached = cache_age.is_some();
+        info!(
+            domain = %query.queries().first().map(|q| q.name().to_utf8()).unwrap_or_default(),
+            qtype = %query_type,
+            protocol,
+            client_ip = ?client_ip,
+            answer_count = message.answer_count(),
+            response_code = ?message.response_code(),
+            dnssec_validated = message.authentic_data(),
+            query_time_ms = duration.as_millis(),
+            is_cached,
+            "DNS query resolved"
+        );
+        // 只在调试级别时记录应答记录详情，减少运行时开销
+        if message.answer_count() > 0 && tracing::enabled!(tracing::Level::DEBUG) {
+            let records: Vec<String> = message.answers().iter()
+                .filter_map(|record| record.data().map(|rdata| {
+                    format!("{}({}): {}", record.name(), record.record_type(), format_rdata_for_json(rdata))
+                }))
+                .collect();
+            debug!(client_ip = ?client_ip, records = ?records, "DNS response record details");
+        }
+        record_query_stats(self.stats.as_deref(), query, &message, is_cached, duration);
+        self.log_query(QueryRecord {
+            protocol,
+            client_ip,
+            query,
+            response: &message,
+            cached: is_cached,
+            trace,
+            latency: duration,
+        });
+        METRICS.dns_responses_total()
+            .with_label_values(&[&format!("{:?}", message.response_code())])
+            .inc();
+        
+        Resolution { message, cache_age, error }
+    }
+    
+    // 在查询日志的追踪上下文中处理查询，记录命中的路由规则与应答的上游
+    async fn traced<F: std::future::Future>(&self, future: F) -> (F::Output, QueryTrace) {
+        query_log::trace(self.query_log.is_some() || self.capture.is_some(), future).await
+    }
+    
+    // 记录调试抓包，并在启用查询日志时输出一条查询日志
+    fn log_query(&self, record: QueryRecord<'_>) {
+        if let Some(capture) = &self.capture {
+            capture.record(&record);
+        }
+        if let Some(query_log) = &self.query_log {
+            query_log.log(record);
+        }
+    }
+}
+
+// resolve_with_protocol 的可选参数
+#[derive(Default, Clone, Copy)]
+pub struct ResolveOptions<'a> {
+    // 受信任客户端指定的上游组，跳过路由规则与缓存
+    pub upstream_override: Option<&'a str>,
+    // 原始查询报文；提供时与报文逐字节相同的进行中请求共享一次解析
+    pub dedup_wire: Option<&'a [u8]>,
+}
+
+// 一次解析的结果
+pub struct Resolution {
+    // 响应报文，处理失败时为 SERVFAIL
+    pub message: Message,
+    // 来自 DNS 缓存时条目已存在的秒数
+    pub cache_age: Option<u64>,
+    // 处理失败的原因
+    pub error: Option<ServerError>,
+}
+
+// 进行中的 DoH wireformat 请求
+//
+// 客户端在收到响应前重发相同查询（报文逐字节相同）时，后到的请求等待先到请求的解析结果，
+// 不再重复查询上游。键为查询报文、客户端 IP 与上游组覆盖的哈希，
+// 结果以序列化后的响应报文广播；解析失败时广播错误，等待者按与解析者相同的错误处理方式响应。
+#[derive(Default)]
+pub struct PendingRequests {
+    requests: DashMap<u64, broadcast::Sender<SharedResult>>,
+}
+
+// 广播给等待者的解析结果
+pub type SharedResult = std::result::Result<Bytes, Arc<ServerError>>;
+
+// 加入进行中请求的结果
+pub enum PendingRequest<'a> {
+    // 当前请求负责解析并广播结果
+    Leader(PendingLeader<'a>),
+    // 已有相同请求在解析，等待其结果
+    Waiter(broadcast::Receiver<SharedResult>),
+}
+
+// 负责解析的请求，未广播结果就被丢弃（请求被取消）时向等待者广播错误
+pub struct PendingLeader<'a> {
+    requests: &'a PendingRequests,
+    key: u64,
+    sender: Option<broadcast::Sender<SharedResult>>,
+}
+
+impl PendingRequests {
+    // 加入相同的进行中请求，没有时由当前请求负责解析
+    pub fn join(&self, query_wire: &[u8], client_ip: Option<IpAddr>, upstream_override: Option<&str>) -> PendingRequest<'_> {
+        let mut hasher = DefaultHasher::new();
+        (query_wire, client_ip, upstream_override).hash(&mut hasher);
+        let key = hasher.finish();
+        
+        match self.requests.entry(key) {
+            dashmap::mapref::entry::Entry::Occupied(entry) => {
+                METRICS.request_deduplication_hits_total().inc();
+                PendingRequest::Waiter(entry.get().subscribe())
+            },
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                let (sender, _) = broadcast::channel(1);
+                entry.insert(sender.clone());
+                PendingRequest::Leader(PendingLeader { requests: self, key, sender: Some(sender) })
+            },
+        }
+    }
+    
+    // 当前进行中的请求数量
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+    
+    // 是否没有进行中的请求
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+impl PendingLeader<'_> {
+    // 向等待者广播解析结果
+    pub fn complete(mut self, result: std::result::Result<&Message, &ServerError>) {
+        let Some(sender) = self.sender.take() else {
+            return;
+        };
+        // 先移除再广播，之后到达的相同请求重新解析；移除后不会再有新的等待者
+        self.requests.requests.remove(&self.key);
+        if sender.receiver_count() == 0 {
+            return;
+        }
+        let shared = match result {
+            Ok(response) => response.to_vec()
+                .map(Bytes::from)
+                .map_err(|e| Arc::new(ServerError::DnsProto(e))),
+            Err(e) => Err(Arc::new(shared_resolution_error(e))),
+        };
+        let _ = sender.send(shared);
+    }
+}
+
+impl Drop for PendingLeader<'_> {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            self.requests.requests.remove(&self.key);
+            let _ = sender.send(Err(Arc::new(ServerError::UpstreamUnavailable(
+                "Shared resolution of identical request was cancelled".to_string()
+            ))));
+        }
+    }
+}
+
+// 复制解析者的错误供等待者使用，保留错误类别以映射到相同的 HTTP 状态码
+fn shared_resolution_error(error: &ServerError) -> ServerError {
+    match error {
+        ServerError::RateLimited(reason) => ServerError::RateLimited(reason.clone()),
+        ServerError::InvalidQuery(reason) => ServerError::InvalidQuery(reason.clone()),
+        ServerError::UpstreamUnavailable(reason) => ServerError::UpstreamUnavailable(reason.clone()),
+        ServerError::UpstreamValidation(reason) => ServerError::UpstreamValidation(reason.clone()),
+        ServerError::UpstreamParse(reason) => ServerError::UpstreamParse(reason.clone()),
+        // 其余上游错误均属于不可达类别
+        error if error.upstream_failure_kind().is_some() => ServerError::Upstream(error.to_string()),
+        error => ServerError::Other(error.to_string()),
+    }
+}
+
+// Oblivious DoH 路由状态
+#[derive(Clone)]
+struct OdohState {
+    server: ServerState,
+    target: Arc<OdohTarget>,
+    // 查询路径（用于指标标签）
+    path: Arc<str>,
 }
 
-// DNS-over-HTTPS JSON 请求参数
+// DNS-over-HTTPS JSON 请求参数（兼容 Google/Cloudflare JSON API）
 #[derive(Debug, Deserialize, Serialize, utoipa::ToSchema)]
 pub struct DnsJsonRequest {
     // 查询名称
     pub name: String,
-    // 查询类型
-    #[serde(default = "default_record_type")]
-    pub type_value: u16,
+    // 查询类型，支持名称（如 "AAAA"）或数值（如 "28"）
+    #[serde(rename = "type", default = "default_record_type")]
+    pub record_type: String,
     // 查询类
     #[serde(default = "default_dns_class")]
     pub dns_class: Option<u16>,
-    // 是否启用 DNSSEC
-    #[serde(default)]
+    // 是否设置 DNSSEC OK (DO) 位
+    #[serde(rename = "do", alias = "dnssec", default, deserialize_with = "deserialize_flag")]
     pub dnssec: bool,
     // 是否启用检查禁用
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_flag")]
     pub cd: bool,
+    // 期望的响应内容类型（application/dns-json 或 application/dns-message）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ct: Option<String>,
 }
 
 // DNS-over-HTTPS GET 请求参数（RFC 8484）
@@ -101,31 +411,44 @@ pub struct DnsMsgGetRequest {
     pub dns: String,
 }
 
+// /dns-query GET 请求的格式分派参数
+#[derive(Debug, Default, Deserialize)]
+struct DohGetDispatchParams {
+    dns: Option<String>,
+    name: Option<String>,
+    ct: Option<String>,
+}
+
 // DNS-over-HTTPS JSON 响应格式
 #[derive(Debug, Serialize, Clone, utoipa::ToSchema)]
 pub struct DnsJsonResponse {
     // 响应状态代码
+    #[serde(rename = "Status")]
     pub status: u16,
     // 是否被截断
-    #[serde(default)]
+    #[serde(rename = "TC")]
     pub tc: bool,
-    // 是否递归可用
-    #[serde(default)]
-    pub rd: bool,
     // 是否递归期望
-    #[serde(default)]
+    #[serde(rename = "RD")]
+    pub rd: bool,
+    // 是否递归可用
+    #[serde(rename = "RA")]
     pub ra: bool,
     // 是否 AD 标志（DNSSEC 验证）
-    #[serde(default)]
+    #[serde(rename = "AD")]
     pub ad: bool,
     // 是否检查禁用
-    #[serde(default)]
+    #[serde(rename = "CD")]
     pub cd: bool,
     // 查询列表
+    #[serde(rename = "Question")]
     pub question: Vec<DnsJsonQuestion>,
     // 应答记录列表
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(rename = "Answer", default, skip_serializing_if = "Vec::is_empty")]
     pub answer: Vec<DnsJsonAnswer>,
+    // 权威记录列表
+    #[serde(rename = "Authority", default, skip_serializing_if = "Vec::is_empty")]
+    pub authority: Vec<DnsJsonAnswer>,
 }
 
 // DNS-over-HTTPS JSON 查询
@@ -134,7 +457,8 @@ pub struct DnsJsonQuestion {
     // 查询名称
     pub name: String,
     // 查询类型
-    pub type_value: u16,
+    #[serde(rename = "type")]
+    pub record_type: u16,
 }
 
 // DNS-over-HTTPS JSON 应答记录
@@ -143,475 +467,910 @@ pub struct DnsJsonAnswer {
     // 记录名称
     pub name: String,
     // 记录类型
-    pub type_value: u16,
-    // 记录类
-    pub class: u16,
+    #[serde(rename = "type")]
+    pub record_type: u16,
     // 生存时间（TTL）
+    #[serde(rename = "TTL")]
     pub ttl: u32,
     // 记录数据
     pub data: String,
 }
 
 // 创建 DoH 路由
+// 每个配置的路径都提供 RFC 8484 wireformat 与 application/dns-json 查询，未配置的路径由 Axum 返回 404
 pub fn doh_routes(state: ServerState) -> AxumRouter {
-    AxumRouter::new()
-        // JSON API 路由（兼容性）
-        .route(DOH_JSON_API_PATH, get(handle_dns_json_query))
-        // RFC 8484 标准路由
-        .route(DOH_STANDARD_PATH, get(handle_dns_wire_get))
-        .route(DOH_STANDARD_PATH, post(handle_dns_wire_post))
-        // 添加状态
-        .with_state(state)
+    let mut router = AxumRouter::new();
+    for path in &state.config.http.doh_paths {
+        router = router.route(path, doh_method_router());
+        // 启用令牌认证时，同时接受嵌入在路径中的令牌 (/dns-query/<token>)
+        if state.config.http.auth.enabled {
+            let token_path = format!("{}/{{token}}", path.trim_end_matches('/'));
+            router = router.route(&token_path, doh_method_router());
+        }
+    }
+    // 启用 DNS over WebSocket 时添加 /dns-ws 端点，令牌同样可嵌入路径 (/dns-ws/<token>)
+    if state.config.http.websocket_enabled {
+        router = router.route(DNS_WEBSOCKET_PATH, get(handle_dns_websocket));
+        if state.config.http.auth.enabled {
+            router = router.route(&format!("{}/{{token}}", DNS_WEBSOCKET_PATH), get(handle_dns_websocket));
+        }
+    }
+    // 请求体解压位于大小检查内侧：Content-Length 检查压缩后的大小，处理器读取时限制解压后的大小
+    let router = match state.config.http.request_decompression {
+        true => apply_request_decompression(router),
+        false => router,
+    };
+    let router = apply_request_body_limit(router, state.config.http.max_request_body_size);
+    let router = apply_request_timeout(router, state.config.request_timeout());
+    // 添加状态
+    router.with_state(state)
 }
 
-// 处理 DNS JSON 查询 (GET 请求，application/dns-json 兼容格式)
-#[axum::debug_handler]
-async fn handle_dns_json_query(
-    State(state): State<ServerState>,
-    Query(params): Query<DnsJsonRequest>,
-    req: Request<axum::body::Body>,
-) -> impl IntoResponse {
-    // 提取客户端 IP
-    let client_ip = get_client_ip_from_request(&req);
-    
-    // 记录开始时间
-    let start = Instant::now();
-    
-    // 相关指标 - 预先提取为常量，避免重复创建
-    let path = DOH_JSON_API_PATH;
-    let format = DOH_FORMAT_JSON;
-    let http_version = format!("{:?}", req.version());
-    let method = HTTP_METHOD_GET;
-    
-    debug!(name = %params.name, type_value = params.type_value, client_ip = ?client_ip, "DNS JSON query received");
-    
-    // 创建 DNS 查询消息
-    let query_message = match create_dns_message_from_json_request(&params) {
-        Ok(msg) => msg,
-        Err(e) => {
-            // 记录请求错误
+// DoH 端点的方法路由；HEAD 与 OPTIONS 不进入 DNS 处理流程，其他方法由 axum 返回带 Allow 头的 405
+fn doh_method_router() -> MethodRouter<ServerState> {
+    get(handle_dns_get)
+        .head(handle_dns_head)
+        .post(handle_dns_post)
+        .options(handle_dns_options)
+}
+
+// 处理 DoH HEAD 请求（健康检查），返回与 GET 相同的响应头和空响应体
+async fn handle_dns_head() -> Response {
+    (
+        [(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE_DNS_MESSAGE))],
+    ).into_response()
+}
+
+// 处理 DoH OPTIONS 请求，返回端点支持的方法；启用 CORS 时由 CORS 中间件补充跨域响应头
+async fn handle_dns_options() -> Response {
+    (
+        StatusCode::NO_CONTENT,
+        [(header::ALLOW, HeaderValue::from_static(DOH_ALLOWED_METHODS))],
+    ).into_response()
+}
+
+// 创建 Oblivious DoH 路由（公钥配置与加密查询）
+pub fn odoh_routes(state: ServerState, target: Arc<OdohTarget>) -> AxumRouter {
+    let path: Arc<str> = Arc::from(state.config.http.odoh.path.as_str());
+    let router = AxumRouter::new()
+        .route(ODOH_CONFIGS_PATH, get(handle_odoh_configs))
+        .route(&path, post(handle_odoh_query));
+    let router = apply_request_body_limit(router, state.config.http.max_request_body_size);
+    apply_request_timeout(router, state.config.request_timeout())
+        .with_state(OdohState { server: state, target, path })
+}
+
+// 根据 Content-Length 提前拒绝超过大小上限的请求，未声明长度的请求体由处理器在读取时限制
+fn apply_request_body_limit<S: Clone + Send + Sync + 'static>(router: AxumRouter<S>, limit: usize) -> AxumRouter<S> {
+    router.layer(middleware::from_fn(move |req: Request<Body>, next: Next| async move {
+        let declared_length = req.headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        
+        if declared_length.is_some_and(|length| length > limit as u64) {
             info!(
-                name = %params.name,
-                type_value = params.type_value,
-                client_ip = ?client_ip,
-                error = %e,
-                "DNS-over-HTTPS request parameter error"
+                client_ip = ?get_client_ip_from_request(&req),
+                size = ?declared_length,
+                max_size = limit,
+                "Rejected request with body larger than max_request_body_size"
             );
-            
-            // 记录错误状态码 - 提前计算一次，重复使用
-            let status = StatusCode::BAD_REQUEST;
-            let status_str = status.as_u16().to_string();
-            let error_body = e.to_string();
-            let error_body_len = error_body.len() as f64;
-            
-            // 记录指标
-            {
-                METRICS.http_requests_total()
-                    .with_label_values(&[method, path, &status_str, format, &http_version])
-                    .inc();
-                
-                // 记录请求持续时间
-                let duration = start.elapsed().as_secs_f64();
-                METRICS.http_request_duration_seconds()
-                    .with_label_values(&[method, path, format])
-                    .observe(duration);
-                
-                // 记录DNS查询错误
-                METRICS.dns_queries_total()
-                    .with_label_values(&[&params.type_value.to_string(), DNS_EVENT_PARAMETER_ERROR])
+            METRICS.http_request_too_large_total()
+                .with_label_values(&[req.method().as_str()])
+                .inc();
+            return (StatusCode::PAYLOAD_TOO_LARGE, ERROR_REQUEST_TOO_LARGE).into_response();
+        }
+        
+        next.run(req).await
+    }))
+}
+
+// 请求处理（包括上游查询）超过时限时返回 504；WebSocket 会话在升级后独立运行，不受该时限约束
+fn apply_request_timeout<S: Clone + Send + Sync + 'static>(router: AxumRouter<S>, timeout: Duration) -> AxumRouter<S> {
+    router.layer(middleware::from_fn(move |req: Request<Body>, next: Next| async move {
+        let method = req.method().clone();
+        let client_ip = get_client_ip_from_request(&req);
+        match tokio::time::timeout(timeout, next.run(req)).await {
+            Ok(response) => response,
+            Err(_) => {
+                warn!(
+                    client_ip = ?client_ip,
+                    method = %method,
+                    timeout_secs = timeout.as_secs(),
+                    "Request exceeded request_timeout, responding with 504"
+                );
+                METRICS.http_request_timeouts_total()
+                    .with_label_values(&[method.as_str()])
                     .inc();
-                
-                METRICS.http_response_bytes()
-                    .with_label_values(&[method, path])
-                    .observe(error_body_len);
-            }
-            
-            // 返回错误响应
-            return (status, error_body).into_response();
+                (StatusCode::GATEWAY_TIMEOUT, ERROR_REQUEST_TIMEOUT).into_response()
+            },
         }
-    };
-    
-    // 记录DNS查询类型 - 提前计算一次，避免重复计算
-    let query_type = if let Some(q) = query_message.queries().first() {
-        format!("{:?}", q.query_type())
-    } else {
-        DNS_QUERY_TYPE_UNKNOWN.to_string()
-    };
+    }))
+}
+
+// 受信任客户端通过请求头指定的上游组
+enum UpstreamOverride {
+    // 未指定或客户端不受信任，按路由规则选择上游
+    None,
+    // 跳过路由规则与缓存，直接使用指定的上游组
+    Group(String),
+    // 指定的上游组不存在
+    UnknownGroup,
+}
+
+impl UpstreamOverride {
+    // 从请求头解析上游组覆盖，仅对 trusted_override_ips 中的客户端生效；
+    // client_ip 取自 get_client_ip_from_request，转发头只有来自受信任代理时才会被采信
+    fn from_headers(config: &ServerConfig, headers: &header::HeaderMap, client_ip: IpAddr) -> Self {
+        if config.http.trusted_override_ips.is_empty() {
+            return Self::None;
+        }
+        let Some(group) = headers.get(config.http.upstream_override_header.as_str())
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty()) else {
+            return Self::None;
+        };
+        
+        if !config.is_trusted_override_ip(client_ip) {
+            debug!(client_ip = ?client_ip, group, "Ignoring upstream override from untrusted client");
+            METRICS.upstream_override_total().with_label_values(&[UPSTREAM_OVERRIDE_IGNORED]).inc();
+            return Self::None;
+        }
+        
+        let routing = &config.dns.routing;
+        if !routing.enabled || !routing.upstream_groups.iter().any(|g| g.name == group) {
+            info!(client_ip = ?client_ip, group, "Rejected upstream override for unknown group");
+            METRICS.upstream_override_total().with_label_values(&[UPSTREAM_OVERRIDE_UNKNOWN_GROUP]).inc();
+            return Self::UnknownGroup;
+        }
+        
+        info!(client_ip = ?client_ip, group, "Honoring upstream override");
+        METRICS.upstream_override_total().with_label_values(&[UPSTREAM_OVERRIDE_HONORED]).inc();
+        Self::Group(group.to_string())
+    }
     
+    // 需要直接使用的上游组
+    fn group(&self) -> Option<&str> {
+        match self {
+            Self::Group(group) => Some(group),
+            _ => None,
+        }
+    }
+}
+
+// 记录上游组不存在的指标并构建 400 响应
+fn unknown_upstream_group_response(
+    method: &str,
+    path: &str,
+    format: &str,
+    http_version: &str,
+    start: Instant,
+) -> Response {
+    let status = StatusCode::BAD_REQUEST.as_u16().to_string();
     {
-        METRICS.dns_queries_total()
-            .with_label_values(&[&query_type, DNS_EVENT_RECEIVED])
+        METRICS.http_requests_total()
+            .with_label_values(&[method, path, &status, format, http_version])
             .inc();
         
-        METRICS.dns_query_type_total()
-            .with_label_values(&[&query_type])
-            .inc();
+        METRICS.http_request_duration_seconds()
+            .with_label_values(&[method, path, format])
+            .observe(start.elapsed().as_secs_f64());
+        
+        METRICS.http_response_bytes()
+            .with_label_values(&[method, path])
+            .observe(ERROR_UNKNOWN_UPSTREAM_GROUP.len() as f64);
     }
     
-    // 发送/接收 DNS 查询响应
-    let (response_message, is_cached) = match process_query(
-        state.upstream.as_ref(),
-        state.router.as_ref(),
-        state.cache.as_ref(),
-        &query_message,
-        client_ip,
-    ).await {
-        Ok((msg, cached)) => (msg, cached),
-        Err(e) => {
-            // 记录处理错误
-            info!(
-                name = %params.name,
-                type_value = params.type_value,
-                client_ip = ?client_ip,
-                error = %e,
-                "DNS-over-HTTPS query processing failed"
-            );
-            
-            // 记录错误状态码 - 提前计算一次，重复使用
-            let status = StatusCode::INTERNAL_SERVER_ERROR;
-            let status_str = status.as_u16().to_string();
-            let error_body = e.to_string();
-            let error_body_len = error_body.len() as f64;
-            
-            // 记录指标
-            {
-                METRICS.http_requests_total()
-                    .with_label_values(&[method, path, &status_str, format, &http_version])
-                    .inc();
-                
-                // 记录请求持续时间
-                let duration = start.elapsed().as_secs_f64();
-                METRICS.http_request_duration_seconds()
-                    .with_label_values(&[method, path, format])
-                    .observe(duration);
-                
-                // 记录DNS查询错误
-                METRICS.dns_queries_total()
-                    .with_label_values(&[&query_type, DNS_EVENT_PROCESSING_FAILED])
-                    .inc();
-                
-                METRICS.http_response_bytes()
-                    .with_label_values(&[method, path])
-                    .observe(error_body_len);
-            }
-            
-            // 返回错误响应
-            return (status, error_body).into_response();
+    (StatusCode::BAD_REQUEST, ERROR_UNKNOWN_UPSTREAM_GROUP).into_response()
+}
+
+// 记录 DoH 响应的 HTTP 请求数、处理时长与响应大小指标
+fn observe_http_response(
+    method: &str,
+    path: &str,
+    format: &str,
+    http_version: &str,
+    start: Instant,
+    status: StatusCode,
+    size: usize,
+) {
+    METRICS.http_requests_total()
+        .with_label_values(&[method, path, status.as_str(), format, http_version])
+        .inc();
+    
+    METRICS.http_request_duration_seconds()
+        .with_label_values(&[method, path, format])
+        .observe(start.elapsed().as_secs_f64());
+    
+    METRICS.http_response_bytes()
+        .with_label_values(&[method, path])
+        .observe(size as f64);
+}
+
+// 解析失败且 error_mode 要求返回 HTTP 错误时记录指标并构建错误响应；返回 None 时按 SERVFAIL 报文响应
+fn resolution_error_response(
+    config: &ServerConfig,
+    resolution: &Resolution,
+    method: &str,
+    path: &str,
+    format: &str,
+    http_version: &str,
+    start: Instant,
+) -> Option<Response> {
+    let error = resolution.error.as_ref().filter(|e| !responds_servfail_on_failure(config, e))?;
+    if let ServerError::RateLimited(_) = error {
+        // 由速率限制中间件替换为带 Retry-After 的 429 响应
+        return Some(StatusCode::TOO_MANY_REQUESTS.into_response());
+    }
+    let (status, error_body) = processing_error_response(error);
+    observe_http_response(method, path, format, http_version, start, status, error_body.len());
+    Some((status, error_body).into_response())
+}
+
+// GET 请求 dns 参数的校验错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DnsParamError {
+    // 包含 Base64 填充字符 '='（RFC 8484 要求省略填充）
+    Padding,
+    // 包含空白或 Base64url 字母表以外的字符
+    InvalidCharacter,
+    // 长度或尾部比特不构成有效的 Base64url 编码
+    InvalidBase64,
+    // 解码后短于 DNS 消息头
+    TooShort,
+    // 无法解析为 DNS 消息
+    Malformed,
+    // 问题部分不是恰好一个问题
+    QuestionCount,
+}
+
+impl DnsParamError {
+    // 返回给客户端的机器可读错误码
+    fn code(self) -> &'static str {
+        match self {
+            Self::Padding => "dns_param_padding",
+            Self::InvalidCharacter => "dns_param_invalid_character",
+            Self::InvalidBase64 => "dns_param_invalid_base64",
+            Self::TooShort => "dns_message_too_short",
+            Self::Malformed => "dns_message_malformed",
+            Self::QuestionCount => "dns_message_question_count",
         }
-    };
+    }
     
-    // 转换为 JSON 响应
-    let json_response = match dns_message_to_json_response(&response_message) {
-        Ok(resp) => resp,
-        Err(e) => {
-            // 记录响应转换错误
-            info!(
-                name = %params.name,
-                type_value = params.type_value,
-                client_ip = ?client_ip,
-                error = %e,
-                "DNS-over-HTTPS response conversion failed"
-            );
-            
-            // 记录错误状态码 - 提前计算一次，重复使用
-            let status = StatusCode::INTERNAL_SERVER_ERROR;
-            let status_str = status.as_u16().to_string();
-            let error_body = e.to_string();
-            let error_body_len = error_body.len() as f64;
-            
-            // 记录指标
-            {
-                METRICS.http_requests_total()
-                    .with_label_values(&[method, path, &status_str, format, &http_version])
-                    .inc();
-                
-                // 记录请求持续时间
-                let duration = start.elapsed().as_secs_f64();
-                METRICS.http_request_duration_seconds()
-                    .with_label_values(&[method, path, format])
-                    .observe(duration);
-                
-                METRICS.http_response_bytes()
-                    .with_label_values(&[method, path])
-                    .observe(error_body_len);
-            }
-            
-            // 返回错误响应
-            return (status, error_body).into_response();
+    // 对应的 DNS 查询事件指标标签
+    fn event(self) -> &'static str {
+        match self {
+            Self::Padding | Self::InvalidCharacter | Self::InvalidBase64 => DNS_EVENT_BASE64_DECODE_ERROR,
+            Self::TooShort | Self::Malformed | Self::QuestionCount => DNS_EVENT_PARSE_ERROR,
         }
-    };
-    
-    // 计算持续时间
-    let duration = start.elapsed();
-    
-    // 记录请求完成的详细日志
-    let answer_count = json_response.answer.len();
-    let rcode = response_message.response_code();
-    let query_time_ms = duration.as_millis();
-    
-    info!(
-        name = %params.name,
-        type_value = params.type_value,
-        client_ip = ?client_ip,
-        response_code = ?rcode,
-        answer_count = answer_count,
-        dnssec_validated = response_message.authentic_data(),
-        query_time_ms = query_time_ms,
-        is_cached = is_cached,
-        "DNS-over-HTTPS request completed"
-    );
+    }
+}
+
+// 严格解码 GET 请求的 dns 参数：仅接受无填充的 Base64url 字母表
+fn decode_dns_param(dns: &str) -> std::result::Result<Vec<u8>, DnsParamError> {
+    if dns.contains('=') {
+        return Err(DnsParamError::Padding);
+    }
+    if !dns.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+        return Err(DnsParamError::InvalidCharacter);
+    }
+    BASE64_ENGINE.decode(dns).map_err(|_| DnsParamError::InvalidBase64)
+}
+
+// 解析 dns 参数中的 DNS 消息，要求恰好包含一个问题
+fn parse_dns_param_message(data: &[u8]) -> std::result::Result<Message, DnsParamError> {
+    if data.len() < DNS_MESSAGE_HEADER_SIZE {
+        return Err(DnsParamError::TooShort);
+    }
+    let message = Message::from_vec(data).map_err(|_| DnsParamError::Malformed)?;
+    if message.queries().len() != 1 {
+        return Err(DnsParamError::QuestionCount);
+    }
+    Ok(message)
+}
+
+// 记录 dns 参数校验失败的指标并构建 400 响应
+fn dns_param_error_response(
+    error: DnsParamError,
+    path: &str,
+    format: &str,
+    http_version: &str,
+    start: Instant,
+) -> Response {
+    let status = StatusCode::BAD_REQUEST.as_u16().to_string();
+    let error_body = error.code();
+    {
+        METRICS.http_requests_total()
+            .with_label_values(&[HTTP_METHOD_GET, path, &status, format, http_version])
+            .inc();
+        
+        METRICS.http_request_duration_seconds()
+            .with_label_values(&[HTTP_METHOD_GET, path, format])
+            .observe(start.elapsed().as_secs_f64());
+        
+        METRICS.dns_queries_total()
+            .with_label_values(&[DNS_QUERY_TYPE_UNKNOWN, error.event()])
+            .inc();
+        
+        METRICS.http_response_bytes()
+            .with_label_values(&[HTTP_METHOD_GET, path])
+            .observe(error_body.len() as f64);
+    }
     
-    // 只在调试级别时记录详细记录信息，减少运行时开销
-    if !json_response.answer.is_empty() && tracing::enabled!(tracing::Level::DEBUG) {
-        // 使用迭代器和预分配容量优化字符串收集
-        let mut record_details = Vec::with_capacity(json_response.answer.len());
-        for ans in &json_response.answer {
-            record_details.push(format!("{}({}): {}", ans.name, RecordType::from(ans.type_value), ans.data));
+    (StatusCode::BAD_REQUEST, error_body).into_response()
+}
+
+// 读取请求体失败的原因
+enum BodyReadError {
+    // 超过请求体大小上限
+    TooLarge,
+    // 读取失败
+    Read(axum::Error),
+}
+
+// 读取请求体，累计长度超过上限时立即停止读取
+async fn read_request_body(mut body: Body, limit: usize) -> std::result::Result<Bytes, BodyReadError> {
+    let mut buf = BytesMut::new();
+    while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = frame.map_err(BodyReadError::Read)?;
+        if let Ok(data) = frame.into_data() {
+            if buf.len() + data.len() > limit {
+                return Err(BodyReadError::TooLarge);
+            }
+            buf.extend_from_slice(&data);
         }
-            
-        debug!(
-            name = %params.name,
-            client_ip = ?client_ip,
-            records = ?record_details,
-            "DNS-over-HTTPS response record details"
-        );
     }
-    
-    // 记录成功状态码和持续时间
-    let status = StatusCode::OK.as_u16().to_string();
+    Ok(buf.freeze())
+}
+
+// 记录请求过大的指标并构建 413 响应
+fn request_too_large_response(
+    method: &str,
+    path: &str,
+    format: &str,
+    http_version: &str,
+    start: Instant,
+) -> Response {
+    let status = StatusCode::PAYLOAD_TOO_LARGE.as_u16().to_string();
     {
+        METRICS.http_request_too_large_total()
+            .with_label_values(&[method])
+            .inc();
+        
         METRICS.http_requests_total()
-            .with_label_values(&[method, path, &status, format, &http_version])
+            .with_label_values(&[method, path, &status, format, http_version])
             .inc();
         
-        // 记录请求持续时间
         METRICS.http_request_duration_seconds()
             .with_label_values(&[method, path, format])
-            .observe(duration.as_secs_f64());
+            .observe(start.elapsed().as_secs_f64());
         
-        // 记录DNS响应
-        METRICS.dns_responses_total()
-            .with_label_values(&[&format!("{:?}", rcode)])
-            .inc();
+        METRICS.http_response_bytes()
+            .with_label_values(&[method, path])
+            .observe(ERROR_REQUEST_TOO_LARGE.len() as f64);
     }
     
-    // 准备JSON响应
-    let json_response_body = Json(json_response.clone());
+    (StatusCode::PAYLOAD_TOO_LARGE, ERROR_REQUEST_TOO_LARGE).into_response()
+}
+
+// 处理 DoH GET 请求，根据参数和 Accept 头分派到 wireformat 或 JSON 处理
+#[axum::debug_handler]
+async fn handle_dns_get(
+    State(state): State<ServerState>,
+    req: Request<axum::body::Body>,
+) -> Response {
+    let dispatch = Query::<DohGetDispatchParams>::try_from_uri(req.uri())
+        .map(|Query(params)| params)
+        .unwrap_or_default();
+    
+    if is_json_get_request(&dispatch, req.headers()) {
+        // 显式的 ct 参数优先于 Accept 头，JSON API 默认返回 application/dns-json
+        let encoding = dispatch.ct.as_deref()
+            .and_then(ResponseEncoding::from_media_type)
+            .or_else(|| negotiate_response_encoding(req.headers(), ResponseEncoding::Json));
+        let Some(encoding) = encoding else {
+            return not_acceptable_response(&req, DOH_FORMAT_JSON);
+        };
+        return match Query::<DnsJsonRequest>::try_from_uri(req.uri()) {
+            Ok(Query(params)) => {
+                let client_ip = get_client_ip_from_request(&req);
+                let upstream_override = UpstreamOverride::from_headers(&state.config, req.headers(), client_ip);
+                let http_version = format!("{:?}", req.version());
+                let path = request_path_label(&req);
+                respond_dns_json_query(&state, params, encoding, client_ip, upstream_override, http_version, HTTP_METHOD_GET, &path).await
+            },
+            Err(rejection) => rejection.into_response(),
+        };
+    }
     
-    // 提前计算响应大小估计，避免后续借用被移动的值
-    let response_size_estimate = serde_json::to_string(&json_response).map(|s| s.len()).unwrap_or(0);
+    // RFC 8484 请求默认返回 application/dns-message
+    let Some(encoding) = negotiate_response_encoding(req.headers(), ResponseEncoding::Wire) else {
+        return not_acceptable_response(&req, DOH_FORMAT_WIRE);
+    };
+    match Query::<DnsMsgGetRequest>::try_from_uri(req.uri()) {
+        Ok(Query(params)) => handle_dns_wire_get(state, params, encoding, req).await,
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+// 响应编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResponseEncoding {
+    // application/dns-message
+    Wire,
+    // application/dns-json
+    Json,
+}
+
+impl ResponseEncoding {
+    // 媒体类型对应的编码（忽略参数与大小写），无法生成的类型返回 None
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        let media_type = media_type.split(';').next().unwrap_or_default().trim();
+        if media_type.eq_ignore_ascii_case(CONTENT_TYPE_DNS_MESSAGE)
+            || media_type.eq_ignore_ascii_case(CONTENT_TYPE_DNS_UDPWIREFORMAT)
+        {
+            Some(Self::Wire)
+        } else if media_type.eq_ignore_ascii_case(CONTENT_TYPE_DNS_JSON)
+            || media_type.eq_ignore_ascii_case(CONTENT_TYPE_JSON)
+        {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
     
-    // 返回 JSON 响应
-    let response = (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, CONTENT_TYPE_DNS_JSON)],
-        json_response_body,
-    ).into_response();
+    fn other(self) -> Self {
+        match self {
+            Self::Wire => Self::Json,
+            Self::Json => Self::Wire,
+        }
+    }
+    
+    // 指标中的格式标签
+    fn format(self) -> &'static str {
+        match self {
+            Self::Wire => DOH_FORMAT_WIRE,
+            Self::Json => DOH_FORMAT_JSON,
+        }
+    }
+}
+
+// 根据 Accept 头选择响应编码
+//
+// 每种编码的权重取最具体的匹配（具体类型 > application/* > */*）的 q 值，选择权重最高的编码，
+// 权重相同时使用默认编码。未携带 Accept 头时使用默认编码，两种编码都不可接受时返回 None。
+fn negotiate_response_encoding(headers: &header::HeaderMap, default: ResponseEncoding) -> Option<ResponseEncoding> {
+    let ranges: Vec<(&str, f32)> = headers.get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let media_type = params.next()?.trim();
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!media_type.is_empty()).then_some((media_type, quality))
+        })
+        .collect();
+    if ranges.is_empty() {
+        return Some(default);
+    }
+    
+    let quality_of = |encoding: ResponseEncoding| {
+        let matching = |predicate: &dyn Fn(&str) -> bool| ranges.iter()
+            .filter(|(media_type, _)| predicate(media_type))
+            .map(|(_, quality)| *quality)
+            .reduce(f32::max);
+        matching(&|media_type| ResponseEncoding::from_media_type(media_type) == Some(encoding))
+            .or_else(|| matching(&|media_type| media_type.eq_ignore_ascii_case("application/*")))
+            .or_else(|| matching(&|media_type| media_type == "*/*"))
+            .unwrap_or(0.0)
+    };
     
-    // 记录响应大小
+    let (default_quality, other_quality) = (quality_of(default), quality_of(default.other()));
+    if other_quality > default_quality {
+        Some(default.other())
+    } else {
+        (default_quality > 0.0).then_some(default)
+    }
+}
+
+// 记录指标并构建 406 响应
+fn not_acceptable_response<T>(req: &Request<T>, format: &str) -> Response {
+    let path = request_path_label(req);
+    let http_version = format!("{:?}", req.version());
+    let status = StatusCode::NOT_ACCEPTABLE.as_u16().to_string();
     {
+        METRICS.http_requests_total()
+            .with_label_values(&[HTTP_METHOD_GET, &path, &status, format, &http_version])
+            .inc();
+        
         METRICS.http_response_bytes()
-            .with_label_values(&[method, path])
-            .observe(response_size_estimate as f64);
+            .with_label_values(&[HTTP_METHOD_GET, &path])
+            .observe(ERROR_NOT_ACCEPTABLE.len() as f64);
     }
     
-    response
+    debug!(
+        accept = ?req.headers().get(header::ACCEPT),
+        "No acceptable DNS response encoding for GET request"
+    );
+    (StatusCode::NOT_ACCEPTABLE, ERROR_NOT_ACCEPTABLE).into_response()
 }
 
-// 处理 DNS GET 请求（RFC 8484）
+// 按编码序列化响应，返回内容类型与响应体
+fn serialize_response(
+    config: &ServerConfig,
+    encoding: ResponseEncoding,
+    query_message: &Message,
+    response_message: &mut Message,
+) -> Result<(&'static str, Vec<u8>)> {
+    match encoding {
+        ResponseEncoding::Wire => {
+            pad_wire_response(config, query_message, response_message);
+            let bytes = response_message.to_vec()?;
+            observe_response_size(config, encoding.format(), query_message, bytes.len());
+            Ok((CONTENT_TYPE_DNS_MESSAGE, bytes))
+        },
+        ResponseEncoding::Json => {
+            let response = dns_message_to_json_response(response_message)?;
+            let bytes = serde_json::to_vec(&response)
+                .map_err(|e| ServerError::Http(format!("{}: {}", ERROR_SERIALIZE_RESPONSE, e)))?;
+            Ok((CONTENT_TYPE_DNS_JSON, bytes))
+        },
+    }
+}
+
+// 判断 GET 请求是否应按 JSON API 处理
+fn is_json_get_request(params: &DohGetDispatchParams, headers: &header::HeaderMap) -> bool {
+    // 携带 dns 参数的请求始终按 RFC 8484 wireformat 处理
+    if params.dns.is_some() {
+        return false;
+    }
+    
+    if params.name.is_some() {
+        return true;
+    }
+    
+    if params.ct.as_deref().is_some_and(|ct| ct.starts_with(CONTENT_TYPE_DNS_JSON)) {
+        return true;
+    }
+    
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains(CONTENT_TYPE_DNS_JSON))
+}
+
+// 处理 DoH POST 请求，根据 Content-Type 分派到 wireformat 或 JSON 处理
 #[axum::debug_handler]
-async fn handle_dns_wire_get(
+async fn handle_dns_post(
     State(state): State<ServerState>,
-    Query(params): Query<DnsMsgGetRequest>,
     req: Request<axum::body::Body>,
-) -> impl IntoResponse {
+) -> Response {
+    let is_json_content_type = req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|s| s.starts_with(CONTENT_TYPE_DNS_JSON));
+    
+    if is_json_content_type {
+        handle_dns_json_post(state, req).await
+    } else {
+        handle_dns_wire_post(state, req).await
+    }
+}
+
+// 处理 JSON 格式的 POST 请求
+async fn handle_dns_json_post(
+    state: ServerState,
+    req: Request<axum::body::Body>,
+) -> Response {
     // 提取客户端 IP
     let client_ip = get_client_ip_from_request(&req);
     
     // 记录开始时间
     let start = Instant::now();
     
-    // 记录请求指标
-    let path = DOH_STANDARD_PATH;
-    let format = DOH_FORMAT_WIRE;
+    let path = request_path_label(&req);
+    let path = path.as_str();
+    let format = DOH_FORMAT_JSON;
     let http_version = format!("{:?}", req.version());
-
-    debug!(client_ip = ?client_ip, "DNS-over-HTTPS GET request received");
     
-    // 解码请求参数中的 DNS 消息（Base64url 编码）
-    let query_message = match BASE64_ENGINE.decode(&params.dns) {
-        Ok(data) => {
+    debug!(client_ip = ?client_ip, "DNS JSON POST request received");
+    
+    let upstream_override = UpstreamOverride::from_headers(&state.config, req.headers(), client_ip);
+    
+    // 读取并解析请求体
+    let params = match read_request_body(req.into_body(), state.config.http.max_request_body_size).await {
+        Ok(bytes) => {
             // 记录请求大小
             {
                 METRICS.http_request_bytes()
-                    .with_label_values(&[HTTP_METHOD_GET, path])
-                    .observe(data.len() as f64);
+                    .with_label_values(&[HTTP_METHOD_POST, path])
+                    .observe(bytes.len() as f64);
             }
             
-            match Message::from_vec(&data) {
-                Ok(msg) => msg,
-                Err(e) => {
-                    info!(
-                        client_ip = ?client_ip,
-                        error = %e,
-                        "Failed to parse DNS message from base64"
-                    );
-                    
-                    // 记录错误状态
-                    let status = StatusCode::BAD_REQUEST.as_u16().to_string();
-                    {
-                        METRICS.http_requests_total()
-                            .with_label_values(&[HTTP_METHOD_GET, path, &status, format, &http_version])
-                            .inc();
-                        
-                        // 记录请求持续时间
-                        let duration = start.elapsed().as_secs_f64();
-                        METRICS.http_request_duration_seconds()
-                            .with_label_values(&[HTTP_METHOD_GET, path, format])
-                            .observe(duration);
-                        
-                        // 记录DNS查询错误
-                        METRICS.dns_queries_total()
-                            .with_label_values(&[DNS_QUERY_TYPE_UNKNOWN, DNS_EVENT_PARSE_ERROR])
-                            .inc();
-                    }
-                    
-                    // 返回错误响应
-                    let error_body = ERROR_INVALID_DNS_MESSAGE;
-                    let response = (StatusCode::BAD_REQUEST, error_body).into_response();
-                    
-                    // 记录响应大小
-                    {
-                        METRICS.http_response_bytes()
-                            .with_label_values(&[HTTP_METHOD_GET, path])
-                            .observe(error_body.len() as f64);
-                    }
-                    
-                    return response;
-                }
-            }
+            serde_json::from_slice::<DnsJsonRequest>(&bytes)
+                .map_err(|e| format!("{}: {}", ERROR_INVALID_JSON_REQUEST, e))
         },
-        Err(e) => {
+        Err(BodyReadError::TooLarge) => {
             info!(
                 client_ip = ?client_ip,
-                error = %e,
-                "Failed to decode base64 DNS query parameter"
+                max_size = state.config.http.max_request_body_size,
+                "DNS JSON POST request body too large"
+            );
+            return request_too_large_response(HTTP_METHOD_POST, path, format, &http_version, start);
+        },
+        Err(BodyReadError::Read(_)) => Err(ERROR_READ_REQUEST_BODY.to_string()),
+    };
+    
+    let params = match params {
+        Ok(params) => params,
+        Err(error_body) => {
+            info!(
+                client_ip = ?client_ip,
+                error = %error_body,
+                "Failed to parse DNS JSON POST request body"
             );
             
             // 记录错误状态
             let status = StatusCode::BAD_REQUEST.as_u16().to_string();
             {
                 METRICS.http_requests_total()
-                    .with_label_values(&[HTTP_METHOD_GET, path, &status, format, &http_version])
+                    .with_label_values(&[HTTP_METHOD_POST, path, &status, format, &http_version])
                     .inc();
                 
                 // 记录请求持续时间
                 let duration = start.elapsed().as_secs_f64();
                 METRICS.http_request_duration_seconds()
-                    .with_label_values(&[HTTP_METHOD_GET, path, format])
+                    .with_label_values(&[HTTP_METHOD_POST, path, format])
                     .observe(duration);
                 
                 // 记录DNS查询错误
                 METRICS.dns_queries_total()
-                    .with_label_values(&[DNS_QUERY_TYPE_UNKNOWN, DNS_EVENT_BASE64_DECODE_ERROR])
+                    .with_label_values(&[DNS_QUERY_TYPE_UNKNOWN, DNS_EVENT_PARSE_ERROR])
                     .inc();
-            }
-            
-            // 返回错误响应
-            let error_body = ERROR_INVALID_BASE64;
-            let response = (StatusCode::BAD_REQUEST, error_body).into_response();
-            
-            // 记录响应大小
-            {
+                
                 METRICS.http_response_bytes()
-                    .with_label_values(&[HTTP_METHOD_GET, path])
+                    .with_label_values(&[HTTP_METHOD_POST, path])
                     .observe(error_body.len() as f64);
             }
             
-            return response;
+            return (StatusCode::BAD_REQUEST, error_body).into_response();
         }
     };
     
-    // 从查询获取域名（用于日志）
-    let domain = query_message.queries().first().map_or_else(
-        || "unknown".to_string(), 
-        |q| q.name().to_utf8()
-    );
+    // 根据 ct 字段决定响应格式，默认返回 JSON
+    let encoding = params.ct.as_deref()
+        .and_then(ResponseEncoding::from_media_type)
+        .unwrap_or(ResponseEncoding::Json);
+    respond_dns_json_query(&state, params, encoding, client_ip, upstream_override, http_version, HTTP_METHOD_POST, path).await
+}
+
+// 执行 JSON API 查询并构建响应（与 wireformat 共享缓存、路由与上游处理流程）
+#[allow(clippy::too_many_arguments)]
+async fn respond_dns_json_query(
+    state: &ServerState,
+    params: DnsJsonRequest,
+    encoding: ResponseEncoding,
+    client_ip: IpAddr,
+    upstream_override: UpstreamOverride,
+    http_version: String,
+    method: &'static str,
+    path: &str,
+) -> Response {
+    // 记录开始时间
+    let start = Instant::now();
     
-    // 记录DNS查询类型
-    let query_type = if let Some(q) = query_message.queries().first() {
-        format!("{:?}", q.query_type())
-    } else {
-        DNS_QUERY_TYPE_UNKNOWN.to_string()
-    };
+    let format = encoding.format();
     
-    {
-        METRICS.dns_queries_total()
-            .with_label_values(&[&query_type, DNS_EVENT_RECEIVED])
-            .inc();
-        
-        METRICS.dns_query_type_total()
-            .with_label_values(&[&query_type])
-            .inc();
+    if let UpstreamOverride::UnknownGroup = upstream_override {
+        return unknown_upstream_group_response(method, path, format, &http_version, start);
     }
     
-    // 处理查询
-    let (response_message, is_cached) = match process_query(
-        state.upstream.as_ref(),
-        state.router.as_ref(),
-        state.cache.as_ref(),
+    debug!(name = %params.name, record_type = %params.record_type, client_ip = ?client_ip, "DNS JSON query received");
+    
+    // 创建 DNS 查询消息
+    let query_message = match create_dns_message_from_json_request(&params) {
+        Ok(msg) => msg,
+        Err(e) => {
+            // 记录请求错误
+            info!(
+                name = %params.name,
+                record_type = %params.record_type,
+                client_ip = ?client_ip,
+                error = %e,
+                "DNS-over-HTTPS request parameter error"
+            );
+            
+            // 记录错误状态码 - 提前计算一次，重复使用
+            let status = StatusCode::BAD_REQUEST;
+            let status_str = status.as_u16().to_string();
+            let error_body = e.to_string();
+            let error_body_len = error_body.len() as f64;
+            
+            // 记录指标
+            {
+                METRICS.http_requests_total()
+                    .with_label_values(&[method, path, &status_str, format, &http_version])
+                    .inc();
+                
+                // 记录请求持续时间
+                let duration = start.elapsed().as_secs_f64();
+                METRICS.http_request_duration_seconds()
+                    .with_label_values(&[method, path, format])
+                    .observe(duration);
+                
+                // 记录DNS查询错误
+                METRICS.dns_queries_total()
+                    .with_label_values(&[&params.record_type, DNS_EVENT_PARAMETER_ERROR])
+                    .inc();
+                
+                METRICS.http_response_bytes()
+                    .with_label_values(&[method, path])
+                    .observe(error_body_len);
+            }
+            
+            // 返回错误响应
+            return (status, error_body).into_response();
+        }
+    };
+    
+    // 与 wireformat 共享缓存、路由与上游处理流程
+    let resolution = state.resolve_with_protocol(
         &query_message,
-        client_ip,
-    ).await {
-        Ok((msg, cached)) => (msg, cached),
+        Some(client_ip),
+        QUERY_PROTOCOL_JSON,
+        ResolveOptions { upstream_override: upstream_override.group(), ..Default::default() },
+    ).await;
+    if let Some(response) = resolution_error_response(&state.config, &resolution, method, path, format, &http_version, start) {
+        return response;
+    }
+    let mut response_message = resolution.message;
+    
+    // 按协商的编码序列化响应
+    let (content_type, response_body) = match serialize_response(&state.config, encoding, &query_message, &mut response_message) {
+        Ok(serialized) => serialized,
         Err(e) => {
+            // 记录响应转换错误
             info!(
-                domain = %domain,
+                name = %params.name,
+                record_type = %params.record_type,
                 client_ip = ?client_ip,
                 error = %e,
-                "DNS-over-HTTPS wire query processing failed"
+                "DNS-over-HTTPS response conversion failed"
             );
             
-            // 记录错误状态
-            let status = StatusCode::INTERNAL_SERVER_ERROR.as_u16().to_string();
+            // 记录错误状态码 - 提前计算一次，重复使用
+            let status = StatusCode::INTERNAL_SERVER_ERROR;
+            let status_str = status.as_u16().to_string();
+            let error_body = e.to_string();
+            let error_body_len = error_body.len() as f64;
+            
+            // 记录指标
             {
                 METRICS.http_requests_total()
-                    .with_label_values(&[HTTP_METHOD_GET, path, &status, format, &http_version])
+                    .with_label_values(&[method, path, &status_str, format, &http_version])
                     .inc();
                 
                 // 记录请求持续时间
                 let duration = start.elapsed().as_secs_f64();
                 METRICS.http_request_duration_seconds()
-                    .with_label_values(&[HTTP_METHOD_GET, path, format])
+                    .with_label_values(&[method, path, format])
                     .observe(duration);
                 
-                // 记录DNS查询错误
-                METRICS.dns_queries_total()
-                    .with_label_values(&[&query_type, DNS_EVENT_PROCESSING_FAILED])
-                    .inc();
-            }
-            
-            // 返回错误响应
-            let error_body = e.to_string();
-            let response = (StatusCode::INTERNAL_SERVER_ERROR, error_body.clone()).into_response();
-            
-            // 记录响应大小
-            {
                 METRICS.http_response_bytes()
-                    .with_label_values(&[HTTP_METHOD_GET, path])
-                    .observe(error_body.len() as f64);
+                    .with_label_values(&[method, path])
+                    .observe(error_body_len);
             }
             
-            return response;
+            // 返回错误响应
+            return (status, error_body).into_response();
         }
     };
     
-    // 将响应消息转换为二进制格式
-    let response_bytes = match response_message.to_vec() {
-        Ok(bytes) => bytes,
+    let rcode = response_message.response_code();
+    observe_http_response(method, path, format, &http_version, start, StatusCode::OK, response_body.len());
+    
+    // 返回响应
+    let mut response = (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, content_type)],
+        response_body,
+    ).into_response();
+    apply_servfail_retry_after(&mut response, state, rcode);
+    response
+}
+
+// 处理 DNS GET 请求（RFC 8484）
+async fn handle_dns_wire_get(
+    state: ServerState,
+    params: DnsMsgGetRequest,
+    encoding: ResponseEncoding,
+    req: Request<axum::body::Body>,
+) -> Response {
+    // 提取客户端 IP
+    let client_ip = get_client_ip_from_request(&req);
+    
+    // 记录开始时间
+    let start = Instant::now();
+    
+    // 记录请求指标
+    let path = request_path_label(&req);
+    let path = path.as_str();
+    let format = encoding.format();
+    let http_version = format!("{:?}", req.version());
+
+    debug!(client_ip = ?client_ip, "DNS-over-HTTPS GET request received");
+    
+    let upstream_override = UpstreamOverride::from_headers(&state.config, req.headers(), client_ip);
+    if let UpstreamOverride::UnknownGroup = upstream_override {
+        return unknown_upstream_group_response(HTTP_METHOD_GET, path, format, &http_version, start);
+    }
+    
+    // 解码前检查 dns 参数长度：Base64url（无填充）编码后的长度为原长度的 4/3
+    let max_size = state.config.http.max_request_body_size;
+    if params.dns.len() > (max_size * 4).div_ceil(3) {
+        info!(
+            client_ip = ?client_ip,
+            param_len = params.dns.len(),
+            max_size,
+            "DNS-over-HTTPS GET dns parameter too large"
+        );
+        return request_too_large_response(HTTP_METHOD_GET, path, format, &http_version, start);
+    }
+    
+    // 解码请求参数中的 DNS 消息（Base64url 编码）
+    let data = match decode_dns_param(&params.dns) {
+        Ok(data) => data,
+        Err(error) => {
+            info!(client_ip = ?client_ip, error = error.code(), "Invalid DNS-over-HTTPS GET dns parameter");
+            return dns_param_error_response(error, path, format, &http_version, start);
+        }
+    };
+    
+    // 记录请求大小
+    {
+        METRICS.http_request_bytes()
+            .with_label_values(&[HTTP_METHOD_GET, path])
+            .observe(data.len() as f64);
+        METRICS.dns_request_size_bytes()
+            .with_label_values(&[format])
+            .observe(data.len() as f64);
+    }
+    
+    if data.len() > max_size {
+        info!(client_ip = ?client_ip, size = data.len(), max_size, "DNS-over-HTTPS GET query too large");
+        return request_too_large_response(HTTP_METHOD_GET, path, format, &http_version, start);
+    }
+    
+    let query_message = match parse_dns_param_message(&data) {
+        Ok(message) => message,
+        Err(error) => {
+            info!(client_ip = ?client_ip, error = error.code(), "Invalid DNS message in GET dns parameter");
+            return dns_param_error_response(error, path, format, &http_version, start);
+        }
+    };
+    
+    // 处理查询
+    let resolution = state.resolve_with_protocol(
+        &query_message,
+        Some(client_ip),
+        QUERY_PROTOCOL_DOH_GET,
+        ResolveOptions { upstream_override: upstream_override.group(), dedup_wire: Some(&data) },
+    ).await;
+    if let Some(response) = resolution_error_response(&state.config, &resolution, HTTP_METHOD_GET, path, format, &http_version, start) {
+        return response;
+    }
+    let Resolution { message: mut response_message, cache_age, .. } = resolution;
+    
+    // 按协商的编码序列化响应
+    let (content_type, response_bytes) = match serialize_response(&state.config, encoding, &query_message, &mut response_message) {
+        Ok(serialized) => serialized,
         Err(e) => {
             info!(
-                domain = %domain,
                 client_ip = ?client_ip,
                 error = %e,
                 "Failed to serialize DNS response message"
@@ -646,68 +1405,25 @@ async fn handle_dns_wire_get(
         }
     };
     
-    // 计算持续时间
-    let duration = start.elapsed();
-    
-    // 记录请求完成
-    let qtype = query_message.queries().first().map_or_else(
-        || "unknown".to_string(), 
-        |q| format!("{:?}", q.query_type())
-    );
-    
-    let answer_count = response_message.answer_count();
     let rcode = response_message.response_code();
-    let query_time_ms = duration.as_millis();
-    
-    info!(
-        domain = %domain,
-        qtype = %qtype,
-        client_ip = ?client_ip,
-        answer_count = answer_count,
-        response_code = ?rcode,
-        dnssec_validated = response_message.authentic_data(),
-        query_time_ms = query_time_ms,
-        is_cached = is_cached,
-        "DNS-over-HTTPS wire GET request completed"
-    );
-    
-    // 记录成功状态和持续时间
-    let status = StatusCode::OK.as_u16().to_string();
-    {
-        METRICS.http_requests_total()
-            .with_label_values(&[HTTP_METHOD_GET, path, &status, format, &http_version])
-            .inc();
-        
-        // 记录请求持续时间
-        METRICS.http_request_duration_seconds()
-            .with_label_values(&[HTTP_METHOD_GET, path, format])
-            .observe(duration.as_secs_f64());
-        
-        // 记录DNS响应
-        METRICS.dns_responses_total()
-            .with_label_values(&[&format!("{:?}", rcode)])
-            .inc();
-        
-        // 记录响应大小
-        METRICS.http_response_bytes()
-            .with_label_values(&[HTTP_METHOD_GET, path])
-            .observe(response_bytes.len() as f64);
-    }
+    observe_http_response(HTTP_METHOD_GET, path, format, &http_version, start, StatusCode::OK, response_bytes.len());
     
     // 返回响应
-    (
+    let mut response = (
         StatusCode::OK,
-        [(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)],
+        [(header::CONTENT_TYPE, content_type)],
         response_bytes,
-    ).into_response()
+    ).into_response();
+    apply_servfail_retry_after(&mut response, &state, rcode);
+    apply_cache_headers(&mut response, &state.config, Some(&response_message), cache_age);
+    response
 }
 
 // 处理 DNS POST 请求（RFC 8484）
-#[axum::debug_handler]
 async fn handle_dns_wire_post(
-    State(state): State<ServerState>,
+    state: ServerState,
     req: Request<axum::body::Body>,
-) -> impl IntoResponse {
+) -> Response {
     // 提取客户端 IP
     let client_ip = get_client_ip_from_request(&req);
     
@@ -715,18 +1431,29 @@ async fn handle_dns_wire_post(
     let start = Instant::now();
     
     // 记录请求指标
-    let path = DOH_STANDARD_PATH;
+    let path = request_path_label(&req);
+    let path = path.as_str();
     let format = DOH_FORMAT_WIRE;
     let http_version = format!("{:?}", req.version());
     
     debug!(client_ip = ?client_ip, "DNS-over-HTTPS POST request received");
     
-    // 验证内容类型
-    let is_valid_content_type = req.headers()
+    let upstream_override = UpstreamOverride::from_headers(&state.config, req.headers(), client_ip);
+    if let UpstreamOverride::UnknownGroup = upstream_override {
+        return unknown_upstream_group_response(HTTP_METHOD_POST, path, format, &http_version, start);
+    }
+    
+    // 验证内容类型，旧版草案的 application/dns-udpwireformat 作为别名接受
+    let content_type = req.headers()
         .get(header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.starts_with(CONTENT_TYPE_DNS_MESSAGE))
-        .unwrap_or(false);
+        .and_then(|v| v.to_str().ok());
+    let is_legacy_content_type = content_type.is_some_and(|s| s.starts_with(CONTENT_TYPE_DNS_UDPWIREFORMAT));
+    let is_valid_content_type = is_legacy_content_type
+        || content_type.is_some_and(|s| s.starts_with(CONTENT_TYPE_DNS_MESSAGE));
+    
+    if is_legacy_content_type {
+        debug!(client_ip = ?client_ip, "Accepted legacy application/dns-udpwireformat content type");
+    }
         
     if !is_valid_content_type {
         info!(
@@ -763,7 +1490,8 @@ async fn handle_dns_wire_post(
     }
     
     // 读取请求体
-    let body_bytes = match to_bytes(req.into_body(), MAX_REQUEST_SIZE).await {
+    let max_size = state.config.http.max_request_body_size;
+    let body_bytes = match read_request_body(req.into_body(), max_size).await {
         Ok(bytes) => {
             // 记录请求大小
             {
@@ -774,7 +1502,15 @@ async fn handle_dns_wire_post(
             
             bytes
         },
-        Err(e) => {
+        Err(BodyReadError::TooLarge) => {
+            info!(
+                client_ip = ?client_ip,
+                max_size,
+                "DNS-over-HTTPS POST request body too large"
+            );
+            return request_too_large_response(HTTP_METHOD_POST, path, format, &http_version, start);
+        },
+        Err(BodyReadError::Read(e)) => {
             info!(
                 client_ip = ?client_ip,
                 error = %e,
@@ -810,42 +1546,10 @@ async fn handle_dns_wire_post(
         }
     };
     
-    // 检查请求大小
-    if body_bytes.len() > MAX_REQUEST_SIZE {
-        info!(
-            client_ip = ?client_ip,
-            size = body_bytes.len(),
-            max_size = MAX_REQUEST_SIZE,
-            "DNS-over-HTTPS POST request body too large"
-        );
-        
-        // 记录错误状态
-        let status = StatusCode::PAYLOAD_TOO_LARGE.as_u16().to_string();
-        {
-            METRICS.http_requests_total()
-                .with_label_values(&[HTTP_METHOD_POST, path, &status, format, &http_version])
-                .inc();
-            
-            // 记录请求持续时间
-            let duration = start.elapsed().as_secs_f64();
-            METRICS.http_request_duration_seconds()
-                .with_label_values(&[HTTP_METHOD_POST, path, format])
-                .observe(duration);
-        }
-        
-        // 返回错误响应
-        let error_body = ERROR_REQUEST_TOO_LARGE;
-        let response = (StatusCode::PAYLOAD_TOO_LARGE, error_body).into_response();
-        
-        // 记录响应大小
-        {
-            METRICS.http_response_bytes()
-                .with_label_values(&[HTTP_METHOD_POST, path])
-                .observe(error_body.len() as f64);
-        }
-        
-        return response;
-    }
+    // 记录 DNS 查询大小
+    METRICS.dns_request_size_bytes()
+        .with_label_values(&[format])
+        .observe(body_bytes.len() as f64);
     
     // 解析 DNS 消息
     let query_message = match Message::from_vec(&body_bytes) {
@@ -891,44 +1595,27 @@ async fn handle_dns_wire_post(
         }
     };
     
-    // 从查询获取域名（用于日志）
-    let domain = query_message.queries().first().map_or_else(
-        || "unknown".to_string(), 
-        |q| q.name().to_utf8()
-    );
-    
-    // 记录DNS查询类型
-    let query_type = if let Some(q) = query_message.queries().first() {
-        format!("{:?}", q.query_type())
-    } else {
-        DNS_QUERY_TYPE_UNKNOWN.to_string()
-    };
-    
-    {
-        METRICS.dns_queries_total()
-            .with_label_values(&[&query_type, DNS_EVENT_RECEIVED])
-            .inc();
-        
-        METRICS.dns_query_type_total()
-            .with_label_values(&[&query_type])
-            .inc();
-    }
-    
     // 处理查询
-    let (response_message, is_cached) = match process_query(
-        state.upstream.as_ref(),
-        state.router.as_ref(),
-        state.cache.as_ref(),
+    let resolution = state.resolve_with_protocol(
         &query_message,
-        client_ip,
-    ).await {
-        Ok((msg, cached)) => (msg, cached),
+        Some(client_ip),
+        QUERY_PROTOCOL_DOH_POST,
+        ResolveOptions { upstream_override: upstream_override.group(), dedup_wire: Some(&body_bytes) },
+    ).await;
+    if let Some(response) = resolution_error_response(&state.config, &resolution, HTTP_METHOD_POST, path, format, &http_version, start) {
+        return response;
+    }
+    let mut response_message = resolution.message;
+    
+    // 将响应消息转换为二进制格式
+    pad_wire_response(&state.config, &query_message, &mut response_message);
+    let response_bytes = match response_message.to_vec() {
+        Ok(bytes) => bytes,
         Err(e) => {
             info!(
-                domain = %domain,
                 client_ip = ?client_ip,
                 error = %e,
-                "DNS-over-HTTPS wire query processing failed"
+                "Failed to serialize DNS response message"
             );
             
             // 记录错误状态
@@ -943,156 +1630,387 @@ async fn handle_dns_wire_post(
                 METRICS.http_request_duration_seconds()
                     .with_label_values(&[HTTP_METHOD_POST, path, format])
                     .observe(duration);
+            }
+            
+            // 返回错误响应
+            let error_body = ERROR_SERIALIZE_RESPONSE;
+            let response = (StatusCode::INTERNAL_SERVER_ERROR, error_body).into_response();
+            
+            // 记录响应大小
+            {
+                METRICS.http_response_bytes()
+                    .with_label_values(&[HTTP_METHOD_POST, path])
+                    .observe(error_body.len() as f64);
+            }
+            
+            return response;
+        }
+    };
+    
+    observe_response_size(&state.config, format, &query_message, response_bytes.len());
+    
+    let rcode = response_message.response_code();
+    observe_http_response(HTTP_METHOD_POST, path, format, &http_version, start, StatusCode::OK, response_bytes.len());
+    
+    // 返回响应
+    let mut response = (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)],
+        response_bytes,
+    ).into_response();
+    apply_servfail_retry_after(&mut response, &state, rcode);
+    // POST 响应无法被 HTTP 缓存按 URL 复用
+    apply_cache_headers(&mut response, &state.config, None, None);
+    response
+}
+
+// 处理 DNS over WebSocket 升级请求
+// 连接建立后，二进制帧承载 wireformat 查询，文本帧承载 base64url 编码的查询，响应使用与查询相同的帧类型
+async fn handle_dns_websocket(
+    State(state): State<ServerState>,
+    ws: WebSocketUpgrade,
+    req: Request<Body>,
+) -> Response {
+    let client_ip = get_client_ip_from_request(&req);
+    debug!(client_ip = ?client_ip, "DNS over WebSocket connection requested");
+    
+    ws.max_message_size(state.config.http.max_request_body_size)
+        .on_upgrade(move |socket| serve_dns_websocket(state, socket, client_ip))
+}
+
+// 在单个 WebSocket 连接上并发处理查询，响应按 DNS 消息 ID 与查询对应，完成顺序不保证与发送顺序一致
+async fn serve_dns_websocket(state: ServerState, mut socket: WebSocket, client_ip: IpAddr) {
+    // 启用速率限制时，每个连接的在途查询数不超过 per_ip_concurrent，达到上限后暂停读取新帧
+    let rate_limit = &state.config.http.rate_limit;
+    let max_in_flight = rate_limit.enabled.then_some(rate_limit.per_ip_concurrent as usize);
+    let mut in_flight = JoinSet::new();
+    let mut in_flight_ids = HashSet::new();
+    
+    METRICS.websocket_connections().inc();
+    info!(client_ip = ?client_ip, "DNS over WebSocket connection established");
+    
+    loop {
+        let accepting = max_in_flight.is_none_or(|limit| in_flight.len() < limit);
+        let (response_message, is_text) = tokio::select! {
+            frame = socket.recv(), if accepting => {
+                let (bytes, is_text) = match frame {
+                    Some(Ok(WsMessage::Binary(bytes))) => (bytes.to_vec(), false),
+                    Some(Ok(WsMessage::Text(text))) => match decode_dns_param(text.as_str()) {
+                        Ok(bytes) => (bytes, true),
+                        Err(e) => {
+                            debug!(client_ip = ?client_ip, error = e.code(), "Ignoring undecodable DNS over WebSocket text frame");
+                            METRICS.dns_queries_total()
+                                .with_label_values(&[DNS_QUERY_TYPE_UNKNOWN, DNS_EVENT_BASE64_DECODE_ERROR])
+                                .inc();
+                            continue;
+                        },
+                    },
+                    // Ping/Pong 由 Axum 自动应答
+                    Some(Ok(WsMessage::Ping(_) | WsMessage::Pong(_))) => continue,
+                    Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                };
                 
-                // 记录DNS查询错误
-                METRICS.dns_queries_total()
-                    .with_label_values(&[&query_type, DNS_EVENT_PROCESSING_FAILED])
-                    .inc();
+                METRICS.dns_request_size_bytes()
+                    .with_label_values(&[DOH_FORMAT_WEBSOCKET])
+                    .observe(bytes.len() as f64);
+                
+                let query_message = match Message::from_vec(&bytes) {
+                    Ok(msg) => msg,
+                    Err(e) => {
+                        debug!(client_ip = ?client_ip, error = %e, "Ignoring malformed DNS over WebSocket query");
+                        METRICS.dns_queries_total()
+                            .with_label_values(&[DNS_QUERY_TYPE_UNKNOWN, DNS_EVENT_PARSE_ERROR])
+                            .inc();
+                        continue;
+                    }
+                };
+                
+                // 响应通过消息 ID 区分，同一连接上重复的在途 ID 直接拒绝
+                if !in_flight_ids.insert(query_message.id()) {
+                    debug!(client_ip = ?client_ip, id = query_message.id(), "Refusing DNS over WebSocket query with an in-flight message ID");
+                    let mut response = build_servfail_response(&query_message);
+                    response.set_response_code(ResponseCode::Refused);
+                    (response, is_text)
+                } else {
+                    let state = state.clone();
+                    in_flight.spawn(async move {
+                        let mut response = state.resolve_with_protocol(&query_message, Some(client_ip), QUERY_PROTOCOL_WEBSOCKET, ResolveOptions::default())
+                            .await
+                            .message;
+                        pad_wire_response(&state.config, &query_message, &mut response);
+                        (response, is_text)
+                    });
+                    continue;
+                }
+            },
+            Some(result) = in_flight.join_next() => {
+                let Ok((response, is_text)) = result else {
+                    continue;
+                };
+                in_flight_ids.remove(&response.id());
+                (response, is_text)
+            },
+            else => break,
+        };
+        
+        let response_bytes = match response_message.to_vec() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                info!(client_ip = ?client_ip, error = %e, "Failed to serialize DNS over WebSocket response");
+                continue;
             }
+        };
+        let frame = if is_text {
+            WsMessage::Text(BASE64_ENGINE.encode(&response_bytes).into())
+        } else {
+            WsMessage::Binary(response_bytes.into())
+        };
+        if socket.send(frame).await.is_err() {
+            break;
+        }
+    }
+    
+    // 连接关闭时放弃尚未完成的查询
+    in_flight.abort_all();
+    METRICS.websocket_connections().dec();
+    info!(client_ip = ?client_ip, "DNS over WebSocket connection closed");
+}
+
+// 返回 ODoH 目标公钥配置（ObliviousDoHConfigs）
+async fn handle_odoh_configs(State(state): State<OdohState>) -> Response {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, CONTENT_TYPE_OCTET_STREAM)],
+        state.target.configs(),
+    ).into_response()
+}
+
+// 处理 Oblivious DoH 查询（RFC 9230）：解密后按普通 DoH 流程解析，再加密响应
+async fn handle_odoh_query(
+    State(state): State<OdohState>,
+    req: Request<axum::body::Body>,
+) -> Response {
+    // 提取客户端 IP（ODoH 请求来自中继）
+    let client_ip = get_client_ip_from_request(&req);
+    
+    // 记录开始时间
+    let start = Instant::now();
+    
+    let path = state.path.as_ref();
+    let http_version = format!("{:?}", req.version());
+    
+    debug!(client_ip = ?client_ip, "Oblivious DoH request received");
+    
+    // 验证内容类型
+    let is_valid_content_type = req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|s| s.starts_with(CONTENT_TYPE_ODOH_MESSAGE));
+    
+    if !is_valid_content_type {
+        info!(client_ip = ?client_ip, "Invalid content type for Oblivious DoH request");
+        return odoh_error_response(path, &http_version, start, StatusCode::UNSUPPORTED_MEDIA_TYPE, ERROR_INVALID_CONTENT_TYPE);
+    }
+    
+    // 读取请求体
+    let max_size = state.server.config.http.max_request_body_size;
+    let body_bytes = match read_request_body(req.into_body(), max_size).await {
+        Ok(bytes) => {
+            METRICS.http_request_bytes()
+                .with_label_values(&[HTTP_METHOD_POST, path])
+                .observe(bytes.len() as f64);
+            bytes
+        },
+        Err(BodyReadError::TooLarge) => {
+            info!(client_ip = ?client_ip, max_size, "Oblivious DoH request body too large");
+            return request_too_large_response(HTTP_METHOD_POST, path, DOH_FORMAT_ODOH, &http_version, start);
+        },
+        Err(BodyReadError::Read(e)) => {
+            info!(client_ip = ?client_ip, error = %e, "Failed to read Oblivious DoH request body");
+            return odoh_error_response(path, &http_version, start, StatusCode::BAD_REQUEST, ERROR_READ_REQUEST_BODY);
+        }
+    };
+    
+    // 解密查询
+    let (query_bytes, responder) = match state.target.decrypt_query(&body_bytes) {
+        Ok(decrypted) => decrypted,
+        Err(e) => {
+            info!(client_ip = ?client_ip, error = %e, "Failed to decrypt Oblivious DoH query");
             
-            // 返回错误响应
-            let error_body = e.to_string();
-            let response = (StatusCode::INTERNAL_SERVER_ERROR, error_body.clone()).into_response();
+            METRICS.dns_queries_total()
+                .with_label_values(&[DNS_QUERY_TYPE_UNKNOWN, DNS_EVENT_DECRYPT_ERROR])
+                .inc();
             
-            // 记录响应大小
-            {
-                METRICS.http_response_bytes()
-                    .with_label_values(&[HTTP_METHOD_POST, path])
-                    .observe(error_body.len() as f64);
-            }
+            // 未知密钥返回 401，提示客户端重新获取公钥配置
+            return match e {
+                ServerError::OdohUnknownKey => odoh_error_response(path, &http_version, start, StatusCode::UNAUTHORIZED, ERROR_UNKNOWN_ODOH_KEY),
+                _ => odoh_error_response(path, &http_version, start, StatusCode::BAD_REQUEST, ERROR_INVALID_ODOH_MESSAGE),
+            };
+        }
+    };
+    
+    // 记录内部 DNS 查询大小
+    METRICS.dns_request_size_bytes()
+        .with_label_values(&[DOH_FORMAT_ODOH])
+        .observe(query_bytes.len() as f64);
+    
+    // 解析内部 DNS 消息
+    let query_message = match Message::from_vec(&query_bytes) {
+        Ok(msg) => msg,
+        Err(e) => {
+            info!(client_ip = ?client_ip, error = %e, "Failed to parse DNS message from Oblivious DoH query");
             
-            return response;
+            METRICS.dns_queries_total()
+                .with_label_values(&[DNS_QUERY_TYPE_UNKNOWN, DNS_EVENT_PARSE_ERROR])
+                .inc();
+            
+            return odoh_error_response(path, &http_version, start, StatusCode::BAD_REQUEST, ERROR_INVALID_DNS_MESSAGE);
         }
     };
     
-    // 将响应消息转换为二进制格式
-    let response_bytes = match response_message.to_vec() {
+    // 与普通 DoH 共享缓存、路由与上游处理流程
+    let server = &state.server;
+    let resolution = server.resolve_with_protocol(&query_message, Some(client_ip), QUERY_PROTOCOL_ODOH, ResolveOptions::default()).await;
+    if let Some(response) = resolution_error_response(&server.config, &resolution, HTTP_METHOD_POST, path, DOH_FORMAT_ODOH, &http_version, start) {
+        return response;
+    }
+    let mut response_message = resolution.message;
+    
+    // 序列化并加密响应
+    pad_wire_response(&server.config, &query_message, &mut response_message);
+    let encrypted = response_message.to_vec()
+        .map_err(ServerError::from)
+        .and_then(|bytes| {
+            observe_response_size(&server.config, DOH_FORMAT_ODOH, &query_message, bytes.len());
+            responder.encrypt_response(&bytes)
+        });
+    
+    let response_bytes = match encrypted {
         Ok(bytes) => bytes,
         Err(e) => {
             info!(
-                domain = %domain,
                 client_ip = ?client_ip,
                 error = %e,
-                "Failed to serialize DNS response message"
+                "Failed to encrypt Oblivious DoH response"
             );
-            
-            // 记录错误状态
-            let status = StatusCode::INTERNAL_SERVER_ERROR.as_u16().to_string();
-            {
-                METRICS.http_requests_total()
-                    .with_label_values(&[HTTP_METHOD_POST, path, &status, format, &http_version])
-                    .inc();
-                
-                // 记录请求持续时间
-                let duration = start.elapsed().as_secs_f64();
-                METRICS.http_request_duration_seconds()
-                    .with_label_values(&[HTTP_METHOD_POST, path, format])
-                    .observe(duration);
-            }
-            
-            // 返回错误响应
-            let error_body = ERROR_SERIALIZE_RESPONSE;
-            let response = (StatusCode::INTERNAL_SERVER_ERROR, error_body).into_response();
-            
-            // 记录响应大小
-            {
-                METRICS.http_response_bytes()
-                    .with_label_values(&[HTTP_METHOD_POST, path])
-                    .observe(error_body.len() as f64);
-            }
-            
-            return response;
+            return odoh_error_response(path, &http_version, start, StatusCode::INTERNAL_SERVER_ERROR, ERROR_ENCRYPT_RESPONSE);
         }
     };
     
-    // 计算持续时间
-    let duration = start.elapsed();
-    
-    // 记录请求完成
-    let qtype = query_message.queries().first().map_or_else(
-        || "unknown".to_string(), 
-        |q| format!("{:?}", q.query_type())
-    );
-    
-    let answer_count = response_message.answer_count();
     let rcode = response_message.response_code();
-    let query_time_ms = duration.as_millis();
-    
-    info!(
-        domain = %domain,
-        qtype = %qtype,
-        client_ip = ?client_ip,
-        answer_count = answer_count,
-        response_code = ?rcode,
-        dnssec_validated = response_message.authentic_data(),
-        query_time_ms = query_time_ms,
-        is_cached = is_cached,
-        "DNS-over-HTTPS wire POST request completed"
-    );
+    observe_http_response(HTTP_METHOD_POST, path, DOH_FORMAT_ODOH, &http_version, start, StatusCode::OK, response_bytes.len());
     
-    // 记录成功状态和持续时间
-    let status = StatusCode::OK.as_u16().to_string();
+    let mut response = (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, CONTENT_TYPE_ODOH_MESSAGE)],
+        response_bytes,
+    ).into_response();
+    apply_servfail_retry_after(&mut response, server, rcode);
+    response
+}
+
+// 记录 ODoH 错误响应的指标并构建响应
+fn odoh_error_response(
+    path: &str,
+    http_version: &str,
+    start: Instant,
+    status: StatusCode,
+    error_body: &str,
+) -> Response {
+    let status_str = status.as_u16().to_string();
     {
         METRICS.http_requests_total()
-            .with_label_values(&[HTTP_METHOD_POST, path, &status, format, &http_version])
+            .with_label_values(&[HTTP_METHOD_POST, path, &status_str, DOH_FORMAT_ODOH, http_version])
             .inc();
         
-        // 记录请求持续时间
         METRICS.http_request_duration_seconds()
-            .with_label_values(&[HTTP_METHOD_POST, path, format])
-            .observe(duration.as_secs_f64());
-        
-        // 记录DNS响应
-        METRICS.dns_responses_total()
-            .with_label_values(&[&format!("{:?}", rcode)])
-            .inc();
+            .with_label_values(&[HTTP_METHOD_POST, path, DOH_FORMAT_ODOH])
+            .observe(start.elapsed().as_secs_f64());
         
-        // 记录响应大小
         METRICS.http_response_bytes()
             .with_label_values(&[HTTP_METHOD_POST, path])
-            .observe(response_bytes.len() as f64);
+            .observe(error_body.len() as f64);
     }
     
-    // 返回响应
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)],
-        response_bytes,
-    ).into_response()
+    (status, error_body.to_string()).into_response()
 }
 
-// 从请求中提取客户端 IP
-fn get_client_ip_from_request<T>(req: &Request<T>) -> IpAddr {
-    // 尝试从 X-Forwarded-For 等头部提取客户端 IP
-    let headers = req.headers();
-    
-    for header_name in &IP_HEADER_NAMES {
-        if let Some(value) = headers.get(*header_name) {
-            if let Ok(value_str) = value.to_str() {
-                if let Some(ip) = value_str.split(',').next() {
-                    if let Ok(ip_addr) = ip.trim().parse::<IpAddr>() {
-                        return ip_addr;
-                    }
-                }
+// 指标使用的请求路径标签（匹配的路由路径）
+fn request_path_label<T>(req: &Request<T>) -> String {
+    req.extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| req.uri().path().to_string(), |path| path.as_str().to_string())
+}
+
+// 由受信任代理的转发头确定的客户端 IP，仅由 apply_client_ip 中间件写入请求扩展
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ForwardedClientIp(pub IpAddr);
+
+// 为路由添加客户端 IP 解析中间件：仅当连接源地址属于 trusted_proxies 时才采信 X-Forwarded-For 等头部
+pub fn apply_client_ip(app: AxumRouter, trusted_proxies: Arc<IpRangeSet>) -> AxumRouter {
+    app.layer(middleware::from_fn(move |mut req: Request<Body>, next: Next| {
+        let trusted_proxies = trusted_proxies.clone();
+        async move {
+            match (connection_peer_ip(&req), forwarded_ip_from_headers(&req)) {
+                (Some(peer), Some(ip)) if trusted_proxies.contains(peer) => {
+                    req.extensions_mut().insert(ForwardedClientIp(ip));
+                },
+                (peer, Some(ip)) => debug!(?peer, forwarded = %ip, "Ignoring forwarded client IP from untrusted peer"),
+                _ => {},
             }
+            next.run(req).await
         }
+    }))
+}
+
+// 传输层的连接源地址
+fn connection_peer_ip<T>(req: &Request<T>) -> Option<IpAddr> {
+    req.extensions()
+        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        .map(|connect_info| connect_info.ip())
+}
+
+// 从 X-Forwarded-For 等头部提取客户端 IP（取第一个有效值）
+fn forwarded_ip_from_headers<T>(req: &Request<T>) -> Option<IpAddr> {
+    let headers = req.headers();
+    IP_HEADER_NAMES.iter()
+        .filter_map(|header_name| headers.get(*header_name))
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|value| value.split(',').next().and_then(|ip| ip.trim().parse::<IpAddr>().ok()))
+}
+
+// 从请求中提取客户端 IP：优先使用受信任代理转发的地址，否则使用传输层的源 IP
+pub(crate) fn get_client_ip_from_request<T>(req: &Request<T>) -> IpAddr {
+    if let Some(ForwardedClientIp(ip)) = req.extensions().get::<ForwardedClientIp>() {
+        return *ip;
     }
-    
-    // 如果没有找到有效的 IP，使用传输层的源 IP
-    match req.extensions().get::<axum::extract::ConnectInfo<std::net::SocketAddr>>() {
-        Some(connect_info) => connect_info.ip(),
-        None => std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)), // 默认为本地回环
+    // 没有连接信息时默认为本地回环
+    connection_peer_ip(req).unwrap_or(IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+}
+
+// 在查询完成后更新按域名的查询统计
+fn record_query_stats(
+    stats: Option<&QueryStats>,
+    query_message: &Message,
+    response_message: &Message,
+    is_cached: bool,
+    duration: std::time::Duration,
+) {
+    if let (Some(stats), Some(query)) = (stats, query_message.queries().first()) {
+        stats.record(&query.name().to_utf8(), is_cached, duration, response_message.response_code());
     }
 }
 
 // 处理 DNS 查询
 async fn process_query(
-    upstream: &UpstreamManager,
-    router: &DnsRouter,
-    cache: &DnsCache,
+    state: &ServerState,
     query_message: &Message,
-    client_ip: IpAddr,
-) -> Result<(Message, bool)> {  // 返回元组，第二个参数表示是否缓存命中
+    client_ip: Option<IpAddr>,
+    upstream_override: Option<&str>,
+) -> Result<(Message, Option<u64>)> {  // 返回元组，第二个参数为缓存命中时条目的存在时间（秒）
+    let cache = state.cache.as_ref();
+    let dns_config = &state.config.dns;
+    
     // 检查查询有效性
     if query_message.queries().is_empty() {
         return Err(ServerError::InvalidQuery("Empty query section".to_string()));
@@ -1101,9 +2019,35 @@ async fn process_query(
     // 获取第一个查询
     let query = &query_message.queries()[0];
     
+    // 私有地址的 PTR 查询与配置为拒绝的记录类型直接返回 REFUSED，不查询上游也不写入缓存
+    if let Some(reason) = refusal_reason(&dns_config.upstream, query) {
+        debug!(name = %query.name(), query_type = ?query.query_type(), ?reason, "Refused query at the network boundary");
+        reason.record_metric();
+        return Ok((refused_response(query_message), None));
+    }
+    
     // 提取客户端 ECS 数据
     let client_ecs = EcsProcessor::extract_ecs_from_message(query_message);
     
+    // 配置了 SVCB/HTTPS 提示的名称直接在本地应答，不查询上游也不写入缓存
+    if let Some(response) = synthesize_svcb_response(&dns_config.svcb_hints, query_message) {
+        debug!(name = %query.name(), query_type = ?query.query_type(), "Answered SVCB/HTTPS query from configured hints");
+        return Ok((response, None));
+    }
+    
+    // 受信任客户端指定了上游组：跳过缓存与路由规则，结果也不写入缓存，避免影响其他客户端
+    if let Some(group) = upstream_override {
+        check_deferred_rate_limit()?;
+        let mut response = state.upstream.resolve(
+            query_message,
+            UpstreamSelection::Group(group.to_string()),
+            client_ip,
+            client_ecs.as_ref(),
+        ).await?;
+        post_process_response(&mut response, dns_config);
+        return Ok((response, None));
+    }
+    
     // 创建缓存键 - 只创建一次，避免重复计算
     let cache_key = if let Some(ecs) = &client_ecs {
         // 使用 ECS 数据创建缓存键，无需克隆 name
@@ -1122,26 +2066,51 @@ async fn process_query(
         )
     };
     
+    // 按客户端子网隔离缓存时，缓存键包含客户端所在子网
+    let cache_key = match client_ip {
+        Some(ip) if dns_config.cache.key_scope == CacheKeyScope::PerClientSubnet => cache_key.with_client_subnet(ip),
+        _ => cache_key,
+    };
+    
     // 尝试从缓存获取
     if cache.is_enabled() {
-        if let Some(cached_response) = cache.get_with_ecs(&cache_key, client_ecs.as_ref()).await {
-            // 从缓存构建响应（复制请求 ID 等信息）
+        if let Some((cached_response, age)) = cache.get_with_ecs_and_age(&cache_key, client_ecs.as_ref()).await {
+            // 从缓存构建响应（复制请求 ID 与问题部分）
             let mut response = cached_response;
-            response.set_id(query_message.id());
+            adopt_client_query(&mut response, query_message);
+            post_process_response(&mut response, dns_config);
             
-            return Ok((response, true));
+            return Ok((response, Some(age)));
         }
     }
     
     // 缓存未命中，需要查询上游
     
-    // 使用路由器确定上游组 - 提前获取域名UTF8字符串，避免重复转换
-    let domain_name = query.name().to_utf8();
-    let route_decision = router.match_domain(&domain_name).await;
+    // 匹配 mDNS 后缀的查询通过组播 DNS 解析，不发送到上游
+    if let Some(mdns) = state.upstream.mdns_forwarder().filter(|mdns| mdns.matches(&query.name().to_ascii())) {
+        let mut response = match mdns.query(query_message).await? {
+            // mDNS 记录变化频繁，肯定应答不缓存
+            Some(response) => response,
+            None => {
+                // 超时无响应时返回 NXDOMAIN，并按配置进行负缓存
+                let mut response = MdnsForwarder::negative_response(query_message);
+                if cache.is_enabled() && mdns.negative_ttl() > 0 {
+                    cache_without_id(cache, &cache_key, &mut response, Some(mdns.negative_ttl()), client_ecs.as_ref()).await?;
+                }
+                response
+            },
+        };
+        post_process_response(&mut response, dns_config);
+        return Ok((response, None));
+    }
+    
+    // 使用路由器确定上游组 - 使用 ASCII 形式（国际化域名为 punycode），与规范化后的规则一致
+    let domain_name = query.name().to_ascii();
+    let route_decision = state.router.match_query(&domain_name, client_ip).await;
     
     // 记录路由结果指标
     match &route_decision {
-        RouteDecision::UseGroup(_) => {
+        RouteDecision::UseGroup(_) | RouteDecision::UseGroups(_) | RouteDecision::UseGroupsWithFailover { .. } => {
             METRICS.route_results_total()
                 .with_label_values(&[ROUTE_RESULT_RULE_MATCH])
                 .inc();
@@ -1158,9 +2127,18 @@ async fn process_query(
         },
     }
     
-    // 选择上游
-    let upstream_selection = match route_decision {
-        RouteDecision::UseGroup(group_name) => UpstreamSelection::Group(group_name),
+    // 选择上游，规则配置了故障转移链时一并取出
+    let (upstream_selection, failover_chain) = match route_decision {
+        RouteDecision::UseGroup(group_name) => (UpstreamSelection::Group(group_name), Vec::new()),
+        RouteDecision::UseGroups(group_names) => (UpstreamSelection::Groups(group_names), Vec::new()),
+        RouteDecision::UseGroupsWithFailover { mut groups, failover_chain } => {
+            let selection = if groups.len() == 1 {
+                UpstreamSelection::Group(groups.remove(0))
+            } else {
+                UpstreamSelection::Groups(groups)
+            };
+            (selection, failover_chain)
+        },
         RouteDecision::Blackhole => {
             // 黑洞策略 - 创建一个响应，直接重用查询信息
             let mut response = Message::new();
@@ -1183,41 +2161,449 @@ async fn process_query(
             }
             
             // 不缓存黑洞响应
-            return Ok((response, false));
+            return Ok((response, None));
+        },
+        RouteDecision::UseGlobal => (UpstreamSelection::Global, Vec::new()),
+    };
+    
+    // 缓存命中不计入速率限制时，仅在需要查询上游的请求上消耗配额
+    check_deferred_rate_limit()?;
+    
+    // 启用后台未命中解析时，由后台任务查询上游，并发的相同查询共享结果
+    if cache.is_enabled() && dns_config.cache.async_miss_resolution {
+        let upstream = state.upstream.clone();
+        let background_cache = state.cache.clone();
+        let background_query = query_message.clone();
+        let background_ecs = client_ecs.clone();
+        let background_key = cache_key.clone();
+        // 后台任务沿用当前请求的 span 与请求 ID，使其日志与上游请求可以关联到触发的请求
+        let resolve = async move {
+            match resolve_and_cache(
+                &upstream,
+                &background_cache,
+                &background_query,
+                upstream_selection,
+                &failover_chain,
+                client_ip,
+                background_ecs.as_ref(),
+                &background_key,
+            ).await {
+                Ok(response) => Some(response),
+                Err(e) => {
+                    warn!(error = %e, "Background cache miss resolution failed");
+                    None
+                }
+            }
+        }.instrument(Span::current());
+        let pending = match current_request_id() {
+            Some(request_id) => cache.prefetch_on_miss(&cache_key, with_request_id(request_id, resolve)),
+            None => cache.prefetch_on_miss(&cache_key, resolve),
+        };
+        
+        let timeout = Duration::from_millis(dns_config.cache.async_miss_resolution_timeout_ms);
+        let result = match pending {
+            // 首个请求立即返回 SERVFAIL，由后台任务完成解析
+            PendingMiss::Started(_) if dns_config.cache.return_servfail_on_miss => {
+                return Ok((build_servfail_response(query_message), None));
+            },
+            // 首个请求等待自己发起的解析，耗时受上游查询超时约束
+            PendingMiss::Started(_) => pending.wait(None).await,
+            PendingMiss::Joined(_) => pending.wait(Some(timeout)).await,
+        };
+        
+        let Some(mut response) = result else {
+            return Err(ServerError::UpstreamUnavailable(
+                "Background cache miss resolution failed or timed out".to_string()
+            ));
+        };
+        adopt_client_query(&mut response, query_message);
+        post_process_response(&mut response, dns_config);
+        return Ok((response, None));
+    }
+    
+    let mut response = resolve_and_cache(
+        &state.upstream,
+        cache,
+        query_message,
+        upstream_selection,
+        &failover_chain,
+        client_ip,
+        client_ecs.as_ref(),
+        &cache_key,
+    ).await?;
+    
+    // 缓存保存上游原始响应，仅调整返回给客户端的副本
+    post_process_response(&mut response, dns_config);
+    
+    Ok((response, None))
+}
+
+// 处理 wireformat 查询，相同的并发请求共享一次解析
+async fn process_query_deduplicated(
+    state: &ServerState,
+    query_message: &Message,
+    query_wire: &[u8],
+    client_ip: Option<IpAddr>,
+    upstream_override: Option<&str>,
+) -> Result<(Message, Option<u64>)> {
+    let leader = match state.pending_requests.join(query_wire, client_ip, upstream_override) {
+        PendingRequest::Leader(leader) => leader,
+        PendingRequest::Waiter(mut receiver) => {
+            let bytes = match receiver.recv().await {
+                Ok(shared) => shared.map_err(|e| shared_resolution_error(&e))?,
+                Err(e) => return Err(ServerError::UpstreamUnavailable(format!(
+                    "Shared resolution of identical request was lost: {}", e
+                ))),
+            };
+            return Ok((Message::from_vec(&bytes)?, None));
         },
-        RouteDecision::UseGlobal => UpstreamSelection::Global,
     };
     
-    // 查询上游，传递客户端 IP 和 ECS 数据 - 避免临时变量
-    let response = upstream.resolve(
-        query_message, 
-        upstream_selection, 
-        Some(client_ip), 
-        client_ecs.as_ref()
+    let result = process_query(state, query_message, client_ip, upstream_override).await;
+    leader.complete(result.as_ref().map(|(response, _)| response));
+    result
+}
+
+// 查询上游并缓存响应，返回上游原始响应
+#[allow(clippy::too_many_arguments)]
+async fn resolve_and_cache(
+    upstream: &UpstreamManager,
+    cache: &DnsCache,
+    query_message: &Message,
+    upstream_selection: UpstreamSelection,
+    failover_chain: &[String],
+    client_ip: Option<IpAddr>,
+    client_ecs: Option<&EcsData>,
+    cache_key: &CacheKey,
+) -> Result<Message> {
+    // 查询上游，传递客户端 IP 和 ECS 数据
+    let mut response = resolve_with_failover(
+        upstream,
+        query_message,
+        upstream_selection,
+        failover_chain,
+        client_ip,
+        client_ecs,
     ).await?;
     
-    // 判断响应代码，避免重复检查
-    let response_code = response.response_code();
-    let cache_enabled = cache.is_enabled();
-    
-    // 缓存响应
-    if cache_enabled {
-        if response_code == ResponseCode::NoError {
-            cache.put_with_auto_ttl_and_ecs(&cache_key, &response, client_ecs.as_ref()).await?;
-        } else if response_code == ResponseCode::NXDomain {
-            // 缓存负响应
-            let negative_ttl = cache.negative_ttl();
-            cache.put_with_ecs(&cache_key, &response, negative_ttl, client_ecs.as_ref()).await?;
+    // 缓存响应，截断响应与配置的错误响应码由缓存自行跳过
+    if cache.is_enabled() {
+        // 肯定响应按记录 TTL 缓存，负响应使用负缓存 TTL
+        let ttl = (response.response_code() != ResponseCode::NoError).then(|| cache.negative_ttl());
+        cache_without_id(cache, cache_key, &mut response, ttl, client_ecs).await?;
+    }
+    
+    Ok(response)
+}
+
+// 查询上游；查询失败（包括超时）或返回 SERVFAIL 时按顺序改用故障转移链中的上游组，
+// 返回第一个成功的响应，故障转移链用尽时返回最后一次的结果
+async fn resolve_with_failover(
+    upstream: &UpstreamManager,
+    query_message: &Message,
+    upstream_selection: UpstreamSelection,
+    failover_chain: &[String],
+    client_ip: Option<IpAddr>,
+    client_ecs: Option<&EcsData>,
+) -> Result<Message> {
+    // 上游组列表以首选组作为故障转移的来源
+    let mut source_group = match &upstream_selection {
+        UpstreamSelection::Group(group) => group.clone(),
+        UpstreamSelection::Groups(groups) => groups.first().cloned().unwrap_or_default(),
+        UpstreamSelection::Global => "global".to_string(),
+    };
+    let mut result = upstream.resolve(query_message, upstream_selection, client_ip, client_ecs).await;
+    
+    for next_group in failover_chain {
+        let reason = match &result {
+            Ok(response) if response.response_code() != ResponseCode::ServFail => break,
+            Ok(_) => "SERVFAIL".to_string(),
+            Err(e) => e.to_string(),
+        };
+        warn!(
+            upstream_group = %source_group,
+            next_group = %next_group,
+            reason = %reason,
+            "Upstream group failed, failing over to next group in chain"
+        );
+        METRICS.upstream_failover_total()
+            .with_label_values(&[&source_group, next_group])
+            .inc();
+        
+        result = upstream.resolve(
+            query_message,
+            UpstreamSelection::Group(next_group.clone()),
+            client_ip,
+            client_ecs,
+        ).await;
+        source_group = next_group.clone();
+    }
+    
+    result
+}
+
+// 缓存条目与请求方无关，不保存消息 ID（写入 0）；命中时由 adopt_client_query 写入当前请求的 ID。
+// ttl 为 None 时按响应记录的 TTL 自动计算
+async fn cache_without_id(
+    cache: &DnsCache,
+    cache_key: &CacheKey,
+    response: &mut Message,
+    ttl: Option<u32>,
+    client_ecs: Option<&EcsData>,
+) -> Result<()> {
+    let request_id = response.id();
+    response.set_id(0);
+    let result = match ttl {
+        Some(ttl) => cache.put_with_ecs(cache_key, response, ttl, client_ecs).await,
+        None => cache.put_with_auto_ttl_and_ecs(cache_key, response, client_ecs).await,
+    };
+    response.set_id(request_id);
+    result
+}
+
+// 共享的响应（缓存命中或合并的后台解析）使用当前客户端的查询 ID 与问题部分，
+// 缓存键不区分大小写，问题部分需保留客户端查询名的原始大小写
+fn adopt_client_query(response: &mut Message, query_message: &Message) {
+    response.set_id(query_message.id());
+    response.take_queries();
+    response.add_queries(query_message.queries().to_vec());
+}
+
+// 对返回给客户端的响应进行后处理
+fn post_process_response(message: &mut Message, dns_config: &DnsResolverConfig) {
+    flatten_cname_chain(message, dns_config);
+    strip_answer_record_types(message, dns_config);
+    apply_response_ttl_overrides(message, dns_config);
+}
+
+// 沿 CNAME 链展平 A/AAAA 应答，只使用应答中已有的记录，不发起额外的上游查询。
+// 链中出现环路或没有以地址记录结束时保持响应不变
+fn flatten_cname_chain(message: &mut Message, dns_config: &DnsResolverConfig) {
+    if !dns_config.flatten_cname || message.response_code() != ResponseCode::NoError {
+        return;
+    }
+    let Some(query) = message.queries().first().cloned() else {
+        return;
+    };
+    let query_type = query.query_type();
+    if !matches!(query_type, RecordType::A | RecordType::AAAA) {
+        return;
+    }
+    
+    // 从查询名称出发沿 CNAME 链前进，记录经过的 CNAME
+    let answers = message.answers();
+    let mut chain: Vec<&Record> = Vec::new();
+    let mut current = query.name().clone();
+    while let Some(cname) = answers.iter().find(|record| {
+        record.record_type() == RecordType::CNAME && *record.name() == current
+    }) {
+        let Some(RData::CNAME(target)) = cname.data() else {
+            return;
+        };
+        if target.0 == *query.name() || chain.iter().any(|link| *link.name() == target.0) {
+            debug!(name = %query.name(), target = %target.0, "CNAME loop in answer, leaving response unflattened");
+            return;
+        }
+        chain.push(cname);
+        current = target.0.clone();
+    }
+    
+    // 链的终点必须有请求类型的地址记录，按记录内容去重
+    let mut finals: Vec<Record> = Vec::new();
+    for record in answers.iter().filter(|record| record.record_type() == query_type && *record.name() == current) {
+        if !finals.iter().any(|existing| existing.data() == record.data()) {
+            finals.push(record.clone());
+        }
+    }
+    if finals.is_empty() {
+        return;
+    }
+    
+    let flattened: Vec<Record> = if dns_config.flatten_cname_strip_chain {
+        // 展平后的记录不能比链上任一环节存活更久，TTL 取链上的最小值；改写后的记录没有对应的 RRSIG
+        let ttl = chain.iter().map(|link| link.ttl())
+            .chain(finals.iter().map(|record| record.ttl()))
+            .min()
+            .unwrap_or_default();
+        finals.into_iter()
+            .map(|mut record| {
+                record.set_name(query.name().clone()).set_ttl(ttl);
+                record
+            })
+            .collect()
+    } else {
+        // 保留链：每个 CNAME 只出现一次并按链的顺序排列，再接地址记录，其余无关记录被丢弃
+        chain.into_iter().cloned().chain(finals).collect()
+    };
+    
+    message.take_answers();
+    message.add_answers(flattened);
+}
+
+// 从应答部分移除配置的记录类型及其 RRSIG；应答被清空时即为 NODATA 响应
+fn strip_answer_record_types(message: &mut Message, dns_config: &DnsResolverConfig) {
+    if dns_config.strip_record_types.is_empty() {
+        return;
+    }
+    // 配置已在启动时校验，无法解析的条目不会出现
+    let strip_types = dns_config.parsed_strip_record_types().unwrap_or_default();
+    
+    message.answers_mut().retain(|record| {
+        let record_type = match record.data() {
+            Some(RData::DNSSEC(DNSSECRData::RRSIG(rrsig))) => rrsig.type_covered(),
+            _ => record.record_type(),
+        };
+        !strip_types.contains(&record_type)
+    });
+}
+
+// 按配置的上下限调整返回给客户端的记录 TTL
+fn apply_response_ttl_overrides(message: &mut Message, dns_config: &DnsResolverConfig) {
+    let min = dns_config.min_response_ttl_override.map(|ttl| ttl.min(u32::MAX as u64) as u32);
+    let max = dns_config.max_response_ttl_override.map(|ttl| ttl.min(u32::MAX as u64) as u32);
+    if min.is_none() && max.is_none() {
+        return;
+    }
+    
+    let clamp = |record: &mut Record| {
+        let mut ttl = record.ttl();
+        if let Some(min) = min {
+            ttl = ttl.max(min);
+        }
+        if let Some(max) = max {
+            ttl = ttl.min(max);
+        }
+        record.set_ttl(ttl);
+    };
+    
+    message.answers_mut().iter_mut().for_each(clamp);
+    message.name_servers_mut().iter_mut().for_each(clamp);
+    message.additionals_mut().iter_mut().for_each(clamp);
+}
+
+// 查询包含 OPT 记录时，对 wireformat 响应进行 EDNS 填充
+// 启用 security.padding 时始终填充；仅启用 edns_padding 时只在 TLS 下填充
+fn pad_wire_response(config: &ServerConfig, query_message: &Message, response_message: &mut Message) {
+    let padding = &config.security.padding;
+    let tls_edns_padding = config.dns.upstream.edns_padding && config.http.tls.is_some();
+    if !(padding.enabled || tls_edns_padding) || query_message.extensions().is_none() {
+        return;
+    }
+    
+    match pad_message(response_message, padding.block_size) {
+        Ok(padding_len) => {
+            METRICS.server_response_padding_bytes_total().inc_by(padding_len as u64);
+        },
+        Err(e) => {
+            warn!(error = %e, "Failed to pad DNS response");
         }
     }
+}
+
+// 构建上游全部不可达时的 SERVFAIL 响应
+fn build_servfail_response(query_message: &Message) -> Message {
+    let mut response = Message::new();
+    response.set_id(query_message.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(query_message.op_code())
+        .set_recursion_desired(query_message.recursion_desired())
+        // RA 置 0，表明服务器当前无法进行递归解析
+        .set_recursion_available(false)
+        .set_checking_disabled(query_message.checking_disabled())
+        .set_response_code(ResponseCode::ServFail);
+    
+    // 复制查询部分
+    for q in query_message.queries() {
+        response.add_query(q.clone());
+    }
+    
+    response
+}
+
+// error_mode 为 dns_rcode 时上游解析失败返回 200 与 SERVFAIL 消息
+fn responds_servfail_on_failure(config: &ServerConfig, error: &ServerError) -> bool {
+    config.http.error_mode == ErrorMode::DnsRcode && error.upstream_failure_kind().is_some()
+}
+
+// 查询处理失败时的 HTTP 状态码与响应体。上游解析失败（error_mode 为 http_status）按类别映射：
+// 超时 504，不可达 502，校验失败 500，响应体只包含状态描述；其余错误返回 500 与错误信息
+fn processing_error_response(error: &ServerError) -> (StatusCode, String) {
+    let status = match error.upstream_failure_kind() {
+        Some(UpstreamFailureKind::Timeout) => StatusCode::GATEWAY_TIMEOUT,
+        Some(UpstreamFailureKind::Unreachable) => StatusCode::BAD_GATEWAY,
+        Some(UpstreamFailureKind::Validation) => StatusCode::INTERNAL_SERVER_ERROR,
+        None => return (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()),
+    };
+    (status, status.canonical_reason().unwrap_or_default().to_string())
+}
+
+// 为 SERVFAIL 响应添加 Retry-After 头，提示客户端退避重试：上游全部降级时使用预计的恢复时间（至少 1 秒），
+// 否则使用配置的 servfail_retry_after_secs
+fn apply_servfail_retry_after(response: &mut Response, state: &ServerState, rcode: ResponseCode) {
+    if rcode != ResponseCode::ServFail {
+        return;
+    }
+    let retry_after = state.upstream.estimated_recovery_time()
+        .map_or(state.config.http.servfail_retry_after_secs, |recovery| recovery.as_secs().max(1));
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+}
+
+// 记录 DNS 响应的 wireformat 大小，超过配置阈值时输出警告
+fn observe_response_size(config: &ServerConfig, format: &str, query_message: &Message, size: usize) {
+    METRICS.dns_response_size_bytes()
+        .with_label_values(&[format])
+        .observe(size as f64);
+    
+    if let Some(threshold) = config.http.alert_on_response_larger_than_bytes.filter(|threshold| size > *threshold) {
+        let (domain, qtype) = query_message.queries().first()
+            .map(|q| (q.name().to_utf8(), format!("{:?}", q.query_type())))
+            .unwrap_or_default();
+        warn!(
+            domain = %domain,
+            qtype = %qtype,
+            response_size = size,
+            threshold,
+            "DNS response exceeds size alert threshold"
+        );
+    }
+}
+
+// 为 wireformat 响应添加 HTTP 缓存头（RFC 8484 第 5.1 节）
+// 带应答记录的成功响应使用应答记录的最小 TTL 作为 max-age，来自 DNS 缓存时按条目存在时间递减；
+// 负响应（NXDOMAIN、无应答记录）、错误响应与不可缓存的响应使用 max-age=0, no-store
+fn apply_cache_headers(response: &mut Response, config: &ServerConfig, cacheable: Option<&Message>, cache_age: Option<u64>) {
+    if !config.http.emit_cache_headers {
+        return;
+    }
     
-    Ok((response, false))
+    let min_ttl = cacheable
+        .filter(|message| message.response_code() == ResponseCode::NoError)
+        .and_then(|message| message.answers().iter()
+            .filter(|record| record.record_type() != RecordType::OPT)
+            .map(|record| record.ttl())
+            .min());
+    let Some(min_ttl) = min_ttl else {
+        response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static(CACHE_CONTROL_NO_STORE));
+        return;
+    };
+    
+    let max_age = u64::from(min_ttl).saturating_sub(cache_age.unwrap_or(0));
+    if let Ok(value) = HeaderValue::from_str(&format!("max-age={}", max_age)) {
+        response.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
 }
 
 // 从 JSON 请求创建 DNS 查询消息
 fn create_dns_message_from_json_request(request: &DnsJsonRequest) -> Result<Message> {
+    // 国际化域名先转换为 punycode，与 wireformat 查询中的名称（以及路由、缓存）保持一致
+    let name = match request.name.is_ascii() {
+        true => Cow::Borrowed(request.name.as_str()),
+        false => Cow::Owned(normalize_query_domain(request.name.trim())),
+    };
+    
     // 解析域名 - 验证输入域名的合法性
-    let name = match Name::parse(&request.name, None) {
+    let name = match Name::parse(&name, Some(&Name::root())) {
+        // 与 wireformat 查询保持一致，使用完全限定域名以共享缓存
         Ok(name) => name,
         Err(e) => {
             // 使用静态字符串减少分配
@@ -1227,14 +2613,7 @@ fn create_dns_message_from_json_request(request: &DnsJsonRequest) -> Result<Mess
     };
     
     // 解析记录类型
-    let rtype = match RecordType::from(request.type_value) {
-        RecordType::Unknown(..) => {
-            // 使用静态字符串减少分配
-            let error_msg = format!("Invalid record type: {}", request.type_value);
-            return Err(ServerError::Http(error_msg));
-        }
-        rt => rt,
-    };
+    let rtype = parse_record_type(&request.record_type)?;
     
     // 解析 DNS 类
     let _dns_class = match request.dns_class {
@@ -1260,6 +2639,13 @@ fn create_dns_message_from_json_request(request: &DnsJsonRequest) -> Result<Mess
         .set_op_code(OpCode::Query)
         .set_checking_disabled(request.cd)
         .set_recursion_desired(true);
+    
+    // 设置 DNSSEC OK (DO) 位
+    if request.dnssec {
+        let mut edns = Edns::new();
+        edns.set_dnssec_ok(true);
+        message.set_edns(edns);
+    }
         
     // 添加查询
     let query = hickory_proto::op::Query::query(name, rtype);
@@ -1268,12 +2654,22 @@ fn create_dns_message_from_json_request(request: &DnsJsonRequest) -> Result<Mess
     Ok(message)
 }
 
+// 解析 JSON API 中的记录类型，支持名称（不区分大小写）或数值
+fn parse_record_type(value: &str) -> Result<RecordType> {
+    let rtype = match value.parse::<u16>() {
+        Ok(num) => RecordType::from(num),
+        Err(_) => RecordType::from_str(&value.to_ascii_uppercase())
+            .map_err(|_| ServerError::Http(format!("Invalid record type: {}", value)))?,
+    };
+    
+    match rtype {
+        RecordType::Unknown(..) => Err(ServerError::Http(format!("Invalid record type: {}", value))),
+        rt => Ok(rt),
+    }
+}
+
 // 将 DNS 响应消息转换为 JSON 响应
 fn dns_message_to_json_response(message: &Message) -> Result<DnsJsonResponse> {
-    // 获取消息元素数量，用于预分配空间
-    let query_count = message.queries().len();
-    let answer_count = message.answers().len();
-    
     // 创建响应对象，预分配空间以减少内存重分配
     let mut response = DnsJsonResponse {
         status: u16::from(message.response_code().low()),
@@ -1282,50 +2678,97 @@ fn dns_message_to_json_response(message: &Message) -> Result<DnsJsonResponse> {
         ra: message.recursion_available(),
         ad: message.authentic_data(),
         cd: message.checking_disabled(),
-        question: Vec::with_capacity(query_count),
-        answer: Vec::with_capacity(answer_count),
+        question: Vec::with_capacity(message.queries().len()),
+        answer: Vec::with_capacity(message.answers().len()),
+        authority: Vec::with_capacity(message.name_servers().len()),
     };
     
     // 添加查询
     for query in message.queries() {
-        // 提前获取name字符串，减少重复转换
-        let name_str = query.name().to_utf8();
-        let type_value = query.query_type().into();
-        
         response.question.push(DnsJsonQuestion {
-            name: name_str,
-            type_value,
+            name: query.name().to_ascii(),
+            record_type: query.query_type().into(),
         });
     }
     
-    // 添加应答记录
-    for record in message.answers() {
-        let data = match record.data() {
-            Some(rdata) => rdata.to_string(),
-            None => continue,
-        };
+    // 添加应答记录和权威记录
+    for (records, target) in [
+        (message.answers(), &mut response.answer),
+        (message.name_servers(), &mut response.authority),
+    ] {
+        for record in records {
+            let data = match record.data() {
+                Some(rdata) => format_rdata_for_json(rdata),
+                None => continue,
+            };
+            
+            target.push(DnsJsonAnswer {
+                name: record.name().to_ascii(),
+                record_type: record.record_type().into(),
+                ttl: record.ttl(),
+                data,
+            });
+        }
+    }
+    
+    Ok(response)
+}
+
+// 按记录类型渲染 JSON 响应中的 data 字段
+fn format_rdata_for_json(rdata: &RData) -> String {
+    match rdata {
+        // TXT 记录的每个字符串分别加引号，与 Google/Cloudflare 保持一致
+        RData::TXT(txt) => txt
+            .txt_data()
+            .iter()
+            .map(|part| format!("\"{}\"", String::from_utf8_lossy(part).replace('"', "\\\"")))
+            .collect::<Vec<_>>()
+            .join(" "),
+        other => other.to_string(),
+    }
+}
+
+// 解析布尔型查询参数，兼容 "1"/"0"、"true"/"false" 以及 JSON 布尔值
+fn deserialize_flag<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct FlagVisitor;
+    
+    impl serde::de::Visitor<'_> for FlagVisitor {
+        type Value = bool;
         
-        // 提前获取name字符串，减少重复转换
-        let name_str = record.name().to_utf8();
-        let type_value = record.record_type().into();
-        let class = record.dns_class().into();
-        let ttl = record.ttl();
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a boolean flag (true/false/1/0)")
+        }
         
-        response.answer.push(DnsJsonAnswer {
-            name: name_str,
-            type_value,
-            class,
-            ttl,
-            data,
-        });
+        fn visit_bool<E: serde::de::Error>(self, value: bool) -> std::result::Result<bool, E> {
+            Ok(value)
+        }
+        
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> std::result::Result<bool, E> {
+            Ok(value != 0)
+        }
+        
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> std::result::Result<bool, E> {
+            Ok(value != 0)
+        }
+        
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> std::result::Result<bool, E> {
+            match value.to_ascii_lowercase().as_str() {
+                "" | "0" | "false" => Ok(false),
+                "1" | "true" => Ok(true),
+                _ => Err(E::invalid_value(serde::de::Unexpected::Str(value), &self)),
+            }
+        }
     }
     
-    Ok(response)
+    deserializer.deserialize_any(FlagVisitor)
 }
 
 // 默认值函数
-fn default_record_type() -> u16 {
-    DNS_RECORD_TYPE_A
+fn default_record_type() -> String {
+    DNS_RECORD_TYPE_A.to_string()
 }
 
 fn default_dns_class() -> Option<u16> {
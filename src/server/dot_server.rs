@@ -0,0 +1,197 @@
+// src/server/dot_server.rs
+
+// 该模块提供 DNS-over-TLS（RFC 7858）监听，供 Android「私人 DNS」等只支持 DoT 的客户端使用。
+//
+// 查询通过与经典 DNS 监听相同的流程进入缓存、路由与上游。每个连接上的消息带两字节长度前缀，
+// 同一连接可以同时有多个进行中的查询，响应按完成顺序写回，客户端通过消息 ID 匹配（RFC 7766 6.2.1.1）。
+// 证书由独立的 TLS 上下文提供，收到 SIGHUP 时与 HTTPS 证书一同热替换，已建立的连接不受影响。
+// 没有进行中的查询且超过空闲超时未收到新查询的连接会被关闭。
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use bytes::{Buf, BytesMut};
+use hickory_proto::op::Message;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+use crate::common::consts::{DOT_MAX_IN_FLIGHT_PER_CONNECTION, TLS_HANDSHAKE_TIMEOUT_SECS};
+use crate::server::config::DotServerConfig;
+use crate::server::dns_server::{answer_query, encode_tcp_response, RunningGuard};
+use crate::server::doh_handler::ServerState;
+use crate::server::drain::DrainController;
+use crate::server::error::Result;
+use crate::server::health::HealthSource;
+use crate::server::metrics::METRICS;
+use crate::server::proxy_protocol::{client_addr, ProxyProtocol};
+use crate::server::tls::TlsContext;
+
+// 指标中的监听标签
+const LISTENER_DOT: &str = "dot";
+
+// 长度前缀的字节数
+const LENGTH_PREFIX_SIZE: usize = 2;
+
+// 连接读取缓冲区的初始大小
+const DOT_READ_BUFFER_SIZE: usize = 4096;
+
+// DNS-over-TLS 监听
+pub struct DotServer {
+    // 与 DoH 处理器共享的服务器状态
+    state: ServerState,
+    // 进行中查询跟踪（关闭时排空）
+    drain: Arc<DrainController>,
+    // 证书上下文（收到 SIGHUP 时重新加载）
+    tls: Arc<TlsContext>,
+    // 与证书上下文共享解析器的 TLS 接收器
+    acceptor: TlsAcceptor,
+    // 监听配置
+    config: DotServerConfig,
+    // PROXY 协议解析器（未启用时为 None）
+    proxy_protocol: Option<ProxyProtocol>,
+    // 监听是否正在运行
+    running: AtomicBool,
+}
+
+impl DotServer {
+    // 加载证书并创建 DoT 监听，证书无效或与私钥不匹配时返回错误
+    pub fn new(state: ServerState, drain: Arc<DrainController>, config: DotServerConfig) -> Result<Self> {
+        let tls = Arc::new(TlsContext::new(&config.tls_config())?);
+        let acceptor = tls.dot_acceptor()?;
+        let proxy_protocol = config.proxy_protocol
+            .then(|| ProxyProtocol::from_config(&state.config.http, LISTENER_DOT));
+        Ok(Self {
+            state,
+            drain,
+            tls,
+            acceptor,
+            config,
+            proxy_protocol,
+            running: AtomicBool::new(false),
+        })
+    }
+
+    // 证书上下文，用于热替换证书
+    pub fn tls(&self) -> Arc<TlsContext> {
+        self.tls.clone()
+    }
+
+    // 在 TCP 监听上提供 DoT 服务，每个连接在独立任务中处理
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> io::Result<()> {
+        let _running = RunningGuard::new(&self.running);
+
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept DoT connection: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            let server = self.clone();
+            tokio::spawn(async move {
+                METRICS.dot_server_connections().inc();
+                if let Err(e) = server.serve_connection(stream, peer).await {
+                    debug!(client_ip = %peer.ip(), "DoT connection closed with error: {}", e);
+                }
+                METRICS.dot_server_connections().dec();
+            });
+        }
+    }
+
+    // 处理单个 DoT 连接：持续读取查询并并发解析，响应按完成顺序写回
+    //
+    // 对端关闭写方向后处理完已收到的查询再关闭；排空期间的查询不再应答并关闭连接。
+    async fn serve_connection(self: &Arc<Self>, mut stream: TcpStream, peer: SocketAddr) -> io::Result<()> {
+        let peer = client_addr(self.proxy_protocol.as_ref(), &mut stream, peer).await?;
+        let handshake_timeout = Duration::from_secs(TLS_HANDSHAKE_TIMEOUT_SECS);
+        let tls_stream = timeout(handshake_timeout, self.acceptor.accept(stream)).await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "DoT TLS handshake timed out"))??;
+        let (mut reader, mut writer) = tokio::io::split(tls_stream);
+
+        let idle_timeout = Duration::from_secs(self.config.idle_timeout);
+        let mut buf = BytesMut::with_capacity(DOT_READ_BUFFER_SIZE);
+        let mut in_flight = JoinSet::new();
+        let mut reading = true;
+
+        loop {
+            // 已缓冲的完整查询在进行中查询数未达上限时立即开始解析
+            while in_flight.len() < DOT_MAX_IN_FLIGHT_PER_CONNECTION {
+                let Some(wire) = take_frame(&mut buf) else {
+                    break;
+                };
+                let server = self.clone();
+                in_flight.spawn(async move { server.answer(&wire, peer.ip()).await });
+            }
+            if !reading && in_flight.is_empty() {
+                break;
+            }
+
+            tokio::select! {
+                read = reader.read_buf(&mut buf), if reading && in_flight.len() < DOT_MAX_IN_FLIGHT_PER_CONNECTION => {
+                    if read? == 0 {
+                        reading = false;
+                    }
+                },
+                Some(joined) = in_flight.join_next() => {
+                    let Ok(Some(response)) = joined else {
+                        break;
+                    };
+                    writer.write_all(&encode_tcp_response(&response)?).await?;
+                },
+                _ = tokio::time::sleep(idle_timeout), if in_flight.is_empty() => {
+                    debug!(client_ip = %peer.ip(), "Closing idle DoT connection");
+                    break;
+                },
+            }
+        }
+
+        writer.shutdown().await
+    }
+
+    async fn answer(&self, wire: &[u8], client_ip: IpAddr) -> Option<Message> {
+        answer_query(&self.state, &self.drain, wire, client_ip, LISTENER_DOT).await
+            .map(|(response, _)| response)
+    }
+}
+
+impl HealthSource for DotServer {
+    // 配置的监听未在运行时报告问题
+    fn health_issue(&self) -> Option<String> {
+        self.config.listen_addr
+            .filter(|_| !self.running.load(Ordering::Relaxed))
+            .map(|addr| format!("DoT listener on {} is not running", addr))
+    }
+}
+
+// 绑定配置的 DoT 监听
+pub async fn bind_dot_server(config: &DotServerConfig) -> io::Result<Option<TcpListener>> {
+    let Some(addr) = config.listen_addr else {
+        return Ok(None);
+    };
+    let listener = TcpListener::bind(addr).await.map_err(|e| io::Error::new(
+        e.kind(), format!("Failed to bind DoT listener to {}: {}", addr, e)
+    ))?;
+    info!("DoT server listening on: {}", addr);
+    Ok(Some(listener))
+}
+
+// 从缓冲区取出一个完整的带长度前缀的消息，数据不足时返回 None
+fn take_frame(buf: &mut BytesMut) -> Option<BytesMut> {
+    if buf.len() < LENGTH_PREFIX_SIZE {
+        return None;
+    }
+    let len = u16::from_be_bytes([buf[0], buf[1]]) as usize;
+    if buf.len() < LENGTH_PREFIX_SIZE + len {
+        return None;
+    }
+    buf.advance(LENGTH_PREFIX_SIZE);
+    Some(buf.split_to(len))
+}
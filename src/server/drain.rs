@@ -0,0 +1,126 @@
+// src/server/drain.rs
+
+// 该模块负责关闭时的连接排空。
+//
+// 每个 DoH 请求（以及经典 DNS 监听上的查询）在处理期间持有信号量的一个许可，用于统计进行中的查询。
+// 收到关闭信号后进入排空状态：新请求直接返回 503 并要求关闭连接，
+// 随后等待全部许可归还；超过宽限期仍未完成的查询立即返回 503 并计入指标。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, Response, StatusCode},
+    middleware::{self, Next},
+    Router,
+};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+use tracing::{info, warn};
+use crate::common::consts::MAX_TRACKED_IN_FLIGHT_QUERIES;
+use crate::server::metrics::METRICS;
+
+// 进行中查询跟踪与关闭排空
+pub struct DrainController {
+    // 每个进行中的查询持有一个许可
+    in_flight: Arc<Semaphore>,
+    // 是否已开始排空
+    draining: AtomicBool,
+    // 等待进行中查询完成的宽限期
+    grace_period: Duration,
+    // 宽限期是否已结束，结束后进行中的查询放弃处理并返回 503
+    expired: watch::Sender<bool>,
+}
+
+impl DrainController {
+    pub fn new(grace_period: Duration) -> Self {
+        Self {
+            in_flight: Arc::new(Semaphore::new(MAX_TRACKED_IN_FLIGHT_QUERIES as usize)),
+            draining: AtomicBool::new(false),
+            grace_period,
+            expired: watch::Sender::new(false),
+        }
+    }
+    
+    // 当前进行中的查询数
+    pub fn in_flight(&self) -> usize {
+        MAX_TRACKED_IN_FLIGHT_QUERIES as usize - self.in_flight.available_permits()
+    }
+    
+    // 是否已开始排空
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+    
+    // 开始处理一个查询，返回的许可在查询结束时释放；排空期间返回 None
+    pub fn begin_query(&self) -> Option<QueryPermit> {
+        if self.is_draining() {
+            return None;
+        }
+        // 许可耗尽时不跟踪该查询，而不是拒绝请求
+        Some(QueryPermit { _permit: self.in_flight.clone().try_acquire_owned().ok() })
+    }
+    
+    // 等待宽限期结束，进行中的查询此时应放弃处理
+    pub async fn expired(&self) {
+        let mut expired = self.expired.subscribe();
+        let _ = expired.wait_for(|expired| *expired).await;
+    }
+    
+    // 停止接受新请求，等待进行中的查询完成，返回宽限期结束时仍未完成的查询数
+    pub async fn drain(&self) -> usize {
+        self.draining.store(true, Ordering::Relaxed);
+        info!(
+            in_flight = self.in_flight(),
+            grace_period_secs = self.grace_period.as_secs(),
+            "Draining in-flight queries"
+        );
+        
+        let all_permits = self.in_flight.acquire_many(MAX_TRACKED_IN_FLIGHT_QUERIES);
+        match tokio::time::timeout(self.grace_period, all_permits).await {
+            Ok(_) => {
+                info!("All in-flight queries completed");
+                0
+            },
+            Err(_) => {
+                let dropped = self.in_flight();
+                METRICS.shutdown_dropped_queries_total().inc_by(dropped as u64);
+                warn!(dropped, "Shutdown grace period elapsed, answering in-flight queries with 503");
+                self.expired.send_replace(true);
+                dropped
+            },
+        }
+    }
+}
+
+// 进行中查询持有的许可
+pub struct QueryPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+// 为路由添加进行中查询跟踪，排空期间拒绝新请求
+pub fn apply_drain(app: Router, drain: Arc<DrainController>) -> Router {
+    app.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let drain = drain.clone();
+        async move { handle_drain(&drain, req, next).await }
+    }))
+}
+
+async fn handle_drain(drain: &DrainController, req: Request, next: Next) -> Response<Body> {
+    let Some(_permit) = drain.begin_query() else {
+        return draining_response();
+    };
+    tokio::select! {
+        response = next.run(req) => response,
+        _ = drain.expired() => draining_response(),
+    }
+}
+
+// 排空期间的 503 响应，要求客户端关闭连接并改用其他实例
+fn draining_response() -> Response<Body> {
+    let mut response = Response::new(Body::from("Server is shutting down"));
+    *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    response.headers_mut().insert(header::CONNECTION, HeaderValue::from_static("close"));
+    response
+}
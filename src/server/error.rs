@@ -5,6 +5,7 @@ use std::result;
 use thiserror::Error;
 use hickory_proto::error::ProtoError;
 use hickory_resolver::error::ResolveError;
+use crate::common::consts::{UPSTREAM_TIMEOUT_REASON, UPSTREAM_VALIDATION_FAILED_REASON};
 
 // 服务器错误类型
 #[derive(Debug, Error)]
@@ -29,6 +30,22 @@ pub enum ServerError {
     #[error("Serialization error: {0}")]
     Serde(#[from] serde_yaml::Error),
     
+    // TLS 错误
+    #[error("TLS error: {0}")]
+    Tls(String),
+    
+    // ACME 证书签发错误
+    #[error("ACME error: {0}")]
+    Acme(String),
+    
+    // Oblivious DoH 消息解析或加解密错误
+    #[error("ODoH error: {0}")]
+    Odoh(String),
+    
+    // Oblivious DoH 查询使用了未知的密钥 ID
+    #[error("Unknown ODoH key id")]
+    OdohUnknownKey,
+    
     // HTTP 错误
     #[error("HTTP error: {0}")]
     Http(String),
@@ -37,6 +54,18 @@ pub enum ServerError {
     #[error("Upstream server error: {0}")]
     Upstream(String),
     
+    // 上游不可达（连接失败、超时或返回错误状态）
+    #[error("Upstream unavailable: {0}")]
+    UpstreamUnavailable(String),
+    
+    // 上游响应无法解析为 DNS 消息（内容损坏或返回了错误页面）
+    #[error("Upstream response parse error: {0}")]
+    UpstreamParse(String),
+    
+    // 上游响应未通过一致性校验（疑似伪造）
+    #[error("Upstream response validation failed: {0}")]
+    UpstreamValidation(String),
+    
     // 缓存错误
     #[error("Cache error: {0}")]
     Cache(String),
@@ -65,10 +94,45 @@ pub enum ServerError {
     #[error("Invalid query: {0}")]
     InvalidQuery(String),
     
+    // 需要查询上游的请求超出速率限制（缓存命中不计入速率限制时）
+    #[error("Rate limit exceeded: {0}")]
+    RateLimited(String),
+    
     // 其他错误
     #[error("Other error: {0}")]
     Other(String),
 }
 
+// 上游解析失败的类别，用于映射 HTTP 状态码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamFailureKind {
+    // 上游查询超时
+    Timeout,
+    // 上游不可达或返回错误
+    Unreachable,
+    // 上游响应无法解析或未通过校验
+    Validation,
+}
+
+impl ServerError {
+    // 上游解析失败的类别，非上游错误返回 None。
+    // 上游组回退依赖 UpstreamUnavailable，超时与校验失败也以该类型返回，此时按原因区分
+    pub fn upstream_failure_kind(&self) -> Option<UpstreamFailureKind> {
+        match self {
+            ServerError::UpstreamValidation(_) | ServerError::UpstreamParse(_) => Some(UpstreamFailureKind::Validation),
+            ServerError::UpstreamUnavailable(reason) if reason.contains(UPSTREAM_TIMEOUT_REASON) => {
+                Some(UpstreamFailureKind::Timeout)
+            },
+            ServerError::UpstreamUnavailable(reason) if reason.contains(UPSTREAM_VALIDATION_FAILED_REASON) => {
+                Some(UpstreamFailureKind::Validation)
+            },
+            ServerError::UpstreamUnavailable(_) | ServerError::Upstream(_) | ServerError::DnsResolve(_) => {
+                Some(UpstreamFailureKind::Unreachable)
+            },
+            _ => None,
+        }
+    }
+}
+
 // 结果类型别名
 pub type Result<T> = result::Result<T, ServerError>;
\ No newline at end of file
@@ -1,9 +1,51 @@
 // src/server/health.rs
 
-use axum::{routing::get, Router};
+use std::sync::Arc;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Router};
+use crate::server::acme::AcmeManager;
+
+// 健康检查正常时的响应内容
+const HEALTH_OK_BODY: &str = "ok!!";
+
+// 参与健康检查的组件，存在问题时返回问题描述
+pub trait HealthSource: Send + Sync {
+    fn health_issue(&self) -> Option<String>;
+}
+
+impl HealthSource for AcmeManager {
+    fn health_issue(&self) -> Option<String> {
+        AcmeManager::health_issue(self)
+    }
+}
 
 // 创建健康检查路由
 pub fn health_routes() -> Router {
     Router::new()
-        .route("/health", get(|| async { "ok!!" }))
-} 
+        .route("/health", get(|| async { HEALTH_OK_BODY }))
+}
+
+// 创建包含 ACME 证书状态的健康检查路由
+// 证书续期持续失败且临近到期时返回 503，便于运维提前发现问题
+pub fn health_routes_with_acme(acme: Arc<AcmeManager>) -> Router {
+    health_routes_with_sources(vec![acme])
+}
+
+// 创建汇总各组件状态的健康检查路由，任一组件存在问题时返回 503
+pub fn health_routes_with_sources(sources: Vec<Arc<dyn HealthSource>>) -> Router {
+    if sources.is_empty() {
+        return health_routes();
+    }
+    Router::new()
+        .route("/health", get(handle_health_with_sources))
+        .with_state(Arc::new(sources))
+}
+
+// 处理汇总各组件状态的健康检查
+async fn handle_health_with_sources(State(sources): State<Arc<Vec<Arc<dyn HealthSource>>>>) -> impl IntoResponse {
+    let issues: Vec<String> = sources.iter().filter_map(|source| source.health_issue()).collect();
+    if issues.is_empty() {
+        HEALTH_OK_BODY.into_response()
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, issues.join("; ")).into_response()
+    }
+}
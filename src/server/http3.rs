@@ -0,0 +1,139 @@
+// src/server/http3.rs
+
+// 该模块负责 HTTP/3 (QUIC) 监听。
+//
+// HTTP/3 监听与 TCP 监听共享同一个 Axum 应用（因此共享缓存、路由与上游等 ServerState），
+// 证书来自同一个 TlsContext，证书热替换对两个监听同时生效。
+// 客户端通过 TCP 监听响应中的 Alt-Svc 头发现 HTTP/3 服务。
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use axum::{
+    body::{to_bytes, Body},
+    extract::ConnectInfo,
+    http::{HeaderValue, Request, Response},
+    middleware::map_response,
+    Router,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use h3::server::RequestStream;
+use quinn::{Endpoint, VarInt};
+use tower::ServiceExt;
+use tracing::{debug, info};
+use crate::common::consts::{
+    HTTP3_ALT_SVC_MAX_AGE_SECS, HTTP3_SHUTDOWN_TIMEOUT_SECS, MAX_REQUEST_BODY_SIZE,
+};
+use crate::server::error::{Result, ServerError};
+use crate::server::tls::TlsContext;
+
+// HTTP/3 正常关闭错误码（H3_NO_ERROR）
+const H3_NO_ERROR_CODE: u32 = 0x100;
+
+// 绑定 HTTP/3 监听端点
+pub fn bind_h3(addr: SocketAddr, tls: &TlsContext) -> Result<Endpoint> {
+    let server_config = tls.quic_server_config()?;
+    Endpoint::server(server_config, addr)
+        .map_err(|e| ServerError::Http(format!("Failed to bind HTTP/3 listener to {}: {}", addr, e)))
+}
+
+// 在 QUIC 端点上提供 HTTP/3 服务，端点关闭后返回
+pub async fn serve_h3(endpoint: Endpoint, app: Router) -> io::Result<()> {
+    while let Some(incoming) = endpoint.accept().await {
+        let app = app.clone();
+        tokio::spawn(async move {
+            let remote_addr = incoming.remote_address();
+            let result = match incoming.await {
+                Ok(connection) => serve_h3_connection(connection, app).await,
+                Err(e) => Err(io::Error::other(e)),
+            };
+            if let Err(e) = result {
+                debug!(client_ip = %remote_addr.ip(), "HTTP/3 connection closed with error: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+// 关闭 HTTP/3 端点并等待连接关闭通知发送完成
+pub async fn shutdown_h3(endpoint: &Endpoint) {
+    endpoint.close(VarInt::from_u32(H3_NO_ERROR_CODE), b"server shutting down");
+    if tokio::time::timeout(Duration::from_secs(HTTP3_SHUTDOWN_TIMEOUT_SECS), endpoint.wait_idle()).await.is_err() {
+        debug!("Timed out waiting for HTTP/3 connections to close");
+    }
+    info!("HTTP/3 listener closed.");
+}
+
+// 为 TCP 监听的响应添加 Alt-Svc 头，告知客户端可用的 HTTP/3 端口
+pub fn apply_alt_svc(app: Router, h3_port: u16) -> Router {
+    let alt_svc = HeaderValue::from_str(&format!("h3=\":{}\"; ma={}", h3_port, HTTP3_ALT_SVC_MAX_AGE_SECS))
+        .expect("Alt-Svc header value is always valid");
+
+    app.layer(map_response(move |mut response: Response<Body>| {
+        let alt_svc = alt_svc.clone();
+        async move {
+            response.headers_mut().insert("alt-svc", alt_svc);
+            response
+        }
+    }))
+}
+
+// 处理单个 HTTP/3 连接上的请求
+async fn serve_h3_connection(connection: quinn::Connection, app: Router) -> io::Result<()> {
+    let remote_addr = connection.remote_address();
+    let mut h3_connection = h3::server::Connection::<_, Bytes>::new(h3_quinn::Connection::new(connection))
+        .await
+        .map_err(io::Error::other)?;
+
+    loop {
+        match h3_connection.accept().await {
+            Ok(Some(resolver)) => {
+                let app = app.clone();
+                tokio::spawn(async move {
+                    let result = match resolver.resolve_request().await {
+                        Ok((request, stream)) => handle_h3_request(request, stream, remote_addr, app).await,
+                        Err(e) => Err(io::Error::other(e)),
+                    };
+                    if let Err(e) = result {
+                        debug!(client_ip = %remote_addr.ip(), "HTTP/3 request failed: {}", e);
+                    }
+                });
+            },
+            Ok(None) => return Ok(()),
+            Err(e) if e.is_h3_no_error() => return Ok(()),
+            Err(e) => return Err(io::Error::other(e)),
+        }
+    }
+}
+
+// 将 HTTP/3 请求交给 Axum 应用处理并写回响应
+async fn handle_h3_request(
+    request: Request<()>,
+    mut stream: RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+    remote_addr: SocketAddr,
+    app: Router,
+) -> io::Result<()> {
+    // 读取请求体，超过上限时停止读取并交由处理器拒绝
+    let mut body = BytesMut::new();
+    while let Some(chunk) = stream.recv_data().await.map_err(io::Error::other)? {
+        body.put(chunk);
+        if body.len() > MAX_REQUEST_BODY_SIZE {
+            break;
+        }
+    }
+
+    // 注入客户端地址，使 ConnectInfo 提取器与 TCP 监听保持一致
+    let (parts, _) = request.into_parts();
+    let mut request = Request::from_parts(parts, Body::from(body.freeze()));
+    request.extensions_mut().insert(ConnectInfo(remote_addr));
+
+    let response = app.oneshot(request).await.unwrap_or_else(|never| match never {});
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.map_err(io::Error::other)?;
+
+    stream.send_response(Response::from_parts(parts, ())).await.map_err(io::Error::other)?;
+    if !body.is_empty() {
+        stream.send_data(body).await.map_err(io::Error::other)?;
+    }
+    stream.finish().await.map_err(io::Error::other)
+}
@@ -0,0 +1,214 @@
+// src/server/http_conn.rs
+
+// 该模块为 DoH 监听（明文 HTTP、TLS 与 Unix 域套接字）提供统一的 HTTP 连接处理，
+// 按配置应用请求头读取超时、空闲 keep-alive 超时与单连接最大请求数。
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::extract::connect_info::ConnectInfo;
+use axum::response::Response;
+use axum::Router;
+use std::pin::Pin;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::Request;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto::Builder as ConnectionBuilder;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tower::Service;
+use tracing::{debug, warn};
+use crate::server::config::HttpServerConfig;
+use crate::server::proxy_protocol::{client_addr, ProxyProtocol};
+
+// 单个连接的限制
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionLimits {
+    // 读取请求头的时限，连接建立后尚未收到请求时同样适用
+    pub header_read_timeout: Duration,
+    // 空闲 keep-alive 连接的保持时间，为 0 时每个请求完成后关闭连接
+    pub idle_timeout: Duration,
+    // 单个连接上处理的最大请求数
+    pub max_requests: Option<u64>,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self::from_config(&HttpServerConfig::default())
+    }
+}
+
+impl ConnectionLimits {
+    pub fn from_config(config: &HttpServerConfig) -> Self {
+        Self {
+            header_read_timeout: Duration::from_secs(config.read_header_timeout),
+            idle_timeout: Duration::from_secs(config.idle_keepalive_timeout),
+            max_requests: (config.max_requests_per_connection > 0).then_some(config.max_requests_per_connection),
+        }
+    }
+}
+
+// 连接上的请求活动
+#[derive(Debug, Clone, Copy)]
+struct Activity {
+    // 正在处理的请求数
+    in_flight: usize,
+    // 已开始处理的请求数
+    served: u64,
+    // 最近一次请求开始或结束的时间
+    last_active: Instant,
+}
+
+// 请求处理结束时更新连接活动
+struct RequestGuard(Arc<watch::Sender<Activity>>);
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        self.0.send_modify(|activity| {
+            activity.in_flight -= 1;
+            activity.last_active = Instant::now();
+        });
+    }
+}
+
+// 等待连接应当关闭：达到最大请求数，或空闲超过时限（尚未收到请求时按请求头读取时限计算）
+async fn wait_for_close(activity: &watch::Sender<Activity>, limits: ConnectionLimits) {
+    let mut changes = activity.subscribe();
+    loop {
+        let current = *changes.borrow_and_update();
+        if limits.max_requests.is_some_and(|max| current.served >= max) {
+            return;
+        }
+        if current.in_flight > 0 {
+            let _ = changes.changed().await;
+            continue;
+        }
+        let idle_limit = if current.served == 0 { limits.header_read_timeout } else { limits.idle_timeout };
+        tokio::select! {
+            _ = tokio::time::sleep_until(current.last_active + idle_limit) => return,
+            _ = changes.changed() => {},
+        }
+    }
+}
+
+// 在单个连接上提供 HTTP/1.1 服务，http2 为 true 时同时接受 HTTP/2（按连接前言自动识别），call 处理每个请求
+pub async fn serve_connection<I, F, Fut>(io: I, limits: ConnectionLimits, http2: bool, call: F) -> io::Result<()>
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    F: Fn(Request<Incoming>) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Result<Response, Infallible>> + Send + 'static,
+{
+    let activity = Arc::new(watch::Sender::new(Activity {
+        in_flight: 0,
+        served: 0,
+        last_active: Instant::now(),
+    }));
+
+    let service = {
+        let activity = activity.clone();
+        hyper::service::service_fn(move |req: Request<Incoming>| {
+            activity.send_modify(|activity| {
+                activity.in_flight += 1;
+                activity.served += 1;
+                activity.last_active = Instant::now();
+            });
+            let guard = RequestGuard(activity.clone());
+            let response = call(req);
+            async move {
+                let response = response.await;
+                drop(guard);
+                response
+            }
+        })
+    };
+
+    let io = TokioIo::new(io);
+    if http2 {
+        let mut builder = ConnectionBuilder::new(TokioExecutor::new());
+        builder.http1()
+            .timer(TokioTimer::new())
+            .header_read_timeout(limits.header_read_timeout)
+            .keep_alive(!limits.idle_timeout.is_zero());
+        let connection = builder.serve_connection_with_upgrades(io, service);
+        tokio::pin!(connection);
+        drive_connection(connection, |connection| connection.graceful_shutdown(), &activity, limits).await
+    } else {
+        // 自动识别的连接会忽略 http1_only，只接受 HTTP/1.1 时直接使用 HTTP/1 连接
+        let connection = http1::Builder::new()
+            .timer(TokioTimer::new())
+            .header_read_timeout(limits.header_read_timeout)
+            .keep_alive(!limits.idle_timeout.is_zero())
+            .serve_connection(io, service)
+            .with_upgrades();
+        tokio::pin!(connection);
+        drive_connection(connection, |connection| connection.graceful_shutdown(), &activity, limits).await
+    }
+}
+
+// 运行连接直到结束，需要关闭时优雅关闭：HTTP/1.1 完成当前请求后关闭，HTTP/2 发送 GOAWAY 并完成进行中的流
+async fn drive_connection<C, E>(
+    mut connection: Pin<&mut C>,
+    graceful_shutdown: impl FnOnce(Pin<&mut C>),
+    activity: &watch::Sender<Activity>,
+    limits: ConnectionLimits,
+) -> io::Result<()>
+where
+    C: Future<Output = Result<(), E>>,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    tokio::select! {
+        result = connection.as_mut() => return result.map_err(io::Error::other),
+        _ = wait_for_close(activity, limits) => {
+            debug!("Closing HTTP connection after reaching idle timeout or request limit");
+            graceful_shutdown(connection.as_mut());
+        },
+    }
+    connection.await.map_err(io::Error::other)
+}
+
+// 在明文 TCP 监听上提供 Axum 应用，enable_h2c 为 true 时在同一端口同时接受 HTTP/2 先验知识（h2c）连接，
+// 启用 PROXY 协议时以 PROXY 头中的地址作为客户端地址
+pub async fn serve_plain(
+    listener: TcpListener,
+    app: Router,
+    limits: ConnectionLimits,
+    enable_h2c: bool,
+    proxy_protocol: Option<Arc<ProxyProtocol>>,
+) -> io::Result<()> {
+    loop {
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Failed to accept connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let app = app.clone();
+        let proxy_protocol = proxy_protocol.clone();
+        tokio::spawn(async move {
+            let remote_addr = match client_addr(proxy_protocol.as_deref(), &mut stream, peer).await {
+                Ok(remote_addr) => remote_addr,
+                Err(e) => {
+                    debug!(client_ip = %peer.ip(), "Closing connection with invalid PROXY protocol header: {}", e);
+                    return;
+                }
+            };
+            // 注入客户端地址，供 ConnectInfo 提取器使用
+            let result = serve_connection(stream, limits, enable_h2c, move |mut req: Request<Incoming>| {
+                req.extensions_mut().insert(ConnectInfo::<SocketAddr>(remote_addr));
+                app.clone().call(req)
+            }).await;
+            if let Err(e) = result {
+                debug!(client_ip = %remote_addr.ip(), "HTTP connection closed with error: {}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,95 @@
+// src/server/ip_set.rs
+
+// 该模块提供基于有序区间的 IP 网段集合，用于大规模 CIDR 列表的快速匹配。
+//
+// 网段在构建时转换为 [起始地址, 结束地址] 区间，排序并合并重叠部分，
+// 查询时通过二分查找完成，复杂度为 O(log n)。
+
+use std::net::IpAddr;
+use ipnet::IpNet;
+use crate::server::error::{ServerError, Result};
+
+// IP 网段集合
+#[derive(Debug, Clone, Default)]
+pub struct IpRangeSet {
+    // IPv4 区间（已排序且互不重叠）
+    v4: Vec<(u32, u32)>,
+    // IPv6 区间（已排序且互不重叠）
+    v6: Vec<(u128, u128)>,
+}
+
+impl IpRangeSet {
+    // 从网段列表构建集合
+    pub fn from_networks<I: IntoIterator<Item = IpNet>>(networks: I) -> Self {
+        let mut v4 = Vec::new();
+        let mut v6 = Vec::new();
+
+        for network in networks {
+            match network {
+                IpNet::V4(net) => v4.push((u32::from(net.network()), u32::from(net.broadcast()))),
+                IpNet::V6(net) => v6.push((u128::from(net.network()), u128::from(net.broadcast()))),
+            }
+        }
+
+        Self {
+            v4: merge_ranges(v4),
+            v6: merge_ranges(v6),
+        }
+    }
+
+    // 判断 IP 是否属于集合中的任一网段（IPv4 映射的 IPv6 地址按 IPv4 处理）
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match ip.to_canonical() {
+            IpAddr::V4(addr) => range_contains(&self.v4, u32::from(addr)),
+            IpAddr::V6(addr) => range_contains(&self.v6, u128::from(addr)),
+        }
+    }
+
+    // 合并后的区间数量
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+
+    // 集合是否为空
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+}
+
+// 解析单个网段，支持 CIDR 与单个 IP 地址
+pub fn parse_network(value: &str) -> Result<IpNet> {
+    let value = value.trim();
+    value.parse::<IpNet>()
+        .map(|net| net.trunc())
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| ServerError::InvalidRuleFormat(format!("Invalid IP network: '{}'", value)))
+}
+
+// 解析网段列表文本：每行一个网段，忽略空行与 '#' 注释
+pub fn parse_network_list(text: &str) -> Result<Vec<IpNet>> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(parse_network)
+        .collect()
+}
+
+// 排序并合并重叠的区间
+fn merge_ranges<T: Copy + Ord>(mut ranges: Vec<(T, T)>) -> Vec<(T, T)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(T, T)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// 二分查找包含指定地址的区间
+fn range_contains<T: Copy + Ord>(ranges: &[(T, T)], addr: T) -> bool {
+    let index = ranges.partition_point(|(start, _)| *start <= addr);
+    index > 0 && ranges[index - 1].1 >= addr
+}
@@ -0,0 +1,82 @@
+// src/server/load_shed.rs
+
+// 该模块实现全局并发请求限制（http_server.max_concurrent_requests）。
+//
+// 每个 DoH 请求在处理期间持有信号量的一个许可。许可耗尽时不排队等待，
+// 直接返回 503 并携带 Retry-After 头，同时计入 owdns_requests_shed_total。
+// 健康检查等管理路由不经过该限制，负载过高时仍可被编排系统探测。
+
+use std::sync::Arc;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, Response, StatusCode},
+    middleware::{self, Next},
+    Router,
+};
+use tokio::sync::Semaphore;
+use tracing::debug;
+use crate::common::consts::LOAD_SHED_RETRY_AFTER_SECS;
+use crate::server::metrics::METRICS;
+
+// 全局并发请求限制
+pub struct ConcurrencyLimiter {
+    permits: Arc<Semaphore>,
+    max_concurrent_requests: usize,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(max_concurrent_requests: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent_requests)),
+            max_concurrent_requests,
+        }
+    }
+    
+    // 当前进行中的请求数
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrent_requests - self.permits.available_permits()
+    }
+}
+
+// 进行中请求计数，请求结束时减少
+struct InFlightGuard;
+
+impl InFlightGuard {
+    fn new() -> Self {
+        METRICS.http_requests_in_flight().inc();
+        Self
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        METRICS.http_requests_in_flight().dec();
+    }
+}
+
+// 为路由添加全局并发限制，超出限制的请求直接返回 503
+pub fn apply_concurrency_limit(app: Router, limiter: Arc<ConcurrencyLimiter>) -> Router {
+    app.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let limiter = limiter.clone();
+        async move { handle_concurrency_limit(&limiter, req, next).await }
+    }))
+}
+
+async fn handle_concurrency_limit(limiter: &ConcurrencyLimiter, req: Request, next: Next) -> Response<Body> {
+    let Ok(_permit) = limiter.permits.clone().try_acquire_owned() else {
+        METRICS.requests_shed_total().inc();
+        debug!(limit = limiter.max_concurrent_requests, path = %req.uri().path(), "Concurrent request limit reached, shedding request");
+        return shed_response();
+    };
+    let _in_flight = InFlightGuard::new();
+    next.run(req).await
+}
+
+// 并发请求数达到上限时的 503 响应
+fn shed_response() -> Response<Body> {
+    let mut response = Response::new(Body::from("Server is overloaded"));
+    *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(LOAD_SHED_RETRY_AFTER_SECS));
+    response
+}
@@ -0,0 +1,87 @@
+// src/server/log_level.rs
+
+// 该模块提供运行时修改日志过滤规则的管理端点。
+//
+// 日志订阅器在启动时通过 reload 层包装 EnvFilter，PUT /log-level 使用请求体中的
+// 过滤规则（如 "debug,hickory_proto=info"）替换当前规则，无需重启服务。
+
+use std::sync::Arc;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::put,
+    Json, Router,
+};
+use serde::Serialize;
+use tracing::{info, warn};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+use crate::common::consts::LOG_LEVEL_PATH;
+use crate::server::error::{Result, ServerError};
+
+// 日志过滤规则的 reload 句柄
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+// 运行时日志过滤规则控制
+pub struct LogLevelControl {
+    handle: LogFilterHandle,
+}
+
+// 修改日志过滤规则的响应
+#[derive(Debug, Serialize)]
+struct LogLevelResponse {
+    // 修改前的过滤规则
+    previous: String,
+    // 当前生效的过滤规则
+    current: String,
+}
+
+impl LogLevelControl {
+    pub fn new(handle: LogFilterHandle) -> Self {
+        Self { handle }
+    }
+    
+    // 当前生效的过滤规则
+    pub fn current(&self) -> Result<String> {
+        self.handle.with_current(|filter| filter.to_string())
+            .map_err(|e| ServerError::Other(format!("Failed to read log filter: {}", e)))
+    }
+    
+    // 替换过滤规则，返回修改前的规则；规则无效时保持当前规则不变
+    pub fn set(&self, directives: &str) -> Result<String> {
+        let directives = directives.trim();
+        if directives.is_empty() {
+            return Err(ServerError::Config("Log filter must not be empty".to_string()));
+        }
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|e| ServerError::Config(format!("Invalid log filter '{}': {}", directives, e)))?;
+        
+        let previous = self.current()?;
+        self.handle.reload(filter)
+            .map_err(|e| ServerError::Other(format!("Failed to reload log filter: {}", e)))?;
+        Ok(previous)
+    }
+}
+
+// 创建修改日志过滤规则的路由（需由调用方添加认证）
+pub fn log_level_routes(control: Arc<LogLevelControl>) -> Router {
+    Router::new()
+        .route(LOG_LEVEL_PATH, put(handle_set_log_level))
+        .with_state(control)
+}
+
+// 处理修改日志过滤规则请求，请求体为过滤规则文本
+async fn handle_set_log_level(State(control): State<Arc<LogLevelControl>>, body: String) -> Response {
+    let previous = match control.set(&body) {
+        Ok(previous) => previous,
+        Err(e @ ServerError::Config(_)) => {
+            warn!(error = %e, "Rejected log filter update");
+            return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+        },
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    
+    let current = control.current().unwrap_or_default();
+    info!(previous = %previous, current = %current, "Log filter updated");
+    (StatusCode::OK, Json(LogLevelResponse { previous, current })).into_response()
+}
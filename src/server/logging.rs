@@ -0,0 +1,142 @@
+// src/server/logging.rs
+
+// 该模块根据 logging 配置构建日志过滤规则与格式化层。
+//
+// JSON 格式下每行输出一个 JSON 对象：timestamp、level、target、message 以及事件字段，
+// 当前所在 span（如 doh_request 的 request_id）的字段同样展开为顶层键，便于日志系统直接检索。
+
+use std::fmt;
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::{FormatEvent, JsonFields, Writer};
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormattedFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+use crate::server::config::{LogFormat, LoggingConfig};
+
+// 构建日志过滤规则：RUST_LOG 环境变量优先，其次为 --debug 参数，再次为 logging.level
+pub fn build_filter(config: &LoggingConfig, debug: bool) -> EnvFilter {
+    if let Ok(filter) = EnvFilter::try_from_default_env() {
+        filter
+    } else if debug {
+        // 启用调试模式，显示更详细的日志
+        EnvFilter::new("oxide_wdns=debug,tower_http=debug,owdns=debug,info")
+    } else if let Some(filter) = config.level.as_deref().and_then(|level| EnvFilter::try_new(level).ok()) {
+        filter
+    } else {
+        // 正常模式，仅显示 info 级别及以上
+        EnvFilter::new("oxide_wdns=info,owdns=info,tokio_graceful_shutdown=info")
+    }
+}
+
+// 按配置的格式创建日志格式化层，日志写入 writer
+pub fn format_layer<S, W>(config: &LoggingConfig, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let source_location = config.add_source_location;
+    match config.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_target(true)
+            .with_level(true)
+            .with_file(source_location)
+            .with_line_number(source_location)
+            .with_ansi(false) // 关闭彩色输出
+            .boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_writer(writer)
+            .with_file(source_location)
+            .with_line_number(source_location)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .fmt_fields(JsonFields::new())
+            .event_format(JsonEventFormat { source_location })
+            .boxed(),
+    }
+}
+
+// 将事件与所在 span 的字段展开为单个 JSON 对象的格式化器
+struct JsonEventFormat {
+    source_location: bool,
+}
+
+impl<S> FormatEvent<S, JsonFields> for JsonEventFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, JsonFields>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut entry = Map::new();
+
+        // 外层 span 的字段先写入，内层 span 与事件的同名字段覆盖外层
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                let extensions = span.extensions();
+                let Some(fields) = extensions.get::<FormattedFields<JsonFields>>() else {
+                    continue;
+                };
+                if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(fields) {
+                    entry.extend(fields);
+                }
+            }
+        }
+        event.record(&mut JsonVisitor(&mut entry));
+
+        // 固定字段最后写入，不会被同名的 span 或事件字段覆盖
+        let metadata = event.metadata();
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+        entry.insert("timestamp".to_string(), Value::String(timestamp));
+        entry.insert("level".to_string(), Value::String(metadata.level().to_string()));
+        entry.insert("target".to_string(), Value::String(metadata.target().to_string()));
+        if self.source_location {
+            if let Some(file) = metadata.file() {
+                entry.insert("file".to_string(), Value::String(file.to_string()));
+            }
+            if let Some(line) = metadata.line() {
+                entry.insert("line".to_string(), Value::from(line));
+            }
+        }
+
+        let line = serde_json::to_string(&entry).map_err(|_| fmt::Error)?;
+        writeln!(writer, "{}", line)
+    }
+}
+
+// 将事件字段写入 JSON 对象，数值与布尔值保留原类型
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0.insert(field.name().to_string(), Value::String(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), Value::String(format!("{:?}", value)));
+    }
+}
@@ -0,0 +1,170 @@
+// src/server/mdns.rs
+
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use hickory_proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+use tracing::debug;
+use crate::server::config::MdnsForwarderConfig;
+use crate::server::error::{ServerError, Result};
+use crate::server::metrics::METRICS;
+
+// mDNS 查询结果标签值
+const MDNS_RESULT_ANSWERED: &str = "answered";
+const MDNS_RESULT_NO_RESPONSE: &str = "no_response";
+const MDNS_RESULT_ERROR: &str = "error";
+
+// mDNS 报文的 IP TTL（RFC 6762 第 11 节）
+const MDNS_IP_TTL: u32 = 255;
+
+// 单个 mDNS 响应的最大长度
+const MDNS_MAX_PACKET_SIZE: usize = 9000;
+
+// mDNS 转发器
+//
+// 使用 RFC 6762 第 5.1 节的一次性查询：从临时端口发送组播查询，
+// 响应方将响应以单播方式发回该端口，返回第一个有效响应。
+pub struct MdnsForwarder {
+    // 通过 mDNS 解析的域名后缀（小写，不含首尾的点）
+    suffixes: Vec<String>,
+    // 组播目标地址
+    destination: SocketAddr,
+    // 收集响应的超时时间
+    response_timeout: Duration,
+    // 无响应时的负缓存 TTL（秒）
+    negative_ttl: u32,
+}
+
+impl MdnsForwarder {
+    // 创建 mDNS 转发器
+    pub fn new(config: &MdnsForwarderConfig) -> Self {
+        let mut suffixes: Vec<String> = config.domains.iter()
+            .map(|domain| domain.trim_matches('.').to_ascii_lowercase())
+            .collect();
+        suffixes.dedup();
+
+        Self {
+            suffixes,
+            destination: config.multicast_addr,
+            response_timeout: Duration::from_millis(config.mdns_response_timeout_ms),
+            negative_ttl: config.mdns_negative_ttl_secs,
+        }
+    }
+
+    // 域名是否应通过 mDNS 解析
+    pub fn matches(&self, domain: &str) -> bool {
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        self.suffixes.iter().any(|suffix| {
+            domain.strip_suffix(suffix.as_str())
+                .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.'))
+        })
+    }
+
+    // 无响应时的负缓存 TTL（秒）
+    pub fn negative_ttl(&self) -> u32 {
+        self.negative_ttl
+    }
+
+    // 发送 mDNS 查询，超时内没有有效响应时返回 None
+    pub async fn query(&self, query_message: &Message) -> Result<Option<Message>> {
+        let result = self.send_query(query_message).await;
+        let label = match &result {
+            Ok(Some(_)) => MDNS_RESULT_ANSWERED,
+            Ok(None) => MDNS_RESULT_NO_RESPONSE,
+            Err(_) => MDNS_RESULT_ERROR,
+        };
+        METRICS.mdns_queries_total().with_label_values(&[label]).inc();
+        result
+    }
+
+    async fn send_query(&self, query_message: &Message) -> Result<Option<Message>> {
+        let query = query_message.queries().first()
+            .ok_or_else(|| ServerError::Upstream("No Query section in query message".to_string()))?;
+
+        // 一次性查询：新的查询 ID，仅包含一个问题，不请求递归
+        let mut mdns_query = Message::new();
+        mdns_query.set_id(fastrand::u16(..))
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(false)
+            .add_query(query.clone());
+        let query_bytes = mdns_query.to_vec()?;
+
+        let bind_addr: SocketAddr = match self.destination {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(bind_addr).await?;
+        match self.destination {
+            SocketAddr::V4(_) => socket.set_multicast_ttl_v4(MDNS_IP_TTL)?,
+            SocketAddr::V6(_) => {},
+        }
+        socket.send_to(&query_bytes, self.destination).await?;
+
+        debug!(
+            name = %query.name(),
+            query_type = ?query.query_type(),
+            destination = %self.destination,
+            "Sent mDNS query"
+        );
+
+        // 在超时内收集响应，忽略与查询不匹配的报文
+        let deadline = Instant::now() + self.response_timeout;
+        let mut buf = vec![0u8; MDNS_MAX_PACKET_SIZE];
+        loop {
+            let (len, from) = match timeout_at(deadline, socket.recv_from(&mut buf)).await {
+                Ok(received) => received?,
+                Err(_) => return Ok(None),
+            };
+
+            let Ok(response) = Message::from_vec(&buf[..len]) else {
+                debug!(from = %from, "Ignoring malformed mDNS response");
+                continue;
+            };
+            if Self::is_valid_response(&response, mdns_query.id(), query) {
+                debug!(from = %from, answers = response.answer_count(), "Received mDNS response");
+                return Ok(Some(Self::build_response(query_message, response)));
+            }
+        }
+    }
+
+    // 响应需与查询 ID 匹配，且包含查询名称的应答记录
+    fn is_valid_response(response: &Message, id: u16, query: &Query) -> bool {
+        response.message_type() == MessageType::Response
+            && response.id() == id
+            && response.response_code() == ResponseCode::NoError
+            && response.answers().iter().any(|record| record.name().eq_case(query.name())
+                || record.name() == query.name())
+    }
+
+    // 使用 mDNS 应答构建返回给客户端的响应
+    fn build_response(query_message: &Message, mdns_response: Message) -> Message {
+        let mut response = Message::new();
+        response.set_id(query_message.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(query_message.op_code())
+            .set_recursion_desired(query_message.recursion_desired())
+            .set_recursion_available(true)
+            .set_response_code(ResponseCode::NoError)
+            .add_queries(query_message.queries().to_vec());
+
+        let mut mdns_response = mdns_response;
+        response.insert_answers(mdns_response.take_answers());
+        response.insert_additionals(mdns_response.take_additionals());
+        response
+    }
+
+    // 超时无响应时返回给客户端的 NXDOMAIN 响应
+    pub fn negative_response(query_message: &Message) -> Message {
+        let mut response = Message::new();
+        response.set_id(query_message.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(query_message.op_code())
+            .set_recursion_desired(query_message.recursion_desired())
+            .set_recursion_available(true)
+            .set_response_code(ResponseCode::NXDomain)
+            .add_queries(query_message.queries().to_vec());
+        response
+    }
+}
@@ -3,7 +3,7 @@
 use axum::{routing::get, Router};
 use prometheus::{
     GaugeVec, HistogramVec, 
-    IntCounter, IntCounterVec, IntGauge, Registry,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Registry,
     opts,
 };
 use once_cell::sync::Lazy;
@@ -21,6 +21,18 @@ pub struct DnsMetrics {
     http_request_bytes: HistogramVec,
     http_response_bytes: HistogramVec,
     rate_limit_rejected_total: IntCounterVec,
+    access_control_rejected_total: IntCounterVec,
+    http_request_too_large_total: IntCounterVec,
+    http_request_timeouts_total: IntCounterVec,
+    request_deduplication_hits_total: IntCounter,
+    auth_requests_total: IntCounterVec,
+    websocket_connections: IntGauge,
+    http_requests_in_flight: IntGauge,
+    requests_shed_total: IntCounter,
+    dns_server_queries_total: IntCounterVec,
+    dns_server_tcp_connections: IntGauge,
+    dot_server_connections: IntGauge,
+    proxy_protocol_rejected_total: IntCounterVec,
     
     // 2. 缓存效率和状态指标
     cache_entries: IntGauge, 
@@ -33,15 +45,29 @@ pub struct DnsMetrics {
     dns_responses_total: IntCounterVec,
     dns_query_type_total: IntCounterVec,
     dns_query_duration_seconds: HistogramVec,
+    dns_request_size_bytes: HistogramVec,
+    dns_response_size_bytes: HistogramVec,
     
     // 4. 上游 DNS 解析器指标
     upstream_requests_total: IntCounterVec,
     upstream_failures_total: IntCounterVec,
     upstream_duration_seconds: HistogramVec,
+    upstream_srv_discovered_resolvers: IntGauge,
+    upstream_consul_discovered_resolvers: IntGaugeVec,
+    upstream_group_fallthrough_total: IntCounterVec,
+    upstream_failover_total: IntCounterVec,
+    upstream_failure_ratio: GaugeVec,
+    upstream_http_version_total: IntCounterVec,
+    upstream_validation_failures_total: IntCounterVec,
+    upstream_parse_errors_total: IntCounterVec,
+    mdns_queries_total: IntCounterVec,
+    private_ptr_refused_total: IntCounter,
+    query_type_refused_total: IntCounterVec,
     
     // 5. DNS 路由/拆分功能指标
     route_results_total: IntCounterVec,
     route_rules: GaugeVec,
+    upstream_override_total: IntCounterVec,
     
     // 6. DNSSEC 验证指标
     dnssec_validations_total: IntCounterVec,
@@ -56,6 +82,22 @@ pub struct DnsMetrics {
     
     // 9. URL规则更新指标
     url_rule_update_duration_seconds: HistogramVec,
+    routing_rules_remote_load_total: IntCounter,
+    routing_rules_remote_load_errors_total: IntCounter,
+    routing_rules_last_loaded_timestamp: IntGauge,
+    
+    // 10. TLS 证书与 ACME 指标
+    tls_certificate_expiry_timestamp_seconds: IntGauge,
+    tls_client_auth_failures_total: IntCounter,
+    shutdown_dropped_queries_total: IntCounter,
+    acme_renewals_total: IntCounterVec,
+    
+    // 11. EDNS 填充指标
+    upstream_edns_padding_bytes_total: IntCounter,
+    server_response_padding_bytes_total: IntCounter,
+    response_dedup_records_removed_total: IntCounter,
+    query_log_dropped_total: IntCounter,
+    response_compression_bytes_total: IntCounterVec,
 }
 
 impl Default for DnsMetrics {
@@ -107,6 +149,60 @@ impl DnsMetrics {
             &["client_ip"]
         ).unwrap();
         
+        let access_control_rejected_total = IntCounterVec::new(
+            opts!("owdns_access_control_rejected_total", "Total requests rejected by client IP access control, classified by the list that rejected them"),
+            &["list"]
+        ).unwrap();
+        
+        let auth_requests_total = IntCounterVec::new(
+            opts!("owdns_auth_requests_total", "Total token-authenticated requests, classified by token name and result (authorized, missing, invalid)"),
+            &["token_name", "result"]
+        ).unwrap();
+        
+        let websocket_connections = IntGauge::new(
+            "owdns_websocket_connections", "Current number of open DNS over WebSocket connections"
+        ).unwrap();
+        
+        let http_requests_in_flight = IntGauge::new(
+            "owdns_http_requests_in_flight", "Current number of DoH requests being processed under max_concurrent_requests"
+        ).unwrap();
+        
+        let requests_shed_total = IntCounter::new(
+            "owdns_requests_shed_total", "Total DoH requests rejected with 503 because max_concurrent_requests was reached"
+        ).unwrap();
+        
+        let dns_server_queries_total = IntCounterVec::new(
+            opts!("owdns_dns_server_queries_total", "Total queries received on the plain DNS listeners, classified by listener and outcome"),
+            &["listener", "outcome"]
+        ).unwrap();
+        
+        let dns_server_tcp_connections = IntGauge::new(
+            "owdns_dns_server_tcp_connections", "Current number of open plain DNS TCP connections"
+        ).unwrap();
+        
+        let dot_server_connections = IntGauge::new(
+            "owdns_dot_server_connections", "Current number of open DNS-over-TLS connections"
+        ).unwrap();
+        
+        let proxy_protocol_rejected_total = IntCounterVec::new(
+            opts!("owdns_proxy_protocol_rejected_total", "Total connections from trusted proxies closed because of a missing or invalid PROXY protocol header, classified by listener"),
+            &["listener"]
+        ).unwrap();
+        
+        let http_request_too_large_total = IntCounterVec::new(
+            opts!("owdns_http_request_too_large_total", "Total requests rejected with 413 because the body or dns parameter exceeded max_request_body_size, classified by method"),
+            &["method"]
+        ).unwrap();
+        
+        let http_request_timeouts_total = IntCounterVec::new(
+            opts!("owdns_http_request_timeouts_total", "Total requests answered with 504 because processing exceeded http_server.request_timeout, classified by method"),
+            &["method"]
+        ).unwrap();
+        
+        let request_deduplication_hits_total = IntCounter::new(
+            "owdns_request_deduplication_hits_total", "Total DoH requests that shared the resolution of an identical in-flight request"
+        ).unwrap();
+        
         // 2. 缓存效率和状态指标
         let cache_entries = IntGauge::new(
             "owdns_cache_entries", "Current number of DNS cache entries"
@@ -155,6 +251,24 @@ impl DnsMetrics {
             &["query_type"]
         ).unwrap();
         
+        let dns_request_size_bytes = HistogramVec::new(
+            prometheus::histogram_opts!(
+                "owdns_dns_request_size_bytes",
+                "Wire-format DNS query size in bytes, classified by request format",
+                vec![64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0, 4096.0, 16384.0, 65535.0]
+            ),
+            &["format"]
+        ).unwrap();
+        
+        let dns_response_size_bytes = HistogramVec::new(
+            prometheus::histogram_opts!(
+                "owdns_dns_response_size_bytes",
+                "Wire-format DNS response size in bytes, classified by response format",
+                vec![64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0, 4096.0, 16384.0, 65535.0]
+            ),
+            &["format"]
+        ).unwrap();
+        
         // 4. 上游 DNS 解析器指标
         let upstream_requests_total = IntCounterVec::new(
             opts!("owdns_upstream_requests_total", "Total requests sent to upstream DNS resolvers, classified by resolver address, protocol and upstream group"),
@@ -175,12 +289,72 @@ impl DnsMetrics {
             &["resolver", "protocol", "upstream_group"]
         ).unwrap();
         
+        let upstream_srv_discovered_resolvers = IntGauge::new(
+            "owdns_upstream_srv_discovered_resolvers",
+            "Number of DoH upstream resolvers currently discovered via SRV records"
+        ).unwrap();
+        
+        let upstream_consul_discovered_resolvers = IntGaugeVec::new(
+            opts!("owdns_upstream_consul_discovered_resolvers", "Number of healthy DoH upstream resolvers currently discovered via Consul, classified by upstream group"),
+            &["upstream_group"]
+        ).unwrap();
+        
+        let upstream_failure_ratio = GaugeVec::new(
+            opts!("owdns_upstream_failure_ratio", "Failure ratio of each DoH upstream resolver over the rolling window, classified by resolver address and upstream group"),
+            &["resolver", "upstream_group"]
+        ).unwrap();
+        
+        let upstream_http_version_total = IntCounterVec::new(
+            opts!("owdns_upstream_http_version_total", "Total DoH upstream responses, classified by negotiated HTTP version (h1, h2, h3)"),
+            &["version"]
+        ).unwrap();
+        
+        let upstream_validation_failures_total = IntCounterVec::new(
+            opts!("owdns_upstream_validation_failures_total", "Total upstream responses discarded by response validation, classified by resolver address and upstream group"),
+            &["resolver", "upstream_group"]
+        ).unwrap();
+        
+        let upstream_parse_errors_total = IntCounterVec::new(
+            opts!("owdns_upstream_parse_errors_total", "Total upstream responses that could not be parsed as DNS messages, classified by resolver address"),
+            &["resolver"]
+        ).unwrap();
+        
+        let upstream_group_fallthrough_total = IntCounterVec::new(
+            opts!("owdns_upstream_group_fallthrough_total", "Total queries that fell through to the next upstream group after a transport failure, classified by failed and next group"),
+            &["from_group", "to_group"]
+        ).unwrap();
+        
+        let upstream_failover_total = IntCounterVec::new(
+            opts!("owdns_upstream_failover_total", "Total queries retried with the next group of a rule's failover chain after a failure or SERVFAIL, classified by source and destination group"),
+            &["source_group", "destination_group"]
+        ).unwrap();
+        
+        let mdns_queries_total = IntCounterVec::new(
+            opts!("owdns_mdns_queries_total", "Total queries forwarded over multicast DNS, classified by result (answered, no_response, error)"),
+            &["result"]
+        ).unwrap();
+        
+        let private_ptr_refused_total = IntCounter::new(
+            "owdns_private_ptr_refused_total",
+            "Total PTR queries for private (RFC 1918, RFC 4193, link-local) addresses refused without forwarding upstream"
+        ).unwrap();
+        
+        let query_type_refused_total = IntCounterVec::new(
+            opts!("owdns_query_type_refused_total", "Total queries refused because their record type is listed in upstream.refused_types, classified by record type"),
+            &["qtype"]
+        ).unwrap();
+        
         // 5. DNS 路由/拆分功能指标
         let route_results_total = IntCounterVec::new(
             opts!("owdns_route_results_total", "Total routing results, classified by result type (rule_match, blackhole, default)"),
             &["result"]
         ).unwrap();
         
+        let upstream_override_total = IntCounterVec::new(
+            opts!("owdns_upstream_override_total", "Total upstream group override header attempts, classified by result (honored, ignored, unknown_group)"),
+            &["result"]
+        ).unwrap();
+        
         let route_rules = GaugeVec::new(
             opts!("owdns_route_rules", "Current active routing rules, classified by rule type (exact, regex, wildcard, file, url)"),
             &["type"]
@@ -226,6 +400,68 @@ impl DnsMetrics {
             ),
             &["status", "upstream_group"]
         ).unwrap();
+        
+        let routing_rules_remote_load_total = IntCounter::new(
+            "owdns_routing_rules_remote_load_total",
+            "Total attempts to load the routing rule set from routing.rules_url"
+        ).unwrap();
+        
+        let routing_rules_remote_load_errors_total = IntCounter::new(
+            "owdns_routing_rules_remote_load_errors_total",
+            "Total failed attempts to load the routing rule set from routing.rules_url"
+        ).unwrap();
+        
+        let routing_rules_last_loaded_timestamp = IntGauge::new(
+            "owdns_routing_rules_last_loaded_timestamp",
+            "Time (unix timestamp) the remote routing rule set was last loaded"
+        ).unwrap();
+
+        // 10. TLS 证书与 ACME 指标
+        let tls_certificate_expiry_timestamp_seconds = IntGauge::new(
+            "owdns_tls_certificate_expiry_timestamp_seconds",
+            "Expiry time (unix timestamp) of the certificate currently served by the TLS listener"
+        ).unwrap();
+        
+        let tls_client_auth_failures_total = IntCounter::new(
+            "owdns_tls_client_auth_failures_total",
+            "Total TLS handshakes rejected because the client certificate was missing or failed verification"
+        ).unwrap();
+        
+        let shutdown_dropped_queries_total = IntCounter::new(
+            "owdns_shutdown_dropped_queries_total",
+            "Total in-flight queries dropped because they did not complete within the shutdown grace period"
+        ).unwrap();
+        
+        let acme_renewals_total = IntCounterVec::new(
+            opts!("owdns_acme_renewals_total", "Total ACME certificate issuance/renewal attempts, classified by result"),
+            &["result"]
+        ).unwrap();
+        
+        // 11. EDNS 填充指标
+        let upstream_edns_padding_bytes_total = IntCounter::new(
+            "owdns_upstream_edns_padding_bytes_total",
+            "Total EDNS padding bytes added to queries sent to upstream resolvers"
+        ).unwrap();
+        
+        let server_response_padding_bytes_total = IntCounter::new(
+            "owdns_server_response_padding_bytes_total",
+            "Total EDNS padding bytes added to responses sent to clients"
+        ).unwrap();
+        
+        let response_dedup_records_removed_total = IntCounter::new(
+            "owdns_response_dedup_records_removed_total",
+            "Total duplicate records removed from upstream responses by response_deduplication"
+        ).unwrap();
+        
+        let query_log_dropped_total = IntCounter::new(
+            "owdns_query_log_dropped_total",
+            "Total query log lines dropped because the query log writer fell behind"
+        ).unwrap();
+        
+        let response_compression_bytes_total = IntCounterVec::new(
+            opts!("owdns_response_compression_bytes_total", "Total bytes of compressed responses before and after compression, classified by stage"),
+            &["stage"]
+        ).unwrap();
 
         // 创建指标实例
         let metrics = DnsMetrics {
@@ -235,6 +471,18 @@ impl DnsMetrics {
             http_request_bytes,
             http_response_bytes,
             rate_limit_rejected_total,
+            access_control_rejected_total,
+            http_request_too_large_total,
+            http_request_timeouts_total,
+            request_deduplication_hits_total,
+            auth_requests_total,
+            websocket_connections,
+            http_requests_in_flight,
+            requests_shed_total,
+            dns_server_queries_total,
+            dns_server_tcp_connections,
+            dot_server_connections,
+            proxy_protocol_rejected_total,
             cache_entries,
             cache_capacity,
             cache_operations_total,
@@ -243,17 +491,43 @@ impl DnsMetrics {
             dns_responses_total,
             dns_query_type_total,
             dns_query_duration_seconds,
+            dns_request_size_bytes,
+            dns_response_size_bytes,
             upstream_requests_total,
             upstream_failures_total,
             upstream_duration_seconds,
+            upstream_srv_discovered_resolvers,
+            upstream_consul_discovered_resolvers,
+            upstream_group_fallthrough_total,
+            upstream_failover_total,
+            upstream_failure_ratio,
+            upstream_http_version_total,
+            upstream_validation_failures_total,
+            upstream_parse_errors_total,
+            mdns_queries_total,
+            private_ptr_refused_total,
+            query_type_refused_total,
             route_results_total,
             route_rules,
+            upstream_override_total,
             dnssec_validations_total,
             ecs_processed_total,
             ecs_cache_matches_total,
             cache_persist_operations_total,
             cache_persist_duration_seconds,
             url_rule_update_duration_seconds,
+            routing_rules_remote_load_total,
+            routing_rules_remote_load_errors_total,
+            routing_rules_last_loaded_timestamp,
+            tls_certificate_expiry_timestamp_seconds,
+            tls_client_auth_failures_total,
+            shutdown_dropped_queries_total,
+            acme_renewals_total,
+            upstream_edns_padding_bytes_total,
+            server_response_padding_bytes_total,
+            response_dedup_records_removed_total,
+            query_log_dropped_total,
+            response_compression_bytes_total,
         };
         
         // 集中注册所有指标
@@ -270,6 +544,18 @@ impl DnsMetrics {
         self.registry.register(Box::new(self.http_request_bytes.clone())).unwrap();
         self.registry.register(Box::new(self.http_response_bytes.clone())).unwrap();
         self.registry.register(Box::new(self.rate_limit_rejected_total.clone())).unwrap();
+        self.registry.register(Box::new(self.access_control_rejected_total.clone())).unwrap();
+        self.registry.register(Box::new(self.http_request_too_large_total.clone())).unwrap();
+        self.registry.register(Box::new(self.http_request_timeouts_total.clone())).unwrap();
+        self.registry.register(Box::new(self.request_deduplication_hits_total.clone())).unwrap();
+        self.registry.register(Box::new(self.auth_requests_total.clone())).unwrap();
+        self.registry.register(Box::new(self.websocket_connections.clone())).unwrap();
+        self.registry.register(Box::new(self.http_requests_in_flight.clone())).unwrap();
+        self.registry.register(Box::new(self.requests_shed_total.clone())).unwrap();
+        self.registry.register(Box::new(self.dns_server_queries_total.clone())).unwrap();
+        self.registry.register(Box::new(self.dns_server_tcp_connections.clone())).unwrap();
+        self.registry.register(Box::new(self.dot_server_connections.clone())).unwrap();
+        self.registry.register(Box::new(self.proxy_protocol_rejected_total.clone())).unwrap();
         
         // 2. 缓存效率和状态指标
         self.registry.register(Box::new(self.cache_entries.clone())).unwrap();
@@ -282,15 +568,29 @@ impl DnsMetrics {
         self.registry.register(Box::new(self.dns_responses_total.clone())).unwrap();
         self.registry.register(Box::new(self.dns_query_type_total.clone())).unwrap();
         self.registry.register(Box::new(self.dns_query_duration_seconds.clone())).unwrap();
+        self.registry.register(Box::new(self.dns_request_size_bytes.clone())).unwrap();
+        self.registry.register(Box::new(self.dns_response_size_bytes.clone())).unwrap();
         
         // 4. 上游 DNS 解析器指标
         self.registry.register(Box::new(self.upstream_requests_total.clone())).unwrap();
         self.registry.register(Box::new(self.upstream_failures_total.clone())).unwrap();
         self.registry.register(Box::new(self.upstream_duration_seconds.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_srv_discovered_resolvers.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_consul_discovered_resolvers.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_group_fallthrough_total.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_failover_total.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_failure_ratio.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_http_version_total.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_validation_failures_total.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_parse_errors_total.clone())).unwrap();
+        self.registry.register(Box::new(self.mdns_queries_total.clone())).unwrap();
+        self.registry.register(Box::new(self.private_ptr_refused_total.clone())).unwrap();
+        self.registry.register(Box::new(self.query_type_refused_total.clone())).unwrap();
         
         // 5. DNS 路由/拆分功能指标
         self.registry.register(Box::new(self.route_results_total.clone())).unwrap();
         self.registry.register(Box::new(self.route_rules.clone())).unwrap();
+        self.registry.register(Box::new(self.upstream_override_total.clone())).unwrap();
         
         // 6. DNSSEC 验证指标
         self.registry.register(Box::new(self.dnssec_validations_total.clone())).unwrap();
@@ -305,6 +605,22 @@ impl DnsMetrics {
         
         // 注册URL规则更新指标
         self.registry.register(Box::new(self.url_rule_update_duration_seconds.clone())).unwrap();
+        self.registry.register(Box::new(self.routing_rules_remote_load_total.clone())).unwrap();
+        self.registry.register(Box::new(self.routing_rules_remote_load_errors_total.clone())).unwrap();
+        self.registry.register(Box::new(self.routing_rules_last_loaded_timestamp.clone())).unwrap();
+        
+        // 10. TLS 证书与 ACME 指标
+        self.registry.register(Box::new(self.tls_certificate_expiry_timestamp_seconds.clone())).unwrap();
+        self.registry.register(Box::new(self.tls_client_auth_failures_total.clone())).unwrap();
+        self.registry.register(Box::new(self.shutdown_dropped_queries_total.clone())).unwrap();
+        self.registry.register(Box::new(self.acme_renewals_total.clone())).unwrap();
+        
+        // 11. EDNS 填充指标
+        self.registry.register(Box::new(self.upstream_edns_padding_bytes_total.clone())).unwrap();
+        self.registry.register(Box::new(self.server_response_padding_bytes_total.clone())).unwrap();
+        self.registry.register(Box::new(self.response_dedup_records_removed_total.clone())).unwrap();
+        self.registry.register(Box::new(self.query_log_dropped_total.clone())).unwrap();
+        self.registry.register(Box::new(self.response_compression_bytes_total.clone())).unwrap();
     }
     
     // 获取 Prometheus 注册表
@@ -340,10 +656,58 @@ impl DnsMetrics {
         &self.http_response_bytes
     }
     
+    pub fn http_request_too_large_total(&self) -> &IntCounterVec {
+        &self.http_request_too_large_total
+    }
+    
+    pub fn http_request_timeouts_total(&self) -> &IntCounterVec {
+        &self.http_request_timeouts_total
+    }
+    
+    pub fn request_deduplication_hits_total(&self) -> &IntCounter {
+        &self.request_deduplication_hits_total
+    }
+    
+    pub fn auth_requests_total(&self) -> &IntCounterVec {
+        &self.auth_requests_total
+    }
+    
+    pub fn websocket_connections(&self) -> &IntGauge {
+        &self.websocket_connections
+    }
+    
+    pub fn http_requests_in_flight(&self) -> &IntGauge {
+        &self.http_requests_in_flight
+    }
+    
+    pub fn requests_shed_total(&self) -> &IntCounter {
+        &self.requests_shed_total
+    }
+    
+    pub fn dns_server_queries_total(&self) -> &IntCounterVec {
+        &self.dns_server_queries_total
+    }
+    
+    pub fn dns_server_tcp_connections(&self) -> &IntGauge {
+        &self.dns_server_tcp_connections
+    }
+    
+    pub fn dot_server_connections(&self) -> &IntGauge {
+        &self.dot_server_connections
+    }
+    
+    pub fn proxy_protocol_rejected_total(&self) -> &IntCounterVec {
+        &self.proxy_protocol_rejected_total
+    }
+    
     pub fn rate_limit_rejected_total(&self) -> &IntCounterVec {
         &self.rate_limit_rejected_total
     }
     
+    pub fn access_control_rejected_total(&self) -> &IntCounterVec {
+        &self.access_control_rejected_total
+    }
+    
     // 2. 缓存效率和状态指标
     pub fn cache_entries(&self) -> &IntGauge {
         &self.cache_entries
@@ -378,6 +742,14 @@ impl DnsMetrics {
         &self.dns_query_duration_seconds
     }
     
+    pub fn dns_request_size_bytes(&self) -> &HistogramVec {
+        &self.dns_request_size_bytes
+    }
+    
+    pub fn dns_response_size_bytes(&self) -> &HistogramVec {
+        &self.dns_response_size_bytes
+    }
+    
     // 4. 上游 DNS 解析器指标
     pub fn upstream_requests_total(&self) -> &IntCounterVec {
         &self.upstream_requests_total
@@ -391,6 +763,50 @@ impl DnsMetrics {
         &self.upstream_duration_seconds
     }
     
+    pub fn upstream_srv_discovered_resolvers(&self) -> &IntGauge {
+        &self.upstream_srv_discovered_resolvers
+    }
+    
+    pub fn upstream_consul_discovered_resolvers(&self) -> &IntGaugeVec {
+        &self.upstream_consul_discovered_resolvers
+    }
+    
+    pub fn upstream_group_fallthrough_total(&self) -> &IntCounterVec {
+        &self.upstream_group_fallthrough_total
+    }
+    
+    pub fn upstream_failover_total(&self) -> &IntCounterVec {
+        &self.upstream_failover_total
+    }
+    
+    pub fn upstream_failure_ratio(&self) -> &GaugeVec {
+        &self.upstream_failure_ratio
+    }
+    
+    pub fn upstream_http_version_total(&self) -> &IntCounterVec {
+        &self.upstream_http_version_total
+    }
+    
+    pub fn upstream_validation_failures_total(&self) -> &IntCounterVec {
+        &self.upstream_validation_failures_total
+    }
+    
+    pub fn upstream_parse_errors_total(&self) -> &IntCounterVec {
+        &self.upstream_parse_errors_total
+    }
+    
+    pub fn mdns_queries_total(&self) -> &IntCounterVec {
+        &self.mdns_queries_total
+    }
+    
+    pub fn private_ptr_refused_total(&self) -> &IntCounter {
+        &self.private_ptr_refused_total
+    }
+    
+    pub fn query_type_refused_total(&self) -> &IntCounterVec {
+        &self.query_type_refused_total
+    }
+    
     // 5. DNS 路由/拆分功能指标
     pub fn route_results_total(&self) -> &IntCounterVec {
         &self.route_results_total
@@ -400,6 +816,10 @@ impl DnsMetrics {
         &self.route_rules
     }
     
+    pub fn upstream_override_total(&self) -> &IntCounterVec {
+        &self.upstream_override_total
+    }
+    
     // 6. DNSSEC 验证指标
     pub fn dnssec_validations_total(&self) -> &IntCounterVec {
         &self.dnssec_validations_total
@@ -427,6 +847,57 @@ impl DnsMetrics {
     pub fn url_rule_update_duration_seconds(&self) -> &HistogramVec {
         &self.url_rule_update_duration_seconds
     }
+    
+    // 远程路由规则加载指标
+    pub fn routing_rules_remote_load_total(&self) -> &IntCounter {
+        &self.routing_rules_remote_load_total
+    }
+    
+    pub fn routing_rules_remote_load_errors_total(&self) -> &IntCounter {
+        &self.routing_rules_remote_load_errors_total
+    }
+    
+    pub fn routing_rules_last_loaded_timestamp(&self) -> &IntGauge {
+        &self.routing_rules_last_loaded_timestamp
+    }
+    
+    // 10. TLS 证书与 ACME 指标
+    pub fn tls_certificate_expiry_timestamp_seconds(&self) -> &IntGauge {
+        &self.tls_certificate_expiry_timestamp_seconds
+    }
+    
+    pub fn tls_client_auth_failures_total(&self) -> &IntCounter {
+        &self.tls_client_auth_failures_total
+    }
+    
+    pub fn shutdown_dropped_queries_total(&self) -> &IntCounter {
+        &self.shutdown_dropped_queries_total
+    }
+    
+    pub fn acme_renewals_total(&self) -> &IntCounterVec {
+        &self.acme_renewals_total
+    }
+    
+    // 11. EDNS 填充指标
+    pub fn upstream_edns_padding_bytes_total(&self) -> &IntCounter {
+        &self.upstream_edns_padding_bytes_total
+    }
+    
+    pub fn server_response_padding_bytes_total(&self) -> &IntCounter {
+        &self.server_response_padding_bytes_total
+    }
+    
+    pub fn response_dedup_records_removed_total(&self) -> &IntCounter {
+        &self.response_dedup_records_removed_total
+    }
+    
+    pub fn query_log_dropped_total(&self) -> &IntCounter {
+        &self.query_log_dropped_total
+    }
+    
+    pub fn response_compression_bytes_total(&self) -> &IntCounterVec {
+        &self.response_compression_bytes_total
+    }
 }
 
 // 提供指标导出路由
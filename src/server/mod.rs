@@ -1,79 +1,274 @@
 // src/server/mod.rs
 
+pub mod acme;
 pub mod cache;
+pub mod capture;
+pub mod compression;
 pub mod config;
+pub mod config_template;
+pub mod consul;
+pub mod cors;
+pub mod dns_server;
+pub mod dot_server;
 pub mod doh_handler;
+pub mod drain;
 pub mod error;
 pub mod health;
+pub mod http3;
+pub mod http_conn;
+pub mod ip_set;
+pub mod load_shed;
+pub mod log_level;
+pub mod logging;
+pub mod mdns;
 pub mod metrics;
+pub mod odoh;
+pub mod padding;
+pub mod proxy_protocol;
+pub mod query_log;
+pub mod root_info;
+pub mod route_test;
+pub mod record_dedup;
+pub mod recursive;
+pub mod redirect;
+pub mod refusal;
+pub mod request_id;
+pub mod response_validation;
 pub mod routing;
 pub mod security;
 pub mod upstream;
+pub mod version;
 pub mod args;
 pub mod ecs;
 pub mod scalar;
+pub mod stats;
+pub mod tls;
+pub mod svcb;
+#[cfg(unix)]
+pub mod unix;
 
 use std::sync::Arc;
+use std::time::Duration;
 use axum::Router as AxumRouter;
+use axum::http::HeaderName;
 use reqwest::Client;
 use tracing::info;
 
+use crate::common::consts::{DNS_WEBSOCKET_PATH, ROOT_INFO_PATH};
 use crate::server::error::{Result, ServerError};
 use crate::server::cache::DnsCache;
-use crate::server::config::ServerConfig;
-use crate::server::doh_handler::{doh_routes, ServerState};
-use crate::server::health::health_routes;
+use crate::server::config::{PoolConfig, ResolverProtocol, ServerConfig, UpstreamConfig};
+use crate::server::compression::apply_compression;
+use crate::server::cors::apply_cors;
+use crate::server::doh_handler::{apply_client_ip, doh_routes, odoh_routes, PendingRequests, ServerState};
+use crate::server::drain::{apply_drain, DrainController};
+use crate::server::load_shed::{apply_concurrency_limit, ConcurrencyLimiter};
+use crate::server::acme::AcmeManager;
+use crate::server::dns_server::DnsServer;
+use crate::server::dot_server::DotServer;
+use crate::server::health::{health_routes_with_sources, HealthSource};
+use crate::server::redirect::https_redirect_routes;
+use crate::server::request_id::apply_request_id;
+use crate::server::ip_set::{parse_network, IpRangeSet};
+use crate::server::odoh::OdohTarget;
+use crate::server::http3::apply_alt_svc;
+use crate::server::log_level::{log_level_routes, LogLevelControl};
+use crate::server::capture::{capture_routes, QueryCapture};
 use crate::server::metrics::metrics_routes;
 use crate::server::routing::Router as DnsRouter;
-use crate::server::security::{apply_rate_limiting, calculate_period_duration};
+use crate::server::security::{
+    apply_access_control, apply_admin_token_auth, apply_metrics_auth, apply_rate_limiting, apply_token_auth,
+    calculate_period_duration,
+};
+use crate::server::stats::{stats_routes, QueryStats};
+use crate::server::query_log::QueryLogger;
 use crate::server::upstream::UpstreamManager;
+use crate::server::version::version_routes;
+use crate::server::root_info::root_info_routes;
 
 // 创建 HTTP 客户端的公共函数
 pub fn create_http_client(config: &ServerConfig) -> Result<Client> {
+    create_pooled_http_client(config, &config.dns.http_client.pool)
+}
+
+// 使用指定的连接池配置创建 HTTP 客户端，每个客户端拥有独立的连接池
+pub fn create_pooled_http_client(config: &ServerConfig, pool: &PoolConfig) -> Result<Client> {
+    build_http_client(pooled_client_builder(config, pool))
+}
+
+// 创建 DoH 上游查询使用的 HTTP 客户端，按上游配置协商 HTTP/2
+pub fn create_upstream_http_client(config: &ServerConfig, pool: &PoolConfig, upstream: &UpstreamConfig) -> Result<Client> {
+    build_http_client(upstream_http_client_builder(config, pool, upstream))
+}
+
+// 上游组 HTTP 客户端的构建器，配置双向 TLS 的 DoH 上游在此基础上添加客户端证书
+pub(crate) fn upstream_http_client_builder(config: &ServerConfig, pool: &PoolConfig, upstream: &UpstreamConfig) -> reqwest::ClientBuilder {
+    // 配置了源地址时，DoH 连接同样从该地址发出
+    let mut builder = pooled_client_builder(config, pool).local_address(upstream.bind_addr);
+    
+    if upstream.prefer_http2 {
+        // 明文 HTTP 无法通过 ALPN 协商，所有 DoH 上游均为 http:// 时直接使用 HTTP/2；
+        // HTTPS 上游通过 ALPN 协商 h2
+        let doh_addresses: Vec<String> = upstream.resolvers.iter()
+            .filter(|resolver| resolver.protocol == ResolverProtocol::Doh)
+            .map(|resolver| resolver.doh_url())
+            .collect();
+        if !doh_addresses.is_empty() && doh_addresses.iter().all(|address| address.starts_with("http://")) {
+            builder = builder.http2_prior_knowledge();
+        }
+        
+        if upstream.http2_keepalive_interval_secs > 0 {
+            builder = builder
+                .http2_keep_alive_interval(Duration::from_secs(upstream.http2_keepalive_interval_secs))
+                .http2_keep_alive_while_idle(true);
+        }
+    } else {
+        builder = builder.http1_only();
+    }
+    
+    builder
+}
+
+// 所有 HTTP 客户端共用的基础配置
+fn pooled_client_builder(config: &ServerConfig, pool: &PoolConfig) -> reqwest::ClientBuilder {
     reqwest::ClientBuilder::new()
         .timeout(config.http_client_timeout())
-        .pool_idle_timeout(config.http_client_pool_idle_timeout())
+        .connect_timeout(config.http_client_connect_timeout())
+        .pool_idle_timeout(Duration::from_secs(pool.idle_timeout))
         .user_agent(&config.dns.http_client.request.user_agent)
-        .pool_max_idle_per_host(config.dns.http_client.pool.max_idle_connections as usize)
+        .pool_max_idle_per_host(pool.max_idle_connections as usize)
+}
+
+pub(crate) fn build_http_client(builder: reqwest::ClientBuilder) -> Result<Client> {
+    builder
         .build()
         .map_err(|e| error::ServerError::Http(format!("Failed to create HTTP client: {}", e)))
 }
 
+// 构建完成的应用组件
+pub struct AppComponents {
+    // DoH 服务主路由
+    pub app: AxumRouter,
+    // 独立监听的管理路由（配置了 admin_listen_addr 时）
+    pub admin_app: Option<AxumRouter>,
+    // 独立监听的指标路由（配置了 metrics_listen_addr 时）
+    pub metrics_app: Option<AxumRouter>,
+    // HTTP/3 监听使用的路由（启用 http3 时）
+    pub h3_app: Option<AxumRouter>,
+    // Unix 域套接字监听使用的路由（配置了 listen_unix 时）
+    pub unix_app: Option<AxumRouter>,
+    // 明文 HTTP 监听上的 HTTPS 重定向路由（启用 http_to_https_redirect 时）
+    pub redirect_app: Option<AxumRouter>,
+    // DNS 缓存
+    pub cache: Arc<DnsCache>,
+    // 路由器（收到 SIGHUP 时重新扫描规则目录）
+    pub router: Arc<DnsRouter>,
+    // 进行中查询跟踪（关闭时排空）
+    pub drain: Arc<DrainController>,
+    // 上游解析管理器（关闭时停止 SRV 发现）
+    pub upstream: Arc<UpstreamManager>,
+    // 经典 DNS 监听（配置了 dns_server 时）
+    pub dns_server: Option<Arc<DnsServer>>,
+    // DoT 监听（配置了 dot_server 时）
+    pub dot_server: Option<Arc<DotServer>>,
+}
+
 // DNS-over-HTTPS 服务器
 pub struct DoHServer {
     // 配置
     config: ServerConfig,
     // 是否启用调试模式
     debug: bool,
+    // ACME 证书管理器（启用 ACME 时用于健康检查）
+    acme: Option<Arc<AcmeManager>>,
+    // 运行时日志过滤规则控制（配置了 admin_token 时提供 /log-level 端点）
+    log_level: Option<Arc<LogLevelControl>>,
 }
 
 impl DoHServer {
     // 创建新的 DoH 服务器
     pub fn new(config: ServerConfig, debug: bool) -> Self {
-        Self { config, debug }
+        Self { config, debug, acme: None, log_level: None }
+    }
+
+    // 关联 ACME 证书管理器，使健康检查反映证书续期状态
+    pub fn with_acme(mut self, acme: Arc<AcmeManager>) -> Self {
+        self.acme = Some(acme);
+        self
+    }
+
+    // 关联日志过滤规则控制，配置了 admin_token 时通过管理端点修改日志级别
+    pub fn with_log_level(mut self, log_level: Arc<LogLevelControl>) -> Self {
+        self.log_level = Some(log_level);
+        self
     }
 
     // 此方法构建 Axum 应用和相关资源，但不启动服务器。
-    // 返回 Axum Router, DNS Cache, 和 cache metrics task handle.
-    pub async fn build_application_components(
-        &self,
-    ) -> Result<(
-        AxumRouter,
-        Arc<DnsCache>,
-    )> {
+    // 返回 DoH 主路由、可选的独立管理/指标路由以及 DNS 缓存。
+    pub async fn build_application_components(&self) -> Result<AppComponents> {
         let cache = Arc::new(DnsCache::new(self.config.dns.cache.clone()));
         let client = create_http_client(&self.config)?;
-        let router_manager = Arc::new(DnsRouter::new(self.config.dns.routing.clone(), Some(client.clone())).await?);
-        let upstream_manager = Arc::new(UpstreamManager::new(Arc::new(self.config.clone()), client.clone()).await?);
+        // 上游查询使用独立的连接池，不与规则下载等其他 HTTP 请求共享
+        let upstream_client = create_upstream_http_client(&self.config, &self.config.dns.http_client.pool, &self.config.dns.upstream)?;
+        let upstream_manager = Arc::new(UpstreamManager::new(Arc::new(self.config.clone()), upstream_client).await?);
+        upstream_manager.start_srv_discovery().await;
+        upstream_manager.start_consul_discovery().await;
+        let router_manager = Arc::new(
+            DnsRouter::new(self.config.dns.routing.clone(), Some(client)).await?
+                .with_upstream(upstream_manager.clone())
+        );
+        let query_stats = self.config.stats.enabled
+            .then(|| Arc::new(QueryStats::new(&self.config.stats)));
+        let query_log = match self.config.logging.query_log.enabled {
+            true => Some(Arc::new(QueryLogger::new(&self.config.logging.query_log)?)),
+            false => None,
+        };
+        let capture = self.config.debug.capture.enabled
+            .then(|| Arc::new(QueryCapture::new(self.config.debug.capture.size)));
 
         let state = ServerState {
             config: self.config.clone(),
-            upstream: upstream_manager,
-            router: router_manager,
+            upstream: upstream_manager.clone(),
+            router: router_manager.clone(),
             cache: cache.clone(),
+            stats: query_stats.clone(),
+            query_log,
+            capture: capture.clone(),
+            pending_requests: Arc::new(PendingRequests::default()),
+            started_at: tokio::time::Instant::now(),
         };
 
-        let mut doh_specific_routes = doh_routes(state);
+        let mut doh_specific_routes = doh_routes(state.clone());
+        
+        // 响应压缩只作用于处理器生成的响应，按内容类型只压缩 JSON API，wireformat 响应保持原样
+        let compression_config = &self.config.http.compression;
+        if compression_config.enabled {
+            doh_specific_routes = apply_compression(doh_specific_routes, compression_config);
+        }
+        
+        // 令牌认证仅作用于 DoH 查询路由，健康检查始终开放
+        let auth_config = &self.config.http.auth;
+        let auth_tokens = if auth_config.enabled { Some(auth_config.load_tokens()?) } else { None };
+        let mut token_paths = self.config.http.doh_paths.clone();
+        if self.config.http.websocket_enabled {
+            token_paths.push(DNS_WEBSOCKET_PATH.to_string());
+        }
+        if let Some(tokens) = &auth_tokens {
+            doh_specific_routes = apply_token_auth(doh_specific_routes, tokens.clone(), &token_paths);
+        }
+        
+        // 启用 ODoH 时添加公钥配置与加密查询路由
+        if self.config.http.odoh.enabled {
+            let target = Arc::new(OdohTarget::new(&self.config.http.odoh)?);
+            target.spawn_rotation();
+            info!(path = %self.config.http.odoh.path, "Oblivious DoH target enabled");
+            doh_specific_routes = doh_specific_routes.merge(odoh_routes(state.clone(), target));
+        }
+        
+        // Unix 域套接字没有客户端 IP，默认不对其应用速率限制
+        let unix_doh_routes = self.config.http.listen_unix.as_ref()
+            .filter(|_| !self.config.http.unix_socket.rate_limit)
+            .map(|_| doh_specific_routes.clone());
         
         let rate_limit_config = &self.config.http.rate_limit;
         if rate_limit_config.enabled {
@@ -87,11 +282,64 @@ impl DoHServer {
                     rate
                 )));
             }
-            doh_specific_routes = apply_rate_limiting(doh_specific_routes, rate_limit_config);
+            // 速率限制在令牌认证之前执行，按令牌名称匹配覆盖时需要自行识别令牌
+            doh_specific_routes = apply_rate_limiting(
+                doh_specific_routes,
+                rate_limit_config,
+                auth_tokens.as_deref().unwrap_or_default(),
+                &token_paths,
+            );
             info!("Rate limiting applied with per_ip_rate: {} and per_ip_concurrent: {}", rate, burst);
         } else {
             info!("Rate limiting is disabled");
         }
+        
+        // 访问控制放在速率限制之外，被拒绝的客户端不占用限速配额
+        doh_specific_routes = apply_access_control(doh_specific_routes, &self.config.http.access_control);
+        
+        // 排空放在访问控制之外，关闭期间所有新请求都直接返回 503
+        let drain = Arc::new(DrainController::new(Duration::from_secs(self.config.http.shutdown_grace_period_secs)));
+        doh_specific_routes = apply_drain(doh_specific_routes, drain.clone());
+        
+        // 全局并发限制放在排空之外，超出限制的请求不做任何处理直接返回 503
+        let concurrency_limiter = match self.config.http.max_concurrent_requests {
+            0 => None,
+            limit => {
+                info!(max_concurrent_requests = limit, "Global concurrent request limit enabled");
+                Some(Arc::new(ConcurrencyLimiter::new(limit)))
+            },
+        };
+        if let Some(limiter) = &concurrency_limiter {
+            doh_specific_routes = apply_concurrency_limit(doh_specific_routes, limiter.clone());
+        }
+        
+        // 经典 DNS 监听与 DoH 共享服务器状态，查询同样计入排空
+        let dns_server = self.config.dns_server.is_enabled()
+            .then(|| Arc::new(DnsServer::new(state.clone(), drain.clone(), self.config.dns_server.clone())));
+        let dot_server = match self.config.dot_server.listen_addr {
+            Some(_) => Some(Arc::new(DotServer::new(state.clone(), drain.clone(), self.config.dot_server.clone())?)),
+            None => None,
+        };
+
+        // 客户端 IP 解析放在访问控制与速率限制之外，只有受信任代理的转发头才能改变客户端 IP
+        let client_ip_proxies = Arc::new(IpRangeSet::from_networks(
+            self.config.http.trusted_proxies.iter().filter_map(|entry| parse_network(entry).ok()),
+        ));
+        doh_specific_routes = apply_client_ip(doh_specific_routes, client_ip_proxies.clone());
+
+        // 请求 ID 中间件放在速率限制之外，使被限速的响应同样带有请求 ID
+        let request_id_header = HeaderName::from_bytes(self.config.http.request_id_header.as_bytes())
+            .map_err(|e| ServerError::Config(format!("Invalid request_id_header: {}", e)))?;
+        let request_id_proxies = Arc::new(IpRangeSet::from_networks(
+            self.config.http.request_id_trusted_proxies.iter().filter_map(|entry| parse_network(entry).ok()),
+        ));
+        doh_specific_routes = apply_request_id(doh_specific_routes, request_id_header.clone(), request_id_proxies.clone());
+
+        // CORS 中间件放在最外层，使预检请求不计入速率限制，被限速的响应同样带有 CORS 头
+        let cors_config = &self.config.http.cors;
+        if cors_config.enabled {
+            doh_specific_routes = apply_cors(doh_specific_routes, cors_config);
+        }
 
         // 创建 Axum Router
         let mut app = AxumRouter::new();
@@ -104,13 +352,129 @@ impl DoHServer {
             app = app.merge(scalar::create_scalar_routes());
         }
 
-        // 添加健康检查和指标路由
-        // 放在doh_specific_routes之前，放置被限速
-        app = app.merge(health_routes()).merge(metrics_routes());
+        // 构建指标路由，按需添加认证
+        let mut metrics_app = metrics_routes();
+        if compression_config.enabled {
+            metrics_app = apply_compression(metrics_app, compression_config);
+        }
+        if let Some(auth) = &self.config.http.metrics_auth {
+            metrics_app = apply_metrics_auth(metrics_app, auth);
+        }
+        if let Some(tokens) = auth_tokens.filter(|_| auth_config.protect_metrics) {
+            metrics_app = apply_token_auth(metrics_app, tokens, &[]);
+        }
+        
+        // 构建管理路由：健康检查、指标与查询统计
+        // 健康检查反映 ACME 证书续期状态与经典 DNS、DoT 监听的运行状态
+        let mut health_sources: Vec<Arc<dyn HealthSource>> = Vec::new();
+        if let Some(acme) = &self.acme {
+            health_sources.push(acme.clone());
+        }
+        if let Some(dns_server) = &dns_server {
+            health_sources.push(dns_server.clone());
+        }
+        if let Some(dot_server) = &dot_server {
+            health_sources.push(dot_server.clone());
+        }
+        let mut admin_app = health_routes_with_sources(health_sources)
+            .merge(version_routes(state.started_at));
+        
+        // 配置了独立指标监听地址时，指标路由单独提供
+        let metrics_app = if self.config.http.metrics_listen_addr.is_some() {
+            Some(metrics_app)
+        } else {
+            admin_app = admin_app.merge(metrics_app);
+            None
+        };
+
+        // 添加查询统计路由（仅在启用统计时）
+        if let Some(stats) = query_stats {
+            info!("Query stats enabled with a {}s window", stats.window_secs());
+            admin_app = admin_app.merge(stats_routes(stats));
+        }
+
+        // 配置了管理令牌时提供运行时修改日志过滤规则的端点
+        if let (Some(token), Some(log_level)) = (&self.config.http.admin_token, &self.log_level) {
+            info!("Runtime log level endpoint enabled");
+            admin_app = admin_app.merge(apply_admin_token_auth(log_level_routes(log_level.clone()), token.clone()));
+        }
+
+        // 启用调试抓包时提供需要管理令牌认证的抓包记录端点（配置校验保证已设置管理令牌）
+        if let (Some(token), Some(capture)) = (&self.config.http.admin_token, capture) {
+            info!(size = capture.size(), "Debug capture endpoint enabled");
+            admin_app = admin_app.merge(apply_admin_token_auth(capture_routes(capture), token.clone()));
+        }
+
+        // 管理路由默认不应用 CORS
+        if cors_config.enabled && cors_config.include_admin_routes {
+            admin_app = apply_cors(admin_app, cors_config);
+        }
+
+        // 配置了管理监听地址时，管理路由不再挂载到 DoH 监听上
+        // 否则放在doh_specific_routes之前，防止被限速
+        let admin_app = if self.config.http.admin_listen_addr.is_some() {
+            Some(admin_app)
+        } else {
+            app = app.merge(admin_app);
+            None
+        };
+
+        // 根路径信息页面不限速；DoH 或 ODoH 路径配置为根路径时不提供
+        let root_is_query_path = self.config.http.doh_paths.iter().any(|path| path == ROOT_INFO_PATH)
+            || (self.config.http.odoh.enabled && self.config.http.odoh.path == ROOT_INFO_PATH);
+        if self.config.http.root_info_enabled && !root_is_query_path {
+            app = app.merge(root_info_routes(state.clone()));
+        }
+
+        // Unix 域套接字监听提供与 TCP 监听相同的路由，按配置决定是否限速
+        let unix_app = self.config.http.listen_unix.as_ref().map(|_| match unix_doh_routes {
+            Some(routes) => {
+                let mut routes = apply_drain(routes, drain.clone());
+                // Unix 域套接字与 TCP 监听共享同一并发限制
+                if let Some(limiter) = &concurrency_limiter {
+                    routes = apply_concurrency_limit(routes, limiter.clone());
+                }
+                let routes = apply_client_ip(routes, client_ip_proxies.clone());
+                let mut routes = apply_request_id(routes, request_id_header.clone(), request_id_proxies.clone());
+                if cors_config.enabled {
+                    routes = apply_cors(routes, cors_config);
+                }
+                app.clone().merge(routes)
+            },
+            None => app.clone().merge(doh_specific_routes.clone()),
+        });
 
         // 添加doh_specific_routes
         app = app.merge(doh_specific_routes);
 
-        Ok((app, cache))
+        // 启用 HTTP/3 时与 TCP 监听共享同一应用，并通过 Alt-Svc 头公告 HTTP/3 端口
+        let h3_app = match self.config.http.http3_listen_addr() {
+            Some(h3_addr) => {
+                let h3_app = app.clone();
+                app = apply_alt_svc(app, h3_addr.port());
+                Some(h3_app)
+            },
+            None => None,
+        };
+
+        // 明文 HTTP 请求重定向到第一个 HTTPS 监听地址的端口
+        let redirect_app = self.config.http.https_redirect_listen_addr()
+            .zip(self.config.http.effective_server_name())
+            .map(|(_, server_name)| https_redirect_routes(server_name, self.config.http.listen_addr.primary().port()));
+
+        Ok(AppComponents {
+            app,
+            admin_app,
+            metrics_app,
+            h3_app,
+            unix_app,
+            redirect_app,
+            cache,
+            router: router_manager,
+            drain,
+            upstream: upstream_manager,
+            dns_server,
+            dot_server,
+        })
     }
 }
@@ -0,0 +1,741 @@
+// src/server/odoh.rs
+
+// 该模块实现 Oblivious DoH (RFC 9230) 目标端。
+//
+// 客户端经由 ODoH 中继发送使用目标公钥加密的查询，目标解密后按普通 DoH 流程解析，
+// 再用从 HPKE 上下文导出的密钥加密响应。HPKE (RFC 9180) 仅实现 ODoH 使用的
+// DHKEM(X25519, HKDF-SHA256) + HKDF-SHA256 + AES-128-GCM 套件的 Base 模式。
+//
+// 私钥保存在 key_dir 中并按配置间隔轮换；轮换后上一个密钥继续保留一个周期，
+// 使持有旧配置的客户端仍可查询。
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use openssl::derive::Deriver;
+use openssl::pkey::{Id, PKey, Private};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_128_GCM};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use crate::common::consts::{ODOH_CURRENT_KEY_FILE, ODOH_PREVIOUS_KEY_FILE};
+use crate::server::config::OdohConfig;
+use crate::server::error::{Result, ServerError};
+
+// ODoH 配置版本
+const ODOH_VERSION: u16 = 0x0001;
+
+// HPKE 套件标识
+const KEM_X25519_HKDF_SHA256: u16 = 0x0020;
+const KDF_HKDF_SHA256: u16 = 0x0001;
+const AEAD_AES_128_GCM: u16 = 0x0001;
+
+// HPKE 套件参数长度
+const N_ENC: usize = 32;
+const N_SECRET: usize = 32;
+const N_PK: usize = 32;
+const N_H: usize = 32;
+const N_K: usize = 16;
+const N_N: usize = 12;
+
+// ODoH 消息类型
+const MESSAGE_TYPE_QUERY: u8 = 0x01;
+const MESSAGE_TYPE_RESPONSE: u8 = 0x02;
+
+// ODoH 标签
+const LABEL_QUERY: &[u8] = b"odoh query";
+const LABEL_RESPONSE: &[u8] = b"odoh response";
+const LABEL_KEY_ID: &[u8] = b"odoh key id";
+const LABEL_KEY: &[u8] = b"odoh key";
+const LABEL_NONCE: &[u8] = b"odoh nonce";
+
+// HPKE 版本标签
+const HPKE_VERSION_LABEL: &[u8] = b"HPKE-v1";
+
+// HPKE Base 模式
+const HPKE_MODE_BASE: u8 = 0x00;
+
+// 目标密钥对
+struct OdohKeyPair {
+    private_key: PKey<Private>,
+    public_key: Vec<u8>,
+    key_id: Vec<u8>,
+}
+
+impl OdohKeyPair {
+    // 生成新的 X25519 密钥对
+    fn generate() -> Result<Self> {
+        let private_key = PKey::generate_x25519()
+            .map_err(|e| ServerError::Odoh(format!("Failed to generate ODoH key: {}", e)))?;
+        Self::from_private_key(private_key)
+    }
+
+    // 从原始私钥字节加载
+    fn from_raw(raw: &[u8]) -> Result<Self> {
+        let private_key = PKey::private_key_from_raw_bytes(raw, Id::X25519)
+            .map_err(|e| ServerError::Odoh(format!("Invalid ODoH private key: {}", e)))?;
+        Self::from_private_key(private_key)
+    }
+
+    fn from_private_key(private_key: PKey<Private>) -> Result<Self> {
+        let public_key = private_key.raw_public_key()
+            .map_err(|e| ServerError::Odoh(format!("Failed to derive ODoH public key: {}", e)))?;
+        let key_id = compute_key_id(&config_contents(&public_key));
+        Ok(Self { private_key, public_key, key_id })
+    }
+
+    // 原始私钥字节
+    fn raw_private_key(&self) -> Result<Vec<u8>> {
+        self.private_key.raw_private_key()
+            .map_err(|e| ServerError::Odoh(format!("Failed to export ODoH private key: {}", e)))
+    }
+
+    // 序列化为 ObliviousDoHConfig
+    fn odoh_config(&self) -> Vec<u8> {
+        let contents = config_contents(&self.public_key);
+        let mut config = Vec::with_capacity(4 + contents.len());
+        config.extend_from_slice(&ODOH_VERSION.to_be_bytes());
+        put_u16_len(&mut config, contents.len());
+        config.extend_from_slice(&contents);
+        config
+    }
+}
+
+// 当前与上一个密钥
+struct OdohKeys {
+    current: Arc<OdohKeyPair>,
+    previous: Option<Arc<OdohKeyPair>>,
+    // 当前密钥的创建时间
+    created_at: SystemTime,
+}
+
+// ODoH 目标：管理密钥并加解密消息
+pub struct OdohTarget {
+    config: OdohConfig,
+    keys: RwLock<OdohKeys>,
+}
+
+// 解密查询后保留的上下文，用于加密对应的响应
+pub struct OdohResponder {
+    // 查询的 ObliviousDoHMessagePlaintext
+    query_plaintext: Vec<u8>,
+    // HPKE 导出的响应密钥材料
+    response_secret: Vec<u8>,
+}
+
+// 客户端侧的查询上下文，用于解密目标返回的响应
+pub struct OdohClientQuery {
+    query_plaintext: Vec<u8>,
+    response_secret: Vec<u8>,
+}
+
+impl OdohTarget {
+    // 加载或生成密钥，当前密钥已超过轮换间隔时立即轮换
+    pub fn new(config: &OdohConfig) -> Result<Self> {
+        fs::create_dir_all(&config.key_dir)?;
+
+        let current_path = config.key_dir.join(ODOH_CURRENT_KEY_FILE);
+        let previous_path = config.key_dir.join(ODOH_PREVIOUS_KEY_FILE);
+
+        let keys = match load_key_file(&current_path)? {
+            Some((current, created_at)) => OdohKeys {
+                current: Arc::new(current),
+                previous: load_key_file(&previous_path)?.map(|(key, _)| Arc::new(key)),
+                created_at,
+            },
+            None => {
+                let current = OdohKeyPair::generate()?;
+                write_key_file(&current_path, &current.raw_private_key()?)?;
+                info!(path = %current_path.display(), "Generated new ODoH key");
+                OdohKeys { current: Arc::new(current), previous: None, created_at: SystemTime::now() }
+            },
+        };
+
+        let target = Self { config: config.clone(), keys: RwLock::new(keys) };
+        if target.rotation_due_in() == Some(Duration::ZERO) {
+            target.rotate()?;
+        }
+        Ok(target)
+    }
+
+    // 启动后台密钥轮换任务（未启用轮换时返回 None）
+    pub fn spawn_rotation(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        self.rotation_due_in()?;
+
+        let target = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            while let Some(due_in) = target.rotation_due_in() {
+                tokio::time::sleep(due_in).await;
+                if let Err(e) = target.rotate() {
+                    warn!("ODoH key rotation failed: {}", e);
+                    // 失败后稍后重试，避免持续占用 CPU
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                }
+            }
+        }))
+    }
+
+    // 轮换密钥：当前密钥变为上一个密钥，并生成新的当前密钥
+    pub fn rotate(&self) -> Result<()> {
+        let next = OdohKeyPair::generate()?;
+
+        let mut keys = self.keys.write()
+            .map_err(|_| ServerError::Odoh("ODoH key lock poisoned".to_string()))?;
+        write_key_file(&self.config.key_dir.join(ODOH_PREVIOUS_KEY_FILE), &keys.current.raw_private_key()?)?;
+        write_key_file(&self.config.key_dir.join(ODOH_CURRENT_KEY_FILE), &next.raw_private_key()?)?;
+
+        keys.previous = Some(Arc::clone(&keys.current));
+        keys.current = Arc::new(next);
+        keys.created_at = SystemTime::now();
+
+        info!(key_dir = %self.config.key_dir.display(), "Rotated ODoH key");
+        Ok(())
+    }
+
+    // 序列化为 ObliviousDoHConfigs，仅公布当前密钥
+    pub fn configs(&self) -> Vec<u8> {
+        let config = self.current_key().odoh_config();
+        let mut configs = Vec::with_capacity(2 + config.len());
+        put_u16_len(&mut configs, config.len());
+        configs.extend_from_slice(&config);
+        configs
+    }
+
+    // 解密 ODoH 查询，返回内部 DNS 消息与用于加密响应的上下文
+    pub fn decrypt_query(&self, body: &[u8]) -> Result<(Vec<u8>, OdohResponder)> {
+        let (message_type, key_id, encrypted) = decode_message(body)?;
+        if message_type != MESSAGE_TYPE_QUERY {
+            return Err(ServerError::Odoh(format!("Unexpected ODoH message type: {}", message_type)));
+        }
+
+        let key = self.find_key(key_id).ok_or(ServerError::OdohUnknownKey)?;
+        if encrypted.len() < N_ENC {
+            return Err(ServerError::Odoh("ODoH query is too short".to_string()));
+        }
+        let (enc, ciphertext) = encrypted.split_at(N_ENC);
+
+        let shared_secret = decap(enc, &key.private_key, &key.public_key)?;
+        let context = HpkeContext::new(&shared_secret, LABEL_QUERY);
+        let query_plaintext = context.open(&build_aad(MESSAGE_TYPE_QUERY, key_id), ciphertext)?;
+        let dns_message = decode_plaintext(&query_plaintext)?.to_vec();
+
+        let responder = OdohResponder {
+            response_secret: context.export(LABEL_RESPONSE, N_K),
+            query_plaintext,
+        };
+        Ok((dns_message, responder))
+    }
+
+    // 距离下一次轮换的时间，未启用轮换时返回 None
+    fn rotation_due_in(&self) -> Option<Duration> {
+        if self.config.key_rotation_secs == 0 {
+            return None;
+        }
+        let created_at = self.keys.read().ok()?.created_at;
+        let age = created_at.elapsed().unwrap_or_default();
+        Some(Duration::from_secs(self.config.key_rotation_secs).saturating_sub(age))
+    }
+
+    fn current_key(&self) -> Arc<OdohKeyPair> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(&keys.current)
+    }
+
+    // 按密钥 ID 查找当前或上一个密钥
+    fn find_key(&self, key_id: &[u8]) -> Option<Arc<OdohKeyPair>> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        std::iter::once(&keys.current)
+            .chain(keys.previous.as_ref())
+            .find(|key| key.key_id == key_id)
+            .cloned()
+    }
+}
+
+impl OdohResponder {
+    // 加密 DNS 响应为 ODoH 响应消息
+    pub fn encrypt_response(&self, dns_message: &[u8]) -> Result<Vec<u8>> {
+        let mut response_nonce = vec![0u8; N_K.max(N_N)];
+        SystemRandom::new().fill(&mut response_nonce)
+            .map_err(|_| ServerError::Odoh("Failed to generate ODoH response nonce".to_string()))?;
+        self.encrypt_response_with_nonce(dns_message, &response_nonce)
+    }
+
+    fn encrypt_response_with_nonce(&self, dns_message: &[u8], response_nonce: &[u8]) -> Result<Vec<u8>> {
+        let (key, nonce) = derive_response_secrets(&self.response_secret, &self.query_plaintext, response_nonce);
+        let aad = build_aad(MESSAGE_TYPE_RESPONSE, response_nonce);
+        let ciphertext = aead_seal(&key, &nonce, &aad, &encode_plaintext(dns_message)?)?;
+        encode_message(MESSAGE_TYPE_RESPONSE, response_nonce, &ciphertext)
+    }
+}
+
+// 使用目标的 ObliviousDoHConfigs 加密 DNS 查询（客户端侧，供测试与工具使用）
+pub fn encrypt_query(configs: &[u8], dns_message: &[u8]) -> Result<(Vec<u8>, OdohClientQuery)> {
+    let public_key = parse_configs(configs)?;
+    let key_id = compute_key_id(&config_contents(&public_key));
+
+    let (shared_secret, enc) = encap(&public_key)?;
+    let context = HpkeContext::new(&shared_secret, LABEL_QUERY);
+    let query_plaintext = encode_plaintext(dns_message)?;
+    let ciphertext = context.seal(&build_aad(MESSAGE_TYPE_QUERY, &key_id), &query_plaintext)?;
+
+    let mut encrypted = enc;
+    encrypted.extend_from_slice(&ciphertext);
+    let message = encode_message(MESSAGE_TYPE_QUERY, &key_id, &encrypted)?;
+
+    let query = OdohClientQuery {
+        response_secret: context.export(LABEL_RESPONSE, N_K),
+        query_plaintext,
+    };
+    Ok((message, query))
+}
+
+impl OdohClientQuery {
+    // 解密目标返回的 ODoH 响应消息
+    pub fn decrypt_response(&self, body: &[u8]) -> Result<Vec<u8>> {
+        let (message_type, response_nonce, ciphertext) = decode_message(body)?;
+        if message_type != MESSAGE_TYPE_RESPONSE {
+            return Err(ServerError::Odoh(format!("Unexpected ODoH message type: {}", message_type)));
+        }
+
+        let (key, nonce) = derive_response_secrets(&self.response_secret, &self.query_plaintext, response_nonce);
+        let plaintext = aead_open(&key, &nonce, &build_aad(MESSAGE_TYPE_RESPONSE, response_nonce), ciphertext)?;
+        Ok(decode_plaintext(&plaintext)?.to_vec())
+    }
+}
+
+// HPKE 接收/发送上下文（每个 ODoH 查询仅加解密一条消息，序号固定为 0）
+struct HpkeContext {
+    key: Vec<u8>,
+    base_nonce: Vec<u8>,
+    exporter_secret: Vec<u8>,
+}
+
+impl HpkeContext {
+    // Base 模式密钥调度
+    fn new(shared_secret: &[u8], info: &[u8]) -> Self {
+        let suite_id = hpke_suite_id();
+        let psk_id_hash = labeled_extract(&suite_id, &[], b"psk_id_hash", &[]);
+        let info_hash = labeled_extract(&suite_id, &[], b"info_hash", info);
+
+        let mut key_schedule_context = vec![HPKE_MODE_BASE];
+        key_schedule_context.extend_from_slice(&psk_id_hash);
+        key_schedule_context.extend_from_slice(&info_hash);
+
+        let secret = labeled_extract(&suite_id, shared_secret, b"secret", &[]);
+        Self {
+            key: labeled_expand(&suite_id, &secret, b"key", &key_schedule_context, N_K),
+            base_nonce: labeled_expand(&suite_id, &secret, b"base_nonce", &key_schedule_context, N_N),
+            exporter_secret: labeled_expand(&suite_id, &secret, b"exp", &key_schedule_context, N_H),
+        }
+    }
+
+    fn seal(&self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        aead_seal(&self.key, &self.base_nonce, aad, plaintext)
+    }
+
+    fn open(&self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        aead_open(&self.key, &self.base_nonce, aad, ciphertext)
+    }
+
+    fn export(&self, exporter_context: &[u8], len: usize) -> Vec<u8> {
+        labeled_expand(&hpke_suite_id(), &self.exporter_secret, b"sec", exporter_context, len)
+    }
+}
+
+// DHKEM 封装：生成临时密钥并与接收方公钥协商共享密钥
+fn encap(public_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let ephemeral = PKey::generate_x25519()
+        .map_err(|e| ServerError::Odoh(format!("Failed to generate ephemeral key: {}", e)))?;
+    let enc = ephemeral.raw_public_key()
+        .map_err(|e| ServerError::Odoh(format!("Failed to export ephemeral key: {}", e)))?;
+
+    let dh = x25519(&ephemeral, public_key)?;
+    let mut kem_context = enc.clone();
+    kem_context.extend_from_slice(public_key);
+    Ok((extract_and_expand(&dh, &kem_context), enc))
+}
+
+// DHKEM 解封装
+fn decap(enc: &[u8], private_key: &PKey<Private>, public_key: &[u8]) -> Result<Vec<u8>> {
+    let dh = x25519(private_key, enc)?;
+    let mut kem_context = enc.to_vec();
+    kem_context.extend_from_slice(public_key);
+    Ok(extract_and_expand(&dh, &kem_context))
+}
+
+fn extract_and_expand(dh: &[u8], kem_context: &[u8]) -> Vec<u8> {
+    let suite_id = kem_suite_id();
+    let eae_prk = labeled_extract(&suite_id, &[], b"eae_prk", dh);
+    labeled_expand(&suite_id, &eae_prk, b"shared_secret", kem_context, N_SECRET)
+}
+
+// X25519 密钥协商，拒绝全零共享密钥
+fn x25519(private_key: &PKey<Private>, peer_public_key: &[u8]) -> Result<Vec<u8>> {
+    let peer = PKey::public_key_from_raw_bytes(peer_public_key, Id::X25519)
+        .map_err(|e| ServerError::Odoh(format!("Invalid X25519 public key: {}", e)))?;
+    let shared = Deriver::new(private_key)
+        .and_then(|mut deriver| {
+            deriver.set_peer(&peer)?;
+            deriver.derive_to_vec()
+        })
+        .map_err(|e| ServerError::Odoh(format!("X25519 key agreement failed: {}", e)))?;
+
+    if shared.iter().all(|b| *b == 0) {
+        return Err(ServerError::Odoh("X25519 key agreement produced an all-zero secret".to_string()));
+    }
+    Ok(shared)
+}
+
+// 由 HPKE 导出密钥材料派生响应加密密钥与 nonce
+fn derive_response_secrets(secret: &[u8], query_plaintext: &[u8], response_nonce: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut salt = query_plaintext.to_vec();
+    put_u16_len(&mut salt, response_nonce.len());
+    salt.extend_from_slice(response_nonce);
+
+    let prk = hkdf_extract(&salt, secret);
+    (hkdf_expand(&prk, LABEL_KEY, N_K), hkdf_expand(&prk, LABEL_NONCE, N_N))
+}
+
+// 密钥 ID = Expand(Extract("", config_contents), "odoh key id", Nh)
+fn compute_key_id(config_contents: &[u8]) -> Vec<u8> {
+    hkdf_expand(&hkdf_extract(&[], config_contents), LABEL_KEY_ID, N_H)
+}
+
+// 序列化 ObliviousDoHConfigContents
+fn config_contents(public_key: &[u8]) -> Vec<u8> {
+    let mut contents = Vec::with_capacity(8 + public_key.len());
+    contents.extend_from_slice(&KEM_X25519_HKDF_SHA256.to_be_bytes());
+    contents.extend_from_slice(&KDF_HKDF_SHA256.to_be_bytes());
+    contents.extend_from_slice(&AEAD_AES_128_GCM.to_be_bytes());
+    put_u16_len(&mut contents, public_key.len());
+    contents.extend_from_slice(public_key);
+    contents
+}
+
+// 从 ObliviousDoHConfigs 中选出第一个受支持的配置，返回其公钥
+fn parse_configs(configs: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = Reader::new(configs);
+    let mut list = Reader::new(reader.read_u16_prefixed()?);
+
+    while !list.is_empty() {
+        let version = list.read_u16()?;
+        let contents = list.read_u16_prefixed()?;
+        if version != ODOH_VERSION {
+            continue;
+        }
+
+        let mut contents = Reader::new(contents);
+        let suite = (contents.read_u16()?, contents.read_u16()?, contents.read_u16()?);
+        let public_key = contents.read_u16_prefixed()?;
+        if suite == (KEM_X25519_HKDF_SHA256, KDF_HKDF_SHA256, AEAD_AES_128_GCM) && public_key.len() == N_PK {
+            return Ok(public_key.to_vec());
+        }
+    }
+
+    Err(ServerError::Odoh("No supported ODoH config found".to_string()))
+}
+
+// 序列化 ObliviousDoHMessage
+fn encode_message(message_type: u8, key_id: &[u8], encrypted: &[u8]) -> Result<Vec<u8>> {
+    check_u16_len(key_id.len())?;
+    check_u16_len(encrypted.len())?;
+
+    let mut message = Vec::with_capacity(5 + key_id.len() + encrypted.len());
+    message.push(message_type);
+    put_u16_len(&mut message, key_id.len());
+    message.extend_from_slice(key_id);
+    put_u16_len(&mut message, encrypted.len());
+    message.extend_from_slice(encrypted);
+    Ok(message)
+}
+
+// 解析 ObliviousDoHMessage，返回消息类型、密钥 ID 与密文
+fn decode_message(bytes: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let mut reader = Reader::new(bytes);
+    let message_type = reader.read_u8()?;
+    let key_id = reader.read_u16_prefixed()?;
+    let encrypted = reader.read_u16_prefixed()?;
+    if !reader.is_empty() || encrypted.is_empty() {
+        return Err(ServerError::Odoh("Malformed ODoH message".to_string()));
+    }
+    Ok((message_type, key_id, encrypted))
+}
+
+// 序列化 ObliviousDoHMessagePlaintext（不附加填充）
+fn encode_plaintext(dns_message: &[u8]) -> Result<Vec<u8>> {
+    check_u16_len(dns_message.len())?;
+
+    let mut plaintext = Vec::with_capacity(4 + dns_message.len());
+    put_u16_len(&mut plaintext, dns_message.len());
+    plaintext.extend_from_slice(dns_message);
+    put_u16_len(&mut plaintext, 0);
+    Ok(plaintext)
+}
+
+// 解析 ObliviousDoHMessagePlaintext，填充必须全为零
+fn decode_plaintext(bytes: &[u8]) -> Result<&[u8]> {
+    let mut reader = Reader::new(bytes);
+    let dns_message = reader.read_u16_prefixed()?;
+    let padding = reader.read_u16_prefixed()?;
+    if !reader.is_empty() || dns_message.is_empty() || padding.iter().any(|b| *b != 0) {
+        return Err(ServerError::Odoh("Malformed ODoH plaintext".to_string()));
+    }
+    Ok(dns_message)
+}
+
+// 构建附加认证数据：类型 || 长度 || 密钥 ID（或响应 nonce）
+fn build_aad(message_type: u8, key_id: &[u8]) -> Vec<u8> {
+    let mut aad = vec![message_type];
+    put_u16_len(&mut aad, key_id.len());
+    aad.extend_from_slice(key_id);
+    aad
+}
+
+fn kem_suite_id() -> Vec<u8> {
+    let mut suite_id = b"KEM".to_vec();
+    suite_id.extend_from_slice(&KEM_X25519_HKDF_SHA256.to_be_bytes());
+    suite_id
+}
+
+fn hpke_suite_id() -> Vec<u8> {
+    let mut suite_id = b"HPKE".to_vec();
+    suite_id.extend_from_slice(&KEM_X25519_HKDF_SHA256.to_be_bytes());
+    suite_id.extend_from_slice(&KDF_HKDF_SHA256.to_be_bytes());
+    suite_id.extend_from_slice(&AEAD_AES_128_GCM.to_be_bytes());
+    suite_id
+}
+
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> Vec<u8> {
+    let labeled_ikm = [HPKE_VERSION_LABEL, suite_id, label, ikm].concat();
+    hkdf_extract(salt, &labeled_ikm)
+}
+
+fn labeled_expand(suite_id: &[u8], prk: &[u8], label: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    let labeled_info = [&(len as u16).to_be_bytes(), HPKE_VERSION_LABEL, suite_id, label, info].concat();
+    hkdf_expand(prk, &labeled_info, len)
+}
+
+// HKDF-SHA256 Extract (RFC 5869)
+fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, salt), ikm).as_ref().to_vec()
+}
+
+// HKDF-SHA256 Expand (RFC 5869)
+fn hkdf_expand(prk: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, prk);
+    let mut okm = Vec::with_capacity(len);
+    let mut block: Vec<u8> = Vec::new();
+    let mut counter = 1u8;
+
+    while okm.len() < len {
+        let mut context = hmac::Context::with_key(&key);
+        context.update(&block);
+        context.update(info);
+        context.update(&[counter]);
+        block = context.sign().as_ref().to_vec();
+        okm.extend_from_slice(&block);
+        counter += 1;
+    }
+
+    okm.truncate(len);
+    okm
+}
+
+fn aead_key(key: &[u8]) -> Result<LessSafeKey> {
+    UnboundKey::new(&AES_128_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| ServerError::Odoh("Invalid AEAD key".to_string()))
+}
+
+fn aead_nonce(nonce: &[u8]) -> Result<Nonce> {
+    Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| ServerError::Odoh("Invalid AEAD nonce".to_string()))
+}
+
+fn aead_seal(key: &[u8], nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut in_out = plaintext.to_vec();
+    aead_key(key)?
+        .seal_in_place_append_tag(aead_nonce(nonce)?, Aad::from(aad), &mut in_out)
+        .map_err(|_| ServerError::Odoh("AEAD encryption failed".to_string()))?;
+    Ok(in_out)
+}
+
+fn aead_open(key: &[u8], nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = aead_key(key)?
+        .open_in_place(aead_nonce(nonce)?, Aad::from(aad), &mut in_out)
+        .map_err(|_| ServerError::Odoh("AEAD decryption failed".to_string()))?;
+    Ok(plaintext.to_vec())
+}
+
+fn check_u16_len(len: usize) -> Result<()> {
+    if len > u16::MAX as usize {
+        return Err(ServerError::Odoh(format!("ODoH field too long: {} bytes", len)));
+    }
+    Ok(())
+}
+
+fn put_u16_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u16).to_be_bytes());
+}
+
+// 读取私钥文件，返回密钥及其修改时间（作为创建时间）
+fn load_key_file(path: &Path) -> Result<Option<(OdohKeyPair, SystemTime)>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read(path)?;
+    let key = OdohKeyPair::from_raw(&raw)
+        .map_err(|e| ServerError::Odoh(format!("{}: {}", path.display(), e)))?;
+    let created_at = fs::metadata(path)?.modified().unwrap_or_else(|_| SystemTime::now());
+    Ok(Some((key, created_at)))
+}
+
+// 以仅所有者可读写的权限写入私钥文件（先写临时文件再重命名）
+fn write_key_file(path: &Path, raw: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(&tmp_path)?.write_all(raw)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+// 大端长度前缀字段读取器
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(ServerError::Odoh("Truncated ODoH message".to_string()));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u16_prefixed(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_u16()? as usize;
+        self.take(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(s: &str) -> Vec<u8> {
+        hex::decode(s).unwrap()
+    }
+
+    // RFC 9180 A.1: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, AES-128-GCM, Base 模式
+    const INFO: &str = "4f6465206f6e2061204772656369616e2055726e";
+    const SK_RM: &str = "4612c550263fc8ad58375df3f557aac531d26850903e55a9f23f21d8534e8ac8";
+    const PK_RM: &str = "3948cfe0ad1ddb695d780e59077195da6c56506b027329794ab02bca80815c4d";
+    const ENC: &str = "37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431";
+    const SHARED_SECRET: &str = "fe0e18c9f024ce43799ae393c7e8fe8fce9d218875e8227b0187c04e7d2ea1fc";
+    const KEY_SCHEDULE_CONTEXT: &str = "00725611c9d98c07c03f60095cd32d400d8347d45ed67097bbad50fc56da742d07cb6cffde367bb0565ba28bb02c90744a20f5ef37f30523526106f637abb05449";
+    const SECRET: &str = "12fff91991e93b48de37e7daddb52981084bd8aa64289c3788471d9a9712f397";
+    const KEY: &str = "4531685d41d65f03dc48f6b8302c05b0";
+    const BASE_NONCE: &str = "56d890e5accaaf011cff4b7d";
+    const EXPORTER_SECRET: &str = "45ff1c2e220db587171952c0592d5f5ebe103f1561a2614e38f2ffd47e99e3f8";
+
+    // ODoH 报文向量：目标密钥与临时密钥沿用 A.1，由独立实现（Python cryptography 库）按 RFC 9230 第 6 节计算
+    const KEY_ID: &str = "9e8dcd70b0b660258285b685197740e491cbdd8101b1783affdfeba52e09bc79";
+    const DNS_QUERY: &str = "000001000001000000000000076578616d706c6503636f6d0000010001";
+    const DNS_RESPONSE: &str = "000081800001000100000000076578616d706c6503636f6d0000010001c00c000100010000012c0004c0000201";
+    const ODOH_QUERY: &str = "0100209e8dcd70b0b660258285b685197740e491cbdd8101b1783affdfeba52e09bc79005137fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431ad7537701ae754a322204196c2fbaeff944b3cc630515f8f31435a0d7cb7c82ae4ed413846288fca5dd4695128b780dbed";
+    const RESPONSE_SECRET: &str = "bd039dcd467b35500d6dd2524faf920a";
+    const ODOH_RESPONSE: &str = "020010000102030405060708090a0b0c0d0e0f00410bcbe471238beac7777b03dc3ea9e88c2a9ed5789fe675c33d9eef3e562892b11cc6b02d121b0427872024ed4627736e86dd5e097114cce46473362a4f6fdd1308";
+
+    fn target_with_key(key: OdohKeyPair) -> OdohTarget {
+        let keys = OdohKeys { current: Arc::new(key), previous: None, created_at: SystemTime::now() };
+        OdohTarget { config: OdohConfig::default(), keys: RwLock::new(keys) }
+    }
+
+    #[test]
+    fn test_hpke_base_mode_known_answer() {
+        let key = OdohKeyPair::from_raw(&unhex(SK_RM)).unwrap();
+        assert_eq!(key.public_key, unhex(PK_RM));
+
+        let shared_secret = decap(&unhex(ENC), &key.private_key, &key.public_key).unwrap();
+        assert_eq!(shared_secret, unhex(SHARED_SECRET));
+
+        // 密钥调度的中间值
+        let suite_id = hpke_suite_id();
+        let mut key_schedule_context = vec![HPKE_MODE_BASE];
+        key_schedule_context.extend_from_slice(&labeled_extract(&suite_id, &[], b"psk_id_hash", &[]));
+        key_schedule_context.extend_from_slice(&labeled_extract(&suite_id, &[], b"info_hash", &unhex(INFO)));
+        assert_eq!(key_schedule_context, unhex(KEY_SCHEDULE_CONTEXT));
+        let secret = labeled_extract(&suite_id, &shared_secret, b"secret", &[]);
+        assert_eq!(secret, unhex(SECRET));
+        assert_eq!(labeled_expand(&suite_id, &secret, b"key", &key_schedule_context, N_K), unhex(KEY));
+
+        let context = HpkeContext::new(&shared_secret, &unhex(INFO));
+        assert_eq!(context.key, unhex(KEY));
+        assert_eq!(context.base_nonce, unhex(BASE_NONCE));
+        assert_eq!(context.exporter_secret, unhex(EXPORTER_SECRET));
+
+        // 序号 0 的加密
+        let ciphertext = context.seal(b"Count-0", b"Beauty is truth, truth beauty").unwrap();
+        assert_eq!(ciphertext, unhex("f938558b5d72f1a23810b4be2ab4f84331acc02fc97babc53a52ae8218a355a96d8770ac83d07bea87e13c512a"));
+        assert_eq!(context.open(b"Count-0", &ciphertext).unwrap(), b"Beauty is truth, truth beauty");
+
+        // 导出值
+        for (exporter_context, exported) in [
+            ("", "3853fe2b4035195a573ffc53856e77058e15d9ea064de3e59f4961d0095250ee"),
+            ("00", "2e8f0b54673c7029649d4eb9d5e33bf1872cf76d623ff164ac185da9e88c21a5"),
+            ("54657374436f6e74657874", "e9e43065102c3836401bed8c3c3c75ae46be1639869391d62c61f1ec7af54931"),
+        ] {
+            assert_eq!(context.export(&unhex(exporter_context), N_H), unhex(exported));
+        }
+    }
+
+    #[test]
+    fn test_odoh_known_answer() {
+        let key = OdohKeyPair::from_raw(&unhex(SK_RM)).unwrap();
+        assert_eq!(compute_key_id(&config_contents(&unhex(PK_RM))), unhex(KEY_ID));
+        assert_eq!(key.key_id, unhex(KEY_ID));
+
+        // 目标解密查询并导出响应密钥材料
+        let target = target_with_key(key);
+        let (dns_message, responder) = target.decrypt_query(&unhex(ODOH_QUERY)).unwrap();
+        assert_eq!(dns_message, unhex(DNS_QUERY));
+        assert_eq!(responder.response_secret, unhex(RESPONSE_SECRET));
+
+        // 固定响应 nonce 时响应密文确定
+        let response_nonce: Vec<u8> = (0..16).collect();
+        let response = responder.encrypt_response_with_nonce(&unhex(DNS_RESPONSE), &response_nonce).unwrap();
+        assert_eq!(response, unhex(ODOH_RESPONSE));
+
+        let client = OdohClientQuery { query_plaintext: responder.query_plaintext, response_secret: responder.response_secret };
+        assert_eq!(client.decrypt_response(&response).unwrap(), unhex(DNS_RESPONSE));
+    }
+}
@@ -0,0 +1,31 @@
+// src/server/padding.rs
+
+// 该模块实现 EDNS(0) 填充（RFC 7830），按 RFC 8467 的块大小策略
+// 将 DNS 消息填充到固定长度的整数倍，以抵抗基于报文长度的流量分析。
+
+use hickory_proto::op::{Edns, Message};
+use hickory_proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+use crate::common::consts::EDNS_PADDING_OPTION_CODE;
+use crate::server::error::Result;
+
+// 将消息填充到 block_size 的整数倍，返回添加的填充字节数
+//
+// 消息中已有的填充选项会被替换；没有 OPT 记录时会添加一个。
+pub fn pad_message(message: &mut Message, block_size: usize) -> Result<usize> {
+    let padding_code = EdnsCode::from(EDNS_PADDING_OPTION_CODE);
+    let edns = message.extensions_mut().get_or_insert_with(Edns::new);
+
+    // 先添加空的填充选项，计算包含选项头部（4 字节）在内的长度
+    edns.options_mut().insert(EdnsOption::Unknown(padding_code.into(), Vec::new()));
+    let unpadded_len = message.to_vec()?.len();
+
+    let padding_len = (block_size - unpadded_len % block_size) % block_size;
+    if padding_len > 0 {
+        if let Some(edns) = message.extensions_mut() {
+            edns.options_mut().insert(EdnsOption::Unknown(padding_code.into(), vec![0; padding_len]));
+        }
+    }
+
+    Ok(padding_len)
+}
+
@@ -0,0 +1,198 @@
+// src/server/proxy_protocol.rs
+
+// 该模块在 TCP 监听接受连接后、交给 HTTP/TLS/DNS 处理之前解析 PROXY 协议头（v1 文本格式与 v2 二进制格式），
+// 使位于四层负载均衡器之后的监听获得真实的客户端地址，供速率限制、访问控制与访问日志使用。
+//
+// 只有连接源地址属于 trusted_proxies 的连接才解析 PROXY 头，其他连接按直连客户端处理且不读取任何数据，
+// 因此客户端无法通过伪造 PROXY 头冒充其他地址。可信代理的连接缺少有效的 PROXY 头时关闭连接。
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::time::timeout;
+use tracing::debug;
+use crate::common::consts::{
+    PROXY_PROTOCOL_HEADER_TIMEOUT_SECS, PROXY_PROTOCOL_V1_MAX_LEN, PROXY_PROTOCOL_V1_PREFIX,
+    PROXY_PROTOCOL_V2_SIGNATURE,
+};
+use crate::server::config::HttpServerConfig;
+use crate::server::ip_set::{parse_network, IpRangeSet};
+use crate::server::metrics::METRICS;
+
+// v2 头中的版本（高 4 位）与命令（低 4 位）
+const V2_VERSION: u8 = 0x2;
+const V2_COMMAND_LOCAL: u8 = 0x0;
+const V2_COMMAND_PROXY: u8 = 0x1;
+
+// v2 头中的地址族（高 4 位）
+const V2_FAMILY_INET: u8 = 0x1;
+const V2_FAMILY_INET6: u8 = 0x2;
+
+// v2 地址部分的长度：源地址、目标地址、源端口与目标端口
+const V2_INET_ADDRESS_LEN: usize = 12;
+const V2_INET6_ADDRESS_LEN: usize = 36;
+
+// PROXY 协议解析器
+#[derive(Debug, Clone)]
+pub struct ProxyProtocol {
+    // 允许发送 PROXY 头的代理地址
+    trusted_proxies: IpRangeSet,
+    // 拒绝指标中的监听标签
+    listener: &'static str,
+}
+
+impl ProxyProtocol {
+    pub fn new(trusted_proxies: IpRangeSet, listener: &'static str) -> Self {
+        Self { trusted_proxies, listener }
+    }
+
+    // 从 http_server.trusted_proxies 构建（各条目已在配置校验中检查）
+    pub fn from_config(config: &HttpServerConfig, listener: &'static str) -> Self {
+        Self::new(
+            IpRangeSet::from_networks(config.trusted_proxies.iter().filter_map(|entry| parse_network(entry).ok())),
+            listener,
+        )
+    }
+
+    // 读取连接上的 PROXY 头并返回客户端地址
+    //
+    // 连接源地址不是可信代理时不读取任何数据，直接返回连接源地址；
+    // LOCAL 命令（如负载均衡器的健康检查）与未知地址族同样使用连接源地址。
+    pub async fn accept<S>(&self, stream: &mut S, peer: SocketAddr) -> io::Result<SocketAddr>
+    where
+        S: AsyncRead + Unpin,
+    {
+        if !self.trusted_proxies.contains(peer.ip()) {
+            debug!(client_ip = %peer.ip(), "Connection is not from a trusted proxy, ignoring PROXY protocol");
+            return Ok(peer);
+        }
+
+        let header_timeout = Duration::from_secs(PROXY_PROTOCOL_HEADER_TIMEOUT_SECS);
+        let source = timeout(header_timeout, read_header(stream)).await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "timed out reading PROXY protocol header"))
+            .and_then(|result| result)
+            .inspect_err(|_| METRICS.proxy_protocol_rejected_total().with_label_values(&[self.listener]).inc())?;
+        if let Some(source) = source {
+            debug!(proxy_ip = %peer.ip(), client_ip = %source.ip(), "Accepted PROXY protocol header");
+        }
+        Ok(source.unwrap_or(peer))
+    }
+}
+
+// 未启用 PROXY 协议时直接返回连接源地址
+pub async fn client_addr<S>(proxy_protocol: Option<&ProxyProtocol>, stream: &mut S, peer: SocketAddr) -> io::Result<SocketAddr>
+where
+    S: AsyncRead + Unpin,
+{
+    match proxy_protocol {
+        Some(proxy_protocol) => proxy_protocol.accept(stream, peer).await,
+        None => Ok(peer),
+    }
+}
+
+// 读取并解析 PROXY 头，只消费头部本身；返回 None 表示头中没有可用的客户端地址
+pub async fn read_header<S>(stream: &mut S) -> io::Result<Option<SocketAddr>>
+where
+    S: AsyncRead + Unpin,
+{
+    // 最短的 v1 头（"PROXY UNKNOWN\r\n"）与 v2 签名都不短于 12 字节
+    let mut signature = [0u8; PROXY_PROTOCOL_V2_SIGNATURE.len()];
+    stream.read_exact(&mut signature).await?;
+
+    if signature == PROXY_PROTOCOL_V2_SIGNATURE {
+        read_v2(stream).await
+    } else if signature.starts_with(PROXY_PROTOCOL_V1_PREFIX) {
+        read_v1(stream, &signature).await
+    } else {
+        Err(invalid_header("missing PROXY protocol header"))
+    }
+}
+
+// 读取 v1 头的剩余部分（直到 CRLF）并解析
+async fn read_v1<S>(stream: &mut S, prefix: &[u8]) -> io::Result<Option<SocketAddr>>
+where
+    S: AsyncRead + Unpin,
+{
+    // 逐字节读取，避免读取到头部之后的应用数据
+    let mut line = prefix.to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= PROXY_PROTOCOL_V1_MAX_LEN {
+            return Err(invalid_header("PROXY protocol v1 header is too long"));
+        }
+        line.push(stream.read_u8().await?);
+    }
+
+    let line = std::str::from_utf8(&line[..line.len() - 2])
+        .map_err(|_| invalid_header("PROXY protocol v1 header is not valid ASCII"))?;
+    parse_v1(line)
+}
+
+// 解析 v1 头（不含结尾的 CRLF），例如 "PROXY TCP4 192.0.2.1 198.51.100.1 56324 443"
+pub fn parse_v1(line: &str) -> io::Result<Option<SocketAddr>> {
+    let mut fields = line.split(' ');
+    if fields.next() != Some("PROXY") {
+        return Err(invalid_header("missing PROXY protocol v1 prefix"));
+    }
+
+    let protocol = fields.next().unwrap_or_default();
+    if protocol == "UNKNOWN" {
+        return Ok(None);
+    }
+    let (Some(source), Some(_destination), Some(source_port), Some(_destination_port), None) =
+        (fields.next(), fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return Err(invalid_header("malformed PROXY protocol v1 header"));
+    };
+
+    let port = source_port.parse::<u16>()
+        .map_err(|_| invalid_header("invalid source port in PROXY protocol v1 header"))?;
+    let ip = match protocol {
+        "TCP4" => source.parse::<Ipv4Addr>().map(Into::into),
+        "TCP6" => source.parse::<Ipv6Addr>().map(Into::into),
+        _ => return Err(invalid_header("unsupported protocol in PROXY protocol v1 header")),
+    }.map_err(|_| invalid_header("invalid source address in PROXY protocol v1 header"))?;
+
+    Ok(Some(SocketAddr::new(ip, port)))
+}
+
+// 读取签名之后的 v2 头并解析，跳过地址之后的 TLV 扩展
+async fn read_v2<S>(stream: &mut S) -> io::Result<Option<SocketAddr>>
+where
+    S: AsyncRead + Unpin,
+{
+    let version_command = stream.read_u8().await?;
+    let family = stream.read_u8().await?;
+    let len = stream.read_u16().await? as usize;
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await?;
+
+    if version_command >> 4 != V2_VERSION {
+        return Err(invalid_header("unsupported PROXY protocol version"));
+    }
+    match version_command & 0x0f {
+        V2_COMMAND_LOCAL => return Ok(None),
+        V2_COMMAND_PROXY => {},
+        _ => return Err(invalid_header("unsupported PROXY protocol v2 command")),
+    }
+
+    match family >> 4 {
+        V2_FAMILY_INET if len >= V2_INET_ADDRESS_LEN => {
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(&payload[..4]).unwrap_or_default());
+            let port = u16::from_be_bytes([payload[8], payload[9]]);
+            Ok(Some(SocketAddr::new(ip.into(), port)))
+        },
+        V2_FAMILY_INET6 if len >= V2_INET6_ADDRESS_LEN => {
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&payload[..16]).unwrap_or_default());
+            let port = u16::from_be_bytes([payload[32], payload[33]]);
+            Ok(Some(SocketAddr::new(ip.into(), port)))
+        },
+        V2_FAMILY_INET | V2_FAMILY_INET6 => Err(invalid_header("truncated PROXY protocol v2 address block")),
+        // 未指定或 Unix 域套接字地址族没有可用的客户端 IP
+        _ => Ok(None),
+    }
+}
+
+fn invalid_header(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
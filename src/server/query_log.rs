@@ -0,0 +1,342 @@
+// src/server/query_log.rs
+
+// 该模块实现 DNS 查询访问日志（logging.query_log）。
+//
+// 每条查询输出一行 JSON：时间、客户端 IP、协议、查询名与类型、响应码、应答数、缓存状态、
+// 命中的路由规则、上游组与解析器以及总耗时。日志行经有界队列交给独立的写入线程输出，
+// 队列满时丢弃新的日志行并计入 owdns_query_log_dropped_total，不阻塞请求处理。
+//
+// 路由规则与上游信息由解析流程通过 record_rule / record_upstream 写入当前查询的追踪上下文，
+// 查询在 trace 范围之外处理（如后台未命中解析任务）时不记录。
+//
+// 输出前先按顺序求值 filters，首个匹配的过滤器决定记录（log，不受采样率影响）或跳过（skip）；
+// 未匹配任何过滤器的查询按 sample_rate 采样。
+
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::{self, BufWriter, Write};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use hickory_proto::op::Message;
+use hickory_proto::rr::RecordType;
+use regex::RegexSet;
+use ring::hmac;
+use ring::rand::SystemRandom;
+use serde::Serialize;
+use tracing::warn;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use crate::common::consts::{QUERY_LOG_CHANNEL_CAPACITY, QUERY_LOG_CLIENT_IP_HASH_BYTES};
+use crate::server::config::{
+    MatchType, QueryLogConfig, QueryLogFilterAction, QueryLogFilterConfig, QueryLogFormat, QueryLogTarget,
+};
+use crate::server::error::{Result, ServerError};
+use crate::server::metrics::METRICS;
+use crate::server::request_id::current_request_id;
+use crate::server::routing::normalize_query_domain;
+
+// 缓存状态
+const CACHE_STATUS_HIT: &str = "hit";
+const CACHE_STATUS_MISS: &str = "miss";
+
+// 查询协议标签
+pub const QUERY_PROTOCOL_DOH_GET: &str = "doh-get";
+pub const QUERY_PROTOCOL_DOH_POST: &str = "doh-post";
+pub const QUERY_PROTOCOL_JSON: &str = "json";
+pub const QUERY_PROTOCOL_ODOH: &str = "odoh";
+pub const QUERY_PROTOCOL_WEBSOCKET: &str = "websocket";
+pub const QUERY_PROTOCOL_IN_PROCESS: &str = "in-process";
+
+tokio::task_local! {
+    static QUERY_TRACE: Arc<Mutex<QueryTrace>>;
+}
+
+// 解析流程中记录的路由与上游信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryTrace {
+    // 命中的路由规则
+    pub rule: Option<String>,
+    // 最终应答的上游组
+    pub upstream_group: Option<String>,
+    // 最终应答的解析器
+    pub resolver: Option<String>,
+}
+
+// 在追踪上下文中执行查询处理，返回处理结果与记录的追踪信息
+//
+// enabled 为 false 时直接执行，不建立追踪上下文。
+pub async fn trace<F: Future>(enabled: bool, future: F) -> (F::Output, QueryTrace) {
+    if !enabled {
+        return (future.await, QueryTrace::default());
+    }
+    let trace = Arc::new(Mutex::new(QueryTrace::default()));
+    let output = QUERY_TRACE.scope(trace.clone(), future).await;
+    let trace = std::mem::take(&mut *trace.lock().unwrap_or_else(|e| e.into_inner()));
+    (output, trace)
+}
+
+// 修改当前查询的追踪信息，不在追踪上下文中时不执行
+fn update_trace(update: impl FnOnce(&mut QueryTrace)) {
+    let _ = QUERY_TRACE.try_with(|trace| {
+        update(&mut trace.lock().unwrap_or_else(|e| e.into_inner()));
+    });
+}
+
+// 记录命中的路由规则，rule 仅在追踪上下文中求值
+pub fn record_rule(rule: impl FnOnce() -> String) {
+    update_trace(|trace| trace.rule = Some(rule()));
+}
+
+// 记录应答的上游组与解析器；故障转移时以最后一次成功的上游为准
+pub fn record_upstream(upstream_group: &str, resolver: &str) {
+    update_trace(|trace| {
+        trace.upstream_group = Some(upstream_group.to_string());
+        trace.resolver = Some(resolver.to_string());
+    });
+}
+
+// 一条已完成的查询
+pub struct QueryRecord<'a> {
+    // 查询协议
+    pub protocol: &'a str,
+    // 客户端 IP（已按受信任代理解析）
+    pub client_ip: Option<IpAddr>,
+    // 客户端查询
+    pub query: &'a Message,
+    // 返回给客户端的响应
+    pub response: &'a Message,
+    // 是否由缓存应答
+    pub cached: bool,
+    // 路由与上游信息
+    pub trace: QueryTrace,
+    // 总耗时
+    pub latency: Duration,
+}
+
+// 查询日志的一行
+#[derive(Debug, Serialize)]
+struct QueryLogEntry<'a> {
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_ip: Option<String>,
+    protocol: &'a str,
+    qname: String,
+    qtype: String,
+    rcode: String,
+    answer_count: usize,
+    cache: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upstream_group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolver: Option<String>,
+    latency_ms: f64,
+}
+
+// 查询名匹配方式
+enum QueryNameMatcher {
+    // 未配置查询名时匹配所有查询
+    Any,
+    Exact(HashSet<String>),
+    Regex(RegexSet),
+}
+
+// 查询日志过滤器（logging.query_log.filters 中的一项）
+pub struct QueryLogFilter {
+    names: QueryNameMatcher,
+    // 为空时匹配所有查询类型
+    query_types: Vec<RecordType>,
+    action: QueryLogFilterAction,
+}
+
+impl QueryLogFilter {
+    pub fn new(config: &QueryLogFilterConfig) -> Result<Self> {
+        let match_ = &config.match_;
+        let names = if match_.values.is_empty() {
+            QueryNameMatcher::Any
+        } else {
+            match match_.type_ {
+                MatchType::Exact => QueryNameMatcher::Exact(
+                    match_.values.iter().map(|name| normalize_query_domain(name.trim())).collect()
+                ),
+                MatchType::Regex => QueryNameMatcher::Regex(RegexSet::new(&match_.values).map_err(|e| ServerError::Config(
+                    format!("Invalid query log filter regex: {}", e)
+                ))?),
+                ref other => return Err(ServerError::Config(format!(
+                    "Unsupported query log filter match type {:?}", other
+                ))),
+            }
+        };
+        let query_types = match_.query_types.iter()
+            .map(|query_type| RecordType::from_str(&query_type.to_uppercase()).map_err(|_| ServerError::Config(
+                format!("Unknown query log filter query type '{}'", query_type)
+            )))
+            .collect::<Result<_>>()?;
+        Ok(Self { names, query_types, action: config.action })
+    }
+
+    // qname 为小写且不含末尾的点
+    pub fn matches(&self, qname: &str, qtype: RecordType) -> bool {
+        let name_matches = match &self.names {
+            QueryNameMatcher::Any => true,
+            QueryNameMatcher::Exact(names) => names.contains(qname),
+            QueryNameMatcher::Regex(set) => set.is_match(qname),
+        };
+        name_matches && (self.query_types.is_empty() || self.query_types.contains(&qtype))
+    }
+
+    pub fn action(&self) -> QueryLogFilterAction {
+        self.action
+    }
+}
+
+// 查询日志记录器
+pub struct QueryLogger {
+    sender: SyncSender<String>,
+    format: QueryLogFormat,
+    // 客户端 IP 摘要密钥，未启用 hash_client_ip 时为 None
+    client_ip_key: Option<hmac::Key>,
+    filters: Vec<QueryLogFilter>,
+    sample_rate: f64,
+}
+
+impl QueryLogger {
+    // 按配置打开输出目标并启动写入线程
+    pub fn new(config: &QueryLogConfig) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = match config.target {
+            QueryLogTarget::Stdout => Box::new(io::stdout()),
+            QueryLogTarget::File => {
+                let path = config.path.as_deref().ok_or_else(|| ServerError::Config(
+                    "logging.query_log.path is required when target is file".to_string()
+                ))?;
+                let file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| ServerError::Config(
+                    format!("Failed to open query log file '{}': {}", path, e)
+                ))?;
+                Box::new(file)
+            },
+        };
+
+        let client_ip_key = if config.hash_client_ip {
+            Some(match &config.hash_key {
+                Some(key) => hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()),
+                None => hmac::Key::generate(hmac::HMAC_SHA256, &SystemRandom::new()).map_err(|_| ServerError::Config(
+                    "Failed to generate query log client IP hash key".to_string()
+                ))?,
+            })
+        } else {
+            None
+        };
+
+        let filters = config.filters.iter().map(QueryLogFilter::new).collect::<Result<_>>()?;
+
+        let (sender, receiver) = mpsc::sync_channel(QUERY_LOG_CHANNEL_CAPACITY);
+        thread::Builder::new()
+            .name("owdns-query-log".to_string())
+            .spawn(move || write_lines(receiver, BufWriter::new(writer)))
+            .map_err(ServerError::Io)?;
+
+        Ok(Self {
+            sender,
+            format: config.format,
+            client_ip_key,
+            filters,
+            sample_rate: config.sample_rate,
+        })
+    }
+
+    // 按过滤器与采样率决定是否记录该查询
+    fn should_log(&self, query: &Message) -> bool {
+        if let (false, Some(question)) = (self.filters.is_empty(), query.queries().first()) {
+            let qname = normalize_query_domain(&question.name().to_ascii());
+            if let Some(filter) = self.filters.iter().find(|filter| filter.matches(&qname, question.query_type())) {
+                return filter.action() == QueryLogFilterAction::Log;
+            }
+        }
+        self.sample_rate >= 1.0 || fastrand::f64() < self.sample_rate
+    }
+
+    // 输出一条查询日志；写入线程处理不及时时丢弃
+    pub fn log(&self, record: QueryRecord<'_>) {
+        if !self.should_log(record.query) {
+            return;
+        }
+
+        let question = record.query.queries().first();
+        let entry = QueryLogEntry {
+            timestamp: timestamp(),
+            request_id: current_request_id(),
+            client_ip: record.client_ip.map(|ip| self.format_client_ip(ip)),
+            protocol: record.protocol,
+            qname: question.map(|q| q.name().to_ascii()).unwrap_or_default(),
+            qtype: question.map(|q| q.query_type().to_string()).unwrap_or_default(),
+            rcode: format!("{:?}", record.response.response_code()),
+            answer_count: record.response.answers().len(),
+            cache: if record.cached { CACHE_STATUS_HIT } else { CACHE_STATUS_MISS },
+            rule: record.trace.rule,
+            upstream_group: record.trace.upstream_group,
+            resolver: record.trace.resolver,
+            latency_ms: record.latency.as_secs_f64() * 1000.0,
+        };
+
+        let line = match self.format {
+            QueryLogFormat::Json => match serde_json::to_string(&entry) {
+                Ok(line) => line,
+                Err(e) => {
+                    warn!(error = %e, "Failed to serialize query log entry");
+                    return;
+                },
+            },
+        };
+
+        match self.sender.try_send(line) {
+            Ok(()) => {},
+            Err(TrySendError::Full(_)) => METRICS.query_log_dropped_total().inc(),
+            Err(TrySendError::Disconnected(_)) => {
+                METRICS.query_log_dropped_total().inc();
+                warn!("Query log writer has stopped, dropping query log line");
+            },
+        }
+    }
+
+    // 启用 hash_client_ip 时输出客户端 IP 的 HMAC-SHA256 摘要（截断），否则输出原始地址
+    fn format_client_ip(&self, ip: IpAddr) -> String {
+        match &self.client_ip_key {
+            Some(key) => {
+                let tag = hmac::sign(key, ip.to_string().as_bytes());
+                hex::encode(&tag.as_ref()[..QUERY_LOG_CLIENT_IP_HASH_BYTES])
+            },
+            None => ip.to_string(),
+        }
+    }
+}
+
+// 写入线程：逐行写出，队列暂时为空时刷新缓冲区；所有发送端关闭后退出
+fn write_lines(receiver: Receiver<String>, mut writer: BufWriter<Box<dyn Write + Send>>) {
+    while let Ok(line) = receiver.recv() {
+        let mut result = writeln!(writer, "{}", line);
+        while result.is_ok() {
+            let Ok(line) = receiver.try_recv() else {
+                break;
+            };
+            result = writeln!(writer, "{}", line);
+        }
+        if let Err(e) = result.and_then(|_| writer.flush()) {
+            warn!(error = %e, "Failed to write query log");
+        }
+    }
+}
+
+// RFC 3339 格式的当前时间
+fn timestamp() -> String {
+    let mut timestamp = String::new();
+    let _ = SystemTime.format_time(&mut Writer::new(&mut timestamp));
+    timestamp
+}
@@ -0,0 +1,49 @@
+// src/server/record_dedup.rs
+
+// 该模块去除 DNS 响应中重复的资源记录。
+//
+// 部分上游（常见于启用 DNSSEC 的解析器）会在应答、授权与附加部分重复携带同一记录（如 RRSIG），
+// 按 (名称, 类型, 记录数据) 判断重复，只保留按应答、授权、附加顺序首次出现的记录。
+
+use hickory_proto::op::Message;
+use hickory_proto::rr::Record;
+
+// 去除三个部分中重复的记录，返回移除的记录数
+pub fn remove_duplicate_records(message: &mut Message) -> usize {
+    let mut seen: Vec<Record> = Vec::new();
+    let mut removed = 0;
+    let mut retain_unique = |records: Vec<Record>| -> Vec<Record> {
+        records.into_iter()
+            .filter(|record| {
+                if seen.iter().any(|kept| is_same_record(kept, record)) {
+                    removed += 1;
+                    return false;
+                }
+                seen.push(record.clone());
+                true
+            })
+            .collect()
+    };
+
+    let answers = retain_unique(message.take_answers());
+    let name_servers = retain_unique(message.take_name_servers());
+    let additionals = retain_unique(message.take_additionals());
+
+    // 同步更新头部计数，附加部分的计数包含 OPT 记录
+    let edns_count = u16::from(message.extensions().is_some());
+    let mut header = *message.header();
+    header
+        .set_answer_count(answers.len() as u16)
+        .set_name_server_count(name_servers.len() as u16)
+        .set_additional_count(additionals.len() as u16 + edns_count);
+    message.set_header(header);
+    message.insert_answers(answers);
+    message.insert_name_servers(name_servers);
+    message.insert_additionals(additionals);
+    removed
+}
+
+// 名称（不区分大小写）、类型与记录数据均相同时视为重复，TTL 不参与比较
+fn is_same_record(a: &Record, b: &Record) -> bool {
+    a.record_type() == b.record_type() && a.name() == b.name() && a.data() == b.data()
+}
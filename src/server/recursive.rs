@@ -0,0 +1,466 @@
+// src/server/recursive.rs
+
+// 该模块实现递归解析模式（upstream.mode: recursive）。
+//
+// 从根服务器开始迭代查询：向已知最近区域的名称服务器发送不带 RD 标志的查询，
+// 按推荐（referral）响应中的 NS 记录与粘合记录逐级下降，直到得到权威应答。
+// 推荐得到的区域委派按 NS 记录的 TTL 缓存，后续查询从最近的已缓存区域开始；
+// 没有粘合记录的名称服务器地址与 CNAME 链的目标同样通过迭代解析获得。
+//
+// 启用 DNSSEC 时迭代解析器由 hickory 的 DnssecDnsHandle 包装，从内置的根信任锚开始验证完整的信任链，
+// 验证过程中查询的 DNSKEY/DS 响应按 TTL 缓存。没有签名的响应原样返回且不设置 AD 标志，
+// 带有签名但验证失败的响应视为伪造，返回 SERVFAIL。
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::stream::{self, Stream, StreamExt};
+use hickory_proto::error::ProtoError;
+use hickory_proto::op::{Edns, Message, MessageType, OpCode, Query, ResponseCode};
+use hickory_proto::rr::{Name, RData, Record, RecordType};
+use hickory_proto::xfer::{DnsHandle, DnsRequest, DnsRequestOptions, DnsResponse, DnssecDnsHandle};
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+use tracing::debug;
+use crate::common::consts::{
+    DNS_NAMESERVER_PORT, RECURSIVE_MAX_REFERRALS, RECURSIVE_MAX_DEPTH, RECURSIVE_SERVER_TIMEOUT_MS,
+    RECURSIVE_EDNS_PAYLOAD_SIZE, RECURSIVE_CACHE_MAX_ENTRIES, RECURSIVE_CACHE_MAX_TTL_SECS,
+};
+use crate::server::config::UpstreamConfig;
+use crate::server::error::{Result, ServerError};
+use crate::server::upstream::{connect_tcp, exchange_framed, TransportFuture, UpstreamTransport};
+
+// 单个 UDP 响应的最大长度
+const MAX_UDP_RESPONSE_SIZE: usize = 65_535;
+
+// 递归解析器，作为全局上游的传输使用
+pub struct RecursiveResolver {
+    iterator: IterativeHandle,
+    // 启用 DNSSEC 时验证信任链的包装
+    validator: Option<DnssecDnsHandle<IterativeHandle>>,
+    // 单次查询的总超时
+    query_timeout: Duration,
+}
+
+impl UpstreamTransport for RecursiveResolver {
+    fn exchange<'a>(&'a self, _upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+        Box::pin(self.query(query))
+    }
+}
+
+impl RecursiveResolver {
+    // 按上游配置创建递归解析器
+    pub fn new(config: &UpstreamConfig) -> Result<Self> {
+        Self::with_nameserver_port(config, DNS_NAMESERVER_PORT)
+    }
+    
+    // 创建向推荐的名称服务器发送查询时使用指定端口的递归解析器（根服务器仍使用 root_hints 中的端口），
+    // 用于在本地模拟完整的解析链
+    pub fn with_nameserver_port(config: &UpstreamConfig, nameserver_port: u16) -> Result<Self> {
+        let iterator = IterativeHandle(Arc::new(IterativeResolver {
+            root_hints: config.parsed_root_hints()?,
+            nameserver_port,
+            bind_addr: config.bind_addr,
+            dnssec_ok: config.enable_dnssec,
+            delegations: Mutex::new(HashMap::new()),
+            responses: Mutex::new(HashMap::new()),
+        }));
+        let validator = config.enable_dnssec.then(|| DnssecDnsHandle::new(iterator.clone()));
+        
+        Ok(Self {
+            iterator,
+            validator,
+            query_timeout: Duration::from_secs(config.query_timeout),
+        })
+    }
+    
+    async fn query(&self, query_message: &Message) -> Result<Message> {
+        let query = query_message.queries().first().cloned().ok_or_else(||
+            ServerError::Upstream("No query in message".to_string())
+        )?;
+        
+        let lookup = async {
+            match &self.validator {
+                Some(validator) => self.validated_lookup(validator, query.clone()).await,
+                None => self.iterator.0.resolve(query.clone(), 0).await.map(|response| (response, false)),
+            }
+        };
+        let (response, authentic) = tokio::time::timeout(self.query_timeout, lookup)
+            .await
+            .map_err(|_| ServerError::UpstreamUnavailable(format!("Recursive resolution of {} timed out", query.name())))??;
+        
+        // 构建返回给客户端的响应，负响应保留权威部分的 SOA 以便计算负缓存 TTL
+        let mut message = Message::new();
+        message.set_id(query_message.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(query_message.op_code())
+            .set_response_code(response.response_code())
+            .set_recursion_desired(query_message.recursion_desired())
+            .set_recursion_available(true)
+            .set_authentic_data(authentic);
+        message.add_queries(query_message.queries().to_vec());
+        message.add_answers(response.answers().to_vec());
+        if response.answers().is_empty() {
+            message.add_name_servers(response.name_servers().to_vec());
+        }
+        Ok(message)
+    }
+    
+    // 通过 DnssecDnsHandle 解析并验证信任链，返回响应与是否通过验证
+    async fn validated_lookup(&self, validator: &DnssecDnsHandle<IterativeHandle>, query: Query) -> Result<(Message, bool)> {
+        let result = validator.lookup(query.clone(), DnsRequestOptions::default()).next().await;
+        match result {
+            Some(Ok(response)) => return Ok((response.into_message(), true)),
+            Some(Err(e)) => debug!(name = %query.name(), error = %e, "DNSSEC validation did not succeed"),
+            None => {},
+        }
+        
+        // 区分未签名与验证失败：未签名的响应不设置 AD 标志返回，带签名的响应视为伪造
+        let response = self.iterator.0.resolve(query.clone(), 0).await?;
+        let signed = response.answers().iter()
+            .chain(response.name_servers())
+            .any(|record| record.record_type() == RecordType::RRSIG);
+        if signed {
+            return Err(ServerError::UpstreamValidation(format!(
+                "DNSSEC validation failed for {} {}", query.name(), query.query_type()
+            )));
+        }
+        Ok((response, false))
+    }
+}
+
+// 可被 DnssecDnsHandle 包装的迭代解析器句柄
+#[derive(Clone)]
+struct IterativeHandle(Arc<IterativeResolver>);
+
+impl DnsHandle for IterativeHandle {
+    type Response = Pin<Box<dyn Stream<Item = std::result::Result<DnsResponse, ProtoError>> + Send>>;
+    type Error = ProtoError;
+    
+    fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(&self, request: R) -> Self::Response {
+        let request = request.into();
+        let resolver = self.0.clone();
+        Box::pin(stream::once(async move {
+            let query = request.queries().first().cloned()
+                .ok_or_else(|| ProtoError::from("No query in request"))?;
+            let response = resolver.resolve(query, 0).await
+                .map_err(|e| ProtoError::from(e.to_string()))?;
+            DnsResponse::from_message(response)
+        }))
+    }
+}
+
+// 缓存的区域委派
+struct CachedDelegation {
+    servers: Vec<SocketAddr>,
+    expires_at: Instant,
+}
+
+// 缓存的 DNSKEY/DS 响应
+struct CachedResponse {
+    message: Message,
+    expires_at: Instant,
+}
+
+// 名称服务器响应的分类
+enum Step {
+    // 权威应答（包括需要继续跟随的 CNAME）
+    Answer,
+    // 推荐到更接近查询名称的区域
+    Referral(Name),
+    // 负响应或错误响应
+    Final,
+}
+
+struct IterativeResolver {
+    // 根服务器地址
+    root_hints: Vec<SocketAddr>,
+    // 推荐的名称服务器使用的端口
+    nameserver_port: u16,
+    // 查询的本地源地址
+    bind_addr: Option<IpAddr>,
+    // 是否请求 DNSSEC 记录
+    dnssec_ok: bool,
+    // 区域（小写）-> 名称服务器地址
+    delegations: Mutex<HashMap<Name, CachedDelegation>>,
+    // (名称（小写）, 类型) -> DNSKEY/DS 响应
+    responses: Mutex<HashMap<(Name, RecordType), CachedResponse>>,
+}
+
+impl IterativeResolver {
+    // 迭代解析查询；depth 为 CNAME 跟随与名称服务器地址解析的嵌套层数
+    fn resolve(&self, query: Query, depth: usize) -> Pin<Box<dyn Future<Output = Result<Message>> + Send + '_>> {
+        Box::pin(async move {
+            if depth > RECURSIVE_MAX_DEPTH {
+                return Err(ServerError::UpstreamUnavailable(format!(
+                    "Recursion depth exceeded while resolving {}", query.name()
+                )));
+            }
+            
+            let cache_key = (query.name().to_lowercase(), query.query_type());
+            let cacheable = matches!(query.query_type(), RecordType::DNSKEY | RecordType::DS);
+            if cacheable {
+                if let Some(message) = self.cached_response(&cache_key) {
+                    return Ok(message);
+                }
+            }
+            
+            // DS 记录由父区域提供，从查询名称的上级区域开始
+            let (mut zone, mut servers) = self.closest_delegation(query.name(), query.query_type() == RecordType::DS);
+            for _ in 0..RECURSIVE_MAX_REFERRALS {
+                let response = self.query_servers(&servers, &query).await?;
+                match classify(&response, &query, &zone) {
+                    Step::Answer => {
+                        let response = self.follow_cname(&query, response, depth).await?;
+                        if cacheable && response.response_code() == ResponseCode::NoError {
+                            self.cache_response(cache_key, &response);
+                        }
+                        return Ok(response);
+                    },
+                    Step::Final => return Ok(response),
+                    Step::Referral(child) => {
+                        debug!(name = %query.name(), zone = %child, "Following referral");
+                        servers = self.delegation_servers(&response, &zone, &child, depth).await?;
+                        zone = child;
+                    },
+                }
+            }
+            
+            Err(ServerError::UpstreamUnavailable(format!(
+                "Too many referrals while resolving {}", query.name()
+            )))
+        })
+    }
+    
+    // 应答只包含 CNAME 时继续解析链的目标，并把 CNAME 记录放在应答部分的前面
+    async fn follow_cname(&self, query: &Query, mut response: Message, depth: usize) -> Result<Message> {
+        if matches!(query.query_type(), RecordType::CNAME | RecordType::ANY) {
+            return Ok(response);
+        }
+        
+        let mut target = query.name().clone();
+        while let Some(next) = response.answers().iter().find_map(|record| match record.data() {
+            Some(RData::CNAME(cname)) if record.name() == &target => Some(cname.0.clone()),
+            _ => None,
+        }) {
+            if next == *query.name() {
+                break;
+            }
+            target = next;
+        }
+        let answered = response.answers().iter()
+            .any(|record| record.name() == &target && record.record_type() == query.query_type());
+        if target == *query.name() || answered {
+            return Ok(response);
+        }
+        
+        let mut target_response = self.resolve(Query::query(target, query.query_type()), depth + 1).await?;
+        let mut answers = response.take_answers();
+        answers.extend(target_response.take_answers());
+        target_response.insert_answers(answers);
+        Ok(target_response)
+    }
+    
+    // 从推荐响应中取得子区域名称服务器的地址：优先使用区域内的粘合记录，否则解析名称服务器名称
+    async fn delegation_servers(&self, response: &Message, zone: &Name, child: &Name, depth: usize) -> Result<Vec<SocketAddr>> {
+        let ns_records: Vec<&Record> = response.name_servers().iter()
+            .filter(|record| record.record_type() == RecordType::NS && record.name() == child)
+            .collect();
+        let ns_names: Vec<Name> = ns_records.iter()
+            .filter_map(|record| match record.data() {
+                Some(RData::NS(ns)) => Some(ns.0.clone()),
+                _ => None,
+            })
+            .collect();
+        
+        // 只接受被查询服务器有权提供的粘合记录，IPv4 地址优先
+        let mut servers: Vec<SocketAddr> = Vec::new();
+        for record_type in [RecordType::A, RecordType::AAAA] {
+            servers.extend(response.additionals().iter()
+                .filter(|record| record.record_type() == record_type && zone.zone_of(record.name()))
+                .filter(|record| ns_names.contains(record.name()))
+                .filter_map(record_ip)
+                .map(|ip| SocketAddr::new(ip, self.nameserver_port)));
+        }
+        
+        if servers.is_empty() {
+            for ns_name in &ns_names {
+                let Ok(addresses) = self.resolve(Query::query(ns_name.clone(), RecordType::A), depth + 1).await else {
+                    continue;
+                };
+                servers.extend(addresses.answers().iter()
+                    .filter(|record| record.record_type() == RecordType::A)
+                    .filter_map(record_ip)
+                    .map(|ip| SocketAddr::new(ip, self.nameserver_port)));
+                if !servers.is_empty() {
+                    break;
+                }
+            }
+        }
+        
+        if servers.is_empty() {
+            return Err(ServerError::UpstreamUnavailable(format!(
+                "No reachable name server address for zone {}", child
+            )));
+        }
+        
+        let ttl = ns_records.iter().map(|record| record.ttl()).min().unwrap_or(0);
+        self.cache_delegation(child, servers.clone(), ttl);
+        Ok(servers)
+    }
+    
+    // 依次向名称服务器发送查询，SERVFAIL/REFUSED 或无响应时尝试下一个
+    async fn query_servers(&self, servers: &[SocketAddr], query: &Query) -> Result<Message> {
+        let mut request = Message::new();
+        request.set_id(rand::random())
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(false)
+            .add_query(query.clone());
+        let mut edns = Edns::new();
+        edns.set_max_payload(RECURSIVE_EDNS_PAYLOAD_SIZE);
+        edns.set_dnssec_ok(self.dnssec_ok);
+        request.set_edns(edns);
+        let wire = request.to_vec()?;
+        
+        let mut last_response = None;
+        for server in servers {
+            let response = tokio::time::timeout(
+                Duration::from_millis(RECURSIVE_SERVER_TIMEOUT_MS),
+                self.exchange(*server, &request, &wire),
+            ).await;
+            match response {
+                Ok(Ok(response)) if matches!(response.response_code(), ResponseCode::ServFail | ResponseCode::Refused) => {
+                    debug!(server = %server, name = %query.name(), rcode = ?response.response_code(), "Name server did not answer, trying next");
+                    last_response = Some(response);
+                },
+                Ok(Ok(response)) => return Ok(response),
+                Ok(Err(e)) => debug!(server = %server, name = %query.name(), error = %e, "Name server query failed"),
+                Err(_) => debug!(server = %server, name = %query.name(), "Name server query timed out"),
+            }
+        }
+        
+        last_response.ok_or_else(|| ServerError::UpstreamUnavailable(format!(
+            "No name server responded for {}", query.name()
+        )))
+    }
+    
+    // 通过 UDP 发送查询，响应被截断时改用 TCP
+    async fn exchange(&self, server: SocketAddr, request: &Message, wire: &[u8]) -> std::io::Result<Message> {
+        let local_ip = self.bind_addr.unwrap_or(match server {
+            SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        });
+        let socket = UdpSocket::bind(SocketAddr::new(local_ip, 0)).await?;
+        socket.connect(server).await?;
+        socket.send(wire).await?;
+        
+        let mut buf = vec![0u8; MAX_UDP_RESPONSE_SIZE];
+        let response = loop {
+            let len = socket.recv(&mut buf).await?;
+            // 忽略与查询不匹配的报文
+            match Message::from_vec(&buf[..len]) {
+                Ok(response) if response.id() == request.id() && response.queries() == request.queries() => break response,
+                _ => continue,
+            }
+        };
+        if !response.truncated() {
+            return Ok(response);
+        }
+        
+        let mut stream = connect_tcp(server, self.bind_addr).await?;
+        let response = exchange_framed(&mut stream, wire).await?;
+        Message::from_vec(&response).map_err(std::io::Error::other)
+    }
+    
+    // 查找查询名称最近的已缓存区域委派，没有时从根服务器开始
+    fn closest_delegation(&self, name: &Name, skip_self: bool) -> (Name, Vec<SocketAddr>) {
+        let now = Instant::now();
+        let delegations = self.delegations.lock().unwrap_or_else(|e| e.into_inner());
+        let mut zone = name.to_lowercase();
+        if skip_self {
+            zone = zone.base_name();
+        }
+        while !zone.is_root() {
+            if let Some(delegation) = delegations.get(&zone).filter(|delegation| delegation.expires_at > now) {
+                return (zone, delegation.servers.clone());
+            }
+            zone = zone.base_name();
+        }
+        (Name::root(), self.root_hints.clone())
+    }
+    
+    fn cache_delegation(&self, zone: &Name, servers: Vec<SocketAddr>, ttl: u32) {
+        let Some(expires_at) = cache_expiry(ttl) else {
+            return;
+        };
+        let mut delegations = self.delegations.lock().unwrap_or_else(|e| e.into_inner());
+        if delegations.len() >= RECURSIVE_CACHE_MAX_ENTRIES {
+            let now = Instant::now();
+            delegations.retain(|_, delegation| delegation.expires_at > now);
+            if delegations.len() >= RECURSIVE_CACHE_MAX_ENTRIES {
+                return;
+            }
+        }
+        delegations.insert(zone.to_lowercase(), CachedDelegation { servers, expires_at });
+    }
+    
+    fn cached_response(&self, key: &(Name, RecordType)) -> Option<Message> {
+        let responses = self.responses.lock().unwrap_or_else(|e| e.into_inner());
+        responses.get(key)
+            .filter(|cached| cached.expires_at > Instant::now())
+            .map(|cached| cached.message.clone())
+    }
+    
+    // 按应答记录的最小 TTL 缓存 DNSKEY/DS 响应
+    fn cache_response(&self, key: (Name, RecordType), message: &Message) {
+        let Some(expires_at) = message.answers().iter().map(|record| record.ttl()).min().and_then(cache_expiry) else {
+            return;
+        };
+        let mut responses = self.responses.lock().unwrap_or_else(|e| e.into_inner());
+        if responses.len() >= RECURSIVE_CACHE_MAX_ENTRIES {
+            let now = Instant::now();
+            responses.retain(|_, cached| cached.expires_at > now);
+            if responses.len() >= RECURSIVE_CACHE_MAX_ENTRIES {
+                return;
+            }
+        }
+        responses.insert(key, CachedResponse { message: message.clone(), expires_at });
+    }
+}
+
+// 对名称服务器的响应分类：推荐只接受比当前区域更接近查询名称的子区域
+fn classify(response: &Message, query: &Query, zone: &Name) -> Step {
+    if response.response_code() != ResponseCode::NoError {
+        return Step::Final;
+    }
+    if !response.answers().is_empty() {
+        return Step::Answer;
+    }
+    let referral = response.name_servers().iter()
+        .filter(|record| record.record_type() == RecordType::NS)
+        .map(Record::name)
+        .find(|child| *child != zone && zone.zone_of(child) && child.zone_of(query.name()));
+    match referral {
+        // DS 记录由父区域应答，不跟随到查询名称自身的区域
+        Some(child) if query.query_type() == RecordType::DS && child == query.name() => Step::Final,
+        Some(child) => Step::Referral(child.to_lowercase()),
+        None => Step::Final,
+    }
+}
+
+fn record_ip(record: &Record) -> Option<IpAddr> {
+    match record.data()? {
+        RData::A(a) => Some(IpAddr::V4(a.0)),
+        RData::AAAA(aaaa) => Some(IpAddr::V6(aaaa.0)),
+        _ => None,
+    }
+}
+
+// 缓存过期时间，TTL 为 0 时不缓存
+fn cache_expiry(ttl: u32) -> Option<Instant> {
+    (ttl > 0).then(|| Instant::now() + Duration::from_secs(u64::from(ttl.min(RECURSIVE_CACHE_MAX_TTL_SECS))))
+}
@@ -0,0 +1,33 @@
+// src/server/redirect.rs
+
+// 该模块提供明文 HTTP 到 HTTPS 的重定向应用。
+//
+// 启用 http_to_https_redirect 时，在 http_listen_addr 上对所有请求返回
+// 301 Moved Permanently，Location 指向 HTTPS 监听上相同的路径与查询参数。
+
+use axum::{
+    http::{header, StatusCode, Uri},
+    response::IntoResponse,
+    Router,
+};
+
+// HTTPS 默认端口，重定向地址中省略
+const HTTPS_DEFAULT_PORT: u16 = 443;
+
+// 创建将所有请求重定向到 https://<server_name>:<port> 的路由
+pub fn https_redirect_routes(server_name: &str, https_port: u16) -> Router {
+    let authority = if https_port == HTTPS_DEFAULT_PORT {
+        server_name.to_string()
+    } else {
+        format!("{}:{}", server_name, https_port)
+    };
+    
+    Router::new().fallback(move |uri: Uri| {
+        let location = format!(
+            "https://{}{}",
+            authority,
+            uri.path_and_query().map_or("/", |path| path.as_str())
+        );
+        async move { (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, location)]).into_response() }
+    })
+}
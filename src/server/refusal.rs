@@ -0,0 +1,83 @@
+// src/server/refusal.rs
+
+// 在网络边界直接以 REFUSED 拒绝不应转发到上游的查询：
+// - 启用 refused_types_for_private_ips 时，反向解析私有地址（RFC 1918、RFC 4193 与链路本地地址）的 PTR 查询，
+//   避免内网拓扑泄露给外部解析器，这类查询在公网上也不会有有效应答；
+// - refused_types 中列出的记录类型（如 AXFR、IXFR）无条件拒绝。
+// 被拒绝的查询不查询上游也不写入缓存，两类拒绝分别计入各自的指标。
+
+use once_cell::sync::Lazy;
+use hickory_proto::op::{Message, MessageType, Query, ResponseCode};
+use hickory_proto::rr::RecordType;
+use ipnet::IpNet;
+use crate::common::consts::PRIVATE_PTR_NETWORKS;
+use crate::server::config::UpstreamConfig;
+use crate::server::metrics::METRICS;
+
+// 私有地址段（RFC 1918、RFC 4193 与链路本地地址）
+static PRIVATE_NETWORKS: Lazy<Vec<IpNet>> = Lazy::new(|| {
+    PRIVATE_PTR_NETWORKS.iter()
+        .map(|network| network.parse().expect("invalid built-in private network"))
+        .collect()
+});
+
+// 查询被拒绝的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefusalReason {
+    // 私有地址的 PTR 查询
+    PrivatePtr,
+    // 配置为拒绝的记录类型
+    QueryType(RecordType),
+}
+
+impl RefusalReason {
+    // 计入对应的拒绝指标
+    pub fn record_metric(&self) {
+        match self {
+            Self::PrivatePtr => METRICS.private_ptr_refused_total().inc(),
+            Self::QueryType(record_type) => METRICS.query_type_refused_total()
+                .with_label_values(&[&record_type.to_string()])
+                .inc(),
+        }
+    }
+}
+
+// 判断查询是否应被拒绝，返回拒绝原因
+pub fn refusal_reason(config: &UpstreamConfig, query: &Query) -> Option<RefusalReason> {
+    let query_type = query.query_type();
+    if !config.refused_types.is_empty()
+        && config.parsed_refused_types().unwrap_or_default().contains(&query_type)
+    {
+        return Some(RefusalReason::QueryType(query_type));
+    }
+
+    if config.refused_types_for_private_ips && query_type == RecordType::PTR {
+        // 反向解析名称可能只包含地址前缀（如 168.192.in-addr.arpa.），整个前缀位于私有地址段内时同样拒绝
+        if let Ok(network) = query.name().parse_arpa_name() {
+            if is_private_network(&network) {
+                return Some(RefusalReason::PrivatePtr);
+            }
+        }
+    }
+
+    None
+}
+
+// 地址段是否完全位于私有地址段内
+pub fn is_private_network(network: &IpNet) -> bool {
+    PRIVATE_NETWORKS.iter().any(|private| private.contains(network))
+}
+
+// 构建 REFUSED 响应，复制请求 ID 与问题部分
+pub fn refused_response(query_message: &Message) -> Message {
+    let mut response = Message::new();
+    response.set_id(query_message.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(query_message.op_code())
+        .set_recursion_desired(query_message.recursion_desired())
+        .set_recursion_available(true)
+        .set_checking_disabled(query_message.checking_disabled())
+        .set_response_code(ResponseCode::Refused)
+        .add_queries(query_message.queries().to_vec());
+    response
+}
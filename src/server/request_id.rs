@@ -0,0 +1,88 @@
+// src/server/request_id.rs
+
+// 该模块负责 DoH 请求的请求 ID。
+//
+// 每个 DoH 请求读取客户端提供的请求 ID（未提供、不合法或来自不受信任的代理时生成 UUID v4），
+// 并将其附加到 tracing span 与任务本地变量中，供上游查询与缓存日志使用，
+// 最终在响应头中回显。
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request},
+    http::{HeaderName, HeaderValue, Response},
+    middleware::{self, Next},
+    Router,
+};
+use tracing::{debug, info_span, Instrument};
+use uuid::Uuid;
+use crate::common::consts::MAX_REQUEST_ID_LEN;
+use crate::server::ip_set::IpRangeSet;
+
+tokio::task_local! {
+    // 当前请求的 ID
+    static REQUEST_ID: String;
+}
+
+// 获取当前任务中的请求 ID（不在 DoH 请求上下文中时返回 None）
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// 在给定请求 ID 的上下文中执行异步任务
+pub async fn with_request_id<F: std::future::Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+// 为路由添加请求 ID 中间件；trusted_proxies 非空时仅接受来自其中连接源地址的请求 ID
+pub fn apply_request_id(app: Router, header_name: HeaderName, trusted_proxies: Arc<IpRangeSet>) -> Router {
+    app.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let header_name = header_name.clone();
+        let trusted_proxies = trusted_proxies.clone();
+        async move { handle_request_id(header_name, &trusted_proxies, req, next).await }
+    }))
+}
+
+// 读取或生成请求 ID，并在响应中回显
+async fn handle_request_id(header_name: HeaderName, trusted_proxies: &IpRangeSet, req: Request, next: Next) -> Response<Body> {
+    let provided = req.headers()
+        .get(&header_name)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value));
+    let request_id = match provided {
+        Some(value) if is_trusted_peer(trusted_proxies, &req) => value.to_string(),
+        Some(value) => {
+            debug!(provided = value, "Ignoring request ID from untrusted peer");
+            Uuid::new_v4().to_string()
+        },
+        None => Uuid::new_v4().to_string(),
+    };
+
+    let span = info_span!("doh_request", request_id = %request_id);
+    let mut response = with_request_id(request_id.clone(), next.run(req))
+        .instrument(span)
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(header_name, value);
+    }
+    response
+}
+
+// 连接源地址是否允许提供请求 ID：未配置受信任代理时接受所有连接，无法获取源地址时视为不受信任
+fn is_trusted_peer(trusted_proxies: &IpRangeSet, req: &Request) -> bool {
+    if trusted_proxies.is_empty() {
+        return true;
+    }
+    req.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .is_some_and(|ConnectInfo(addr)| trusted_proxies.contains(addr.ip()))
+}
+
+// 客户端提供的请求 ID 必须非空、长度受限且仅包含可见 ASCII 字符
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value.bytes().all(|b| b.is_ascii_graphic())
+}
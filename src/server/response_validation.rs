@@ -0,0 +1,125 @@
+// src/server/response_validation.rs
+
+// 该模块校验上游 DNS 响应是否与查询一致，用于发现 BGP 劫持或中间人注入的伪造响应（缓存投毒）。
+// 校验失败的响应会被丢弃，由调用方改用下一个上游。
+
+use hickory_proto::op::{Message, MessageType, ResponseCode};
+use hickory_proto::rr::{Name, RData, RecordType};
+use url::{Host, Url};
+use crate::common::consts::DNS_RECORD_TYPE_DNAME;
+
+// 校验响应与查询是否一致，失败时返回原因
+pub fn validate_response(query: &Message, response: &Message) -> std::result::Result<(), String> {
+    if response.message_type() != MessageType::Response {
+        return Err("message is not a response".to_string());
+    }
+
+    let Some(question) = query.queries().first() else {
+        return Ok(());
+    };
+
+    // 响应中的问题必须与查询相同（部分错误响应不带问题部分）
+    if let Some(echoed) = response.queries().first() {
+        if echoed.name() != question.name()
+            || echoed.query_type() != question.query_type()
+            || echoed.query_class() != question.query_class()
+        {
+            return Err(format!(
+                "question mismatch: queried {} {}, response is for {} {}",
+                question.name(), question.query_type(), echoed.name(), echoed.query_type()
+            ));
+        }
+    }
+
+    validate_answer_names(question.name(), response)?;
+    validate_authoritative_negative(question.name(), response)
+}
+
+// 应答部分的记录必须属于查询名称，或属于从查询名称出发的 CNAME/DNAME 链
+fn validate_answer_names(qname: &Name, response: &Message) -> std::result::Result<(), String> {
+    let mut chain = vec![qname.clone()];
+    let in_chain = |chain: &[Name], name: &Name| chain.iter().any(|n| n == name);
+    let under_dname = |chain: &[Name], owner: &Name| chain.iter().any(|n| owner.zone_of(n));
+
+    // CNAME 可能乱序出现，反复扩展直到链不再增长
+    loop {
+        let before = chain.len();
+        for record in response.answers() {
+            if let Some(RData::CNAME(target)) = record.data() {
+                if in_chain(&chain, record.name()) && !in_chain(&chain, &target.0) {
+                    chain.push(target.0.clone());
+                }
+            }
+        }
+        if chain.len() == before {
+            break;
+        }
+    }
+
+    for record in response.answers() {
+        let owner = record.name();
+        let allowed = if u16::from(record.record_type()) == DNS_RECORD_TYPE_DNAME {
+            under_dname(&chain, owner)
+        } else {
+            in_chain(&chain, owner)
+        };
+        if !allowed {
+            return Err(format!(
+                "answer {} {} does not belong to query name {}",
+                owner, record.record_type(), qname
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// 权威的否定应答（NXDOMAIN 或 NODATA）必须在授权部分携带查询名称所在区的 SOA（RFC 2308）
+fn validate_authoritative_negative(qname: &Name, response: &Message) -> std::result::Result<(), String> {
+    let negative = match response.response_code() {
+        ResponseCode::NXDomain => true,
+        ResponseCode::NoError => response.answers().is_empty(),
+        _ => false,
+    };
+    if !response.authoritative() || !negative {
+        return Ok(());
+    }
+
+    let has_soa = response.name_servers().iter().any(|record| {
+        record.record_type() == RecordType::SOA && record.name().zone_of(qname)
+    });
+    if has_soa {
+        Ok(())
+    } else {
+        Err(format!("authoritative negative answer for {} carries no SOA for its zone", qname))
+    }
+}
+
+// 校验 DoH 响应来自配置的服务器：重定向后的主机与端口不变，IP 地址形式的上游须与对端地址一致
+pub fn validate_doh_origin(
+    configured: &str,
+    final_url: &Url,
+    remote_ip: Option<std::net::IpAddr>,
+) -> std::result::Result<(), String> {
+    let configured = Url::parse(configured).map_err(|e| format!("invalid upstream URL: {}", e))?;
+
+    let same_host = match (configured.host(), final_url.host()) {
+        (Some(Host::Domain(a)), Some(Host::Domain(b))) => a.eq_ignore_ascii_case(b),
+        (a, b) => a.is_some() && a == b,
+    };
+    if !same_host || configured.port_or_known_default() != final_url.port_or_known_default() {
+        return Err(format!("response came from {} instead of {}", final_url, configured));
+    }
+
+    let expected_ip = match configured.host() {
+        Some(Host::Ipv4(ip)) => Some(std::net::IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => Some(std::net::IpAddr::V6(ip)),
+        _ => None,
+    };
+    match (expected_ip, remote_ip) {
+        (Some(expected), Some(actual)) if expected != actual => Err(format!(
+            "response came from {} instead of {}", actual, expected
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,107 @@
+// src/server/root_info.rs
+
+// 该模块提供根路径 (/) 的服务器信息页面，便于验证部署是否生效。
+// 默认返回简短的 HTML 页面；Accept 头包含 application/json 时返回版本、构建提交、
+// 已启用的功能、DoH 路径与运行时间。可通过 http_server.root_info_enabled 关闭。
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use crate::common::consts::{CONTENT_TYPE_JSON, DNS_WEBSOCKET_PATH, DOH_JSON_API_PATH, ROOT_INFO_PATH};
+use crate::server::doh_handler::ServerState;
+use crate::server::version::{GIT_COMMIT, VERSION};
+
+// 已启用的功能
+#[derive(Debug, Serialize)]
+pub struct EnabledFeatures {
+    pub routing: bool,
+    pub cache: bool,
+    pub dnssec: bool,
+    pub odoh: bool,
+    pub websocket: bool,
+}
+
+// 服务器信息响应
+#[derive(Debug, Serialize)]
+pub struct ServerInfo {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub features: EnabledFeatures,
+    // 配置的 DoH 路径
+    pub doh_paths: Vec<String>,
+    // JSON API 路径
+    pub json_api_path: &'static str,
+    // 启用 ODoH 时的查询路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub odoh_path: Option<String>,
+    // 启用 WebSocket 时的端点路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub websocket_path: Option<&'static str>,
+    // 进程启动以来的秒数
+    pub uptime_secs: u64,
+}
+
+impl ServerInfo {
+    fn from_state(state: &ServerState) -> Self {
+        let config = &state.config;
+        let odoh = config.http.odoh.enabled;
+        let websocket = config.http.websocket_enabled;
+        Self {
+            version: VERSION,
+            git_commit: GIT_COMMIT,
+            features: EnabledFeatures {
+                routing: config.dns.routing.enabled,
+                cache: config.dns.cache.enabled,
+                dnssec: config.dns.upstream.enable_dnssec,
+                odoh,
+                websocket,
+            },
+            doh_paths: config.http.doh_paths.clone(),
+            json_api_path: DOH_JSON_API_PATH,
+            odoh_path: odoh.then(|| config.http.odoh.path.clone()),
+            websocket_path: websocket.then_some(DNS_WEBSOCKET_PATH),
+            uptime_secs: state.started_at.elapsed().as_secs(),
+        }
+    }
+}
+
+// 创建根路径信息路由
+pub fn root_info_routes(state: ServerState) -> Router {
+    Router::new()
+        .route(ROOT_INFO_PATH, get(handle_root_info))
+        .with_state(state)
+}
+
+// 处理根路径请求，按 Accept 头返回 JSON 或 HTML
+async fn handle_root_info(State(state): State<ServerState>, headers: HeaderMap) -> Response {
+    let info = ServerInfo::from_state(&state);
+    let wants_json = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains(CONTENT_TYPE_JSON));
+    if wants_json {
+        return Json(info).into_response();
+    }
+    
+    let paths = info.doh_paths.iter()
+        .map(|path| format!("<li><code>{}</code></li>", html_escape(path)))
+        .collect::<String>();
+    Html(format!(
+        "<!DOCTYPE html>\n<html><head><title>Oxide WDNS</title></head><body>\n\
+         <h1>Oxide WDNS {}</h1>\n<p>DNS-over-HTTPS endpoints:</p>\n<ul>{}</ul>\n</body></html>\n",
+        info.version,
+        paths,
+    )).into_response()
+}
+
+// 转义配置中的路径，避免其被解释为 HTML
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
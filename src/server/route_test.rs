@@ -0,0 +1,102 @@
+// src/server/route_test.rs
+
+// 该模块实现 route-test 子命令：加载配置并构建路由器，输出域名命中的规则与处理查询的上游组。
+//
+// 只执行路由匹配，不查询上游、不绑定端口，也不下载任何规则：URL 规则、远程规则集与客户端 IP 网段 URL 均被跳过，
+// 只使用配置与本地文件中的规则。
+// 命中的规则与查询日志中的 rule 字段格式一致，未命中任何规则时使用全局上游（显示为 __default__）。
+
+use std::fmt;
+use std::io::{self, BufRead};
+use std::net::IpAddr;
+use crate::common::consts::{BLACKHOLE_UPSTREAM_GROUP_NAME, GLOBAL_UPSTREAM_ROUTE_TARGET};
+use tracing::warn;
+use crate::server::config::{MatchType, ServerConfig};
+use crate::server::error::Result;
+use crate::server::query_log;
+use crate::server::routing::{normalize_query_domain, RouteDecision, Router};
+
+// 单个域名的路由测试结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteTestResult {
+    // 规范化后的域名
+    pub domain: String,
+    // 命中的规则，未命中任何规则时为 None
+    pub rule: Option<String>,
+    // 路由决策
+    pub decision: RouteDecision,
+}
+
+impl RouteTestResult {
+    // 处理查询的上游组（多个组按回退顺序以 " -> " 连接）
+    pub fn target(&self) -> String {
+        match &self.decision {
+            RouteDecision::UseGroup(group) => group.clone(),
+            RouteDecision::UseGroups(groups) => groups.join(" -> "),
+            RouteDecision::UseGroupsWithFailover { groups, failover_chain } => {
+                format!("{} (failover: {})", groups.join(" -> "), failover_chain.join(" -> "))
+            },
+            RouteDecision::UseGlobal => GLOBAL_UPSTREAM_ROUTE_TARGET.to_string(),
+            RouteDecision::Blackhole => BLACKHOLE_UPSTREAM_GROUP_NAME.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RouteTestResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\tupstream_group={}\trule={}",
+            self.domain,
+            self.target(),
+            self.rule.as_deref().unwrap_or("-"),
+        )
+    }
+}
+
+// 按配置构建路由器（不关联上游管理器，跨上游组负载均衡时按规则顺序选择）。
+// 不提供 HTTP 客户端，需要下载的规则来源被跳过
+pub async fn build_router(config: &ServerConfig) -> Result<Router> {
+    let skipped = skipped_rule_sources(config);
+    if !skipped.is_empty() {
+        warn!(sources = ?skipped, "route-test does not download rules, rules from these URLs are skipped");
+    }
+    Router::new(config.dns.routing.clone(), None).await
+}
+
+// 路由测试时跳过的规则来源 URL（URL 规则、客户端 IP 网段 URL 与远程规则集）
+pub fn skipped_rule_sources(config: &ServerConfig) -> Vec<String> {
+    let routing = &config.dns.routing;
+    if !routing.enabled {
+        return Vec::new();
+    }
+    routing.rules.iter()
+        .filter(|rule| matches!(rule.match_.type_, MatchType::Url | MatchType::ClientIp))
+        .filter_map(|rule| rule.match_.url.clone())
+        .chain(routing.rules_url.clone())
+        .collect()
+}
+
+// 测试单个域名的路由结果
+pub async fn route_test(router: &Router, domain: &str, client_ip: Option<IpAddr>) -> RouteTestResult {
+    let domain = normalize_query_domain(domain.trim());
+    let (decision, trace) = query_log::trace(true, router.match_query(&domain, client_ip)).await;
+    RouteTestResult {
+        domain,
+        rule: trace.rule,
+        decision,
+    }
+}
+
+// 读取待测试的域名：每行一个，忽略空行与 # 开头的注释
+pub fn read_domains(reader: impl BufRead) -> io::Result<Vec<String>> {
+    let mut domains = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let domain = line.trim();
+        if !domain.is_empty() && !domain.starts_with('#') {
+            domains.push(domain.to_string());
+        }
+    }
+    Ok(domains)
+}
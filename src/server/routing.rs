@@ -2,29 +2,39 @@
 
 use std::collections::{HashMap, HashSet, BTreeMap};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
+use std::net::IpAddr;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use ipnet::IpNet;
 use lazy_static::lazy_static;
 use regex::Regex;
-use tokio::sync::RwLock as AsyncRwLock;
+use tokio::sync::{watch, RwLock as AsyncRwLock};
 use tracing::{debug, error, info, warn};
 use reqwest::Client;
 use tokio::time::{Duration, interval};
 use xxhash_rust::xxh64::xxh64;
 
-use crate::server::config::{RoutingConfig, MatchType};
+use crate::server::config::{RoutingConfig, MatchType, Rule, RuleFileFormat, RuleSourceConfig};
 use crate::server::error::{ServerError, Result};
 use crate::common::consts::{
-    BLACKHOLE_UPSTREAM_GROUP_NAME,
+    BLACKHOLE_UPSTREAM_GROUP_NAME, GLOBAL_UPSTREAM_ROUTE_TARGET, MAX_DECOMPRESSED_RULE_LIST_SIZE,
 };
 use crate::server::metrics::METRICS;
+use crate::server::query_log;
+use crate::server::ip_set::{IpRangeSet, parse_network, parse_network_list};
+use crate::server::upstream::UpstreamManager;
 
 // 规则类型标签值
 const ROUTE_RULE_TYPE_EXACT: &str = "exact";
 const ROUTE_RULE_TYPE_REGEX: &str = "regex";
 const ROUTE_RULE_TYPE_WILDCARD: &str = "wildcard";
 const ROUTE_RULE_TYPE_FILE: &str = "file";
+const ROUTE_RULE_TYPE_DIR: &str = "dir";
 const ROUTE_RULE_TYPE_URL: &str = "url";
+const ROUTE_RULE_TYPE_CLIENT_IP: &str = "client_ip";
 
 // 路由结果类型标签值
 const ROUTE_RESULT_DISABLED: &str = "disabled";
@@ -43,25 +53,93 @@ const URL_RULE_UPDATE_STATUS_UNCHANGED: &str = "unchanged";
 pub enum RouteDecision {
     // 使用特定上游组
     UseGroup(String),
+    // 按顺序使用多个上游组，前一组传输失败时回退到下一组
+    UseGroups(Vec<String>),
+    // 使用上游组（列表），查询失败或返回 SERVFAIL 时依次改用故障转移链中的上游组
+    UseGroupsWithFailover {
+        groups: Vec<String>,
+        failover_chain: Vec<String>,
+    },
     // 使用全局上游配置
     UseGlobal,
     // 黑洞（阻止查询）
     Blackhole,
 }
 
+// 规则目标：按顺序尝试的上游组名称（通常只有一个）与故障转移链
+#[derive(Debug, Clone, PartialEq)]
+struct RouteTarget {
+    groups: Arc<[String]>,
+    failover_chain: Arc<[String]>,
+}
+
+impl RouteTarget {
+    // 从规则配置创建
+    fn from_rule(rule: &Rule) -> Self {
+        Self {
+            groups: rule.target_groups().into(),
+            failover_chain: rule.failover_chain.as_slice().into(),
+        }
+    }
+    
+    // 单个上游组
+    fn single(group: &str) -> Self {
+        Self {
+            groups: Arc::new([group.to_string()]),
+            failover_chain: Arc::new([]),
+        }
+    }
+    
+    // 首选上游组名称
+    fn primary(&self) -> &str {
+        self.groups.first().map(String::as_str).unwrap_or_default()
+    }
+    
+    // 是否为黑洞
+    fn is_blackhole(&self) -> bool {
+        self.primary() == BLACKHOLE_UPSTREAM_GROUP_NAME
+    }
+    
+    // 转换为路由决策
+    fn decision(&self) -> RouteDecision {
+        if !self.failover_chain.is_empty() {
+            return RouteDecision::UseGroupsWithFailover {
+                groups: self.groups.to_vec(),
+                failover_chain: self.failover_chain.to_vec(),
+            };
+        }
+        match &*self.groups {
+            // 规则指定使用全局上游（例如从通配符规则中排除部分子域名）
+            [group] if group == GLOBAL_UPSTREAM_ROUTE_TARGET => RouteDecision::UseGlobal,
+            [group] => RouteDecision::UseGroup(group.clone()),
+            groups => RouteDecision::UseGroups(groups.to_vec()),
+        }
+    }
+}
+
+impl std::fmt::Display for RouteTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.groups.join(" -> "))?;
+        if !self.failover_chain.is_empty() {
+            write!(f, " (failover: {})", self.failover_chain.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
 // 优化的路由引擎核心数据结构
 struct RouterCore {
     // 精确匹配规则 - 域名 -> (上游组名)
-    exact_rules: HashMap<String, String>,
+    exact_rules: HashMap<String, RouteTarget>,
     
     // 通配符匹配规则 - 反转后缀 -> (上游组名, 模式)
-    wildcard_rules: BTreeMap<String, (String, String)>,
+    wildcard_rules: BTreeMap<String, (RouteTarget, String)>,
     
     // 全局通配符规则 (*) -> (上游组名)
-    global_wildcard: Option<String>,
+    global_wildcard: Option<RouteTarget>,
     
     // 正则表达式规则 - (正则表达式, 上游组名, 原始模式)
-    regex_rules: Vec<(Regex, String, String)>,
+    regex_rules: Vec<(Regex, RouteTarget, String)>,
     
     // 正则预筛选 - 特征 -> 规则索引集合
     regex_prefilter: HashMap<String, HashSet<usize>>,
@@ -92,8 +170,18 @@ struct WildcardPattern {
 struct FileRuleData {
     // 规则内容
     core: RouterCore,
-    // 上游组名
-    upstream_group: String,
+    // 目标上游组
+    upstream_group: RouteTarget,
+}
+
+// 规则目录数据
+struct DirRuleData {
+    // 目录来源配置
+    source: RuleSourceConfig,
+    // 未在 groups 中映射的文件使用的目标上游组
+    upstream_group: RouteTarget,
+    // 当前生效的文件规则 - 重新扫描时整体替换
+    files: AsyncRwLock<Vec<FileRuleData>>,
 }
 
 // URL规则数据
@@ -102,12 +190,40 @@ struct UrlRuleData {
     url: String,
     // 规则内容 - 使用RwLock以支持异步更新
     rules: Arc<AsyncRwLock<UrlRules>>,
-    // 上游组名
-    upstream_group: String,
+    // 目标上游组
+    upstream_group: RouteTarget,
+    // 周期性更新配置
+    periodic: Option<PeriodicConfig>,
+}
+
+// 客户端 IP 规则数据
+struct ClientIpRuleData {
+    // 内联与文件中的网段
+    static_networks: Vec<IpNet>,
+    // 当前生效的网段集合 - URL 更新时整体替换
+    ranges: Arc<AsyncRwLock<IpRangeSet>>,
+    // 网段列表 URL（可选）
+    url: Option<String>,
+    // 目标上游组
+    upstream_group: RouteTarget,
     // 周期性更新配置
     periodic: Option<PeriodicConfig>,
 }
 
+// 远程规则集数据
+struct RemoteRuleSet {
+    // 规则集地址
+    url: String,
+    // 当前生效的规则 - 加载成功后整体替换
+    core: AsyncRwLock<RouterCore>,
+    // 最近一次成功响应的 ETag
+    etag: AsyncRwLock<Option<String>>,
+    // 可引用的上游组名称
+    group_names: HashSet<String>,
+    // 重新加载间隔（秒），0 表示仅在启动时加载
+    reload_interval_secs: u64,
+}
+
 // 周期性更新配置 - 与之前相同
 #[derive(Debug, Clone)]
 struct PeriodicConfig {
@@ -115,6 +231,53 @@ struct PeriodicConfig {
     interval_secs: u64,
 }
 
+// 规范化规则中的域名：去除首尾空白、转为小写、去掉末尾的点，并将国际化域名转换为 punycode
+pub fn normalize_rule_domain(domain: &str) -> std::result::Result<String, String> {
+    let trimmed = domain.trim().trim_end_matches('.');
+    if trimmed.is_empty() {
+        return Err("domain is empty".to_string());
+    }
+    
+    // 使用 URL 标准的禁止字符列表拒绝空白等字符，同时保留 _dmarc 等下划线标签
+    let ascii = idna::domain_to_ascii_cow(trimmed.as_bytes(), idna::AsciiDenyList::URL)
+        .map_err(|e| format!("not a valid domain name ({})", e))?;
+    if ascii.is_empty() || ascii.split('.').any(str::is_empty) {
+        return Err("domain contains an empty label".to_string());
+    }
+    
+    Ok(ascii.into_owned())
+}
+
+// 规范化查询域名：转为小写、去掉末尾的点；含非 ASCII 字符的国际化域名与规则一样转换为 punycode，
+// 无法转换时保留小写形式
+pub fn normalize_query_domain(domain: &str) -> String {
+    let lower = domain.trim_end_matches('.').to_lowercase();
+    if lower.is_ascii() {
+        return lower;
+    }
+    normalize_rule_domain(&lower).unwrap_or(lower)
+}
+
+// 规范化通配符规则：含 * 的标签仅转为小写，其余标签按域名规范化
+pub fn normalize_wildcard_pattern(pattern: &str) -> std::result::Result<String, String> {
+    let trimmed = pattern.trim().trim_end_matches('.');
+    if trimmed == "*" {
+        return Ok(trimmed.to_string());
+    }
+    if trimmed.is_empty() {
+        return Err("pattern is empty".to_string());
+    }
+    
+    let labels = trimmed.split('.')
+        .map(|label| match label.contains('*') {
+            true => Ok(label.to_lowercase()),
+            false => normalize_rule_domain(label),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    
+    Ok(labels.join("."))
+}
+
 // DNS 路由器 - 优化重构版
 pub struct Router {
     // 是否启用
@@ -126,14 +289,35 @@ pub struct Router {
     // 文件规则列表
     file_rules: Vec<FileRuleData>,
     
+    // 规则目录列表（收到 SIGHUP 时重新扫描）
+    dir_rules: Vec<DirRuleData>,
+    
     // URL规则列表
     url_rules: Vec<UrlRuleData>,
     
+    // 客户端 IP 规则列表（在所有域名规则之后匹配）
+    client_ip_rules: Vec<ClientIpRuleData>,
+    
+    // 远程规则集（配置了 rules_url 时）
+    remote_rules: Option<Arc<RemoteRuleSet>>,
+    
     // 默认上游组名称
     default_upstream_group: Option<String>,
     
     // HTTP客户端（用于URL规则）
     http_client: Option<Client>,
+    
+    // 多条规则命中时是否按上游组健康评分选择
+    load_balance_across_groups: bool,
+    
+    // 上游管理器（跨上游组负载均衡时用于查询健康评分）
+    upstream: Option<Arc<UpstreamManager>>,
+    
+    // 健康评分相同时的轮询计数
+    round_robin: AtomicUsize,
+    
+    // 后台更新任务的停止信号，关闭时置为 true
+    stop: watch::Sender<bool>,
 }
 
 impl Router {
@@ -145,36 +329,69 @@ impl Router {
                 enabled: false,
                 core: RouterCore::new(),
                 file_rules: Vec::new(),
+                dir_rules: Vec::new(),
                 url_rules: Vec::new(),
+                client_ip_rules: Vec::new(),
+                remote_rules: None,
                 default_upstream_group: None,
                 http_client: None,
+                load_balance_across_groups: false,
+                upstream: None,
+                round_robin: AtomicUsize::new(0),
+                stop: watch::Sender::new(false),
             });
         }
         
+        // 规则引用的上游组必须已定义（或为 __blackhole__/__default__），避免运行时误路由
+        let group_names: HashSet<String> = routing_config.upstream_groups.iter()
+            .map(|g| g.name.clone())
+            .collect();
+        for (i, rule) in routing_config.rules.iter().enumerate() {
+            rule.validate_targets(i + 1, &group_names)?;
+        }
+        if let Some(default_group) = routing_config.default_upstream_group.as_ref().filter(|group| !group_names.contains(*group)) {
+            return Err(ServerError::Config(format!("Default upstream group does not exist: {}", default_group)));
+        }
+        
         // 创建主核心路由结构
         let mut core = RouterCore::new();
         
         // 文件规则列表
         let mut file_rules = Vec::new();
         
+        // 规则目录列表
+        let mut dir_rules = Vec::new();
+        
         // URL规则列表
         let mut url_rules = Vec::new();
         
+        // 客户端 IP 规则列表
+        let mut client_ip_rules = Vec::new();
+        
         // 跟踪不同类型规则的数量
         let mut exact_count = 0;
         let mut regex_count = 0;
         let mut wildcard_count = 0;
         let mut file_count = 0;
+        let mut dir_count = 0;
         let mut url_count = 0;
+        let mut client_ip_count = 0;
         
         // 编译所有规则
-        for rule in routing_config.rules {
+        for (i, rule) in routing_config.rules.into_iter().enumerate() {
+            // 规则索引（从1开始，用于错误消息）
+            let rule_index = i + 1;
+            let target = RouteTarget::from_rule(&rule);
             match &rule.match_ {
                 condition if condition.type_ == MatchType::Exact => {
                     // 处理精确匹配规则
                     if let Some(values) = &condition.values {
                         for domain in values {
-                            core.add_exact_rule(domain.clone(), rule.upstream_group.clone());
+                            let normalized = normalize_rule_domain(domain).map_err(|e| ServerError::InvalidRuleFormat(format!(
+                                "Rule [{}]: invalid exact domain '{}': {}",
+                                rule_index, domain, e
+                            )))?;
+                            core.add_exact_rule(normalized, target.clone());
                             exact_count += 1;
                         }
                     }
@@ -184,7 +401,11 @@ impl Router {
                     // 处理通配符规则
                     if let Some(values) = &condition.values {
                         for pattern in values {
-                            core.add_wildcard_rule(pattern.clone(), rule.upstream_group.clone());
+                            let normalized = normalize_wildcard_pattern(pattern).map_err(|e| ServerError::InvalidRuleFormat(format!(
+                                "Rule [{}]: invalid wildcard pattern '{}': {}",
+                                rule_index, pattern, e
+                            )))?;
+                            core.add_wildcard_rule(normalized, target.clone());
                             wildcard_count += 1;
                         }
                     }
@@ -196,13 +417,13 @@ impl Router {
                         for pattern in values {
                             match Regex::new(pattern) {
                                 Ok(regex) => {
-                                    core.add_regex_rule(pattern.clone(), regex, rule.upstream_group.clone());
+                                    core.add_regex_rule(pattern.clone(), regex, target.clone());
                                     regex_count += 1;
                                 },
                                 Err(e) => {
                                     return Err(ServerError::RegexCompilation(format!(
-                                        "Failed to compile regex '{}': {}", 
-                                        pattern, e
+                                        "Rule [{}]: failed to compile regex '{}': {}", 
+                                        rule_index, pattern, e
                                     )));
                                 }
                             }
@@ -217,13 +438,27 @@ impl Router {
                         
                         file_rules.push(FileRuleData {
                             core: file_rule_core,
-                            upstream_group: rule.upstream_group.clone(),
+                            upstream_group: target.clone(),
                         });
                         
                         file_count += 1;
                     }
                 },
                 
+                condition if condition.type_ == MatchType::Dir => {
+                    // 处理规则目录：启动时目录必须可读，单个文件读取失败仅记录警告
+                    if let Some(source) = &condition.source {
+                        let (files, rule_count) = Self::load_rules_from_dir(source, &target)?;
+                        dir_rules.push(DirRuleData {
+                            source: source.clone(),
+                            upstream_group: target.clone(),
+                            files: AsyncRwLock::new(files),
+                        });
+                        
+                        dir_count += rule_count;
+                    }
+                },
+                
                 condition if condition.type_ == MatchType::Url => {
                     // 处理URL规则
                     if let Some(url) = &condition.url {
@@ -239,7 +474,7 @@ impl Router {
                         url_rules.push(UrlRuleData {
                             url: url.clone(),
                             rules,
-                            upstream_group: rule.upstream_group.clone(),
+                            upstream_group: target.clone(),
                             periodic,
                         });
                         
@@ -247,6 +482,33 @@ impl Router {
                     }
                 },
                 
+                condition if condition.type_ == MatchType::ClientIp => {
+                    // 处理客户端 IP 规则：合并内联网段与文件中的网段
+                    let mut networks = Vec::new();
+                    for value in condition.values.iter().flatten() {
+                        networks.push(parse_network(value)?);
+                    }
+                    if let Some(path) = &condition.path {
+                        let content = std::fs::read_to_string(path).map_err(|e| ServerError::RuleLoad(format!(
+                            "Failed to read client IP file '{}': {}",
+                            path, e
+                        )))?;
+                        networks.extend(parse_network_list(&content)?);
+                    }
+                    
+                    client_ip_count += networks.len();
+                    client_ip_rules.push(ClientIpRuleData {
+                        ranges: Arc::new(AsyncRwLock::new(IpRangeSet::from_networks(networks.iter().copied()))),
+                        static_networks: networks,
+                        url: condition.url.clone(),
+                        upstream_group: target.clone(),
+                        periodic: condition.periodic.as_ref().map(|p| PeriodicConfig {
+                            enabled: p.enabled,
+                            interval_secs: p.interval_secs,
+                        }),
+                    });
+                },
+                
                 _ => {
                     return Err(ServerError::InvalidRuleFormat("Unknown match type".to_string()));
                 }
@@ -259,27 +521,73 @@ impl Router {
             METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_REGEX]).set(regex_count as f64);
             METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_WILDCARD]).set(wildcard_count as f64);
             METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_FILE]).set(file_count as f64);
+            METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_DIR]).set(dir_count as f64);
             METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_URL]).set(url_count as f64);
+            METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_CLIENT_IP]).set(client_ip_count as f64);
         }
         
+        // 创建远程规则集（首次加载在启动更新任务时完成）
+        let remote_rules = routing_config.rules_url.map(|url| {
+            let mut group_names: HashSet<String> = routing_config.upstream_groups.iter()
+                .map(|g| g.name.clone())
+                .collect();
+            group_names.insert(BLACKHOLE_UPSTREAM_GROUP_NAME.to_string());
+            group_names.insert(GLOBAL_UPSTREAM_ROUTE_TARGET.to_string());
+            
+            Arc::new(RemoteRuleSet {
+                url,
+                core: AsyncRwLock::new(RouterCore::new()),
+                etag: AsyncRwLock::new(None),
+                group_names,
+                reload_interval_secs: routing_config.rules_reload_interval_secs,
+            })
+        });
+        
         // 创建路由器实例
         let router = Self {
             enabled: true,
             core,
             file_rules,
+            dir_rules,
             url_rules,
+            client_ip_rules,
+            remote_rules,
             default_upstream_group: routing_config.default_upstream_group,
             http_client,
+            load_balance_across_groups: routing_config.load_balance_across_groups,
+            upstream: None,
+            round_robin: AtomicUsize::new(0),
+            stop: watch::Sender::new(false),
         };
         
         // 启动URL规则更新任务
         router.start_url_updaters().await;
         
+        // 加载远程规则集并启动重新加载任务
+        router.start_remote_rules_updater().await;
+        
+        // 加载客户端 IP 网段 URL 并启动更新任务
+        router.start_client_ip_updaters().await;
+        
         Ok(router)
     }
     
-    // 匹配域名，返回路由决策 - 主要入口方法
+    // 关联上游管理器，启用 load_balance_across_groups 时按上游组健康评分选择
+    pub fn with_upstream(mut self, upstream: Arc<UpstreamManager>) -> Self {
+        self.upstream = Some(upstream);
+        self
+    }
+    
+    // 匹配域名，返回路由决策（不考虑客户端 IP 规则）
     pub async fn match_domain(&self, domain: &str) -> RouteDecision {
+        self.match_query(domain, None).await
+    }
+    
+    // 根据域名与客户端 IP 返回路由决策 - 主要入口方法
+    //
+    // 域名规则（核心、文件、URL、远程规则集）优先；均未命中时按配置顺序匹配客户端 IP 规则，
+    // 最后回退到默认上游组。
+    pub async fn match_query(&self, domain: &str, client_ip: Option<IpAddr>) -> RouteDecision {
         // 如果路由未启用，返回使用全局上游
         if !self.enabled {
             {
@@ -288,14 +596,21 @@ impl Router {
             return RouteDecision::UseGlobal;
         }
         
-        // 规范化域名（转换为小写，去除尾部的点）
-        let domain_lower = domain.to_lowercase();
-        let domain_normalized = domain_lower.trim_end_matches('.');
+        // 规范化域名（转换为小写，去除尾部的点，国际化域名转换为 punycode）
+        let domain_normalized = normalize_query_domain(domain);
+        let domain_normalized = domain_normalized.as_str();
+        
+        // 启用跨上游组负载均衡时，在所有命中的规则中选择
+        if let (true, Some(upstream)) = (self.load_balance_across_groups, &self.upstream) {
+            return self.match_balanced(domain_normalized, client_ip, upstream).await;
+        }
         
         // 1. 首先尝试匹配核心规则 (高效的数据结构)
         if let Some((upstream_group, pattern, rule_type)) = self.core.match_domain(domain_normalized) {
+            query_log::record_rule(|| format!("{}:{}", rule_type, pattern));
+            
             // 如果是黑洞，返回黑洞决策
-            if upstream_group == BLACKHOLE_UPSTREAM_GROUP_NAME {
+            if upstream_group.is_blackhole() {
                 {
                     METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
                 }
@@ -315,40 +630,27 @@ impl Router {
                 "Domain matched core rule"
             );
             
-            return RouteDecision::UseGroup(upstream_group);
+            return upstream_group.decision();
         }
         
         // 2. 然后尝试匹配文件规则 (文件规则也使用高效数据结构)
         for file_rule in &self.file_rules {
-            if let Some((_, pattern, rule_type)) = file_rule.core.match_domain(domain_normalized) {
-                let upstream_group = &file_rule.upstream_group;
-                
-                // 如果是黑洞，返回黑洞决策
-                if upstream_group == BLACKHOLE_UPSTREAM_GROUP_NAME {
-                    {
-                        METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
-                    }
-                    return RouteDecision::Blackhole;
-                }
-                
-                // 记录匹配
-                {
-                    METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_RULE_MATCH]).inc();
+            if let Some(decision) = Self::match_file_rule(file_rule, domain_normalized, ROUTE_RULE_TYPE_FILE) {
+                return decision;
+            }
+        }
+        
+        // 3. 接着按文件名顺序匹配规则目录中的文件
+        for dir_rule in &self.dir_rules {
+            let files = dir_rule.files.read().await;
+            for file_rule in files.iter() {
+                if let Some(decision) = Self::match_file_rule(file_rule, domain_normalized, ROUTE_RULE_TYPE_DIR) {
+                    return decision;
                 }
-                
-                debug!(
-                    domain = %domain_normalized,
-                    pattern = %pattern,
-                    rule_type = %rule_type,
-                    source = "file",
-                    "Domain matched file rule"
-                );
-                
-                return RouteDecision::UseGroup(upstream_group.clone());
             }
         }
         
-        // 3. 最后尝试匹配URL规则 (需要异步读取)
+        // 4. 最后尝试匹配URL规则 (需要异步读取)
         for url_rule in &self.url_rules {
             // 读取URL规则
             let url_rules = url_rule.rules.read().await;
@@ -356,9 +658,10 @@ impl Router {
             // 先检查精确匹配
             if url_rules.exact.contains(domain_normalized) {
                 let upstream_group = &url_rule.upstream_group;
+                query_log::record_rule(|| format!("{}:{}:{}", ROUTE_RULE_TYPE_URL, ROUTE_RULE_TYPE_EXACT, domain_normalized));
                 
                 // 如果是黑洞，返回黑洞决策
-                if upstream_group == BLACKHOLE_UPSTREAM_GROUP_NAME {
+                if upstream_group.is_blackhole() {
                     {
                         METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
                     }
@@ -378,16 +681,17 @@ impl Router {
                     "Domain matched URL exact rule"
                 );
                 
-                return RouteDecision::UseGroup(upstream_group.clone());
+                return upstream_group.decision();
             }
             
             // 检查正则表达式匹配
             for regex in &url_rules.regex {
                 if regex.is_match(domain_normalized) {
                     let upstream_group = &url_rule.upstream_group;
+                    query_log::record_rule(|| format!("{}:{}:{}", ROUTE_RULE_TYPE_URL, ROUTE_RULE_TYPE_REGEX, regex.as_str()));
                     
                     // 如果是黑洞，返回黑洞决策
-                    if upstream_group == BLACKHOLE_UPSTREAM_GROUP_NAME {
+                    if upstream_group.is_blackhole() {
                         {
                             METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
                         }
@@ -407,16 +711,17 @@ impl Router {
                         "Domain matched URL regex rule"
                     );
                     
-                    return RouteDecision::UseGroup(upstream_group.clone());
+                    return upstream_group.decision();
                 }
             }
             
             // 检查通配符匹配
             if Self::match_wildcard_patterns(domain_normalized, &url_rules.wildcard) {
                 let upstream_group = &url_rule.upstream_group;
+                query_log::record_rule(|| format!("{}:{}", ROUTE_RULE_TYPE_URL, ROUTE_RULE_TYPE_WILDCARD));
                 
                 // 如果是黑洞，返回黑洞决策
-                if upstream_group == BLACKHOLE_UPSTREAM_GROUP_NAME {
+                if upstream_group.is_blackhole() {
                     {
                         METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
                     }
@@ -436,12 +741,84 @@ impl Router {
                     "Domain matched URL wildcard rule"
                 );
                 
-                return RouteDecision::UseGroup(upstream_group.clone());
+                return upstream_group.decision();
             }
         }
         
+        // 5. 尝试匹配远程规则集（在所有本地规则之后）
+        if let Some(remote_rules) = &self.remote_rules {
+            let remote_core = remote_rules.core.read().await;
+            if let Some((upstream_group, pattern, rule_type)) = remote_core.match_domain(domain_normalized) {
+                query_log::record_rule(|| format!("rules_url:{}:{}", rule_type, pattern));
+                
+                // 如果是黑洞，返回黑洞决策
+                if upstream_group.is_blackhole() {
+                    {
+                        METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
+                    }
+                    return RouteDecision::Blackhole;
+                }
+                
+                // 记录匹配
+                {
+                    METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_RULE_MATCH]).inc();
+                }
+                
+                debug!(
+                    domain = %domain_normalized,
+                    pattern = %pattern,
+                    rule_type = %rule_type,
+                    upstream_group = %upstream_group,
+                    source = "rules_url",
+                    "Domain matched remote rule"
+                );
+                
+                return upstream_group.decision();
+            }
+        }
+        
+        // 6. 域名规则均未命中时，按客户端源地址匹配客户端 IP 规则
+        if let Some(client_ip) = client_ip {
+            for client_ip_rule in &self.client_ip_rules {
+                if !client_ip_rule.ranges.read().await.contains(client_ip) {
+                    continue;
+                }
+                let upstream_group = &client_ip_rule.upstream_group;
+                query_log::record_rule(|| format!("{}:{}", ROUTE_RULE_TYPE_CLIENT_IP, client_ip));
+                
+                // 如果是黑洞，返回黑洞决策
+                if upstream_group.is_blackhole() {
+                    {
+                        METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
+                    }
+                    return RouteDecision::Blackhole;
+                }
+                
+                // 记录匹配
+                {
+                    METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_RULE_MATCH]).inc();
+                }
+                
+                debug!(
+                    domain = %domain_normalized,
+                    client_ip = %client_ip,
+                    rule_type = ROUTE_RULE_TYPE_CLIENT_IP,
+                    upstream_group = %upstream_group,
+                    "Query matched client IP rule"
+                );
+                
+                return upstream_group.decision();
+            }
+        }
+        
+        self.unmatched_decision()
+    }
+    
+    // 没有规则命中时的路由决策：默认上游组，未配置时使用全局上游
+    fn unmatched_decision(&self) -> RouteDecision {
         // 如果没有规则匹配，检查默认上游组
         if let Some(default_group) = &self.default_upstream_group {
+            query_log::record_rule(|| ROUTE_RESULT_DEFAULT.to_string());
             {
                 METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_DEFAULT]).inc();
             }
@@ -455,8 +832,232 @@ impl Router {
         RouteDecision::UseGlobal
     }
     
+    // 按规则优先级收集所有命中的规则目标
+    async fn matched_targets(&self, domain: &str, client_ip: Option<IpAddr>) -> Vec<RouteTarget> {
+        let mut targets = self.core.match_all(domain);
+        
+        for file_rule in &self.file_rules {
+            if file_rule.core.match_domain(domain).is_some() {
+                targets.push(file_rule.upstream_group.clone());
+            }
+        }
+        
+        for dir_rule in &self.dir_rules {
+            let files = dir_rule.files.read().await;
+            for file_rule in files.iter() {
+                if file_rule.core.match_domain(domain).is_some() {
+                    targets.push(file_rule.upstream_group.clone());
+                }
+            }
+        }
+        
+        for url_rule in &self.url_rules {
+            let url_rules = url_rule.rules.read().await;
+            if url_rules.exact.contains(domain)
+                || url_rules.regex.iter().any(|regex| regex.is_match(domain))
+                || Self::match_wildcard_patterns(domain, &url_rules.wildcard)
+            {
+                targets.push(url_rule.upstream_group.clone());
+            }
+        }
+        
+        if let Some(remote_rules) = &self.remote_rules {
+            targets.extend(remote_rules.core.read().await.match_all(domain));
+        }
+        
+        if let Some(client_ip) = client_ip {
+            for client_ip_rule in &self.client_ip_rules {
+                if client_ip_rule.ranges.read().await.contains(client_ip) {
+                    targets.push(client_ip_rule.upstream_group.clone());
+                }
+            }
+        }
+        
+        targets
+    }
+    
+    // 在所有命中的规则中选择首选上游组健康评分最高的目标，评分相同时轮询
+    //
+    // 优先级最高的规则为黑洞时仍阻止查询，黑洞不参与负载均衡。
+    async fn match_balanced(&self, domain: &str, client_ip: Option<IpAddr>, upstream: &UpstreamManager) -> RouteDecision {
+        let targets = self.matched_targets(domain, client_ip).await;
+        let Some(first) = targets.first() else {
+            return self.unmatched_decision();
+        };
+        
+        // 如果是黑洞，返回黑洞决策
+        if first.is_blackhole() {
+            query_log::record_rule(|| format!("balanced:{}", first));
+            {
+                METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
+            }
+            return RouteDecision::Blackhole;
+        }
+        
+        // 按首选上游组去重，保留优先级最高的规则目标
+        let mut candidates: Vec<(&RouteTarget, f64)> = Vec::with_capacity(targets.len());
+        for target in targets.iter().filter(|target| !target.is_blackhole()) {
+            if candidates.iter().all(|(candidate, _)| candidate.primary() != target.primary()) {
+                candidates.push((target, upstream.get_group_health_score(target.primary())));
+            }
+        }
+        
+        let best_score = candidates.iter().map(|(_, score)| *score).fold(f64::NEG_INFINITY, f64::max);
+        let best: Vec<&RouteTarget> = candidates.iter()
+            .filter(|(_, score)| *score == best_score)
+            .map(|(target, _)| *target)
+            .collect();
+        let selected = match best.as_slice() {
+            [target] => *target,
+            _ => best[self.round_robin.fetch_add(1, Ordering::Relaxed) % best.len()],
+        };
+        
+        // 记录匹配
+        {
+            METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_RULE_MATCH]).inc();
+        }
+        query_log::record_rule(|| format!("balanced:{}", selected));
+        
+        debug!(
+            domain = %domain,
+            candidates = candidates.len(),
+            upstream_group = %selected,
+            health_score = best_score,
+            "Selected upstream group by health score"
+        );
+        
+        selected.decision()
+    }
+    
+    // 匹配单个文件规则，命中时返回路由决策
+    fn match_file_rule(file_rule: &FileRuleData, domain: &str, source: &'static str) -> Option<RouteDecision> {
+        let (_, pattern, rule_type) = file_rule.core.match_domain(domain)?;
+        let upstream_group = &file_rule.upstream_group;
+        query_log::record_rule(|| format!("{}:{}:{}", source, rule_type, pattern));
+        
+        // 如果是黑洞，返回黑洞决策
+        if upstream_group.is_blackhole() {
+            {
+                METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_BLACKHOLE]).inc();
+            }
+            return Some(RouteDecision::Blackhole);
+        }
+        
+        // 记录匹配
+        {
+            METRICS.route_results_total().with_label_values(&[ROUTE_RESULT_RULE_MATCH]).inc();
+        }
+        
+        debug!(
+            domain = %domain,
+            pattern = %pattern,
+            rule_type = %rule_type,
+            source = source,
+            "Domain matched file rule"
+        );
+        
+        Some(upstream_group.decision())
+    }
+    
+    // 重新扫描所有规则目录，应用新增、删除与修改的文件
+    //
+    // 目录本身无法读取时记录警告并保留当前规则。
+    pub async fn reload_rule_dirs(&self) {
+        if self.dir_rules.is_empty() {
+            return;
+        }
+        
+        let mut rule_count = 0;
+        for dir_rule in &self.dir_rules {
+            match Self::load_rules_from_dir(&dir_rule.source, &dir_rule.upstream_group) {
+                Ok((files, count)) => {
+                    *dir_rule.files.write().await = files;
+                    rule_count += count;
+                },
+                Err(e) => {
+                    warn!(dir = %dir_rule.source.dir, error = %e, "Failed to rescan rules directory, keeping current rules");
+                    rule_count += dir_rule.files.read().await.iter().map(|f| f.core.rule_count()).sum::<usize>();
+                }
+            }
+        }
+        
+        {
+            METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_DIR]).set(rule_count as f64);
+        }
+    }
+    
+    // 加载目录中的所有规则文件并按文件名排序
+    //
+    // 隐藏文件与子目录被忽略；无法读取或格式错误的文件记录警告后跳过，不影响其他文件。
+    // 文件名（不含扩展名）在 groups 中有映射时使用映射的上游组，否则使用规则的目标上游组。
+    fn load_rules_from_dir(source: &RuleSourceConfig, default_target: &RouteTarget) -> Result<(Vec<FileRuleData>, usize)> {
+        let entries = std::fs::read_dir(&source.dir).map_err(|e| ServerError::RuleLoad(format!(
+            "Failed to read rules directory '{}': {}",
+            source.dir, e
+        )))?;
+        
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && !Self::is_hidden_file(path))
+            .collect();
+        paths.sort();
+        
+        let mut files = Vec::with_capacity(paths.len());
+        let mut rule_count = 0;
+        for path in paths {
+            let path_str = path.to_string_lossy();
+            let parsed = match source.format {
+                RuleFileFormat::DomainList => Self::parse_rules_file(&path_str),
+            };
+            let (core, count) = match parsed {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!(dir = %source.dir, file = %path_str, error = %e, "Skipping unreadable rules file");
+                    continue;
+                }
+            };
+            
+            let upstream_group = path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| source.groups.get(stem))
+                .map_or_else(|| default_target.clone(), |group| RouteTarget::single(group));
+            
+            rule_count += count;
+            files.push(FileRuleData { core, upstream_group });
+        }
+        
+        info!(
+            dir = %source.dir,
+            files = files.len(),
+            rules = rule_count,
+            "Loaded domain rules from directory"
+        );
+        
+        Ok((files, rule_count))
+    }
+    
+    // 以点开头的文件视为隐藏文件（如编辑器的临时文件）
+    fn is_hidden_file(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'))
+    }
+    
     // 从文件加载规则
     fn load_rules_from_file(path: &str) -> Result<RouterCore> {
+        let (core, rule_count) = Self::parse_rules_file(path)?;
+        
+        // 更新文件规则指标
+        {
+            // 注意：这里使用文件规则类型标签，与URL规则区分
+            METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_FILE]).set(rule_count as f64);
+        }
+        
+        Ok(core)
+    }
+    
+    // 解析规则文件，返回规则内容与规则数量
+    fn parse_rules_file(path: &str) -> Result<(RouterCore, usize)> {
         // 打开文件
         let file = match File::open(path) {
             Ok(f) => f,
@@ -500,11 +1101,7 @@ impl Router {
             }
         }
         
-        // 更新文件规则指标
-        {
-            // 注意：这里使用文件规则类型标签，与URL规则区分
-            METRICS.route_rules().with_label_values(&[ROUTE_RULE_TYPE_FILE]).set((exact.len() + regex.len() + wildcard.len()) as f64);
-        }
+        let rule_count = exact.len() + regex.len() + wildcard.len();
         
         info!(
             file = path,
@@ -519,21 +1116,21 @@ impl Router {
         
         // 添加精确匹配规则
         for domain in exact {
-            core.add_exact_rule(domain, "file_rule".to_string());
+            core.add_exact_rule(domain, RouteTarget::single("file_rule"));
         }
         
         // 添加通配符规则
         for pattern in wildcard {
-            core.add_wildcard_rule(pattern.pattern.clone(), "file_rule".to_string());
+            core.add_wildcard_rule(pattern.pattern.clone(), RouteTarget::single("file_rule"));
         }
         
         // 添加正则表达式规则
         for (i, re) in regex.iter().enumerate() {
             let pattern = format!("regex_pattern_{}", i);
-            core.add_regex_rule(pattern, re.clone(), "file_rule".to_string());
+            core.add_regex_rule(pattern, re.clone(), RouteTarget::single("file_rule"));
         }
         
-        Ok(core)
+        Ok((core, rule_count))
     }
     
     // 处理规则行
@@ -761,14 +1358,8 @@ impl Router {
             )));
         }
         
-        // 获取响应文本
-        let text = match response.text().await {
-            Ok(t) => t,
-            Err(e) => {
-                error!("Failed to read response body from {}: {}", url, e);
-                return Err(ServerError::Http(e.to_string()));
-            }
-        };
+        // 获取响应文本（gzip 压缩的列表先解压）
+        let text = Self::read_rule_list(response, url).await?;
         
         // 初始化URL规则
         let mut url_rules = UrlRules::default();
@@ -826,6 +1417,52 @@ impl Router {
         Ok((text, url_rules))
     }
     
+    // 读取规则列表响应体，通过 Content-Encoding: gzip 或 URL 的 .gz 后缀识别压缩的列表并解压
+    // 解压失败时返回错误，由调用方保留当前规则
+    async fn read_rule_list(response: reqwest::Response, url: &str) -> Result<String> {
+        let gzip_encoded = response.headers()
+            .get(reqwest::header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|encoding| encoding.trim().eq_ignore_ascii_case("gzip"));
+        let gzip_suffix = response.url().path().ends_with(".gz");
+        
+        let body = response.bytes().await.map_err(|e| {
+            error!("Failed to read response body from {}: {}", url, e);
+            ServerError::Http(e.to_string())
+        })?;
+        
+        if !gzip_encoded && !gzip_suffix {
+            return Ok(String::from_utf8_lossy(&body).into_owned());
+        }
+        
+        let mut text = String::new();
+        let decompressed = flate2::read::MultiGzDecoder::new(body.as_ref())
+            .take(MAX_DECOMPRESSED_RULE_LIST_SIZE + 1)
+            .read_to_string(&mut text);
+        match decompressed {
+            Ok(size) if size as u64 > MAX_DECOMPRESSED_RULE_LIST_SIZE => {
+                warn!(url = url, max_size = MAX_DECOMPRESSED_RULE_LIST_SIZE, "Decompressed rule list is too large, keeping current rules");
+                Err(ServerError::RuleFetch(format!(
+                    "Decompressed rule list from URL '{}' exceeds {} bytes",
+                    url, MAX_DECOMPRESSED_RULE_LIST_SIZE
+                )))
+            },
+            Ok(_) => {
+                debug!(url = url, compressed_size = body.len(), decompressed_size = text.len(), "Decompressed gzip rule list");
+                Ok(text)
+            },
+            Err(e) => {
+                warn!(url = url, error = %e, "Failed to decompress gzip rule list, keeping current rules");
+                Err(ServerError::RuleFetch(format!("Failed to decompress rule list from URL '{}': {}", url, e)))
+            },
+        }
+    }
+    
+    // 通知所有后台规则更新任务退出（关闭时调用）
+    pub fn stop_background_tasks(&self) {
+        self.stop.send_replace(true);
+    }
+    
     // 启动所有URL规则更新任务
     async fn start_url_updaters(&self) {
         // 如果没有HTTP客户端，无法更新URL规则
@@ -844,7 +1481,8 @@ impl Router {
                     let url_clone = rule.url.clone();
                     let rules_clone = Arc::clone(&rule.rules);
                     let interval_secs = config.interval_secs;
-                    let upstream_group = rule.upstream_group.clone();
+                    let upstream_group = rule.upstream_group.primary().to_string();
+                    let mut stop = self.stop.subscribe();
                     
                     // 启动独立的更新任务
                     tokio::spawn(async move {
@@ -862,11 +1500,15 @@ impl Router {
                         // 立即执行第一次更新
                         Self::update_single_url_rule(&client_clone, &url_clone, &rules_clone, &upstream_group).await;
                         
-                        // 定期更新
+                        // 定期更新，收到停止信号后退出
                         loop {
-                            interval_timer.tick().await;
+                            tokio::select! {
+                                _ = interval_timer.tick() => {},
+                                _ = stop.wait_for(|stopped| *stopped) => break,
+                            }
                             Self::update_single_url_rule(&client_clone, &url_clone, &rules_clone, &upstream_group).await;
                         }
+                        debug!(url = url_clone, "Stopped URL rule periodic updater");
                     });
                 } else {
                     debug!(url = rule.url, rule_index = index, "URL rule periodic update disabled by config");
@@ -877,6 +1519,239 @@ impl Router {
         }
     }
     
+    // 加载客户端 IP 规则中的网段 URL，并按配置定期更新
+    async fn start_client_ip_updaters(&self) {
+        for rule in &self.client_ip_rules {
+            let Some(url) = &rule.url else {
+                continue;
+            };
+            
+            let Some(client) = &self.http_client else {
+                warn!(url = url, "HTTP client not available, client IP networks from URL will not be loaded");
+                continue;
+            };
+            
+            // 启动时同步加载一次，保证服务开始处理查询时网段已生效
+            Self::refresh_client_ip_ranges(client, url, &rule.static_networks, &rule.ranges).await;
+            
+            let Some(periodic) = rule.periodic.as_ref().filter(|p| p.enabled) else {
+                continue;
+            };
+            
+            let client_clone = client.clone();
+            let url_clone = url.clone();
+            let static_networks = rule.static_networks.clone();
+            let ranges = Arc::clone(&rule.ranges);
+            let interval_secs = periodic.interval_secs;
+            let mut stop = self.stop.subscribe();
+            tokio::spawn(async move {
+                let mut interval_timer = interval(Duration::from_secs(interval_secs));
+                // 跳过立即触发的第一次 tick，启动时已加载
+                interval_timer.tick().await;
+                
+                info!(url = url_clone, interval_secs, "Started client IP network list periodic updater");
+                
+                loop {
+                    tokio::select! {
+                        _ = interval_timer.tick() => {},
+                        _ = stop.wait_for(|stopped| *stopped) => break,
+                    }
+                    Self::refresh_client_ip_ranges(&client_clone, &url_clone, &static_networks, &ranges).await;
+                }
+                debug!(url = url_clone, "Stopped client IP network list periodic updater");
+            });
+        }
+    }
+    
+    // 下载网段列表并与静态网段合并，失败时保留当前集合
+    async fn refresh_client_ip_ranges(
+        client: &Client,
+        url: &str,
+        static_networks: &[IpNet],
+        ranges: &AsyncRwLock<IpRangeSet>,
+    ) {
+        let networks = match client.get(url).send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => Self::read_rule_list(response, url).await.and_then(|text| parse_network_list(&text)),
+            Err(e) => Err(ServerError::Http(e.to_string())),
+        };
+        
+        match networks {
+            Ok(networks) => {
+                let count = networks.len();
+                let set = IpRangeSet::from_networks(static_networks.iter().copied().chain(networks));
+                *ranges.write().await = set;
+                info!(url = url, networks = count, "Loaded client IP networks from URL");
+            },
+            Err(e) => {
+                warn!(url = url, error = %e, "Failed to load client IP networks from URL, keeping current networks");
+            }
+        }
+    }
+    
+    // 加载远程规则集，并按配置间隔定期重新加载
+    async fn start_remote_rules_updater(&self) {
+        let Some(remote_rules) = &self.remote_rules else {
+            return;
+        };
+        
+        let Some(client) = &self.http_client else {
+            warn!(url = remote_rules.url, "HTTP client not available, remote routing rules will not be loaded");
+            return;
+        };
+        
+        // 启动时同步加载一次，保证服务开始处理查询时规则已生效
+        Self::refresh_remote_rules(client, remote_rules).await;
+        
+        if remote_rules.reload_interval_secs == 0 {
+            debug!(url = remote_rules.url, "Remote routing rules periodic reload disabled");
+            return;
+        }
+        
+        let client_clone = client.clone();
+        let remote_clone = Arc::clone(remote_rules);
+        let mut stop = self.stop.subscribe();
+        tokio::spawn(async move {
+            let mut interval_timer = interval(Duration::from_secs(remote_clone.reload_interval_secs));
+            // 跳过立即触发的第一次 tick，启动时已加载
+            interval_timer.tick().await;
+            
+            info!(
+                url = remote_clone.url,
+                interval_secs = remote_clone.reload_interval_secs,
+                "Started remote routing rules periodic reloader"
+            );
+            
+            loop {
+                tokio::select! {
+                    _ = interval_timer.tick() => {},
+                    _ = stop.wait_for(|stopped| *stopped) => break,
+                }
+                Self::refresh_remote_rules(&client_clone, &remote_clone).await;
+            }
+            debug!(url = remote_clone.url, "Stopped remote routing rules periodic reloader");
+        });
+    }
+    
+    // 重新加载远程规则集，失败时保留当前规则
+    async fn refresh_remote_rules(client: &Client, remote_rules: &RemoteRuleSet) {
+        METRICS.routing_rules_remote_load_total().inc();
+        
+        if let Err(e) = Self::load_remote_rules(client, remote_rules).await {
+            METRICS.routing_rules_remote_load_errors_total().inc();
+            warn!(url = remote_rules.url, error = %e, "Failed to load remote routing rules, keeping current rules");
+        }
+    }
+    
+    // 下载并解析远程规则集，内容变化时替换当前规则
+    async fn load_remote_rules(client: &Client, remote_rules: &RemoteRuleSet) -> Result<()> {
+        let url = &remote_rules.url;
+        let start_time = std::time::Instant::now();
+        
+        // 携带上次的 ETag，内容未变化时服务器返回 304
+        let mut request = client.get(url);
+        if let Some(etag) = remote_rules.etag.read().await.as_deref() {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        
+        let response = request.send().await
+            .map_err(|e| ServerError::RuleFetch(format!("Failed to fetch rules from URL '{}': {}", url, e)))?;
+        
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            debug!(url = url, "Remote routing rules not modified (ETag match), skipping update");
+            return Ok(());
+        }
+        
+        if !response.status().is_success() {
+            return Err(ServerError::RuleFetch(format!(
+                "Failed to fetch rules from URL '{}': HTTP status {}",
+                url, response.status()
+            )));
+        }
+        
+        let etag = response.headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        
+        let text = Self::read_rule_list(response, url).await?;
+        
+        let rules: Vec<Rule> = serde_yaml::from_str(&text)
+            .map_err(|e| ServerError::InvalidRuleFormat(format!("Invalid rules from URL '{}': {}", url, e)))?;
+        let rule_count = rules.len();
+        let core = Self::build_remote_core(rules, &remote_rules.group_names)?;
+        
+        // 解析成功后整体替换，查询不会看到部分更新的规则
+        *remote_rules.core.write().await = core;
+        *remote_rules.etag.write().await = etag;
+        
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        METRICS.routing_rules_last_loaded_timestamp().set(now);
+        
+        info!(
+            url = url,
+            rules = rule_count,
+            elapsed_ms = start_time.elapsed().as_millis(),
+            "Loaded remote routing rules"
+        );
+        
+        Ok(())
+    }
+    
+    // 从远程规则列表构建规则核心，仅支持 exact、wildcard 与 regex 类型
+    fn build_remote_core(rules: Vec<Rule>, group_names: &HashSet<String>) -> Result<RouterCore> {
+        let mut core = RouterCore::new();
+        
+        for (i, rule) in rules.into_iter().enumerate() {
+            let rule_index = i + 1;
+            
+            rule.check_target_groups(group_names)
+                .map_err(|e| ServerError::InvalidRuleFormat(format!("Remote rule #{} {}", rule_index, e)))?;
+            let target = RouteTarget::from_rule(&rule);
+            
+            let values = rule.match_.values.unwrap_or_default();
+            match rule.match_.type_ {
+                MatchType::Exact => {
+                    for domain in values {
+                        let normalized = normalize_rule_domain(&domain).map_err(|e| ServerError::InvalidRuleFormat(format!(
+                            "Remote rule #{}: invalid exact domain '{}': {}",
+                            rule_index, domain, e
+                        )))?;
+                        core.add_exact_rule(normalized, target.clone());
+                    }
+                },
+                MatchType::Wildcard => {
+                    for pattern in values {
+                        let normalized = normalize_wildcard_pattern(&pattern).map_err(|e| ServerError::InvalidRuleFormat(format!(
+                            "Remote rule #{}: invalid wildcard pattern '{}': {}",
+                            rule_index, pattern, e
+                        )))?;
+                        core.add_wildcard_rule(normalized, target.clone());
+                    }
+                },
+                MatchType::Regex => {
+                    for pattern in values {
+                        let regex = Regex::new(&pattern).map_err(|e| ServerError::RegexCompilation(format!(
+                            "Remote rule #{}: failed to compile regex '{}': {}",
+                            rule_index, pattern, e
+                        )))?;
+                        core.add_regex_rule(pattern, regex, target.clone());
+                    }
+                },
+                MatchType::File | MatchType::Dir | MatchType::Url | MatchType::ClientIp => {
+                    return Err(ServerError::InvalidRuleFormat(format!(
+                        "Remote rule #{}: only exact, wildcard and regex match types are supported",
+                        rule_index
+                    )));
+                },
+            }
+        }
+        
+        Ok(core)
+    }
+    
     // 更新单个URL规则
     async fn update_single_url_rule(client: &Client, url: &str, rules: &Arc<AsyncRwLock<UrlRules>>, upstream_group: &str) {
         let start_time = std::time::Instant::now();
@@ -954,13 +1829,18 @@ impl RouterCore {
         }
     }
     
+    // 规则总数
+    fn rule_count(&self) -> usize {
+        self.exact_rules.len() + self.wildcard_rules.len() + self.regex_rules.len() + usize::from(self.global_wildcard.is_some())
+    }
+    
     // 添加精确匹配规则
-    fn add_exact_rule(&mut self, domain: String, upstream_group: String) {
+    fn add_exact_rule(&mut self, domain: String, upstream_group: RouteTarget) {
         self.exact_rules.insert(domain.to_lowercase().trim_end_matches('.').to_string(), upstream_group);
     }
     
     // 添加通配符规则
-    fn add_wildcard_rule(&mut self, pattern: String, upstream_group: String) {
+    fn add_wildcard_rule(&mut self, pattern: String, upstream_group: RouteTarget) {
         // 全局通配符特殊处理
         if pattern == "*" {
             self.global_wildcard = Some(upstream_group);
@@ -985,7 +1865,7 @@ impl RouterCore {
     }
     
     // 添加正则表达式规则
-    fn add_regex_rule(&mut self, pattern: String, regex: Regex, upstream_group: String) {
+    fn add_regex_rule(&mut self, pattern: String, regex: Regex, upstream_group: RouteTarget) {
         let index = self.regex_rules.len();
         let pattern_clone = pattern.clone();
         self.regex_rules.push((regex, upstream_group, pattern));
@@ -1025,11 +1905,36 @@ impl RouterCore {
         }
     }
     
-    // 匹配域名 - 核心匹配逻辑
-    fn match_domain(&self, domain: &str) -> Option<(String, String, &'static str)> {
+    // 匹配域名 - 返回优先级最高的命中规则
+    fn match_domain(&self, domain: &str) -> Option<(RouteTarget, String, &'static str)> {
+        let mut matched = None;
+        self.visit_matches(domain, |upstream_group, pattern, rule_type| {
+            matched = Some((upstream_group.clone(), pattern.to_string(), rule_type));
+            ControlFlow::Break(())
+        });
+        matched
+    }
+    
+    // 按优先级返回所有命中规则的目标
+    fn match_all(&self, domain: &str) -> Vec<RouteTarget> {
+        let mut targets = Vec::new();
+        self.visit_matches(domain, |upstream_group, _, _| {
+            targets.push(upstream_group.clone());
+            ControlFlow::Continue(())
+        });
+        targets
+    }
+    
+    // 核心匹配逻辑：按精确、通配符、正则、全局通配符的优先级访问命中的规则，访问者返回 Break 时停止
+    fn visit_matches<F>(&self, domain: &str, mut visit: F)
+    where
+        F: FnMut(&RouteTarget, &str, &'static str) -> ControlFlow<()>,
+    {
         // 1. 优先尝试精确匹配 (O(1)复杂度)
         if let Some(upstream_group) = self.exact_rules.get(domain) {
-            return Some((upstream_group.clone(), domain.to_string(), ROUTE_RULE_TYPE_EXACT));
+            if visit(upstream_group, domain, ROUTE_RULE_TYPE_EXACT).is_break() {
+                return;
+            }
         }
         
         // 2. 然后尝试通配符匹配 (O(log n)复杂度)
@@ -1056,7 +1961,9 @@ impl RouterCore {
             
             // 检查当前后缀是否匹配
             if let Some((upstream_group, pattern)) = self.wildcard_rules.get(&current_suffix_rev) {
-                return Some((upstream_group.clone(), pattern.clone(), ROUTE_RULE_TYPE_WILDCARD));
+                if visit(upstream_group, pattern, ROUTE_RULE_TYPE_WILDCARD).is_break() {
+                    return;
+                }
             }
             
             // 继续查找更高级别的域名
@@ -1066,7 +1973,9 @@ impl RouterCore {
                 current_suffix_rev = Self::reverse_domain_labels(current_suffix);
                 
                 if let Some((upstream_group, pattern)) = self.wildcard_rules.get(&current_suffix_rev) {
-                    return Some((upstream_group.clone(), pattern.clone(), ROUTE_RULE_TYPE_WILDCARD));
+                    if visit(upstream_group, pattern, ROUTE_RULE_TYPE_WILDCARD).is_break() {
+                        return;
+                    }
                 }
                 
                 next_dot = current_suffix.find('.');
@@ -1103,19 +2012,16 @@ impl RouterCore {
         
         // 尝试匹配候选正则表达式
         for &index in &candidate_indices {
-            let (regex, upstream_group, pattern): &(Regex, String, String) = &self.regex_rules[index];
-            if regex.is_match(domain) {
-                return Some((upstream_group.clone(), pattern.clone(), ROUTE_RULE_TYPE_REGEX));
+            let (regex, upstream_group, pattern): &(Regex, RouteTarget, String) = &self.regex_rules[index];
+            if regex.is_match(domain) && visit(upstream_group, pattern, ROUTE_RULE_TYPE_REGEX).is_break() {
+                return;
             }
         }
         
         // 4. 全局通配符匹配
         if let Some(upstream_group) = &self.global_wildcard {
-            return Some((upstream_group.clone(), "*".to_string(), ROUTE_RULE_TYPE_WILDCARD));
+            let _ = visit(upstream_group, "*", ROUTE_RULE_TYPE_WILDCARD);
         }
-        
-        // 没有匹配的规则
-        None
     }
     
     // 反转域名标签，例如 "example.com" -> "com.example"
@@ -34,10 +34,11 @@ pub fn create_scalar_routes() -> Router {
     tag = "DoH",
     params(
         ("name" = String, Query, description = "Domain name to query"),
-        ("type" = Option<u16>, Query, description = "DNS record type, defaults to 1 (A record)"),
+        ("type" = Option<String>, Query, description = "DNS record type as name or number, defaults to 1 (A record)"),
         ("dns_class" = Option<u16>, Query, description = "DNS class, defaults to 1 (IN)"),
-        ("dnssec" = Option<bool>, Query, description = "Enable DNSSEC, defaults to false"),
-        ("cd" = Option<bool>, Query, description = "Enable checking disabled, defaults to false")
+        ("do" = Option<bool>, Query, description = "Set the DNSSEC OK bit (alias: dnssec), defaults to false"),
+        ("cd" = Option<bool>, Query, description = "Enable checking disabled, defaults to false"),
+        ("ct" = Option<String>, Query, description = "Response content type: application/dns-json (default) or application/dns-message")
     ),
     responses(
         (status = 200, description = "DNS query successful", body = DnsJsonResponse),
@@ -47,30 +48,35 @@ pub fn create_scalar_routes() -> Router {
 )]
 pub fn get_dns_json_query() {}
 
-/// Query DNS records in binary format (GET)
+/// Query DNS records in binary or JSON format (GET)
 #[utoipa::path(
     get,
     path = "/dns-query",
     operation_id = "getDnsWireQuery",
     tag = "DoH",
     params(
-        ("dns" = String, Query, description = "Base64url encoded DNS request")
+        ("dns" = Option<String>, Query, description = "Base64url encoded DNS request (RFC 8484)"),
+        ("name" = Option<String>, Query, description = "Domain name to query (JSON API, used when `dns` is absent)"),
+        ("type" = Option<String>, Query, description = "DNS record type as name or number (JSON API)"),
+        ("do" = Option<bool>, Query, description = "Set the DNSSEC OK bit (JSON API)"),
+        ("cd" = Option<bool>, Query, description = "Enable checking disabled (JSON API)"),
+        ("ct" = Option<String>, Query, description = "Response content type (JSON API)")
     ),
     responses(
-        (status = 200, description = "DNS query successful", content_type = "application/dns-message"),
+        (status = 200, description = "DNS query successful (application/dns-message, or application/dns-json for JSON API requests)", content_type = "application/dns-message"),
         (status = 400, description = "Invalid request parameters", body = String),
         (status = 500, description = "Internal server error", body = String)
     )
 )]
 pub fn get_dns_wire_query() {}
 
-/// Query DNS records in binary format (POST)
+/// Query DNS records in binary or JSON format (POST)
 #[utoipa::path(
     post,
     path = "/dns-query",
     operation_id = "postDnsWireQuery",
     tag = "DoH",
-    request_body(content_type = "application/dns-message", description = "Binary content of DNS request message"),
+    request_body(content_type = "application/dns-message", description = "Binary content of DNS request message, or a JSON request with content type application/dns-json"),
     responses(
         (status = 200, description = "DNS query successful", content_type = "application/dns-message"),
         (status = 400, description = "Invalid request parameters", body = String),
@@ -1,106 +1,252 @@
 // src/server/security.rs
 
+use std::io;
+use std::net::IpAddr;
 use std::num::NonZeroU32;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
 use std::time::Duration;
-use axum::{Router, http::StatusCode, response::Response};
-use axum::body::Body;
+use axum::{Router, http::{header, Request, StatusCode}, response::Response};
+use axum::body::{Body, Bytes};
+use hyper::body::Frame;
+use axum::middleware::{self, Next};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
 use tokio::time;
-use tracing::{info, warn, debug};
-use tower_governor::{
-    governor::GovernorConfigBuilder,
-    key_extractor::SmartIpKeyExtractor,
-    GovernorLayer,
-    errors::GovernorError,
-};
+use tracing::{info, info_span, warn, debug, Instrument};
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
 
-use crate::server::config::RateLimitConfig;
-use crate::common::consts::{MIN_PER_IP_RATE, MAX_PER_IP_RATE, MIN_PER_IP_CONCURRENT, MAX_PER_IP_CONCURRENT};
+use crate::server::config::{
+    AccessControlAction, AccessControlConfig, AccessControlOrder, AuthTokenConfig, MetricsAuthConfig,
+    MetricsAuthType, RateLimitConfig,
+};
+use crate::server::doh_handler::get_client_ip_from_request;
+use crate::server::ip_set::{parse_network, IpRangeSet};
+use crate::server::tls::ClientCertificate;
+use crate::common::consts::{
+    MIN_PER_IP_RATE, MAX_PER_IP_RATE, MIN_PER_IP_CONCURRENT, MAX_PER_IP_CONCURRENT,
+    DEFAULT_RATE_LIMIT_BUCKET, RATE_LIMIT_BUCKET_HEADER,
+};
+use crate::server::error::ServerError;
 use crate::server::metrics::METRICS;
 
+// 指标端点认证相关常量
+const METRICS_AUTH_REALM: &str = "metrics";
+const AUTH_SCHEME_BASIC: &str = "Basic";
+const AUTH_SCHEME_BEARER: &str = "Bearer";
 
-// 返回应用了速率限制的路由或者错误
-pub fn apply_rate_limiting(routes: Router, config: &RateLimitConfig) -> Router {
-    if !config.enabled {
-        return routes;
+// DoH 令牌认证相关常量
+const DOH_AUTH_REALM: &str = "dns";
+
+// 管理端点令牌认证相关常量
+const ADMIN_AUTH_REALM: &str = "admin";
+
+// 访问控制指标标签：拒绝请求的列表
+const ACCESS_CONTROL_LIST_ALLOW: &str = "allow";
+const ACCESS_CONTROL_LIST_DENY: &str = "deny";
+
+// 速率限制桶：未匹配覆盖的请求使用默认桶，覆盖桶可不限速
+struct RateLimitBucket {
+    name: String,
+    // None 表示不限速
+    limiter: Option<DefaultKeyedRateLimiter<String>>,
+    // 429 响应中的 Retry-After 值（秒）
+    retry_after: String,
+}
+
+impl RateLimitBucket {
+    fn new(name: &str, rate: u32, burst: u32) -> Self {
+        // 确保速率与突发大小在有效范围内
+        let rate = rate.clamp(MIN_PER_IP_RATE, MAX_PER_IP_RATE);
+        let burst_size = burst.clamp(MIN_PER_IP_CONCURRENT, MAX_PER_IP_CONCURRENT);
+        let burst_size_nz = NonZeroU32::new(burst_size).unwrap_or_else(|| {
+            warn!("per_ip_concurrent configuration resulted in zero burst size, defaulting to {}", MIN_PER_IP_CONCURRENT);
+            NonZeroU32::new(MIN_PER_IP_CONCURRENT).unwrap()
+        });
+        
+        // 计算令牌补充周期，速率已限制在有效范围内
+        let period_duration = calculate_period_duration(rate).unwrap();
+        
+        // 预先计算 Retry-After 值（向上取整的秒数，最小为5秒）
+        let retry_after = (period_duration.as_secs_f64().ceil() as u64).max(5).to_string();
+        
+        info!(
+            bucket = name,
+            per_second = rate,
+            burst_size = burst_size_nz.get(),
+            interval_milliseconds = period_duration.as_millis() as u64,
+            retry_after = retry_after,
+            "Rate limiting bucket configured",
+        );
+        
+        let quota = Quota::with_period(period_duration).unwrap().allow_burst(burst_size_nz);
+        Self {
+            name: name.to_string(),
+            limiter: Some(RateLimiter::keyed(quota)),
+            retry_after,
+        }
     }
     
-    // 确保突发大小在有效范围内
-    let burst_size = config.per_ip_concurrent.clamp(MIN_PER_IP_CONCURRENT, MAX_PER_IP_CONCURRENT);
-    let burst_size_nz = NonZeroU32::new(burst_size).unwrap_or_else(|| {
-        warn!("per_ip_concurrent configuration resulted in zero burst size, defaulting to {}", MIN_PER_IP_CONCURRENT);
-        NonZeroU32::new(MIN_PER_IP_CONCURRENT).unwrap()
-    });
-    let burst_size_u32 = burst_size_nz.get();
+    fn unlimited(name: &str) -> Self {
+        info!(bucket = name, "Rate limiting bucket configured without limits");
+        Self {
+            name: name.to_string(),
+            limiter: None,
+            retry_after: String::new(),
+        }
+    }
+}
+
+// 速率限制覆盖：按令牌名称或客户端网段匹配
+struct RateLimitOverride {
+    networks: IpRangeSet,
+    token_names: Vec<String>,
+    bucket: Arc<RateLimitBucket>,
+}
+
+// 解析请求所属的速率限制桶：令牌匹配优先于网段，均未匹配时使用默认桶
+struct RateLimitBuckets {
+    default: Arc<RateLimitBucket>,
+    overrides: Vec<RateLimitOverride>,
+}
+
+impl RateLimitBuckets {
+    fn new(config: &RateLimitConfig) -> Self {
+        let overrides = config.overrides.iter()
+            .map(|entry| RateLimitOverride {
+                // 网段已在配置验证时检查
+                networks: IpRangeSet::from_networks(entry.cidrs.iter().filter_map(|cidr| parse_network(cidr).ok())),
+                token_names: entry.tokens.clone(),
+                bucket: Arc::new(if entry.unlimited {
+                    RateLimitBucket::unlimited(&entry.name)
+                } else {
+                    RateLimitBucket::new(
+                        &entry.name,
+                        entry.rate.unwrap_or(config.per_ip_rate),
+                        entry.burst.unwrap_or(config.per_ip_concurrent),
+                    )
+                }),
+            })
+            .collect();
+        
+        Self {
+            default: Arc::new(RateLimitBucket::new(DEFAULT_RATE_LIMIT_BUCKET, config.per_ip_rate, config.per_ip_concurrent)),
+            overrides,
+        }
+    }
     
-    // 确保速率在有效范围内
-    let rate = config.per_ip_rate.clamp(MIN_PER_IP_RATE, MAX_PER_IP_RATE);
+    // 返回匹配的桶与桶内的计数键：令牌覆盖按令牌名称计数，
+    // 其余按客户端证书主题（mTLS 连接）或客户端 IP 计数
+    fn resolve(&self, client_ip: IpAddr, token_name: Option<&str>, client_cert: Option<&ClientCertificate>) -> (&Arc<RateLimitBucket>, String) {
+        if let Some(name) = token_name {
+            if let Some(entry) = self.overrides.iter().find(|entry| entry.token_names.iter().any(|n| n == name)) {
+                return (&entry.bucket, name.to_string());
+            }
+        }
+        
+        let bucket = self.overrides.iter()
+            .find(|entry| entry.networks.contains(client_ip))
+            .map_or(&self.default, |entry| &entry.bucket);
+        let key = match client_cert {
+            Some(cert) => format!("cert:{}", cert.subject),
+            None => client_ip.to_canonical().to_string(),
+        };
+        (bucket, key)
+    }
     
-    // 计算令牌补充周期
-    let period_duration = calculate_period_duration(rate);
+    // 清理各桶中过期的限制器状态
+    fn retain_recent(&self) -> usize {
+        std::iter::once(&self.default)
+            .chain(self.overrides.iter().map(|entry| &entry.bucket))
+            .filter_map(|bucket| bucket.limiter.as_ref())
+            .map(|limiter| {
+                limiter.retain_recent();
+                limiter.len()
+            })
+            .sum()
+    }
+}
 
-    // 转换间隔为毫秒
-    let interval_milliseconds = if let Some(duration) = period_duration {
-        duration.as_millis() as u64
-    } else {
-        0
-    };
+// 延迟到需要查询上游时才进行的速率限制检查（count_cache_hits 为 false 时使用）
+struct DeferredRateLimit {
+    bucket: Arc<RateLimitBucket>,
+    key: String,
+    // 同一请求仅消耗一次配额
+    checked: AtomicBool,
+    rejected: AtomicBool,
+}
+
+tokio::task_local! {
+    static DEFERRED_RATE_LIMIT: Arc<DeferredRateLimit>;
+}
+
+// 在查询上游前调用：当前请求的速率限制被延迟时消耗一次配额，超出限制返回 RateLimited
+// 未处于延迟检查的请求中（未启用或缓存命中计入速率限制）时直接通过
+pub fn check_deferred_rate_limit() -> crate::server::error::Result<()> {
+    DEFERRED_RATE_LIMIT.try_with(|deferred| {
+        if deferred.checked.swap(true, Ordering::Relaxed) {
+            return if deferred.rejected.load(Ordering::Relaxed) {
+                Err(ServerError::RateLimited(deferred.bucket.name.clone()))
+            } else {
+                Ok(())
+            };
+        }
+        
+        let allowed = deferred.bucket.limiter.as_ref()
+            .is_none_or(|limiter| limiter.check_key(&deferred.key).is_ok());
+        if allowed {
+            return Ok(());
+        }
+        
+        deferred.rejected.store(true, Ordering::Relaxed);
+        Err(ServerError::RateLimited(deferred.bucket.name.clone()))
+    }).unwrap_or(Ok(()))
+}
+
+// 构建 429 Too Many Requests 响应并记录速率限制指标，响应中标明拒绝请求的桶
+fn rate_limited_response(client_ip: IpAddr, bucket: &RateLimitBucket) -> Response {
+    // 记录速率限制指标
+    METRICS.rate_limit_rejected_total().with_label_values(&[&client_ip.to_string()]).inc();
+    debug!(
+        client_ip = %client_ip,
+        bucket = %bucket.name,
+        "Rate limit exceeded by client. Too Many Requests!"
+    );
+    
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::RETRY_AFTER, &bucket.retry_after)
+        .header(RATE_LIMIT_BUCKET_HEADER, &bucket.name)
+        .body(Body::from("Rate limit exceeded, please slow down and retry later."))
+        .unwrap()
+}
 
-    // 预先计算 Retry-After 值（向上取整的秒数，最小为5秒）
-    let retry_seconds = if let Some(duration) = period_duration {
-        let secs = (duration.as_secs_f64().ceil() as u64).max(5);
-        secs.to_string()
+// 返回应用了速率限制的路由
+// 启用令牌认证时，tokens 与 token_paths 用于识别请求所用的令牌，以匹配按令牌名称配置的覆盖
+pub fn apply_rate_limiting(routes: Router, config: &RateLimitConfig, tokens: &[AuthTokenConfig], token_paths: &[String]) -> Router {
+    if !config.enabled {
+        return routes;
+    }
+    
+    let buckets = Arc::new(RateLimitBuckets::new(config));
+    // 仅在存在按令牌匹配的覆盖时识别令牌
+    let tokens = Arc::new(if config.overrides.iter().any(|entry| !entry.tokens.is_empty()) {
+        tokens.to_vec()
     } else {
-        "5".to_string()
-    };
+        Vec::new()
+    });
+    let path_prefixes = Arc::new(token_path_prefixes(token_paths));
     
+    let count_cache_hits = config.count_cache_hits;
     info!(
-        per_second = rate,
-        burst_size = burst_size_u32,
-        interval_milliseconds = interval_milliseconds,
-        retry_after = retry_seconds,
-        key_extractor = "SmartIpKeyExtractor",
+        overrides = config.overrides.len(),
+        count_cache_hits = count_cache_hits,
         "Rate limiting enabled",
     );
-
-    // 构建 Governor 配置，添加错误处理程序
-    let governor_conf = Arc::new(
-        GovernorConfigBuilder::default()
-            .key_extractor(SmartIpKeyExtractor)
-            .period(period_duration.unwrap()) // 在此处使用 unwrap()，实际的错误处理转移到了调用者
-            .burst_size(burst_size_u32)
-            .error_handler(move |err: GovernorError| {
-                // 获取客户端 IP 并记录指标
-                if let GovernorError::TooManyRequests { .. } = &err {
-                    // 直接从请求上下文中获取客户端 IP (这里没有想好如何获取，先写死)
-                    let client_ip = "unknown".to_string();
-
-                    // 记录速率限制指标
-                    {
-                        METRICS.rate_limit_rejected_total().with_label_values(&[&client_ip]).inc();
-                    }
-                    
-                    // 使用毫秒更新日志消息
-                    debug!(
-                        client_ip = %client_ip,
-                        "Rate limit exceeded by client. Too Many Requests! Wait for {}ms", 
-                        interval_milliseconds
-                    );
-                }
-                
-                // 返回 429 Too Many Requests 响应
-                Response::builder()
-                    .status(StatusCode::TOO_MANY_REQUESTS)
-                    .header("Retry-After", &retry_seconds.to_string()) // Ensure retry_seconds is converted to string
-                    .body(Body::from("Rate limit exceeded, please slow down and retry later."))
-                    .unwrap()
-            })
-            .finish()
-            .unwrap(),
-    );
     
     // 启动后台清理任务
-    let limiter = governor_conf.limiter().clone();
+    let cleanup_buckets = buckets.clone();
     tokio::spawn(async move {
         let interval = Duration::from_secs(60); // 每分钟清理一次
         let mut interval_timer = time::interval(interval);
@@ -108,14 +254,146 @@ pub fn apply_rate_limiting(routes: Router, config: &RateLimitConfig) -> Router {
         loop {
             interval_timer.tick().await;
             // 清理旧的限制器状态
-            limiter.retain_recent();
-            let size = limiter.len();
+            let size = cleanup_buckets.retain_recent();
             info!("Cleaned up rate limiter state: current size {}", size);
         }
     });
     
-    // 应用 GovernorLayer 到路由
-    routes.layer(GovernorLayer { config: governor_conf })
+    routes.layer(middleware::from_fn(move |req: Request<Body>, next: Next| {
+        let buckets = buckets.clone();
+        let tokens = tokens.clone();
+        let path_prefixes = path_prefixes.clone();
+        async move {
+            let client_ip = get_client_ip_from_request(&req);
+            let token_name = if tokens.is_empty() {
+                None
+            } else {
+                presented_token(&req, &path_prefixes).and_then(|token| find_token_name(&tokens, token))
+            };
+            
+            let client_cert = req.extensions().get::<ClientCertificate>();
+            let (bucket, key) = buckets.resolve(client_ip, token_name.as_deref(), client_cert);
+            // 不限速的覆盖完全跳过限制器
+            let Some(limiter) = &bucket.limiter else {
+                return next.run(req).await;
+            };
+            
+            // 缓存命中不计入时，由处理器在查询上游前检查，超出限制的请求在此转换为 429
+            if !count_cache_hits {
+                let deferred = Arc::new(DeferredRateLimit {
+                    bucket: bucket.clone(),
+                    key,
+                    checked: AtomicBool::new(false),
+                    rejected: AtomicBool::new(false),
+                });
+                let response = DEFERRED_RATE_LIMIT.scope(deferred.clone(), next.run(req)).await;
+                if deferred.rejected.load(Ordering::Relaxed) {
+                    return rate_limited_response(client_ip, &deferred.bucket);
+                }
+                return response;
+            }
+            
+            if limiter.check_key(&key).is_ok() {
+                return next.run(req).await;
+            }
+            
+            rate_limited_response(client_ip, bucket)
+        }
+    }))
+}
+
+// 客户端 IP 访问控制列表
+struct AccessControlList {
+    allow: IpRangeSet,
+    deny: IpRangeSet,
+    order: AccessControlOrder,
+}
+
+impl AccessControlList {
+    // 配置已通过验证，无法解析的网段被忽略
+    fn new(config: &AccessControlConfig) -> Self {
+        let networks = |entries: &[String]| IpRangeSet::from_networks(
+            entries.iter().filter_map(|entry| parse_network(entry).ok())
+        );
+        Self {
+            allow: networks(&config.allow),
+            deny: networks(&config.deny),
+            order: config.order,
+        }
+    }
+    
+    // 判断客户端是否被拒绝，返回拒绝请求的列表名称
+    fn rejected_by(&self, client_ip: IpAddr) -> Option<&'static str> {
+        match self.order {
+            AccessControlOrder::DenyThenAllow => {
+                if self.deny.contains(client_ip) {
+                    Some(ACCESS_CONTROL_LIST_DENY)
+                } else if !self.allow.is_empty() && !self.allow.contains(client_ip) {
+                    Some(ACCESS_CONTROL_LIST_ALLOW)
+                } else {
+                    None
+                }
+            },
+            AccessControlOrder::AllowThenDeny => {
+                (!self.allow.contains(client_ip) && self.deny.contains(client_ip))
+                    .then_some(ACCESS_CONTROL_LIST_DENY)
+            },
+        }
+    }
+}
+
+// 立即返回错误的响应体，使 HTTP 服务端不发送响应而直接中止请求
+struct AbortedBody;
+
+impl hyper::body::Body for AbortedBody {
+    type Data = Bytes;
+    type Error = io::Error;
+    
+    fn poll_frame(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, io::Error>>> {
+        Poll::Ready(Some(Err(io::Error::new(io::ErrorKind::ConnectionAborted, "request rejected by access control"))))
+    }
+}
+
+// 返回应用了客户端 IP 访问控制的路由，客户端 IP 的识别方式与速率限制相同
+pub fn apply_access_control(routes: Router, config: &AccessControlConfig) -> Router {
+    if !config.is_enabled() {
+        return routes;
+    }
+    
+    let list = Arc::new(AccessControlList::new(config));
+    let action = config.action;
+    info!(
+        allow = config.allow.len(),
+        deny = config.deny.len(),
+        order = ?config.order,
+        action = ?action,
+        "Client IP access control enabled",
+    );
+    
+    routes.layer(middleware::from_fn(move |req: Request<Body>, next: Next| {
+        let list = list.clone();
+        async move {
+            let client_ip = get_client_ip_from_request(&req);
+            let Some(rejected_by) = list.rejected_by(client_ip) else {
+                return next.run(req).await;
+            };
+            
+            METRICS.access_control_rejected_total().with_label_values(&[rejected_by]).inc();
+            debug!(
+                client_ip = %client_ip,
+                list = rejected_by,
+                "Request rejected by client IP access control"
+            );
+            
+            match action {
+                AccessControlAction::Forbidden => Response::builder()
+                    .status(StatusCode::FORBIDDEN)
+                    .body(Body::from("Forbidden"))
+                    .unwrap(),
+                AccessControlAction::Drop => Response::new(Body::new(AbortedBody)),
+            }
+        }
+    }))
 }
 
 // 根据速率计算补充周期，返回 Option<Duration>
@@ -132,4 +410,154 @@ pub fn calculate_period_duration(rate: u32) -> Option<Duration> {
     // 周期 (纳秒) = 1,000,000,000 / 速率
     let period_nanos = 1000000000 / rate;
     Some(Duration::from_nanos(period_nanos.into()))
-} 
\ No newline at end of file
+} 
+
+// 为指标路由添加认证中间件，凭据缺失或错误时返回 401
+pub fn apply_metrics_auth(routes: Router, config: &MetricsAuthConfig) -> Router {
+    // 预先计算期望的认证方案和凭据
+    let (scheme, expected) = match config.type_ {
+        MetricsAuthType::Basic => {
+            let username = config.username.as_deref().unwrap_or_default();
+            let password = config.password.as_deref().unwrap_or_default();
+            (AUTH_SCHEME_BASIC, BASE64_STANDARD.encode(format!("{}:{}", username, password)))
+        },
+        MetricsAuthType::Bearer => {
+            (AUTH_SCHEME_BEARER, config.token.clone().unwrap_or_default())
+        },
+    };
+    let expected = Arc::new(expected);
+    
+    info!(scheme = scheme, "Metrics endpoint authentication enabled");
+    
+    routes.layer(middleware::from_fn(move |req: Request<Body>, next: Next| {
+        let expected = expected.clone();
+        async move {
+            let authorized = req.headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split_once(' '))
+                .is_some_and(|(req_scheme, credentials)| {
+                    req_scheme.eq_ignore_ascii_case(scheme)
+                        && constant_time_eq(credentials.trim().as_bytes(), expected.as_bytes())
+                });
+            
+            if authorized {
+                return next.run(req).await;
+            }
+            
+            debug!("Rejected unauthenticated metrics request");
+            
+            // 返回 401 Unauthorized 响应
+            Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .header(header::WWW_AUTHENTICATE, format!("{} realm=\"{}\"", scheme, METRICS_AUTH_REALM))
+                .body(Body::from("Unauthorized"))
+                .unwrap()
+        }
+    }))
+}
+
+// 为管理路由添加 Bearer 令牌认证，令牌缺失或错误时返回 401
+pub fn apply_admin_token_auth(routes: Router, token: String) -> Router {
+    let token = Arc::new(token);
+    routes.layer(middleware::from_fn(move |req: Request<Body>, next: Next| {
+        let token = token.clone();
+        async move {
+            let authorized = bearer_token(&req)
+                .is_some_and(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes()));
+            if authorized {
+                return next.run(req).await;
+            }
+            
+            debug!(path = %req.uri().path(), "Rejected unauthenticated admin request");
+            unauthorized_response(format!("{} realm=\"{}\"", AUTH_SCHEME_BEARER, ADMIN_AUTH_REALM))
+        }
+    }))
+}
+
+// 为 DoH 路由添加令牌认证中间件
+// 令牌从 Authorization: Bearer 头读取，或从 doh_paths 之后的路径段 (/dns-query/<token>) 读取；
+// 认证成功的令牌名称附加到 tracing span 与指标标签中
+pub fn apply_token_auth(routes: Router, tokens: Vec<AuthTokenConfig>, doh_paths: &[String]) -> Router {
+    let tokens = Arc::new(tokens);
+    let path_prefixes = Arc::new(token_path_prefixes(doh_paths));
+    
+    info!(tokens = tokens.len(), "DoH token authentication enabled");
+    
+    routes.layer(middleware::from_fn(move |req: Request<Body>, next: Next| {
+        let tokens = tokens.clone();
+        let path_prefixes = path_prefixes.clone();
+        async move {
+            let Some(presented) = presented_token(&req, &path_prefixes) else {
+                debug!("Rejected DoH request without a token");
+                METRICS.auth_requests_total().with_label_values(&["-", "missing"]).inc();
+                return unauthorized_response(format!("{} realm=\"{}\"", AUTH_SCHEME_BEARER, DOH_AUTH_REALM));
+            };
+            
+            let Some(name) = find_token_name(&tokens, presented) else {
+                debug!("Rejected DoH request with an invalid token");
+                METRICS.auth_requests_total().with_label_values(&["-", "invalid"]).inc();
+                return unauthorized_response(format!(
+                    "{} realm=\"{}\", error=\"invalid_token\"",
+                    AUTH_SCHEME_BEARER, DOH_AUTH_REALM
+                ));
+            };
+            
+            METRICS.auth_requests_total().with_label_values(&[&name, "authorized"]).inc();
+            let span = info_span!("doh_auth", token_name = %name);
+            next.run(req).instrument(span).await
+        }
+    }))
+}
+
+// 嵌入令牌的路径前缀 (/dns-query/)
+fn token_path_prefixes(paths: &[String]) -> Vec<String> {
+    paths.iter()
+        .map(|path| format!("{}/", path.trim_end_matches('/')))
+        .collect()
+}
+
+// 读取客户端提供的令牌：优先 Authorization: Bearer 头，其次路径前缀之后的路径段
+fn presented_token<'a>(req: &'a Request<Body>, path_prefixes: &[String]) -> Option<&'a str> {
+    bearer_token(req)
+        .or_else(|| {
+            let path = req.uri().path();
+            path_prefixes.iter().find_map(|prefix| path.strip_prefix(prefix.as_str()))
+        })
+        .filter(|token| !token.is_empty())
+}
+
+// 读取 Authorization: Bearer 头中的令牌
+fn bearer_token(req: &Request<Body>) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(AUTH_SCHEME_BEARER))
+        .map(|(_, token)| token.trim())
+}
+
+// 查找令牌对应的名称，比较所有令牌以避免通过响应时间泄露匹配位置
+fn find_token_name(tokens: &[AuthTokenConfig], presented: &str) -> Option<String> {
+    tokens.iter().fold(None, |found, entry| {
+        let matched = constant_time_eq(presented.as_bytes(), entry.token.as_bytes());
+        found.or(matched.then(|| entry.name.clone()))
+    })
+}
+
+// 返回带 WWW-Authenticate 头的 401 响应
+fn unauthorized_response(challenge: String) -> Response {
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(header::WWW_AUTHENTICATE, challenge)
+        .body(Body::from("Unauthorized"))
+        .unwrap()
+}
+
+// 常量时间比较，避免通过响应时间泄露凭据信息
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
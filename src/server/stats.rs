@@ -0,0 +1,541 @@
+// src/server/stats.rs
+
+// 该模块负责按域名聚合查询统计信息。
+//
+// 每个域名维护一组按时间划分的统计桶，组成一个滑动窗口（默认 5 分钟），
+// 统计内容包括：查询总数、缓存命中/未命中、平均延迟以及响应码分布。
+// 所有计数器均为原子类型，可在多个请求处理任务间无锁并发更新。
+//
+// 每个域名另外维护一组按分钟划分的统计桶，覆盖最近 1 小时，供缓存分析（GET /admin/cache/top）
+// 按 1m、5m、15m、1h 窗口查询缓存命中率。两组统计桶都没有数据的域名被清理，
+// 跟踪的域名数受 max_tracked_domains 限制，达到上限后不再跟踪新域名。
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use dashmap::DashMap;
+use hickory_proto::op::ResponseCode;
+use serde::{Deserialize, Serialize};
+use tokio::time::{interval, Instant};
+use tracing::debug;
+use crate::common::consts::{
+    DEFAULT_TOP_DOMAINS_LIMIT, MAX_TOP_DOMAINS_LIMIT,
+    STATS_TOP_DOMAINS_PATH, STATS_WINDOW_BUCKETS,
+    CACHE_TOP_PATH, DEFAULT_CACHE_TOP_LIMIT,
+    CACHE_ANALYTICS_BUCKET_SECS, CACHE_ANALYTICS_BUCKETS,
+};
+use crate::server::config::StatsConfig;
+
+// 响应码计数槽位数：0-15 为标准响应码，最后一个槽位记录扩展响应码
+const RCODE_SLOTS: usize = 17;
+
+// 扩展响应码的统计标签
+const RCODE_LABEL_EXTENDED: &str = "Extended";
+
+// 单个时间桶内的统计计数器；R 为响应码计数槽位数，缓存分析的桶不记录响应码（R 为 0）
+struct StatsBucket<const R: usize> {
+    // 桶所属的时间段编号（0 表示从未使用）
+    epoch: AtomicU64,
+    // 查询总数
+    queries: AtomicU64,
+    // 缓存命中数
+    cache_hits: AtomicU64,
+    // 缓存未命中数
+    cache_misses: AtomicU64,
+    // 累计延迟（微秒）
+    latency_micros: AtomicU64,
+    // 响应码分布
+    rcodes: [AtomicU64; R],
+}
+
+impl<const R: usize> Default for StatsBucket<R> {
+    fn default() -> Self {
+        Self {
+            epoch: AtomicU64::new(0),
+            queries: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            latency_micros: AtomicU64::new(0),
+            rcodes: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+}
+
+impl<const R: usize> StatsBucket<R> {
+    // 清空桶内计数器
+    fn reset(&self) {
+        self.queries.store(0, Ordering::Relaxed);
+        self.cache_hits.store(0, Ordering::Relaxed);
+        self.cache_misses.store(0, Ordering::Relaxed);
+        self.latency_micros.store(0, Ordering::Relaxed);
+        for rcode in &self.rcodes {
+            rcode.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+// 时间窗口内各统计桶的合计
+struct BucketTotals<const R: usize> {
+    queries: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+    latency_micros: u64,
+    rcodes: [u64; R],
+}
+
+impl<const R: usize> BucketTotals<R> {
+    // 平均延迟（毫秒），没有查询时为 0
+    fn avg_latency_ms(&self) -> f64 {
+        if self.queries == 0 {
+            return 0.0;
+        }
+        self.latency_micros as f64 / self.queries as f64 / 1000.0
+    }
+}
+
+// 记录一次查询到 epoch 对应的桶（环形使用，桶属于旧的时间段时先清空）
+fn record_bucket<const R: usize>(buckets: &[StatsBucket<R>], epoch: u64, cache_hit: bool, latency: Duration, rcode: ResponseCode) {
+    let bucket = &buckets[(epoch % buckets.len() as u64) as usize];
+    if claim_epoch(&bucket.epoch, epoch) {
+        bucket.reset();
+    }
+
+    bucket.queries.fetch_add(1, Ordering::Relaxed);
+    if cache_hit {
+        bucket.cache_hits.fetch_add(1, Ordering::Relaxed);
+    } else {
+        bucket.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+    bucket.latency_micros.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    if let Some(count) = bucket.rcodes.get(rcode_slot(rcode)) {
+        count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// 汇总最近 window_buckets 个时间段（含当前时间段 epoch）的桶
+fn sum_buckets<const R: usize>(buckets: &[StatsBucket<R>], epoch: u64, window_buckets: u64) -> BucketTotals<R> {
+    let oldest_epoch = epoch.saturating_sub(window_buckets - 1);
+    let mut totals = BucketTotals { queries: 0, cache_hits: 0, cache_misses: 0, latency_micros: 0, rcodes: [0; R] };
+
+    for bucket in buckets {
+        let bucket_epoch = bucket.epoch.load(Ordering::Acquire);
+        if bucket_epoch == 0 || bucket_epoch < oldest_epoch || bucket_epoch > epoch {
+            continue;
+        }
+
+        totals.queries += bucket.queries.load(Ordering::Relaxed);
+        totals.cache_hits += bucket.cache_hits.load(Ordering::Relaxed);
+        totals.cache_misses += bucket.cache_misses.load(Ordering::Relaxed);
+        totals.latency_micros += bucket.latency_micros.load(Ordering::Relaxed);
+        for (total, count) in totals.rcodes.iter_mut().zip(&bucket.rcodes) {
+            *total += count.load(Ordering::Relaxed);
+        }
+    }
+
+    totals
+}
+
+// 单个域名的统计信息
+pub struct DomainStats {
+    // 热门域名统计窗口内的统计桶（环形使用）
+    buckets: [StatsBucket<RCODE_SLOTS>; STATS_WINDOW_BUCKETS],
+    // 缓存分析按分钟划分的统计桶（环形使用），覆盖最近 1 小时
+    minute_buckets: [StatsBucket<0>; CACHE_ANALYTICS_BUCKETS],
+    // 最后一次查询所在的时间（秒，相对于统计器启动时间）
+    last_seen_secs: AtomicU64,
+}
+
+impl Default for DomainStats {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| StatsBucket::default()),
+            minute_buckets: std::array::from_fn(|_| StatsBucket::default()),
+            last_seen_secs: AtomicU64::new(0),
+        }
+    }
+}
+
+impl DomainStats {
+    // 记录一次查询；epoch 为热门域名统计的时间段编号，minute 为缓存分析的分钟编号
+    fn record(&self, epoch: u64, minute: u64, now_secs: u64, cache_hit: bool, latency: Duration, rcode: ResponseCode) {
+        record_bucket(&self.buckets, epoch, cache_hit, latency, rcode);
+        record_bucket(&self.minute_buckets, minute, cache_hit, latency, rcode);
+        self.last_seen_secs.fetch_max(now_secs, Ordering::Relaxed);
+    }
+
+    // 汇总当前窗口内的统计数据
+    fn snapshot(&self, domain: &str, epoch: u64, window_buckets: u64) -> DomainStatsSnapshot {
+        let totals = sum_buckets(&self.buckets, epoch, window_buckets);
+        let response_codes = totals.rcodes.iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(slot, count)| (rcode_label(slot), *count))
+            .collect();
+
+        DomainStatsSnapshot {
+            domain: domain.to_string(),
+            total_queries: totals.queries,
+            cache_hits: totals.cache_hits,
+            cache_misses: totals.cache_misses,
+            avg_latency_ms: totals.avg_latency_ms(),
+            response_codes,
+        }
+    }
+
+    // 汇总最近 window_minutes 分钟（含当前分钟）的缓存分析数据
+    fn cache_snapshot(&self, domain: &str, minute: u64, window_minutes: u64) -> CacheTopEntry {
+        let totals = sum_buckets(&self.minute_buckets, minute, window_minutes);
+        let hit_rate = if totals.queries > 0 {
+            totals.cache_hits as f64 / totals.queries as f64
+        } else {
+            0.0
+        };
+
+        CacheTopEntry {
+            domain: domain.to_string(),
+            query_count: totals.queries,
+            cache_hit_count: totals.cache_hits,
+            cache_miss_count: totals.cache_misses,
+            hit_rate,
+            avg_latency_ms: totals.avg_latency_ms(),
+        }
+    }
+}
+
+// 缓存分析的时间窗口
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheWindow {
+    OneMinute,
+    #[default]
+    FiveMinutes,
+    FifteenMinutes,
+    OneHour,
+}
+
+impl CacheWindow {
+    // 窗口覆盖的分钟数
+    pub fn minutes(self) -> u64 {
+        match self {
+            Self::OneMinute => 1,
+            Self::FiveMinutes => 5,
+            Self::FifteenMinutes => 15,
+            Self::OneHour => 60,
+        }
+    }
+}
+
+impl FromStr for CacheWindow {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "1m" => Ok(Self::OneMinute),
+            "5m" => Ok(Self::FiveMinutes),
+            "15m" => Ok(Self::FifteenMinutes),
+            "1h" => Ok(Self::OneHour),
+            other => Err(format!("Invalid window '{}': expected one of 1m, 5m, 15m, 1h", other)),
+        }
+    }
+}
+
+// 单个域名在缓存分析窗口内的统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheTopEntry {
+    // 域名
+    pub domain: String,
+    // 查询总数
+    pub query_count: u64,
+    // 缓存命中数
+    pub cache_hit_count: u64,
+    // 缓存未命中数
+    pub cache_miss_count: u64,
+    // 缓存命中率（0.0-1.0）
+    pub hit_rate: f64,
+    // 平均延迟（毫秒）
+    pub avg_latency_ms: f64,
+}
+
+// 缓存分析查询参数
+#[derive(Debug, Deserialize)]
+pub struct CacheTopParams {
+    // 返回的最大域名数
+    pub limit: Option<usize>,
+    // 时间窗口：1m、5m、15m 或 1h
+    pub window: Option<String>,
+}
+
+// 单个域名在当前窗口内的统计快照
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DomainStatsSnapshot {
+    // 域名
+    pub domain: String,
+    // 查询总数
+    pub total_queries: u64,
+    // 缓存命中数
+    pub cache_hits: u64,
+    // 缓存未命中数
+    pub cache_misses: u64,
+    // 平均延迟（毫秒）
+    pub avg_latency_ms: f64,
+    // 响应码分布
+    pub response_codes: BTreeMap<String, u64>,
+}
+
+// 热门域名统计响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopDomainsResponse {
+    // 统计窗口大小（秒）
+    pub window_secs: u64,
+    // 按查询次数降序排列的域名统计
+    pub domains: Vec<DomainStatsSnapshot>,
+}
+
+// 热门域名查询参数
+#[derive(Debug, Deserialize)]
+pub struct TopDomainsParams {
+    // 返回的最大域名数
+    pub limit: Option<usize>,
+}
+
+// 查询统计聚合器
+pub struct QueryStats {
+    // 按域名的统计数据
+    domains: Arc<DashMap<String, DomainStats>>,
+    // 统计窗口大小（秒）
+    window_secs: u64,
+    // 每个桶覆盖的时间（秒）
+    bucket_secs: u64,
+    // 窗口实际覆盖的桶数
+    window_buckets: u64,
+    // 域名无查询后保留统计数据的时间（秒），覆盖统计窗口和缓存分析的最大窗口
+    retention_secs: u64,
+    // 最多跟踪的域名数
+    max_tracked_domains: usize,
+    // 统计器启动时间
+    started: Instant,
+}
+
+impl QueryStats {
+    // 创建新的查询统计聚合器，并启动过期域名清理任务
+    pub fn new(config: &StatsConfig) -> Self {
+        let window_secs = config.top_domains_window_secs.max(1);
+        let bucket_secs = window_secs.div_ceil(STATS_WINDOW_BUCKETS as u64);
+        let stats = Self {
+            domains: Arc::new(DashMap::new()),
+            window_secs,
+            bucket_secs,
+            window_buckets: window_secs.div_ceil(bucket_secs),
+            retention_secs: window_secs.max(CACHE_ANALYTICS_BUCKET_SECS * CACHE_ANALYTICS_BUCKETS as u64),
+            max_tracked_domains: config.max_tracked_domains.max(1),
+            started: Instant::now(),
+        };
+
+        // 清理任务仅持有弱引用，聚合器被释放后任务自动退出
+        let domains = Arc::downgrade(&stats.domains);
+        let retention_secs = stats.retention_secs;
+        let started = stats.started;
+        tokio::spawn(async move {
+            // 缓存分析数据按分钟划分，清理间隔不超过 1 分钟
+            let mut interval_timer = interval(Duration::from_secs(window_secs.min(CACHE_ANALYTICS_BUCKET_SECS)));
+
+            // 首次调用 tick() 会立即返回，我们在这里消耗掉它
+            interval_timer.tick().await;
+
+            loop {
+                interval_timer.tick().await;
+
+                let Some(domains) = Weak::upgrade(&domains) else {
+                    debug!("Query stats purge task stopped");
+                    break;
+                };
+
+                let now_secs = started.elapsed().as_secs();
+                let removed = Self::purge_domains(&domains, now_secs, retention_secs);
+                if removed > 0 {
+                    debug!(removed, remaining = domains.len(), "Purged stale domain stats");
+                }
+            }
+        });
+
+        stats
+    }
+
+    // 记录一次已完成的查询
+    pub fn record(&self, domain: &str, cache_hit: bool, latency: Duration, rcode: ResponseCode) {
+        let key = normalize_domain(domain);
+        let now_secs = self.started.elapsed().as_secs();
+        let epoch = self.epoch_at(now_secs);
+        let minute = minute_at(now_secs);
+
+        // 先尝试只读访问，避免对已存在的域名获取写锁
+        if let Some(stats) = self.domains.get(&key) {
+            stats.record(epoch, minute, now_secs, cache_hit, latency, rcode);
+        } else if self.domains.len() < self.max_tracked_domains {
+            self.domains
+                .entry(key)
+                .or_default()
+                .record(epoch, minute, now_secs, cache_hit, latency, rcode);
+        }
+    }
+
+    // 获取当前窗口内查询次数最多的域名
+    pub fn top_domains(&self, limit: usize) -> Vec<DomainStatsSnapshot> {
+        let epoch = self.epoch_at(self.started.elapsed().as_secs());
+
+        let mut snapshots: Vec<DomainStatsSnapshot> = self.domains
+            .iter()
+            .map(|entry| entry.value().snapshot(entry.key(), epoch, self.window_buckets))
+            .filter(|snapshot| snapshot.total_queries > 0)
+            .collect();
+
+        snapshots.sort_by(|a, b| {
+            b.total_queries.cmp(&a.total_queries).then_with(|| a.domain.cmp(&b.domain))
+        });
+        snapshots.truncate(limit);
+
+        snapshots
+    }
+
+    // 获取缓存分析窗口内查询次数最多的域名
+    pub fn cache_top(&self, window: CacheWindow, limit: usize) -> Vec<CacheTopEntry> {
+        let minute = minute_at(self.started.elapsed().as_secs());
+
+        let mut entries: Vec<CacheTopEntry> = self.domains
+            .iter()
+            .map(|entry| entry.value().cache_snapshot(entry.key(), minute, window.minutes()))
+            .filter(|entry| entry.query_count > 0)
+            .collect();
+
+        entries.sort_by(|a, b| {
+            b.query_count.cmp(&a.query_count).then_with(|| a.domain.cmp(&b.domain))
+        });
+        entries.truncate(limit);
+
+        entries
+    }
+
+    // 清理统计窗口和缓存分析窗口内都没有任何查询的域名，返回清理数量
+    pub fn purge_stale(&self) -> usize {
+        Self::purge_domains(&self.domains, self.started.elapsed().as_secs(), self.retention_secs)
+    }
+
+    // 当前跟踪的域名数量
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    // 是否没有跟踪任何域名
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    // 统计窗口大小（秒）
+    pub fn window_secs(&self) -> u64 {
+        self.window_secs
+    }
+
+    // 计算给定时间所在的时间段编号（从 1 开始，0 保留表示未使用）
+    fn epoch_at(&self, now_secs: u64) -> u64 {
+        now_secs / self.bucket_secs + 1
+    }
+
+    fn purge_domains(domains: &DashMap<String, DomainStats>, now_secs: u64, retention_secs: u64) -> usize {
+        let before = domains.len();
+        domains.retain(|_, stats| {
+            now_secs.saturating_sub(stats.last_seen_secs.load(Ordering::Relaxed)) < retention_secs
+        });
+        before.saturating_sub(domains.len())
+    }
+}
+
+// 创建查询统计路由
+pub fn stats_routes(stats: Arc<QueryStats>) -> Router {
+    Router::new()
+        .route(STATS_TOP_DOMAINS_PATH, get(handle_top_domains))
+        .route(CACHE_TOP_PATH, get(handle_cache_top))
+        .with_state(stats)
+}
+
+// 处理热门域名统计请求
+async fn handle_top_domains(
+    State(stats): State<Arc<QueryStats>>,
+    Query(params): Query<TopDomainsParams>,
+) -> impl IntoResponse {
+    let limit = params.limit
+        .unwrap_or(DEFAULT_TOP_DOMAINS_LIMIT)
+        .min(MAX_TOP_DOMAINS_LIMIT);
+
+    let response = TopDomainsResponse {
+        window_secs: stats.window_secs(),
+        domains: stats.top_domains(limit),
+    };
+
+    (StatusCode::OK, Json(response))
+}
+
+// 处理缓存分析请求，返回窗口内查询最多的域名及其缓存命中情况
+async fn handle_cache_top(
+    State(stats): State<Arc<QueryStats>>,
+    Query(params): Query<CacheTopParams>,
+) -> impl IntoResponse {
+    let window = match params.window.as_deref().map(CacheWindow::from_str).transpose() {
+        Ok(window) => window.unwrap_or_default(),
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let limit = params.limit
+        .unwrap_or(DEFAULT_CACHE_TOP_LIMIT)
+        .min(MAX_TOP_DOMAINS_LIMIT);
+
+    (StatusCode::OK, Json(stats.cache_top(window, limit))).into_response()
+}
+
+// 计算给定时间所在的缓存分析分钟编号（从 1 开始，0 保留表示未使用）
+fn minute_at(now_secs: u64) -> u64 {
+    now_secs / CACHE_ANALYTICS_BUCKET_SECS + 1
+}
+
+// 桶属于旧的时间段时将其轮转到当前时间段，返回调用方是否需要清空桶内计数器。
+// 并发轮转期间可能丢失极少量计数，这对统计用途是可接受的。
+fn claim_epoch(bucket_epoch: &AtomicU64, epoch: u64) -> bool {
+    let current = bucket_epoch.load(Ordering::Acquire);
+    current != epoch
+        && bucket_epoch.compare_exchange(current, epoch, Ordering::AcqRel, Ordering::Acquire).is_ok()
+}
+
+// 规范化域名作为统计键：小写并去掉末尾的点
+fn normalize_domain(domain: &str) -> String {
+    let trimmed = domain.trim_end_matches('.');
+    if trimmed.is_empty() {
+        ".".to_string()
+    } else {
+        trimmed.to_ascii_lowercase()
+    }
+}
+
+// 响应码对应的计数槽位
+fn rcode_slot(rcode: ResponseCode) -> usize {
+    let value = u16::from(rcode) as usize;
+    if value < RCODE_SLOTS - 1 {
+        value
+    } else {
+        RCODE_SLOTS - 1
+    }
+}
+
+// 计数槽位对应的响应码标签
+fn rcode_label(slot: usize) -> String {
+    if slot < RCODE_SLOTS - 1 {
+        format!("{:?}", ResponseCode::from_low(slot as u8))
+    } else {
+        RCODE_LABEL_EXTENDED.to_string()
+    }
+}
@@ -0,0 +1,71 @@
+// src/server/svcb.rs
+
+// 为配置的名称在本地合成 SVCB/HTTPS 应答（RFC 9460），用于向客户端提示 ALPN、端口与地址，
+// 调整客户端的连接行为（例如提示支持 HTTP/3）。只处理 SVCB 与 HTTPS 查询，
+// 同一名称的其他查询类型仍按正常流程解析。合成的应答不写入缓存。
+
+use std::collections::HashMap;
+use hickory_proto::op::{Message, MessageType, ResponseCode};
+use hickory_proto::rr::rdata::svcb::{Alpn, IpHint, SvcParamKey, SvcParamValue, SVCB};
+use hickory_proto::rr::rdata::{A, AAAA, HTTPS};
+use hickory_proto::rr::{Name, RData, Record, RecordType};
+use crate::common::consts::SVCB_HINT_PRIORITY;
+use crate::server::config::SvcbHintConfig;
+
+// 按 SvcParamKey 升序（RFC 9460 第 2.2 节）构建记录，目标名称为 "." 表示名称本身
+pub fn svcb_rdata(hint: &SvcbHintConfig) -> SVCB {
+    let mut params = Vec::new();
+    if !hint.alpn.is_empty() {
+        params.push((SvcParamKey::Alpn, SvcParamValue::Alpn(Alpn(hint.alpn.clone()))));
+    }
+    if let Some(port) = hint.port {
+        params.push((SvcParamKey::Port, SvcParamValue::Port(port)));
+    }
+    if !hint.ipv4hint.is_empty() {
+        let hints = hint.ipv4hint.iter().copied().map(A).collect();
+        params.push((SvcParamKey::Ipv4Hint, SvcParamValue::Ipv4Hint(IpHint(hints))));
+    }
+    if !hint.ipv6hint.is_empty() {
+        let hints = hint.ipv6hint.iter().copied().map(AAAA).collect();
+        params.push((SvcParamKey::Ipv6Hint, SvcParamValue::Ipv6Hint(IpHint(hints))));
+    }
+    SVCB::new(SVCB_HINT_PRIORITY, Name::root(), params)
+}
+
+// 名称比较忽略大小写与末尾的点
+fn normalize_name(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+// 查询为配置名称的 SVCB/HTTPS 查询时返回合成的应答，否则返回 None
+pub fn synthesize_svcb_response(hints: &HashMap<String, SvcbHintConfig>, query_message: &Message) -> Option<Message> {
+    if hints.is_empty() {
+        return None;
+    }
+    let query = query_message.queries().first()?;
+    let rdata = match query.query_type() {
+        RecordType::HTTPS | RecordType::SVCB => query.query_type(),
+        _ => return None,
+    };
+    let query_name = normalize_name(&query.name().to_ascii());
+    let hint = hints.iter()
+        .find(|(name, _)| normalize_name(name) == query_name)
+        .map(|(_, hint)| hint)?;
+
+    let svcb = svcb_rdata(hint);
+    let rdata = match rdata {
+        RecordType::HTTPS => RData::HTTPS(HTTPS(svcb)),
+        _ => RData::SVCB(svcb),
+    };
+
+    let mut response = Message::new();
+    response.set_id(query_message.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(query_message.op_code())
+        .set_recursion_desired(query_message.recursion_desired())
+        .set_recursion_available(true)
+        .set_response_code(ResponseCode::NoError)
+        .add_queries(query_message.queries().to_vec())
+        .add_answer(Record::from_rdata(query.name().clone(), hint.ttl, rdata));
+    Some(response)
+}
@@ -0,0 +1,629 @@
+// src/server/tls.rs
+
+// 该模块负责 DoH 监听上的 TLS 终止。
+//
+// 证书通过可热替换的解析器提供：重新加载（例如收到 SIGHUP）时只替换证书与私钥，
+// 已建立的连接不受影响，新连接使用新证书，因此证书续期无需重启服务。
+// TLS 监听默认通过 ALPN 协商 h2 与 http/1.1，并对误发到该端口的明文 HTTP 请求
+// 直接返回 400 响应，而不是让客户端看到难以理解的握手失败。
+// 最低 TLS 版本、密码套件与 ALPN 协议可按配置限制，低于最低版本的握手在 TLS 层被拒绝。
+// 启用 ACME 时，解析器还负责在 TLS-ALPN-01 验证握手中返回挑战证书（RFC 8737）。
+// 配置 client_auth 时在握手阶段验证客户端证书（mTLS），证书主题与 SAN 通过请求扩展
+// ClientCertificate 提供给处理器，验证失败的连接直接在 TLS 层关闭。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use axum::{extract::ConnectInfo, Router};
+use hyper::body::Incoming;
+use hyper::Request;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use tokio_rustls::rustls::crypto::ring::default_provider;
+use tokio_rustls::rustls::crypto::CryptoProvider;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::danger::ClientCertVerifier;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tokio_rustls::rustls::version::{TLS12, TLS13};
+use tokio_rustls::rustls::{
+    Error as RustlsError, RootCertStore, ServerConfig as RustlsServerConfig, SupportedProtocolVersion,
+};
+use tokio_rustls::TlsAcceptor;
+use tower::Service;
+use tracing::{debug, info, info_span, warn, Instrument, Span};
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+use crate::common::consts::{
+    ACME_TLS_ALPN_PROTOCOL, DOT_ALPN_PROTOCOL, HTTP3_ALPN_PROTOCOL, TLS_ALPN_PROTOCOLS,
+    TLS_HANDSHAKE_RECORD_TYPE, TLS_HANDSHAKE_TIMEOUT_SECS,
+};
+use crate::server::config::{AcmeChallengeType, ClientAuthConfig, ClientAuthMode, TlsConfig, TlsMinVersion};
+use crate::server::error::{Result, ServerError};
+use crate::server::http_conn::{serve_connection, ConnectionLimits};
+use crate::server::metrics::METRICS;
+use crate::server::proxy_protocol::{client_addr, ProxyProtocol};
+
+// 明文 HTTP 请求发送到 TLS 端口时返回的响应
+const PLAIN_HTTP_REJECT_RESPONSE: &[u8] = b"HTTP/1.1 400 Bad Request\r\n\
+Content-Type: text/plain\r\n\
+Content-Length: 48\r\n\
+Connection: close\r\n\
+\r\n\
+Client sent an HTTP request to an HTTPS server.\n";
+
+// 加载证书链与私钥，并校验两者是否匹配
+pub fn load_certified_key(config: &TlsConfig) -> Result<CertifiedKey> {
+    let cert_path = config.cert_path();
+    let key_path = config.key_path();
+
+    let cert_pem = fs::read(&cert_path).map_err(|e| {
+        ServerError::Tls(format!("Failed to read TLS certificate {}: {}", cert_path.display(), e))
+    })?;
+    let key_pem = fs::read(&key_path).map_err(|e| {
+        ServerError::Tls(format!("Failed to read TLS private key {}: {}", key_path.display(), e))
+    })?;
+
+    certified_key_from_pem(
+        &cert_pem,
+        &key_pem,
+        &cert_path.display().to_string(),
+        &key_path.display().to_string(),
+    )
+}
+
+// 加载上游解析器的客户端证书与私钥（PEM），校验两者匹配且私钥为 native-tls 要求的 PKCS#8 格式
+pub fn load_client_identity_pem(cert_path: &Path, key_path: &Path) -> Result<(Vec<u8>, Vec<u8>)> {
+    let cert_pem = fs::read(cert_path).map_err(|e| {
+        ServerError::Tls(format!("Failed to read client certificate {}: {}", cert_path.display(), e))
+    })?;
+    let key_pem = fs::read(key_path).map_err(|e| {
+        ServerError::Tls(format!("Failed to read client private key {}: {}", key_path.display(), e))
+    })?;
+
+    certified_key_from_pem(
+        &cert_pem,
+        &key_pem,
+        &cert_path.display().to_string(),
+        &key_path.display().to_string(),
+    )?;
+    native_tls::Identity::from_pkcs8(&cert_pem, &key_pem).map_err(|e| ServerError::Tls(format!(
+        "Client private key {} must be an unencrypted PKCS#8 PEM key (BEGIN PRIVATE KEY): {}",
+        key_path.display(), e
+    )))?;
+    Ok((cert_pem, key_pem))
+}
+
+// 加载验证上游服务器证书的 CA 证书（PEM）
+pub fn load_ca_cert_pem(path: &Path) -> Result<Vec<u8>> {
+    let ca_pem = fs::read(path).map_err(|e| {
+        ServerError::Tls(format!("Failed to read CA certificate {}: {}", path.display(), e))
+    })?;
+    native_tls::Certificate::from_pem(&ca_pem).map_err(|e| {
+        ServerError::Tls(format!("Failed to parse CA certificate {}: {}", path.display(), e))
+    })?;
+    Ok(ca_pem)
+}
+
+// 从 PEM 数据构建证书，并校验私钥与证书是否匹配
+fn certified_key_from_pem(cert_pem: &[u8], key_pem: &[u8], cert_label: &str, key_label: &str) -> Result<CertifiedKey> {
+    let certs = CertificateDer::pem_slice_iter(cert_pem)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| {
+            ServerError::Tls(format!("Failed to parse TLS certificate {}: {}", cert_label, e))
+        })?;
+    if certs.is_empty() {
+        return Err(ServerError::Tls(format!("No certificates found in {}", cert_label)));
+    }
+
+    let key = PrivateKeyDer::from_pem_slice(key_pem).map_err(|e| {
+        ServerError::Tls(format!("Failed to parse TLS private key {}: {}", key_label, e))
+    })?;
+
+    CertifiedKey::from_der(certs, key, &default_provider()).map_err(|e| match e {
+        RustlsError::InconsistentKeys(_) => ServerError::Tls(format!(
+            "TLS private key {} does not match certificate {}",
+            key_label, cert_label
+        )),
+        e => ServerError::Tls(format!(
+            "Invalid TLS certificate/key pair ({}, {}): {}",
+            cert_label, key_label, e
+        )),
+    })
+}
+
+// 加载客户端证书的 CA 并创建验证器，可选模式下允许不提供证书的客户端
+pub fn load_client_verifier(config: &ClientAuthConfig) -> Result<Arc<dyn ClientCertVerifier>> {
+    let ca_label = config.ca_cert.display();
+    let ca_pem = fs::read(&config.ca_cert).map_err(|e| {
+        ServerError::Tls(format!("Failed to read client CA certificate {}: {}", ca_label, e))
+    })?;
+    let ca_certs = CertificateDer::pem_slice_iter(&ca_pem)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| {
+            ServerError::Tls(format!("Failed to parse client CA certificate {}: {}", ca_label, e))
+        })?;
+
+    let mut roots = RootCertStore::empty();
+    for cert in ca_certs {
+        roots.add(cert).map_err(|e| {
+            ServerError::Tls(format!("Invalid client CA certificate {}: {}", ca_label, e))
+        })?;
+    }
+    if roots.is_empty() {
+        return Err(ServerError::Tls(format!("No certificates found in {}", ca_label)));
+    }
+
+    let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), Arc::new(default_provider()));
+    let builder = match config.mode {
+        ClientAuthMode::Required => builder,
+        ClientAuthMode::Optional => builder.allow_unauthenticated(),
+    };
+    builder.build().map_err(|e| {
+        ServerError::Tls(format!("Failed to create client certificate verifier: {}", e))
+    })
+}
+
+// 通过验证的客户端证书信息，作为请求扩展提供给处理器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    // 证书主题（如 "CN=device1, O=Example"）
+    pub subject: String,
+    // 主题备用名称（如 "DNS:device1.example.com"、"IP:10.0.0.1"）
+    pub subject_alt_names: Vec<String>,
+}
+
+impl ClientCertificate {
+    // 从 DER 编码的终端实体证书解析主题与 SAN
+    fn from_der(cert: &CertificateDer<'_>) -> Option<Self> {
+        let (_, parsed) = X509Certificate::from_der(cert.as_ref()).ok()?;
+        let subject_alt_names = parsed.subject_alternative_name().ok().flatten()
+            .map(|san| san.value.general_names.iter().map(format_general_name).collect())
+            .unwrap_or_default();
+        Some(Self {
+            subject: parsed.subject().to_string(),
+            subject_alt_names,
+        })
+    }
+}
+
+// 按 OpenSSL 的习惯格式化 SAN 条目
+fn format_general_name(name: &GeneralName<'_>) -> String {
+    match name {
+        GeneralName::DNSName(dns) => format!("DNS:{}", dns),
+        GeneralName::RFC822Name(email) => format!("email:{}", email),
+        GeneralName::URI(uri) => format!("URI:{}", uri),
+        GeneralName::IPAddress(bytes) => match *bytes {
+            [a, b, c, d] => format!("IP:{}", std::net::Ipv4Addr::new(*a, *b, *c, *d)),
+            octets => match <[u8; 16]>::try_from(octets) {
+                Ok(octets) => format!("IP:{}", std::net::Ipv6Addr::from(octets)),
+                Err(_) => name.to_string(),
+            },
+        },
+        other => other.to_string(),
+    }
+}
+
+// 握手失败是否由客户端证书验证导致
+fn is_client_auth_failure(error: &io::Error) -> bool {
+    matches!(
+        error.get_ref().and_then(|e| e.downcast_ref::<RustlsError>()),
+        Some(RustlsError::InvalidCertificate(_) | RustlsError::NoCertificatesPresented)
+    )
+}
+
+// 解析证书的到期时间（Unix 时间戳）
+fn certificate_not_after(key: &CertifiedKey) -> Option<i64> {
+    let cert = key.cert.first()?;
+    let (_, parsed) = X509Certificate::from_der(cert.as_ref()).ok()?;
+    Some(parsed.validity().not_after.timestamp())
+}
+
+// 生成自签名的临时证书，在 ACME 首次签发完成前使用
+fn placeholder_certified_key(domains: &[String]) -> Result<CertifiedKey> {
+    let key_pair = rcgen::KeyPair::generate()
+        .map_err(|e| ServerError::Tls(format!("Failed to generate placeholder key: {}", e)))?;
+    let cert = rcgen::CertificateParams::new(domains.to_vec())
+        .and_then(|params| params.self_signed(&key_pair))
+        .map_err(|e| ServerError::Tls(format!("Failed to generate placeholder certificate: {}", e)))?;
+
+    certified_key_from_pem(
+        cert.pem().as_bytes(),
+        key_pair.serialize_pem().as_bytes(),
+        "placeholder certificate",
+        "placeholder key",
+    )
+}
+
+// 按配置构建加密提供者，配置了 cipher_suites 时只保留列出的套件（按列出顺序优先）
+fn crypto_provider(config: &TlsConfig) -> Result<CryptoProvider> {
+    let mut provider = default_provider();
+    let Some(names) = &config.cipher_suites else {
+        return Ok(provider);
+    };
+    if names.is_empty() {
+        return Err(ServerError::Tls("tls.cipher_suites must not be empty".to_string()));
+    }
+
+    let mut suites = Vec::with_capacity(names.len());
+    for name in names {
+        let suite = provider.cipher_suites.iter()
+            .find(|suite| suite.suite().as_str().is_some_and(|known| known.eq_ignore_ascii_case(name)))
+            .ok_or_else(|| ServerError::Tls(format!("Unsupported TLS cipher suite '{}'", name)))?;
+        suites.push(*suite);
+    }
+    provider.cipher_suites = suites;
+    Ok(provider)
+}
+
+// 最低版本对应的可用协议版本
+static TLS12_AND_LATER: [&SupportedProtocolVersion; 2] = [&TLS13, &TLS12];
+static TLS13_ONLY: [&SupportedProtocolVersion; 1] = [&TLS13];
+
+fn protocol_versions(min_version: TlsMinVersion) -> &'static [&'static SupportedProtocolVersion] {
+    match min_version {
+        TlsMinVersion::Tls12 => &TLS12_AND_LATER,
+        TlsMinVersion::Tls13 => &TLS13_ONLY,
+    }
+}
+
+// 按配置确定 ALPN 协议列表，只接受 TLS 监听支持的协议
+fn alpn_protocols(config: &TlsConfig) -> Result<Vec<Vec<u8>>> {
+    let Some(names) = &config.alpn else {
+        return Ok(TLS_ALPN_PROTOCOLS.iter().map(|p| p.to_vec()).collect());
+    };
+    if names.is_empty() {
+        return Err(ServerError::Tls("tls.alpn must not be empty".to_string()));
+    }
+
+    names.iter()
+        .map(|name| {
+            TLS_ALPN_PROTOCOLS.iter()
+                .find(|protocol| protocol.eq_ignore_ascii_case(name.as_bytes()))
+                .map(|protocol| protocol.to_vec())
+                .ok_or_else(|| ServerError::Tls(format!("Unsupported ALPN protocol '{}' (expected h2 or http/1.1)", name)))
+        })
+        .collect()
+}
+
+// 校验最低版本、密码套件与 ALPN 的组合可用，启用 HTTP/3 时还需要至少一个 TLS 1.3 套件
+pub fn validate_tls_protocol(config: &TlsConfig, http3_enabled: bool) -> Result<()> {
+    let provider = Arc::new(crypto_provider(config)?);
+    alpn_protocols(config)?;
+
+    // 所选套件中没有适用于允许版本的套件时 rustls 返回错误
+    RustlsServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(protocol_versions(config.min_version))
+        .map_err(|e| ServerError::Tls(format!(
+            "tls.cipher_suites is not usable with min_version {:?}: {}", config.min_version, e
+        )))?;
+
+    if http3_enabled && !provider.cipher_suites.iter().any(|suite| suite.tls13().is_some()) {
+        return Err(ServerError::Tls(
+            "http3 requires at least one TLS 1.3 suite in tls.cipher_suites".to_string()
+        ));
+    }
+    Ok(())
+}
+
+// 可热替换证书的解析器
+struct ReloadableCertResolver {
+    // 当前使用的证书
+    current: RwLock<Arc<CertifiedKey>>,
+    // TLS-ALPN-01 挑战证书（按域名）
+    challenges: RwLock<HashMap<String, Arc<CertifiedKey>>>,
+}
+
+impl fmt::Debug for ReloadableCertResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReloadableCertResolver").finish_non_exhaustive()
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        // ACME 验证握手只提供 acme-tls/1，此时返回对应域名的挑战证书
+        let is_acme_challenge = client_hello.alpn()
+            .is_some_and(|mut protocols| protocols.any(|p| p == ACME_TLS_ALPN_PROTOCOL));
+        if is_acme_challenge {
+            let domain = client_hello.server_name()?.to_ascii_lowercase();
+            return self.challenges.read().ok()?.get(&domain).cloned();
+        }
+
+        self.current.read().ok().map(|key| key.clone())
+    }
+}
+
+// DoH 监听的 TLS 上下文
+pub struct TlsContext {
+    // 证书配置（重新加载时使用）
+    config: TlsConfig,
+    // 客户端证书验证器（配置了 client_auth 时）
+    client_verifier: Option<Arc<dyn ClientCertVerifier>>,
+    // 按 cipher_suites 筛选后的加密提供者
+    provider: Arc<CryptoProvider>,
+    // 按 min_version 允许的协议版本
+    versions: &'static [&'static SupportedProtocolVersion],
+    // 证书解析器
+    resolver: Arc<ReloadableCertResolver>,
+    // TLS 接收器
+    acceptor: TlsAcceptor,
+    // 当前证书的到期时间（Unix 时间戳，0 表示临时证书或未知）
+    not_after: AtomicI64,
+}
+
+impl TlsContext {
+    // 加载证书并创建 TLS 上下文，证书无效或与私钥不匹配时返回错误
+    pub fn new(config: &TlsConfig) -> Result<Self> {
+        let acme = &config.acme;
+        let has_cached_cert = config.cert_path().exists() && config.key_path().exists();
+
+        // 启用 ACME 且尚无证书时，先使用临时证书启动监听，等待首次签发
+        let (key, not_after) = if acme.enabled && !has_cached_cert {
+            info!(domains = ?acme.domains, "No ACME certificate cached yet, serving a temporary self-signed certificate");
+            (placeholder_certified_key(&acme.domains)?, 0)
+        } else {
+            let key = load_certified_key(config)?;
+            let not_after = certificate_not_after(&key).unwrap_or_default();
+            (key, not_after)
+        };
+
+        let resolver = Arc::new(ReloadableCertResolver {
+            current: RwLock::new(Arc::new(key)),
+            challenges: RwLock::new(HashMap::new()),
+        });
+
+        let client_verifier = config.client_auth.as_ref().map(load_client_verifier).transpose()?;
+        let provider = Arc::new(crypto_provider(config)?);
+        let versions = protocol_versions(config.min_version);
+        let builder = RustlsServerConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(versions)
+            .map_err(|e| ServerError::Tls(format!("Failed to configure TLS protocol versions: {}", e)))?;
+        let builder = match &client_verifier {
+            Some(verifier) => builder.with_client_cert_verifier(verifier.clone()),
+            None => builder.with_no_client_auth(),
+        };
+        let mut server_config = builder.with_cert_resolver(resolver.clone());
+        server_config.alpn_protocols = alpn_protocols(config)?;
+        if acme.enabled && acme.challenge == AcmeChallengeType::TlsAlpn01 {
+            server_config.alpn_protocols.push(ACME_TLS_ALPN_PROTOCOL.to_vec());
+        }
+
+        info!(
+            cert = %config.cert_path().display(),
+            key = %config.key_path().display(),
+            "TLS certificate loaded"
+        );
+        if let Some(client_auth) = &config.client_auth {
+            info!(
+                ca_cert = %client_auth.ca_cert.display(),
+                mode = ?client_auth.mode,
+                "TLS client certificate authentication enabled"
+            );
+        }
+        METRICS.tls_certificate_expiry_timestamp_seconds().set(not_after);
+
+        Ok(Self {
+            config: config.clone(),
+            client_verifier,
+            provider,
+            versions,
+            resolver,
+            acceptor: TlsAcceptor::from(Arc::new(server_config)),
+            not_after: AtomicI64::new(not_after),
+        })
+    }
+
+    // 从磁盘重新加载证书与私钥，失败时继续使用当前证书
+    pub fn reload(&self) -> Result<()> {
+        let key = load_certified_key(&self.config)?;
+        self.swap_certificate(key)?;
+
+        info!(
+            cert = %self.config.cert_path().display(),
+            key = %self.config.key_path().display(),
+            "TLS certificate reloaded"
+        );
+        Ok(())
+    }
+
+    // 安装新签发的证书：校验后写入磁盘并立即替换，已建立的连接不受影响
+    pub fn install_certificate(&self, cert_pem: &str, key_pem: &str) -> Result<()> {
+        let cert_path = self.config.cert_path();
+        let key_path = self.config.key_path();
+        let key = certified_key_from_pem(
+            cert_pem.as_bytes(),
+            key_pem.as_bytes(),
+            &cert_path.display().to_string(),
+            &key_path.display().to_string(),
+        )?;
+
+        write_file_atomically(&key_path, key_pem.as_bytes())?;
+        write_file_atomically(&cert_path, cert_pem.as_bytes())?;
+        self.swap_certificate(key)?;
+
+        info!(cert = %cert_path.display(), "New TLS certificate installed");
+        Ok(())
+    }
+
+    // 设置或清除某个域名的 TLS-ALPN-01 挑战证书
+    pub fn set_alpn_challenge(&self, domain: &str, challenge: Option<CertifiedKey>) {
+        if let Ok(mut challenges) = self.resolver.challenges.write() {
+            let domain = domain.to_ascii_lowercase();
+            match challenge {
+                Some(key) => challenges.insert(domain, Arc::new(key)),
+                None => challenges.remove(&domain),
+            };
+        }
+    }
+
+    // 创建 HTTP/3 监听使用的 QUIC 配置，与 TCP 监听共享证书解析器，证书热替换同样生效
+    pub fn quic_server_config(&self) -> Result<quinn::ServerConfig> {
+        let builder = RustlsServerConfig::builder_with_provider(self.provider.clone())
+            .with_protocol_versions(&[&TLS13])
+            .map_err(|e| ServerError::Tls(format!("Failed to configure QUIC TLS versions: {}", e)))?;
+        // HTTP/3 监听同样要求客户端证书，避免绕过 mTLS
+        let builder = match &self.client_verifier {
+            Some(verifier) => builder.with_client_cert_verifier(verifier.clone()),
+            None => builder.with_no_client_auth(),
+        };
+        let mut server_config = builder.with_cert_resolver(self.resolver.clone());
+        server_config.alpn_protocols = vec![HTTP3_ALPN_PROTOCOL.to_vec()];
+
+        let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(server_config)
+            .map_err(|e| ServerError::Tls(format!("Failed to create QUIC TLS configuration: {}", e)))?;
+        Ok(quinn::ServerConfig::with_crypto(Arc::new(crypto)))
+    }
+
+    // 创建 DoT 监听使用的 TLS 接收器，与本上下文共享证书解析器，证书热替换同样生效
+    pub fn dot_acceptor(&self) -> Result<TlsAcceptor> {
+        let builder = RustlsServerConfig::builder_with_provider(self.provider.clone())
+            .with_protocol_versions(self.versions)
+            .map_err(|e| ServerError::Tls(format!("Failed to configure DoT TLS versions: {}", e)))?;
+        let builder = match &self.client_verifier {
+            Some(verifier) => builder.with_client_cert_verifier(verifier.clone()),
+            None => builder.with_no_client_auth(),
+        };
+        let mut server_config = builder.with_cert_resolver(self.resolver.clone());
+        server_config.alpn_protocols = vec![DOT_ALPN_PROTOCOL.to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(server_config)))
+    }
+
+    // 当前证书的到期时间（Unix 时间戳），临时证书返回 None
+    pub fn certificate_not_after(&self) -> Option<i64> {
+        let not_after = self.not_after.load(Ordering::Relaxed);
+        (not_after > 0).then_some(not_after)
+    }
+
+    // 当前使用的终端实体证书
+    pub fn current_certificate(&self) -> Option<CertificateDer<'static>> {
+        self.resolver.current.read().ok()
+            .and_then(|key| key.cert.first().map(|cert| cert.clone().into_owned()))
+    }
+
+    // 替换当前证书并更新到期时间
+    fn swap_certificate(&self, key: CertifiedKey) -> Result<()> {
+        let not_after = certificate_not_after(&key).unwrap_or_default();
+
+        let mut current = self.resolver.current.write()
+            .map_err(|_| ServerError::Tls("TLS certificate lock poisoned".to_string()))?;
+        *current = Arc::new(key);
+
+        self.not_after.store(not_after, Ordering::Relaxed);
+        METRICS.tls_certificate_expiry_timestamp_seconds().set(not_after);
+        Ok(())
+    }
+}
+
+// 先写入临时文件再重命名，避免进程中断时留下不完整的文件
+fn write_file_atomically(path: &std::path::Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+// 在 TLS 监听上提供 Axum 应用，启用 PROXY 协议时在 TLS 握手之前读取 PROXY 头
+pub async fn serve_tls(
+    listener: TcpListener,
+    app: Router,
+    tls: Arc<TlsContext>,
+    limits: ConnectionLimits,
+    proxy_protocol: Option<Arc<ProxyProtocol>>,
+) -> io::Result<()> {
+    loop {
+        let (stream, remote_addr) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                // 接受连接失败（如文件描述符耗尽）时短暂等待后重试
+                warn!("Failed to accept TCP connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let app = app.clone();
+        let acceptor = tls.acceptor.clone();
+        let proxy_protocol = proxy_protocol.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_tls_connection(stream, remote_addr, app, acceptor, limits, proxy_protocol).await {
+                debug!(client_ip = %remote_addr.ip(), "TLS connection closed with error: {}", e);
+            }
+        });
+    }
+}
+
+// 处理单个 TLS 连接
+async fn serve_tls_connection(
+    mut stream: TcpStream,
+    remote_addr: SocketAddr,
+    app: Router,
+    acceptor: TlsAcceptor,
+    limits: ConnectionLimits,
+    proxy_protocol: Option<Arc<ProxyProtocol>>,
+) -> io::Result<()> {
+    let remote_addr = client_addr(proxy_protocol.as_deref(), &mut stream, remote_addr).await?;
+    let handshake_timeout = Duration::from_secs(TLS_HANDSHAKE_TIMEOUT_SECS);
+
+    // 检查首字节，明文 HTTP 请求直接返回 400
+    let mut first_byte = [0u8; 1];
+    let peeked = timeout(handshake_timeout, stream.peek(&mut first_byte)).await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "timed out waiting for TLS ClientHello"))??;
+    if peeked == 0 {
+        return Ok(());
+    }
+    if first_byte[0] != TLS_HANDSHAKE_RECORD_TYPE {
+        debug!(client_ip = %remote_addr.ip(), "Rejected plain HTTP request on TLS listener");
+        // 先读取已到达的请求数据，避免带着未读数据关闭连接导致客户端收到 RST
+        let mut discard = [0u8; 4096];
+        let _ = stream.read(&mut discard).await;
+        stream.write_all(PLAIN_HTTP_REJECT_RESPONSE).await?;
+        return stream.shutdown().await;
+    }
+
+    let mut tls_stream = timeout(handshake_timeout, acceptor.accept(stream)).await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"))?
+        .inspect_err(|e| {
+            if is_client_auth_failure(e) {
+                METRICS.tls_client_auth_failures_total().inc();
+                info!(client_ip = %remote_addr.ip(), error = %e, "Rejected TLS client certificate");
+            }
+        })?;
+
+    // ACME TLS-ALPN-01 验证连接在握手完成后即关闭
+    if tls_stream.get_ref().1.alpn_protocol() == Some(ACME_TLS_ALPN_PROTOCOL) {
+        debug!(client_ip = %remote_addr.ip(), "Completed ACME TLS-ALPN-01 validation handshake");
+        return tls_stream.shutdown().await;
+    }
+
+    // 通过验证的客户端证书在该连接的所有请求中可用，并附加到日志上下文
+    let client_cert = tls_stream.get_ref().1.peer_certificates()
+        .and_then(|certs| certs.first())
+        .and_then(ClientCertificate::from_der);
+    let span = match &client_cert {
+        Some(cert) => {
+            debug!(client_ip = %remote_addr.ip(), subject = %cert.subject, "TLS client certificate verified");
+            info_span!("tls_client", client_cert = %cert.subject)
+        },
+        None => Span::none(),
+    };
+
+    // 注入客户端地址，使 ConnectInfo 提取器与明文监听保持一致；HTTP/2 由 ALPN 协商
+    serve_connection(tls_stream, limits, true, move |mut req: Request<Incoming>| {
+        req.extensions_mut().insert(ConnectInfo(remote_addr));
+        if let Some(cert) = &client_cert {
+            req.extensions_mut().insert(cert.clone());
+        }
+        app.clone().call(req).instrument(span.clone())
+    }).await
+}
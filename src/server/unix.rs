@@ -0,0 +1,92 @@
+// src/server/unix.rs
+
+// 该模块提供 Unix 域套接字上的 DoH 监听，供同一主机上的本地存根解析器或 sidecar 代理使用。
+//
+// 套接字连接没有客户端 IP，请求中注入固定的本地回环地址作为 ConnectInfo，
+// 使客户端 IP 提取与速率限制（启用时所有连接共享同一个限速键）保持与 TCP 监听一致。
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::{chown, FileTypeExt, PermissionsExt};
+use std::path::Path;
+use std::time::Duration;
+use axum::{extract::ConnectInfo, Router};
+use hyper::body::Incoming;
+use hyper::Request;
+use tokio::net::{UnixListener, UnixStream};
+use tower::Service;
+use tracing::{debug, info, warn};
+use crate::common::consts::unix_socket_peer_addr;
+use crate::server::config::UnixSocketConfig;
+use crate::server::error::{Result, ServerError};
+use crate::server::http_conn::{serve_connection, ConnectionLimits};
+
+// 绑定 Unix 域套接字：移除遗留的套接字文件，并设置权限与所有者
+pub fn bind_unix(path: &Path, config: &UnixSocketConfig) -> Result<UnixListener> {
+    remove_stale_socket(path)?;
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let listener = UnixListener::bind(path).map_err(|e| {
+        ServerError::Config(format!("Failed to bind unix socket {}: {}", path.display(), e))
+    })?;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(config.parsed_mode()?))?;
+    if config.owner.is_some() || config.group.is_some() {
+        chown(path, config.owner, config.group).map_err(|e| {
+            ServerError::Config(format!("Failed to change owner of unix socket {}: {}", path.display(), e))
+        })?;
+    }
+
+    Ok(listener)
+}
+
+// 删除上次运行遗留的套接字文件；路径上存在的不是套接字时拒绝覆盖
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            info!(path = %path.display(), "Removing stale unix socket");
+            fs::remove_file(path)?;
+            Ok(())
+        },
+        Ok(_) => Err(ServerError::Config(format!(
+            "listen_unix path {} exists and is not a socket",
+            path.display()
+        ))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// 在 Unix 域套接字上提供 Axum 应用
+pub async fn serve_unix(listener: UnixListener, app: Router, limits: ConnectionLimits) -> io::Result<()> {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("Failed to accept unix socket connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let app = app.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_unix_connection(stream, app, limits).await {
+                debug!("Unix socket connection closed with error: {}", e);
+            }
+        });
+    }
+}
+
+// 处理单个 Unix 域套接字连接
+async fn serve_unix_connection(stream: UnixStream, app: Router, limits: ConnectionLimits) -> io::Result<()> {
+    // 注入固定的本地地址，使 ConnectInfo 提取器与 TCP 监听保持一致
+    let peer_addr = unix_socket_peer_addr();
+    serve_connection(stream, limits, true, move |mut req: Request<Incoming>| {
+        req.extensions_mut().insert(ConnectInfo(peer_addr));
+        app.clone().call(req)
+    }).await
+}
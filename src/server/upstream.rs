@@ -1,27 +1,57 @@
 // src/server/upstream.rs
 
-use std::collections::HashMap;
-use std::net::{SocketAddr, IpAddr};
-use std::sync::Arc;
-
-use reqwest::{Client, header};
-use tracing::{debug, info};
-use hickory_resolver::TokioAsyncResolver;
-use hickory_resolver::proto::op::{Message, MessageType, OpCode, ResponseCode};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+
+use reqwest::{Client, StatusCode, header};
+use tracing::{debug, info, warn};
+use hickory_resolver::AsyncResolver;
+use hickory_resolver::name_server::{GenericConnector, RuntimeProvider, TokioHandle, TokioRuntimeProvider};
+use hickory_resolver::proto::TokioTime;
+use hickory_resolver::proto::iocompat::AsyncIoTokioAsStd;
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::proto::op::{Edns, Message, MessageType, OpCode, Query, ResponseCode};
+use hickory_resolver::proto::rr::{Name, RData, RecordType};
+use hickory_resolver::proto::rr::rdata::SRV;
+use hickory_resolver::proto::rr::rdata::opt::{EdnsCode, EdnsOption};
 use hickory_resolver::config::{
     NameServerConfig, Protocol, ResolverConfig, ResolverOpts,
 };
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{watch, Semaphore};
 use tokio::time::Instant;
 
-use crate::server::config::{ServerConfig, UpstreamConfig, ResolverProtocol};
+use crate::server::config::{self, PoolConfig, ServerConfig, UpstreamConfig, UpstreamMode, ResolverProtocol};
+use crate::server::{build_http_client, create_upstream_http_client, upstream_http_client_builder};
 use crate::server::error::{Result, ServerError};
 use crate::server::ecs::{EcsProcessor, EcsData};
-use crate::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+use crate::common::consts::{
+    CONTENT_TYPE_DNS_MESSAGE, EDNS_QUERY_PADDING_BLOCK_SIZE, DEFAULT_HTTP_CLIENT_RETRY_BACKOFF_MS,
+    SRV_DISCOVERED_DOH_PATH, MIN_FAILURE_RATIO_SAMPLES, EDNS_TCP_KEEPALIVE_OPTION_CODE,
+    CONSUL_REQUEST_TIMEOUT_SECS,
+};
+use crate::server::consul;
+use crate::server::mdns::MdnsForwarder;
 use crate::server::metrics::METRICS;
+use crate::server::request_id::current_request_id;
+use crate::server::padding::pad_message;
+use crate::server::record_dedup::remove_duplicate_records;
+use crate::server::recursive::RecursiveResolver;
+use crate::server::query_log;
+use crate::server::response_validation::{validate_doh_origin, validate_response};
 
 // Metrics 标签常量
 const DNS_QUERY_DESTINATION_UPSTREAM: &str = "sent_to_upstream";
 const UPSTREAM_PROTOCOL_DOH: &str = "DoH";
+const UPSTREAM_PROTOCOL_DOT: &str = "DoT";
+const UPSTREAM_PROTOCOL_TCP: &str = "TCP";
+const UPSTREAM_PROTOCOL_RECURSIVE: &str = "Recursive";
+const RECURSIVE_RESOLVER_ID: &str = "recursive";
 const UPSTREAM_FAILURE_REASON_ERROR: &str = "error";
 const DNSSEC_VALIDATION_SUCCESS: &str = "success";
 const DNSSEC_VALIDATION_FAILURE: &str = "failure";
@@ -29,87 +59,910 @@ const DNSSEC_VALIDATION_FAILURE: &str = "failure";
 // ECS 处理结果标签常量
 const ECS_PROCESSED_DETECTED: &str = "processed";
 
+// 上游组健康评分：延迟 EWMA 的平滑系数与延迟下限（秒），下限避免极小延迟使评分失真
+const GROUP_LATENCY_EWMA_ALPHA: f64 = 0.3;
+const MIN_GROUP_LATENCY_SECS: f64 = 0.001;
+
 // 上游选择
 #[derive(Debug, Clone)]
 pub enum UpstreamSelection {
     // 使用特定上游组
     Group(String),
+    // 按顺序使用多个上游组，仅在传输失败时回退到下一组
+    Groups(Vec<String>),
     // 使用全局默认上游
     Global,
 }
 
-// DoH查询客户端
-struct DoHClient {
+// 查询可使用的上游范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UpstreamScope {
+    // 静态配置与 SRV 发现的全部上游
+    All,
+    // 仅静态配置的上游，用于 SRV 发现的引导查询
+    Bootstrap,
+}
+
+// DoH 上游按状态码重试的策略
+#[derive(Debug, Clone)]
+struct DoHRetryPolicy {
+    // 触发重试的 HTTP 状态码
+    statuses: Vec<u16>,
+    // 最大重试次数
+    max_retries: u32,
+    // 单次查询（含重试与退避）的总时间上限
+    query_timeout: Duration,
+}
+
+impl DoHRetryPolicy {
+    // 计算下一次重试前的退避时间，超出重试次数、状态码不匹配或超过总时间上限时返回 None
+    fn backoff(&self, status: StatusCode, headers: &header::HeaderMap, attempt: u32, started: Instant) -> Option<Duration> {
+        if attempt >= self.max_retries || !self.statuses.contains(&status.as_u16()) {
+            return None;
+        }
+        
+        // 优先遵循上游的 Retry-After（秒），否则指数退避
+        let backoff = headers.get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| Duration::from_millis(DEFAULT_HTTP_CLIENT_RETRY_BACKOFF_MS << attempt.min(16)));
+        
+        (started.elapsed() + backoff < self.query_timeout).then_some(backoff)
+    }
+}
+
+// 上游失败率降级策略
+#[derive(Debug, Clone, Copy)]
+struct FailureRatioPolicy {
+    // 超过该失败率时标记为降级
+    threshold: f64,
+    // 低于该失败率时恢复
+    recovery_threshold: f64,
+    // 滑动窗口长度
+    window: Duration,
+}
+
+// 滑动窗口内每秒的查询计数
+#[derive(Debug, Clone, Copy)]
+struct FailureBucket {
+    second: u64,
+    total: u64,
+    failed: u64,
+}
+
+// 按秒聚合查询结果的滑动窗口
+struct FailureWindow {
+    // 窗口长度
+    window: Duration,
+    // 计时起点，用于计算每秒桶的序号
+    started: Instant,
+    // 按秒聚合的计数，最早的桶在前
+    buckets: Mutex<VecDeque<FailureBucket>>,
+}
+
+impl FailureWindow {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            started: Instant::now(),
+            buckets: Mutex::new(VecDeque::new()),
+        }
+    }
+    
+    // 移除窗口之外的桶，返回窗口内的 (总查询数, 失败数)
+    fn prune(&self, buckets: &mut VecDeque<FailureBucket>, now: u64) -> (u64, u64) {
+        let window = self.window.as_secs();
+        while buckets.front().is_some_and(|bucket| bucket.second + window <= now) {
+            buckets.pop_front();
+        }
+        buckets.iter().fold((0, 0), |(total, failed), bucket| (total + bucket.total, failed + bucket.failed))
+    }
+    
+    // 记录一次查询结果，返回窗口内的 (总查询数, 失败数)
+    fn record(&self, failed: bool) -> (u64, u64) {
+        let now = self.started.elapsed().as_secs();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        match buckets.back_mut() {
+            Some(bucket) if bucket.second == now => {
+                bucket.total += 1;
+                bucket.failed += u64::from(failed);
+            },
+            _ => buckets.push_back(FailureBucket { second: now, total: 1, failed: u64::from(failed) }),
+        }
+        self.prune(&mut buckets, now)
+    }
+    
+    // 窗口内的 (总查询数, 失败数)
+    fn counts(&self) -> (u64, u64) {
+        let now = self.started.elapsed().as_secs();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        self.prune(&mut buckets, now)
+    }
+    
+    // 不再有新的查询结果时，窗口内失败率降到 threshold 以下所需的时间（按最早的桶依次移出窗口估算）
+    fn time_until_ratio_below(&self, threshold: f64) -> Duration {
+        let now = self.started.elapsed().as_secs();
+        let window = self.window.as_secs();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut total, mut failed) = self.prune(&mut buckets, now);
+        let mut wait = 0;
+        for bucket in buckets.iter() {
+            if total == 0 || (failed as f64) < threshold * total as f64 {
+                break;
+            }
+            total -= bucket.total;
+            failed -= bucket.failed;
+            wait = bucket.second + window - now;
+        }
+        Duration::from_secs(wait)
+    }
+    
+    // 窗口内的失败率，没有样本时为 0
+    fn ratio(&self) -> f64 {
+        match self.counts() {
+            (0, _) => 0.0,
+            (total, failed) => failed as f64 / total as f64,
+        }
+    }
+}
+
+// 单个上游的滑动窗口失败率跟踪器
+struct FailureRatioTracker {
+    policy: FailureRatioPolicy,
+    // 窗口内的查询结果
+    window: FailureWindow,
+    // 是否已标记为降级
+    degraded: AtomicBool,
+}
+
+impl FailureRatioTracker {
+    fn new(policy: FailureRatioPolicy) -> Self {
+        Self {
+            policy,
+            window: FailureWindow::new(policy.window),
+            degraded: AtomicBool::new(false),
+        }
+    }
+    
+    // 记录一次查询结果，返回 (窗口内失败率, 降级状态是否发生变化)
+    fn record(&self, failed: bool) -> (f64, bool) {
+        let (total, failed_count) = self.window.record(failed);
+        let ratio = failed_count as f64 / total as f64;
+        let changed = if self.degraded.load(Ordering::Relaxed) {
+            ratio < self.policy.recovery_threshold && self.degraded.swap(false, Ordering::Relaxed)
+        } else {
+            total >= MIN_FAILURE_RATIO_SAMPLES
+                && ratio > self.policy.threshold
+                && !self.degraded.swap(true, Ordering::Relaxed)
+        };
+        (ratio, changed)
+    }
+    
+    // 检查降级状态：降级的上游不再被优先选择，也就不再产生新的查询结果，
+    // 因此在选择时按窗口内的失败率判断是否恢复。返回 (是否降级, 本次恢复时的失败率)
+    fn check(&self) -> (bool, Option<f64>) {
+        if !self.degraded.load(Ordering::Relaxed) {
+            return (false, None);
+        }
+        let ratio = self.window.ratio();
+        if ratio < self.policy.recovery_threshold && self.degraded.swap(false, Ordering::Relaxed) {
+            return (false, Some(ratio));
+        }
+        (self.degraded.load(Ordering::Relaxed), None)
+    }
+    
+    // 降级状态下预计恢复所需的时间，未降级时为 None
+    fn recovery_time(&self) -> Option<Duration> {
+        self.degraded.load(Ordering::Relaxed)
+            .then(|| self.window.time_until_ratio_below(self.policy.recovery_threshold))
+    }
+}
+
+// 上游组健康状态：滑动窗口内的失败率与查询延迟的 EWMA
+struct GroupHealth {
+    // 窗口内的查询结果
+    failures: FailureWindow,
+    // 查询延迟（秒）的指数加权移动平均，尚无样本时为 None
+    ewma_latency: Mutex<Option<f64>>,
+}
+
+impl GroupHealth {
+    fn new(window: Duration) -> Self {
+        Self {
+            failures: FailureWindow::new(window),
+            ewma_latency: Mutex::new(None),
+        }
+    }
+    
+    // 记录一次查询的延迟与结果；失败的查询（如连接被拒绝）往往返回很快，不计入延迟
+    fn record(&self, latency: Duration, failed: bool) {
+        self.failures.record(failed);
+        if failed {
+            return;
+        }
+        let latency = latency.as_secs_f64();
+        let mut ewma = self.ewma_latency.lock().unwrap_or_else(|e| e.into_inner());
+        *ewma = Some(match *ewma {
+            Some(previous) => GROUP_LATENCY_EWMA_ALPHA * latency + (1.0 - GROUP_LATENCY_EWMA_ALPHA) * previous,
+            None => latency,
+        });
+    }
+    
+    // 健康评分 (1 - 失败率) / 延迟 EWMA，越高越好；尚未查询过的组评分为无穷大，以便优先获得样本
+    fn score(&self) -> f64 {
+        let Some(latency) = *self.ewma_latency.lock().unwrap_or_else(|e| e.into_inner()) else {
+            return f64::INFINITY;
+        };
+        (1.0 - self.failures.ratio()) / latency.max(MIN_GROUP_LATENCY_SECS)
+    }
+}
+
+// 上游传输返回的 Future
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<Message>> + Send + 'a>>;
+
+// 上游传输：将查询发送到指定上游并返回响应
+// 上游不可达时应返回 ServerError::UpstreamUnavailable，上游组回退与失败率统计依赖该约定。
+// 默认使用真实的 DoH 与 UDP/TCP/DoT 传输，可通过 UpstreamManager::with_transport 注入替身
+pub trait UpstreamTransport: Send + Sync {
+    // upstream 为 DoH 上游的 URL，或非 DoH 上游组中第一个解析器的地址
+    fn exchange<'a>(&'a self, upstream: &'a str, query: &'a Message) -> TransportFuture<'a>;
+}
+
+// DoH 传输：组内所有 DoH 上游共享 HTTP 客户端与连接数限制
+#[derive(Clone)]
+struct DoHTransport {
     // HTTP客户端
     client: Client,
-    // DoH服务器URL
+    // 请求 ID 头名称（启用请求 ID 透传时设置）
+    request_id_header: Option<String>,
+    // 是否对查询进行 EDNS 填充
+    edns_padding: bool,
+    // 按状态码重试的策略
+    retry: DoHRetryPolicy,
+    // 上游组的并发连接数限制
+    connection_limit: Option<Arc<Semaphore>>,
+    // 是否校验响应来自配置的服务器
+    validate_origin: bool,
+}
+
+impl UpstreamTransport for DoHTransport {
+    fn exchange<'a>(&'a self, upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+        Box::pin(self.query(upstream, query))
+    }
+}
+
+// UDP/TCP/DoT 传输：由 hickory-resolver 在组内的解析器之间选择
+struct ResolverTransport {
+    resolver: AsyncResolver<GenericConnector<BoundRuntimeProvider>>,
+}
+
+// 在 Tokio 运行时之上将 UDP/TCP 套接字绑定到指定源地址，未指定时由系统选择
+#[derive(Clone)]
+struct BoundRuntimeProvider {
+    inner: TokioRuntimeProvider,
+    bind_addr: Option<IpAddr>,
+}
+
+impl RuntimeProvider for BoundRuntimeProvider {
+    type Handle = TokioHandle;
+    type Timer = TokioTime;
+    type Udp = tokio::net::UdpSocket;
+    type Tcp = AsyncIoTokioAsStd<tokio::net::TcpStream>;
+
+    fn create_handle(&self) -> Self::Handle {
+        self.inner.create_handle()
+    }
+
+    fn connect_tcp(
+        &self,
+        server_addr: SocketAddr,
+    ) -> Pin<Box<dyn Send + Future<Output = std::io::Result<Self::Tcp>>>> {
+        let Some(ip) = self.bind_addr else {
+            return self.inner.connect_tcp(server_addr);
+        };
+        Box::pin(async move {
+            let socket = match server_addr {
+                SocketAddr::V4(_) => tokio::net::TcpSocket::new_v4()?,
+                SocketAddr::V6(_) => tokio::net::TcpSocket::new_v6()?,
+            };
+            socket.bind(SocketAddr::new(ip, 0))?;
+            socket.connect(server_addr).await.map(AsyncIoTokioAsStd)
+        })
+    }
+
+    fn bind_udp(
+        &self,
+        local_addr: SocketAddr,
+        server_addr: SocketAddr,
+    ) -> Pin<Box<dyn Send + Future<Output = std::io::Result<Self::Udp>>>> {
+        // 保留 hickory 选择的随机源端口，仅替换源 IP
+        let local_addr = self.bind_addr
+            .map_or(local_addr, |ip| SocketAddr::new(ip, local_addr.port()));
+        self.inner.bind_udp(local_addr, server_addr)
+    }
+}
+
+impl UpstreamTransport for ResolverTransport {
+    fn exchange<'a>(&'a self, _upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+        Box::pin(self.query(query))
+    }
+}
+
+impl ResolverTransport {
+    // 使用 lookup 执行查询并构建响应消息
+    async fn query(&self, query_message: &Message) -> Result<Message> {
+        let query = query_message.queries().first().ok_or_else(|| 
+            ServerError::Upstream("No query in message".to_string())
+        )?;
+        
+        let lookup = match self.resolver.lookup(query.name().clone(), query.query_type()).await {
+            Ok(lookup) => lookup,
+            // 无记录属于正常的解析结果，其余错误视为上游不可达
+            Err(e) => return Err(match e.kind() {
+                ResolveErrorKind::NoRecordsFound { .. } => {
+                    ServerError::Upstream(format!("DNS query failed: {}", e))
+                },
+                _ => ServerError::UpstreamUnavailable(format!("DNS query failed: {}", e)),
+            }),
+        };
+        
+        // 构建DNS响应消息
+        let mut message = Message::new();
+        message.set_id(query_message.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(query_message.op_code())
+            .set_response_code(ResponseCode::NoError)
+            .set_recursion_desired(query_message.recursion_desired())
+            .set_recursion_available(true);
+        
+        // 添加原始查询
+        for q in query_message.queries() {
+            message.add_query(q.clone());
+        }
+        
+        // 添加记录
+        for record in lookup.record_iter() {
+            message.add_answer(record.clone());
+        }
+        
+        Ok(message)
+    }
+}
+
+// 出示客户端证书或信任额外 CA 的 DoT 传输（hickory-resolver 的 DoT 连接不支持这些设置）
+//
+// 每个查询使用独立的 TLS 连接
+struct MutualTlsDotTransport {
+    connector: tokio_native_tls::TlsConnector,
+    // TLS 服务器名称
+    server_name: String,
+    // 上游地址
+    addr: SocketAddr,
+    // 源地址（未配置时由系统选择）
+    bind_addr: Option<IpAddr>,
+    // 单次查询超时
+    query_timeout: Duration,
+}
+
+impl UpstreamTransport for MutualTlsDotTransport {
+    fn exchange<'a>(&'a self, _upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+        Box::pin(self.query(query))
+    }
+}
+
+impl MutualTlsDotTransport {
+    fn new(
+        resolver: &config::ResolverConfig,
+        identity: Option<(Vec<u8>, Vec<u8>)>,
+        ca_cert: Option<Vec<u8>>,
+        upstream_config: &UpstreamConfig,
+    ) -> Result<Self> {
+        let (server_name, addr) = resolver.parse_socket_addr()?;
+        let mut builder = native_tls::TlsConnector::builder();
+        if let Some((cert, key)) = identity {
+            builder.identity(native_tls::Identity::from_pkcs8(&cert, &key).map_err(|e| ServerError::Tls(format!(
+                "Invalid client certificate for resolver {}: {}", resolver.address, e
+            )))?);
+        }
+        if let Some(ca_cert) = ca_cert {
+            builder.add_root_certificate(native_tls::Certificate::from_pem(&ca_cert).map_err(|e| ServerError::Tls(format!(
+                "Invalid CA certificate for resolver {}: {}", resolver.address, e
+            )))?);
+        }
+        let connector = builder.build().map_err(|e| ServerError::Tls(format!(
+            "Failed to create DoT client for resolver {}: {}", resolver.address, e
+        )))?;
+        
+        Ok(Self {
+            connector: connector.into(),
+            server_name: server_name.unwrap_or_default(),
+            addr,
+            bind_addr: upstream_config.bind_addr,
+            query_timeout: Duration::from_secs(upstream_config.query_timeout),
+        })
+    }
+    
+    // 执行DoT查询
+    async fn query(&self, dns_message: &Message) -> Result<Message> {
+        let dns_wire = dns_message.to_vec()?;
+        let response_bytes = tokio::time::timeout(self.query_timeout, self.exchange_wire(&dns_wire))
+            .await
+            .map_err(|_| ServerError::UpstreamUnavailable(format!("DoT query to {} timed out", self.addr)))?
+            .map_err(|e| ServerError::UpstreamUnavailable(format!("DoT query to {} failed: {}", self.addr, e)))?;
+        
+        Message::from_vec(&response_bytes).map_err(|e| {
+            debug!(upstream = %self.addr, body_len = response_bytes.len(), error = %e, "Failed to parse DoT upstream response");
+            ServerError::UpstreamParse(format!("Failed to parse DNS response: {}", e))
+        })
+    }
+    
+    // 建立 TLS 连接，发送带长度前缀的查询并读取响应
+    async fn exchange_wire(&self, dns_wire: &[u8]) -> std::io::Result<Vec<u8>> {
+        let stream = connect_tcp(self.addr, self.bind_addr).await?;
+        let mut stream = self.connector.connect(&self.server_name, stream).await
+            .map_err(std::io::Error::other)?;
+        exchange_framed(&mut stream, dns_wire).await
+    }
+}
+
+// 连接上游 TCP 端口，配置了源地址时先绑定
+pub(crate) async fn connect_tcp(addr: SocketAddr, bind_addr: Option<IpAddr>) -> std::io::Result<tokio::net::TcpStream> {
+    let socket = match addr {
+        SocketAddr::V4(_) => tokio::net::TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => tokio::net::TcpSocket::new_v6()?,
+    };
+    if let Some(ip) = bind_addr {
+        socket.bind(SocketAddr::new(ip, 0))?;
+    }
+    socket.connect(addr).await
+}
+
+// 在流上发送带两字节长度前缀的查询并读取响应
+pub(crate) async fn exchange_framed<S: AsyncRead + AsyncWrite + Unpin + ?Sized>(stream: &mut S, dns_wire: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut framed = Vec::with_capacity(dns_wire.len() + 2);
+    framed.extend_from_slice(&(dns_wire.len() as u16).to_be_bytes());
+    framed.extend_from_slice(dns_wire);
+    stream.write_all(&framed).await?;
+    
+    let len = stream.read_u16().await?;
+    let mut response = vec![0u8; len as usize];
+    stream.read_exact(&mut response).await?;
+    Ok(response)
+}
+
+// 可放回连接池的上游连接（TCP 或 TLS）
+trait UpstreamStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> UpstreamStream for T {}
+
+// 连接池中的空闲连接
+struct IdleStream {
+    stream: Box<dyn UpstreamStream>,
+    // 超过该时间后不再复用
+    expires_at: Instant,
+}
+
+// 复用连接的 TCP/DoT 传输（upstream.tcp_keepalive）
+//
+// 查询携带 EDNS TCP Keepalive 选项（RFC 7828），每个连接同时只承载一个查询，响应后放回连接池。
+// 空闲连接的保持时间使用上游在响应中通告的超时，未通告时使用连接池的 idle_timeout，通告为 0 时关闭连接。
+// 复用的连接可能已被上游关闭，此时改用新连接重试一次。
+struct KeepaliveStreamTransport {
+    // TLS 连接器与服务器名称，TCP 上游为 None
+    tls: Option<(tokio_native_tls::TlsConnector, String)>,
+    // 上游地址
+    addr: SocketAddr,
+    // 源地址（未配置时由系统选择）
+    bind_addr: Option<IpAddr>,
+    // 单次查询超时
+    query_timeout: Duration,
+    // 上游未通告超时时的空闲保持时间
+    idle_timeout: Duration,
+    // 最多保留的空闲连接数
+    max_idle_connections: usize,
+    idle: Mutex<Vec<IdleStream>>,
+}
+
+impl UpstreamTransport for KeepaliveStreamTransport {
+    fn exchange<'a>(&'a self, _upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+        Box::pin(self.query(query))
+    }
+}
+
+impl KeepaliveStreamTransport {
+    fn new(
+        resolver: &config::ResolverConfig,
+        identity: Option<(Vec<u8>, Vec<u8>)>,
+        ca_cert: Option<Vec<u8>>,
+        upstream_config: &UpstreamConfig,
+        pool: &PoolConfig,
+    ) -> Result<Self> {
+        let (server_name, addr) = resolver.parse_socket_addr()?;
+        let tls = match resolver.protocol {
+            ResolverProtocol::Dot => {
+                let mut builder = native_tls::TlsConnector::builder();
+                if let Some((cert, key)) = identity {
+                    builder.identity(native_tls::Identity::from_pkcs8(&cert, &key).map_err(|e| ServerError::Tls(format!(
+                        "Invalid client certificate for resolver {}: {}", resolver.address, e
+                    )))?);
+                }
+                if let Some(ca_cert) = ca_cert {
+                    builder.add_root_certificate(native_tls::Certificate::from_pem(&ca_cert).map_err(|e| ServerError::Tls(format!(
+                        "Invalid CA certificate for resolver {}: {}", resolver.address, e
+                    )))?);
+                }
+                let connector = builder.build().map_err(|e| ServerError::Tls(format!(
+                    "Failed to create DoT client for resolver {}: {}", resolver.address, e
+                )))?;
+                Some((connector.into(), server_name.unwrap_or_default()))
+            },
+            _ => None,
+        };
+        
+        Ok(Self {
+            tls,
+            addr,
+            bind_addr: upstream_config.bind_addr,
+            query_timeout: Duration::from_secs(upstream_config.query_timeout),
+            idle_timeout: Duration::from_secs(pool.idle_timeout),
+            max_idle_connections: pool.max_idle_connections as usize,
+            idle: Mutex::new(Vec::new()),
+        })
+    }
+    
+    // 发送带 Keepalive 选项的查询，按上游通告的超时放回连接
+    async fn query(&self, dns_message: &Message) -> Result<Message> {
+        let mut query = dns_message.clone();
+        query.extensions_mut().get_or_insert_with(Edns::new).options_mut()
+            .insert(EdnsOption::Unknown(EDNS_TCP_KEEPALIVE_OPTION_CODE, Vec::new()));
+        let dns_wire = query.to_vec()?;
+        
+        let (response_bytes, stream) = tokio::time::timeout(self.query_timeout, self.exchange_wire(&dns_wire))
+            .await
+            .map_err(|_| ServerError::UpstreamUnavailable(format!("Query to {} timed out", self.addr)))?
+            .map_err(|e| ServerError::UpstreamUnavailable(format!("Query to {} failed: {}", self.addr, e)))?;
+        
+        let mut response = Message::from_vec(&response_bytes).map_err(|e| {
+            debug!(upstream = %self.addr, body_len = response_bytes.len(), error = %e, "Failed to parse TCP upstream response");
+            ServerError::UpstreamParse(format!("Failed to parse DNS response: {}", e))
+        })?;
+        
+        // Keepalive 选项只作用于当前连接，不转发给客户端
+        let keepalive_code = EdnsCode::from(EDNS_TCP_KEEPALIVE_OPTION_CODE);
+        let advertised = response.extensions().as_ref()
+            .and_then(|edns| edns.option(keepalive_code))
+            .and_then(|option| match option {
+                EdnsOption::Unknown(_, data) if data.len() == 2 => {
+                    Some(Duration::from_millis(u64::from(u16::from_be_bytes([data[0], data[1]])) * 100))
+                },
+                _ => None,
+            });
+        if let Some(edns) = response.extensions_mut() {
+            edns.options_mut().remove(keepalive_code);
+        }
+        
+        let idle_timeout = advertised.unwrap_or(self.idle_timeout);
+        debug!(upstream = %self.addr, advertised_ms = advertised.map(|d| d.as_millis() as u64), idle_timeout_ms = idle_timeout.as_millis() as u64, "Returning upstream connection to pool");
+        self.release(stream, idle_timeout);
+        Ok(response)
+    }
+    
+    // 优先使用空闲连接交换查询，返回响应与可复用的连接
+    async fn exchange_wire(&self, dns_wire: &[u8]) -> std::io::Result<(Vec<u8>, Box<dyn UpstreamStream>)> {
+        if let Some(mut stream) = self.take_idle() {
+            match exchange_framed(&mut stream, dns_wire).await {
+                Ok(response) => return Ok((response, stream)),
+                Err(e) => debug!(upstream = %self.addr, error = %e, "Pooled upstream connection failed, reconnecting"),
+            }
+        }
+        let mut stream = self.connect().await?;
+        let response = exchange_framed(&mut stream, dns_wire).await?;
+        Ok((response, stream))
+    }
+    
+    async fn connect(&self) -> std::io::Result<Box<dyn UpstreamStream>> {
+        let stream = connect_tcp(self.addr, self.bind_addr).await?;
+        Ok(match &self.tls {
+            Some((connector, server_name)) => Box::new(connector.connect(server_name, stream).await
+                .map_err(std::io::Error::other)?),
+            None => Box::new(stream),
+        })
+    }
+    
+    // 取出最近放回且未过期的空闲连接，同时丢弃已过期的连接
+    fn take_idle(&self) -> Option<Box<dyn UpstreamStream>> {
+        let now = Instant::now();
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        idle.retain(|entry| entry.expires_at > now);
+        idle.pop().map(|entry| entry.stream)
+    }
+    
+    // 放回连接池；保持时间为 0 或连接池已满时关闭连接
+    fn release(&self, stream: Box<dyn UpstreamStream>, idle_timeout: Duration) {
+        if idle_timeout.is_zero() {
+            return;
+        }
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        if idle.len() < self.max_idle_connections {
+            idle.push(IdleStream { stream, expires_at: Instant::now() + idle_timeout });
+        }
+    }
+}
+
+// 按地址逐个尝试的上游客户端：DoH 上游，以及需要专用 TLS 设置的 DoT 上游
+struct DoHClient {
+    // DoH服务器URL（DoT 上游为配置的地址）
     url: String,
+    // 指标中的协议标签
+    protocol: &'static str,
+    // 发送查询的传输
+    transport: Arc<dyn UpstreamTransport>,
+    // 失败率跟踪器（配置了 failure_ratio_threshold 时）
+    failure_ratio: Option<FailureRatioTracker>,
 }
 
 impl DoHClient {
     // 创建新的DoH客户端
-    fn new(url: String, client: Client) -> Self {
-        Self { client, url }
+    fn new(url: String, protocol: &'static str, transport: Arc<dyn UpstreamTransport>, options: &DoHClientOptions) -> Self {
+        Self {
+            url,
+            protocol,
+            transport,
+            failure_ratio: options.failure_ratio.map(FailureRatioTracker::new),
+        }
     }
     
     // 执行DoH查询
     async fn query(&self, dns_message: &Message) -> Result<Message> {
-        // 将DNS消息转换为二进制格式
-        let dns_wire = dns_message.to_vec()?;
+        self.transport.exchange(&self.url, dns_message).await
+    }
+    
+    // 是否已因失败率过高被标记为降级
+    fn is_degraded(&self, group_name: &str) -> bool {
+        let Some(tracker) = &self.failure_ratio else {
+            return false;
+        };
+        let (degraded, recovered_ratio) = tracker.check();
+        if let Some(ratio) = recovered_ratio {
+            METRICS.upstream_failure_ratio().with_label_values(&[&self.url, group_name]).set(ratio);
+            info!(
+                url = %self.url,
+                upstream_group = group_name,
+                failure_ratio = ratio,
+                "DoH upstream recovered from degraded state"
+            );
+        }
+        degraded
+    }
+    
+    // 降级状态下预计恢复所需的时间，未降级或未跟踪失败率时为 None
+    fn recovery_time(&self) -> Option<Duration> {
+        self.failure_ratio.as_ref().and_then(FailureRatioTracker::recovery_time)
+    }
+    
+    // 记录查询结果（传输错误或 SERVFAIL 视为失败），并在降级状态变化时输出日志
+    fn record_outcome(&self, failed: bool, group_name: &str) {
+        let Some(tracker) = &self.failure_ratio else {
+            return;
+        };
+        let (ratio, changed) = tracker.record(failed);
+        METRICS.upstream_failure_ratio().with_label_values(&[&self.url, group_name]).set(ratio);
+        if !changed {
+            return;
+        }
+        if tracker.degraded.load(Ordering::Relaxed) {
+            warn!(
+                url = %self.url,
+                upstream_group = group_name,
+                failure_ratio = ratio,
+                threshold = tracker.policy.threshold,
+                "DoH upstream failure ratio exceeded threshold, marking as degraded"
+            );
+        } else {
+            info!(
+                url = %self.url,
+                upstream_group = group_name,
+                failure_ratio = ratio,
+                "DoH upstream recovered from degraded state"
+            );
+        }
+    }
+    
+}
+
+impl DoHTransport {
+    // 执行DoH查询
+    async fn query(&self, url: &str, dns_message: &Message) -> Result<Message> {
+        // 将DNS消息转换为二进制格式，启用时填充到固定块大小
+        let dns_wire = if self.edns_padding {
+            let mut padded = dns_message.clone();
+            let padding_len = pad_message(&mut padded, EDNS_QUERY_PADDING_BLOCK_SIZE)?;
+            METRICS.upstream_edns_padding_bytes_total().inc_by(padding_len as u64);
+            padded.to_vec()?
+        } else {
+            dns_message.to_vec()?
+        };
         
         // 构建请求 - 提前创建内容类型变量避免重复创建
         let content_type = CONTENT_TYPE_DNS_MESSAGE;
+        let started = Instant::now();
+        let mut attempt = 0;
         
-        // 构建请求
-        let response = self.client
-            .post(&self.url)
-            .header(header::CONTENT_TYPE, content_type)
-            .header(header::ACCEPT, content_type)
-            .body(dns_wire)
-            .send()
-            .await
-            .map_err(|e| ServerError::Upstream(format!("DoH request failed: {}", e)))?;
-        
-        // 检查HTTP状态码
-        if !response.status().is_success() {
-            return Err(ServerError::Upstream(format!(
+        let (response, _permit) = loop {
+            // 达到上游组的连接数上限时等待，直到响应体读取完成才释放
+            let permit = match &self.connection_limit {
+                Some(limit) => Some(limit.clone().acquire_owned().await
+                    .map_err(|e| ServerError::UpstreamUnavailable(format!("DoH connection limit closed: {}", e)))?),
+                None => None,
+            };
+            
+            // 构建请求
+            let mut request = self.client
+                .post(url)
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::ACCEPT, content_type);
+            
+            // 透传当前请求 ID，便于跨服务追踪
+            if let (Some(header_name), Some(request_id)) = (&self.request_id_header, current_request_id()) {
+                request = request.header(header_name.as_str(), request_id);
+            }
+            
+            let response = request
+                .body(dns_wire.clone())
+                .send()
+                .await
+                .map_err(|e| ServerError::UpstreamUnavailable(if e.is_timeout() {
+                    format!("DoH request to {} timed out", url)
+                } else {
+                    format!("DoH request failed: {}", e)
+                }))?;
+            METRICS.upstream_http_version_total().with_label_values(&[http_version_label(response.version())]).inc();
+            
+            // 检查HTTP状态码
+            let status = response.status();
+            if status.is_success() {
+                break (response, permit);
+            }
+            drop(permit);
+            
+            // 上游暂时繁忙时在同一上游上退避重试，而不是立即判定失败
+            if let Some(backoff) = self.retry.backoff(status, response.headers(), attempt, started) {
+                attempt += 1;
+                debug!(
+                    url = %url,
+                    status = status.as_u16(),
+                    attempt,
+                    backoff_ms = backoff.as_millis() as u64,
+                    "DoH upstream returned retryable status, retrying after backoff"
+                );
+                tokio::time::sleep(backoff).await;
+                continue;
+            }
+            
+            return Err(ServerError::UpstreamUnavailable(format!(
                 "DoH server returned error status: {}", 
-                response.status()
+                status
             )));
+        };
+        
+        // 校验响应来源，防止重定向或劫持到其他服务器
+        if self.validate_origin {
+            let remote_ip = response.remote_addr().map(|addr| addr.ip());
+            validate_doh_origin(url, response.url(), remote_ip).map_err(ServerError::UpstreamValidation)?;
         }
         
-        // 验证内容类型
         let response_content_type = response.headers()
             .get(header::CONTENT_TYPE)
             .and_then(|h| h.to_str().ok())
-            .unwrap_or("");
-            
-        if response_content_type != content_type {
-            return Err(ServerError::Upstream(format!(
-                "DoH server returned invalid content type: {}", 
-                response_content_type
-            )));
-        }
+            .unwrap_or("")
+            .to_string();
         
         // 读取响应体
         let response_bytes = response.bytes()
             .await
             .map_err(|e| ServerError::Upstream(format!("Failed to read DoH response: {}", e)))?;
+        
+        // 验证内容类型：配置错误的 URL 常返回 HTML 错误页面，按解析失败处理
+        if response_content_type != content_type {
+            debug!(
+                url = %url,
+                content_type = %response_content_type,
+                body_len = response_bytes.len(),
+                "DoH upstream returned unexpected content type"
+            );
+            return Err(ServerError::UpstreamParse(format!(
+                "DoH server returned invalid content type: {}", 
+                response_content_type
+            )));
+        }
             
         // 解析DNS消息
-        Message::from_vec(&response_bytes)
-            .map_err(|e| ServerError::Upstream(format!("Failed to parse DNS response: {}", e)))
+        Message::from_vec(&response_bytes).map_err(|e| {
+            debug!(
+                url = %url,
+                content_type = %response_content_type,
+                body_len = response_bytes.len(),
+                error = %e,
+                "Failed to parse DoH upstream response"
+            );
+            ServerError::UpstreamParse(format!("Failed to parse DNS response: {}", e))
+        })
+    }
+}
+
+// 上游响应实际使用的 HTTP 版本标签
+fn http_version_label(version: reqwest::Version) -> &'static str {
+    match version {
+        reqwest::Version::HTTP_2 => "h2",
+        reqwest::Version::HTTP_3 => "h3",
+        _ => "h1",
+    }
+}
+
+// 创建 DoH 客户端所需的共享参数
+#[derive(Clone)]
+struct DoHClientOptions {
+    transport: Arc<dyn UpstreamTransport>,
+    failure_ratio: Option<FailureRatioPolicy>,
+}
+
+impl DoHClientOptions {
+    // 为指定 URL 创建 DoH 客户端
+    fn build(&self, url: String) -> Arc<DoHClient> {
+        Arc::new(DoHClient::new(url, UPSTREAM_PROTOCOL_DOH, self.transport.clone(), self))
+    }
+    
+    // 使用解析器专用的传输创建客户端
+    fn build_with_transport(&self, url: String, protocol: &'static str, transport: Arc<dyn UpstreamTransport>) -> Arc<DoHClient> {
+        Arc::new(DoHClient::new(url, protocol, transport, self))
     }
 }
 
 // 上游组解析配置
 struct UpstreamGroupConfig {
-    // 内部 TokioAsyncResolver
-    resolver: TokioAsyncResolver,
-    // DoH客户端
+    // UDP/TCP/DoT 上游的传输
+    resolver: Arc<dyn UpstreamTransport>,
+    // 静态配置的DoH客户端
     doh_clients: Vec<Arc<DoHClient>>,
+    // 通过 SRV 或 Consul 发现的 DoH 客户端（按选择顺序）
+    discovered_doh_clients: RwLock<Vec<Arc<DoHClient>>>,
+    // 创建 DoH 客户端的参数
+    doh_options: DoHClientOptions,
     // 上游配置 - 使用引用代替克隆整个配置
     config: Arc<UpstreamConfig>,
+    // 组健康状态，用于跨上游组负载均衡
+    health: GroupHealth,
+}
+
+impl UpstreamGroupConfig {
+    // 按尝试顺序列出 DoH 客户端：优先使用 SRV/Consul 发现的上游，静态配置的上游作为后备
+    // 发现的上游全部降级时先尝试静态上游；降级的上游排在未降级的上游之后，全部降级时仍按原顺序选择
+    fn ordered_doh_clients(&self, scope: UpstreamScope, group_name: &str) -> Vec<Arc<DoHClient>> {
+        let discovered = match scope {
+            UpstreamScope::All => self.discovered_doh_clients.read().unwrap_or_else(|e| e.into_inner()).clone(),
+            UpstreamScope::Bootstrap => Vec::new(),
+        };
+        let candidates = if discovered.is_empty() {
+            self.doh_clients.clone()
+        } else if !self.doh_clients.is_empty() && discovered.iter().all(|client| client.is_degraded(group_name)) {
+            self.doh_clients.iter().cloned().chain(discovered).collect()
+        } else {
+            discovered
+        };
+        
+        let (healthy, degraded): (Vec<_>, Vec<_>) = candidates.into_iter()
+            .partition(|client| !client.is_degraded(group_name));
+        healthy.into_iter().chain(degraded).collect()
+    }
+    
+    // 启用严格校验时检查上游响应与查询是否一致
+    fn validate(&self, query: &Message, response: Message) -> Result<Message> {
+        if self.config.strict_response_validation {
+            validate_response(query, &response).map_err(ServerError::UpstreamValidation)?;
+        }
+        Ok(response)
+    }
 }
 
 // 上游 DNS 解析管理器
@@ -120,13 +973,81 @@ pub struct UpstreamManager {
     group_configs: HashMap<String, UpstreamGroupConfig>,
     // 服务器配置（使用Arc代替完整clone）
     server_config: Arc<ServerConfig>,
+    // mDNS 转发器（启用 mdns_forwarder 时）
+    mdns: Option<MdnsForwarder>,
+    // 后台发现任务的停止信号，关闭时置为 true
+    stop: watch::Sender<bool>,
+}
+
+// 上游组使用的传输
+struct GroupTransports {
+    // 组内 DoH 上游共享的传输
+    doh: Arc<dyn UpstreamTransport>,
+    // UDP/TCP/DoT 上游的传输
+    resolver: Arc<dyn UpstreamTransport>,
+    // 配置了客户端证书或 CA 的解析器专用的传输（按解析器在配置中的序号）
+    dedicated: HashMap<usize, Arc<dyn UpstreamTransport>>,
+}
+
+impl GroupTransports {
+    // 所有上游共用同一个传输
+    fn shared(transport: Arc<dyn UpstreamTransport>) -> Self {
+        Self {
+            doh: transport.clone(),
+            resolver: transport,
+            dedicated: HashMap::new(),
+        }
+    }
+}
+
+// 上游组传输的来源
+enum TransportSource {
+    // 真实的 DoH 与 UDP/TCP/DoT 传输，全局上游使用指定的 HTTP 客户端
+    Default(Client),
+    // 所有上游组共用注入的传输
+    Injected(Arc<dyn UpstreamTransport>),
 }
 
 impl UpstreamManager {
     // 创建新的上游解析管理器
     pub async fn new(config: Arc<ServerConfig>, http_client: Client) -> Result<Self> {
+        Self::build(config, TransportSource::Default(http_client))
+    }
+    
+    // 创建通过指定传输发送所有上游查询的管理器，
+    // 用于在不启动真实上游的情况下测试回退、重试与降级行为
+    pub async fn with_transport(config: Arc<ServerConfig>, transport: Arc<dyn UpstreamTransport>) -> Result<Self> {
+        Self::build(config, TransportSource::Injected(transport))
+    }
+    
+    fn build(config: Arc<ServerConfig>, source: TransportSource) -> Result<Self> {
         // 创建全局上游配置，使用Arc引用避免clone
-        let global_config = Self::create_upstream_group_config(&config, Arc::new(config.dns.upstream.clone()), http_client.clone())?;
+        // 全局上游使用传入的客户端，连接池配置来自 http_client.pool
+        // 递归模式下全局上游从根服务器迭代解析，不使用 resolvers
+        let mut global_upstream = config.dns.upstream.clone();
+        let recursive = global_upstream.mode == UpstreamMode::Recursive;
+        if recursive {
+            global_upstream.resolvers.clear();
+        }
+        let global_upstream = Arc::new(global_upstream);
+        let transports = match &source {
+            TransportSource::Default(_) if recursive => {
+                info!(
+                    root_hints = global_upstream.root_hints.len(),
+                    dnssec_enabled = global_upstream.enable_dnssec,
+                    "Global upstream uses recursive resolution"
+                );
+                GroupTransports::shared(Arc::new(RecursiveResolver::new(&global_upstream)?))
+            },
+            TransportSource::Default(http_client) => Self::create_transports(
+                &config,
+                &global_upstream,
+                http_client.clone(),
+                &config.dns.http_client.pool,
+            )?,
+            TransportSource::Injected(transport) => GroupTransports::shared(transport.clone()),
+        };
+        let global_config = Self::create_upstream_group_config(global_upstream, transports);
         
         // 创建上游组配置映射
         let mut group_configs = HashMap::new();
@@ -138,8 +1059,18 @@ impl UpstreamManager {
                 // 获取此组的有效配置（继承与覆盖全局配置）
                 let effective_config = Arc::new(config.get_effective_upstream_config(&group.name)?);
                 
+                // 每个上游组使用独立的连接池，避免某个组的流量耗尽其他组的连接
+                let pool = config.get_effective_connection_pool(&group.name);
+                let transports = match &source {
+                    TransportSource::Default(_) => {
+                        let group_client = create_upstream_http_client(&config, &pool, &effective_config)?;
+                        Self::create_transports(&config, &effective_config, group_client, &pool)?
+                    },
+                    TransportSource::Injected(transport) => GroupTransports::shared(transport.clone()),
+                };
+                
                 // 创建上游组配置
-                let group_config = Self::create_upstream_group_config(&config, effective_config.clone(), http_client.clone())?;
+                let group_config = Self::create_upstream_group_config(effective_config.clone(), transports);
                 
                 // 添加到映射
                 group_configs.insert(group.name.clone(), group_config);
@@ -149,6 +1080,8 @@ impl UpstreamManager {
                     resolvers_count = effective_config.resolvers.len(),
                     dnssec_enabled = effective_config.enable_dnssec,
                     query_timeout = effective_config.query_timeout,
+                    max_idle_connections = pool.max_idle_connections,
+                    max_connections = pool.max_connections,
                     "Initialized upstream group"
                 );
             }
@@ -160,45 +1093,461 @@ impl UpstreamManager {
             "Upstream resolver manager initialized"
         );
         
+        let mdns = config.dns.mdns_forwarder.enabled
+            .then(|| MdnsForwarder::new(&config.dns.mdns_forwarder));
+
         Ok(Self {
             global_config,
             group_configs,
             server_config: config,
+            mdns,
+            stop: watch::Sender::new(false),
         })
     }
     
-    // 创建上游组配置
-    fn create_upstream_group_config(
-        _config: &ServerConfig, 
-        upstream_config: Arc<UpstreamConfig>, 
-        http_client: Client
-    ) -> Result<UpstreamGroupConfig> {
+    // 创建上游组的真实传输
+    fn create_transports(
+        config: &ServerConfig, 
+        upstream_config: &UpstreamConfig, 
+        http_client: Client,
+        pool: &PoolConfig,
+    ) -> Result<GroupTransports> {
         // 构建 hickory-resolver 配置（用于非DoH协议）
-        let (resolver_config, resolver_opts) = Self::build_resolver_config(&upstream_config)?;
+        let (resolver_config, resolver_opts) = Self::build_resolver_config(upstream_config)?;
+        
+        // 创建异步解析器，UDP/TCP/DoT 套接字绑定到配置的源地址
+        let provider = BoundRuntimeProvider {
+            inner: TokioRuntimeProvider::default(),
+            bind_addr: upstream_config.bind_addr,
+        };
+        let resolver = AsyncResolver::new(resolver_config, resolver_opts, GenericConnector::new(provider));
+        
+        // 组内所有 DoH 上游共享同一个 HTTP 客户端与连接数限制
+        let doh = DoHTransport {
+            client: http_client,
+            request_id_header: config.http.propagate_request_id_upstream
+                .then(|| config.http.request_id_header.clone()),
+            edns_padding: upstream_config.edns_padding,
+            retry: DoHRetryPolicy {
+                statuses: config.dns.http_client.retry_status.clone(),
+                max_retries: config.dns.http_client.max_retries,
+                query_timeout: Duration::from_secs(upstream_config.query_timeout),
+            },
+            connection_limit: (pool.max_connections > 0)
+                .then(|| Arc::new(Semaphore::new(pool.max_connections as usize))),
+            validate_origin: upstream_config.strict_response_validation,
+        };
         
-        // 创建异步解析器
-        let resolver = TokioAsyncResolver::tokio(resolver_config, resolver_opts);
+        // 配置了客户端证书或 CA 的解析器使用独立的 TLS 客户端，与组内其他 DoH 上游共享连接数限制
+        let mut dedicated: HashMap<usize, Arc<dyn UpstreamTransport>> = HashMap::new();
+        for (index, resolver_config) in upstream_config.resolvers.iter().enumerate() {
+            let keepalive = upstream_config.tcp_keepalive
+                && matches!(resolver_config.protocol, ResolverProtocol::Tcp | ResolverProtocol::Dot);
+            if !resolver_config.has_tls_client_settings() && !keepalive {
+                continue;
+            }
+            let identity = resolver_config.client_identity_pem()?;
+            let ca_cert = resolver_config.ca_cert_pem()?;
+            let transport: Arc<dyn UpstreamTransport> = match resolver_config.protocol {
+                ResolverProtocol::Doh => {
+                    let mut builder = upstream_http_client_builder(config, pool, upstream_config);
+                    if let Some((cert, key)) = &identity {
+                        builder = builder.identity(reqwest::Identity::from_pkcs8_pem(cert, key).map_err(|e| ServerError::Tls(format!(
+                            "Invalid client certificate for resolver {}: {}", resolver_config.address, e
+                        )))?);
+                    }
+                    if let Some(ca_cert) = &ca_cert {
+                        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(ca_cert).map_err(|e| ServerError::Tls(format!(
+                            "Invalid CA certificate for resolver {}: {}", resolver_config.address, e
+                        )))?);
+                    }
+                    Arc::new(DoHTransport { client: build_http_client(builder)?, ..doh.clone() })
+                },
+                // 启用 tcp_keepalive 时 TCP/DoT 上游使用复用连接的传输
+                ResolverProtocol::Tcp | ResolverProtocol::Dot if keepalive => {
+                    Arc::new(KeepaliveStreamTransport::new(resolver_config, identity, ca_cert, upstream_config, pool)?)
+                },
+                ResolverProtocol::Dot => {
+                    Arc::new(MutualTlsDotTransport::new(resolver_config, identity, ca_cert, upstream_config)?)
+                },
+                ResolverProtocol::Udp | ResolverProtocol::Tcp => continue,
+            };
+            dedicated.insert(index, transport);
+        }
         
+        Ok(GroupTransports {
+            doh: Arc::new(doh),
+            resolver: Arc::new(ResolverTransport { resolver }),
+            dedicated,
+        })
+    }
+    
+    // 创建上游组配置
+    fn create_upstream_group_config(
+        upstream_config: Arc<UpstreamConfig>, 
+        transports: GroupTransports,
+    ) -> UpstreamGroupConfig {
+        let GroupTransports { doh: doh_transport, resolver, mut dedicated } = transports;
         // 创建DoH客户端列表
         let mut doh_clients = Vec::new();
+        let doh_options = DoHClientOptions {
+            transport: doh_transport,
+            failure_ratio: upstream_config.failure_ratio_threshold
+                .zip(upstream_config.effective_recovery_threshold())
+                .map(|(threshold, recovery_threshold)| FailureRatioPolicy {
+                    threshold,
+                    recovery_threshold,
+                    window: Duration::from_secs(upstream_config.rolling_window_secs),
+                }),
+        };
         
-        for resolver_config in &upstream_config.resolvers {
-            if resolver_config.protocol == ResolverProtocol::Doh {
-                // 使用共享的 HTTP 客户端
-                let client = DoHClient::new(resolver_config.address.clone(), http_client.clone());
-                doh_clients.push(Arc::new(client));
-                debug!(
-                    url = ?resolver_config.address,
-                    "Added DoH upstream resolver"
-                );
+        for (index, resolver_config) in upstream_config.resolvers.iter().enumerate() {
+            match resolver_config.protocol {
+                ResolverProtocol::Doh => {
+                    let url = resolver_config.doh_url();
+                    debug!(
+                        url = ?url,
+                        mutual_tls = resolver_config.client_cert_path.is_some(),
+                        "Added DoH upstream resolver"
+                    );
+                    doh_clients.push(match dedicated.remove(&index) {
+                        Some(transport) => doh_options.build_with_transport(url, UPSTREAM_PROTOCOL_DOH, transport),
+                        None => doh_options.build(url),
+                    });
+                },
+                // 复用连接的 TCP/DoT 上游不经过 hickory-resolver，与 DoH 上游一样按顺序尝试
+                ResolverProtocol::Tcp | ResolverProtocol::Dot if upstream_config.tcp_keepalive => {
+                    let Some(transport) = dedicated.remove(&index) else {
+                        continue;
+                    };
+                    debug!(address = %resolver_config.address, protocol = ?resolver_config.protocol, "Added TCP upstream resolver with connection reuse");
+                    let protocol = match resolver_config.protocol {
+                        ResolverProtocol::Dot => UPSTREAM_PROTOCOL_DOT,
+                        _ => UPSTREAM_PROTOCOL_TCP,
+                    };
+                    doh_clients.push(doh_options.build_with_transport(resolver_config.address.clone(), protocol, transport));
+                },
+                // 需要专用 TLS 设置的 DoT 上游不经过 hickory-resolver，与 DoH 上游一样按顺序尝试
+                ResolverProtocol::Dot if resolver_config.has_tls_client_settings() => {
+                    debug!(
+                        address = %resolver_config.address,
+                        mutual_tls = resolver_config.client_cert_path.is_some(),
+                        "Added DoT upstream resolver with dedicated TLS settings"
+                    );
+                    let transport = dedicated.remove(&index).unwrap_or_else(|| doh_options.transport.clone());
+                    doh_clients.push(doh_options.build_with_transport(
+                        resolver_config.address.clone(), UPSTREAM_PROTOCOL_DOT, transport
+                    ));
+                },
+                ResolverProtocol::Udp | ResolverProtocol::Tcp | ResolverProtocol::Dot => {},
             }
         }
         
-        Ok(UpstreamGroupConfig {
+        UpstreamGroupConfig {
             resolver,
             doh_clients,
+            discovered_doh_clients: RwLock::new(Vec::new()),
+            doh_options,
+            health: GroupHealth::new(Duration::from_secs(upstream_config.rolling_window_secs)),
             config: upstream_config,
-        })
+        }
+    }
+    
+    // 启动 SRV 上游发现：启动时同步加载一次，之后按配置间隔定期刷新
+    pub async fn start_srv_discovery(self: &Arc<Self>) {
+        let upstream_config = &self.global_config.config;
+        if !upstream_config.discover_via_srv {
+            return;
+        }
+        
+        // 启动时同步加载一次，保证服务开始处理查询时发现的上游已生效
+        if let Err(e) = self.refresh_srv_upstreams().await {
+            warn!(srv_name = %upstream_config.srv_name, error = %e, "Initial SRV upstream discovery failed, using static resolvers");
+        }
+        
+        // 持有弱引用，管理器释放后更新任务自动退出
+        let manager = Arc::downgrade(self);
+        let interval_secs = upstream_config.srv_refresh_interval_secs;
+        tokio::spawn(Self::run_srv_discovery(manager, interval_secs, self.stop.subscribe()));
+    }
+    
+    // 通知后台发现任务退出（关闭时调用）
+    pub fn stop_background_tasks(&self) {
+        self.stop.send_replace(true);
+    }
+    
+    // 定期刷新 SRV 发现的上游
+    async fn run_srv_discovery(manager: Weak<Self>, interval_secs: u64, mut stop: watch::Receiver<bool>) {
+        let mut interval_timer = tokio::time::interval(Duration::from_secs(interval_secs));
+        // 跳过立即触发的第一次 tick，启动时已加载
+        interval_timer.tick().await;
+        
+        info!(interval_secs, "Started SRV upstream discovery periodic updater");
+        
+        loop {
+            tokio::select! {
+                _ = interval_timer.tick() => {},
+                _ = stop.wait_for(|stopped| *stopped) => {
+                    debug!("Stopped SRV upstream discovery periodic updater");
+                    break;
+                },
+            }
+            let Some(manager) = manager.upgrade() else {
+                debug!("Upstream manager dropped, stopping SRV upstream discovery");
+                break;
+            };
+            
+            if let Err(e) = manager.refresh_srv_upstreams().await {
+                warn!(
+                    srv_name = %manager.global_config.config.srv_name,
+                    error = %e,
+                    "SRV upstream discovery failed, keeping current resolvers"
+                );
+            }
+        }
+    }
+    
+    // 查询 SRV 记录并更新发现的 DoH 上游，返回当前发现的上游数量
+    // 查询失败或没有可用记录时保留当前上游，避免上游池被清空
+    pub async fn refresh_srv_upstreams(&self) -> Result<usize> {
+        let upstream_config = &self.global_config.config;
+        let srv_name = Name::from_ascii(upstream_config.srv_name.trim())
+            .map_err(|e| ServerError::Config(format!("Invalid upstream.srv_name {}: {}", upstream_config.srv_name, e)))?;
+        
+        let mut query = Message::new();
+        query.set_id(fastrand::u16(..))
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true)
+            .add_query(Query::query(srv_name.clone(), RecordType::SRV));
+        
+        // 通过静态配置的上游引导查询，不依赖已发现的上游
+        let response = self.resolve_in_scope(&query, UpstreamSelection::Global, None, None, UpstreamScope::Bootstrap).await?;
+        if response.response_code() != ResponseCode::NoError {
+            return Err(ServerError::Upstream(format!(
+                "SRV query for {} returned {}", srv_name, response.response_code()
+            )));
+        }
+        
+        let records = response.answers().iter()
+            .filter_map(|record| match record.data() {
+                Some(RData::SRV(srv)) => Some(srv.clone()),
+                _ => None,
+            })
+            // 目标为 "." 表示该服务不可用
+            .filter(|srv| !srv.target().is_root())
+            .collect::<Vec<_>>();
+        
+        let mut urls: Vec<String> = Vec::with_capacity(records.len());
+        for srv in order_srv_records(records) {
+            let url = srv_doh_url(&srv);
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        
+        if urls.is_empty() {
+            return Err(ServerError::Upstream(format!("No usable SRV records found for {}", srv_name)));
+        }
+        
+        let mut discovered = self.global_config.discovered_doh_clients.write().unwrap_or_else(|e| e.into_inner());
+        for client in discovered.iter().filter(|client| !urls.contains(&client.url)) {
+            info!(url = %client.url, "Removed DoH upstream withdrawn from SRV records");
+        }
+        for url in urls.iter().filter(|url| !discovered.iter().any(|client| &client.url == *url)) {
+            info!(url = %url, "Added DoH upstream discovered via SRV records");
+        }
+        
+        *discovered = urls.into_iter()
+            .map(|url| self.global_config.doh_options.build(url))
+            .collect();
+        METRICS.upstream_srv_discovered_resolvers().set(discovered.len() as i64);
+        
+        Ok(discovered.len())
+    }
+    
+    // 启动 Consul 上游发现：为配置了 consul_upstream_discovery 的全局上游和上游组
+    // 启动时同步加载一次，之后按各自的刷新间隔定期刷新
+    pub async fn start_consul_discovery(self: &Arc<Self>) {
+        let targets = std::iter::once((None, &self.global_config))
+            .chain(self.group_configs.iter().map(|(name, group)| (Some(name.clone()), group)))
+            .filter_map(|(group, group_config)| {
+                group_config.config.consul_upstream_discovery.as_ref()
+                    .map(|consul| (group, consul.refresh_interval_secs))
+            })
+            .collect::<Vec<_>>();
+        if targets.is_empty() {
+            return;
+        }
+        
+        let client = match Client::builder()
+            .timeout(Duration::from_secs(CONSUL_REQUEST_TIMEOUT_SECS))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!(error = %e, "Failed to create Consul HTTP client, Consul upstream discovery disabled");
+                return;
+            }
+        };
+        
+        for (group, interval_secs) in targets {
+            // 启动时同步加载一次，保证服务开始处理查询时发现的上游已生效
+            if let Err(e) = self.refresh_consul_upstreams_with(&client, group.as_deref()).await {
+                warn!(
+                    upstream_group = group.as_deref().unwrap_or("global"),
+                    error = %e,
+                    "Initial Consul upstream discovery failed, using static resolvers"
+                );
+            }
+            
+            // 持有弱引用，管理器释放后更新任务自动退出
+            let manager = Arc::downgrade(self);
+            tokio::spawn(Self::run_consul_discovery(manager, client.clone(), group, interval_secs, self.stop.subscribe()));
+        }
+    }
+    
+    // 定期刷新 Consul 发现的上游
+    async fn run_consul_discovery(
+        manager: Weak<Self>,
+        client: Client,
+        group: Option<String>,
+        interval_secs: u64,
+        mut stop: watch::Receiver<bool>,
+    ) {
+        let group_label = group.as_deref().unwrap_or("global").to_string();
+        let mut interval_timer = tokio::time::interval(Duration::from_secs(interval_secs));
+        // 跳过立即触发的第一次 tick，启动时已加载
+        interval_timer.tick().await;
+        
+        info!(upstream_group = %group_label, interval_secs, "Started Consul upstream discovery periodic updater");
+        
+        loop {
+            tokio::select! {
+                _ = interval_timer.tick() => {},
+                _ = stop.wait_for(|stopped| *stopped) => {
+                    debug!(upstream_group = %group_label, "Stopped Consul upstream discovery periodic updater");
+                    break;
+                },
+            }
+            let Some(manager) = manager.upgrade() else {
+                debug!("Upstream manager dropped, stopping Consul upstream discovery");
+                break;
+            };
+            
+            if let Err(e) = manager.refresh_consul_upstreams_with(&client, group.as_deref()).await {
+                warn!(
+                    upstream_group = %group_label,
+                    error = %e,
+                    "Consul upstream discovery failed, keeping current resolvers"
+                );
+            }
+        }
+    }
+    
+    // 从 Consul 获取健康实例并更新指定上游组（None 表示全局上游）发现的 DoH 上游，返回当前发现的上游数量
+    pub async fn refresh_consul_upstreams(&self, group: Option<&str>) -> Result<usize> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(CONSUL_REQUEST_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| ServerError::Upstream(format!("Failed to create Consul HTTP client: {}", e)))?;
+        self.refresh_consul_upstreams_with(&client, group).await
+    }
+    
+    // 查询失败时保留当前上游；没有健康实例时清空发现的上游，回退到静态配置的上游
+    async fn refresh_consul_upstreams_with(&self, client: &Client, group: Option<&str>) -> Result<usize> {
+        let group_config = self.discovery_group_config(group)?;
+        let group_label = group.unwrap_or("global");
+        let consul_config = group_config.config.consul_upstream_discovery.as_ref()
+            .ok_or_else(|| ServerError::Config(format!(
+                "Consul upstream discovery is not configured for upstream group {}", group_label
+            )))?;
+        
+        let urls = consul::fetch_healthy_upstreams(client, consul_config).await?;
+        
+        let mut discovered = group_config.discovered_doh_clients.write().unwrap_or_else(|e| e.into_inner());
+        for client in discovered.iter().filter(|client| !urls.contains(&client.url)) {
+            info!(upstream_group = group_label, url = %client.url, "Removed DoH upstream no longer passing Consul health checks");
+        }
+        for url in urls.iter().filter(|url| !discovered.iter().any(|client| &client.url == *url)) {
+            info!(upstream_group = group_label, url = %url, "Added DoH upstream discovered via Consul");
+        }
+        if urls.is_empty() {
+            warn!(
+                upstream_group = group_label,
+                service_name = %consul_config.service_name,
+                "No healthy Consul instances found, falling back to static resolvers"
+            );
+        }
+        
+        // 保留仍然健康的已有客户端，避免刷新时重置其失败率统计
+        *discovered = urls.into_iter()
+            .map(|url| discovered.iter()
+                .find(|client| client.url == url)
+                .cloned()
+                .unwrap_or_else(|| group_config.doh_options.build(url)))
+            .collect();
+        METRICS.upstream_consul_discovered_resolvers()
+            .with_label_values(&[group_label])
+            .set(discovered.len() as i64);
+        
+        Ok(discovered.len())
+    }
+    
+    // 按组名查找上游组配置（None 表示全局上游）
+    fn discovery_group_config(&self, group: Option<&str>) -> Result<&UpstreamGroupConfig> {
+        match group {
+            None => Ok(&self.global_config),
+            Some(name) => self.group_configs.get(name)
+                .ok_or_else(|| ServerError::Config(format!("Unknown upstream group: {}", name))),
+        }
+    }
+    
+    // 指定上游组（None 表示全局上游）当前通过 SRV 或 Consul 发现的 DoH 上游 URL（按选择顺序）
+    pub fn discovered_group_resolvers(&self, group: Option<&str>) -> Vec<String> {
+        self.discovery_group_config(group)
+            .map(|group_config| group_config.discovered_doh_clients.read()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .map(|client| client.url.clone())
+                .collect())
+            .unwrap_or_default()
+    }
+    
+    // mDNS 转发器（未启用时为 None）
+    pub fn mdns_forwarder(&self) -> Option<&MdnsForwarder> {
+        self.mdns.as_ref()
+    }
+    
+    // 当前通过 SRV 发现的 DoH 上游 URL（按选择顺序）
+    pub fn discovered_resolvers(&self) -> Vec<String> {
+        self.global_config.discovered_doh_clients.read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|client| client.url.clone())
+            .collect()
+    }
+    
+    // 上游组的健康评分 (1 - 失败率) * (1 / 延迟 EWMA)，越高越好
+    // 尚未查询过的组返回无穷大，未知的组返回 0
+    pub fn get_group_health_score(&self, group_name: &str) -> f64 {
+        self.group_configs.get(group_name)
+            .map(|config| config.health.score())
+            .unwrap_or(0.0)
+    }
+    
+    // 上游预计恢复可用的时间：所有 DoH 上游均已降级时，取最早恢复的上游按失败率窗口估算的时间；
+    // 存在未降级的上游、未启用失败率降级或没有 DoH 上游时返回 None
+    pub fn estimated_recovery_time(&self) -> Option<Duration> {
+        let mut earliest: Option<Duration> = None;
+        for group in std::iter::once(&self.global_config).chain(self.group_configs.values()) {
+            let discovered = group.discovered_doh_clients.read().unwrap_or_else(|e| e.into_inner());
+            for client in group.doh_clients.iter().chain(discovered.iter()) {
+                let recovery = client.recovery_time()?;
+                earliest = Some(earliest.map_or(recovery, |current| current.min(recovery)));
+            }
+        }
+        earliest
     }
     
     // 执行 DNS 查询
@@ -208,6 +1557,46 @@ impl UpstreamManager {
         selection: UpstreamSelection,
         client_ip: Option<IpAddr>,
         client_ecs: Option<&EcsData>
+    ) -> Result<Message> {
+        let groups = match selection {
+            UpstreamSelection::Groups(groups) => groups,
+            selection => return self.resolve_in_scope(query_message, selection, client_ip, client_ecs, UpstreamScope::All).await,
+        };
+        
+        // 依次尝试各上游组；负响应（包括 NXDOMAIN）是有效结果，只有上游不可达才回退到下一组
+        let mut last_error = None;
+        for (i, group_name) in groups.iter().enumerate() {
+            let selection = UpstreamSelection::Group(group_name.clone());
+            match self.resolve_in_scope(query_message, selection, client_ip, client_ecs, UpstreamScope::All).await {
+                Err(ServerError::UpstreamUnavailable(reason)) => {
+                    if let Some(next_group) = groups.get(i + 1) {
+                        warn!(
+                            upstream_group = %group_name,
+                            next_group = %next_group,
+                            error = %reason,
+                            "Upstream group unavailable, falling through to next group"
+                        );
+                        METRICS.upstream_group_fallthrough_total()
+                            .with_label_values(&[group_name, next_group])
+                            .inc();
+                    }
+                    last_error = Some(ServerError::UpstreamUnavailable(reason));
+                },
+                result => return result,
+            }
+        }
+        
+        Err(last_error.unwrap_or_else(|| ServerError::Upstream("No upstream group to query".to_string())))
+    }
+    
+    // 在指定的上游范围内执行 DNS 查询
+    async fn resolve_in_scope(
+        &self, 
+        query_message: &Message, 
+        selection: UpstreamSelection,
+        client_ip: Option<IpAddr>,
+        client_ecs: Option<&EcsData>,
+        scope: UpstreamScope,
     ) -> Result<Message> {
         if query_message.message_type() != MessageType::Query {
             return Err(ServerError::Upstream("Not a query message type".to_string()));
@@ -235,6 +1624,9 @@ impl UpstreamManager {
                 }
             },
             UpstreamSelection::Global => (&self.global_config, "global"),
+            UpstreamSelection::Groups(_) => {
+                return Err(ServerError::Upstream("Upstream group list must be resolved group by group".to_string()));
+            },
         };
         
         // 获取 ECS 策略
@@ -284,71 +1676,117 @@ impl UpstreamManager {
         let query_start = Instant::now();
         
         // 执行查询
-        let response = if !target_config.doh_clients.is_empty() {
-            // 有 DoH 客户端，优先使用
-            let client = &target_config.doh_clients[0]; // 简单选择第一个，后续可以实现更复杂的负载均衡
-            
-            // 记录上游请求
-            {
-                METRICS.upstream_requests_total().with_label_values(&[
-                    &client.url, UPSTREAM_PROTOCOL_DOH, group_name
-                ]).inc();
-            }
-            
-            // 开始计时
-            let upstream_start = Instant::now();
-            
-            // 执行查询
-            match client.query(&processed_query).await {
-                Ok(resp) => {
-                    // 计算查询时间
-                    let upstream_duration = upstream_start.elapsed().as_secs_f64();
-                    
-                    // 记录上游查询时间
-                    {
-                        METRICS.upstream_duration_seconds().with_label_values(&[
-                            &client.url, UPSTREAM_PROTOCOL_DOH, group_name
-                        ]).observe(upstream_duration);
+        let doh_clients = target_config.ordered_doh_clients(scope, group_name);
+        let mut response = if !doh_clients.is_empty() {
+            // 有 DoH 客户端，优先使用；传输失败、响应无法解析或未通过校验时改用下一个 DoH 上游
+            let mut validated = None;
+            let mut last_error = None;
+            for client in &doh_clients {
+                // 记录上游请求
+                {
+                    METRICS.upstream_requests_total().with_label_values(&[
+                        &client.url, client.protocol, group_name
+                    ]).inc();
+                }
+
+                // 开始计时
+                let upstream_start = Instant::now();
+
+                // 执行查询
+                let result = client.query(&processed_query).await
+                    .and_then(|resp| target_config.validate(&processed_query, resp));
+                let failed = match &result {
+                    Ok(resp) => resp.response_code() == ResponseCode::ServFail,
+                    Err(_) => true,
+                };
+                client.record_outcome(failed, group_name);
+                target_config.health.record(upstream_start.elapsed(), failed);
+
+                // 计算查询时间
+                let upstream_duration = upstream_start.elapsed().as_secs_f64();
+
+                // 记录上游查询时间
+                {
+                    METRICS.upstream_duration_seconds().with_label_values(&[
+                        &client.url, client.protocol, group_name
+                    ]).observe(upstream_duration);
+                }
+
+                match result {
+                    Ok(resp) => {
+                        // 如果启用了DNSSEC，记录验证结果
+                        if target_config.config.enable_dnssec {
+                            let is_validated = resp.authentic_data();
+                            let status = if is_validated { DNSSEC_VALIDATION_SUCCESS } else { DNSSEC_VALIDATION_FAILURE };
+                            METRICS.dnssec_validations_total().with_label_values(&[status]).inc();
+                        }
+
+                        query_log::record_upstream(group_name, &client.url);
+                        validated = Some(resp);
+                        break;
                     }
-                    
-                    // 如果启用了DNSSEC，记录验证结果
-                    if target_config.config.enable_dnssec {
-                        let is_validated = resp.authentic_data();
-                        let status = if is_validated { DNSSEC_VALIDATION_SUCCESS } else { DNSSEC_VALIDATION_FAILURE };
-                        METRICS.dnssec_validations_total().with_label_values(&[status]).inc();
+                    Err(ServerError::UpstreamValidation(reason)) => {
+                        warn!(
+                            url = %client.url,
+                            upstream_group = group_name,
+                            name = %query.name(),
+                            reason = %reason,
+                            "Discarding upstream response that failed validation"
+                        );
+                        METRICS.upstream_validation_failures_total()
+                            .with_label_values(&[&client.url, group_name])
+                            .inc();
                     }
-                    
-                    resp
-                }
-                Err(e) => {
-                    // 计算查询时间
-                    let upstream_duration = upstream_start.elapsed().as_secs_f64();
-                    
-                    // 记录查询失败
-                    {
-                        METRICS.upstream_failures_total().with_label_values(&[
-                            UPSTREAM_FAILURE_REASON_ERROR, &client.url, group_name
-                        ]).inc();
-                        
-                        METRICS.upstream_duration_seconds().with_label_values(&[
-                            &client.url, UPSTREAM_PROTOCOL_DOH, group_name
-                        ]).observe(upstream_duration);
+                    Err(ServerError::UpstreamParse(reason)) => {
+                        warn!(
+                            url = %client.url,
+                            upstream_group = group_name,
+                            name = %query.name(),
+                            reason = %reason,
+                            "Discarding unparsable upstream response, trying next upstream"
+                        );
+                        METRICS.upstream_parse_errors_total()
+                            .with_label_values(&[&client.url])
+                            .inc();
+                    }
+                    Err(e) => {
+                        // 记录查询失败
+                        {
+                            METRICS.upstream_failures_total().with_label_values(&[
+                                UPSTREAM_FAILURE_REASON_ERROR, &client.url, group_name
+                            ]).inc();
+                        }
+
+                        warn!(
+                            url = %client.url,
+                            upstream_group = group_name,
+                            name = %query.name(),
+                            error = %e,
+                            "DoH upstream query failed, trying next upstream"
+                        );
+                        last_error = Some(e);
                     }
-                    
-                    return Err(e);
                 }
             }
+
+            // 所有 DoH 上游都失败时返回最后一个传输错误；
+            // 响应都无法解析或未通过校验时按上游不可用处理（返回 SERVFAIL）
+            match (validated, last_error) {
+                (Some(resp), _) => resp,
+                (None, Some(e)) => return Err(e),
+                (None, None) => return Err(ServerError::UpstreamUnavailable(format!(
+                    "All upstream responses for {} were unparsable or failed validation", query.name()
+                ))),
+            }
         } else {
             // 没有 DoH 客户端，使用标准解析器
-            let query = processed_query.queries().first().ok_or_else(|| 
-                ServerError::Upstream("No query in message".to_string())
-            )?;
-            
             // 记录上游请求（使用通用标识）
-            let resolver_id = "hickory-resolver";
-            let protocol = match target_config.config.resolvers.first() {
-                Some(r) => format!("{:?}", r.protocol),
-                None => "Unknown".to_string(),
+            let (resolver_id, protocol, address) = match target_config.config.resolvers.first() {
+                _ if target_config.config.mode == UpstreamMode::Recursive => {
+                    (RECURSIVE_RESOLVER_ID, UPSTREAM_PROTOCOL_RECURSIVE.to_string(), RECURSIVE_RESOLVER_ID)
+                },
+                Some(r) => ("hickory-resolver", format!("{:?}", r.protocol), r.address.as_str()),
+                None => ("hickory-resolver", "Unknown".to_string(), ""),
             };
             
             {
@@ -360,15 +1798,20 @@ impl UpstreamManager {
             // 开始计时
             let upstream_start = Instant::now();
             
-            // 使用lookup方法进行查询
-            let lookup_result = target_config.resolver.lookup(
-                query.name().clone(),
-                query.query_type()
-            ).await;
-            
+            // 执行查询
+            let result = target_config.resolver.exchange(address, &processed_query).await
+                .and_then(|message| target_config.validate(&processed_query, message));
+
             // 计算查询时间
             let upstream_duration = upstream_start.elapsed().as_secs_f64();
             
+            // 无记录属于正常的解析结果，只有上游不可达、响应未通过校验或 SERVFAIL 计为失败
+            let failed = match &result {
+                Ok(message) => message.response_code() == ResponseCode::ServFail,
+                Err(e) => matches!(e, ServerError::UpstreamUnavailable(_) | ServerError::UpstreamValidation(_)),
+            };
+            target_config.health.record(upstream_start.elapsed(), failed);
+            
             // 记录查询时间
             {
                 METRICS.upstream_duration_seconds().with_label_values(&[
@@ -377,27 +1820,8 @@ impl UpstreamManager {
             }
             
             // 处理查询结果
-            let response = match lookup_result {
-                Ok(lookup) => {
-                    // 构建DNS响应消息
-                    let mut message = Message::new();
-                    message.set_id(processed_query.id())
-                        .set_message_type(MessageType::Response)
-                        .set_op_code(processed_query.op_code())
-                        .set_response_code(ResponseCode::NoError)
-                        .set_recursion_desired(processed_query.recursion_desired())
-                        .set_recursion_available(true);
-                    
-                    // 添加原始查询
-                    for q in processed_query.queries() {
-                        message.add_query(q.clone());
-                    }
-                    
-                    // 添加记录
-                    for record in lookup.record_iter() {
-                        message.add_answer(record.clone());
-                    }
-                    
+            match result {
+                Ok(message) => {
                     // 如果启用了DNSSEC，记录验证统计
                     if target_config.config.enable_dnssec {
                         // lookup 对象没有 dnssec_status 方法，直接设置 AD 标志
@@ -411,8 +1835,26 @@ impl UpstreamManager {
                         }
                     }
                     
+                    query_log::record_upstream(group_name, address);
                     message
                 },
+                // hickory-resolver 已在组内选择过解析器，响应未通过校验时按上游不可用处理（返回 SERVFAIL）
+                Err(ServerError::UpstreamValidation(reason)) => {
+                    warn!(
+                        upstream = address,
+                        upstream_group = group_name,
+                        name = %query.name(),
+                        reason = %reason,
+                        "Discarding upstream response that failed validation"
+                    );
+                    METRICS.upstream_validation_failures_total()
+                        .with_label_values(&[resolver_id, group_name])
+                        .inc();
+                    
+                    return Err(ServerError::UpstreamUnavailable(format!(
+                        "Upstream response for {} failed validation: {}", query.name(), reason
+                    )));
+                },
                 Err(e) => {
                     // 记录查询失败
                     {
@@ -421,13 +1863,20 @@ impl UpstreamManager {
                         ]).inc();
                     }
                     
-                    return Err(ServerError::Upstream(format!("DNS query failed: {}", e)));
+                    return Err(e);
                 }
-            };
-            
-            response
+            }
         };
         
+        // 去除各部分之间重复的记录，缓存与客户端得到的都是去重后的响应
+        if self.server_config.dns.response_deduplication {
+            let removed = remove_duplicate_records(&mut response);
+            if removed > 0 {
+                debug!(name = %query.name(), removed, "Removed duplicate records from upstream response");
+                METRICS.response_dedup_records_removed_total().inc_by(removed as u64);
+            }
+        }
+        
         // 计算总查询时间
         let query_duration = query_start.elapsed().as_secs_f64();
         
@@ -459,45 +1908,25 @@ impl UpstreamManager {
         // 添加解析器
         for resolver in &config.resolvers {
             match resolver.protocol {
-                // UDP/TCP 协议
-                ResolverProtocol::Udp | ResolverProtocol::Tcp => {
-                    // 解析地址
-                    let socket_addr = Self::parse_socket_addr(&resolver.address)?;
+                // 需要专用 TLS 设置的 DoT 上游由 MutualTlsDotTransport 处理
+                ResolverProtocol::Dot if resolver.has_tls_client_settings() => {},
+                
+                // UDP/TCP/DoT 协议
+                ResolverProtocol::Udp | ResolverProtocol::Tcp | ResolverProtocol::Dot => {
+                    // 解析地址（支持 [IPv6]:端口），DoT 同时解析 TLS 域名
+                    let (tls_dns_name, socket_addr) = resolver.parse_socket_addr()?;
                     
-                    // 添加解析器
                     let protocol = match resolver.protocol {
                         ResolverProtocol::Udp => Protocol::Udp,
                         ResolverProtocol::Tcp => Protocol::Tcp,
-                        _ => unreachable!(),
+                        _ => Protocol::Tls,
                     };
                     
+                    // 源地址由 BoundRuntimeProvider 统一绑定（hickory 的 UDP/TCP 连接会忽略 bind_addr）
                     resolver_config.add_name_server(NameServerConfig {
                         socket_addr,
                         protocol,
-                        tls_dns_name: None,
-                        trust_negative_responses: true,
-                        bind_addr: None,
-                    });
-                },
-                
-                // DoT 协议
-                ResolverProtocol::Dot => {
-                    // 解析 DoT 地址 (domain@ip:port)
-                    let parts: Vec<&str> = resolver.address.split('@').collect();
-                    if parts.len() != 2 {
-                        return Err(ServerError::Config(format!(
-                            "Invalid DoT address format, expected 'domain@ip:port': {}", 
-                            resolver.address
-                        )));
-                    }
-                    
-                    let domain = parts[0].to_string();
-                    let socket_addr = Self::parse_socket_addr(parts[1])?;
-                    
-                    resolver_config.add_name_server(NameServerConfig {
-                        socket_addr,
-                        protocol: Protocol::Tls,
-                        tls_dns_name: Some(domain),
+                        tls_dns_name,
                         trust_negative_responses: true,
                         bind_addr: None,
                     });
@@ -523,13 +1952,39 @@ impl UpstreamManager {
         
         Ok((resolver_config, resolver_opts))
     }
+}
 
-    // 解析 socket 地址
-    fn parse_socket_addr(addr_str: &str) -> Result<SocketAddr> {
-        addr_str.parse()
-            .map_err(|e| ServerError::Config(format!(
-                "Invalid socket address '{}': {}", 
-                addr_str, e
-            )))
+// 按 RFC 2782 排序 SRV 记录：优先级升序，同一优先级内按权重加权随机排序
+pub fn order_srv_records(mut records: Vec<SRV>) -> Vec<SRV> {
+    records.sort_by_key(|srv| srv.priority());
+    
+    let mut ordered = Vec::with_capacity(records.len());
+    while let Some(priority) = records.first().map(|srv| srv.priority()) {
+        let split = records.iter().position(|srv| srv.priority() != priority).unwrap_or(records.len());
+        let rest = records.split_off(split);
+        let mut group = std::mem::replace(&mut records, rest);
+        
+        // 权重为 0 的记录放在最前面，使其仅在累计权重为 0 时有机会被优先选中
+        group.sort_by_key(|srv| srv.weight() != 0);
+        while !group.is_empty() {
+            let total: u32 = group.iter().map(|srv| u32::from(srv.weight())).sum();
+            let target = fastrand::u32(0..=total);
+            let mut running = 0;
+            let index = group.iter()
+                .position(|srv| {
+                    running += u32::from(srv.weight());
+                    running >= target
+                })
+                .unwrap_or(0);
+            ordered.push(group.remove(index));
+        }
     }
-} 
+    
+    ordered
+}
+
+// 由 SRV 记录构建 DoH 上游 URL
+fn srv_doh_url(srv: &SRV) -> String {
+    let target = srv.target().to_ascii();
+    format!("https://{}:{}{}", target.trim_end_matches('.'), srv.port(), SRV_DISCOVERED_DOH_PATH)
+}
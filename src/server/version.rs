@@ -0,0 +1,45 @@
+// src/server/version.rs
+
+// 该模块提供 /version 管理端点，报告运行中的构建版本与进程运行时间，便于批量管理实例。
+// git 提交哈希与构建时间由构建脚本（build.rs）在编译时注入。
+
+use axum::{extract::State, routing::get, Json, Router};
+use serde::Serialize;
+use tokio::time::Instant;
+use crate::common::consts::VERSION_PATH;
+
+// crate 版本
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// 构建时的 git 提交哈希（不在 git 仓库中构建时为 "unknown"）
+pub const GIT_COMMIT: &str = env!("OWDNS_GIT_COMMIT");
+
+// 构建时间（RFC 3339 UTC）
+pub const BUILD_TIMESTAMP: &str = env!("OWDNS_BUILD_TIMESTAMP");
+
+// 版本信息响应
+#[derive(Debug, Serialize)]
+pub struct VersionResponse {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub build_timestamp: &'static str,
+    // 进程启动以来的秒数
+    pub uptime_secs: u64,
+}
+
+// 创建版本信息路由，started_at 为服务器启动时间
+pub fn version_routes(started_at: Instant) -> Router {
+    Router::new()
+        .route(VERSION_PATH, get(handle_version))
+        .with_state(started_at)
+}
+
+// 处理版本信息请求
+async fn handle_version(State(started_at): State<Instant>) -> Json<VersionResponse> {
+    Json(VersionResponse {
+        version: VERSION,
+        git_commit: GIT_COMMIT,
+        build_timestamp: BUILD_TIMESTAMP,
+        uptime_secs: started_at.elapsed().as_secs(),
+    })
+}
@@ -0,0 +1,143 @@
+// tests/client/query_tool_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use oxide_wdns::client::args::DohFormat;
+    use oxide_wdns::client::query_tool::{execute_queries, format_answer_table, LatencySummary, QueryToolArgs};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use clap::Parser;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use hickory_proto::op::{Message, MessageType, Query};
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use hickory_proto::rr::rdata::A;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::method;
+    use tracing::info;
+
+    // 创建包含单条 A 记录的响应消息
+    fn create_response_message() -> Message {
+        let name = Name::from_ascii("example.com.").unwrap();
+        let mut message = Message::new();
+        message.set_message_type(MessageType::Response);
+        message.add_query(Query::query(name.clone(), RecordType::A));
+        message.add_answer(Record::from_rdata(name, 300, RData::A(A(Ipv4Addr::new(192, 0, 2, 1)))));
+        message
+    }
+
+    #[test]
+    fn test_latency_summary_percentiles() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_latency_summary_percentiles");
+
+        assert!(LatencySummary::from_samples(&[]).is_none());
+
+        // 1..=100 毫秒的乱序样本
+        let samples: Vec<Duration> = (1..=100u64).rev().map(Duration::from_millis).collect();
+        let summary = LatencySummary::from_samples(&samples).unwrap();
+        assert_eq!(summary.p50, Duration::from_millis(50));
+        assert_eq!(summary.p95, Duration::from_millis(95));
+        assert_eq!(summary.p99, Duration::from_millis(99));
+
+        // 单个样本时所有分位数相同
+        let summary = LatencySummary::from_samples(&[Duration::from_millis(7)]).unwrap();
+        assert_eq!(summary.p50, Duration::from_millis(7));
+        assert_eq!(summary.p99, Duration::from_millis(7));
+
+        info!("Test completed: test_latency_summary_percentiles");
+    }
+
+    #[test]
+    fn test_answer_table_format() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_answer_table_format");
+
+        let table = format_answer_table(&create_response_message());
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "NAME          TTL  TYPE  DATA");
+        assert_eq!(lines[1], "example.com.  300  A     192.0.2.1");
+
+        // 无应答记录时只输出表头
+        assert_eq!(format_answer_table(&Message::new()), "NAME  TTL  TYPE  DATA");
+
+        info!("Test completed: test_answer_table_format");
+    }
+
+    #[test]
+    fn test_query_tool_args() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_query_tool_args");
+
+        let args = QueryToolArgs::try_parse_from([
+            "wdns-query", "--server", "https://localhost:8053/dns-query",
+            "--name", "example.com", "--type", "AAAA", "--format", "json",
+            "--repeat", "10", "--parallel", "4", "--trace",
+        ]).unwrap();
+        assert!(args.validate().is_ok());
+        assert!(matches!(args.format, DohFormat::Json));
+        assert_eq!((args.repeat, args.parallel, args.trace), (10, 4, true));
+        let cli_args = args.to_cli_args();
+        assert_eq!(cli_args.domain, "example.com");
+        assert_eq!(cli_args.record_type, "AAAA");
+
+        // 默认值
+        let args = QueryToolArgs::try_parse_from([
+            "wdns-query", "--server", "https://localhost:8053/dns-query", "--name", "example.com",
+        ]).unwrap();
+        assert_eq!(args.record_type, "A");
+        assert_eq!((args.repeat, args.parallel, args.trace), (1, 1, false));
+
+        // 次数与并发数必须至少为 1
+        let mut invalid = args.clone();
+        invalid.repeat = 0;
+        assert!(invalid.validate().is_err());
+        let mut invalid = args.clone();
+        invalid.parallel = 0;
+        assert!(invalid.validate().is_err());
+
+        // 缺少必填参数
+        assert!(QueryToolArgs::try_parse_from(["wdns-query", "--name", "example.com"]).is_err());
+
+        info!("Test completed: test_query_tool_args");
+    }
+
+    #[tokio::test]
+    async fn test_execute_repeated_parallel_queries() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_execute_repeated_parallel_queries");
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("content-type", CONTENT_TYPE_DNS_MESSAGE)
+                .insert_header("x-request-id", "trace-1234")
+                .set_body_bytes(create_response_message().to_vec().unwrap()))
+            .mount(&mock_server)
+            .await;
+
+        let args = QueryToolArgs::try_parse_from([
+            "wdns-query", "--server", &format!("{}/dns-query", mock_server.uri()),
+            "--name", "example.com", "--repeat", "12", "--parallel", "4", "--trace", "--no-color",
+        ]).unwrap();
+        assert!(args.validate().is_ok());
+
+        let outcomes = execute_queries(&args).await.unwrap();
+
+        // 每次查询都到达服务器，结果按序号排列
+        assert_eq!(outcomes.len(), 12);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 12);
+        for (i, outcome) in outcomes.iter().enumerate() {
+            assert_eq!(outcome.index as usize, i + 1);
+            assert_eq!(outcome.request_id(), Some("trace-1234"));
+            let response = outcome.result.as_ref().unwrap();
+            assert_eq!(response.message.answers().len(), 1);
+        }
+
+        info!("Test completed: test_execute_repeated_parallel_queries");
+    }
+}
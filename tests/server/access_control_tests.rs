@@ -0,0 +1,208 @@
+// tests/server/access_control_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::rr::RecordType;
+    use tokio::net::TcpListener;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::{AccessControlAction, AccessControlOrder, ServerConfig};
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 允许内网与文档网段，拒绝其中的个别地址；默认桶每秒 1 个请求
+    fn access_control_config(upstream_uri: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: true
+            per_ip_rate: 1
+            per_ip_concurrent: 1
+          access_control:
+            allow: ["10.0.0.0/8", "2001:db8::/32", "127.0.0.1"]
+            deny: ["10.0.0.66", "2001:db8:bad::/48"]
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+        "#, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    async fn build_app(config: ServerConfig) -> Router {
+        DoHServer::new(config, false).build_application_components().await.unwrap().app
+    }
+
+    fn query_uri() -> String {
+        let query = create_test_query("example.com", RecordType::A);
+        format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()))
+    }
+
+    // 以指定连接源地址发送 GET 查询，可附带 X-Forwarded-For 头
+    async fn get_status_via(app: &Router, peer_ip: &str, forwarded_for: Option<&str>) -> StatusCode {
+        let mut request = Request::get(query_uri());
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header("X-Forwarded-For", forwarded_for);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        let peer = SocketAddr::new(peer_ip.parse().unwrap(), 40000);
+        request.extensions_mut().insert(ConnectInfo(peer));
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    // 以指定客户端 IP 直连发送 GET 查询
+    async fn get_status(app: &Router, client_ip: &str) -> StatusCode {
+        get_status_via(app, client_ip, None).await
+    }
+
+    fn rejected(list: &str) -> u64 {
+        METRICS.access_control_rejected_total().with_label_values(&[list]).get()
+    }
+
+    #[tokio::test]
+    async fn test_access_control_deny_then_allow() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_access_control_deny_then_allow");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = build_app(access_control_config(&mock_server.uri())).await;
+        let (denied, not_allowed) = (rejected("deny"), rejected("allow"));
+
+        assert_eq!(get_status(&app, "10.1.1.1").await, StatusCode::OK);
+        assert_eq!(get_status(&app, "2001:db8::1").await, StatusCode::OK);
+        // IPv4 映射的 IPv6 地址按 IPv4 匹配
+        assert_eq!(get_status(&app, "::ffff:10.2.2.2").await, StatusCode::OK);
+
+        // deny 优先于 allow，不在 allow 中的客户端同样被拒绝
+        assert_eq!(get_status(&app, "10.0.0.66").await, StatusCode::FORBIDDEN);
+        assert_eq!(get_status(&app, "2001:db8:bad::1").await, StatusCode::FORBIDDEN);
+        assert_eq!(get_status(&app, "203.0.113.1").await, StatusCode::FORBIDDEN);
+        assert_eq!(get_status(&app, "2001:db9::1").await, StatusCode::FORBIDDEN);
+        assert!(rejected("deny") >= denied + 2);
+        assert!(rejected("allow") >= not_allowed + 2);
+
+        // 访问控制在速率限制之前执行，被拒绝的请求不消耗限速配额
+        for _ in 0..5 {
+            assert_eq!(get_status(&app, "10.0.0.66").await, StatusCode::FORBIDDEN);
+        }
+
+        info!("Test completed: test_access_control_deny_then_allow");
+    }
+
+    #[tokio::test]
+    async fn test_access_control_allow_then_deny() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_access_control_allow_then_deny");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let mut config = access_control_config(&mock_server.uri());
+        config.http.rate_limit.enabled = false;
+        config.http.access_control.order = AccessControlOrder::AllowThenDeny;
+        config.http.access_control.allow = vec!["192.168.1.10".to_string(), "fd00::1".to_string()];
+        config.http.access_control.deny = vec!["192.168.0.0/16".to_string(), "fd00::/8".to_string()];
+        let app = build_app(config).await;
+
+        // allow 中的地址是 deny 网段中的例外，其余未命中 deny 的客户端放行
+        assert_eq!(get_status(&app, "192.168.1.10").await, StatusCode::OK);
+        assert_eq!(get_status(&app, "fd00::1").await, StatusCode::OK);
+        assert_eq!(get_status(&app, "203.0.113.1").await, StatusCode::OK);
+        assert_eq!(get_status(&app, "192.168.1.11").await, StatusCode::FORBIDDEN);
+        assert_eq!(get_status(&app, "fd00::2").await, StatusCode::FORBIDDEN);
+
+        info!("Test completed: test_access_control_allow_then_deny");
+    }
+
+    #[tokio::test]
+    async fn test_access_control_proxy_headers_and_drop() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_access_control_proxy_headers_and_drop");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let mut config = access_control_config(&mock_server.uri());
+        config.http.rate_limit.enabled = false;
+        config.http.access_control.deny.push("127.0.0.1".to_string());
+        config.http.access_control.action = AccessControlAction::Drop;
+        config.http.trusted_proxies = vec!["127.0.0.1".to_string()];
+        let app = build_app(config).await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+        });
+        let client = reqwest::Client::new();
+        let url = format!("http://{}{}", addr, query_uri());
+
+        // 没有代理头时按连接的源地址判断，被拒绝的请求不返回任何响应
+        assert!(client.get(&url).send().await.is_err());
+
+        // 受信任代理转发的客户端 IP 优先于连接的源地址
+        let response = client.get(&url).header("X-Forwarded-For", "10.1.1.1").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(client.get(&url).header("X-Forwarded-For", "10.0.0.66, 10.1.1.1").send().await.is_err());
+
+        info!("Test completed: test_access_control_proxy_headers_and_drop");
+    }
+
+    #[tokio::test]
+    async fn test_access_control_ignores_spoofed_proxy_headers() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_access_control_ignores_spoofed_proxy_headers");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let mut config = access_control_config(&mock_server.uri());
+        config.http.rate_limit.enabled = false;
+        config.http.trusted_proxies = vec!["10.9.0.0/16".to_string()];
+        let app = build_app(config).await;
+        let denied = rejected("deny");
+
+        // 被拒绝的直连客户端伪造代理头，仍按连接的源地址判断
+        assert_eq!(get_status_via(&app, "10.0.0.66", Some("10.1.1.1")).await, StatusCode::FORBIDDEN);
+        assert_eq!(get_status_via(&app, "10.0.0.66", Some("10.1.1.1, 10.9.0.1")).await, StatusCode::FORBIDDEN);
+        assert!(rejected("deny") >= denied + 2);
+
+        // 受信任代理转发的被拒绝地址同样被拒绝，转发的允许地址被放行
+        assert_eq!(get_status_via(&app, "10.9.0.1", Some("10.0.0.66")).await, StatusCode::FORBIDDEN);
+        assert_eq!(get_status_via(&app, "10.9.0.1", Some("10.1.1.1")).await, StatusCode::OK);
+
+        info!("Test completed: test_access_control_ignores_spoofed_proxy_headers");
+    }
+
+    #[test]
+    fn test_access_control_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_access_control_config_validation");
+
+        let default_config = ServerConfig::default();
+        assert!(!default_config.http.access_control.is_enabled());
+        assert_eq!(default_config.http.access_control.order, AccessControlOrder::DenyThenAllow);
+        assert_eq!(default_config.http.access_control.action, AccessControlAction::Forbidden);
+
+        let mut config = access_control_config("https://dns.example.com");
+        assert!(config.test().is_ok());
+
+        config.http.access_control.deny.push("not-a-network".to_string());
+        assert!(config.test().is_err());
+
+        let mut config = access_control_config("https://dns.example.com");
+        config.http.access_control.allow.push("10.0.0.0/33".to_string());
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_access_control_config_validation");
+    }
+}
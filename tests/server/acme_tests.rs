@@ -0,0 +1,315 @@
+// tests/server/acme_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL};
+    use serde_json::{json, Value};
+    use tempfile::TempDir;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request as MockRequest, Respond, ResponseTemplate};
+    use oxide_wdns::server::acme::AcmeManager;
+    use oxide_wdns::server::config::{AcmeChallengeType, AcmeConfig, ServerConfig, TlsConfig};
+    use oxide_wdns::server::health::health_routes_with_acme;
+    use oxide_wdns::server::tls::TlsContext;
+
+    // 测试使用的 nonce
+    const TEST_NONCE: &str = "test-nonce";
+
+    // 创建启用 ACME 的 TLS 配置
+    fn acme_tls_config(cache_dir: &std::path::Path, directory_url: &str) -> TlsConfig {
+        TlsConfig {
+            cert: None,
+            key: None,
+            acme: AcmeConfig {
+                enabled: true,
+                domains: vec!["dns.example.com".to_string()],
+                contact: Some("admin@example.com".to_string()),
+                cache_dir: cache_dir.to_path_buf(),
+                directory_url: directory_url.to_string(),
+                challenge: AcmeChallengeType::Http01,
+                http01_listen_addr: Some("127.0.0.1:8080".parse().unwrap()),
+                ..Default::default()
+            },
+            client_auth: None,
+            min_version: Default::default(),
+            cipher_suites: None,
+            alpn: None,
+        }
+    }
+
+    // 请求健康检查并返回状态码
+    async fn health_status(acme: Arc<AcmeManager>) -> StatusCode {
+        let response = health_routes_with_acme(acme)
+            .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        response.status()
+    }
+
+    // 解码 JWS 请求的 payload
+    fn jws_payload(request: &MockRequest) -> Value {
+        let jws: Value = serde_json::from_slice(&request.body).unwrap();
+        let payload = BASE64_URL.decode(jws["payload"].as_str().unwrap()).unwrap();
+        serde_json::from_slice(&payload).unwrap()
+    }
+
+    // 模拟 CA：对 finalize 请求中的 CSR 进行签名
+    struct FinalizeResponder {
+        base_url: String,
+        issued: Arc<Mutex<Option<String>>>,
+    }
+
+    impl Respond for FinalizeResponder {
+        fn respond(&self, request: &MockRequest) -> ResponseTemplate {
+            let payload = jws_payload(request);
+            let csr_der = BASE64_URL.decode(payload["csr"].as_str().unwrap()).unwrap();
+            let csr = rcgen::CertificateSigningRequestParams::from_der(&csr_der.into()).unwrap();
+
+            let ca_key = rcgen::KeyPair::generate().unwrap();
+            let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+            ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+            let cert = csr.signed_by(&ca_cert, &ca_key).unwrap();
+            *self.issued.lock().unwrap() = Some(format!("{}{}", cert.pem(), ca_cert.pem()));
+
+            ResponseTemplate::new(200)
+                .insert_header("Replay-Nonce", TEST_NONCE)
+                .set_body_json(json!({
+                    "status": "valid",
+                    "authorizations": [format!("{}/authz/1", self.base_url)],
+                    "finalize": format!("{}/finalize/1", self.base_url),
+                    "certificate": format!("{}/cert/1", self.base_url),
+                }))
+        }
+    }
+
+    // 模拟 CA：返回已签发的证书链
+    struct CertificateResponder {
+        issued: Arc<Mutex<Option<String>>>,
+    }
+
+    impl Respond for CertificateResponder {
+        fn respond(&self, _request: &MockRequest) -> ResponseTemplate {
+            let pem = self.issued.lock().unwrap().clone().unwrap_or_default();
+            ResponseTemplate::new(200)
+                .insert_header("Replay-Nonce", TEST_NONCE)
+                .insert_header("Content-Type", "application/pem-certificate-chain")
+                .set_body_string(pem)
+        }
+    }
+
+    // 构建 ACME 授权响应
+    fn authz_response(base_url: &str, status: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .insert_header("Replay-Nonce", TEST_NONCE)
+            .set_body_json(json!({
+                "status": status,
+                "identifier": { "type": "dns", "value": "dns.example.com" },
+                "challenges": [
+                    { "type": "http-01", "url": format!("{}/chall/1", base_url), "token": "token-1", "status": status },
+                    { "type": "tls-alpn-01", "url": format!("{}/chall/2", base_url), "token": "token-2", "status": status },
+                ],
+            }))
+    }
+
+    // 启动模拟 ACME 服务器
+    async fn start_mock_acme_server() -> MockServer {
+        let server = MockServer::start().await;
+        let base_url = server.uri();
+        let issued = Arc::new(Mutex::new(None));
+
+        Mock::given(method("GET")).and(path("/directory"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "newNonce": format!("{}/new-nonce", base_url),
+                "newAccount": format!("{}/new-account", base_url),
+                "newOrder": format!("{}/new-order", base_url),
+            })))
+            .mount(&server).await;
+
+        Mock::given(method("HEAD")).and(path("/new-nonce"))
+            .respond_with(ResponseTemplate::new(200).insert_header("Replay-Nonce", TEST_NONCE))
+            .mount(&server).await;
+
+        Mock::given(method("POST")).and(path("/new-account"))
+            .respond_with(ResponseTemplate::new(201)
+                .insert_header("Replay-Nonce", TEST_NONCE)
+                .insert_header("Location", format!("{}/account/1", base_url).as_str())
+                .set_body_json(json!({ "status": "valid" })))
+            .mount(&server).await;
+
+        Mock::given(method("POST")).and(path("/new-order"))
+            .respond_with(ResponseTemplate::new(201)
+                .insert_header("Replay-Nonce", TEST_NONCE)
+                .insert_header("Location", format!("{}/order/1", base_url).as_str())
+                .set_body_json(json!({
+                    "status": "pending",
+                    "authorizations": [format!("{}/authz/1", base_url)],
+                    "finalize": format!("{}/finalize/1", base_url),
+                })))
+            .mount(&server).await;
+
+        // 首次查询授权为 pending，完成挑战后变为 valid
+        Mock::given(method("POST")).and(path("/authz/1"))
+            .respond_with(authz_response(&base_url, "pending"))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/authz/1"))
+            .respond_with(authz_response(&base_url, "valid"))
+            .mount(&server).await;
+
+        Mock::given(method("POST")).and(path("/chall/1"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("Replay-Nonce", TEST_NONCE)
+                .set_body_json(json!({ "type": "http-01", "status": "processing", "token": "token-1" })))
+            .expect(1)
+            .mount(&server).await;
+
+        Mock::given(method("POST")).and(path("/finalize/1"))
+            .respond_with(FinalizeResponder { base_url: base_url.clone(), issued: issued.clone() })
+            .mount(&server).await;
+
+        Mock::given(method("POST")).and(path("/cert/1"))
+            .respond_with(CertificateResponder { issued })
+            .mount(&server).await;
+
+        server
+    }
+
+    #[tokio::test]
+    async fn test_acme_issues_and_installs_certificate() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_acme_issues_and_installs_certificate");
+
+        let server = start_mock_acme_server().await;
+        let cache_dir = TempDir::new().unwrap();
+        let tls_config = acme_tls_config(cache_dir.path(), &format!("{}/directory", server.uri()));
+
+        // 尚无缓存证书时使用临时证书启动
+        let tls = Arc::new(TlsContext::new(&tls_config).unwrap());
+        assert!(tls.current_certificate().is_some());
+        assert!(tls.certificate_not_after().is_none());
+        let placeholder = tls.current_certificate().unwrap();
+
+        let acme = Arc::new(AcmeManager::new(&tls_config.acme, tls.clone()).unwrap());
+        info!("Requesting certificate from mock ACME server at {}", server.uri());
+        assert!(acme.renew_if_needed().await.unwrap());
+
+        // 证书应已替换并写入缓存目录
+        assert_ne!(tls.current_certificate().unwrap(), placeholder);
+        assert!(tls.certificate_not_after().is_some());
+        assert!(tls_config.cert_path().exists());
+        assert!(tls_config.key_path().exists());
+        assert!(cache_dir.path().join("account.key").exists());
+        assert!(acme.health_issue().is_none());
+        assert_eq!(health_status(acme.clone()).await, StatusCode::OK);
+
+        // 新证书远未到期，不应再次签发
+        assert!(!acme.renew_if_needed().await.unwrap());
+
+        // 重启后应直接加载缓存的证书
+        let reloaded = TlsContext::new(&tls_config).unwrap();
+        assert_eq!(reloaded.current_certificate(), tls.current_certificate());
+
+        info!("Test completed: test_acme_issues_and_installs_certificate");
+    }
+
+    #[tokio::test]
+    async fn test_acme_failure_reported_by_health() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_acme_failure_reported_by_health");
+
+        // ACME 服务器不可用
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/directory"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server).await;
+
+        let cache_dir = TempDir::new().unwrap();
+        let tls_config = acme_tls_config(cache_dir.path(), &format!("{}/directory", server.uri()));
+        let tls = Arc::new(TlsContext::new(&tls_config).unwrap());
+        let acme = Arc::new(AcmeManager::new(&tls_config.acme, tls).unwrap());
+
+        // 首次签发前健康检查正常
+        assert_eq!(health_status(acme.clone()).await, StatusCode::OK);
+
+        // 签发失败且尚无正式证书时，健康检查应返回 503
+        assert!(acme.renew_if_needed().await.is_err());
+        let issue = acme.health_issue().expect("Renewal failure should be reported");
+        assert!(issue.contains("not issued"), "Unexpected issue: {}", issue);
+        assert_eq!(health_status(acme.clone()).await, StatusCode::SERVICE_UNAVAILABLE);
+
+        // 未知的 HTTP-01 token 应返回 404
+        let response = acme.http01_routes()
+            .oneshot(Request::builder().uri("/.well-known/acme-challenge/unknown").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        info!("Test completed: test_acme_failure_reported_by_health");
+    }
+
+    #[test]
+    fn test_acme_config_validation() {
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          tls:
+            acme:
+              enabled: true
+              domains: ["dns.example.com"]
+              contact: "admin@example.com"
+              cache_dir: "/tmp/oxide-wdns-acme-validation"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#;
+        let config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        let tls = config.http.tls.as_ref().unwrap();
+        assert!(tls.acme.enabled);
+        assert_eq!(tls.acme.challenge, AcmeChallengeType::TlsAlpn01);
+        assert_eq!(tls.acme.renew_before_days, 30);
+        assert!(config.test().is_ok());
+
+        // 未启用 ACME 时必须提供证书与私钥
+        let without_acme: ServerConfig = serde_yaml::from_str(
+            &config_str.replace("enabled: true", "enabled: false")
+        ).unwrap();
+        assert!(without_acme.test().is_err());
+
+        // 域名列表不能为空
+        let no_domains: ServerConfig = serde_yaml::from_str(
+            &config_str.replace(r#"["dns.example.com"]"#, "[]")
+        ).unwrap();
+        assert!(no_domains.test().is_err());
+
+        // HTTP-01 验证需要独立监听地址，且不能与 DoH 监听地址相同
+        let http01 = config_str.replace(
+            r#"contact: "admin@example.com""#,
+            "contact: \"admin@example.com\"\n              challenge: http-01",
+        );
+        let missing_addr: ServerConfig = serde_yaml::from_str(&http01).unwrap();
+        assert!(missing_addr.test().is_err());
+
+        let with_addr = http01.replace(
+            "challenge: http-01",
+            "challenge: http-01\n              http01_listen_addr: \"0.0.0.0:80\"",
+        );
+        let valid_http01: ServerConfig = serde_yaml::from_str(&with_addr).unwrap();
+        assert!(valid_http01.test().is_ok());
+
+        let same_addr: ServerConfig = serde_yaml::from_str(
+            &with_addr.replace("0.0.0.0:80", "127.0.0.1:8053")
+        ).unwrap();
+        assert!(same_addr.test().is_err());
+    }
+}
@@ -124,4 +124,30 @@ dns_resolver:
             .success()
             .stdout(predicatesStr::contains("Debug logging enabled"));
     }
+    
+    #[test]
+    fn test_generate_config_flag() {
+        let mut cmd = Command::cargo_bin("owdns").expect("Failed to find binary");
+        
+        // 不需要配置文件即可输出默认配置
+        let output = cmd.arg("--config")
+            .arg("/nonexistent/config.yaml")
+            .arg("--generate-config")
+            .assert()
+            .success()
+            .stdout(predicatesStr::contains("http_server:"))
+            .get_output()
+            .stdout
+            .clone();
+        
+        // 输出的配置可通过 --test 校验
+        let tmp_file = NamedTempFile::new().expect("Failed to create temp file");
+        fs::write(&tmp_file, output).expect("Failed to write generated config");
+        Command::cargo_bin("owdns").expect("Failed to find binary")
+            .arg("--config")
+            .arg(tmp_file.path())
+            .arg("--test")
+            .assert()
+            .success();
+    }
 }
@@ -0,0 +1,239 @@
+// tests/server/async_miss_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::{DNSClass, Name, RecordType};
+    use tracing::info;
+    use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::cache::{CacheKey, DnsCache, PendingMiss};
+    use oxide_wdns::server::config::{CacheConfig, ServerConfig};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::{post_query, server_state};
+
+    // 创建启用缓存的测试缓存实例
+    fn enabled_cache() -> Arc<DnsCache> {
+        Arc::new(DnsCache::new(CacheConfig {
+            enabled: true,
+            async_miss_resolution: true,
+            ..CacheConfig::default()
+        }))
+    }
+
+    // 创建测试用缓存键
+    fn cache_key(domain: &str) -> CacheKey {
+        CacheKey::new(Name::from_ascii(domain).unwrap(), RecordType::A, DNSClass::IN)
+    }
+
+    // 启动响应延迟指定时间的模拟 DoH 上游
+    async fn setup_delayed_doh_server(delay: Duration) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(move |request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let response = create_test_response(&query, Ipv4Addr::new(192, 0, 2, 60));
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+                    .set_delay(delay)
+            })
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    // 创建启用后台未命中解析的配置
+    fn async_miss_config(upstream_uri: &str, return_servfail: bool, timeout_ms: u64) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: true
+            async_miss_resolution: true
+            return_servfail_on_miss: {}
+            async_miss_resolution_timeout_ms: {}
+        "#, upstream_uri, return_servfail, timeout_ms);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_on_miss_shares_resolution() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_prefetch_on_miss_shares_resolution");
+
+        let cache = enabled_cache();
+        let key = cache_key("shared.example.com.");
+        let resolutions = Arc::new(AtomicUsize::new(0));
+
+        // 并发的相同未命中只触发一次解析
+        let mut pendings = Vec::new();
+        for _ in 0..5 {
+            let resolutions = resolutions.clone();
+            pendings.push(cache.prefetch_on_miss(&key, async move {
+                resolutions.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Some(create_test_query("shared.example.com.", RecordType::A))
+            }));
+        }
+        assert!(matches!(pendings[0], PendingMiss::Started(_)));
+        assert!(pendings[1..].iter().all(|pending| matches!(pending, PendingMiss::Joined(_))));
+        assert_eq!(cache.pending_miss_count(), 1);
+
+        // 所有等待者都收到同一结果
+        let results = futures::future::join_all(
+            pendings.into_iter().map(|pending| pending.wait(Some(Duration::from_secs(2))))
+        ).await;
+        assert!(results.iter().all(|result| result.is_some()));
+        assert_eq!(resolutions.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.pending_miss_count(), 0);
+
+        // 解析完成后再次未命中会启动新的解析
+        let pending = cache.prefetch_on_miss(&key, async { None });
+        assert!(matches!(pending, PendingMiss::Started(_)));
+        assert!(pending.wait(None).await.is_none());
+
+        info!("Test completed: test_prefetch_on_miss_shares_resolution");
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_on_miss_waiter_timeout() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_prefetch_on_miss_waiter_timeout");
+
+        let cache = enabled_cache();
+        let key = cache_key("slow.example.com.");
+        let slow_resolution = async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            Some(create_test_query("slow.example.com.", RecordType::A))
+        };
+
+        let started = cache.prefetch_on_miss(&key, slow_resolution);
+        let joined = cache.prefetch_on_miss(&key, async { unreachable!() });
+
+        // 等待者超时返回 None，不影响后台解析继续完成
+        assert!(joined.wait(Some(Duration::from_millis(50))).await.is_none());
+        assert_eq!(cache.pending_miss_count(), 1);
+        assert!(started.wait(None).await.is_some());
+        assert_eq!(cache.pending_miss_count(), 0);
+
+        info!("Test completed: test_prefetch_on_miss_waiter_timeout");
+    }
+
+    #[tokio::test]
+    async fn test_async_miss_returns_servfail_then_serves_waiters() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_async_miss_returns_servfail_then_serves_waiters");
+
+        let mock_server = setup_delayed_doh_server(Duration::from_millis(200)).await;
+        let state = server_state(async_miss_config(&mock_server.uri(), true, 2000)).await;
+
+        // 首个请求立即得到 SERVFAIL，后续请求等待后台解析结果
+        let first = post_query(state.clone(), "burst.example.com", 1).await;
+        assert_eq!(first.response_code(), ResponseCode::ServFail);
+        assert_eq!(first.id(), 1);
+
+        let waiters = futures::future::join_all(
+            (2..5).map(|id| post_query(state.clone(), "burst.example.com", id))
+        ).await;
+        for (waiter, id) in waiters.iter().zip(2..5) {
+            assert_eq!(waiter.response_code(), ResponseCode::NoError);
+            assert_eq!(waiter.answers().len(), 1);
+            assert_eq!(waiter.id(), id);
+        }
+
+        // 结果已写入缓存，上游只收到一次查询
+        let cached = post_query(state.clone(), "burst.example.com", 9).await;
+        assert_eq!(cached.answers().len(), 1);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+        assert_eq!(state.cache.pending_miss_count(), 0);
+
+        info!("Test completed: test_async_miss_returns_servfail_then_serves_waiters");
+    }
+
+    #[tokio::test]
+    async fn test_async_miss_waiter_timeout_returns_servfail() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_async_miss_waiter_timeout_returns_servfail");
+
+        let mock_server = setup_delayed_doh_server(Duration::from_millis(400)).await;
+        let state = server_state(async_miss_config(&mock_server.uri(), false, 50)).await;
+
+        // 未启用 return_servfail_on_miss 时首个请求等待自己发起的解析；
+        // 在其完成前到达的请求超过等待时间后返回 SERVFAIL
+        let first = post_query(state.clone(), "timeout.example.com", 1);
+        let waiter = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            post_query(state.clone(), "timeout.example.com", 2).await
+        };
+        let (first, waiter) = tokio::join!(first, waiter);
+
+        assert_eq!(first.response_code(), ResponseCode::NoError);
+        assert_eq!(first.answers().len(), 1);
+        assert_eq!(waiter.response_code(), ResponseCode::ServFail);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+
+        info!("Test completed: test_async_miss_waiter_timeout_returns_servfail");
+    }
+
+    #[test]
+    fn test_async_miss_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_async_miss_config_validation");
+
+        let mut config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+          cache:
+            enabled: true
+        "#).unwrap();
+
+        // 默认关闭
+        assert!(!config.dns.cache.async_miss_resolution);
+        assert!(!config.dns.cache.return_servfail_on_miss);
+        assert!(config.test().is_ok());
+
+        config.dns.cache.async_miss_resolution = true;
+        config.dns.cache.return_servfail_on_miss = true;
+        assert!(config.test().is_ok());
+
+        // 超时时间必须大于 0
+        config.dns.cache.async_miss_resolution_timeout_ms = 0;
+        assert!(config.test().is_err());
+        config.dns.cache.async_miss_resolution_timeout_ms = 100;
+
+        // 依赖缓存本身
+        config.dns.cache.enabled = false;
+        assert!(config.test().is_err());
+        config.dns.cache.enabled = true;
+
+        // return_servfail_on_miss 依赖 async_miss_resolution
+        config.dns.cache.async_miss_resolution = false;
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_async_miss_config_validation");
+    }
+}
@@ -0,0 +1,214 @@
+// tests/server/auth_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::Ipv4Addr;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 创建启用令牌认证的配置
+    fn auth_config(upstream_uri: &str, protect_metrics: bool) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+          auth:
+            enabled: true
+            protect_metrics: {}
+            tokens:
+              - name: "auth-test-alice"
+                token: "alice-secret"
+              - name: "auth-test-bob"
+                token: "bob-secret"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: false
+        "#, protect_metrics, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 构建 DoH GET 请求，可选携带 Bearer 令牌
+    fn dns_get_request(path: &str, bearer: Option<&str>) -> Request<Body> {
+        let query = create_test_query("example.com", RecordType::A).to_vec().unwrap();
+        let mut builder = Request::get(format!("{}?dns={}", path, URL_SAFE_NO_PAD.encode(query)));
+        if let Some(token) = bearer {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    // 发送请求并返回状态码与 WWW-Authenticate 头
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Option<String>) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let challenge = response.headers()
+            .get(header::WWW_AUTHENTICATE)
+            .map(|v| v.to_str().unwrap().to_string());
+        (response.status(), challenge)
+    }
+
+    #[tokio::test]
+    async fn test_doh_token_auth() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_token_auth");
+
+        let (mock_server, request_count) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = auth_config(&mock_server.uri(), false);
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+        let alice_authorized = METRICS.auth_requests_total().with_label_values(&["auth-test-alice", "authorized"]).get();
+        let bob_authorized = METRICS.auth_requests_total().with_label_values(&["auth-test-bob", "authorized"]).get();
+
+        // 未提供令牌时返回 401 与认证质询
+        let (status, challenge) = send(&app, dns_get_request("/dns-query", None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(challenge.as_deref(), Some("Bearer realm=\"dns\""));
+
+        // 错误的令牌（Bearer 或路径）返回 401 并标记 invalid_token
+        let (status, challenge) = send(&app, dns_get_request("/dns-query", Some("wrong-secret"))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(challenge.as_deref(), Some("Bearer realm=\"dns\", error=\"invalid_token\""));
+        let (status, _) = send(&app, dns_get_request("/dns-query/wrong-secret", None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(*request_count.lock().unwrap(), 0);
+
+        // Bearer 令牌与路径令牌均可通过认证
+        let (status, _) = send(&app, dns_get_request("/dns-query", Some("alice-secret"))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&app, dns_get_request("/dns-query/bob-secret", None)).await;
+        assert_eq!(status, StatusCode::OK);
+        let query = create_test_query("example.com", RecordType::A).to_vec().unwrap();
+        let post = Request::post("/dns-query/bob-secret")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+            .body(Body::from(query))
+            .unwrap();
+        let (status, _) = send(&app, post).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(*request_count.lock().unwrap(), 3);
+
+        // 令牌名称作为指标标签
+        assert_eq!(METRICS.auth_requests_total().with_label_values(&["auth-test-alice", "authorized"]).get(), alice_authorized + 1);
+        assert_eq!(METRICS.auth_requests_total().with_label_values(&["auth-test-bob", "authorized"]).get(), bob_authorized + 2);
+
+        // 健康检查与指标默认无需认证
+        let (status, _) = send(&app, Request::get("/health").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&app, Request::get("/metrics").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        info!("Test completed: test_doh_token_auth");
+    }
+
+    #[tokio::test]
+    async fn test_token_auth_protects_metrics() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_token_auth_protects_metrics");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = auth_config(&mock_server.uri(), true);
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        let (status, _) = send(&app, Request::get("/metrics").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let metrics = Request::get("/metrics")
+            .header(header::AUTHORIZATION, "Bearer alice-secret")
+            .body(Body::empty())
+            .unwrap();
+        let (status, _) = send(&app, metrics).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // 健康检查始终开放
+        let (status, _) = send(&app, Request::get("/health").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        info!("Test completed: test_token_auth_protects_metrics");
+    }
+
+    #[test]
+    fn test_token_auth_config() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_token_auth_config");
+
+        // 默认不启用认证
+        assert!(!ServerConfig::default().http.auth.enabled);
+
+        let mut config = auth_config("https://dns.example.com", false);
+        assert!(config.test().is_ok());
+
+        // 令牌文件与内联令牌合并，忽略空行与注释
+        let mut token_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(token_file, "# users\ncarol:carol-secret\n\n dave : dave-secret ").unwrap();
+        config.http.auth.token_file = Some(token_file.path().to_path_buf());
+        let tokens = config.http.auth.load_tokens().unwrap();
+        let names: Vec<&str> = tokens.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["auth-test-alice", "auth-test-bob", "carol", "dave"]);
+        assert_eq!(tokens[3].token, "dave-secret");
+        assert!(config.test().is_ok());
+
+        // 令牌文件格式错误或不存在
+        writeln!(token_file, "missing-separator").unwrap();
+        assert!(config.test().is_err());
+        config.http.auth.token_file = Some("/nonexistent/tokens.txt".into());
+        assert!(config.test().is_err());
+        config.http.auth.token_file = None;
+
+        // 重复的名称或令牌、包含 '/' 的令牌均被拒绝
+        let mut invalid = config.clone();
+        invalid.http.auth.tokens[1].name = "auth-test-alice".to_string();
+        assert!(invalid.test().is_err());
+        let mut invalid = config.clone();
+        invalid.http.auth.tokens[1].token = "alice-secret".to_string();
+        assert!(invalid.test().is_err());
+        let mut invalid = config.clone();
+        invalid.http.auth.tokens[0].token = "a/b".to_string();
+        assert!(invalid.test().is_err());
+
+        // 启用时至少需要一个令牌
+        let mut invalid = config.clone();
+        invalid.http.auth.tokens.clear();
+        assert!(invalid.test().is_err());
+
+        // protect_metrics 不能与 metrics_auth 同时使用
+        let mut invalid: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          metrics_auth:
+            type: bearer
+            token: "metrics-secret"
+          auth:
+            enabled: true
+            protect_metrics: true
+            tokens:
+              - name: "alice"
+                token: "alice-secret"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+        assert!(invalid.test().is_err());
+        invalid.http.auth.protect_metrics = false;
+        assert!(invalid.test().is_ok());
+
+        info!("Test completed: test_token_auth_config");
+    }
+}
@@ -0,0 +1,154 @@
+// tests/server/body_limit_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_ENGINE};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_JSON, CONTENT_TYPE_DNS_MESSAGE};
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::doh_handler::doh_routes;
+    use oxide_wdns::server::metrics::METRICS;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+    use crate::server::test_helpers::server_state;
+
+    const BODY_LIMIT: usize = 512;
+
+    // 创建设置了请求体大小上限的配置
+    fn body_limit_config(upstream_uri: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          max_request_body_size: {}
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: false
+        "#, BODY_LIMIT, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 当前 413 拒绝计数
+    fn too_large_count(method: &str) -> u64 {
+        METRICS.http_request_too_large_total().with_label_values(&[method]).get()
+    }
+
+    // 构建 wireformat POST 请求，可选声明 Content-Length
+    fn wire_post_request(body: Vec<u8>, declare_length: bool) -> Request<Body> {
+        let mut builder = Request::post("/dns-query")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE);
+        if declare_length {
+            builder = builder.header(header::CONTENT_LENGTH, body.len());
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_post_body_over_limit_rejected_with_413() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_post_body_over_limit_rejected_with_413");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = doh_routes(server_state(body_limit_config(&mock_server.uri())).await);
+        let before = too_large_count("POST");
+
+        // 声明了 Content-Length 的请求在读取前被拒绝
+        let response = app.clone()
+            .oneshot(wire_post_request(vec![0u8; BODY_LIMIT + 1], true))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"Request body too large");
+
+        // 未声明长度的请求体在读取超过上限时被拒绝
+        let response = app.clone()
+            .oneshot(wire_post_request(vec![0u8; BODY_LIMIT * 4], false))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // JSON POST 同样受限
+        let json = format!(r#"{{"name": "{}.com"}}"#, "a".repeat(BODY_LIMIT));
+        let response = app.clone()
+            .oneshot(
+                Request::post("/dns-query")
+                    .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_JSON)
+                    .body(Body::from(json))
+                    .unwrap()
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(too_large_count("POST") >= before + 3);
+
+        // 上限内的请求正常处理，超大请求不会到达上游
+        let query = create_test_query("example.com", RecordType::A);
+        let response = app.oneshot(wire_post_request(query.to_vec().unwrap(), true)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+
+        info!("Test completed: test_post_body_over_limit_rejected_with_413");
+    }
+
+    #[tokio::test]
+    async fn test_get_dns_param_over_limit_rejected_with_413() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_get_dns_param_over_limit_rejected_with_413");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = doh_routes(server_state(body_limit_config(&mock_server.uri())).await);
+        let before = too_large_count("GET");
+
+        // 编码后超过上限的 dns 参数在解码前被拒绝
+        let encoded = BASE64_ENGINE.encode(vec![0u8; BODY_LIMIT + 1]);
+        let response = app.clone()
+            .oneshot(Request::get(format!("/dns-query?dns={}", encoded)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(too_large_count("GET"), before + 1);
+
+        // 上限内的查询正常处理
+        let query = create_test_query("example.com", RecordType::A);
+        let encoded = BASE64_ENGINE.encode(query.to_vec().unwrap());
+        let response = app
+            .oneshot(Request::get(format!("/dns-query?dns={}", encoded)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        info!("Test completed: test_get_dns_param_over_limit_rejected_with_413");
+    }
+
+    #[test]
+    fn test_max_request_body_size_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_max_request_body_size_validation");
+
+        let mut config = ServerConfig::default();
+        assert_eq!(config.http.max_request_body_size, 65535);
+        assert!(config.test().is_ok());
+
+        // 不能小于最小值，也不能超过 DNS 消息的最大长度
+        config.http.max_request_body_size = 100;
+        assert!(config.test().is_err());
+        config.http.max_request_body_size = 65536;
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_max_request_body_size_validation");
+    }
+}
@@ -0,0 +1,104 @@
+// tests/server/cache_key_scope_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::{DNSClass, Name, RecordType};
+    use tracing::info;
+    use oxide_wdns::server::cache::CacheKey;
+    use oxide_wdns::server::config::{CacheKeyScope, ServerConfig};
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{extract_ip_addresses, mount_doh_answer, test_with_server_config};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 78);
+
+    // 以指定客户端 IP（通过受信任的本地代理转发的 X-Forwarded-For）发送查询
+    async fn query_from(server_addr: &str, client_ip: &str) -> Message {
+        let query = create_test_query("scoped.example.com", RecordType::A);
+        let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let response = reqwest::Client::new().get(url).header("X-Forwarded-For", client_ip).send().await.unwrap();
+        Message::from_vec(&response.bytes().await.unwrap()).unwrap()
+    }
+
+    fn key_for(client_ip: &str) -> CacheKey {
+        let name = Name::from_ascii("scoped.example.com").unwrap();
+        CacheKey::new(name, RecordType::A, DNSClass::IN).with_client_subnet(client_ip.parse::<IpAddr>().unwrap())
+    }
+
+    #[test]
+    fn test_cache_key_client_subnet_prefixes() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cache_key_client_subnet_prefixes");
+
+        // IPv4 按 /24 归并
+        assert_eq!(key_for("198.51.100.7").client_subnet.as_deref().map(String::as_str), Some("198.51.100.0/24"));
+        assert_eq!(key_for("198.51.100.7"), key_for("198.51.100.200"));
+        assert_ne!(key_for("198.51.100.7"), key_for("198.51.101.7"));
+
+        // IPv6 按 /56 归并
+        assert_eq!(key_for("2001:db8:0:12ab::1").client_subnet.as_deref().map(String::as_str), Some("2001:db8:0:1200::/56"));
+        assert_eq!(key_for("2001:db8:0:12ab::1"), key_for("2001:db8:0:12ff::2"));
+        assert_ne!(key_for("2001:db8:0:12ab::1"), key_for("2001:db8:0:1300::1"));
+
+        // 基础键保留客户端子网，不会回退到其他子网的条目
+        assert_eq!(key_for("198.51.100.7").get_base_key(), key_for("198.51.100.7"));
+
+        info!("Test completed: test_cache_key_client_subnet_prefixes");
+    }
+
+    #[tokio::test]
+    async fn test_per_client_subnet_cache_isolation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_per_client_subnet_cache_isolation");
+
+        let customize = |config: &mut ServerConfig| {
+            config.dns.cache.enabled = true;
+            config.dns.cache.key_scope = CacheKeyScope::PerClientSubnet;
+            config.http.trusted_proxies = vec!["127.0.0.1".to_string()];
+        };
+        test_with_server_config(customize, |server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+
+            // 第一个子网的首次查询访问上游，同一 /24 内的其他客户端命中缓存
+            let response = query_from(&server_addr, "192.0.2.10").await;
+            assert_eq!(extract_ip_addresses(&response), vec![ANSWER_IP.to_string()]);
+            query_from(&server_addr, "192.0.2.20").await;
+            assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+
+            // 另一子网的客户端查询同一名称时不共享缓存条目
+            let response = query_from(&server_addr, "198.51.100.10").await;
+            assert_eq!(extract_ip_addresses(&response), vec![ANSWER_IP.to_string()]);
+            assert_eq!(mock.received_requests().await.unwrap().len(), 2);
+
+            // 两个子网各自的缓存条目均可命中
+            query_from(&server_addr, "198.51.100.11").await;
+            query_from(&server_addr, "192.0.2.30").await;
+            assert_eq!(mock.received_requests().await.unwrap().len(), 2);
+        }).await;
+
+        info!("Test completed: test_per_client_subnet_cache_isolation");
+    }
+
+    #[tokio::test]
+    async fn test_global_cache_scope_shared_between_clients() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_global_cache_scope_shared_between_clients");
+
+        assert_eq!(ServerConfig::default().dns.cache.key_scope, CacheKeyScope::Global);
+        test_with_server_config(|config| config.dns.cache.enabled = true, |server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+
+            // 默认作用域下不同子网的客户端共享同一缓存条目
+            query_from(&server_addr, "192.0.2.10").await;
+            query_from(&server_addr, "198.51.100.10").await;
+            assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+        }).await;
+
+        info!("Test completed: test_global_cache_scope_shared_between_clients");
+    }
+}
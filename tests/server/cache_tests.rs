@@ -31,6 +31,7 @@ mod tests {
                 negative: negative_ttl,
             },
             persistence: PersistenceCacheConfig::default(),
+            ..CacheConfig::default()
         };
         DnsCache::new(config)
     }
@@ -43,6 +44,7 @@ mod tests {
             record_class: 1, // IN 类
             ecs_network: None,
             ecs_scope_prefix_length: None,
+            client_subnet: None,
         }
     }
     
@@ -447,6 +449,7 @@ mod tests {
                 negative: 60,
             },
             persistence: PersistenceCacheConfig::default(),
+            ..CacheConfig::default()
         };
         info!("Creating DnsCache instance with disabled config...");
         let cache = DnsCache::new(config);
@@ -524,6 +527,323 @@ mod tests {
         info!("Test finished: test_negative_caching");
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cache_skips_truncated_and_error_responses() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cache_skips_truncated_and_error_responses");
+
+        let cache = create_test_cache(100, 60, 3600, 300);
+
+        // 设置 TC 位的截断响应不缓存
+        let key = create_cache_key("truncated.example.com", 1);
+        let mut truncated = create_test_message("truncated.example.com", RecordType::A, 300, Some("192.0.2.1"));
+        truncated.set_truncated(true);
+        assert!(!cache.is_cacheable(&truncated));
+        cache.put_with_auto_ttl(&key, &truncated).await.unwrap();
+        assert!(cache.get(&key).await.is_none(), "Truncated response should not be served from cache");
+
+        // 默认不缓存 SERVFAIL/FORMERR/NOTIMP/REFUSED
+        let key = create_cache_key("servfail.example.com", 1);
+        let mut servfail = create_test_message("servfail.example.com", RecordType::A, 300, None);
+        servfail.set_response_code(ResponseCode::ServFail);
+        cache.put(&key, &servfail, cache.negative_ttl()).await.unwrap();
+        assert!(cache.get(&key).await.is_none(), "SERVFAIL response should not be served from cache");
+        for rcode in [ResponseCode::FormErr, ResponseCode::NotImp, ResponseCode::Refused] {
+            servfail.set_response_code(rcode);
+            assert!(!cache.is_cacheable(&servfail), "{:?} should not be cacheable", rcode);
+        }
+
+        // NXDOMAIN 仍使用负缓存
+        let nxdomain = create_test_message("nxdomain.example.com", RecordType::A, 300, None);
+        assert!(cache.is_cacheable(&nxdomain));
+
+        // 从列表中移除后 SERVFAIL 可以被缓存，TC 响应始终不缓存
+        let cache = DnsCache::new(CacheConfig {
+            enabled: true,
+            no_cache_rcodes: vec!["FORMERR".to_string(), "5".to_string()],
+            ..CacheConfig::default()
+        });
+        servfail.set_response_code(ResponseCode::ServFail);
+        cache.put(&key, &servfail, 300).await.unwrap();
+        assert_eq!(cache.get(&key).await.unwrap().response_code(), ResponseCode::ServFail);
+        servfail.set_response_code(ResponseCode::Refused);
+        assert!(!cache.is_cacheable(&servfail));
+        assert!(!cache.is_cacheable(&truncated));
+
+        info!("Test finished: test_cache_skips_truncated_and_error_responses");
+    }
+
+    #[test]
+    fn test_no_cache_rcodes_parsing() {
+        let mut config = CacheConfig::default();
+        assert_eq!(config.parsed_no_cache_rcodes().unwrap(), vec![
+            ResponseCode::ServFail, ResponseCode::FormErr, ResponseCode::NotImp, ResponseCode::Refused,
+        ]);
+
+        // 支持不区分大小写的助记符与数值
+        config.no_cache_rcodes = vec!["nxdomain".to_string(), "2".to_string()];
+        assert_eq!(config.parsed_no_cache_rcodes().unwrap(), vec![ResponseCode::NXDomain, ResponseCode::ServFail]);
+
+        for invalid in ["BOGUS", "4096"] {
+            config.no_cache_rcodes = vec![invalid.to_string()];
+            assert!(config.parsed_no_cache_rcodes().is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_truncates_oversized_answer_section() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cache_truncates_oversized_answer_section");
+
+        assert_eq!(CacheConfig::default().max_answer_records, Some(20));
+        let cache = DnsCache::new(CacheConfig {
+            enabled: true,
+            max_answer_records: Some(5),
+            ..CacheConfig::default()
+        });
+
+        // 包含 100 条 A 记录的响应只缓存前 5 条，并设置 TC 位
+        let key = create_cache_key("many.example.com", 1);
+        let mut message = create_test_message("many.example.com", RecordType::A, 300, Some("192.0.2.0"));
+        let name = Name::from_ascii("many.example.com").unwrap();
+        for i in 1..100u8 {
+            message.add_answer(Record::from_rdata(name.clone(), 300, RData::A(A::new(192, 0, 2, i))));
+        }
+        cache.put_with_auto_ttl(&key, &message).await.unwrap();
+
+        let cached = cache.get(&key).await.unwrap();
+        assert_eq!(cached.answers().len(), 5);
+        assert_eq!(cached.answer_count(), 5);
+        assert!(cached.truncated());
+        assert_eq!(cached.answers()[4].data(), Some(&RData::A(A::new(192, 0, 2, 4))));
+        let decoded = Message::from_vec(&cached.to_vec().unwrap()).unwrap();
+        assert_eq!(decoded.answers().len(), 5);
+
+        // 未超过上限的响应原样缓存
+        let key = create_cache_key("few.example.com", 1);
+        let message = create_test_message("few.example.com", RecordType::A, 300, Some("192.0.2.1"));
+        cache.put_with_auto_ttl(&key, &message).await.unwrap();
+        let cached = cache.get(&key).await.unwrap();
+        assert_eq!(cached.answers().len(), 1);
+        assert!(!cached.truncated());
+
+        // None 表示不限制
+        let cache = DnsCache::new(CacheConfig {
+            enabled: true,
+            max_answer_records: None,
+            ..CacheConfig::default()
+        });
+        let key = create_cache_key("many.example.com", 1);
+        let mut message = create_test_message("many.example.com", RecordType::A, 300, Some("192.0.2.0"));
+        for i in 1..100u8 {
+            message.add_answer(Record::from_rdata(name.clone(), 300, RData::A(A::new(192, 0, 2, i))));
+        }
+        cache.put_with_auto_ttl(&key, &message).await.unwrap();
+        assert_eq!(cache.get(&key).await.unwrap().answers().len(), 100);
+
+        info!("Test completed: test_cache_truncates_oversized_answer_section");
+    }
+
+    #[tokio::test]
+    async fn test_cache_key_case_insensitive() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cache_key_case_insensitive");
+
+        let cache = create_test_cache(100, 60, 3600, 60);
+
+        // 以大写域名写入，以小写域名查找应命中（RFC 1035 §2.3.3）
+        let upper_key = CacheKey::new(Name::from_ascii("EXAMPLE.COM.").unwrap(), RecordType::A, DNSClass::IN);
+        let message = create_test_message("EXAMPLE.COM.", RecordType::A, 300, Some("192.0.2.1"));
+        cache.put(&upper_key, &message, 300).await.unwrap();
+
+        let lower_key = CacheKey::new(Name::from_ascii("example.com.").unwrap(), RecordType::A, DNSClass::IN);
+        assert_eq!(upper_key, lower_key);
+        assert!(cache.get(&lower_key).await.is_some());
+
+        // 从消息构建的键同样规范化
+        assert_eq!(CacheKey::from(&message), lower_key);
+
+        info!("Test completed: test_cache_key_case_insensitive");
+    }
+
+    #[tokio::test]
+    async fn test_cache_key_mixed_case_mx_aaaa_txt() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cache_key_mixed_case_mx_aaaa_txt");
+
+        let cache = create_test_cache(100, 60, 3600, 60);
+        let name = Name::from_ascii("Mail.Example.COM.").unwrap();
+        let records = [
+            (RecordType::MX, RData::MX(hickory_proto::rr::rdata::MX::new(10, Name::from_ascii("mx1.example.com.").unwrap()))),
+            (RecordType::AAAA, RData::AAAA("2001:db8::25".parse::<std::net::Ipv6Addr>().unwrap().into())),
+            (RecordType::TXT, RData::TXT(hickory_proto::rr::rdata::TXT::new(vec!["v=spf1 -all".to_string()]))),
+        ];
+
+        for (record_type, rdata) in &records {
+            let mut message = Message::new();
+            message.set_message_type(hickory_proto::op::MessageType::Response)
+                .add_query(Query::query(name.clone(), *record_type))
+                .add_answer(Record::from_rdata(name.clone(), 300, rdata.clone()));
+            let key = CacheKey::new(name.clone(), *record_type, DNSClass::IN);
+            cache.put(&key, &message, 300).await.unwrap();
+        }
+
+        for (record_type, rdata) in &records {
+            for lookup in ["mail.example.com.", "MAIL.EXAMPLE.COM.", "mAiL.eXaMpLe.CoM."] {
+                let key = CacheKey::new(Name::from_ascii(lookup).unwrap(), *record_type, DNSClass::IN);
+                let cached = cache.get(&key).await
+                    .unwrap_or_else(|| panic!("{} {:?} should hit the cache", lookup, record_type));
+                assert_eq!(cached.answers()[0].data(), Some(rdata));
+            }
+        }
+
+        // 记录类型仍然区分
+        let key = CacheKey::new(Name::from_ascii("mail.example.com.").unwrap(), RecordType::A, DNSClass::IN);
+        assert!(cache.get(&key).await.is_none());
+        assert_eq!(cache.len().await, 3);
+
+        info!("Test completed: test_cache_key_mixed_case_mx_aaaa_txt");
+    }
+
+    #[tokio::test]
+    async fn test_doh_cache_hit_across_name_case() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::{header, Request, StatusCode};
+        use tower::util::ServiceExt;
+        use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+        use oxide_wdns::server::config::ServerConfig;
+        use oxide_wdns::server::doh_handler::doh_routes;
+        use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+        use crate::server::test_helpers::server_state;
+
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_cache_hit_across_name_case");
+
+        let (mock_server, _) = setup_mock_doh_server(std::net::Ipv4Addr::new(192, 0, 2, 7)).await;
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: true
+        "#, mock_server.uri());
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        let app = doh_routes(server_state(config).await);
+
+        for domain in ["Example.COM", "example.com", "EXAMPLE.com"] {
+            let query = create_test_query(domain, RecordType::A);
+            let response = app.clone()
+                .oneshot(
+                    Request::post("/dns-query")
+                        .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+                        .body(Body::from(query.to_vec().unwrap()))
+                        .unwrap()
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let response = Message::from_vec(&body).unwrap();
+
+            // 缓存命中的响应保留客户端查询名的大小写
+            assert_eq!(response.queries()[0].name().to_ascii(), format!("{}.", domain));
+            assert_eq!(response.answers().len(), 1);
+        }
+
+        // JSON API 的记录类型参数不区分大小写，同样命中缓存
+        let response = app
+            .oneshot(Request::get("/dns-query?name=EXAMPLE.COM&type=a").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+
+        info!("Test completed: test_doh_cache_hit_across_name_case");
+    }
+
+    #[tokio::test]
+    async fn test_doh_cache_hit_echoes_requesting_client_id() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::{header, Request, StatusCode};
+        use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_ENGINE};
+        use tower::util::ServiceExt;
+        use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+        use oxide_wdns::server::config::ServerConfig;
+        use oxide_wdns::server::doh_handler::doh_routes;
+        use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+        use crate::server::test_helpers::server_state;
+
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_cache_hit_echoes_requesting_client_id");
+
+        let (mock_server, _) = setup_mock_doh_server(std::net::Ipv4Addr::new(192, 0, 2, 8)).await;
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: true
+        "#, mock_server.uri());
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        let state = server_state(config).await;
+        let cache = state.cache.clone();
+        let app = doh_routes(state);
+
+        let mut answers = Vec::new();
+        for (id, use_get) in [(1111, false), (2222, false), (3333, true)] {
+            let mut query = create_test_query("example.com", RecordType::A);
+            query.set_id(id);
+            let request = if use_get {
+                Request::get(format!("/dns-query?dns={}", BASE64_ENGINE.encode(query.to_vec().unwrap())))
+                    .body(Body::empty())
+                    .unwrap()
+            } else {
+                Request::post("/dns-query")
+                    .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+                    .body(Body::from(query.to_vec().unwrap()))
+                    .unwrap()
+            };
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let response = Message::from_vec(&body).unwrap();
+
+            // 每个响应都回显当前请求的消息 ID
+            assert_eq!(response.id(), id);
+            answers.push(response.answers().to_vec());
+        }
+
+        // 后两次请求命中缓存，响应内容相同
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+        assert!(answers.windows(2).all(|pair| pair[0] == pair[1]));
+
+        // 缓存条目不保存首个请求方的消息 ID
+        let key = CacheKey::new(Name::from_ascii("example.com.").unwrap(), RecordType::A, DNSClass::IN);
+        assert_eq!(cache.get(&key).await.unwrap().id(), 0);
+
+        info!("Test completed: test_doh_cache_hit_echoes_requesting_client_id");
+    }
+
     // 持久化缓存测试
     #[tokio::test(flavor = "multi_thread")]
     async fn test_persistent_cache_save_and_load() {
@@ -1008,6 +1328,7 @@ mod tests {
                 shutdown_save_timeout_secs: 5,
                 periodic: Default::default(),
             },
+            ..CacheConfig::default()
         };
         let cache = DnsCache::new(config);
         
@@ -0,0 +1,155 @@
+// tests/server/cname_flatten_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::rdata::{A, CNAME};
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::test_with_server_config;
+
+    const FIRST_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const SECOND_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 2);
+
+    fn a_record(owner: &str, ttl: u32, ip: Ipv4Addr) -> Record {
+        Record::from_rdata(Name::from_str(owner).unwrap(), ttl, RData::A(A(ip)))
+    }
+
+    fn cname_record(owner: &str, ttl: u32, target: &str) -> Record {
+        Record::from_rdata(Name::from_str(owner).unwrap(), ttl, RData::CNAME(CNAME(Name::from_str(target).unwrap())))
+    }
+
+    // www.example.com -> cdn.example.net -> edge.cdn.example.net，记录乱序且有重复
+    fn multi_hop_answers() -> Vec<Record> {
+        vec![
+            a_record("edge.cdn.example.net.", 300, FIRST_IP),
+            cname_record("cdn.example.net.", 120, "edge.cdn.example.net."),
+            a_record("edge.cdn.example.net.", 300, SECOND_IP),
+            cname_record("www.example.com.", 600, "cdn.example.net."),
+            a_record("edge.cdn.example.net.", 300, FIRST_IP),
+            cname_record("cdn.example.net.", 120, "edge.cdn.example.net."),
+        ]
+    }
+
+    // 上游对所有查询返回指定的应答记录
+    async fn mount_answers(mock: &MockServer, answers: Vec<Record>) {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(move |request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let mut response = create_test_response(&query, FIRST_IP);
+                response.take_answers();
+                response.add_answers(answers.clone());
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(mock)
+            .await;
+    }
+
+    async fn query_answers(server_addr: &str, name: &str) -> Vec<Record> {
+        let query = create_test_query(name, RecordType::A);
+        let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let body = reqwest::get(url).await.unwrap().bytes().await.unwrap();
+        Message::from_vec(&body).unwrap().take_answers()
+    }
+
+    fn flatten(strip_chain: bool) -> impl FnOnce(&mut ServerConfig) {
+        move |config| {
+            config.dns.flatten_cname = true;
+            config.dns.flatten_cname_strip_chain = strip_chain;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flatten_cname_strips_chain() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_flatten_cname_strips_chain");
+
+        test_with_server_config(flatten(true), |server_addr, mock| async move {
+            mount_answers(&mock, multi_hop_answers()).await;
+
+            // 只剩去重后的地址记录，所有者改写为查询名称，TTL 取链上最小值
+            let answers = query_answers(&server_addr, "www.example.com").await;
+            assert_eq!(answers.len(), 2);
+            for (record, ip) in answers.iter().zip([FIRST_IP, SECOND_IP]) {
+                assert_eq!(record.name(), &Name::from_str("www.example.com.").unwrap());
+                assert_eq!(record.data(), Some(&RData::A(A(ip))));
+                assert_eq!(record.ttl(), 120);
+            }
+        }).await;
+
+        info!("Test completed: test_flatten_cname_strips_chain");
+    }
+
+    #[tokio::test]
+    async fn test_flatten_cname_keeps_ordered_chain() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_flatten_cname_keeps_ordered_chain");
+
+        test_with_server_config(flatten(false), |server_addr, mock| async move {
+            mount_answers(&mock, multi_hop_answers()).await;
+
+            // 保留链时按链的顺序排列并去除重复记录
+            let answers = query_answers(&server_addr, "www.example.com").await;
+            let summary: Vec<(String, RecordType)> = answers.iter()
+                .map(|record| (record.name().to_string(), record.record_type()))
+                .collect();
+            assert_eq!(summary, vec![
+                ("www.example.com.".to_string(), RecordType::CNAME),
+                ("cdn.example.net.".to_string(), RecordType::CNAME),
+                ("edge.cdn.example.net.".to_string(), RecordType::A),
+                ("edge.cdn.example.net.".to_string(), RecordType::A),
+            ]);
+            assert_eq!(answers[0].ttl(), 600);
+        }).await;
+
+        info!("Test completed: test_flatten_cname_keeps_ordered_chain");
+    }
+
+    #[tokio::test]
+    async fn test_flatten_cname_leaves_unusual_chains() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_flatten_cname_leaves_unusual_chains");
+
+        // CNAME 环路：保持上游响应不变
+        let looped = vec![
+            cname_record("loop.example.com.", 300, "a.example.net."),
+            cname_record("a.example.net.", 300, "b.example.net."),
+            cname_record("b.example.net.", 300, "a.example.net."),
+            a_record("b.example.net.", 300, FIRST_IP),
+        ];
+        test_with_server_config(flatten(true), |server_addr, mock| async move {
+            mount_answers(&mock, looped.clone()).await;
+            assert_eq!(query_answers(&server_addr, "loop.example.com").await, looped);
+        }).await;
+
+        // 链没有以地址记录结束：保持不变，不发起额外查询
+        let dangling = vec![cname_record("dangling.example.com.", 300, "target.example.net.")];
+        test_with_server_config(flatten(true), |server_addr, mock| async move {
+            mount_answers(&mock, dangling.clone()).await;
+            assert_eq!(query_answers(&server_addr, "dangling.example.com").await, dangling);
+            assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+        }).await;
+
+        // 默认不展平
+        assert!(!ServerConfig::default().dns.flatten_cname);
+        test_with_server_config(|_| {}, |server_addr, mock| async move {
+            mount_answers(&mock, multi_hop_answers()).await;
+            assert_eq!(query_answers(&server_addr, "www.example.com").await, multi_hop_answers());
+        }).await;
+
+        info!("Test completed: test_flatten_cname_leaves_unusual_chains");
+    }
+}
@@ -0,0 +1,221 @@
+// tests/server/compression_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::Ipv4Addr;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, Response, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_JSON, CONTENT_TYPE_DNS_MESSAGE};
+    use oxide_wdns::server::config::{CompressionAlgorithm, ServerConfig};
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+    use crate::server::test_helpers::extract_ip_addresses;
+
+    // gzip 数据的魔数
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    fn compression_config(upstream_uri: &str, enabled: bool) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          compression:
+            enabled: {}
+            min_size: 16
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+        "#, enabled, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    async fn build_app(config: ServerConfig) -> Router {
+        DoHServer::new(config, false).build_application_components().await.unwrap().app
+    }
+
+    async fn get(app: &Router, uri: &str, accept_encoding: &str) -> Response<Body> {
+        app.clone()
+            .oneshot(Request::get(uri)
+                .header(header::ACCEPT_ENCODING, accept_encoding)
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn content_encoding(response: &Response<Body>) -> Option<&str> {
+        response.headers().get(header::CONTENT_ENCODING).map(|value| value.to_str().unwrap())
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    async fn post_wire(app: &Router, body: Vec<u8>, content_encoding: &str) -> Response<Body> {
+        app.clone()
+            .oneshot(Request::post("/dns-query")
+                .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+                .header(header::CONTENT_ENCODING, content_encoding)
+                .body(Body::from(body))
+                .unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn wireformat_uri() -> String {
+        let query = create_test_query("example.com", RecordType::A);
+        format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_compression_applies_to_metrics_and_json() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_compression_applies_to_metrics_and_json");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = build_app(compression_config(&mock_server.uri(), true)).await;
+
+        // 指标响应按 Accept-Encoding 压缩
+        let response = get(&app, "/metrics", "gzip").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(content_encoding(&response), Some("gzip"));
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body[..2], GZIP_MAGIC);
+
+        let response = get(&app, "/metrics", "br").await;
+        assert_eq!(content_encoding(&response), Some("br"));
+
+        // JSON API 响应同样压缩
+        let response = get(&app, "/dns-query?name=example.com&type=A", "gzip").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), CONTENT_TYPE_DNS_JSON);
+        assert_eq!(content_encoding(&response), Some("gzip"));
+
+        // 未声明 Accept-Encoding 时不压缩
+        let response = app.clone().oneshot(Request::get("/metrics").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(content_encoding(&response), None);
+
+        info!("Test completed: test_compression_applies_to_metrics_and_json");
+    }
+
+    #[tokio::test]
+    async fn test_compression_skips_dns_message() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_compression_skips_dns_message");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = build_app(compression_config(&mock_server.uri(), true)).await;
+
+        // wireformat 响应始终保持原样
+        let response = get(&app, &wireformat_uri(), "gzip, br").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), CONTENT_TYPE_DNS_MESSAGE);
+        assert_eq!(content_encoding(&response), None);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(Message::from_vec(&body).is_ok());
+
+        // 未启用压缩时指标响应同样保持原样
+        let app = build_app(compression_config(&mock_server.uri(), false)).await;
+        let response = get(&app, "/metrics", "gzip").await;
+        assert_eq!(content_encoding(&response), None);
+
+        info!("Test completed: test_compression_skips_dns_message");
+    }
+
+    #[tokio::test]
+    async fn test_compression_records_savings() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_compression_records_savings");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = build_app(compression_config(&mock_server.uri(), true)).await;
+        let uncompressed = METRICS.response_compression_bytes_total().with_label_values(&["uncompressed"]);
+        let compressed = METRICS.response_compression_bytes_total().with_label_values(&["compressed"]);
+        let before = (uncompressed.get(), compressed.get());
+
+        let response = get(&app, "/dns-query?name=example.com&type=A", "gzip").await;
+        assert_eq!(content_encoding(&response), Some("gzip"));
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut decoded = Vec::new();
+        GzDecoder::new(&body[..]).read_to_end(&mut decoded).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&decoded).is_ok());
+
+        // 指标为全局计数，其他测试可能同时增加
+        assert!(uncompressed.get() - before.0 >= decoded.len() as u64);
+        assert!(compressed.get() - before.1 >= body.len() as u64);
+
+        info!("Test completed: test_compression_records_savings");
+    }
+
+    #[tokio::test]
+    async fn test_request_decompression() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_request_decompression");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let mut config = compression_config(&mock_server.uri(), false);
+        config.http.request_decompression = true;
+        config.http.max_request_body_size = 512;
+        let app = build_app(config.clone()).await;
+        let query = create_test_query("example.com", RecordType::A).to_vec().unwrap();
+
+        // gzip 压缩的查询解压后正常处理
+        let response = post_wire(&app, gzip(&query), "gzip").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(extract_ip_addresses(&Message::from_vec(&body).unwrap()), vec!["192.0.2.1".to_string()]);
+
+        // 解压后超过大小上限的请求体返回 413
+        let response = post_wire(&app, gzip(&[0u8; 4096]), "gzip").await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // 不支持的编码返回 415
+        let response = post_wire(&app, query.clone(), "zstd").await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        // 未启用时压缩的请求体按原样解析，不是有效的 DNS 消息
+        config.http.request_decompression = false;
+        let app = build_app(config).await;
+        let response = post_wire(&app, gzip(&query), "gzip").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(!ServerConfig::default().http.request_decompression);
+
+        info!("Test completed: test_request_decompression");
+    }
+
+    #[test]
+    fn test_compression_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_compression_config_validation");
+
+        let default_config = ServerConfig::default();
+        assert!(!default_config.http.compression.enabled);
+        assert_eq!(default_config.http.compression.algorithms, vec![CompressionAlgorithm::Gzip, CompressionAlgorithm::Brotli]);
+        assert_eq!(default_config.http.compression.min_size, 256);
+
+        let mut config = compression_config("https://dns.example.com", true);
+        assert!(config.test().is_ok());
+        config.http.compression.algorithms.clear();
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_compression_config_validation");
+    }
+}
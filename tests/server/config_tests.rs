@@ -3,7 +3,9 @@
 #[cfg(test)]
 mod tests {
     use oxide_wdns::server::config::{ServerConfig, ResolverProtocol, MatchType};
+    use oxide_wdns::server::config_template::generate_default_config;
     use oxide_wdns::common::consts::{DEFAULT_CACHE_SIZE,DEFAULT_HTTP_CLIENT_AGENT};
+    use std::net::SocketAddr;
     use std::path::PathBuf;
     use std::fs::File;
     use std::io::Write;
@@ -352,8 +354,8 @@ dns_resolver:
         info!("Validating default values...");
 
         // HTTP服务器默认值
-        assert!(config.http.timeout > 0, "Timeout should have a default value > 0");
-        info!(config.http.timeout, "Validated http.timeout default value.");
+        assert!(config.http.request_timeout > 0, "Request timeout should have a default value > 0");
+        info!(config.http.request_timeout, "Validated http.request_timeout default value.");
         // 注意：默认情况下速率限制可能已启用或禁用，这取决于实际实现
         // 这里假设默认禁用，如果实现变化，需要调整断言
         // assert!(!config.http.rate_limit.enabled, "Rate limit should be disabled by default");
@@ -617,6 +619,87 @@ dns_resolver:
         }
         info!("Test finished: test_config_validate_regex_compile");
     }
+
+    #[test]
+    fn test_config_multiple_listen_addrs() {
+        // 启用 tracing 日志
+        let _guard = setup_test_tracing();
+        info!("Starting test: test_config_multiple_listen_addrs");
+
+        let config_with = |listen: &str| -> ServerConfig {
+            serde_yaml::from_str(&format!(r#"
+http_server:
+  listen_addr: {}
+dns_resolver:
+  upstream:
+    resolvers:
+      - address: "8.8.8.8:53"
+        protocol: udp
+"#, listen)).unwrap()
+        };
+
+        // 单个地址与地址列表均可解析
+        let single = config_with(r#""127.0.0.1:8053""#);
+        assert_eq!(single.http.listen_addr.len(), 1);
+        assert_eq!(single.http.listen_addr.primary(), "127.0.0.1:8053".parse().unwrap());
+        assert!(single.test().is_ok());
+
+        let multi = config_with(r#"["127.0.0.1:8053", "[::1]:8053", "192.168.1.10:8053"]"#);
+        let addrs: Vec<SocketAddr> = multi.http.listen_addr.iter().copied().collect();
+        assert_eq!(addrs, vec![
+            "127.0.0.1:8053".parse().unwrap(),
+            "[::1]:8053".parse().unwrap(),
+            "192.168.1.10:8053".parse().unwrap(),
+        ]);
+        assert_eq!(multi.http.listen_addr.primary(), addrs[0]);
+        assert!(multi.test().is_ok());
+
+        // 序列化后保持原有形式
+        let yaml = serde_yaml::to_string(&single.http).unwrap();
+        assert!(yaml.contains("listen_addr: 127.0.0.1:8053"), "{}", yaml);
+        let reparsed: ServerConfig = serde_yaml::from_str(&serde_yaml::to_string(&multi).unwrap()).unwrap();
+        assert_eq!(reparsed.http.listen_addr, multi.http.listen_addr);
+
+        // 空列表与重复地址无效
+        assert!(config_with("[]").test().is_err());
+        let err = config_with(r#"["127.0.0.1:8053", "127.0.0.1:8053"]"#).test().unwrap_err();
+        assert!(err.to_string().contains("127.0.0.1:8053"), "{}", err);
+
+        // 管理与指标监听地址不能与任一 DoH 监听地址相同
+        let mut conflict = multi.clone();
+        conflict.http.admin_listen_addr = Some("[::1]:8053".parse().unwrap());
+        assert!(conflict.test().is_err());
+        let mut conflict = multi.clone();
+        conflict.http.metrics_listen_addr = Some("192.168.1.10:8053".parse().unwrap());
+        assert!(conflict.test().is_err());
+
+        info!("Test finished: test_config_multiple_listen_addrs");
+    }
+
+    #[test]
+    fn test_generate_default_config() {
+        let _guard = setup_test_tracing();
+        info!("Starting test: test_generate_default_config");
+
+        let generated = generate_default_config().unwrap();
+
+        // 生成的配置可以直接加载，并且与内置默认值一致
+        let config: ServerConfig = serde_yaml::from_str(&generated).unwrap();
+        assert!(config.test().is_ok());
+        assert_eq!(
+            serde_yaml::to_value(&config).unwrap(),
+            serde_yaml::to_value(ServerConfig::default()).unwrap()
+        );
+        assert_eq!(config.dns.upstream.resolvers.len(), 2);
+
+        // 配置项附带来自 config.default.yaml 的注释，未设置的可选项以注释形式给出
+        assert!(generated.contains("# --- HTTP 服务器配置 ---\nhttp_server:\n"));
+        assert!(generated.contains("  # 单个连接处理的最大请求数，达到后关闭连接（0 表示不限制）\n  max_requests_per_connection: 0\n"));
+        assert!(generated.contains("  # tls:\n"));
+        assert!(generated.contains("    # 是否启用 DNSSEC 验证。此为全局默认设置。\n"));
+        info!("Test finished: test_generate_default_config");
+    }
+
 }
 
 #[cfg(test)]
@@ -742,4 +825,4 @@ dns_resolver:
         assert_eq!(persistence.periodic.interval_secs, 1800);
         info!("Test finished: test_parse_persistence_cache_config_from_yaml");
     }
-} 
\ No newline at end of file
+}
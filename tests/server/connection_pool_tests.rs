@@ -0,0 +1,132 @@
+// tests/server/connection_pool_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use futures::future::join_all;
+    use hickory_proto::rr::RecordType;
+    use reqwest::Client;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::upstream::{UpstreamManager, UpstreamSelection};
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::mount_doh_answer_with_delay;
+
+    const UPSTREAM_DELAY: Duration = Duration::from_millis(300);
+
+    // 创建包含两个上游组的配置：limited 组限制为 1 个并发连接，open 组继承全局连接池
+    fn pool_config(upstream_uri: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{uri}/dns-query"
+                protocol: doh
+            query_timeout: 5
+          http_client:
+            pool:
+              idle_timeout: 45
+              max_idle_connections: 8
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "limited"
+                resolvers:
+                  - address: "{uri}/dns-query"
+                    protocol: doh
+                connection_pool:
+                  max_idle_connections: 2
+                  max_connections: 1
+              - name: "open"
+                resolvers:
+                  - address: "{uri}/dns-query"
+                    protocol: doh
+        "#, uri = upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 向指定上游组并发发送查询，返回总耗时
+    async fn concurrent_queries(upstream: &UpstreamManager, group: &str, count: usize) -> Duration {
+        let started = Instant::now();
+        let queries = (0..count).map(|i| {
+            let query = create_test_query(&format!("host{}.example.com", i), RecordType::A);
+            async move {
+                upstream.resolve(&query, UpstreamSelection::Group(group.to_string()), None, None).await
+            }
+        });
+        for result in join_all(queries).await {
+            assert!(result.is_ok());
+        }
+        started.elapsed()
+    }
+
+    #[test]
+    fn test_connection_pool_inherits_global_settings() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_connection_pool_inherits_global_settings");
+
+        let config = pool_config("https://dns.example.com");
+
+        // 组内设置的字段覆盖全局值，未设置的字段继承 http_client.pool
+        let limited = config.get_effective_connection_pool("limited");
+        assert_eq!(limited.max_idle_connections, 2);
+        assert_eq!(limited.max_connections, 1);
+        assert_eq!(limited.idle_timeout, 45);
+
+        // 未配置 connection_pool 的组完全继承全局连接池配置（默认不限制并发连接数）
+        let open = config.get_effective_connection_pool("open");
+        assert_eq!(open.max_idle_connections, 8);
+        assert_eq!(open.max_connections, 0);
+        assert_eq!(open.idle_timeout, 45);
+
+        info!("Test completed: test_connection_pool_inherits_global_settings");
+    }
+
+    #[tokio::test]
+    async fn test_group_connection_limit_is_isolated() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_group_connection_limit_is_isolated");
+
+        let mock_server = MockServer::start().await;
+        mount_doh_answer_with_delay(&mock_server, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let config = pool_config(&mock_server.uri());
+        let upstream = Arc::new(UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap());
+
+        // limited 组同一时间只允许一个上游请求，三个查询依次完成
+        let limited_elapsed = concurrent_queries(&upstream, "limited", 3).await;
+        assert!(limited_elapsed >= UPSTREAM_DELAY * 3, "limited group took {:?}", limited_elapsed);
+
+        // limited 组饱和时，open 组的查询不受其连接限制影响
+        let (limited_elapsed, open_elapsed) = tokio::join!(
+            concurrent_queries(&upstream, "limited", 3),
+            concurrent_queries(&upstream, "open", 3),
+        );
+        assert!(limited_elapsed >= UPSTREAM_DELAY * 3, "limited group took {:?}", limited_elapsed);
+        assert!(open_elapsed < UPSTREAM_DELAY * 3, "open group took {:?}", open_elapsed);
+
+        info!("Test completed: test_group_connection_limit_is_isolated");
+    }
+
+    #[test]
+    fn test_connection_pool_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_connection_pool_validation");
+
+        let mut config = pool_config("https://dns.example.com");
+        assert!(config.test().is_ok());
+
+        // 组内空闲超时必须大于 0
+        config.dns.routing.upstream_groups[0].connection_pool.as_mut().unwrap().idle_timeout_secs = Some(0);
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_connection_pool_validation");
+    }
+}
@@ -0,0 +1,288 @@
+// tests/server/consul_discovery_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::{Arc, Mutex};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use reqwest::Client;
+    use serde_json::{json, Value};
+    use tracing::info;
+    use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+    use oxide_wdns::server::config::{ConsulUpstreamDiscoveryConfig, ResolverProtocol, ServerConfig};
+    use oxide_wdns::server::consul::health_service_url;
+    use oxide_wdns::server::error::ServerError;
+    use oxide_wdns::server::upstream::{TransportFuture, UpstreamManager, UpstreamSelection, UpstreamTransport};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+
+    const SERVICE_NAME: &str = "doh-resolvers";
+    const STATIC_URL: &str = "https://static.example.com/dns-query";
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+
+    // 创建 Consul 健康检查接口返回的服务实例
+    fn entry(node_address: &str, service_address: &str, port: u16) -> Value {
+        json!({
+            "Node": { "Node": "node", "Address": node_address },
+            "Service": { "ID": "doh", "Service": SERVICE_NAME, "Address": service_address, "Port": port },
+            "Checks": [],
+        })
+    }
+
+    // 启动返回当前健康实例列表的模拟 Consul 服务
+    async fn setup_consul_server(entries: Arc<Mutex<Vec<Value>>>) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path(format!("/v1/health/service/{}", SERVICE_NAME)))
+            .and(matchers::query_param("passing", "true"))
+            .respond_with(move |_: &wiremock::Request| {
+                ResponseTemplate::new(200).set_body_json(Value::Array(entries.lock().unwrap().clone()))
+            })
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    // 创建全局上游启用 Consul 发现的服务器配置，静态上游作为后备
+    fn consul_server_config(consul_addr: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}"
+                protocol: doh
+            query_timeout: 3
+            failure_ratio_threshold: 0.5
+            rolling_window_secs: 60
+            consul_upstream_discovery:
+              consul_addr: "{}"
+              service_name: "{}"
+        "#, STATIC_URL, consul_addr, SERVICE_NAME);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 全局上游的 Consul 发现配置
+    fn consul_mut(config: &mut ServerConfig) -> &mut ConsulUpstreamDiscoveryConfig {
+        config.dns.upstream.consul_upstream_discovery.as_mut().unwrap()
+    }
+
+    // 传输替身：发现的上游返回 SERVFAIL，其他上游正常应答，并记录每次查询的上游
+    #[derive(Default)]
+    struct RecordingTransport {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl RecordingTransport {
+        // 取出并清空已记录的查询
+        fn take_calls(&self) -> Vec<String> {
+            std::mem::take(&mut *self.calls.lock().unwrap())
+        }
+    }
+
+    impl UpstreamTransport for RecordingTransport {
+        fn exchange<'a>(&'a self, upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+            self.calls.lock().unwrap().push(upstream.to_string());
+            let mut response = create_test_response(query, ANSWER_IP);
+            if upstream != STATIC_URL {
+                response.take_answers();
+                response.set_response_code(ResponseCode::ServFail);
+            }
+            Box::pin(async move { Ok::<_, ServerError>(response) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consul_discovery_adds_and_removes_upstreams() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_consul_discovery_adds_and_removes_upstreams");
+
+        let entries = Arc::new(Mutex::new(vec![
+            entry("10.0.0.1", "doh1.example.com", 8443),
+            // 未设置服务地址时使用节点地址，IPv6 地址加方括号
+            entry("2001:db8::1", "", 443),
+            // 重复的实例只保留一个
+            entry("10.0.0.3", "doh1.example.com", 8443),
+        ]));
+        let consul = setup_consul_server(entries.clone()).await;
+        let config = consul_server_config(&consul.uri());
+        let upstream = Arc::new(UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap());
+
+        // 启动时同步发现，按 Consul 返回的顺序
+        upstream.start_consul_discovery().await;
+        assert_eq!(upstream.discovered_group_resolvers(None), vec![
+            "https://doh1.example.com:8443/dns-query".to_string(),
+            "https://[2001:db8::1]:443/dns-query".to_string(),
+        ]);
+
+        // 不再通过健康检查的实例在刷新后被移除，新增的实例被加入
+        *entries.lock().unwrap() = vec![entry("10.0.0.4", "doh4.example.com", 443)];
+        assert_eq!(upstream.refresh_consul_upstreams(None).await.unwrap(), 1);
+        assert_eq!(upstream.discovered_group_resolvers(None), vec!["https://doh4.example.com:443/dns-query".to_string()]);
+
+        // 没有健康实例时清空发现的上游，回退到静态上游
+        entries.lock().unwrap().clear();
+        assert_eq!(upstream.refresh_consul_upstreams(None).await.unwrap(), 0);
+        assert!(upstream.discovered_group_resolvers(None).is_empty());
+
+        // Consul 不可用时保留当前上游
+        *entries.lock().unwrap() = vec![entry("10.0.0.5", "doh5.example.com", 443)];
+        upstream.refresh_consul_upstreams(None).await.unwrap();
+        consul.reset().await;
+        assert!(upstream.refresh_consul_upstreams(None).await.is_err());
+        assert_eq!(upstream.discovered_group_resolvers(None), vec!["https://doh5.example.com:443/dns-query".to_string()]);
+
+        upstream.stop_background_tasks();
+        info!("Test completed: test_consul_discovery_adds_and_removes_upstreams");
+    }
+
+    #[tokio::test]
+    async fn test_consul_discovery_health_check_tag_and_group() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_consul_discovery_health_check_tag_and_group");
+
+        let consul = MockServer::start().await;
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path(format!("/v1/health/service/{}", SERVICE_NAME)))
+            .and(matchers::query_param("passing", "true"))
+            .and(matchers::query_param("tag", "doh"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([entry("10.0.0.1", "10.0.0.10", 443)])))
+            .expect(1)
+            .mount(&consul)
+            .await;
+
+        // 只有组内配置了 Consul 发现，组内可以不配置静态上游
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}"
+                protocol: doh
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "internal"
+                resolvers: []
+                consul_upstream_discovery:
+                  consul_addr: "{}"
+                  service_name: "{}"
+                  health_check_tag: "doh"
+        "#, STATIC_URL, consul.uri(), SERVICE_NAME);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        assert!(config.test().is_ok());
+        let upstream = Arc::new(UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap());
+
+        upstream.start_consul_discovery().await;
+        assert_eq!(upstream.discovered_group_resolvers(Some("internal")), vec!["https://10.0.0.10:443/dns-query".to_string()]);
+        // 全局上游不继承组的 Consul 发现
+        assert!(upstream.discovered_group_resolvers(None).is_empty());
+        assert!(upstream.refresh_consul_upstreams(None).await.is_err());
+
+        upstream.stop_background_tasks();
+        info!("Test completed: test_consul_discovery_health_check_tag_and_group");
+    }
+
+    #[tokio::test]
+    async fn test_consul_discovery_falls_back_to_static_when_unhealthy() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_consul_discovery_falls_back_to_static_when_unhealthy");
+
+        let entries = Arc::new(Mutex::new(vec![entry("10.0.0.1", "doh1.example.com", 443)]));
+        let consul = setup_consul_server(entries).await;
+        let transport = Arc::new(RecordingTransport::default());
+        let config = consul_server_config(&consul.uri());
+        let upstream = UpstreamManager::with_transport(Arc::new(config), transport.clone()).await.unwrap();
+        upstream.refresh_consul_upstreams(None).await.unwrap();
+
+        // 发现的上游优先于静态上游
+        let query = create_test_query("example.com", RecordType::A);
+        for _ in 0..10 {
+            let response = upstream.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+            assert_eq!(response.response_code(), ResponseCode::ServFail);
+        }
+        assert!(transport.take_calls().iter().all(|call| call == "https://doh1.example.com:443/dns-query"));
+
+        // 发现的上游全部降级后回退到静态上游
+        let response = upstream.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(transport.take_calls(), vec![STATIC_URL.to_string()]);
+
+        info!("Test completed: test_consul_discovery_falls_back_to_static_when_unhealthy");
+    }
+
+    #[test]
+    fn test_consul_discovery_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_consul_discovery_config_validation");
+
+        let mut config = consul_server_config("http://127.0.0.1:8500");
+        let consul = config.dns.upstream.consul_upstream_discovery.clone().unwrap();
+        assert_eq!(consul.refresh_interval_secs, 30);
+        assert_eq!(consul.protocol, ResolverProtocol::Doh);
+        assert!(consul.health_check_tag.is_none());
+        assert!(config.test().is_ok());
+
+        let check = |update: &dyn Fn(&mut ServerConfig)| {
+            let mut invalid = config.clone();
+            update(&mut invalid);
+            invalid.test().is_err()
+        };
+
+        // Consul 地址必须是 http/https URL
+        assert!(check(&|c| consul_mut(c).consul_addr = "127.0.0.1:8500".to_string()));
+        // 服务名称不能为空或包含路径字符
+        assert!(check(&|c| consul_mut(c).service_name = String::new()));
+        assert!(check(&|c| consul_mut(c).service_name = "doh/../x".to_string()));
+        // 目前只支持 DoH 协议
+        assert!(check(&|c| consul_mut(c).protocol = ResolverProtocol::Udp));
+        // 刷新间隔不能过短
+        assert!(check(&|c| consul_mut(c).refresh_interval_secs = 1));
+        // 标签不能为空
+        assert!(check(&|c| consul_mut(c).health_check_tag = Some(String::new())));
+        // 不能与 SRV 发现同时启用
+        assert!(check(&|c| {
+            c.dns.upstream.discover_via_srv = true;
+            c.dns.upstream.srv_name = "_dns-query._tcp.example.com.".to_string();
+        }));
+
+        consul_mut(&mut config).refresh_interval_secs = 60;
+        assert!(config.test().is_ok());
+
+        info!("Test completed: test_consul_discovery_config_validation");
+    }
+
+    #[test]
+    fn test_consul_health_service_url_encodes_service_name() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_consul_health_service_url_encodes_service_name");
+
+        let mut consul = consul_server_config("http://127.0.0.1:8500/").dns.upstream.consul_upstream_discovery.unwrap();
+        assert_eq!(
+            health_service_url(&consul).unwrap().as_str(),
+            format!("http://127.0.0.1:8500/v1/health/service/{}", SERVICE_NAME)
+        );
+
+        // 服务名称作为单个路径段编码，特殊字符不会改变请求路径或查询参数
+        for (name, encoded) in [("doh resolvers", "doh%20resolvers"), ("doh/../x?y#z", "doh%2F..%2Fx%3Fy%23z"), ("100%", "100%25")] {
+            consul.service_name = name.to_string();
+            let url = health_service_url(&consul).unwrap();
+            assert_eq!(url.path(), format!("/v1/health/service/{}", encoded), "{}", name);
+            assert!(url.query().is_none() && url.fragment().is_none());
+        }
+
+        // Consul 地址带有路径前缀时保留前缀
+        consul.consul_addr = "https://consul.example.com/proxy/".to_string();
+        consul.service_name = SERVICE_NAME.to_string();
+        assert_eq!(health_service_url(&consul).unwrap().path(), format!("/proxy/v1/health/service/{}", SERVICE_NAME));
+
+        info!("Test completed: test_consul_health_service_url_encodes_service_name");
+    }
+}
@@ -0,0 +1,102 @@
+// tests/server/content_negotiation_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use reqwest::StatusCode;
+    use tracing::info;
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_JSON, CONTENT_TYPE_DNS_MESSAGE};
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{extract_ip_addresses, mount_doh_answer, test_with_server};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+
+    // 期望的协商结果
+    #[derive(Debug, Clone, Copy)]
+    enum Expected {
+        Wire,
+        Json,
+        NotAcceptable,
+    }
+
+    #[tokio::test]
+    async fn test_get_accept_header_content_negotiation_matrix() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_get_accept_header_content_negotiation_matrix");
+
+        test_with_server(|server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+
+            let query = create_test_query("example.com", RecordType::A);
+            let dns_style = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+            let name_style = format!("{}/dns-query?name=example.com&type=A", server_addr);
+
+            // (Accept 头, ?dns= 的期望结果, ?name= 的期望结果)
+            let matrix = [
+                (None, Expected::Wire, Expected::Json),
+                (Some("*/*"), Expected::Wire, Expected::Json),
+                (Some("application/*"), Expected::Wire, Expected::Json),
+                (Some(CONTENT_TYPE_DNS_MESSAGE), Expected::Wire, Expected::Wire),
+                (Some(CONTENT_TYPE_DNS_JSON), Expected::Json, Expected::Json),
+                (Some("application/json"), Expected::Json, Expected::Json),
+                (Some("text/html, application/dns-json;q=0.9"), Expected::Json, Expected::Json),
+                (Some("application/dns-json;q=0.5, application/dns-message"), Expected::Wire, Expected::Wire),
+                (Some("application/dns-message;q=0, */*"), Expected::Json, Expected::Json),
+                (Some("application/dns-json, application/dns-message"), Expected::Wire, Expected::Json),
+                (Some("text/html"), Expected::NotAcceptable, Expected::NotAcceptable),
+                (Some("application/dns-message;q=0, application/dns-json;q=0"), Expected::NotAcceptable, Expected::NotAcceptable),
+            ];
+
+            let client = reqwest::Client::new();
+            for (accept, dns_expected, name_expected) in matrix {
+                for (url, expected) in [(&dns_style, dns_expected), (&name_style, name_expected)] {
+                    let mut request = client.get(url);
+                    if let Some(accept) = accept {
+                        request = request.header("Accept", accept);
+                    }
+                    let response = request.send().await.unwrap();
+                    let status = response.status();
+                    let content_type = response.headers().get("Content-Type")
+                        .map(|v| v.to_str().unwrap().to_string())
+                        .unwrap_or_default();
+                    let body = response.bytes().await.unwrap();
+                    let case = format!("{} with Accept {:?}", url, accept);
+
+                    match expected {
+                        Expected::Wire => {
+                            assert_eq!(status, StatusCode::OK, "{}", case);
+                            assert_eq!(content_type, CONTENT_TYPE_DNS_MESSAGE, "{}", case);
+                            let message = Message::from_vec(&body).unwrap();
+                            assert_eq!(extract_ip_addresses(&message), vec![ANSWER_IP.to_string()], "{}", case);
+                        },
+                        Expected::Json => {
+                            assert_eq!(status, StatusCode::OK, "{}", case);
+                            assert_eq!(content_type, CONTENT_TYPE_DNS_JSON, "{}", case);
+                            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                            assert_eq!(json["Answer"][0]["data"], ANSWER_IP.to_string(), "{}", case);
+                        },
+                        Expected::NotAcceptable => {
+                            assert_eq!(status, StatusCode::NOT_ACCEPTABLE, "{}", case);
+                            assert!(content_type.starts_with("text/plain"), "{}", case);
+                        },
+                    }
+                }
+            }
+
+            // 显式的 ct 参数优先于 Accept 头
+            let response = client.get(format!("{}&ct={}", name_style, CONTENT_TYPE_DNS_MESSAGE))
+                .header("Accept", CONTENT_TYPE_DNS_JSON)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["Content-Type"], CONTENT_TYPE_DNS_MESSAGE);
+        }).await;
+
+        info!("Test completed: test_get_accept_header_content_negotiation_matrix");
+    }
+}
@@ -0,0 +1,185 @@
+// tests/server/cors_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 创建启用 CORS 的服务器配置
+    fn cors_config(upstream_uri: &str, allowed_origins: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+          cors:
+            enabled: true
+            allowed_origins: {}
+            max_age: 300
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: false
+        "#, allowed_origins, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 构建预检请求
+    fn preflight_request(path: &str, origin: &str) -> Request<Body> {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri(path)
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    // 构建带 Origin 的 DoH POST 请求
+    fn dns_post_request(origin: &str) -> Request<Body> {
+        let query = create_test_query("example.com", RecordType::A);
+        Request::post("/dns-query")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+            .header(header::ORIGIN, origin)
+            .body(Body::from(query.to_vec().unwrap()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_and_disallowed_origin() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cors_preflight_and_disallowed_origin");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = cors_config(&mock_server.uri(), r#"["https://dash.example.com"]"#);
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        // 允许的来源：预检返回 204 与完整的 Access-Control-Allow-* 头
+        let response = app.clone()
+            .oneshot(preflight_request("/dns-query", "https://dash.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let headers = response.headers();
+        assert_eq!(headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "https://dash.example.com");
+        assert_eq!(headers.get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap(), "GET, POST, OPTIONS");
+        assert_eq!(headers.get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap(), "Content-Type, Accept");
+        assert_eq!(headers.get(header::ACCESS_CONTROL_MAX_AGE).unwrap(), "300");
+        assert_eq!(headers.get(header::VARY).unwrap(), "Origin");
+
+        // 允许的来源：实际请求的响应回显来源
+        let response = app.clone().oneshot(dns_post_request("https://dash.example.com")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "https://dash.example.com");
+
+        // 不允许的来源：预检被拒绝，实际请求的响应不带 CORS 头
+        let response = app.clone()
+            .oneshot(preflight_request("/dns-query", "https://evil.example.net"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        let response = app.clone().oneshot(dns_post_request("https://evil.example.net")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        // 健康检查路由默认不应用 CORS
+        let response = app
+            .oneshot(Request::get("/health").header(header::ORIGIN, "https://dash.example.com").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        info!("Test completed: test_cors_preflight_and_disallowed_origin");
+    }
+
+    #[tokio::test]
+    async fn test_cors_wildcard_origin() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cors_wildcard_origin");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = cors_config(&mock_server.uri(), r#"["*"]"#);
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        // 通配符模式下任意来源均被允许，且不需要 Vary: Origin
+        let response = app.clone()
+            .oneshot(preflight_request("/resolve", "https://anything.example.org"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+        assert!(response.headers().get(header::VARY).is_none());
+
+        let response = app.clone().oneshot(dns_post_request("https://anything.example.org")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+
+        // 未携带 Origin 的请求不受影响
+        let query = create_test_query("example.com", RecordType::A);
+        let response = app
+            .oneshot(
+                Request::post("/dns-query")
+                    .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+                    .body(Body::from(query.to_vec().unwrap()))
+                    .unwrap()
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        info!("Test completed: test_cors_wildcard_origin");
+    }
+
+    #[test]
+    fn test_cors_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cors_config_validation");
+
+        let mut config = cors_config("https://1.1.1.1", r#"["https://dash.example.com", "http://localhost:8080"]"#);
+        assert!(config.test().is_ok());
+
+        // 通配符不能与显式来源混用
+        config.http.cors.allowed_origins.push("*".to_string());
+        assert!(config.test().is_err());
+
+        // 来源不能包含路径
+        config.http.cors.allowed_origins = vec!["https://dash.example.com/app".to_string()];
+        assert!(config.test().is_err());
+
+        // 来源列表不能为空
+        config.http.cors.allowed_origins.clear();
+        assert!(config.test().is_err());
+
+        // 预检缓存时间受限
+        config.http.cors.allowed_origins = vec!["*".to_string()];
+        config.http.cors.max_age = 86401;
+        assert!(config.test().is_err());
+
+        // 默认关闭，默认允许任意来源
+        let default_config = ServerConfig::default();
+        assert!(!default_config.http.cors.enabled);
+        assert_eq!(default_config.http.cors.allowed_origins, vec!["*".to_string()]);
+        assert_eq!(default_config.http.cors.max_age, 600);
+
+        info!("Test completed: test_cors_config_validation");
+    }
+}
@@ -0,0 +1,183 @@
+// tests/server/debug_capture_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Method, Request, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use serde_json::Value;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::DoHServer;
+    use oxide_wdns::server::config::ServerConfig;
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{mock_upstream_config, mount_doh_answer};
+
+    const ADMIN_TOKEN: &str = "capture-secret";
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 7);
+
+    // 启用调试抓包的配置
+    fn capture_config(upstream_uri: &str, size: usize) -> ServerConfig {
+        let mut config = mock_upstream_config(8053, upstream_uri);
+        config.http.admin_token = Some(ADMIN_TOKEN.to_string());
+        config.debug.capture.enabled = true;
+        config.debug.capture.size = size;
+        config.dns.cache.enabled = false;
+        config
+    }
+
+    // 以 POST 发送一条 A 查询
+    async fn send_query(app: &Router, name: &str) {
+        let query = create_test_query(name, RecordType::A);
+        let request = Request::post("/dns-query")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+            .body(Body::from(query.to_vec().unwrap()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // 访问抓包端点，返回状态码与 JSON 响应
+    async fn captures(app: &Router, method: Method, query: &str, token: Option<&str>) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("/api/debug/captures{}", query));
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_debug_capture_records_recent_queries() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_debug_capture_records_recent_queries");
+
+        let mock = MockServer::start().await;
+        mount_doh_answer(&mock, ANSWER_IP).await;
+        let app = DoHServer::new(capture_config(&mock.uri(), 2), false)
+            .build_application_components().await.unwrap().app;
+
+        send_query(&app, "first.example.com").await;
+        send_query(&app, "second.example.com").await;
+        send_query(&app, "third.example.com").await;
+
+        // 需要管理令牌
+        let (status, _) = captures(&app, Method::GET, "", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = captures(&app, Method::GET, "", Some("wrong")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // 缓冲区有界：只保留最近的 2 条，最新的在前
+        let (status, body) = captures(&app, Method::GET, "", Some(ADMIN_TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["size"], 2);
+        assert_eq!(body["count"], 2);
+        let names = body["captures"].as_array().unwrap().iter()
+            .map(|capture| capture["qname"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["third.example.com.", "second.example.com."]);
+
+        // 记录包含原始报文、上游与耗时
+        let capture = &body["captures"][0];
+        assert_eq!(capture["protocol"], "doh-post");
+        assert_eq!(capture["qtype"], "A");
+        assert_eq!(capture["rcode"], "NoError");
+        assert_eq!(capture["cached"], false);
+        assert_eq!(capture["upstream_group"], "global");
+        assert!(capture["resolver"].as_str().unwrap().starts_with(&mock.uri()));
+        assert!(capture["client_ip"].is_string());
+        assert!(capture["timestamp"].is_string());
+        assert!(capture["latency_ms"].as_f64().unwrap() >= 0.0);
+        let query = Message::from_vec(&STANDARD.decode(capture["query"].as_str().unwrap()).unwrap()).unwrap();
+        assert_eq!(query.queries()[0].name().to_ascii(), "third.example.com.");
+        let response = Message::from_vec(&STANDARD.decode(capture["response"].as_str().unwrap()).unwrap()).unwrap();
+        assert_eq!(response.answers().len(), 1);
+
+        // 按查询名过滤，不区分大小写与尾部的点
+        let (_, body) = captures(&app, Method::GET, "?qname=Second.Example.com", Some(ADMIN_TOKEN)).await;
+        assert_eq!(body["count"], 1);
+        assert_eq!(body["captures"][0]["qname"], "second.example.com.");
+        let (_, body) = captures(&app, Method::GET, "?qname=first.example.com.", Some(ADMIN_TOKEN)).await;
+        assert_eq!(body["count"], 0);
+
+        // DELETE 清空缓冲区
+        let (status, _) = captures(&app, Method::DELETE, "", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = captures(&app, Method::DELETE, "", Some(ADMIN_TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["cleared"], 2);
+        let (_, body) = captures(&app, Method::GET, "", Some(ADMIN_TOKEN)).await;
+        assert_eq!(body["count"], 0);
+
+        info!("Test completed: test_debug_capture_records_recent_queries");
+    }
+
+    #[tokio::test]
+    async fn test_debug_capture_disabled_by_default() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_debug_capture_disabled_by_default");
+
+        let defaults = ServerConfig::default();
+        assert!(!defaults.debug.capture.enabled);
+        assert_eq!(defaults.debug.capture.size, 256);
+
+        // 未启用时即使配置了管理令牌也不提供端点
+        let mut config = mock_upstream_config(8053, "http://127.0.0.1:9");
+        config.http.admin_token = Some(ADMIN_TOKEN.to_string());
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+        let (status, _) = captures(&app, Method::GET, "", Some(ADMIN_TOKEN)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        info!("Test completed: test_debug_capture_disabled_by_default");
+    }
+
+    #[test]
+    fn test_debug_capture_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_debug_capture_config_validation");
+
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          admin_token: "capture-secret"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "https://1.1.1.1/dns-query"
+                protocol: doh
+        debug:
+          capture:
+            enabled: true
+            size: 64
+        "#;
+        let mut config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(config.debug.capture.size, 64);
+        assert!(config.test().is_ok());
+
+        // 容量必须在有效范围内
+        config.debug.capture.size = 0;
+        assert!(config.test().is_err());
+        config.debug.capture.size = 1_000_000;
+        assert!(config.test().is_err());
+        config.debug.capture.size = 64;
+
+        // 需要管理令牌保护端点
+        config.http.admin_token = None;
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_debug_capture_config_validation");
+    }
+}
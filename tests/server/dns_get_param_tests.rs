@@ -0,0 +1,101 @@
+// tests/server/dns_get_param_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use base64::{Engine as _, engine::general_purpose::{STANDARD, URL_SAFE, URL_SAFE_NO_PAD}};
+    use hickory_proto::op::{Message, Query};
+    use hickory_proto::rr::{Name, RecordType};
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::doh_handler::doh_routes;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+    use crate::server::test_helpers::server_state;
+
+    fn get_param_config(upstream_uri: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: false
+        "#, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 构造包含指定数量问题的查询
+    fn query_with_questions(count: usize) -> Vec<u8> {
+        let mut message = Message::new();
+        message.set_id(1234).set_recursion_desired(true);
+        for i in 0..count {
+            let name = Name::from_ascii(format!("host{}.example.com.", i)).unwrap();
+            message.add_query(Query::query(name, RecordType::A));
+        }
+        message.to_vec().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_malformed_dns_get_param_rejected() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_malformed_dns_get_param_rejected");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = doh_routes(server_state(get_param_config(&mock_server.uri())).await);
+
+        let query = create_test_query("example.com", RecordType::A).to_vec().unwrap();
+        let valid = URL_SAFE_NO_PAD.encode(&query);
+        // 问题部分声明了 1 个问题，但消息在头部之后就结束了
+        let mut truncated = query_with_questions(1);
+        truncated.truncate(14);
+
+        let cases: Vec<(&str, String, &str)> = vec![
+            ("padding", format!("{}==", valid), "dns_param_padding"),
+            ("padded url-safe encoding", URL_SAFE.encode([0u8; 13]), "dns_param_padding"),
+            ("percent-encoded padding", format!("{}%3D", valid), "dns_param_padding"),
+            ("embedded whitespace", format!("{}%20{}", &valid[..8], &valid[8..]), "dns_param_invalid_character"),
+            ("standard alphabet '/'", STANDARD.encode([0xffu8; 15]).replace('/', "%2F"), "dns_param_invalid_character"),
+            ("standard alphabet '+'", format!("{}+{}", &valid[..8], &valid[8..]), "dns_param_invalid_character"),
+            ("non-alphabet characters", "invalid@base64".to_string(), "dns_param_invalid_character"),
+            ("impossible length", "A".repeat(17), "dns_param_invalid_base64"),
+            ("empty parameter", String::new(), "dns_message_too_short"),
+            ("shorter than header", URL_SAFE_NO_PAD.encode([0u8; 11]), "dns_message_too_short"),
+            ("truncated question", URL_SAFE_NO_PAD.encode(&truncated), "dns_message_malformed"),
+            ("no question", URL_SAFE_NO_PAD.encode(query_with_questions(0)), "dns_message_question_count"),
+            ("two questions", URL_SAFE_NO_PAD.encode(query_with_questions(2)), "dns_message_question_count"),
+        ];
+
+        for (description, dns, expected_code) in cases {
+            let response = app.clone()
+                .oneshot(Request::get(format!("/dns-query?dns={}", dns)).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "case: {}", description);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(&body[..], expected_code.as_bytes(), "case: {}", description);
+        }
+
+        // 所有畸形输入都不会到达上游
+        assert!(mock_server.received_requests().await.unwrap().is_empty());
+
+        // 有效的查询仍正常处理
+        let response = app
+            .oneshot(Request::get(format!("/dns-query?dns={}", valid)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+
+        info!("Test completed: test_malformed_dns_get_param_rejected");
+    }
+}
@@ -0,0 +1,236 @@
+// tests/server/dns_server_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::str::FromStr;
+    use std::time::Duration;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use hickory_proto::op::{Edns, Message, ResponseCode};
+    use hickory_proto::rr::rdata::A;
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream, UdpSocket};
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::{AppComponents, DoHServer};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::{mock_upstream_config, mount_doh_answer};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const RECV_TIMEOUT: Duration = Duration::from_secs(5);
+
+    // 已启动经典 DNS 监听的测试服务器
+    struct DnsTestServer {
+        components: AppComponents,
+        udp_addr: SocketAddr,
+        tcp_addr: SocketAddr,
+    }
+
+    // 以模拟上游构建服务器并在随机端口上启动 UDP 与 TCP 监听
+    async fn start_dns_server(upstream_uri: &str) -> DnsTestServer {
+        let mut config = mock_upstream_config(8053, upstream_uri);
+        config.dns_server.listen_udp = Some("127.0.0.1:0".parse().unwrap());
+        config.dns_server.listen_tcp = Some("127.0.0.1:0".parse().unwrap());
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        let dns_server = components.dns_server.clone().expect("dns_server should be built when configured");
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let udp_addr = socket.local_addr().unwrap();
+        let tcp_addr = listener.local_addr().unwrap();
+        tokio::spawn(dns_server.clone().serve_udp(socket));
+        tokio::spawn(dns_server.serve_tcp(listener));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        DnsTestServer { components, udp_addr, tcp_addr }
+    }
+
+    // 上游对 A 查询返回指定数量的地址记录
+    async fn mount_many_answers(mock: &MockServer, count: u8) {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(move |request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let mut response = create_test_response(&query, ANSWER_IP);
+                let name = Name::from_str("big.example.com.").unwrap();
+                for i in 0..count {
+                    response.add_answer(Record::from_rdata(name.clone(), 300, RData::A(A::new(198, 51, 100, i))));
+                }
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(mock)
+            .await;
+    }
+
+    async fn udp_exchange(server: SocketAddr, wire: &[u8]) -> Option<Message> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.send_to(wire, server).await.unwrap();
+        let mut buf = vec![0u8; 65535];
+        let len = tokio::time::timeout(RECV_TIMEOUT, socket.recv(&mut buf)).await.ok()?.unwrap();
+        Some(Message::from_vec(&buf[..len]).unwrap())
+    }
+
+    async fn tcp_exchange(stream: &mut TcpStream, query: &Message) -> Message {
+        let wire = query.to_vec().unwrap();
+        stream.write_u16(wire.len() as u16).await.unwrap();
+        stream.write_all(&wire).await.unwrap();
+        let len = stream.read_u16().await.unwrap();
+        let mut buf = vec![0u8; len as usize];
+        stream.read_exact(&mut buf).await.unwrap();
+        Message::from_vec(&buf).unwrap()
+    }
+
+    async fn health_status(components: &AppComponents) -> StatusCode {
+        let request = Request::get("/health").body(Body::empty()).unwrap();
+        components.app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_dns_server_udp_and_tcp_queries() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_dns_server_udp_and_tcp_queries");
+
+        let mock = MockServer::start().await;
+        mount_doh_answer(&mock, ANSWER_IP).await;
+        let server = start_dns_server(&mock.uri()).await;
+        let answered = METRICS.dns_server_queries_total().with_label_values(&["udp", "answered"]);
+        let before = answered.get();
+
+        // UDP 查询经过同一上游流程得到应答
+        let query = create_test_query("udp.example.com", RecordType::A);
+        let response = udp_exchange(server.udp_addr, &query.to_vec().unwrap()).await.unwrap();
+        assert_eq!(response.id(), query.id());
+        assert_eq!(response.answers()[0].data(), Some(&RData::A(A(ANSWER_IP))));
+        assert!(answered.get() > before);
+
+        // 同一 TCP 连接上按长度前缀依次处理多个查询
+        let mut stream = TcpStream::connect(server.tcp_addr).await.unwrap();
+        for name in ["tcp1.example.com", "tcp2.example.com"] {
+            let query = create_test_query(name, RecordType::A);
+            let response = tcp_exchange(&mut stream, &query).await;
+            assert_eq!(response.id(), query.id());
+            assert_eq!(response.queries()[0].name(), query.queries()[0].name());
+            assert_eq!(response.answers().len(), 1);
+        }
+
+        // 无法解析但消息头完整的查询返回 FORMERR
+        let garbage = [0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff];
+        let response = udp_exchange(server.udp_addr, &garbage).await.unwrap();
+        assert_eq!(response.id(), 0x1234);
+        assert_eq!(response.response_code(), ResponseCode::FormErr);
+
+        info!("Test completed: test_dns_server_udp_and_tcp_queries");
+    }
+
+    #[tokio::test]
+    async fn test_dns_server_udp_truncation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_dns_server_udp_truncation");
+
+        let mock = MockServer::start().await;
+        mount_many_answers(&mock, 60).await;
+        let server = start_dns_server(&mock.uri()).await;
+
+        // 无 EDNS 时响应超过 512 字节被截断，只保留问题部分
+        let truncated = METRICS.dns_server_queries_total().with_label_values(&["udp", "truncated"]);
+        let truncated_before = truncated.get();
+        let query = create_test_query("big.example.com", RecordType::A);
+        let response = udp_exchange(server.udp_addr, &query.to_vec().unwrap()).await.unwrap();
+        assert!(response.truncated());
+        assert!(truncated.get() > truncated_before);
+        assert!(response.answers().is_empty());
+        assert_eq!(response.queries(), query.queries());
+
+        // EDNS 缓冲区足够大时返回完整响应
+        let mut query = create_test_query("big.example.com", RecordType::A);
+        let mut edns = Edns::new();
+        edns.set_max_payload(4096);
+        query.set_edns(edns);
+        let response = udp_exchange(server.udp_addr, &query.to_vec().unwrap()).await.unwrap();
+        assert!(!response.truncated());
+        assert_eq!(response.answers().len(), 61);
+
+        // TCP 不受大小限制
+        let mut stream = TcpStream::connect(server.tcp_addr).await.unwrap();
+        let query = create_test_query("big.example.com", RecordType::A);
+        let response = tcp_exchange(&mut stream, &query).await;
+        assert!(!response.truncated());
+        assert_eq!(response.answers().len(), 61);
+
+        info!("Test completed: test_dns_server_udp_truncation");
+    }
+
+    #[tokio::test]
+    async fn test_dns_server_health_and_drain() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_dns_server_health_and_drain");
+
+        let mock = MockServer::start().await;
+        mount_doh_answer(&mock, ANSWER_IP).await;
+
+        // 配置的监听尚未运行时健康检查返回 503
+        let mut config = mock_upstream_config(8053, &mock.uri());
+        config.dns_server.listen_udp = Some("127.0.0.1:0".parse().unwrap());
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        assert_eq!(health_status(&components).await, StatusCode::SERVICE_UNAVAILABLE);
+
+        // 监听运行后恢复正常
+        let server = start_dns_server(&mock.uri()).await;
+        assert_eq!(health_status(&server.components).await, StatusCode::OK);
+
+        // 排空后不再处理新查询
+        assert_eq!(server.components.drain.drain().await, 0);
+        let query = create_test_query("drain.example.com", RecordType::A);
+        assert!(udp_exchange(server.udp_addr, &query.to_vec().unwrap()).await.is_none());
+
+        info!("Test completed: test_dns_server_health_and_drain");
+    }
+
+    #[test]
+    fn test_dns_server_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_dns_server_config_validation");
+
+        let config = ServerConfig::default();
+        assert!(!config.dns_server.is_enabled());
+        assert_eq!(config.dns_server.tcp_idle_timeout, 10);
+
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        dns_server:
+          listen_udp: "0.0.0.0:53"
+          listen_tcp: "0.0.0.0:53"
+        "#).unwrap();
+        assert!(config.dns_server.is_enabled());
+        assert!(config.test().is_ok());
+
+        // TCP 监听地址不能与 HTTP 监听冲突
+        let mut conflicting = config.clone();
+        conflicting.dns_server.listen_tcp = Some("127.0.0.1:8053".parse().unwrap());
+        assert!(conflicting.test().is_err());
+
+        let mut invalid_timeout = config;
+        invalid_timeout.dns_server.tcp_idle_timeout = 0;
+        assert!(invalid_timeout.test().is_err());
+
+        info!("Test completed: test_dns_server_config_validation");
+    }
+}
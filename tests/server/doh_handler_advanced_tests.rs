@@ -2,24 +2,21 @@
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
     use std::time::Duration;
-    use reqwest::Client;
     use axum::body::{Body, to_bytes};
     use axum::http::{Method, Request, header, StatusCode};
     use tower::util::ServiceExt; // 用于oneshot方法的trait
     use hickory_proto::op::{Message, MessageType, OpCode};
     use hickory_proto::rr::{Name, RecordType};
-    use wiremock::MockServer;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
     use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_ENGINE};
-    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_JSON, CONTENT_TYPE_DNS_MESSAGE, CONTENT_TYPE_DNS_UDPWIREFORMAT};
     use oxide_wdns::server::config::ServerConfig;
-    use oxide_wdns::server::upstream::UpstreamManager;
-    use oxide_wdns::server::cache::DnsCache;
     use oxide_wdns::server::metrics::METRICS;
     use oxide_wdns::server::doh_handler::{ServerState, doh_routes};
     use tracing::info;
-    use oxide_wdns::server::routing::Router;
+    use crate::server::mock_http_server::{create_test_response, setup_mock_doh_server};
+    use crate::server::test_helpers::server_state;
 
     // === 辅助函数 / 模拟 ===
     
@@ -55,17 +52,32 @@ mod tests {
     // 创建模拟的服务器状态，用于测试
     async fn create_mock_server_state() -> ServerState {
         let config = create_test_config();
-        let router = Arc::new(Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap());
-        let http_client = Client::new();
-        let upstream = Arc::new(UpstreamManager::new(Arc::new(config.clone()), http_client).await.unwrap());
-        let cache = Arc::new(DnsCache::new(config.dns.cache.clone())); // 移除unwrap并传递值而非引用
-        
-        ServerState {
-            config,
-            upstream,
-            router,
-            cache,
-        }
+        server_state(config).await
+    }
+    
+    // 创建使用模拟 DoH 上游的服务器状态
+    async fn create_doh_upstream_server_state(upstream_uri: &str, cache_enabled: bool) -> ServerState {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          timeout: 10
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+            enable_dnssec: false
+          http_client:
+            timeout: 5
+          cache:
+            enabled: {}
+        "#, upstream_uri, cache_enabled);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        
+        server_state(config).await
     }
     
     // 创建一个DNS查询Message
@@ -144,6 +156,46 @@ mod tests {
         info!("Test completed: test_doh_post_invalid_content_type");
     }
 
+    #[tokio::test]
+    async fn test_doh_post_legacy_udpwireformat_content_type() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_post_legacy_udpwireformat_content_type");
+
+        let (mock_server, _counter) = setup_mock_doh_server(std::net::Ipv4Addr::new(192, 0, 2, 33)).await;
+        let state = create_doh_upstream_server_state(&mock_server.uri(), false).await;
+        let app = doh_routes(state);
+
+        // 旧版草案内容类型按 application/dns-message 处理
+        let mut query = create_test_query("example.com", RecordType::A);
+        query.set_id(4242);
+        let request = build_http_request(
+            Method::POST,
+            "/dns-query",
+            vec![("Content-Type", CONTENT_TYPE_DNS_UDPWIREFORMAT)],
+            query.to_vec().unwrap()
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), CONTENT_TYPE_DNS_MESSAGE);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response = Message::from_vec(&body).unwrap();
+        assert_eq!(response.id(), 4242);
+        assert_eq!(response.answers().len(), 1);
+
+        // 其他不支持的内容类型仍返回 415
+        let request = build_http_request(
+            Method::POST,
+            "/dns-query",
+            vec![("Content-Type", "text/plain")],
+            query.to_vec().unwrap()
+        );
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        info!("Test completed: test_doh_post_legacy_udpwireformat_content_type");
+    }
+
     #[tokio::test]
     async fn test_doh_get_missing_dns_param() {
         // 启用 tracing 日志
@@ -534,17 +586,7 @@ mod tests {
         let config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
         
         // 创建服务器状态
-        let router = Arc::new(Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap());
-        let http_client = Client::new();
-        let upstream = Arc::new(UpstreamManager::new(Arc::new(config.clone()), http_client).await.unwrap());
-        let cache = Arc::new(DnsCache::new(config.dns.cache.clone()));
-        
-        let state = ServerState {
-            config,
-            upstream,
-            cache,
-            router,
-        };
+        let state = server_state(config).await;
         
         // 创建测试应用
         let state_clone = state.clone();
@@ -725,17 +767,7 @@ mod tests {
         let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
         
         // 创建服务器状态
-        let router = Arc::new(Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap());
-        let http_client = Client::new();
-        let upstream = Arc::new(UpstreamManager::new(Arc::new(config.clone()), http_client).await.unwrap());
-        let cache = Arc::new(DnsCache::new(config.dns.cache.clone()));
-        
-        let state = ServerState {
-            config,
-            upstream,
-            cache,
-            router,
-        };
+        let state = server_state(config).await;
         
         // 创建测试应用
         let state_clone = state.clone();
@@ -803,4 +835,576 @@ mod tests {
         
         info!("Test completed: test_doh_handler_multiple_upstream_groups");
     }
-} 
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_doh_json_get_on_standard_path() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_json_get_on_standard_path");
+
+        // 创建模拟 DoH 上游与服务器状态
+        let (mock_server, counter) = setup_mock_doh_server(std::net::Ipv4Addr::new(93, 184, 216, 34)).await;
+        let state = create_doh_upstream_server_state(&mock_server.uri(), true).await;
+        let app = doh_routes(state);
+
+        // 使用 name/type 参数发送 JSON API 请求
+        info!("Sending JSON API GET request to /dns-query...");
+        let request = build_http_request(
+            Method::GET,
+            "/dns-query?name=json.example.com&type=a&do=1",
+            vec![],
+            vec![]
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE_DNS_JSON
+        );
+
+        // 验证响应结构
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        info!(response = %json, "Received JSON response");
+        assert_eq!(json["Status"], 0);
+        assert_eq!(json["RA"], true);
+        assert_eq!(json["Question"][0]["name"], "json.example.com.");
+        assert_eq!(json["Question"][0]["type"], 1);
+        assert_eq!(json["Answer"][0]["type"], 1);
+        assert_eq!(json["Answer"][0]["TTL"], 300);
+        assert_eq!(json["Answer"][0]["data"], "93.184.216.34");
+
+        // 相同查询的 wireformat 请求应命中共享缓存，不再访问上游
+        let query = create_test_query("json.example.com", RecordType::A);
+        let request = build_http_request(
+            Method::GET,
+            &format!("/dns-query?dns={}", encode_dns_message_base64url(&query)),
+            vec![],
+            vec![]
+        );
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(*counter.lock().unwrap(), 1, "Wire query should be served from the shared cache");
+
+        info!("Test completed: test_doh_json_get_on_standard_path");
+    }
+
+    #[tokio::test]
+    async fn test_doh_json_content_negotiation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_json_content_negotiation");
+
+        let (mock_server, _counter) = setup_mock_doh_server(std::net::Ipv4Addr::new(10, 0, 0, 1)).await;
+        let state = create_doh_upstream_server_state(&mock_server.uri(), false).await;
+        let app = doh_routes(state);
+
+        // ct=application/dns-message 时返回 wireformat
+        info!("Requesting wireformat response via ct parameter...");
+        let request = build_http_request(
+            Method::GET,
+            "/dns-query?name=example.com&type=1&ct=application/dns-message",
+            vec![],
+            vec![]
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE_DNS_MESSAGE
+        );
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let message = decode_dns_response(&body).await.unwrap();
+        assert_eq!(message.answers().len(), 1);
+
+        // 通过 POST 提交 application/dns-json 请求体
+        info!("Sending JSON API POST request...");
+        let request = build_http_request(
+            Method::POST,
+            "/dns-query",
+            vec![("Content-Type", CONTENT_TYPE_DNS_JSON), ("Accept", CONTENT_TYPE_DNS_JSON)],
+            br#"{"name":"example.com","type":"A","dnssec":false}"#.to_vec()
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["Answer"][0]["data"], "10.0.0.1");
+
+        // 缺少 name 参数但 Accept 为 JSON 时返回 400
+        info!("Sending JSON API GET request without name...");
+        let request = build_http_request(
+            Method::GET,
+            "/dns-query",
+            vec![("Accept", CONTENT_TYPE_DNS_JSON)],
+            vec![]
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // 无效的记录类型返回 400
+        let request = build_http_request(
+            Method::GET,
+            "/dns-query?name=example.com&type=NOTATYPE",
+            vec![],
+            vec![]
+        );
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        info!("Test completed: test_doh_json_content_negotiation");
+    }
+
+    #[tokio::test]
+    async fn test_doh_servfail_when_upstream_unavailable() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_servfail_when_upstream_unavailable");
+
+        // 模拟一个始终返回 503 的上游 DoH 服务器
+        use wiremock::{Mock, ResponseTemplate};
+        use wiremock::matchers::{method, path};
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+
+        let state = create_doh_upstream_server_state(&mock_server.uri(), true).await;
+        let app = doh_routes(state);
+
+        // wireformat 请求应返回 SERVFAIL，RA 置 0，并带 Retry-After 头
+        let query = create_test_query("down.example.com", RecordType::A);
+        let request = build_http_request(
+            Method::POST,
+            "/dns-query",
+            vec![(header::CONTENT_TYPE.as_str(), CONTENT_TYPE_DNS_MESSAGE)],
+            query.to_vec().unwrap()
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "5");
+
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let dns_response = Message::from_vec(&body).unwrap();
+        info!(?dns_response, "Received SERVFAIL response");
+        assert_eq!(dns_response.id(), query.id());
+        assert_eq!(dns_response.response_code(), hickory_proto::op::ResponseCode::ServFail);
+        assert!(!dns_response.recursion_available());
+        assert_eq!(dns_response.queries(), query.queries());
+
+        // JSON API 请求同样返回 SERVFAIL 状态
+        let request = build_http_request(
+            Method::GET,
+            "/resolve?name=down.example.com&type=A",
+            vec![],
+            vec![]
+        );
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "5");
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["Status"], 2);
+        assert_eq!(json["RA"], false);
+
+        info!("Test completed: test_doh_servfail_when_upstream_unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_servfail_retry_after_follows_upstream_recovery() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_servfail_retry_after_follows_upstream_recovery");
+
+        assert_eq!(ServerConfig::default().http.servfail_retry_after_secs, 5);
+
+        // 模拟一个始终返回 SERVFAIL 的上游 DoH 服务器
+        let mock_server = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let mut response = Message::new();
+                response.set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .set_response_code(hickory_proto::op::ResponseCode::ServFail)
+                    .add_queries(query.queries().to_vec());
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(&mock_server)
+            .await;
+
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          servfail_retry_after_secs: 9
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+            failure_ratio_threshold: 0.5
+            rolling_window_secs: 30
+        "#, mock_server.uri());
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        let app = doh_routes(server_state(config).await);
+        let retry_after = |app: axum::Router| async move {
+            let query = create_test_query("down.example.com", RecordType::A);
+            let request = build_http_request(
+                Method::POST,
+                "/dns-query",
+                vec![(header::CONTENT_TYPE.as_str(), CONTENT_TYPE_DNS_MESSAGE)],
+                query.to_vec().unwrap()
+            );
+            let response = app.oneshot(request).await.unwrap();
+            response.headers().get(header::RETRY_AFTER).unwrap().to_str().unwrap().parse::<u64>().unwrap()
+        };
+
+        // 上游尚未降级时使用配置的后备值
+        assert_eq!(retry_after(app.clone()).await, 9);
+
+        // 失败样本足够后上游被降级，Retry-After 改为失败记录移出窗口所需的时间
+        for _ in 0..10 {
+            retry_after(app.clone()).await;
+        }
+        let recovery = retry_after(app).await;
+        assert!((28..=30).contains(&recovery), "unexpected Retry-After: {}", recovery);
+
+        // 配置节名必须为 http_server
+        let config_str = config_str.replace("http_server:", "server:");
+        assert!(serde_yaml::from_str::<ServerConfig>(&config_str).is_err());
+
+        info!("Test completed: test_servfail_retry_after_follows_upstream_recovery");
+    }
+
+    #[tokio::test]
+    async fn test_doh_custom_paths() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_custom_paths");
+
+        // 仅在自定义路径上提供 DoH 服务
+        let (mock_server, counter) = setup_mock_doh_server(std::net::Ipv4Addr::new(192, 0, 2, 10)).await;
+        let mut state = create_doh_upstream_server_state(&mock_server.uri(), false).await;
+        state.config.http.doh_paths = vec!["/secret-path-abc123".to_string()];
+        let app = doh_routes(state);
+
+        // 自定义路径上的 wireformat 与 JSON 查询均可用
+        let query = create_test_query("custom.example.com", RecordType::A);
+        let request = build_http_request(
+            Method::POST,
+            "/secret-path-abc123",
+            vec![(header::CONTENT_TYPE.as_str(), CONTENT_TYPE_DNS_MESSAGE)],
+            query.to_vec().unwrap()
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = build_http_request(
+            Method::GET,
+            "/secret-path-abc123?name=custom.example.com&type=A",
+            vec![],
+            vec![]
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE_DNS_JSON
+        );
+        assert_eq!(*counter.lock().unwrap(), 2);
+
+        // 未配置的默认路径返回 404，且不会访问上游
+        for uri in ["/dns-query", "/resolve?name=custom.example.com&type=A"] {
+            info!("Requesting unconfigured path {}...", uri);
+            let request = build_http_request(
+                Method::POST,
+                uri,
+                vec![(header::CONTENT_TYPE.as_str(), CONTENT_TYPE_DNS_MESSAGE)],
+                query.to_vec().unwrap()
+            );
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+        assert_eq!(*counter.lock().unwrap(), 2, "Unconfigured paths must not reach the upstream");
+
+        info!("Test completed: test_doh_custom_paths");
+    }
+
+    #[test]
+    fn test_doh_paths_validation() {
+        let mut config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+
+        // 默认路径保持不变
+        assert_eq!(config.http.doh_paths, vec!["/dns-query", "/resolve"]);
+        assert!(config.test().is_ok());
+
+        config.http.doh_paths = vec!["/dns-query".to_string(), "/private/doh".to_string()];
+        assert!(config.test().is_ok());
+
+        // 非法路径：缺少前导 '/'、通配符、参数、内置路由、重复以及空列表
+        for paths in [
+            vec!["dns-query"],
+            vec!["/dns/*"],
+            vec!["/{name}"],
+            vec!["/health"],
+            vec!["/dns-query", "/dns-query"],
+            vec![],
+        ] {
+            config.http.doh_paths = paths.iter().map(|p| p.to_string()).collect();
+            assert!(config.test().is_err(), "doh_paths {:?} should be rejected", paths);
+        }
+
+        // doh_path 配置单个路径，替换默认路径
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          doh_path: "/private/doh"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+        assert_eq!(config.http.doh_paths, vec!["/private/doh"]);
+        assert!(config.test().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_doh_response_ttl_overrides() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_response_ttl_overrides");
+
+        // 模拟上游返回 TTL 为 300 的记录
+        let (mock_server, counter) = setup_mock_doh_server(std::net::Ipv4Addr::new(192, 0, 2, 20)).await;
+        let mut state = create_doh_upstream_server_state(&mock_server.uri(), true).await;
+        state.config.dns.min_response_ttl_override = Some(600);
+        let cache = state.cache.clone();
+        let app = doh_routes(state);
+
+        // 发送查询并解析响应中的 TTL
+        let query_ttl = |app: axum::Router, domain: &'static str| async move {
+            let query = create_test_query(domain, RecordType::A);
+            let request = build_http_request(
+                Method::POST,
+                "/dns-query",
+                vec![(header::CONTENT_TYPE.as_str(), CONTENT_TYPE_DNS_MESSAGE)],
+                query.to_vec().unwrap()
+            );
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+            Message::from_vec(&body).unwrap().answers()[0].ttl()
+        };
+
+        // 客户端看到的 TTL 被提升到下限，缓存命中时同样生效
+        assert_eq!(query_ttl(app.clone(), "ttl.example.com").await, 600);
+        assert_eq!(query_ttl(app.clone(), "ttl.example.com").await, 600);
+        assert_eq!(*counter.lock().unwrap(), 1);
+
+        // 缓存内部仍保存上游原始 TTL
+        let key = oxide_wdns::server::cache::CacheKey::new(
+            Name::from_ascii("ttl.example.com.").unwrap(),
+            RecordType::A,
+            hickory_proto::rr::DNSClass::IN,
+        );
+        let cached = cache.get(&key).await.unwrap();
+        assert_eq!(cached.answers()[0].ttl(), 300);
+
+        // 上限将客户端 TTL 压低
+        let mut state = create_doh_upstream_server_state(&mock_server.uri(), false).await;
+        state.config.dns.max_response_ttl_override = Some(30);
+        assert_eq!(query_ttl(doh_routes(state), "short.example.com").await, 30);
+
+        info!("Test completed: test_doh_response_ttl_overrides");
+    }
+
+    #[test]
+    fn test_response_ttl_overrides_validation() {
+        let mut config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+          min_response_ttl_override: 60
+          max_response_ttl_override: 3600
+        "#).unwrap();
+        assert_eq!(config.dns.min_response_ttl_override, Some(60));
+        assert_eq!(config.dns.max_response_ttl_override, Some(3600));
+        assert!(config.test().is_ok());
+
+        // 下限不能大于上限
+        config.dns.min_response_ttl_override = Some(7200);
+        assert!(config.test().is_err());
+
+        // 超出 u32 范围
+        config.dns.min_response_ttl_override = None;
+        config.dns.max_response_ttl_override = Some(u32::MAX as u64 + 1);
+        assert!(config.test().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_max_serve_ttl_clamps_long_upstream_ttl() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_max_serve_ttl_clamps_long_upstream_ttl");
+
+        // max_serve_ttl 是 max_response_ttl_override 的别名
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+          max_serve_ttl: 3600
+        "#).unwrap();
+        assert_eq!(config.dns.max_response_ttl_override, Some(3600));
+
+        // 模拟上游返回 TTL 为 7 天的记录
+        let mock_server = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let mut response = create_test_response(&query, std::net::Ipv4Addr::new(192, 0, 2, 21));
+                response.answers_mut().iter_mut().for_each(|record| { record.set_ttl(604800); });
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let mut state = create_doh_upstream_server_state(&mock_server.uri(), true).await;
+        state.config.dns.max_response_ttl_override = config.dns.max_response_ttl_override;
+        let cache = state.cache.clone();
+        let app = doh_routes(state);
+
+        // 上游应答与缓存命中返回给客户端的 TTL 均被压低到上限
+        for _ in 0..2 {
+            let query = create_test_query("week.example.com", RecordType::A);
+            let request = build_http_request(
+                Method::POST,
+                "/dns-query",
+                vec![(header::CONTENT_TYPE.as_str(), CONTENT_TYPE_DNS_MESSAGE)],
+                query.to_vec().unwrap()
+            );
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+            assert_eq!(Message::from_vec(&body).unwrap().answers()[0].ttl(), 3600);
+        }
+
+        // 缓存内部的过期时间不受影响
+        let key = oxide_wdns::server::cache::CacheKey::new(
+            Name::from_ascii("week.example.com.").unwrap(),
+            RecordType::A,
+            hickory_proto::rr::DNSClass::IN,
+        );
+        assert!(cache.get(&key).await.unwrap().answers()[0].ttl() > 3600);
+
+        info!("Test completed: test_max_serve_ttl_clamps_long_upstream_ttl");
+    }
+
+    #[tokio::test]
+    async fn test_doh_strip_record_types() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_strip_record_types");
+
+        // 模拟上游对任意查询返回一条 A 记录
+        let (mock_server, _counter) = setup_mock_doh_server(std::net::Ipv4Addr::new(192, 0, 2, 30)).await;
+
+        // 发送 wireformat 查询并返回解析后的响应
+        let query_app = |app: axum::Router| async move {
+            let query = create_test_query("strip.example.com", RecordType::A);
+            let request = build_http_request(
+                Method::POST,
+                "/dns-query",
+                vec![(header::CONTENT_TYPE.as_str(), CONTENT_TYPE_DNS_MESSAGE)],
+                query.to_vec().unwrap()
+            );
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+            Message::from_vec(&body).unwrap()
+        };
+
+        // 未匹配的记录类型不受影响
+        let mut state = create_doh_upstream_server_state(&mock_server.uri(), false).await;
+        state.config.dns.strip_record_types = vec!["HTTPS".to_string(), "svcb".to_string()];
+        let response = query_app(doh_routes(state)).await;
+        assert_eq!(response.answers().len(), 1);
+
+        // 移除查询类型本身的记录时返回 NODATA：NOERROR、保留问题部分、应答为空
+        let mut state = create_doh_upstream_server_state(&mock_server.uri(), false).await;
+        state.config.dns.strip_record_types = vec!["1".to_string()];
+        let app = doh_routes(state);
+        let response = query_app(app.clone()).await;
+        info!(?response, "Received stripped response");
+        assert_eq!(response.response_code(), hickory_proto::op::ResponseCode::NoError);
+        assert_eq!(response.queries().len(), 1);
+        assert!(response.answers().is_empty());
+
+        // JSON API 同样返回没有 Answer 的 NOERROR 响应
+        let request = build_http_request(
+            Method::GET,
+            "/resolve?name=strip.example.com&type=A",
+            vec![],
+            vec![]
+        );
+        let response = app.oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["Status"], 0);
+        assert!(json["Answer"].as_array().is_none_or(|answers| answers.is_empty()));
+
+        info!("Test completed: test_doh_strip_record_types");
+    }
+
+    #[test]
+    fn test_strip_record_types_validation() {
+        let mut config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+          strip_record_types: [HTTPS, svcb, "65"]
+        "#).unwrap();
+        assert!(config.test().is_ok());
+        assert_eq!(
+            config.dns.parsed_strip_record_types().unwrap(),
+            vec![RecordType::HTTPS, RecordType::SVCB, RecordType::HTTPS]
+        );
+
+        // 无法识别的记录类型
+        config.dns.strip_record_types = vec!["BOGUS".to_string()];
+        assert!(config.test().is_err());
+        config.dns.strip_record_types = vec!["65280".to_string()];
+        assert!(config.test().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+// tests/server/doh_methods_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use axum::Router;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_MESSAGE, DOH_ALLOWED_METHODS};
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::setup_mock_doh_server;
+
+    async fn build_app(upstream_uri: &str, cors_enabled: bool) -> Router {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          cors:
+            enabled: {}
+            allowed_origins: ["https://dash.example.com"]
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+        "#, cors_enabled, upstream_uri);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        DoHServer::new(config, false).build_application_components().await.unwrap().app
+    }
+
+    fn method_request(method: Method) -> Request<Body> {
+        Request::builder().method(method).uri("/dns-query").body(Body::empty()).unwrap()
+    }
+
+    async fn body_len(response: axum::response::Response) -> usize {
+        axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn test_doh_method_matrix() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_method_matrix");
+
+        let (mock_server, counter) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = build_app(&mock_server.uri(), false).await;
+
+        // HEAD 返回与 GET 相同的内容类型和空响应体
+        let response = app.clone().oneshot(method_request(Method::HEAD)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), CONTENT_TYPE_DNS_MESSAGE);
+        assert_eq!(body_len(response).await, 0);
+
+        // OPTIONS 返回支持的方法
+        let response = app.clone().oneshot(method_request(Method::OPTIONS)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers().get(header::ALLOW).unwrap(), DOH_ALLOWED_METHODS);
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        // 不支持的方法返回 405 与 Allow 头
+        for method in [Method::PUT, Method::DELETE, Method::PATCH] {
+            let response = app.clone().oneshot(method_request(method.clone())).await.unwrap();
+            assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED, "method {}", method);
+            let allow = response.headers().get(header::ALLOW).unwrap().to_str().unwrap().to_string();
+            for allowed in ["GET", "HEAD", "POST", "OPTIONS"] {
+                assert!(allow.contains(allowed), "Allow header {} should contain {}", allow, allowed);
+            }
+        }
+
+        // 以上请求均不进入 DNS 处理流程
+        assert_eq!(*counter.lock().unwrap(), 0);
+
+        info!("Test completed: test_doh_method_matrix");
+    }
+
+    #[tokio::test]
+    async fn test_doh_options_with_cors() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_options_with_cors");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = build_app(&mock_server.uri(), true).await;
+
+        // 非预检的 OPTIONS 请求由处理器应答，CORS 中间件补充跨域响应头
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/dns-query")
+            .header(header::ORIGIN, "https://dash.example.com")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers().get(header::ALLOW).unwrap(), DOH_ALLOWED_METHODS);
+        assert_eq!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "https://dash.example.com");
+
+        info!("Test completed: test_doh_options_with_cors");
+    }
+}
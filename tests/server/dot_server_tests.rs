@@ -0,0 +1,199 @@
+// tests/server/dot_server_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::rdata::A;
+    use hickory_proto::rr::{RData, RecordType};
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::client::TlsStream;
+    use tokio_rustls::rustls::crypto::ring::default_provider;
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::TlsConnector;
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::{AppComponents, DoHServer};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::{mock_upstream_config, write_self_signed_cert};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const SLOW_UPSTREAM_DELAY: Duration = Duration::from_millis(800);
+
+    // 以模拟上游构建服务器并在随机端口上启动 DoT 监听
+    async fn start_dot_server(dir: &Path, upstream_uri: &str, idle_timeout: u64) -> (AppComponents, SocketAddr) {
+        let mut config = mock_upstream_config(8053, upstream_uri);
+        config.dot_server.listen_addr = Some("127.0.0.1:0".parse().unwrap());
+        config.dot_server.cert = Some(dir.join("cert.pem"));
+        config.dot_server.key = Some(dir.join("key.pem"));
+        config.dot_server.idle_timeout = idle_timeout;
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        let dot_server = components.dot_server.clone().expect("dot_server should be built when configured");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(dot_server.serve(listener));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        (components, addr)
+    }
+
+    // 以指定证书为信任根建立 DoT 连接
+    async fn connect(addr: SocketAddr, cert_pem: &str) -> TlsStream<TcpStream> {
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from_pem_slice(cert_pem.as_bytes()).unwrap()).unwrap();
+        let mut tls_config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        tls_config.alpn_protocols = vec![b"dot".to_vec()];
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        TlsConnector::from(Arc::new(tls_config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap()
+    }
+
+    async fn send_query(stream: &mut TlsStream<TcpStream>, query: &Message) {
+        let wire = query.to_vec().unwrap();
+        let mut framed = (wire.len() as u16).to_be_bytes().to_vec();
+        framed.extend_from_slice(&wire);
+        stream.write_all(&framed).await.unwrap();
+    }
+
+    async fn read_response(stream: &mut TlsStream<TcpStream>) -> Message {
+        let len = stream.read_u16().await.unwrap();
+        let mut buf = vec![0u8; len as usize];
+        stream.read_exact(&mut buf).await.unwrap();
+        Message::from_vec(&buf).unwrap()
+    }
+
+    // 对名称以 slow 开头的查询延迟应答
+    async fn mount_mixed_latency_upstream(mock: &MockServer) {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let slow = query.queries()[0].name().to_ascii().starts_with("slow");
+                let response = ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(create_test_response(&query, ANSWER_IP).to_vec().unwrap());
+                if slow { response.set_delay(SLOW_UPSTREAM_DELAY) } else { response }
+            })
+            .mount(mock)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_dot_server_answers_out_of_order() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_dot_server_answers_out_of_order");
+
+        let dir = TempDir::new().unwrap();
+        let cert_pem = write_self_signed_cert(dir.path());
+        let mock = MockServer::start().await;
+        mount_mixed_latency_upstream(&mock).await;
+        let (_components, addr) = start_dot_server(dir.path(), &mock.uri(), 30).await;
+
+        let mut stream = connect(addr, &cert_pem).await;
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"dot"[..]));
+
+        // 同一连接上先发送慢查询再发送快查询，快查询的响应先返回
+        let slow = create_test_query("slow.example.com", RecordType::A);
+        let mut fast = create_test_query("fast.example.com", RecordType::A);
+        fast.set_id(slow.id().wrapping_add(1));
+        send_query(&mut stream, &slow).await;
+        send_query(&mut stream, &fast).await;
+
+        let first = read_response(&mut stream).await;
+        let second = read_response(&mut stream).await;
+        assert_eq!(first.id(), fast.id());
+        assert_eq!(first.queries(), fast.queries());
+        assert_eq!(second.id(), slow.id());
+        assert_eq!(second.queries(), slow.queries());
+        assert_eq!(second.answers()[0].data(), Some(&RData::A(A(ANSWER_IP))));
+
+        info!("Test completed: test_dot_server_answers_out_of_order");
+    }
+
+    #[tokio::test]
+    async fn test_dot_server_reloads_certificate_and_closes_idle_connections() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_dot_server_reloads_certificate_and_closes_idle_connections");
+
+        let dir = TempDir::new().unwrap();
+        let cert_pem = write_self_signed_cert(dir.path());
+        let (components, addr) = start_dot_server(dir.path(), "http://127.0.0.1:9", 1).await;
+
+        // 空闲超时后服务器关闭连接
+        let mut stream = connect(addr, &cert_pem).await;
+        let mut buf = [0u8; 1];
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await.unwrap();
+        assert!(matches!(read, Ok(0) | Err(_)));
+
+        // 重新加载后新连接使用新证书
+        let new_cert_pem = write_self_signed_cert(dir.path());
+        components.dot_server.as_ref().unwrap().tls().reload().unwrap();
+        let stream = connect(addr, &new_cert_pem).await;
+        let served = stream.get_ref().1.peer_certificates().unwrap()[0].clone();
+        assert_eq!(served, CertificateDer::from_pem_slice(new_cert_pem.as_bytes()).unwrap());
+
+        info!("Test completed: test_dot_server_reloads_certificate_and_closes_idle_connections");
+    }
+
+    #[test]
+    fn test_dot_server_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_dot_server_config_validation");
+
+        let config = ServerConfig::default();
+        assert!(config.dot_server.listen_addr.is_none());
+        assert_eq!(config.dot_server.idle_timeout, 30);
+
+        let dir = TempDir::new().unwrap();
+        write_self_signed_cert(dir.path());
+        let config: ServerConfig = serde_yaml::from_str(&format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        dot_server:
+          listen_addr: "0.0.0.0:853"
+          cert: "{}"
+          key: "{}"
+        "#, dir.path().join("cert.pem").display(), dir.path().join("key.pem").display())).unwrap();
+        assert!(config.test().is_ok());
+
+        // 缺少证书或私钥
+        let mut missing_key = config.clone();
+        missing_key.dot_server.key = None;
+        assert!(missing_key.test().is_err());
+
+        // 监听地址不能与 HTTP 监听冲突
+        let mut conflicting = config.clone();
+        conflicting.dot_server.listen_addr = Some("127.0.0.1:8053".parse().unwrap());
+        assert!(conflicting.test().is_err());
+
+        let mut invalid_timeout = config;
+        invalid_timeout.dot_server.idle_timeout = 0;
+        assert!(invalid_timeout.test().is_err());
+
+        info!("Test completed: test_dot_server_config_validation");
+    }
+}
@@ -0,0 +1,187 @@
+// tests/server/drain_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::time::Duration;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use tokio::net::TcpListener;
+    use tokio::sync::watch;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::{AppComponents, DoHServer};
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::mount_doh_answer_with_delay;
+
+    const UPSTREAM_DELAY: Duration = Duration::from_millis(1500);
+
+    async fn build_components(upstream_uri: &str, grace_period_secs: u64) -> AppComponents {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          shutdown_grace_period_secs: {}
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 5
+          cache:
+            enabled: false
+        "#, grace_period_secs, upstream_uri);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        DoHServer::new(config, false).build_application_components().await.unwrap()
+    }
+
+    fn query_request(name: &str) -> Request<Body> {
+        let query = create_test_query(name, RecordType::A);
+        Request::get(format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap())))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    // 在后台发送一个查询，并等待其进入上游查询阶段
+    async fn spawn_slow_query(app: &Router) -> tokio::task::JoinHandle<StatusCode> {
+        let app = app.clone();
+        let handle = tokio::spawn(async move {
+            app.oneshot(query_request("slow.example.com")).await.unwrap().status()
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        handle
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_queries() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_drain_waits_for_in_flight_queries");
+
+        let mock_server = MockServer::start().await;
+        mount_doh_answer_with_delay(&mock_server, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let components = build_components(&mock_server.uri(), 5).await;
+        assert!(!components.drain.is_draining());
+
+        let in_flight = spawn_slow_query(&components.app).await;
+        assert_eq!(components.drain.in_flight(), 1);
+
+        let drain = components.drain.clone();
+        let draining = tokio::spawn(async move { drain.drain().await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // 排空期间的新请求返回 503 并要求关闭连接
+        let response = components.app.clone().oneshot(query_request("new.example.com")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get(header::CONNECTION).unwrap(), "close");
+
+        // 进行中的查询正常完成，排空没有丢弃任何查询
+        assert_eq!(in_flight.await.unwrap(), StatusCode::OK);
+        assert_eq!(draining.await.unwrap(), 0);
+        assert_eq!(components.drain.in_flight(), 0);
+
+        info!("Test completed: test_drain_waits_for_in_flight_queries");
+    }
+
+    #[tokio::test]
+    async fn test_drain_drops_queries_after_grace_period() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_drain_drops_queries_after_grace_period");
+
+        let mock_server = MockServer::start().await;
+        mount_doh_answer_with_delay(&mock_server, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let components = build_components(&mock_server.uri(), 0).await;
+        let in_flight = spawn_slow_query(&components.app).await;
+
+        // 宽限期为 0 时立即结束排空，未完成的查询计入指标并返回 503
+        let dropped_before = METRICS.shutdown_dropped_queries_total().get();
+        assert_eq!(components.drain.drain().await, 1);
+        assert!(METRICS.shutdown_dropped_queries_total().get() > dropped_before);
+        assert_eq!(in_flight.await.unwrap(), StatusCode::SERVICE_UNAVAILABLE);
+
+        info!("Test completed: test_drain_drops_queries_after_grace_period");
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_answers_in_flight_client() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_graceful_shutdown_answers_in_flight_client");
+
+        let mock_server = MockServer::start().await;
+        mount_doh_answer_with_delay(&mock_server, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let components = build_components(&mock_server.uri(), 5).await;
+
+        // 与服务进程相同的关闭顺序：排空、停止后台任务、通知监听停止接受连接
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+        let app = components.app.clone();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown_rx.wait_for(|shutdown| *shutdown).await;
+                })
+                .await
+                .unwrap();
+        });
+
+        let query = create_test_query("slow.example.com", RecordType::A);
+        let url = format!("http://{}/dns-query?dns={}", addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let client = tokio::spawn(async move { reqwest::get(url).await });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(components.drain.in_flight(), 1);
+
+        // 慢速上游的响应跨越关闭信号，客户端仍收到应答
+        assert_eq!(components.drain.drain().await, 0);
+        components.router.stop_background_tasks();
+        components.upstream.stop_background_tasks();
+        shutdown_tx.send(true).unwrap();
+
+        let response = client.await.unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let answer = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+        assert_eq!(answer.id(), query.id());
+        assert_eq!(answer.answers().len(), 1);
+        tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+
+        info!("Test completed: test_graceful_shutdown_answers_in_flight_client");
+    }
+
+    #[test]
+    fn test_shutdown_grace_period_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_shutdown_grace_period_validation");
+
+        let mut config = ServerConfig::default();
+        assert_eq!(config.http.shutdown_grace_period_secs, 30);
+
+        config.http.shutdown_grace_period_secs = 0;
+        assert!(config.test().is_ok());
+        config.http.shutdown_grace_period_secs = 3601;
+        assert!(config.test().is_err());
+
+        // shutdown_timeout 是 shutdown_grace_period_secs 的别名
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          shutdown_timeout: 10
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+        assert_eq!(config.http.shutdown_grace_period_secs, 10);
+
+        info!("Test completed: test_shutdown_grace_period_validation");
+    }
+}
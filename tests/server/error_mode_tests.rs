@@ -0,0 +1,164 @@
+// tests/server/error_mode_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::DoHServer;
+    use oxide_wdns::server::config::{ErrorMode, ServerConfig};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::mock_upstream_config;
+
+    const QUERY_NAME: &str = "broken.example.com";
+
+    // 以 GET、POST 与 JSON 三种方式发送同一查询，返回各自的状态码与响应体
+    async fn query_all_endpoints(app: Router) -> Vec<(&'static str, StatusCode, Vec<u8>)> {
+        let query = create_test_query(QUERY_NAME, RecordType::A);
+        let wire = query.to_vec().unwrap();
+        let requests = vec![
+            ("GET", Request::get(format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(&wire)))
+                .header(header::ACCEPT, CONTENT_TYPE_DNS_MESSAGE)
+                .body(Body::empty()).unwrap()),
+            ("POST", Request::post("/dns-query")
+                .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+                .body(Body::from(wire)).unwrap()),
+            ("JSON", Request::get(format!("/resolve?name={}&type=A", QUERY_NAME))
+                .body(Body::empty()).unwrap()),
+        ];
+
+        let mut results = Vec::new();
+        for (endpoint, request) in requests {
+            let response = app.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            results.push((endpoint, status, body.to_vec()));
+        }
+        results
+    }
+
+    // 构建使用指定上游与错误响应方式的应用
+    async fn build_app(upstream_uri: &str, error_mode: ErrorMode) -> Router {
+        let mut config: ServerConfig = mock_upstream_config(8053, upstream_uri);
+        config.http.error_mode = error_mode;
+        config.dns.cache.enabled = false;
+        config.dns.http_client.timeout = 1;
+        DoHServer::new(config, false).build_application_components().await.unwrap().app
+    }
+
+    // 启动返回问题部分不匹配的响应的上游，响应无法通过严格校验
+    async fn setup_mismatched_upstream() -> MockServer {
+        let mock_server = MockServer::start().await;
+        let other = create_test_query("other.example.com", RecordType::A);
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                .set_body_bytes(create_test_response(&other, Ipv4Addr::new(192, 0, 2, 1)).to_vec().unwrap()))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    // 启动响应慢于上游 HTTP 超时的上游
+    async fn setup_slow_upstream() -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn test_error_mode_dns_rcode_returns_servfail() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_error_mode_dns_rcode_returns_servfail");
+
+        assert_eq!(ServerConfig::default().http.error_mode, ErrorMode::DnsRcode);
+
+        // 上游不可达与响应未通过校验时都返回 200 与 SERVFAIL 消息
+        let mismatched = setup_mismatched_upstream().await;
+        for upstream_uri in ["http://127.0.0.1:9".to_string(), mismatched.uri()] {
+            let app = build_app(&upstream_uri, ErrorMode::DnsRcode).await;
+            for (endpoint, status, body) in query_all_endpoints(app).await {
+                assert_eq!(status, StatusCode::OK, "{} via {}", endpoint, upstream_uri);
+                if endpoint == "JSON" {
+                    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                    assert_eq!(json["Status"], u16::from(ResponseCode::ServFail));
+                } else {
+                    let message = Message::from_vec(&body).unwrap();
+                    assert_eq!(message.response_code(), ResponseCode::ServFail);
+                    assert_eq!(message.queries()[0].name().to_ascii(), format!("{}.", QUERY_NAME));
+                }
+            }
+        }
+
+        info!("Test completed: test_error_mode_dns_rcode_returns_servfail");
+    }
+
+    #[tokio::test]
+    async fn test_error_mode_http_status_maps_failures() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_error_mode_http_status_maps_failures");
+
+        let mismatched = setup_mismatched_upstream().await;
+        let slow = setup_slow_upstream().await;
+        let cases = [
+            // 上游不可达
+            ("http://127.0.0.1:9".to_string(), StatusCode::BAD_GATEWAY),
+            // 上游响应未通过校验
+            (mismatched.uri(), StatusCode::INTERNAL_SERVER_ERROR),
+            // 上游超时
+            (slow.uri(), StatusCode::GATEWAY_TIMEOUT),
+        ];
+
+        for (upstream_uri, expected) in cases {
+            let app = build_app(&upstream_uri, ErrorMode::HttpStatus).await;
+            for (endpoint, status, body) in query_all_endpoints(app).await {
+                assert_eq!(status, expected, "{} via {}", endpoint, upstream_uri);
+                // 响应体只包含状态描述，不泄露上游细节
+                assert_eq!(body, expected.canonical_reason().unwrap().as_bytes(), "{} via {}", endpoint, upstream_uri);
+            }
+        }
+
+        info!("Test completed: test_error_mode_http_status_maps_failures");
+    }
+
+    #[test]
+    fn test_error_mode_config_parsing() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_error_mode_config_parsing");
+
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          error_mode: http_status
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "https://1.1.1.1/dns-query"
+                protocol: doh
+        "#;
+        let config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(config.http.error_mode, ErrorMode::HttpStatus);
+
+        let invalid = config_str.replace("http_status", "http_code");
+        assert!(serde_yaml::from_str::<ServerConfig>(&invalid).is_err());
+
+        info!("Test completed: test_error_mode_config_parsing");
+    }
+}
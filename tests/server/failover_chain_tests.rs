@@ -0,0 +1,197 @@
+// tests/server/failover_chain_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use reqwest::Client;
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::{RoutingConfig, ServerConfig};
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::routing::{RouteDecision, Router};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::{extract_ip_addresses, mount_doh_answer, test_with_server_config};
+
+    const OK_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 77);
+
+    // 始终返回 SERVFAIL 的上游
+    async fn mount_servfail(mock: &MockServer) {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let mut response = create_test_response(&query, OK_IP);
+                response.take_answers();
+                response.set_response_code(ResponseCode::ServFail);
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(mock)
+            .await;
+    }
+
+    // 应答晚于 HTTP 客户端超时的上游
+    async fn mount_slow(mock: &MockServer) {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(create_test_response(&query, OK_IP).to_vec().unwrap())
+                    .set_delay(Duration::from_secs(3))
+            })
+            .mount(mock)
+            .await;
+    }
+
+    // servfail_group、slow_group 与 ok_group 三个上游组及其路由规则
+    fn routing_config(servfail_uri: &str, slow_uri: &str, ok_uri: &str, rules: &str) -> RoutingConfig {
+        serde_yaml::from_str(&format!(r#"
+        enabled: true
+        upstream_groups:
+          - name: "servfail_group"
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+          - name: "slow_group"
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+          - name: "ok_group"
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+        rules:
+{}
+        "#, servfail_uri, slow_uri, ok_uri, rules)).unwrap()
+    }
+
+    const RULES: &str = r#"
+          - match:
+              type: exact
+              values: ["chain.example.com"]
+            upstream_group: "servfail_group"
+            failover_chain: ["slow_group", "ok_group"]
+          - match:
+              type: exact
+              values: ["healthy.example.com"]
+            upstream_group: "ok_group"
+            failover_chain: ["servfail_group"]
+          - match:
+              type: exact
+              values: ["exhausted.example.com"]
+            upstream_group: "slow_group"
+            failover_chain: ["servfail_group"]"#;
+
+    async fn query(server_addr: &str, name: &str) -> Message {
+        let query = create_test_query(name, RecordType::A);
+        let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let body = reqwest::get(url).await.unwrap().bytes().await.unwrap();
+        Message::from_vec(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_failover_chain_retries_next_group() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_failover_chain_retries_next_group");
+
+        let servfail = MockServer::start().await;
+        mount_servfail(&servfail).await;
+        let slow = MockServer::start().await;
+        mount_slow(&slow).await;
+        let ok = MockServer::start().await;
+        mount_doh_answer(&ok, OK_IP).await;
+        let routing = routing_config(&servfail.uri(), &slow.uri(), &ok.uri(), RULES);
+
+        let servfail_to_slow = METRICS.upstream_failover_total().with_label_values(&["servfail_group", "slow_group"]);
+        let slow_to_ok = METRICS.upstream_failover_total().with_label_values(&["slow_group", "ok_group"]);
+        let ok_to_servfail = METRICS.upstream_failover_total().with_label_values(&["ok_group", "servfail_group"]);
+        let slow_to_servfail = METRICS.upstream_failover_total().with_label_values(&["slow_group", "servfail_group"]);
+        let before = (servfail_to_slow.get(), slow_to_ok.get(), ok_to_servfail.get(), slow_to_servfail.get());
+
+        // HTTP 客户端超时短于 slow_group 的应答延迟
+        let customize = |config: &mut ServerConfig| {
+            config.dns.routing = routing;
+            config.dns.http_client.timeout = 1;
+        };
+        test_with_server_config(customize, |server_addr, _mock| async move {
+            // SERVFAIL 与超时依次触发故障转移，最终由 ok_group 应答
+            let response = query(&server_addr, "chain.example.com").await;
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+            assert_eq!(extract_ip_addresses(&response), vec![OK_IP.to_string()]);
+            assert_eq!(servfail_to_slow.get(), before.0 + 1);
+            assert_eq!(slow_to_ok.get(), before.1 + 1);
+            let servfail_requests = servfail.received_requests().await.unwrap().len();
+            assert!(servfail_requests >= 1);
+
+            // 首选组应答成功时不使用故障转移链
+            let response = query(&server_addr, "healthy.example.com").await;
+            assert_eq!(extract_ip_addresses(&response), vec![OK_IP.to_string()]);
+            assert_eq!(ok_to_servfail.get(), before.2);
+            assert_eq!(servfail.received_requests().await.unwrap().len(), servfail_requests);
+
+            // 故障转移链用尽时返回最后一组的结果
+            let response = query(&server_addr, "exhausted.example.com").await;
+            assert_eq!(response.response_code(), ResponseCode::ServFail);
+            assert_eq!(slow_to_servfail.get(), before.3 + 1);
+        }).await;
+
+        info!("Test completed: test_failover_chain_retries_next_group");
+    }
+
+    #[tokio::test]
+    async fn test_failover_chain_routing_and_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_failover_chain_routing_and_validation");
+
+        let uri = "https://doh.example.net";
+        let routing = routing_config(uri, uri, uri, RULES);
+        let router = Router::new(routing.clone(), Some(Client::new())).await.unwrap();
+        assert_eq!(router.match_domain("chain.example.com").await, RouteDecision::UseGroupsWithFailover {
+            groups: vec!["servfail_group".to_string()],
+            failover_chain: vec!["slow_group".to_string(), "ok_group".to_string()],
+        });
+
+        let config_with_rules = |rules: &str| -> ServerConfig {
+            let mut config: ServerConfig = serde_yaml::from_str(r#"
+            http_server:
+              listen_addr: "127.0.0.1:8053"
+            dns_resolver:
+              upstream:
+                resolvers:
+                  - address: "8.8.8.8:53"
+                    protocol: udp
+            "#).unwrap();
+            config.dns.routing = routing_config(uri, uri, uri, rules);
+            config
+        };
+        let rule = |target: &str, chain: &str| format!(r#"
+          - match:
+              type: exact
+              values: ["example.com"]
+            {}
+            failover_chain: {}"#, target, chain);
+
+        assert!(config_with_rules(RULES).test().is_ok());
+        assert!(config_with_rules(&rule("upstream_groups: [\"servfail_group\", \"slow_group\"]", "[\"ok_group\"]")).test().is_ok());
+
+        // 未知组、重复组、黑洞均无效
+        let err = config_with_rules(&rule("upstream_group: \"servfail_group\"", "[\"missing_group\"]")).test().unwrap_err();
+        assert!(err.to_string().contains("failover_chain"), "Unexpected error: {}", err);
+        assert!(config_with_rules(&rule("upstream_group: \"servfail_group\"", "[\"servfail_group\"]")).test().is_err());
+        assert!(config_with_rules(&rule("upstream_group: \"servfail_group\"", "[\"ok_group\", \"ok_group\"]")).test().is_err());
+        assert!(config_with_rules(&rule("upstream_group: \"servfail_group\"", "[\"__blackhole__\"]")).test().is_err());
+        assert!(config_with_rules(&rule("upstream_group: \"__blackhole__\"", "[\"ok_group\"]")).test().is_err());
+
+        info!("Test completed: test_failover_chain_routing_and_validation");
+    }
+}
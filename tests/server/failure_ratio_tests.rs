@@ -0,0 +1,134 @@
+// tests/server/failure_ratio_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use reqwest::Client;
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::upstream::{UpstreamManager, UpstreamSelection};
+    use crate::server::mock_http_server::{create_test_query, create_test_response, setup_mock_doh_server};
+
+    const HEALTHY_UPSTREAM_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+
+    // 启动一个始终返回 SERVFAIL 的 DoH 上游
+    async fn start_servfail_doh_server() -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let mut response = create_test_response(&query, HEALTHY_UPSTREAM_IP);
+                response.take_answers();
+                response.set_response_code(ResponseCode::ServFail);
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    // 全局上游依次为失败的上游与健康的上游
+    fn failure_ratio_config(failing_uri: &str, healthy_uri: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+            failure_ratio_threshold: 0.3
+            rolling_window_secs: 2
+        "#, failing_uri, healthy_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    async fn resolve(upstream: &UpstreamManager) -> ResponseCode {
+        let query = create_test_query("example.com", RecordType::A);
+        upstream.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap().response_code()
+    }
+
+    #[tokio::test]
+    async fn test_degraded_upstream_is_deprioritized_and_recovers() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_degraded_upstream_is_deprioritized_and_recovers");
+
+        let failing_server = start_servfail_doh_server().await;
+        let (healthy_server, healthy_count) = setup_mock_doh_server(HEALTHY_UPSTREAM_IP).await;
+        let config = failure_ratio_config(&failing_server.uri(), &healthy_server.uri());
+        let upstream = UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap();
+        let failing_url = format!("{}/dns-query", failing_server.uri());
+
+        // 样本数不足前，失败的上游仍是首选
+        for _ in 0..10 {
+            assert_eq!(resolve(&upstream).await, ResponseCode::ServFail);
+        }
+        assert_eq!(*healthy_count.lock().unwrap(), 0);
+        assert_eq!(METRICS.upstream_failure_ratio().with_label_values(&[&failing_url, "global"]).get(), 1.0);
+
+        // 失败率超过阈值后被降级，查询转向健康的上游
+        for _ in 0..3 {
+            assert_eq!(resolve(&upstream).await, ResponseCode::NoError);
+        }
+        assert_eq!(*healthy_count.lock().unwrap(), 3);
+        assert_eq!(failing_server.received_requests().await.unwrap().len(), 10);
+
+        // 窗口过期后失败率回落，恢复原有优先级
+        tokio::time::sleep(Duration::from_millis(3100)).await;
+        assert_eq!(resolve(&upstream).await, ResponseCode::ServFail);
+        assert_eq!(failing_server.received_requests().await.unwrap().len(), 11);
+
+        info!("Test completed: test_degraded_upstream_is_deprioritized_and_recovers");
+    }
+
+    #[test]
+    fn test_failure_ratio_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_failure_ratio_config_validation");
+
+        // 默认不跟踪失败率
+        let default_config = ServerConfig::default();
+        assert!(default_config.dns.upstream.failure_ratio_threshold.is_none());
+        assert!(default_config.dns.upstream.effective_recovery_threshold().is_none());
+        assert_eq!(default_config.dns.upstream.rolling_window_secs, 60);
+
+        // 恢复阈值默认为降级阈值的一半
+        let mut config = failure_ratio_config("https://a.example.com", "https://b.example.com");
+        assert!(config.test().is_ok());
+        assert_eq!(config.dns.upstream.effective_recovery_threshold(), Some(0.15));
+
+        // 阈值必须在 (0, 1] 内
+        config.dns.upstream.failure_ratio_threshold = Some(1.5);
+        assert!(config.test().is_err());
+        config.dns.upstream.failure_ratio_threshold = Some(0.0);
+        assert!(config.test().is_err());
+        config.dns.upstream.failure_ratio_threshold = Some(0.3);
+
+        // 恢复阈值必须低于降级阈值
+        config.dns.upstream.recovery_threshold = Some(0.3);
+        assert!(config.test().is_err());
+        config.dns.upstream.recovery_threshold = Some(0.1);
+        assert!(config.test().is_ok());
+
+        // 窗口长度必须在 1-3600 秒内
+        config.dns.upstream.rolling_window_secs = 0;
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_failure_ratio_config_validation");
+    }
+}
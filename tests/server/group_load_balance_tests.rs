@@ -0,0 +1,173 @@
+// tests/server/group_load_balance_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::net::Ipv4Addr;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use tracing::info;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::error::ServerError;
+    use oxide_wdns::server::routing::{RouteDecision, Router};
+    use oxide_wdns::server::upstream::{TransportFuture, UpstreamManager, UpstreamSelection, UpstreamTransport};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+
+    const FAST_URL: &str = "https://fast.example.com/dns-query";
+    const SLOW_URL: &str = "https://slow.example.com/dns-query";
+
+    // 传输替身：按上游地址延迟应答，未设置延迟的上游不可达
+    #[derive(Default)]
+    struct DelayTransport {
+        delays: Mutex<HashMap<String, Duration>>,
+    }
+
+    impl DelayTransport {
+        fn set(&self, upstream: &str, delay: Option<Duration>) {
+            let mut delays = self.delays.lock().unwrap();
+            match delay {
+                Some(delay) => delays.insert(upstream.to_string(), delay),
+                None => delays.remove(upstream),
+            };
+        }
+    }
+
+    impl UpstreamTransport for DelayTransport {
+        fn exchange<'a>(&'a self, upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+            let delay = self.delays.lock().unwrap().get(upstream).copied();
+            Box::pin(async move {
+                let Some(delay) = delay else {
+                    return Err(ServerError::UpstreamUnavailable(format!("{} is down", upstream)));
+                };
+                tokio::time::sleep(delay).await;
+                Ok(create_test_response(query, Ipv4Addr::new(192, 0, 2, 1)))
+            })
+        }
+    }
+
+    // example.com 同时命中指向 slow 组的精确规则与指向 fast 组的通配符规则；
+    // blocked.example.com 优先命中黑洞规则
+    fn load_balance_config(enabled: bool) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+          routing:
+            enabled: true
+            load_balance_across_groups: {}
+            upstream_groups:
+              - name: "slow"
+                resolvers:
+                  - address: "{}"
+                    protocol: doh
+              - name: "fast"
+                resolvers:
+                  - address: "{}"
+                    protocol: doh
+            rules:
+              - match:
+                  type: exact
+                  values: ["example.com"]
+                upstream_group: "slow"
+              - match:
+                  type: exact
+                  values: ["blocked.example.com"]
+                upstream_group: "__blackhole__"
+              - match:
+                  type: regex
+                  values: [".*example\\.com$"]
+                upstream_group: "fast"
+        "#, enabled, SLOW_URL, FAST_URL);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    async fn build(enabled: bool) -> (Arc<DelayTransport>, Arc<UpstreamManager>, Router) {
+        let config = load_balance_config(enabled);
+        assert!(config.test().is_ok());
+        let transport = Arc::new(DelayTransport::default());
+        transport.set(SLOW_URL, Some(Duration::from_millis(60)));
+        transport.set(FAST_URL, Some(Duration::from_millis(5)));
+        let upstream = Arc::new(UpstreamManager::with_transport(Arc::new(config.clone()), transport.clone()).await.unwrap());
+        let router = Router::new(config.dns.routing, None).await.unwrap().with_upstream(upstream.clone());
+        (transport, upstream, router)
+    }
+
+    async fn query_group(upstream: &UpstreamManager, group: &str) {
+        let query = create_test_query("example.com", RecordType::A);
+        let _ = upstream.resolve(&query, UpstreamSelection::Group(group.to_string()), None, None).await;
+    }
+
+    fn group(name: &str) -> RouteDecision {
+        RouteDecision::UseGroup(name.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_load_balance_selects_healthiest_group() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_load_balance_selects_healthiest_group");
+
+        let (transport, upstream, router) = build(true).await;
+
+        // 尚未查询过的组评分相同，按轮询选择
+        assert!(upstream.get_group_health_score("fast").is_infinite());
+        assert_eq!(upstream.get_group_health_score("unknown"), 0.0);
+        let mut selected = HashSet::new();
+        for _ in 0..2 {
+            match router.match_domain("example.com").await {
+                RouteDecision::UseGroup(name) => selected.insert(name),
+                decision => panic!("unexpected decision: {:?}", decision),
+            };
+        }
+        assert_eq!(selected, HashSet::from(["slow".to_string(), "fast".to_string()]));
+
+        // 延迟更低的组评分更高
+        query_group(&upstream, "slow").await;
+        query_group(&upstream, "fast").await;
+        assert!(upstream.get_group_health_score("fast") > upstream.get_group_health_score("slow"));
+        for _ in 0..3 {
+            assert_eq!(router.match_domain("example.com").await, group("fast"));
+        }
+
+        // 失败率升高后评分下降，转向健康的组
+        transport.set(FAST_URL, None);
+        for _ in 0..30 {
+            query_group(&upstream, "fast").await;
+        }
+        assert!(upstream.get_group_health_score("fast") < upstream.get_group_health_score("slow"));
+        assert_eq!(router.match_domain("example.com").await, group("slow"));
+
+        // 只命中一条规则时不受评分影响，优先级最高的黑洞规则仍阻止查询
+        assert_eq!(router.match_domain("www.example.com").await, group("fast"));
+        assert_eq!(router.match_domain("blocked.example.com").await, RouteDecision::Blackhole);
+
+        info!("Test completed: test_load_balance_selects_healthiest_group");
+    }
+
+    #[tokio::test]
+    async fn test_load_balance_disabled_uses_first_match() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_load_balance_disabled_uses_first_match");
+
+        assert!(!ServerConfig::default().dns.routing.load_balance_across_groups);
+
+        let (_transport, upstream, router) = build(false).await;
+        query_group(&upstream, "slow").await;
+        query_group(&upstream, "fast").await;
+        assert!(upstream.get_group_health_score("fast") > upstream.get_group_health_score("slow"));
+
+        // 未启用时保持首个命中规则的语义
+        for _ in 0..3 {
+            assert_eq!(router.match_domain("example.com").await, group("slow"));
+        }
+
+        info!("Test completed: test_load_balance_disabled_uses_first_match");
+    }
+}
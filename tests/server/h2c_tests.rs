@@ -0,0 +1,101 @@
+// tests/server/h2c_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use reqwest::{Client, Version};
+    use tokio::net::TcpListener;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::http_conn::{serve_plain, ConnectionLimits};
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{extract_ip_addresses, mock_upstream_config, mount_doh_answer};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 76);
+
+    // 在明文监听上启动 DoH 服务器，返回服务地址与模拟上游（需保持存活）
+    async fn start_server(enable_h2c: bool) -> (SocketAddr, MockServer) {
+        let mock = MockServer::start().await;
+        mount_doh_answer(&mock, ANSWER_IP).await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut config = mock_upstream_config(addr.port(), &mock.uri());
+        config.http.enable_h2c = enable_h2c;
+        let limits = ConnectionLimits::from_config(&config.http);
+        let components = DoHServer::new(config, false)
+            .build_application_components()
+            .await
+            .unwrap();
+        tokio::spawn(serve_plain(listener, components.app, limits, enable_h2c, None));
+        (addr, mock)
+    }
+
+    async fn post_query(client: &Client, addr: SocketAddr) -> reqwest::Result<reqwest::Response> {
+        let query = create_test_query("h2c.example.com", RecordType::A);
+        client.post(format!("http://{}/dns-query", addr))
+            .header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+            .body(query.to_vec().unwrap())
+            .send()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_h2c_prior_knowledge_alongside_http1() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_h2c_prior_knowledge_alongside_http1");
+
+        let (addr, _mock) = start_server(true).await;
+
+        // 强制使用 HTTP/2 先验知识的客户端
+        let h2c_client = Client::builder().http2_prior_knowledge().build().unwrap();
+        let response = post_query(&h2c_client, addr).await.unwrap();
+        assert_eq!(response.version(), Version::HTTP_2);
+        let message = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+        assert_eq!(extract_ip_addresses(&message), vec![ANSWER_IP.to_string()]);
+
+        // 同一连接上的多个并发流
+        let query = create_test_query("h2c.example.com", RecordType::A);
+        let url = format!("http://{}/dns-query?dns={}", addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let responses = futures::future::join_all((0..4).map(|_| h2c_client.get(&url).send())).await;
+        for response in responses {
+            let response = response.unwrap();
+            assert_eq!(response.version(), Version::HTTP_2);
+            assert!(response.status().is_success());
+        }
+
+        // 同一端口上的 HTTP/1.1 客户端不受影响
+        let http1_client = Client::builder().http1_only().build().unwrap();
+        let response = post_query(&http1_client, addr).await.unwrap();
+        assert_eq!(response.version(), Version::HTTP_11);
+        assert!(response.status().is_success());
+
+        info!("Test completed: test_h2c_prior_knowledge_alongside_http1");
+    }
+
+    #[tokio::test]
+    async fn test_h2c_disabled_by_default() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_h2c_disabled_by_default");
+
+        assert!(!mock_upstream_config(8053, "http://127.0.0.1:1").http.enable_h2c);
+        let (addr, _mock) = start_server(false).await;
+
+        // 未启用时明文监听只提供 HTTP/1.1，HTTP/2 先验知识连接失败
+        let h2c_client = Client::builder().http2_prior_knowledge().build().unwrap();
+        assert!(post_query(&h2c_client, addr).await.is_err());
+
+        let http1_client = Client::builder().http1_only().build().unwrap();
+        let response = post_query(&http1_client, addr).await.unwrap();
+        assert_eq!(response.version(), Version::HTTP_11);
+        assert!(response.status().is_success());
+
+        info!("Test completed: test_h2c_disabled_by_default");
+    }
+}
@@ -172,4 +172,57 @@ mod tests {
         info!("Validated second response body.");
         info!("Test completed: test_health_check_upstream_dependency");
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_admin_routes_on_separate_listener() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::util::ServiceExt;
+        use oxide_wdns::server::config::ServerConfig;
+        use oxide_wdns::server::DoHServer;
+
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_admin_routes_on_separate_listener");
+
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          admin_listen_addr: "127.0.0.1:9053"
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#;
+        let config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        assert!(config.test().is_ok());
+
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        let admin_app = components.admin_app.expect("Admin router should be split out");
+        assert!(components.metrics_app.is_none());
+
+        let status_of = |app: axum::Router, uri: &'static str| async move {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            app.oneshot(request).await.unwrap().status().as_u16()
+        };
+
+        // 管理路由只在管理监听上提供
+        assert_eq!(status_of(admin_app.clone(), "/health").await, 200);
+        assert_eq!(status_of(admin_app.clone(), "/metrics").await, 200);
+        assert_eq!(status_of(components.app.clone(), "/health").await, 404);
+        assert_eq!(status_of(components.app.clone(), "/metrics").await, 404);
+
+        // DoH 路由仍在主监听上，不在管理监听上
+        assert_eq!(status_of(components.app, "/dns-query").await, 400);
+        assert_eq!(status_of(admin_app, "/dns-query").await, 404);
+
+        // 管理监听地址与主监听地址相同应被拒绝
+        let same_addr: ServerConfig = serde_yaml::from_str(&config_str.replace("127.0.0.1:9053", "127.0.0.1:8053")).unwrap();
+        assert!(same_addr.test().is_err());
+
+        info!("Test completed: test_admin_routes_on_separate_listener");
+    }
+}
@@ -0,0 +1,163 @@
+// tests/server/http3_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::path::Path;
+    use std::sync::Arc;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use bytes::{Buf, Bytes};
+    use tempfile::TempDir;
+    use tokio_rustls::rustls::crypto::ring::default_provider;
+    use tokio_rustls::rustls::pki_types::CertificateDer;
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::version::TLS13;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::http3::{bind_h3, serve_h3, shutdown_h3};
+    use oxide_wdns::server::tls::TlsContext;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::test_helpers::write_self_signed_cert;
+
+    // 创建启用 HTTP/3 的服务器配置
+    fn http3_config(dir: &Path, h3_port: u16) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          tls:
+            cert: "{}"
+            key: "{}"
+          http3:
+            enabled: true
+            listen_addr: "127.0.0.1:{}"
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#, dir.join("cert.pem").display(), dir.join("key.pem").display(), h3_port);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 通过 HTTP/3 发送 GET 请求，返回状态码与响应体
+    async fn h3_get(addr: SocketAddr, cert_pem: &str, path: &str) -> (u16, String) {
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from_pem_slice(cert_pem.as_bytes()).unwrap()).unwrap();
+        let mut tls_config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_protocol_versions(&[&TLS13])
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        tls_config.alpn_protocols = vec![b"h3".to_vec()];
+
+        let quic_config = quinn::crypto::rustls::QuicClientConfig::try_from(tls_config).unwrap();
+        let mut endpoint = quinn::Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(quic_config)));
+
+        let connection = endpoint.connect(addr, "localhost").unwrap().await.unwrap();
+        let (mut driver, mut send_request) = h3::client::new(h3_quinn::Connection::new(connection)).await.unwrap();
+        tokio::spawn(async move {
+            let _ = std::future::poll_fn(|cx| driver.poll_close(cx)).await;
+        });
+
+        let request = Request::get(format!("https://localhost:{}{}", addr.port(), path))
+            .body(())
+            .unwrap();
+        let mut stream = send_request.send_request(request).await.unwrap();
+        stream.finish().await.unwrap();
+
+        let response = stream.recv_response().await.unwrap();
+        let mut body = Vec::new();
+        while let Some(mut chunk) = stream.recv_data().await.unwrap() {
+            let bytes: Bytes = chunk.copy_to_bytes(chunk.remaining());
+            body.extend_from_slice(&bytes);
+        }
+
+        (response.status().as_u16(), String::from_utf8(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_http3_serves_shared_application() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_http3_serves_shared_application");
+
+        let dir = TempDir::new().unwrap();
+        let cert_pem = write_self_signed_cert(dir.path());
+
+        // 绑定 QUIC 端点后再构建应用，使 Alt-Svc 使用实际端口
+        let tls = TlsContext::new(http3_config(dir.path(), 0).http.tls.as_ref().unwrap()).unwrap();
+        let endpoint = bind_h3("127.0.0.1:0".parse().unwrap(), &tls).unwrap();
+        let h3_addr = endpoint.local_addr().unwrap();
+        let config = http3_config(dir.path(), h3_addr.port());
+        assert!(config.test().is_ok());
+
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        let h3_app = components.h3_app.expect("HTTP/3 app should be built when http3 is enabled");
+        tokio::spawn(serve_h3(endpoint.clone(), h3_app));
+
+        // HTTP/3 监听应提供与 TCP 监听相同的路由
+        info!("Sending HTTP/3 request to {}", h3_addr);
+        let (status, body) = h3_get(h3_addr, &cert_pem, "/health").await;
+        assert_eq!(status, 200);
+        assert_eq!(body, "ok!!");
+
+        // TCP 监听的响应应通过 Alt-Svc 公告 HTTP/3 端口
+        let response = components.app
+            .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let alt_svc = response.headers().get("alt-svc").unwrap().to_str().unwrap();
+        assert_eq!(alt_svc, format!("h3=\":{}\"; ma=86400", h3_addr.port()));
+
+        shutdown_h3(&endpoint).await;
+
+        info!("Test completed: test_http3_serves_shared_application");
+    }
+
+    #[tokio::test]
+    async fn test_http3_disabled_by_default() {
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+        assert!(!config.http.http3.enabled);
+        assert!(config.http.http3_listen_addr().is_none());
+
+        // 未启用时不构建 HTTP/3 应用，也不添加 Alt-Svc 头
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        assert!(components.h3_app.is_none());
+        let response = components.app
+            .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(response.headers().get("alt-svc").is_none());
+    }
+
+    #[test]
+    fn test_http3_config_validation() {
+        let dir = TempDir::new().unwrap();
+        write_self_signed_cert(dir.path());
+
+        // 未设置监听地址时与 listen_addr 相同
+        let mut config = http3_config(dir.path(), 8443);
+        config.http.http3.listen_addr = None;
+        assert_eq!(config.http.http3_listen_addr(), Some("127.0.0.1:8053".parse().unwrap()));
+        assert!(config.test().is_ok());
+
+        // HTTP/3 需要 TLS
+        config.http.tls = None;
+        assert!(config.test().is_err());
+    }
+}
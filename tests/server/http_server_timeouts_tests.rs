@@ -0,0 +1,199 @@
+// tests/server/http_server_timeouts_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::time::Duration;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::http_conn::{serve_plain, ConnectionLimits};
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::mock_upstream_config;
+
+    const REQUEST: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+    // 启动提供固定应答的明文 HTTP 服务器
+    async fn start_plain_server(limits: ConnectionLimits) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", get(|| async { "ok" }));
+        tokio::spawn(serve_plain(listener, app, limits, false, None));
+        addr
+    }
+
+    // 在已有连接上发送一个请求并读取完整响应
+    async fn send_request(stream: &mut TcpStream) -> String {
+        stream.write_all(REQUEST).await.unwrap();
+        let mut response = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !response.ends_with(b"\r\n\r\nok") {
+            let len = stream.read(&mut buf).await.unwrap();
+            assert!(len > 0, "connection closed before the response was complete");
+            response.extend_from_slice(&buf[..len]);
+        }
+        String::from_utf8(response).unwrap().to_ascii_lowercase()
+    }
+
+    // 等待服务器关闭连接
+    async fn assert_closed_within(stream: &mut TcpStream, within: Duration) {
+        let mut buf = [0u8; 16];
+        let read = tokio::time::timeout(within, stream.read(&mut buf)).await
+            .expect("server should have closed the connection");
+        assert!(matches!(read, Ok(0) | Err(_)));
+    }
+
+    fn limits(header_read_timeout: u64, idle_timeout: u64, max_requests: Option<u64>) -> ConnectionLimits {
+        ConnectionLimits {
+            header_read_timeout: Duration::from_secs(header_read_timeout),
+            idle_timeout: Duration::from_secs(idle_timeout),
+            max_requests,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_returns_504() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_request_timeout_returns_504");
+
+        // 上游在请求时限之后才应答
+        let mock = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(create_test_response(&query, Ipv4Addr::new(192, 0, 2, 1)).to_vec().unwrap())
+                    .set_delay(Duration::from_secs(3))
+            })
+            .mount(&mock)
+            .await;
+
+        let mut config = mock_upstream_config(8053, &mock.uri());
+        config.http.request_timeout = 1;
+        config.dns.upstream.query_timeout = 5;
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+
+        let timeouts = METRICS.http_request_timeouts_total().with_label_values(&["GET"]);
+        let before = timeouts.get();
+        let query = create_test_query("slow.example.com", RecordType::A);
+        let request = Request::get(format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap())))
+            .body(Body::empty())
+            .unwrap();
+        let response = components.app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(timeouts.get() > before);
+
+        info!("Test completed: test_request_timeout_returns_504");
+    }
+
+    #[tokio::test]
+    async fn test_max_requests_per_connection() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_max_requests_per_connection");
+
+        let addr = start_plain_server(limits(10, 60, Some(2))).await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        // 第一个请求保持连接，达到上限的请求完成后连接关闭
+        assert!(!send_request(&mut stream).await.contains("connection: close"));
+        send_request(&mut stream).await;
+        assert_closed_within(&mut stream, Duration::from_secs(2)).await;
+
+        info!("Test completed: test_max_requests_per_connection");
+    }
+
+    #[tokio::test]
+    async fn test_idle_and_header_read_timeouts() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_idle_and_header_read_timeouts");
+
+        let addr = start_plain_server(limits(1, 1, None)).await;
+
+        // 空闲超过 idle_keepalive_timeout 的连接被关闭
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        send_request(&mut stream).await;
+        send_request(&mut stream).await;
+        assert_closed_within(&mut stream, Duration::from_secs(3)).await;
+
+        // 连接后未发送完整请求头的连接在 read_header_timeout 后被关闭
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        assert_closed_within(&mut stream, Duration::from_secs(3)).await;
+
+        // idle_keepalive_timeout 为 0 时不保持连接
+        let addr = start_plain_server(limits(1, 0, None)).await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        assert!(send_request(&mut stream).await.contains("connection: close"));
+        assert_closed_within(&mut stream, Duration::from_secs(2)).await;
+
+        info!("Test completed: test_idle_and_header_read_timeouts");
+    }
+
+    #[test]
+    fn test_http_server_timeouts_config() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_http_server_timeouts_config");
+
+        let config = ServerConfig::default();
+        assert_eq!(config.http.request_timeout, 120);
+        assert_eq!(config.http.read_header_timeout, 10);
+        assert_eq!(config.http.idle_keepalive_timeout, 60);
+        assert_eq!(config.http.max_requests_per_connection, 0);
+        assert!(ConnectionLimits::default().max_requests.is_none());
+
+        // timeout 是 request_timeout 的别名
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          timeout: 15
+          read_header_timeout: 5
+          idle_keepalive_timeout: 0
+          max_requests_per_connection: 100
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+        assert_eq!(config.http.request_timeout, 15);
+        // 请求时限小于上游查询超时只告警，不视为错误
+        assert!(config.test().is_ok());
+        let limits = ConnectionLimits::from_config(&config.http);
+        assert_eq!(limits.header_read_timeout, Duration::from_secs(5));
+        assert!(limits.idle_timeout.is_zero());
+        assert_eq!(limits.max_requests, Some(100));
+
+        let invalid: [fn(&mut ServerConfig); 4] = [
+            |c| c.http.request_timeout = 0,
+            |c| c.http.request_timeout = 3601,
+            |c| c.http.read_header_timeout = 0,
+            |c| c.http.idle_keepalive_timeout = 3601,
+        ];
+        for mutate in invalid {
+            let mut config = ServerConfig::default();
+            mutate(&mut config);
+            assert!(config.test().is_err());
+        }
+
+        info!("Test completed: test_http_server_timeouts_config");
+    }
+}
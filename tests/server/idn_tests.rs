@@ -0,0 +1,153 @@
+// tests/server/idn_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use serde_json::Value;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::DoHServer;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::route_test::{build_router, route_test};
+    use oxide_wdns::server::routing::normalize_query_domain;
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{extract_ip_addresses, mount_doh_answer};
+
+    const PUNYCODE_NAME: &str = "xn--caf-dma.example";
+    const GLOBAL_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const INTERNAL_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 2);
+
+    // 创建将国际化域名路由到 internal 组的配置，规则可使用 Unicode 或 punycode 形式
+    fn idn_config(global_uri: &str, internal_uri: &str, rule_domain: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: false
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "internal"
+                resolvers:
+                  - address: "{}/dns-query"
+                    protocol: doh
+            rules:
+              - match:
+                  type: exact
+                  values: ["{}"]
+                upstream_group: "internal"
+        "#, global_uri, internal_uri, rule_domain);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 通过 JSON API 查询，返回应答地址与问题部分的名称
+    async fn resolve_json(app: &Router, name: &str) -> (Vec<String>, String) {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("name", name)
+            .append_pair("type", "A")
+            .finish();
+        let request = Request::get(format!("/resolve?{}", query))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", name);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        let ips = json["Answer"].as_array().unwrap().iter()
+            .map(|answer| answer["data"].as_str().unwrap().to_string())
+            .collect();
+        (ips, json["Question"][0]["name"].as_str().unwrap().to_string())
+    }
+
+    // 通过 wireformat POST 查询，返回应答地址
+    async fn resolve_wire(app: &Router, name: &str) -> Vec<String> {
+        let query = create_test_query(name, RecordType::A);
+        let request = Request::post("/dns-query")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+            .body(Body::from(query.to_vec().unwrap()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", name);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        extract_ip_addresses(&Message::from_vec(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_idn_json_and_wire_queries_match_same_rule() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_idn_json_and_wire_queries_match_same_rule");
+
+        let global = MockServer::start().await;
+        mount_doh_answer(&global, GLOBAL_IP).await;
+        let internal = MockServer::start().await;
+        mount_doh_answer(&internal, INTERNAL_IP).await;
+
+        // 规则以 Unicode 或 punycode 书写时行为一致
+        for rule_domain in ["café.example", PUNYCODE_NAME] {
+            let config = idn_config(&global.uri(), &internal.uri(), rule_domain);
+            let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+            // JSON 的 Unicode 名称被转换为 punycode，与 punycode 名称和 wireformat 查询命中同一规则
+            for name in ["café.example", "CAFÉ.example.", PUNYCODE_NAME] {
+                let (ips, question) = resolve_json(&app, name).await;
+                assert_eq!(ips, vec![INTERNAL_IP.to_string()], "rule {} name {}", rule_domain, name);
+                assert_eq!(question, format!("{}.", PUNYCODE_NAME));
+            }
+            assert_eq!(resolve_wire(&app, PUNYCODE_NAME).await, vec![INTERNAL_IP.to_string()]);
+
+            // 未命中规则的国际化域名使用全局上游
+            let (ips, question) = resolve_json(&app, "thé.example").await;
+            assert_eq!(ips, vec![GLOBAL_IP.to_string()]);
+            assert_eq!(question, "xn--th-cja.example.");
+        }
+
+        // 上游收到的查询名为 punycode
+        let requests = internal.received_requests().await.unwrap();
+        assert!(!requests.is_empty());
+        for request in requests {
+            let query = Message::from_vec(&request.body).unwrap();
+            assert_eq!(query.queries()[0].name().to_ascii(), format!("{}.", PUNYCODE_NAME));
+        }
+
+        info!("Test completed: test_idn_json_and_wire_queries_match_same_rule");
+    }
+
+    #[tokio::test]
+    async fn test_idn_query_domain_normalization() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_idn_query_domain_normalization");
+
+        assert_eq!(normalize_query_domain("Café.Example."), PUNYCODE_NAME);
+        assert_eq!(normalize_query_domain("XN--CAF-DMA.example."), PUNYCODE_NAME);
+        assert_eq!(normalize_query_domain("WWW.Example.com."), "www.example.com");
+        // 下划线标签保持不变
+        assert_eq!(normalize_query_domain("_dmarc.Example.com"), "_dmarc.example.com");
+
+        // 路由测试对 Unicode 与 punycode 名称给出相同结果
+        let config = idn_config("http://127.0.0.1:9", "http://127.0.0.1:9", PUNYCODE_NAME);
+        let router = build_router(&config).await.unwrap();
+        for name in ["café.example", "Café.Example.", PUNYCODE_NAME] {
+            let result = route_test(&router, name, None).await;
+            assert_eq!(result.domain, PUNYCODE_NAME);
+            assert_eq!(result.target(), "internal");
+            assert_eq!(result.rule, Some(format!("exact:{}", PUNYCODE_NAME)));
+        }
+
+        info!("Test completed: test_idn_query_domain_normalization");
+    }
+}
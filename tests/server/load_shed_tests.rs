@@ -0,0 +1,120 @@
+// tests/server/load_shed_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::common::consts::LOAD_SHED_RETRY_AFTER_SECS;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::mount_doh_answer_with_delay;
+
+    const UPSTREAM_DELAY: Duration = Duration::from_millis(1000);
+
+    async fn build_app(upstream_uri: &str, max_concurrent_requests: usize) -> Router {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          max_concurrent_requests: {}
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 5
+          cache:
+            enabled: false
+        "#, max_concurrent_requests, upstream_uri);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        DoHServer::new(config, false).build_application_components().await.unwrap().app
+    }
+
+    fn query_request(name: &str) -> Request<Body> {
+        let query = create_test_query(name, RecordType::A);
+        Request::get(format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap())))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_sheds_excess_requests() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_concurrency_limit_sheds_excess_requests");
+
+        let upstream = MockServer::start().await;
+        mount_doh_answer_with_delay(&upstream, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let app = build_app(&upstream.uri(), 2).await;
+        let shed_before = METRICS.requests_shed_total().get();
+
+        // 两个慢查询占满并发限制
+        let handles: Vec<_> = ["slow-1.example.com", "slow-2.example.com"].into_iter()
+            .map(|name| {
+                let app = app.clone();
+                tokio::spawn(async move { app.oneshot(query_request(name)).await.unwrap().status() })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(METRICS.http_requests_in_flight().get() >= 2);
+
+        // 超出限制的请求不排队，立即返回 503
+        let started = std::time::Instant::now();
+        let response = app.clone().oneshot(query_request("shed.example.com")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(started.elapsed() < UPSTREAM_DELAY / 2);
+        assert_eq!(
+            response.headers().get(header::RETRY_AFTER).unwrap().to_str().unwrap(),
+            LOAD_SHED_RETRY_AFTER_SECS.to_string()
+        );
+        assert!(METRICS.requests_shed_total().get() > shed_before);
+
+        // 健康检查不受并发限制
+        let response = app.clone().oneshot(Request::get("/health").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), StatusCode::OK);
+        }
+
+        // 进行中的请求完成后恢复处理
+        let response = app.oneshot(query_request("after.example.com")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        info!("Test completed: test_concurrency_limit_sheds_excess_requests");
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_disabled_by_default() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_concurrency_limit_disabled_by_default");
+
+        assert_eq!(ServerConfig::default().http.max_concurrent_requests, 0);
+
+        // 未配置限制时并发请求全部被处理
+        let upstream = MockServer::start().await;
+        mount_doh_answer_with_delay(&upstream, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let app = build_app(&upstream.uri(), 0).await;
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let app = app.clone();
+                tokio::spawn(async move { app.oneshot(query_request(&format!("unlimited-{}.example.com", i))).await.unwrap().status() })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), StatusCode::OK);
+        }
+
+        info!("Test completed: test_concurrency_limit_disabled_by_default");
+    }
+}
@@ -0,0 +1,136 @@
+// tests/server/log_level_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use tracing_subscriber::{reload, EnvFilter, Registry};
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::log_level::{log_level_routes, LogLevelControl};
+    use oxide_wdns::server::DoHServer;
+
+    const ADMIN_TOKEN: &str = "s3cret-admin";
+
+    // 创建过滤规则控制；reload 句柄只持有弱引用，需同时保留 reload 层
+    fn log_level_control(directives: &str) -> (reload::Layer<EnvFilter, Registry>, Arc<LogLevelControl>) {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new(directives));
+        (layer, Arc::new(LogLevelControl::new(handle)))
+    }
+
+    fn admin_config(admin_token: Option<&str>) -> ServerConfig {
+        let mut config = ServerConfig::default();
+        config.http.admin_token = admin_token.map(str::to_string);
+        config
+    }
+
+    fn put_request(filter: &str, token: Option<&str>) -> Request<Body> {
+        let mut builder = Request::put("/log-level");
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        builder.body(Body::from(filter.to_string())).unwrap()
+    }
+
+    async fn put_log_level(app: &Router, filter: &str, token: Option<&str>) -> (StatusCode, serde_json::Value) {
+        let response = app.clone().oneshot(put_request(filter, token)).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    #[test]
+    fn test_log_level_control_set_returns_previous() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_log_level_control_set_returns_previous");
+
+        let (_layer, control) = log_level_control("info");
+        assert_eq!(control.current().unwrap(), "info");
+
+        assert_eq!(control.set("debug,hickory_proto=info").unwrap(), "info");
+        let current = control.current().unwrap();
+        assert!(current.contains("debug") && current.contains("hickory_proto=info"));
+
+        // 无效或空规则被拒绝，当前规则保持不变
+        assert!(control.set("hickory_proto=loud").is_err());
+        assert!(control.set("  ").is_err());
+        assert_eq!(control.current().unwrap(), current);
+
+        info!("Test completed: test_log_level_control_set_returns_previous");
+    }
+
+    #[tokio::test]
+    async fn test_log_level_endpoint_responses() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_log_level_endpoint_responses");
+
+        let (_layer, control) = log_level_control("warn");
+        let app = log_level_routes(control.clone());
+
+        let (status, body) = put_log_level(&app, "oxide_wdns=trace", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["previous"], "warn");
+        assert_eq!(body["current"], "oxide_wdns=trace");
+
+        let (status, _) = put_log_level(&app, "oxide_wdns=verbose", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(control.current().unwrap(), "oxide_wdns=trace");
+
+        info!("Test completed: test_log_level_endpoint_responses");
+    }
+
+    #[tokio::test]
+    async fn test_log_level_endpoint_requires_admin_token() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_log_level_endpoint_requires_admin_token");
+
+        let (_layer, control) = log_level_control("info");
+        let components = DoHServer::new(admin_config(Some(ADMIN_TOKEN)), false)
+            .with_log_level(control.clone())
+            .build_application_components()
+            .await
+            .unwrap();
+
+        let (status, _) = put_log_level(&components.app, "debug", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = put_log_level(&components.app, "debug", Some("wrong-token")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(control.current().unwrap(), "info");
+
+        let (status, body) = put_log_level(&components.app, "debug", Some(ADMIN_TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["previous"], "info");
+        assert_eq!(control.current().unwrap(), "debug");
+
+        // 未配置 admin_token 时不提供该端点
+        let components = DoHServer::new(admin_config(None), false)
+            .with_log_level(control.clone())
+            .build_application_components()
+            .await
+            .unwrap();
+        let response = components.app.oneshot(put_request("info", Some(ADMIN_TOKEN))).await.unwrap();
+        assert_ne!(response.status(), StatusCode::OK);
+        assert_eq!(control.current().unwrap(), "debug");
+
+        info!("Test completed: test_log_level_endpoint_requires_admin_token");
+    }
+
+    #[test]
+    fn test_admin_token_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_admin_token_validation");
+
+        assert!(ServerConfig::default().http.admin_token.is_none());
+        assert!(admin_config(Some(ADMIN_TOKEN)).test().is_ok());
+        assert!(admin_config(Some(" ")).test().is_err());
+
+        info!("Test completed: test_admin_token_validation");
+    }
+}
@@ -0,0 +1,193 @@
+// tests/server/logging_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::Ipv4Addr;
+    use std::sync::{Arc, Mutex};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::rr::RecordType;
+    use serde_json::Value;
+    use tracing::{info, info_span};
+    use tracing_subscriber::prelude::*;
+    use oxide_wdns::server::config::{LogFormat, LoggingConfig, ServerConfig};
+    use oxide_wdns::server::logging::format_layer;
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{mount_doh_answer, test_with_server_config};
+
+    // 收集日志输出的缓冲区
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl LogBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+
+        // 按行解析 JSON 日志，任一行不是 JSON 对象时测试失败
+        fn json_lines(&self) -> Vec<serde_json::Map<String, Value>> {
+            self.contents().lines()
+                .map(|line| match serde_json::from_str(line) {
+                    Ok(Value::Object(entry)) => entry,
+                    other => panic!("Log line is not a JSON object: {} ({:?})", line, other),
+                })
+                .collect()
+        }
+    }
+
+    // 使用指定日志配置的线程本地订阅器，返回日志缓冲区与订阅器守卫
+    fn capture(config: LoggingConfig) -> (LogBuffer, tracing::subscriber::DefaultGuard) {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(format_layer(&config, move || writer.clone()));
+        (buffer, tracing::subscriber::set_default(subscriber))
+    }
+
+    fn json_config(add_source_location: bool) -> LoggingConfig {
+        LoggingConfig { format: LogFormat::Json, level: None, add_source_location, ..Default::default() }
+    }
+
+    #[test]
+    fn test_json_log_format_flattens_span_fields() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_json_log_format_flattens_span_fields");
+
+        let (buffer, guard) = capture(json_config(true));
+        let span = info_span!("doh_request", request_id = "req-1", client_ip = "192.0.2.1");
+        span.in_scope(|| {
+            info!(name = "example.com", query_type = "A", upstream = "8.8.8.8:53", cached = false, "Query resolved");
+        });
+        drop(guard);
+
+        let lines = buffer.json_lines();
+        assert_eq!(lines.len(), 1);
+        let entry = &lines[0];
+        assert!(entry["timestamp"].as_str().is_some_and(|ts| !ts.is_empty()));
+        assert_eq!(entry["level"], "INFO");
+        assert_eq!(entry["target"], module_path!());
+        assert_eq!(entry["message"], "Query resolved");
+        // 事件字段与 span 字段均为顶层键，布尔值保留原类型
+        assert_eq!(entry["name"], "example.com");
+        assert_eq!(entry["query_type"], "A");
+        assert_eq!(entry["upstream"], "8.8.8.8:53");
+        assert_eq!(entry["cached"], false);
+        assert_eq!(entry["request_id"], "req-1");
+        assert_eq!(entry["client_ip"], "192.0.2.1");
+        // 启用源码位置
+        assert!(entry["file"].as_str().is_some_and(|file| file.ends_with("logging_tests.rs")));
+        assert!(entry["line"].as_u64().is_some());
+
+        // 未启用源码位置时不输出 file 与 line
+        let (buffer, guard) = capture(json_config(false));
+        info!("No source location");
+        drop(guard);
+        let lines = buffer.json_lines();
+        assert!(!lines[0].contains_key("file") && !lines[0].contains_key("line"));
+
+        // 文本格式不是 JSON
+        let (buffer, guard) = capture(LoggingConfig::default());
+        info!(name = "example.com", "Text line");
+        drop(guard);
+        let contents = buffer.contents();
+        assert!(contents.contains("Text line") && contents.contains("name=\"example.com\""));
+        assert!(serde_json::from_str::<Value>(contents.trim()).is_err());
+
+        info!("Test completed: test_json_log_format_flattens_span_fields");
+    }
+
+    #[tokio::test]
+    async fn test_json_log_format_for_doh_requests() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_json_log_format_for_doh_requests");
+
+        let (buffer, guard) = capture(json_config(false));
+        test_with_server_config(|_| {}, |server_addr, mock| async move {
+            mount_doh_answer(&mock, Ipv4Addr::new(192, 0, 2, 1)).await;
+            let query = create_test_query("example.com", RecordType::A);
+            let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+            let response = reqwest::Client::new().get(url).header("X-Request-ID", "json-log-test").send().await.unwrap();
+            assert!(response.status().is_success());
+        }).await;
+        drop(guard);
+
+        // 所有日志行均可解析为 JSON，请求内的日志带有请求 ID
+        let lines = buffer.json_lines();
+        let entry = lines.iter()
+            .find(|entry| entry.get("message").and_then(Value::as_str) == Some("DNS-over-HTTPS GET request received"))
+            .expect("Missing DoH request log line");
+        assert_eq!(entry["request_id"], "json-log-test");
+        assert_eq!(entry["level"], "DEBUG");
+        assert!(entry.contains_key("client_ip"));
+
+        info!("Test completed: test_json_log_format_for_doh_requests");
+    }
+
+    #[test]
+    fn test_logging_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_logging_config_validation");
+
+        let parse = |logging: &str| -> ServerConfig {
+            serde_yaml::from_str(&format!(r#"
+            http_server:
+              listen_addr: "127.0.0.1:8053"
+            dns_resolver:
+              upstream:
+                resolvers:
+                  - address: "8.8.8.8:53"
+                    protocol: udp
+            logging:
+{}
+            "#, logging)).unwrap()
+        };
+
+        let config = parse(r#"
+              format: json
+              level: "info,oxide_wdns=debug"
+              add_source_location: true"#);
+        assert!(config.test().is_ok());
+        assert_eq!(config.logging.format, LogFormat::Json);
+        assert!(config.logging.add_source_location);
+
+        // pretty 格式；未配置时默认为文本格式
+        let config = parse("              format: pretty");
+        assert_eq!(config.logging.format, LogFormat::Pretty);
+        assert_eq!(ServerConfig::default().logging.format, LogFormat::Text);
+
+        // 无效的过滤规则
+        let config = parse(r#"              level: "oxide_wdns=[""#);
+        let err = config.test().unwrap_err();
+        assert!(err.to_string().contains("logging.level"), "Unexpected error: {}", err);
+
+        // 未知的格式
+        let result: Result<ServerConfig, _> = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        logging:
+          format: xml
+        "#);
+        assert!(result.is_err());
+
+        info!("Test completed: test_logging_config_validation");
+    }
+}
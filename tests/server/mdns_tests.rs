@@ -0,0 +1,211 @@
+// tests/server/mdns_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use hickory_proto::op::{Message, MessageType, ResponseCode};
+    use hickory_proto::rr::{RData, Record, RecordType};
+    use hickory_proto::rr::rdata::A;
+    use tokio::net::UdpSocket;
+    use tracing::info;
+    use oxide_wdns::server::config::{MdnsForwarderConfig, ServerConfig};
+    use oxide_wdns::server::mdns::MdnsForwarder;
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{post_query, server_state};
+
+    const PRINTER_IP: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 50);
+
+    // 启动模拟 mDNS 响应方，只应答 known_name 的查询，返回监听地址与收到的查询数
+    async fn start_mdns_responder(known_name: &'static str) -> (SocketAddr, Arc<AtomicUsize>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 4096];
+            while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let query = Message::from_vec(&buf[..len]).unwrap();
+                let name = query.queries()[0].name().clone();
+                if name.to_ascii() != known_name {
+                    continue;
+                }
+
+                // 先发送一个 ID 不匹配的响应，应被忽略
+                let mut stray = Message::new();
+                stray.set_id(query.id().wrapping_add(1)).set_message_type(MessageType::Response);
+                socket.send_to(&stray.to_vec().unwrap(), from).await.unwrap();
+
+                let mut response = Message::new();
+                response.set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .set_authoritative(true)
+                    .add_queries(query.queries().to_vec())
+                    .add_answer(Record::from_rdata(name, 120, RData::A(A(PRINTER_IP))));
+                socket.send_to(&response.to_vec().unwrap(), from).await.unwrap();
+            }
+        });
+
+        (addr, received)
+    }
+
+    // 创建指向模拟响应方的 mDNS 转发配置
+    fn mdns_config(responder: SocketAddr) -> MdnsForwarderConfig {
+        MdnsForwarderConfig {
+            enabled: true,
+            multicast_addr: responder,
+            mdns_response_timeout_ms: 200,
+            ..MdnsForwarderConfig::default()
+        }
+    }
+
+    // 创建启用 mDNS 转发与缓存的配置
+    fn mdns_server_config(responder: SocketAddr) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "127.0.0.1:1"
+                protocol: udp
+            query_timeout: 1
+          cache:
+            enabled: true
+          mdns_forwarder:
+            enabled: true
+            multicast_addr: "{}"
+            mdns_response_timeout_ms: 200
+            mdns_negative_ttl_secs: 30
+        "#, responder);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        assert!(config.test().is_ok());
+        config
+    }
+
+    #[test]
+    fn test_mdns_forwarder_matches_suffixes() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_mdns_forwarder_matches_suffixes");
+
+        let forwarder = MdnsForwarder::new(&MdnsForwarderConfig::default());
+        assert!(forwarder.matches("printer.local"));
+        assert!(forwarder.matches("printer.local."));
+        assert!(forwarder.matches("Office-Printer.LOCAL."));
+        assert!(forwarder.matches("_ipp._tcp.local."));
+
+        // 仅匹配完整的标签后缀
+        assert!(!forwarder.matches("local."));
+        assert!(!forwarder.matches("printerlocal."));
+        assert!(!forwarder.matches("printer.local.example.com."));
+
+        info!("Test completed: test_mdns_forwarder_matches_suffixes");
+    }
+
+    #[tokio::test]
+    async fn test_mdns_forwarder_returns_first_valid_response() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_mdns_forwarder_returns_first_valid_response");
+
+        let (responder, _) = start_mdns_responder("printer.local.").await;
+        let forwarder = MdnsForwarder::new(&mdns_config(responder));
+
+        let mut query = create_test_query("printer.local.", RecordType::A);
+        query.set_id(4321);
+        let response = forwarder.query(&query).await.unwrap().expect("mDNS answer");
+
+        // 响应使用客户端的查询 ID 与问题部分
+        assert_eq!(response.id(), 4321);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.queries(), query.queries());
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(response.answers()[0].data(), Some(&RData::A(A(PRINTER_IP))));
+
+        // 超时无响应时返回 None
+        let query = create_test_query("unknown.local.", RecordType::A);
+        assert!(forwarder.query(&query).await.unwrap().is_none());
+
+        info!("Test completed: test_mdns_forwarder_returns_first_valid_response");
+    }
+
+    #[tokio::test]
+    async fn test_doh_query_forwarded_over_mdns_with_negative_cache() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_doh_query_forwarded_over_mdns_with_negative_cache");
+
+        let (responder, received) = start_mdns_responder("printer.local.").await;
+        let state = server_state(mdns_server_config(responder)).await;
+
+        // .local 查询通过 mDNS 解析，不经过（不可达的）上游
+        let response = post_query(state.clone(), "printer.local.", 1001).await;
+        assert_eq!(response.id(), 1001);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.answers()[0].data(), Some(&RData::A(A(PRINTER_IP))));
+
+        // 肯定应答不缓存，再次查询会重新发送 mDNS 查询
+        post_query(state.clone(), "printer.local.", 1002).await;
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+
+        // 超时无响应时返回 NXDOMAIN，并进行负缓存
+        let response = post_query(state.clone(), "missing.local.", 1003).await;
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert_eq!(received.load(Ordering::SeqCst), 3);
+
+        let response = post_query(state, "missing.local.", 1004).await;
+        assert_eq!(response.id(), 1004);
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert_eq!(received.load(Ordering::SeqCst), 3);
+
+        info!("Test completed: test_doh_query_forwarded_over_mdns_with_negative_cache");
+    }
+
+    #[test]
+    fn test_mdns_forwarder_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_mdns_forwarder_config_validation");
+
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "1.1.1.1:53"
+                protocol: udp
+          mdns_forwarder:
+            enabled: true
+        "#;
+        let mut config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(config.dns.mdns_forwarder.domains, vec![".local".to_string(), ".local.".to_string()]);
+        assert_eq!(config.dns.mdns_forwarder.multicast_addr, "224.0.0.251:5353".parse::<SocketAddr>().unwrap());
+        assert_eq!(config.dns.mdns_forwarder.mdns_response_timeout_ms, 1000);
+        assert_eq!(config.dns.mdns_forwarder.mdns_negative_ttl_secs, 30);
+        assert!(config.test().is_ok());
+
+        // 域名后缀必须以 "." 开头
+        config.dns.mdns_forwarder.domains = vec!["local".to_string()];
+        assert!(config.test().is_err());
+
+        // 至少需要一个域名后缀
+        config.dns.mdns_forwarder.domains.clear();
+        assert!(config.test().is_err());
+        config.dns.mdns_forwarder.domains = vec![".local".to_string()];
+
+        // 超时时间必须在有效范围内
+        config.dns.mdns_forwarder.mdns_response_timeout_ms = 0;
+        assert!(config.test().is_err());
+        config.dns.mdns_forwarder.mdns_response_timeout_ms = 60_000;
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_mdns_forwarder_config_validation");
+    }
+}
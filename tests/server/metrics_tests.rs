@@ -1 +1,141 @@
 // tests/server/metrics_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::{MetricsAuthConfig, MetricsAuthType, ServerConfig};
+    use oxide_wdns::server::metrics::metrics_routes;
+    use oxide_wdns::server::security::apply_metrics_auth;
+
+    // 发送指标请求并返回状态码和 WWW-Authenticate 头
+    async fn request_metrics(auth: &MetricsAuthConfig, authorization: Option<&str>) -> (StatusCode, Option<String>) {
+        let app = apply_metrics_auth(metrics_routes(), auth);
+
+        let mut builder = Request::builder().uri("/metrics");
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        let response = app.oneshot(builder.body(Body::empty()).unwrap()).await.unwrap();
+
+        let challenge = response.headers()
+            .get(header::WWW_AUTHENTICATE)
+            .map(|v| v.to_str().unwrap().to_string());
+        (response.status(), challenge)
+    }
+
+    #[tokio::test]
+    async fn test_metrics_basic_auth() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_metrics_basic_auth");
+
+        let auth = MetricsAuthConfig {
+            type_: MetricsAuthType::Basic,
+            username: Some("prometheus".to_string()),
+            password: Some("s3cret".to_string()),
+            token: None,
+        };
+
+        // 无凭据应返回 401 并带认证质询
+        let (status, challenge) = request_metrics(&auth, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(challenge.as_deref(), Some("Basic realm=\"metrics\""));
+
+        // 错误密码应返回 401
+        let wrong = format!("Basic {}", BASE64_STANDARD.encode("prometheus:wrong"));
+        let (status, _) = request_metrics(&auth, Some(&wrong)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // 正确凭据应返回 200，认证方案不区分大小写
+        let valid = format!("basic {}", BASE64_STANDARD.encode("prometheus:s3cret"));
+        let (status, _) = request_metrics(&auth, Some(&valid)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        info!("Test completed: test_metrics_basic_auth");
+    }
+
+    #[tokio::test]
+    async fn test_metrics_bearer_auth() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_metrics_bearer_auth");
+
+        let auth = MetricsAuthConfig {
+            type_: MetricsAuthType::Bearer,
+            username: None,
+            password: None,
+            token: Some("metrics-token".to_string()),
+        };
+
+        let (status, challenge) = request_metrics(&auth, Some("Bearer other-token")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(challenge.as_deref(), Some("Bearer realm=\"metrics\""));
+
+        // Basic 方案不应被 Bearer 认证接受
+        let (status, _) = request_metrics(&auth, Some("Basic metrics-token")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = request_metrics(&auth, Some("Bearer metrics-token")).await;
+        assert_eq!(status, StatusCode::OK);
+
+        info!("Test completed: test_metrics_bearer_auth");
+    }
+
+    #[test]
+    fn test_metrics_auth_config_validation() {
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          metrics_listen_addr: "127.0.0.1:9090"
+          metrics_auth:
+            type: basic
+            username: "prometheus"
+            password: "s3cret"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#;
+        let config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        let auth = config.http.metrics_auth.as_ref().unwrap();
+        assert_eq!(auth.type_, MetricsAuthType::Basic);
+        assert_eq!(config.http.metrics_listen_addr, Some("127.0.0.1:9090".parse().unwrap()));
+        assert!(config.test().is_ok());
+
+        // 默认不启用认证，也不使用独立监听地址
+        let default_config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+        assert!(default_config.http.metrics_auth.is_none());
+        assert!(default_config.http.metrics_listen_addr.is_none());
+
+        // Basic 认证缺少密码应被拒绝
+        let missing_password: ServerConfig = serde_yaml::from_str(
+            &config_str.replace("password: \"s3cret\"", "")
+        ).unwrap();
+        assert!(missing_password.test().is_err());
+
+        // Bearer 认证缺少令牌应被拒绝
+        let missing_token: ServerConfig = serde_yaml::from_str(
+            &config_str.replace("type: basic", "type: bearer")
+        ).unwrap();
+        assert!(missing_token.test().is_err());
+
+        // 指标监听地址与 DoH 监听地址相同应被拒绝
+        let same_addr: ServerConfig = serde_yaml::from_str(
+            &config_str.replace("127.0.0.1:9090", "127.0.0.1:8053")
+        ).unwrap();
+        assert!(same_addr.test().is_err());
+    }
+}
@@ -2,6 +2,7 @@
 
 // 公共测试模块，包含共享的测试函数和工具
 pub mod mock_http_server;
+pub mod test_helpers;
 
 // 声明测试模块
 mod args_tests;
@@ -15,6 +16,71 @@ mod server_integration_tests;
 // mod signal_tests;
 mod upstream_tests;
 mod ecs_tests;
+mod stats_tests;
+mod tls_tests;
+mod acme_tests;
+mod http3_tests;
+mod request_id_tests;
+mod odoh_tests;
+mod padding_tests;
+mod async_miss_tests;
+mod unix_tests;
+mod srv_discovery_tests;
+mod mdns_tests;
+mod cors_tests;
+mod body_limit_tests;
+mod connection_pool_tests;
+mod upstream_override_tests;
+mod dns_get_param_tests;
+mod auth_tests;
+mod failure_ratio_tests;
+mod websocket_tests;
+mod rate_limit_override_tests;
+mod rule_dir_tests;
+mod resolve_api_tests;
+mod mtls_tests;
+mod upstream_transport_tests;
+mod access_control_tests;
+mod drain_tests;
+mod redirect_tests;
+mod log_level_tests;
+mod group_load_balance_tests;
+mod compression_tests;
+mod upstream_http2_tests;
+mod doh_methods_tests;
+mod test_helpers_tests;
+mod upstream_bind_addr_tests;
+mod response_validation_tests;
+mod cname_flatten_tests;
+mod http_server_timeouts_tests;
+mod request_dedup_tests;
+mod dns_server_tests;
+mod version_tests;
+mod record_passthrough_tests;
+mod dot_server_tests;
+mod content_negotiation_tests;
+mod upstream_mtls_tests;
+mod svcb_hint_tests;
+mod logging_tests;
+mod h2c_tests;
+mod failover_chain_tests;
+mod cache_key_scope_tests;
+mod upstream_parse_error_tests;
+mod record_dedup_tests;
+mod query_log_tests;
+mod tcp_keepalive_tests;
+mod load_shed_tests;
+mod recursive_tests;
+mod rate_limit_cache_tests;
+mod root_info_tests;
+mod consul_discovery_tests;
+mod error_mode_tests;
+mod route_test_tests;
+mod debug_capture_tests;
+mod idn_tests;
+mod refusal_tests;
+mod routing_group_validation_tests;
+mod proxy_protocol_tests;
 
 // 注意：在Rust测试中，不需要使用pub use语句导出测试模块
 // 可以通过 cargo test -p oxide-wdns server::server_integration_tests 等方式直接运行指定测试
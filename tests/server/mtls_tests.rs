@@ -0,0 +1,223 @@
+// tests/server/mtls_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Arc;
+    use axum::{routing::get, Extension, Router};
+    use rcgen::{BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+    use reqwest::{Client, Identity, StatusCode};
+    use tempfile::TempDir;
+    use tokio::net::TcpListener;
+    use tracing::info;
+    use oxide_wdns::server::config::{ClientAuthConfig, ClientAuthMode, RateLimitConfig, ServerConfig, TlsConfig};
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::security::apply_rate_limiting;
+    use oxide_wdns::server::http_conn::ConnectionLimits;
+    use oxide_wdns::server::tls::{serve_tls, ClientCertificate, TlsContext};
+
+    // 测试 CA：签发客户端证书
+    struct TestCa {
+        cert: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    impl TestCa {
+        fn new(name: &str) -> Self {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(Vec::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params.distinguished_name.push(DnType::CommonName, name);
+            let cert = params.self_signed(&key).unwrap();
+            Self { cert, key }
+        }
+
+        // 签发客户端证书，返回 reqwest 使用的身份
+        fn issue_client(&self, common_name: &str) -> Identity {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec![format!("{}.example.com", common_name)]).unwrap();
+            params.distinguished_name.push(DnType::CommonName, common_name);
+            params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+            let cert = params.signed_by(&key, &self.cert, &self.key).unwrap();
+            Identity::from_pkcs8_pem(cert.pem().as_bytes(), key.serialize_pem().as_bytes()).unwrap()
+        }
+    }
+
+    // 写入服务端证书与客户端 CA，返回启用 client_auth 的 TLS 配置
+    fn mtls_config(dir: &Path, ca: &TestCa, mode: ClientAuthMode) -> TlsConfig {
+        let server = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        let ca_cert = dir.join("client-ca.pem");
+        fs::write(&cert, server.cert.pem()).unwrap();
+        fs::write(&key, server.key_pair.serialize_pem()).unwrap();
+        fs::write(&ca_cert, ca.cert.pem()).unwrap();
+        TlsConfig {
+            cert: Some(cert),
+            key: Some(key),
+            acme: Default::default(),
+            client_auth: Some(ClientAuthConfig { ca_cert, mode }),
+            min_version: Default::default(),
+            cipher_suites: None,
+            alpn: None,
+        }
+    }
+
+    // 返回客户端证书主题与 SAN 的应用
+    fn whoami_app() -> Router {
+        Router::new().route("/whoami", get(|cert: Option<Extension<ClientCertificate>>| async move {
+            match cert {
+                Some(Extension(cert)) => format!("{}|{}", cert.subject, cert.subject_alt_names.join(",")),
+                None => "anonymous".to_string(),
+            }
+        }))
+    }
+
+    async fn start_server(app: Router, tls_config: &TlsConfig) -> u16 {
+        let tls = Arc::new(TlsContext::new(tls_config).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(serve_tls(listener, app, tls, ConnectionLimits::default(), None));
+        port
+    }
+
+    fn client(identity: Option<Identity>) -> Client {
+        let mut builder = Client::builder().danger_accept_invalid_certs(true);
+        if let Some(identity) = identity {
+            builder = builder.identity(identity);
+        }
+        builder.build().unwrap()
+    }
+
+    async fn whoami(client: &Client, port: u16) -> reqwest::Result<(StatusCode, String)> {
+        let response = client.get(format!("https://localhost:{}/whoami", port)).send().await?;
+        let status = response.status();
+        Ok((status, response.text().await?))
+    }
+
+    #[tokio::test]
+    async fn test_mtls_required_verifies_client_certificates() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_mtls_required_verifies_client_certificates");
+
+        let dir = TempDir::new().unwrap();
+        let ca = TestCa::new("Test Client CA");
+        let port = start_server(whoami_app(), &mtls_config(dir.path(), &ca, ClientAuthMode::Required)).await;
+
+        // 持有 CA 签发证书的客户端可以连接，处理器可读取证书主题与 SAN
+        let (status, body) = whoami(&client(Some(ca.issue_client("device1"))), port).await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "CN=device1|DNS:device1.example.com");
+
+        // 未提供证书或证书由其他 CA 签发时，连接在 TLS 层被关闭
+        let failures = METRICS.tls_client_auth_failures_total().get();
+        assert!(whoami(&client(None), port).await.is_err());
+        let other_ca = TestCa::new("Other CA");
+        assert!(whoami(&client(Some(other_ca.issue_client("intruder"))), port).await.is_err());
+        assert!(METRICS.tls_client_auth_failures_total().get() >= failures + 2);
+
+        info!("Test completed: test_mtls_required_verifies_client_certificates");
+    }
+
+    #[tokio::test]
+    async fn test_mtls_optional_allows_anonymous_clients() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_mtls_optional_allows_anonymous_clients");
+
+        let dir = TempDir::new().unwrap();
+        let ca = TestCa::new("Test Client CA");
+        let port = start_server(whoami_app(), &mtls_config(dir.path(), &ca, ClientAuthMode::Optional)).await;
+
+        let (status, body) = whoami(&client(None), port).await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "anonymous");
+
+        let (_, body) = whoami(&client(Some(ca.issue_client("device2"))), port).await.unwrap();
+        assert!(body.starts_with("CN=device2|"), "unexpected body: {}", body);
+
+        // 提供的证书无效时仍然拒绝
+        let other_ca = TestCa::new("Other CA");
+        assert!(whoami(&client(Some(other_ca.issue_client("intruder"))), port).await.is_err());
+
+        info!("Test completed: test_mtls_optional_allows_anonymous_clients");
+    }
+
+    #[tokio::test]
+    async fn test_mtls_rate_limit_keyed_by_certificate() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_mtls_rate_limit_keyed_by_certificate");
+
+        let dir = TempDir::new().unwrap();
+        let ca = TestCa::new("Test Client CA");
+        let rate_limit = RateLimitConfig {
+            enabled: true,
+            per_ip_rate: 1,
+            per_ip_concurrent: 1,
+            ..Default::default()
+        };
+        let app = apply_rate_limiting(whoami_app(), &rate_limit, &[], &[]);
+        let port = start_server(app, &mtls_config(dir.path(), &ca, ClientAuthMode::Required)).await;
+
+        // 同一 IP 上的不同证书分别计数
+        let alice = client(Some(ca.issue_client("alice")));
+        let bob = client(Some(ca.issue_client("bob")));
+        assert_eq!(whoami(&alice, port).await.unwrap().0, StatusCode::OK);
+        assert_eq!(whoami(&bob, port).await.unwrap().0, StatusCode::OK);
+        assert_eq!(whoami(&alice, port).await.unwrap().0, StatusCode::TOO_MANY_REQUESTS);
+
+        info!("Test completed: test_mtls_rate_limit_keyed_by_certificate");
+    }
+
+    #[test]
+    fn test_mtls_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_mtls_config_validation");
+
+        let dir = TempDir::new().unwrap();
+        let ca = TestCa::new("Test Client CA");
+        let tls_config = mtls_config(dir.path(), &ca, ClientAuthMode::Required);
+        let config_for = |mode: &str, ca_cert: &Path, acme: &str| -> ServerConfig {
+            let config_str = format!(r#"
+            http_server:
+              listen_addr: "127.0.0.1:8053"
+              tls:
+                cert: "{}"
+                key: "{}"
+                client_auth:
+                  ca_cert: "{}"
+                  mode: {}
+                {}
+            dns_resolver:
+              upstream:
+                resolvers:
+                  - address: "8.8.8.8:53"
+                    protocol: udp
+            "#, tls_config.cert_path().display(), tls_config.key_path().display(), ca_cert.display(), mode, acme);
+            serde_yaml::from_str(&config_str).unwrap()
+        };
+
+        let ca_cert = dir.path().join("client-ca.pem");
+        assert!(config_for("required", &ca_cert, "").test().is_ok());
+        assert!(config_for("optional", &ca_cert, "").test().is_ok());
+
+        // CA 文件不存在或不是证书
+        assert!(config_for("required", &dir.path().join("missing.pem"), "").test().is_err());
+        assert!(config_for("required", &dir.path().join("key.pem"), "").test().is_err());
+
+        // TLS-ALPN-01 验证方不会提供客户端证书，不能与 required 模式同时使用
+        let acme = format!(r#"acme:
+                  enabled: true
+                  domains: ["dns.example.com"]
+                  cache_dir: "{}"
+                  challenge: tls-alpn-01"#, dir.path().join("acme").display());
+        assert!(config_for("required", &ca_cert, &acme).test().is_err());
+        assert!(config_for("optional", &ca_cert, &acme).test().is_ok());
+
+        info!("Test completed: test_mtls_config_validation");
+    }
+}
@@ -0,0 +1,219 @@
+// tests/server/odoh_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::path::Path;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::{RData, RecordType};
+    use tempfile::TempDir;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_MESSAGE, CONTENT_TYPE_ODOH_MESSAGE};
+    use oxide_wdns::server::config::{OdohConfig, ServerConfig};
+    use oxide_wdns::server::error::ServerError;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::odoh::{encrypt_query, OdohTarget};
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 创建启用 ODoH 并使用模拟 DoH 上游的服务器配置
+    fn odoh_config(upstream_uri: &str, key_dir: &Path) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+          odoh:
+            enabled: true
+            path: "/odoh"
+            key_dir: "{}"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: false
+        "#, key_dir.display(), upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 获取目标公钥配置
+    async fn fetch_configs(app: &Router) -> Vec<u8> {
+        let response = app.clone()
+            .oneshot(Request::get("/.well-known/odohconfigs").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()
+    }
+
+    // 发送 ODoH 查询请求
+    async fn post_odoh(app: &Router, content_type: &str, body: Vec<u8>) -> (StatusCode, Option<String>, Vec<u8>) {
+        let response = app.clone()
+            .oneshot(
+                Request::post("/odoh")
+                    .header(header::CONTENT_TYPE, content_type)
+                    .body(Body::from(body))
+                    .unwrap()
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let content_type = response.headers().get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec();
+        (status, content_type, body)
+    }
+
+    #[tokio::test]
+    async fn test_odoh_query_round_trip() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_odoh_query_round_trip");
+
+        let key_dir = TempDir::new().unwrap();
+        let (mock_server, counter) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 53)).await;
+        let app = DoHServer::new(odoh_config(&mock_server.uri(), key_dir.path()), false)
+            .build_application_components().await.unwrap().app;
+
+        let odoh_requests = METRICS.http_requests_total()
+            .with_label_values(&["POST", "/odoh", "200", "odoh", "HTTP/1.1"]);
+        let odoh_before = odoh_requests.get();
+
+        // 使用公布的公钥配置加密查询
+        let configs = fetch_configs(&app).await;
+        let query = create_test_query("example.com", RecordType::A);
+        let (encrypted, client_query) = encrypt_query(&configs, &query.to_vec().unwrap()).unwrap();
+
+        info!("Sending encrypted ODoH query ({} bytes)", encrypted.len());
+        let (status, content_type, body) = post_odoh(&app, CONTENT_TYPE_ODOH_MESSAGE, encrypted).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.as_deref(), Some(CONTENT_TYPE_ODOH_MESSAGE));
+
+        // 解密响应，内部查询经由普通上游流程解析
+        let response = Message::from_vec(&client_query.decrypt_response(&body).unwrap()).unwrap();
+        assert_eq!(response.id(), query.id());
+        assert_eq!(response.answers().len(), 1);
+        match response.answers()[0].data() {
+            Some(RData::A(a)) => assert_eq!(a.0, Ipv4Addr::new(192, 0, 2, 53)),
+            other => panic!("Unexpected answer: {:?}", other),
+        }
+        assert_eq!(*counter.lock().unwrap(), 1);
+
+        // ODoH 流量按 odoh 格式单独计数
+        assert_eq!(odoh_requests.get(), odoh_before + 1);
+
+        info!("Test completed: test_odoh_query_round_trip");
+    }
+
+    #[tokio::test]
+    async fn test_odoh_rejects_invalid_queries() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_odoh_rejects_invalid_queries");
+
+        let key_dir = TempDir::new().unwrap();
+        let (mock_server, counter) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 53)).await;
+        let app = DoHServer::new(odoh_config(&mock_server.uri(), key_dir.path()), false)
+            .build_application_components().await.unwrap().app;
+
+        let configs = fetch_configs(&app).await;
+        let query = create_test_query("example.com", RecordType::A).to_vec().unwrap();
+
+        // 非 ODoH 内容类型
+        let (encrypted, _) = encrypt_query(&configs, &query).unwrap();
+        let (status, _, _) = post_odoh(&app, CONTENT_TYPE_DNS_MESSAGE, encrypted).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        // 格式错误的消息
+        let (status, _, _) = post_odoh(&app, CONTENT_TYPE_ODOH_MESSAGE, vec![0x01, 0x00]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // 密文被篡改
+        let (mut tampered, _) = encrypt_query(&configs, &query).unwrap();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0xff;
+        let (status, _, _) = post_odoh(&app, CONTENT_TYPE_ODOH_MESSAGE, tampered).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // 使用其他目标的公钥加密，密钥 ID 未知
+        let other_dir = TempDir::new().unwrap();
+        let other = OdohTarget::new(&OdohConfig { key_dir: other_dir.path().to_path_buf(), ..Default::default() }).unwrap();
+        let (foreign, _) = encrypt_query(&other.configs(), &query).unwrap();
+        let (status, _, _) = post_odoh(&app, CONTENT_TYPE_ODOH_MESSAGE, foreign).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // 无效请求不会发送到上游
+        assert_eq!(*counter.lock().unwrap(), 0);
+
+        info!("Test completed: test_odoh_rejects_invalid_queries");
+    }
+
+    #[test]
+    fn test_odoh_key_persistence_and_rotation() {
+        let key_dir = TempDir::new().unwrap();
+        let config = OdohConfig { key_dir: key_dir.path().to_path_buf(), ..Default::default() };
+        let query = create_test_query("example.com", RecordType::A).to_vec().unwrap();
+
+        // 密钥持久化，重新加载后公钥配置不变
+        let target = OdohTarget::new(&config).unwrap();
+        let initial_configs = target.configs();
+        assert_eq!(OdohTarget::new(&config).unwrap().configs(), initial_configs);
+
+        // 轮换后公布新公钥，旧公钥加密的查询仍可解密
+        target.rotate().unwrap();
+        assert_ne!(target.configs(), initial_configs);
+        let (old_query, _) = encrypt_query(&initial_configs, &query).unwrap();
+        assert_eq!(target.decrypt_query(&old_query).unwrap().0, query);
+
+        // 重启后保留上一个密钥
+        let reloaded = OdohTarget::new(&config).unwrap();
+        assert_eq!(reloaded.configs(), target.configs());
+        assert!(reloaded.decrypt_query(&old_query).is_ok());
+
+        // 再次轮换后最早的密钥失效
+        target.rotate().unwrap();
+        assert!(matches!(target.decrypt_query(&old_query), Err(ServerError::OdohUnknownKey)));
+    }
+
+    #[test]
+    fn test_odoh_config_validation() {
+        let key_dir = TempDir::new().unwrap();
+        let mut config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+
+        // 默认关闭
+        assert!(!config.http.odoh.enabled);
+        assert_eq!(config.http.odoh.path, "/odoh-query");
+
+        config.http.odoh.enabled = true;
+        config.http.odoh.key_dir = key_dir.path().to_path_buf();
+        assert!(config.test().is_ok());
+
+        // 路径必须以 '/' 开头且不能与内置路由冲突
+        config.http.odoh.path = "odoh".to_string();
+        assert!(config.test().is_err());
+        config.http.odoh.path = "/dns-query".to_string();
+        assert!(config.test().is_err());
+        config.http.odoh.path = "/odoh".to_string();
+
+        // 轮换间隔为 0（不轮换）或不小于最小值
+        config.http.odoh.key_rotation_secs = 0;
+        assert!(config.test().is_ok());
+        config.http.odoh.key_rotation_secs = 60;
+        assert!(config.test().is_err());
+    }
+}
@@ -0,0 +1,243 @@
+// tests/server/padding_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use hickory_proto::rr::{RData, Record};
+    use hickory_proto::rr::rdata::A;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use hickory_proto::op::{Edns, Message};
+    use hickory_proto::rr::RecordType;
+    use hickory_proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::{AcmeConfig, ServerConfig, TlsConfig};
+    use oxide_wdns::server::doh_handler::{doh_routes, ServerState};
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::padding::pad_message;
+    use crate::server::mock_http_server::{create_test_query, create_test_response, setup_mock_doh_server};
+    use crate::server::test_helpers::server_state;
+
+    // 创建使用模拟 DoH 上游的配置
+    fn padding_config(upstream_uri: &str, edns_padding: bool, tls: bool) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+            edns_padding: {}
+          cache:
+            enabled: false
+        "#, upstream_uri, edns_padding);
+        let mut config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        if tls {
+            // 处理器只根据配置判断是否处于 TLS 模式，无需真实证书
+            config.http.tls = Some(TlsConfig { cert: None, key: None, acme: AcmeConfig::default(), client_auth: None, min_version: Default::default(), cipher_suites: None, alpn: None });
+        }
+        config
+    }
+
+    // 创建带 OPT 记录的查询
+    fn edns_query(domain: &str) -> Message {
+        let mut query = create_test_query(domain, RecordType::A);
+        query.set_edns(Edns::new());
+        query
+    }
+
+    // 发送 wireformat 查询并返回响应体
+    async fn post_query(state: ServerState, query: &Message) -> Vec<u8> {
+        let response = doh_routes(state)
+            .oneshot(
+                Request::post("/dns-query")
+                    .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+                    .body(Body::from(query.to_vec().unwrap()))
+                    .unwrap()
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()
+    }
+
+    // 获取消息中的填充选项长度
+    fn padding_option_len(message: &Message) -> Option<usize> {
+        match message.extensions().as_ref()?.option(EdnsCode::Padding)? {
+            EdnsOption::Unknown(_, data) => Some(data.len()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_pad_message_block_boundaries() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_pad_message_block_boundaries");
+
+        // 覆盖从短域名到接近多个块长度的查询
+        for labels in [0, 1, 5, 12, 20, 28] {
+            let domain = (0..labels)
+                .map(|i| format!("label{}.", i))
+                .collect::<String>() + "example.com";
+
+            for block_size in [128, 468] {
+                let mut message = create_test_query(&domain, RecordType::A);
+                let padding_len = pad_message(&mut message, block_size).unwrap();
+                let wire = message.to_vec().unwrap();
+                assert_eq!(wire.len() % block_size, 0, "domain {} block {}", domain, block_size);
+
+                // 填充选项可被解析，且再次填充会替换原有选项而不是叠加
+                let parsed = Message::from_vec(&wire).unwrap();
+                assert_eq!(padding_option_len(&parsed), Some(padding_len));
+                let mut repadded = parsed.clone();
+                assert_eq!(pad_message(&mut repadded, block_size).unwrap(), padding_len);
+                assert_eq!(repadded.to_vec().unwrap().len(), wire.len());
+            }
+        }
+
+        info!("Test completed: test_pad_message_block_boundaries");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_query_padding() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_query_padding");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 40)).await;
+        let padding_before = METRICS.upstream_edns_padding_bytes_total().get();
+
+        // 未启用时上游查询保持原样
+        let state = server_state(padding_config(&mock_server.uri(), false, false)).await;
+        post_query(state, &create_test_query("plain.example.com", RecordType::A)).await;
+
+        // 启用后上游查询长度为 128 的整数倍
+        let state = server_state(padding_config(&mock_server.uri(), true, false)).await;
+        post_query(state, &create_test_query("padded.example.com", RecordType::A)).await;
+
+        let requests = mock_server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let plain = Message::from_vec(&requests[0].body).unwrap();
+        assert_eq!(padding_option_len(&plain), None);
+
+        let padded = Message::from_vec(&requests[1].body).unwrap();
+        info!(len = requests[1].body.len(), "Upstream received padded query");
+        assert_eq!(requests[1].body.len() % 128, 0);
+        let padding_len = padding_option_len(&padded).unwrap();
+        assert!(METRICS.upstream_edns_padding_bytes_total().get() >= padding_before + padding_len as u64);
+
+        info!("Test completed: test_upstream_query_padding");
+    }
+
+    #[tokio::test]
+    async fn test_response_padding_requires_tls_and_edns() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_response_padding_requires_tls_and_edns");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 41)).await;
+        let padding_before = METRICS.server_response_padding_bytes_total().get();
+
+        // TLS 模式下，包含 OPT 的查询的响应被填充到 468 的整数倍
+        let state = server_state(padding_config(&mock_server.uri(), true, true)).await;
+        let body = post_query(state, &edns_query("tls.example.com")).await;
+        assert_eq!(body.len() % 468, 0);
+        let response = Message::from_vec(&body).unwrap();
+        assert_eq!(response.answers().len(), 1);
+        let padding_len = padding_option_len(&response).unwrap();
+        assert!(METRICS.server_response_padding_bytes_total().get() >= padding_before + padding_len as u64);
+
+        // 查询未包含 OPT 记录时不填充
+        let state = server_state(padding_config(&mock_server.uri(), true, true)).await;
+        let body = post_query(state, &create_test_query("tls.example.com", RecordType::A)).await;
+        assert_eq!(padding_option_len(&Message::from_vec(&body).unwrap()), None);
+
+        // 未启用 TLS 时不填充响应
+        let state = server_state(padding_config(&mock_server.uri(), true, false)).await;
+        let body = post_query(state, &edns_query("plain.example.com")).await;
+        assert_eq!(padding_option_len(&Message::from_vec(&body).unwrap()), None);
+
+        info!("Test completed: test_response_padding_requires_tls_and_edns");
+    }
+
+    #[test]
+    fn test_pad_response_block_boundaries() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_pad_response_block_boundaries");
+
+        // 覆盖从空应答到超过多个块长度的响应
+        for answers in [0, 1, 3, 10, 25, 40, 80] {
+            let query = edns_query("answers.example.com");
+            let mut response = create_test_response(&query, Ipv4Addr::new(192, 0, 2, 1));
+            response.take_answers();
+            for i in 0..answers {
+                let name = query.queries()[0].name().clone();
+                response.add_answer(Record::from_rdata(name, 300, RData::A(A::new(192, 0, 2, i as u8))));
+            }
+
+            for block_size in [468, 256, 1] {
+                let mut padded = response.clone();
+                pad_message(&mut padded, block_size).unwrap();
+                let wire = padded.to_vec().unwrap();
+                assert_eq!(wire.len() % block_size, 0, "answers {} block {}", answers, block_size);
+                assert_eq!(Message::from_vec(&wire).unwrap().answers().len(), answers);
+            }
+        }
+
+        info!("Test completed: test_pad_response_block_boundaries");
+    }
+
+    #[tokio::test]
+    async fn test_security_padding_without_tls() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_security_padding_without_tls");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 42)).await;
+
+        // security.padding 不依赖 TLS，按配置的块大小填充
+        let mut state = server_state(padding_config(&mock_server.uri(), false, false)).await;
+        state.config.security.padding.enabled = true;
+        state.config.security.padding.block_size = 256;
+        let body = post_query(state.clone(), &edns_query("secure.example.com")).await;
+        assert_eq!(body.len() % 256, 0);
+        assert!(padding_option_len(&Message::from_vec(&body).unwrap()).is_some());
+
+        // 查询未包含 OPT 记录时仍不填充
+        let body = post_query(state, &create_test_query("secure.example.com", RecordType::A)).await;
+        assert_eq!(padding_option_len(&Message::from_vec(&body).unwrap()), None);
+
+        info!("Test completed: test_security_padding_without_tls");
+    }
+
+    #[test]
+    fn test_security_padding_config() {
+        let mut config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+        security:
+          padding:
+            enabled: true
+        "#).unwrap();
+        assert!(config.security.padding.enabled);
+        assert_eq!(config.security.padding.block_size, 468);
+        assert!(config.test().is_ok());
+
+        config.security.padding.block_size = 0;
+        assert!(config.test().is_err());
+        config.security.padding.block_size = 8192;
+        assert!(config.test().is_err());
+    }
+}
@@ -0,0 +1,228 @@
+// tests/server/proxy_protocol_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use axum::extract::ConnectInfo;
+    use axum::routing::get;
+    use axum::Router;
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::common::consts::PROXY_PROTOCOL_LISTENER_DOH;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::http_conn::{serve_plain, ConnectionLimits};
+    use oxide_wdns::server::ip_set::{parse_network, IpRangeSet};
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::proxy_protocol::{read_header, ProxyProtocol};
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{extract_ip_addresses, mock_upstream_config, mount_doh_answer};
+
+    const REQUEST: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+    const V1_HEADER: &[u8] = b"PROXY TCP4 203.0.113.7 192.0.2.10 51000 443\r\n";
+    const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+    // 构建 v2 PROXY 头：IPv4 地址块之后附带一个 TLV 扩展
+    fn v2_header(command: u8) -> Vec<u8> {
+        let mut header = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+        header.push(0x20 | command);
+        header.push(0x11);
+        let tlv = [0x04, 0x00, 0x02, 0xab, 0xcd];
+        header.extend_from_slice(&((12 + tlv.len()) as u16).to_be_bytes());
+        header.extend_from_slice(&[198, 51, 100, 20, 192, 0, 2, 10]);
+        header.extend_from_slice(&40000u16.to_be_bytes());
+        header.extend_from_slice(&443u16.to_be_bytes());
+        header.extend_from_slice(&tlv);
+        header
+    }
+
+    fn proxy_protocol(trusted: &str) -> Arc<ProxyProtocol> {
+        let trusted = IpRangeSet::from_networks([parse_network(trusted).unwrap()]);
+        Arc::new(ProxyProtocol::new(trusted, PROXY_PROTOCOL_LISTENER_DOH))
+    }
+
+    // 启动返回客户端地址的明文 HTTP 服务器
+    async fn start_echo_server(proxy_protocol: Arc<ProxyProtocol>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", get(|ConnectInfo(client): ConnectInfo<SocketAddr>| async move {
+            client.to_string()
+        }));
+        tokio::spawn(serve_plain(listener, app, ConnectionLimits::default(), false, Some(proxy_protocol)));
+        addr
+    }
+
+    // 发送可选的 PROXY 头与一个请求，读取服务器关闭连接前的全部响应
+    async fn exchange(addr: SocketAddr, header: &[u8]) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(header).await.unwrap();
+        stream.write_all(REQUEST).await.unwrap();
+        let mut response = Vec::new();
+        let _ = tokio::time::timeout(READ_TIMEOUT, stream.read_to_end(&mut response)).await
+            .expect("server should close the connection");
+        String::from_utf8_lossy(&response).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_proxy_protocol_header_parsing() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_proxy_protocol_header_parsing");
+
+        // v1 头只消费到 CRLF 为止，之后的应用数据保留
+        let mut input = [V1_HEADER, b"payload"].concat();
+        let mut reader = input.as_slice();
+        assert_eq!(read_header(&mut reader).await.unwrap(), Some("203.0.113.7:51000".parse().unwrap()));
+        assert_eq!(reader, b"payload");
+
+        let mut reader: &[u8] = b"PROXY TCP6 2001:db8::1 2001:db8::2 51000 443\r\n";
+        assert_eq!(read_header(&mut reader).await.unwrap(), Some("[2001:db8::1]:51000".parse().unwrap()));
+        let mut reader: &[u8] = b"PROXY UNKNOWN\r\n";
+        assert_eq!(read_header(&mut reader).await.unwrap(), None);
+
+        // v2 头跳过 TLV 扩展，LOCAL 命令没有客户端地址
+        input = [v2_header(0x1), b"payload".to_vec()].concat();
+        let mut reader = input.as_slice();
+        assert_eq!(read_header(&mut reader).await.unwrap(), Some("198.51.100.20:40000".parse().unwrap()));
+        assert_eq!(reader, b"payload");
+        let local = v2_header(0x0);
+        assert_eq!(read_header(&mut local.as_slice()).await.unwrap(), None);
+
+        // 无效的头
+        for invalid in [
+            &b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"[..],
+            b"PROXY TCP4 203.0.113.7 192.0.2.10 51000\r\n",
+            b"PROXY TCP4 2001:db8::1 192.0.2.10 51000 443\r\n",
+            b"PROXY TCP4 203.0.113.7 192.0.2.10 70000 443\r\n",
+        ] {
+            let mut reader = invalid;
+            assert!(read_header(&mut reader).await.is_err(), "{}", String::from_utf8_lossy(invalid));
+        }
+        let mut too_long = b"PROXY TCP4 ".to_vec();
+        too_long.extend([b'1'; 200]);
+        assert!(read_header(&mut too_long.as_slice()).await.is_err());
+
+        info!("Test completed: test_proxy_protocol_header_parsing");
+    }
+
+    #[tokio::test]
+    async fn test_proxy_protocol_sets_client_address_for_trusted_proxies() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_proxy_protocol_sets_client_address_for_trusted_proxies");
+
+        // 可信代理的连接以 PROXY 头中的地址作为客户端地址
+        let addr = start_echo_server(proxy_protocol("127.0.0.0/8")).await;
+        assert!(exchange(addr, V1_HEADER).await.ends_with("203.0.113.7:51000"));
+        assert!(exchange(addr, &v2_header(0x1)).await.ends_with("198.51.100.20:40000"));
+        // LOCAL 命令（如健康检查）使用连接源地址
+        assert!(exchange(addr, &v2_header(0x0)).await.contains("127.0.0.1:"));
+
+        // 可信代理缺少 PROXY 头时关闭连接
+        let rejected_before = METRICS.proxy_protocol_rejected_total().with_label_values(&[PROXY_PROTOCOL_LISTENER_DOH]).get();
+        assert_eq!(exchange(addr, b"").await, "");
+        assert!(METRICS.proxy_protocol_rejected_total().with_label_values(&[PROXY_PROTOCOL_LISTENER_DOH]).get() > rejected_before);
+
+        // 非可信地址发送的 PROXY 头不被解析，无法伪造客户端地址
+        let addr = start_echo_server(proxy_protocol("192.0.2.0/24")).await;
+        let response = exchange(addr, V1_HEADER).await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        assert!(!response.contains("203.0.113.7"));
+        assert!(exchange(addr, b"").await.contains("127.0.0.1:"));
+
+        info!("Test completed: test_proxy_protocol_sets_client_address_for_trusted_proxies");
+    }
+
+    #[tokio::test]
+    async fn test_proxy_protocol_on_dns_tcp_listener() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_proxy_protocol_on_dns_tcp_listener");
+
+        let mock = MockServer::start().await;
+        mount_doh_answer(&mock, Ipv4Addr::new(192, 0, 2, 1)).await;
+        let mut config = mock_upstream_config(8053, &mock.uri());
+        config.http.trusted_proxies = vec!["127.0.0.1".to_string()];
+        config.dns_server.listen_tcp = Some("127.0.0.1:0".parse().unwrap());
+        config.dns_server.proxy_protocol = true;
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(components.dns_server.unwrap().serve_tcp(listener));
+
+        let wire = create_test_query("example.com", RecordType::A).to_vec().unwrap();
+        let mut framed = (wire.len() as u16).to_be_bytes().to_vec();
+        framed.extend_from_slice(&wire);
+
+        // PROXY 头之后的查询正常应答
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(&[V1_HEADER, &framed].concat()).await.unwrap();
+        let len = tokio::time::timeout(READ_TIMEOUT, stream.read_u16()).await.unwrap().unwrap();
+        let mut response = vec![0u8; len as usize];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(extract_ip_addresses(&Message::from_vec(&response).unwrap()), vec!["192.0.2.1"]);
+
+        // 缺少 PROXY 头的连接被关闭
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(&framed).await.unwrap();
+        let mut buf = Vec::new();
+        let read = tokio::time::timeout(READ_TIMEOUT, stream.read_to_end(&mut buf)).await.unwrap();
+        assert!(read.is_err() || buf.is_empty());
+
+        info!("Test completed: test_proxy_protocol_on_dns_tcp_listener");
+    }
+
+    #[test]
+    fn test_proxy_protocol_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_proxy_protocol_config_validation");
+
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          proxy_protocol: true
+          trusted_proxies: ["10.0.0.0/8", "192.0.2.1"]
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "https://1.1.1.1/dns-query"
+                protocol: doh
+        "#;
+        let mut config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        assert!(config.http.proxy_protocol);
+        assert!(config.test().is_ok());
+
+        // 默认关闭
+        let defaults = ServerConfig::default();
+        assert!(!defaults.http.proxy_protocol && !defaults.dns_server.proxy_protocol && !defaults.dot_server.proxy_protocol);
+        assert!(defaults.http.trusted_proxies.is_empty());
+
+        // 无效的可信代理条目
+        config.http.trusted_proxies.push("not-an-ip".to_string());
+        assert!(config.test().is_err());
+
+        // 启用 PROXY 协议时必须指定可信代理
+        config.http.trusted_proxies.clear();
+        assert!(config.test().is_err());
+        config.http.proxy_protocol = false;
+        config.dns_server.proxy_protocol = true;
+        config.dns_server.listen_tcp = Some("127.0.0.1:5353".parse().unwrap());
+        assert!(config.test().is_err());
+
+        // 经典 DNS 只支持在 TCP 监听上使用 PROXY 协议
+        config.http.trusted_proxies = vec!["10.0.0.0/8".to_string()];
+        assert!(config.test().is_ok());
+        config.dns_server.listen_tcp = None;
+        config.dns_server.listen_udp = Some("127.0.0.1:5353".parse().unwrap());
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_proxy_protocol_config_validation");
+    }
+}
@@ -0,0 +1,312 @@
+// tests/server/query_log_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::path::Path;
+    use std::time::Duration;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::rr::RecordType;
+    use ring::hmac;
+    use serde_json::{Map, Value};
+    use tempfile::TempDir;
+    use tracing::info;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::{QueryLogConfig, QueryLogFilterAction, QueryLogTarget, ServerConfig};
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{mount_doh_answer, test_with_server_config};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 88);
+
+    fn file_query_log(path: &Path) -> QueryLogConfig {
+        QueryLogConfig {
+            enabled: true,
+            target: QueryLogTarget::File,
+            path: Some(path.to_string_lossy().into_owned()),
+            ..Default::default()
+        }
+    }
+
+    // 等待写入线程写出指定行数的查询日志
+    async fn read_log_lines(path: &Path, count: usize) -> Vec<Map<String, Value>> {
+        for _ in 0..50 {
+            let contents = std::fs::read_to_string(path).unwrap_or_default();
+            if contents.lines().count() >= count {
+                return contents.lines()
+                    .map(|line| match serde_json::from_str(line) {
+                        Ok(Value::Object(entry)) => entry,
+                        other => panic!("Query log line is not a JSON object: {} ({:?})", line, other),
+                    })
+                    .collect();
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Timed out waiting for {} query log lines in {}", count, path.display());
+    }
+
+    #[tokio::test]
+    async fn test_query_log_writes_one_line_per_query() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_query_log_writes_one_line_per_query");
+
+        let dir = TempDir::new().unwrap();
+        let log_path = dir.path().join("query.log");
+        let query_log = file_query_log(&log_path);
+        let customize = |config: &mut ServerConfig| {
+            // logged.example.com 由规则路由到 logged_group，其余查询使用全局上游
+            let upstream = config.dns.upstream.resolvers[0].address.clone();
+            config.dns.routing = serde_yaml::from_str(&format!(r#"
+            enabled: true
+            upstream_groups:
+              - name: "logged_group"
+                resolvers:
+                  - address: "{}"
+                    protocol: doh
+            rules:
+              - match:
+                  type: exact
+                  values: ["logged.example.com"]
+                upstream_group: "logged_group"
+            "#, upstream)).unwrap();
+            config.dns.cache.enabled = true;
+            config.logging.query_log = query_log;
+            config.http.trusted_proxies = vec!["127.0.0.1".to_string()];
+        };
+        test_with_server_config(customize, |server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+            let client = reqwest::Client::new();
+            let query = create_test_query("logged.example.com", RecordType::A);
+            let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+
+            // 首次查询未命中缓存，第二次命中
+            for _ in 0..2 {
+                let response = client.get(&url)
+                    .header("X-Forwarded-For", "198.51.100.7")
+                    .header("X-Request-ID", "query-log-test")
+                    .send().await.unwrap();
+                assert!(response.status().is_success());
+            }
+            let query = create_test_query("other.example.com", RecordType::AAAA);
+            let response = client.post(format!("{}/dns-query", server_addr))
+                .header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                .body(query.to_vec().unwrap())
+                .send().await.unwrap();
+            assert!(response.status().is_success());
+
+            let lines = read_log_lines(&log_path, 3).await;
+            assert_eq!(lines.len(), 3);
+            let miss = &lines[0];
+            assert!(miss["timestamp"].as_str().is_some_and(|ts| !ts.is_empty()));
+            assert_eq!(miss["request_id"], "query-log-test");
+            assert_eq!(miss["client_ip"], "198.51.100.7");
+            assert_eq!(miss["protocol"], "doh-get");
+            assert_eq!(miss["qname"], "logged.example.com.");
+            assert_eq!(miss["qtype"], "A");
+            assert_eq!(miss["rcode"], "NoError");
+            assert_eq!(miss["answer_count"], 1);
+            assert_eq!(miss["cache"], "miss");
+            assert_eq!(miss["rule"], "exact:logged.example.com");
+            assert_eq!(miss["upstream_group"], "logged_group");
+            assert_eq!(miss["resolver"], format!("{}/dns-query", mock.uri()));
+            assert!(miss["latency_ms"].as_f64().is_some());
+
+            // 缓存命中不经过路由与上游
+            let hit = &lines[1];
+            assert_eq!(hit["cache"], "hit");
+            assert!(!hit.contains_key("rule") && !hit.contains_key("upstream_group"));
+
+            // 未命中规则的查询使用全局上游
+            let post = &lines[2];
+            assert_eq!(post["protocol"], "doh-post");
+            assert_eq!(post["qtype"], "AAAA");
+            assert_eq!(post["upstream_group"], "global");
+            assert!(!post.contains_key("rule"));
+        }).await;
+
+        info!("Test completed: test_query_log_writes_one_line_per_query");
+    }
+
+    #[tokio::test]
+    async fn test_query_log_hashes_client_ip() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_query_log_hashes_client_ip");
+
+        let dir = TempDir::new().unwrap();
+        let log_path = dir.path().join("query.log");
+        let query_log = QueryLogConfig {
+            hash_client_ip: true,
+            hash_key: Some("query-log-key".to_string()),
+            ..file_query_log(&log_path)
+        };
+        let customize = |config: &mut ServerConfig| {
+            config.logging.query_log = query_log;
+            config.http.trusted_proxies = vec!["127.0.0.1".to_string()];
+        };
+        test_with_server_config(customize, |server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+            let query = create_test_query("hashed.example.com", RecordType::A);
+            let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+            let response = reqwest::Client::new().get(url)
+                .header("X-Forwarded-For", "198.51.100.9")
+                .send().await.unwrap();
+            assert!(response.status().is_success());
+
+            // 输出为客户端 IP 的 HMAC-SHA256 摘要的前 16 字节
+            let key = hmac::Key::new(hmac::HMAC_SHA256, b"query-log-key");
+            let expected = hex::encode(&hmac::sign(&key, b"198.51.100.9").as_ref()[..16]);
+            let lines = read_log_lines(&log_path, 1).await;
+            assert_eq!(lines[0]["client_ip"], expected);
+            assert!(!std::fs::read_to_string(&log_path).unwrap().contains("198.51.100.9"));
+        }).await;
+
+        info!("Test completed: test_query_log_hashes_client_ip");
+    }
+
+    #[tokio::test]
+    async fn test_query_log_filters_and_sampling() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_query_log_filters_and_sampling");
+
+        let dir = TempDir::new().unwrap();
+        let log_path = dir.path().join("query.log");
+        // 采样率为 0：仅 log 过滤器匹配的查询被记录
+        let query_log = QueryLogConfig {
+            sample_rate: 0.0,
+            filters: serde_yaml::from_str(r#"
+            - match:
+                type: regex
+                values: ['(^|\.)example\.com$']
+                query_types: ["AAAA"]
+              action: skip
+            - match:
+                type: regex
+                values: ['example\.com$']
+              action: log
+            "#).unwrap(),
+            ..file_query_log(&log_path)
+        };
+        test_with_server_config(|config| config.logging.query_log = query_log, |server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+            let client = reqwest::Client::new();
+            let queries = [
+                // 命中 skip 过滤器
+                ("noisy.example.com", RecordType::AAAA),
+                // 未命中过滤器，按采样率 0 不记录
+                ("sampled.example.net", RecordType::A),
+                // 命中 log 过滤器，不受采样率影响
+                ("kept.example.com", RecordType::A),
+            ];
+            for (name, record_type) in queries {
+                let query = create_test_query(name, record_type);
+                let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+                assert!(client.get(url).send().await.unwrap().status().is_success());
+            }
+
+            // 日志按查询顺序写出，首行即为唯一被记录的查询
+            let lines = read_log_lines(&log_path, 1).await;
+            assert_eq!(lines.len(), 1);
+            assert_eq!(lines[0]["qname"], "kept.example.com.");
+            assert_eq!(lines[0]["qtype"], "A");
+        }).await;
+
+        info!("Test completed: test_query_log_filters_and_sampling");
+    }
+
+    #[test]
+    fn test_query_log_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_query_log_config_validation");
+
+        let parse = |query_log: &str| -> ServerConfig {
+            serde_yaml::from_str(&format!(r#"
+            http_server:
+              listen_addr: "127.0.0.1:8053"
+            dns_resolver:
+              upstream:
+                resolvers:
+                  - address: "8.8.8.8:53"
+                    protocol: udp
+            logging:
+              query_log:
+{}
+            "#, query_log)).unwrap()
+        };
+
+        let config = parse(r#"
+                enabled: true
+                target: file
+                path: "/var/log/owdns/query.log"
+                format: json
+                hash_client_ip: true"#);
+        assert!(config.test().is_ok());
+        assert_eq!(config.logging.query_log.target, QueryLogTarget::File);
+        assert!(config.logging.query_log.hash_client_ip);
+
+        // 默认未启用，输出到标准输出
+        let defaults = ServerConfig::default().logging.query_log;
+        assert!(!defaults.enabled);
+        assert_eq!(defaults.target, QueryLogTarget::Stdout);
+        assert_eq!(defaults.sample_rate, 1.0);
+        assert!(defaults.filters.is_empty());
+
+        let config = parse(r#"
+                enabled: true
+                sample_rate: 0.1
+                filters:
+                  - match:
+                      type: exact
+                      values: ["health.example.com"]
+                    action: skip
+                  - match:
+                      query_types: ["aaaa", "HTTPS"]
+                    action: log"#);
+        assert!(config.test().is_ok());
+        assert_eq!(config.logging.query_log.filters[1].action, QueryLogFilterAction::Log);
+
+        // 采样率超出范围、无效的正则、不支持的匹配类型与未知的查询类型
+        for invalid in [
+            "sample_rate: 1.5",
+            "filters: [{match: {type: regex, values: ['(']}, action: skip}]",
+            "filters: [{match: {type: wildcard, values: ['*.example.com']}, action: skip}]",
+            "filters: [{match: {query_types: ['BOGUS']}, action: skip}]",
+        ] {
+            let config = parse(&format!("                {}", invalid));
+            let err = config.test().unwrap_err();
+            assert!(err.to_string().contains("logging.query_log"), "Unexpected error: {}", err);
+        }
+
+        // 输出到文件时必须配置路径
+        let config = parse(r#"
+                enabled: true
+                target: file"#);
+        let err = config.test().unwrap_err();
+        assert!(err.to_string().contains("logging.query_log.path"), "Unexpected error: {}", err);
+
+        // 空的摘要密钥
+        let config = parse(r#"
+                enabled: true
+                hash_key: """#);
+        assert!(config.test().is_err());
+
+        // 未知的格式
+        let result: Result<ServerConfig, _> = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        logging:
+          query_log:
+            format: csv
+        "#);
+        assert!(result.is_err());
+
+        info!("Test completed: test_query_log_config_validation");
+    }
+}
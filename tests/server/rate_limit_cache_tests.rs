@@ -0,0 +1,110 @@
+// tests/server/rate_limit_cache_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::{RateLimitConfig, ServerConfig};
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 每秒 1 个请求且无突发余量，启用缓存
+    fn cache_rate_limit_config(upstream_uri: &str, count_cache_hits: bool) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: true
+            per_ip_rate: 1
+            per_ip_concurrent: 1
+            count_cache_hits: {}
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: true
+        "#, count_cache_hits, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 以固定客户端 IP 发送 GET 查询，返回状态码与拒绝请求的桶名称
+    async fn get_query(app: &Router, domain: &str) -> (StatusCode, Option<String>) {
+        let query = create_test_query(domain, RecordType::A);
+        let uri = format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let response = app.clone()
+            .oneshot(Request::get(uri)
+                .extension(ConnectInfo(SocketAddr::from(([203, 0, 113, 7], 40000))))
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            assert!(response.headers().contains_key(header::RETRY_AFTER));
+        }
+        let bucket = response.headers().get("X-RateLimit-Bucket")
+            .map(|v| v.to_str().unwrap().to_string());
+        (response.status(), bucket)
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_skips_cache_hits() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_rate_limit_skips_cache_hits");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = DoHServer::new(cache_rate_limit_config(&mock_server.uri(), false), false)
+            .build_application_components().await.unwrap().app;
+
+        // 首次查询需要访问上游，消耗唯一的配额
+        assert_eq!(get_query(&app, "cached.example.com").await, (StatusCode::OK, None));
+
+        // 重复的缓存命中不计入速率限制
+        for _ in 0..10 {
+            assert_eq!(get_query(&app, "cached.example.com").await, (StatusCode::OK, None));
+        }
+
+        // 需要访问上游的新查询仍被限制，返回标明桶名称的 429
+        assert_eq!(
+            get_query(&app, "uncached.example.com").await,
+            (StatusCode::TOO_MANY_REQUESTS, Some("default".to_string()))
+        );
+
+        // 被拒绝的查询未写入缓存，缓存命中仍可正常应答
+        assert_eq!(get_query(&app, "cached.example.com").await, (StatusCode::OK, None));
+
+        info!("Test completed: test_rate_limit_skips_cache_hits");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_counts_cache_hits_by_default() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_rate_limit_counts_cache_hits_by_default");
+
+        // 默认缓存命中计入速率限制
+        assert!(RateLimitConfig::default().count_cache_hits);
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = DoHServer::new(cache_rate_limit_config(&mock_server.uri(), true), false)
+            .build_application_components().await.unwrap().app;
+
+        assert_eq!(get_query(&app, "cached.example.com").await, (StatusCode::OK, None));
+        assert_eq!(
+            get_query(&app, "cached.example.com").await,
+            (StatusCode::TOO_MANY_REQUESTS, Some("default".to_string()))
+        );
+
+        info!("Test completed: test_rate_limit_counts_cache_hits_by_default");
+    }
+}
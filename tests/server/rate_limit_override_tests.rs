@@ -0,0 +1,158 @@
+// tests/server/rate_limit_override_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::{RateLimitOverrideConfig, ServerConfig};
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 修改配置使其失效的用例
+    type ConfigMutation = fn(&mut ServerConfig);
+
+    // 默认桶每秒 1 个请求且无突发余量；monitoring 网段不限速，family 网段突发 3 个，alice 令牌突发 2 个
+    fn override_config(upstream_uri: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: true
+            per_ip_rate: 1
+            per_ip_concurrent: 1
+            overrides:
+              - name: "monitoring"
+                cidrs: ["10.0.0.5"]
+                unlimited: true
+              - name: "family"
+                cidrs: ["192.168.0.0/16"]
+                burst: 3
+              - name: "alice-override"
+                tokens: ["rate-test-alice"]
+                burst: 2
+          auth:
+            enabled: true
+            tokens:
+              - name: "rate-test-alice"
+                token: "alice-secret"
+              - name: "rate-test-bob"
+                token: "bob-secret"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+        "#, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 以指定客户端 IP 与令牌发送 GET 查询，返回状态码与拒绝请求的桶名称
+    async fn get_query(app: &Router, client_ip: &str, token: &str) -> (StatusCode, Option<String>) {
+        let query = create_test_query("example.com", RecordType::A);
+        let uri = format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let response = app.clone()
+            .oneshot(Request::get(uri)
+                .extension(ConnectInfo(SocketAddr::new(client_ip.parse().unwrap(), 40000)))
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        let bucket = response.headers().get("X-RateLimit-Bucket")
+            .map(|v| v.to_str().unwrap().to_string());
+        (response.status(), bucket)
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_overrides_resolve_buckets() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_rate_limit_overrides_resolve_buckets");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let app = DoHServer::new(override_config(&mock_server.uri()), false)
+            .build_application_components().await.unwrap().app;
+
+        // 未匹配覆盖的客户端使用默认桶，429 响应标明桶名称并带有 Retry-After
+        assert_eq!(get_query(&app, "203.0.113.1", "bob-secret").await, (StatusCode::OK, None));
+        let query = create_test_query("example.com", RecordType::A);
+        let response = app.clone()
+            .oneshot(Request::get(format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap())))
+                .extension(ConnectInfo(SocketAddr::from(([203, 0, 113, 1], 40000))))
+                .header(header::AUTHORIZATION, "Bearer bob-secret")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get("X-RateLimit-Bucket").unwrap(), "default");
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        // 不限速的覆盖完全跳过限制器
+        for _ in 0..10 {
+            assert_eq!(get_query(&app, "10.0.0.5", "bob-secret").await, (StatusCode::OK, None));
+        }
+
+        // 网段覆盖按客户端 IP 计数
+        for _ in 0..3 {
+            assert_eq!(get_query(&app, "192.168.1.1", "bob-secret").await.0, StatusCode::OK);
+        }
+        assert_eq!(
+            get_query(&app, "192.168.1.1", "bob-secret").await,
+            (StatusCode::TOO_MANY_REQUESTS, Some("family".to_string()))
+        );
+        assert_eq!(get_query(&app, "192.168.1.2", "bob-secret").await.0, StatusCode::OK);
+
+        // 令牌覆盖优先于网段，按令牌名称计数（默认桶已耗尽的客户端同样可用）
+        assert_eq!(get_query(&app, "203.0.113.1", "alice-secret").await.0, StatusCode::OK);
+        assert_eq!(get_query(&app, "192.168.1.1", "alice-secret").await.0, StatusCode::OK);
+        assert_eq!(
+            get_query(&app, "10.0.0.5", "alice-secret").await,
+            (StatusCode::TOO_MANY_REQUESTS, Some("alice-override".to_string()))
+        );
+
+        info!("Test completed: test_rate_limit_overrides_resolve_buckets");
+    }
+
+    #[test]
+    fn test_rate_limit_override_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_rate_limit_override_validation");
+
+        let config = override_config("https://dns.example.com");
+        assert!(config.test().is_ok());
+        assert!(ServerConfig::default().http.rate_limit.overrides.is_empty());
+
+        let invalid_cases: Vec<(&str, ConfigMutation)> = vec![
+            ("empty name", |c| c.http.rate_limit.overrides[0].name = String::new()),
+            ("reserved name", |c| c.http.rate_limit.overrides[0].name = "default".to_string()),
+            ("duplicate name", |c| c.http.rate_limit.overrides[1].name = "monitoring".to_string()),
+            ("no match", |c| c.http.rate_limit.overrides.push(RateLimitOverrideConfig {
+                name: "empty".to_string(),
+                ..Default::default()
+            })),
+            ("invalid cidr", |c| c.http.rate_limit.overrides[1].cidrs.push("not-a-network".to_string())),
+            ("unlimited with rate", |c| c.http.rate_limit.overrides[0].rate = Some(10)),
+            ("zero rate", |c| c.http.rate_limit.overrides[1].rate = Some(0)),
+            ("zero burst", |c| c.http.rate_limit.overrides[1].burst = Some(0)),
+            ("tokens without auth", |c| c.http.auth.enabled = false),
+        ];
+
+        for (description, mutate) in invalid_cases {
+            let mut config = override_config("https://dns.example.com");
+            mutate(&mut config);
+            assert!(config.test().is_err(), "case should be rejected: {}", description);
+        }
+
+        info!("Test completed: test_rate_limit_override_validation");
+    }
+}
@@ -0,0 +1,113 @@
+// tests/server/record_dedup_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::rdata::{A, NS};
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use tracing::info;
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::record_dedup::remove_duplicate_records;
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::test_with_server_config;
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 81);
+
+    fn a_record(name: &str, ttl: u32, ip: Ipv4Addr) -> Record {
+        Record::from_rdata(Name::from_str(name).unwrap(), ttl, RData::A(A(ip)))
+    }
+
+    fn ns_record(name: &str, target: &str) -> Record {
+        Record::from_rdata(Name::from_str(name).unwrap(), 300, RData::NS(NS(Name::from_str(target).unwrap())))
+    }
+
+    // 应答、授权与附加部分包含重复记录的响应
+    fn response_with_duplicates(query: &Message) -> Message {
+        let mut response = create_test_response(query, ANSWER_IP);
+        response.take_answers();
+        response.add_answer(a_record("dup.example.com.", 300, ANSWER_IP));
+        // 同一部分内的重复记录（名称大小写与 TTL 不同）
+        response.add_answer(a_record("DUP.example.com.", 60, ANSWER_IP));
+        response.add_answer(a_record("dup.example.com.", 300, Ipv4Addr::new(192, 0, 2, 82)));
+        response.add_name_server(ns_record("example.com.", "ns1.example.com."));
+        response.add_name_server(ns_record("example.com.", "ns1.example.com."));
+        // 附加部分重复了应答与授权部分已有的记录
+        response.add_additional(a_record("dup.example.com.", 300, ANSWER_IP));
+        response.add_additional(ns_record("example.com.", "ns1.example.com."));
+        response.add_additional(a_record("ns1.example.com.", 300, Ipv4Addr::new(192, 0, 2, 53)));
+        response
+    }
+
+    #[test]
+    fn test_remove_duplicate_records_across_sections() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_remove_duplicate_records_across_sections");
+
+        let query = create_test_query("dup.example.com", RecordType::A);
+        let mut response = response_with_duplicates(&query);
+        assert_eq!(remove_duplicate_records(&mut response), 4);
+
+        // 保留首次出现的记录，不同记录数据的同名记录不受影响
+        assert_eq!(response.answers(), &[
+            a_record("dup.example.com.", 300, ANSWER_IP),
+            a_record("dup.example.com.", 300, Ipv4Addr::new(192, 0, 2, 82)),
+        ]);
+        assert_eq!(response.name_servers(), &[ns_record("example.com.", "ns1.example.com.")]);
+        assert_eq!(response.additionals(), &[a_record("ns1.example.com.", 300, Ipv4Addr::new(192, 0, 2, 53))]);
+
+        // 头部计数同步更新，去重后的消息可以正确编解码
+        assert_eq!(response.answer_count(), 2);
+        assert_eq!(response.name_server_count(), 1);
+        let decoded = Message::from_vec(&response.to_vec().unwrap()).unwrap();
+        assert_eq!(decoded.answers().len(), 2);
+        assert_eq!(decoded.additionals().len(), 1);
+
+        // 没有重复记录时保持不变
+        let unchanged = response.clone();
+        assert_eq!(remove_duplicate_records(&mut response), 0);
+        assert_eq!(response, unchanged);
+
+        info!("Test completed: test_remove_duplicate_records_across_sections");
+    }
+
+    #[tokio::test]
+    async fn test_response_deduplication_applied_to_upstream_responses() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_response_deduplication_applied_to_upstream_responses");
+
+        let removed_before = METRICS.response_dedup_records_removed_total().get();
+        let customize = |config: &mut oxide_wdns::server::config::ServerConfig| {
+            config.dns.response_deduplication = true;
+        };
+        test_with_server_config(customize, |server_addr, mock| async move {
+            Mock::given(matchers::method("POST"))
+                .and(matchers::path("/dns-query"))
+                .respond_with(|request: &wiremock::Request| {
+                    let query = Message::from_vec(&request.body).unwrap();
+                    ResponseTemplate::new(200)
+                        .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                        .set_body_bytes(response_with_duplicates(&query).to_vec().unwrap())
+                })
+                .mount(&mock)
+                .await;
+
+            let query = create_test_query("dup.example.com", RecordType::A);
+            let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+            let body = reqwest::get(url).await.unwrap().bytes().await.unwrap();
+            let response = Message::from_vec(&body).unwrap();
+            assert_eq!(response.answers().len(), 2);
+            assert_eq!(response.name_servers().len(), 1);
+            assert_eq!(response.additionals().len(), 1);
+            assert!(METRICS.response_dedup_records_removed_total().get() >= removed_before + 4);
+        }).await;
+
+        info!("Test completed: test_response_deduplication_applied_to_upstream_responses");
+    }
+}
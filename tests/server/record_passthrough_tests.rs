@@ -0,0 +1,162 @@
+// tests/server/record_passthrough_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::str::FromStr;
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::rdata::{A, AAAA, CNAME, MX, NS, NULL, SRV, TXT};
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::{extract_ip_addresses, test_with_server};
+
+    // URI 记录（RFC 7553）的类型值，hickory 以未知类型保留其原始数据
+    const RECORD_TYPE_URI: u16 = 256;
+
+    fn name(value: &str) -> Name {
+        Name::from_str(value).unwrap()
+    }
+
+    // 上游对各类型查询返回的应答记录
+    fn answers_for(query_name: &Name, record_type: RecordType) -> Vec<Record> {
+        let rdata = match record_type {
+            RecordType::AAAA => vec![
+                RData::AAAA(AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))),
+                RData::AAAA(AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2))),
+            ],
+            RecordType::MX => vec![
+                RData::MX(MX::new(10, name("mx1.example.com."))),
+                RData::MX(MX::new(20, name("mx2.example.com."))),
+            ],
+            RecordType::SRV => vec![
+                RData::SRV(SRV::new(10, 60, 5060, name("sip1.example.com."))),
+                RData::SRV(SRV::new(20, 40, 5061, name("sip2.example.com."))),
+            ],
+            RecordType::TXT => vec![
+                RData::TXT(TXT::new(vec!["v=spf1 -all".to_string()])),
+                RData::TXT(TXT::new(vec!["part one".to_string(), "part two".to_string()])),
+            ],
+            RecordType::Unknown(RECORD_TYPE_URI) => vec![RData::Unknown {
+                code: RecordType::Unknown(RECORD_TYPE_URI),
+                rdata: NULL::with(b"\x00\x0a\x00\x01ftp://ftp.example.com/public".to_vec()),
+            }],
+            _ => Vec::new(),
+        };
+        rdata.into_iter()
+            .enumerate()
+            .map(|(i, rdata)| Record::from_rdata(query_name.clone(), 300 + i as u32 * 100, rdata))
+            .collect()
+    }
+
+    // 构建包含应答、授权与附加部分的完整上游响应
+    fn full_response(query: &Message) -> Message {
+        let question = &query.queries()[0];
+        let mut response = create_test_response(query, Ipv4Addr::new(192, 0, 2, 1));
+        response.take_answers();
+        response.add_answers(answers_for(question.name(), question.query_type()));
+        response.add_name_server(Record::from_rdata(name("example.com."), 86400, RData::NS(NS(name("ns1.example.com.")))));
+        response.add_name_server(Record::from_rdata(name("example.com."), 86400, RData::NS(NS(name("ns2.example.com.")))));
+        response.add_additional(Record::from_rdata(name("ns1.example.com."), 3600, RData::A(A::new(192, 0, 2, 53))));
+        response.add_additional(Record::from_rdata(
+            name("ns2.example.com."), 1800, RData::AAAA(AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53)))
+        ));
+        response
+    }
+
+    async fn mount_full_responses(mock: &MockServer) {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(full_response(&query).to_vec().unwrap())
+            })
+            .mount(mock)
+            .await;
+    }
+
+    async fn query_via_proxy(server_addr: &str, query: &Message) -> Message {
+        let response = reqwest::Client::new()
+            .post(format!("{}/dns-query", server_addr))
+            .header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+            .body(query.to_vec().unwrap())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        Message::from_vec(&response.bytes().await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_types_pass_through_unmodified() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_record_types_pass_through_unmodified");
+
+        test_with_server(|server_addr, mock| async move {
+            mount_full_responses(&mock).await;
+
+            let record_types = [
+                RecordType::AAAA,
+                RecordType::MX,
+                RecordType::SRV,
+                RecordType::TXT,
+                RecordType::Unknown(RECORD_TYPE_URI),
+            ];
+            for record_type in record_types {
+                let query = create_test_query("_svc._tcp.example.com", record_type);
+                let expected = full_response(&query);
+
+                // 应答、授权与附加部分（含 TTL 与全部字段）与上游响应一致
+                let response = query_via_proxy(&server_addr, &query).await;
+                assert_eq!(response.id(), query.id());
+                assert_eq!(response.queries(), query.queries());
+                assert_eq!(response.answers(), expected.answers(), "answers for {}", record_type);
+                assert_eq!(response.name_servers(), expected.name_servers(), "authority for {}", record_type);
+                assert_eq!(response.additionals(), expected.additionals(), "additional for {}", record_type);
+
+                // 上游收到的查询保留原始类型
+                let requests = mock.received_requests().await.unwrap();
+                let forwarded = Message::from_vec(&requests.last().unwrap().body).unwrap();
+                assert_eq!(forwarded.queries()[0].query_type(), record_type);
+
+                // 缓存命中的响应保留所有部分，TTL 不超过上游的值
+                let cached = query_via_proxy(&server_addr, &query).await;
+                assert_eq!(extract_ip_addresses(&cached), extract_ip_addresses(&expected));
+                assert_eq!(cached.name_servers().len(), expected.name_servers().len());
+                assert_eq!(cached.additionals().len(), expected.additionals().len());
+                for (cached, upstream) in cached.answers().iter().zip(expected.answers()) {
+                    assert!(cached.ttl() <= upstream.ttl());
+                }
+            }
+        }).await;
+
+        info!("Test completed: test_record_types_pass_through_unmodified");
+    }
+
+    #[test]
+    fn test_extract_ip_addresses() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_extract_ip_addresses");
+
+        let owner = name("_svc._tcp.example.com.");
+        let mut message = Message::new();
+        message.add_answers(answers_for(&owner, RecordType::SRV));
+        message.add_answers(answers_for(&owner, RecordType::MX));
+        message.add_answer(Record::from_rdata(owner.clone(), 60, RData::CNAME(CNAME(name("target.example.net.")))));
+        assert_eq!(extract_ip_addresses(&message), vec![
+            "10 60 5060 sip1.example.com.",
+            "20 40 5061 sip2.example.com.",
+            "10 mx1.example.com.",
+            "20 mx2.example.com.",
+            "target.example.net.",
+        ]);
+
+        info!("Test completed: test_extract_ip_addresses");
+    }
+}
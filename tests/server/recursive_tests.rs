@@ -0,0 +1,235 @@
+// tests/server/recursive_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use hickory_proto::op::{Message, MessageType, ResponseCode};
+    use hickory_proto::rr::rdata::{A, CNAME, NS, SOA};
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use tokio::net::UdpSocket;
+    use tracing::info;
+    use oxide_wdns::server::config::{ServerConfig, UpstreamMode};
+    use oxide_wdns::server::recursive::RecursiveResolver;
+    use oxide_wdns::server::upstream::{UpstreamManager, UpstreamSelection};
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::extract_ip_addresses;
+
+    const ROOT_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+    const TLD_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 2);
+    const AUTH_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 3);
+
+    fn name(value: &str) -> Name {
+        Name::from_str(value).unwrap()
+    }
+
+    fn ns(zone: &str, target: &str) -> Record {
+        Record::from_rdata(name(zone), 3600, RData::NS(NS(name(target))))
+    }
+
+    fn a(owner: &str, ip: Ipv4Addr) -> Record {
+        Record::from_rdata(name(owner), 300, RData::A(A(ip)))
+    }
+
+    // 模拟的名称服务器，按查询名称返回推荐或权威应答
+    type Zone = fn(&Name, RecordType, &mut Message);
+
+    // 根服务器：test. 委派给 ns.test.（带粘合记录）
+    fn root_zone(_qname: &Name, _qtype: RecordType, response: &mut Message) {
+        response.add_name_server(ns("test.", "ns.test."));
+        response.add_additional(a("ns.test.", TLD_IP));
+    }
+
+    // test. 区域：example.test. 带粘合记录，glueless.test. 的名称服务器位于 example.test. 中且不带粘合记录
+    fn tld_zone(qname: &Name, _qtype: RecordType, response: &mut Message) {
+        if name("glueless.test.").zone_of(qname) {
+            response.add_name_server(ns("glueless.test.", "ns.example.test."));
+        } else {
+            response.add_name_server(ns("example.test.", "ns.example.test."));
+            response.add_additional(a("ns.example.test.", AUTH_IP));
+        }
+    }
+
+    // example.test. 与 glueless.test. 的权威服务器
+    fn auth_zone(qname: &Name, qtype: RecordType, response: &mut Message) {
+        response.set_authoritative(true);
+        match (qname.to_ascii().as_str(), qtype) {
+            ("www.example.test." | "www2.example.test.", RecordType::A) => response.add_answer(a(&qname.to_ascii(), Ipv4Addr::new(192, 0, 2, 10))),
+            ("ns.example.test.", RecordType::A) => response.add_answer(a("ns.example.test.", AUTH_IP)),
+            ("alias.example.test.", _) => response.add_answer(Record::from_rdata(qname.clone(), 300, RData::CNAME(CNAME(name("host.glueless.test."))))),
+            ("host.glueless.test.", RecordType::A) => response.add_answer(a("host.glueless.test.", Ipv4Addr::new(192, 0, 2, 20))),
+            _ => {
+                response.set_response_code(ResponseCode::NXDomain);
+                let soa = SOA::new(name("ns.example.test."), name("hostmaster.example.test."), 1, 3600, 600, 86400, 60);
+                response.add_name_server(Record::from_rdata(name("example.test."), 60, RData::SOA(soa)))
+            },
+        };
+    }
+
+    // 在指定地址启动模拟名称服务器，返回收到的查询数
+    async fn spawn_nameserver(addr: SocketAddr, zone: Zone) -> Arc<AtomicUsize> {
+        let socket = UdpSocket::bind(addr).await.unwrap();
+        let queries = Arc::new(AtomicUsize::new(0));
+        let counter = queries.clone();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 4096];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let query = Message::from_vec(&buf[..len]).unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                // 迭代查询不应请求递归
+                assert!(!query.recursion_desired());
+                let question = query.queries()[0].clone();
+                let mut response = Message::new();
+                response.set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .add_query(question.clone());
+                zone(question.name(), question.query_type(), &mut response);
+                socket.send_to(&response.to_vec().unwrap(), peer).await.unwrap();
+            }
+        });
+        queries
+    }
+
+    struct Hierarchy {
+        upstream: UpstreamManager,
+        root_queries: Arc<AtomicUsize>,
+        tld_queries: Arc<AtomicUsize>,
+    }
+
+    // 在 127.0.0.1-3 的同一端口上启动根、test. 与权威服务器
+    async fn start_hierarchy() -> Hierarchy {
+        let port = UdpSocket::bind((ROOT_IP, 0)).await.unwrap().local_addr().unwrap().port();
+        let root_queries = spawn_nameserver(SocketAddr::new(IpAddr::V4(ROOT_IP), port), root_zone).await;
+        let tld_queries = spawn_nameserver(SocketAddr::new(IpAddr::V4(TLD_IP), port), tld_zone).await;
+        spawn_nameserver(SocketAddr::new(IpAddr::V4(AUTH_IP), port), auth_zone).await;
+
+        let config: ServerConfig = serde_yaml::from_str(&format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            mode: recursive
+            root_hints: ["{}:{}"]
+            resolvers: []
+            query_timeout: 5
+        "#, ROOT_IP, port)).unwrap();
+        config.test().unwrap();
+        let resolver = RecursiveResolver::with_nameserver_port(&config.dns.upstream, port).unwrap();
+        let upstream = UpstreamManager::with_transport(Arc::new(config), Arc::new(resolver)).await.unwrap();
+        Hierarchy { upstream, root_queries, tld_queries }
+    }
+
+    async fn resolve(upstream: &UpstreamManager, domain: &str) -> Message {
+        let query = create_test_query(domain, RecordType::A);
+        let response = upstream.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+        assert_eq!(response.id(), query.id());
+        assert!(response.recursion_available());
+        response
+    }
+
+    #[tokio::test]
+    async fn test_recursive_resolution_follows_referrals() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_recursive_resolution_follows_referrals");
+
+        let hierarchy = start_hierarchy().await;
+        let response = resolve(&hierarchy.upstream, "www.example.test").await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(extract_ip_addresses(&response), vec!["192.0.2.10"]);
+        assert_eq!(hierarchy.root_queries.load(Ordering::SeqCst), 1);
+        assert_eq!(hierarchy.tld_queries.load(Ordering::SeqCst), 1);
+
+        // 区域委派已缓存，同一区域的查询直接发往权威服务器
+        let response = resolve(&hierarchy.upstream, "www2.example.test").await;
+        assert_eq!(extract_ip_addresses(&response), vec!["192.0.2.10"]);
+        assert_eq!(hierarchy.root_queries.load(Ordering::SeqCst), 1);
+        assert_eq!(hierarchy.tld_queries.load(Ordering::SeqCst), 1);
+
+        info!("Test completed: test_recursive_resolution_follows_referrals");
+    }
+
+    #[tokio::test]
+    async fn test_recursive_resolution_cname_and_glueless_delegation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_recursive_resolution_cname_and_glueless_delegation");
+
+        let hierarchy = start_hierarchy().await;
+        // CNAME 指向 glueless.test.，其名称服务器地址需要单独解析
+        let response = resolve(&hierarchy.upstream, "alias.example.test").await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(extract_ip_addresses(&response), vec!["host.glueless.test.", "192.0.2.20"]);
+
+        // 负响应保留权威部分的 SOA
+        let response = resolve(&hierarchy.upstream, "missing.example.test").await;
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert!(response.answers().is_empty());
+        assert!(response.name_servers().iter().any(|record| record.record_type() == RecordType::SOA));
+
+        info!("Test completed: test_recursive_resolution_cname_and_glueless_delegation");
+    }
+
+    #[test]
+    fn test_recursive_mode_config() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_recursive_mode_config");
+
+        // 默认转发到 resolvers，根服务器为 IANA 根服务器
+        let defaults = ServerConfig::default().dns.upstream;
+        assert_eq!(defaults.mode, UpstreamMode::Forwarding);
+        assert_eq!(defaults.root_hints.len(), 13);
+
+        let parse = |upstream: &str| -> ServerConfig {
+            serde_yaml::from_str(&format!(r#"
+            http_server:
+              listen_addr: "127.0.0.1:8053"
+            dns_resolver:
+              upstream:
+{}
+              routing:
+                enabled: true
+                upstream_groups:
+                  - name: "forwarded"
+                    resolvers:
+                      - address: "8.8.8.8:53"
+                        protocol: udp
+            "#, upstream)).unwrap()
+        };
+
+        // 递归模式的 resolvers 可以为空，根服务器地址未指定端口时使用 53
+        let config = parse(r#"
+                mode: recursive
+                root_hints: ["198.41.0.4", "[2001:503:ba3e::2:30]:53"]
+                resolvers: []"#);
+        assert!(config.test().is_ok());
+        let hints = config.dns.upstream.parsed_root_hints().unwrap();
+        assert_eq!(hints[0], "198.41.0.4:53".parse::<SocketAddr>().unwrap());
+        assert_eq!(hints[1].port(), 53);
+        // 上游组不继承递归模式
+        assert_eq!(config.get_effective_upstream_config("forwarded").unwrap().mode, UpstreamMode::Forwarding);
+
+        // 无效的根服务器地址
+        let config = parse(r#"
+                mode: recursive
+                root_hints: ["a.root-servers.net"]
+                resolvers: []"#);
+        let err = config.test().unwrap_err();
+        assert!(err.to_string().contains("root_hints"), "Unexpected error: {}", err);
+
+        // 递归模式不能与 SRV 上游发现同时使用
+        let config = parse(r#"
+                mode: recursive
+                discover_via_srv: true
+                srv_name: "_dns._https.example.com"
+                resolvers:
+                  - address: "8.8.8.8:53"
+                    protocol: udp"#);
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_recursive_mode_config");
+    }
+}
@@ -0,0 +1,132 @@
+// tests/server/redirect_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use tempfile::TempDir;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::redirect::https_redirect_routes;
+    use oxide_wdns::server::DoHServer;
+
+    // 修改配置使其失效的用例
+    type ConfigMutation = fn(&mut ServerConfig);
+
+    // HTTPS 监听 8443，明文 HTTP 监听 8080 并重定向到 dns.example.com
+    fn redirect_config(dir: &Path) -> ServerConfig {
+        let certified = rcgen::generate_simple_self_signed(vec!["dns.example.com".to_string()]).unwrap();
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        fs::write(&cert, certified.cert.pem()).unwrap();
+        fs::write(&key, certified.key_pair.serialize_pem()).unwrap();
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8443"
+          server_name: "dns.example.com"
+          http_listen_addr: "127.0.0.1:8080"
+          http_to_https_redirect: true
+          tls:
+            cert: "{}"
+            key: "{}"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#, cert.display(), key.display());
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    async fn redirect_location(app: &Router, request: Request<Body>) -> String {
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        response.headers().get(header::LOCATION).unwrap().to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_https_redirect_preserves_path_and_query() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_https_redirect_preserves_path_and_query");
+
+        let app = https_redirect_routes("dns.example.com", 8443);
+        let location = redirect_location(&app, Request::get("/dns-query?dns=AAABAAABAAAAAAAA").body(Body::empty()).unwrap()).await;
+        assert_eq!(location, "https://dns.example.com:8443/dns-query?dns=AAABAAABAAAAAAAA");
+
+        // 所有方法与路径都被重定向
+        let location = redirect_location(&app, Request::post("/resolve").body(Body::from("query")).unwrap()).await;
+        assert_eq!(location, "https://dns.example.com:8443/resolve");
+        let location = redirect_location(&app, Request::get("/").body(Body::empty()).unwrap()).await;
+        assert_eq!(location, "https://dns.example.com:8443/");
+
+        // 默认 HTTPS 端口在重定向地址中省略
+        let app = https_redirect_routes("dns.example.com", 443);
+        let location = redirect_location(&app, Request::get("/dns-query").body(Body::empty()).unwrap()).await;
+        assert_eq!(location, "https://dns.example.com/dns-query");
+
+        info!("Test completed: test_https_redirect_preserves_path_and_query");
+    }
+
+    #[tokio::test]
+    async fn test_https_redirect_app_uses_config() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_https_redirect_app_uses_config");
+
+        let dir = TempDir::new().unwrap();
+        let config = redirect_config(dir.path());
+        assert_eq!(config.http.https_redirect_listen_addr(), Some("127.0.0.1:8080".parse().unwrap()));
+
+        let components = DoHServer::new(config.clone(), false).build_application_components().await.unwrap();
+        let app = components.redirect_app.expect("redirect app should be built");
+        let location = redirect_location(&app, Request::get("/dns-query").body(Body::empty()).unwrap()).await;
+        assert_eq!(location, "https://dns.example.com:8443/dns-query");
+
+        // 未启用重定向时不创建应用
+        let mut config = config;
+        config.http.http_to_https_redirect = false;
+        assert!(config.http.https_redirect_listen_addr().is_none());
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        assert!(components.redirect_app.is_none());
+
+        info!("Test completed: test_https_redirect_app_uses_config");
+    }
+
+    #[test]
+    fn test_https_redirect_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_https_redirect_config_validation");
+
+        let dir = TempDir::new().unwrap();
+        let config = redirect_config(dir.path());
+        assert!(config.test().is_ok());
+        assert_eq!(config.http.effective_server_name(), Some("dns.example.com"));
+
+        let default_config = ServerConfig::default();
+        assert!(!default_config.http.http_to_https_redirect);
+        assert!(default_config.http.effective_server_name().is_none());
+
+        let invalid_cases: Vec<(&str, ConfigMutation)> = vec![
+            ("no tls", |c| c.http.tls = None),
+            ("no http listen addr", |c| c.http.http_listen_addr = None),
+            ("same as listen addr", |c| c.http.http_listen_addr = Some("127.0.0.1:8443".parse().unwrap())),
+            ("same as admin addr", |c| c.http.admin_listen_addr = Some("127.0.0.1:8080".parse().unwrap())),
+            ("no server name", |c| c.http.server_name = String::new()),
+            ("invalid server name", |c| c.http.server_name = "dns example.com".to_string()),
+        ];
+
+        for (description, mutate) in invalid_cases {
+            let mut config = redirect_config(dir.path());
+            mutate(&mut config);
+            assert!(config.test().is_err(), "case should be rejected: {}", description);
+        }
+
+        info!("Test completed: test_https_redirect_config_validation");
+    }
+}
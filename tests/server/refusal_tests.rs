@@ -0,0 +1,176 @@
+// tests/server/refusal_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::str::FromStr;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use hickory_proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+    use hickory_proto::rr::{Name, RecordType};
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::DoHServer;
+    use oxide_wdns::server::config::{ServerConfig, UpstreamConfig};
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::refusal::{refusal_reason, RefusalReason};
+    use crate::server::test_helpers::{mock_upstream_config, mount_doh_answer};
+
+    // 启用私有地址 PTR 拒绝与 AXFR/IXFR 拒绝的上游配置
+    fn refusal_config() -> UpstreamConfig {
+        let mut config = ServerConfig::default().dns.upstream;
+        config.refused_types_for_private_ips = true;
+        config.refused_types = vec!["AXFR".to_string(), "ixfr".to_string()];
+        config
+    }
+
+    // 反向解析指定地址的 PTR 查询
+    fn ptr_query(ip: &str) -> Query {
+        Query::query(Name::from(IpAddr::from_str(ip).unwrap()), RecordType::PTR)
+    }
+
+    // 创建指定名称与类型的查询消息
+    fn query_message(name: Name, record_type: RecordType) -> Message {
+        let mut message = Message::new();
+        message.set_id(4321)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true)
+            .add_query(Query::query(name, record_type));
+        message
+    }
+
+    // 以 POST 发送查询并解析响应
+    async fn send_query(app: &Router, message: &Message) -> Message {
+        let request = Request::post("/dns-query")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+            .body(Body::from(message.to_vec().unwrap()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        Message::from_vec(&body).unwrap()
+    }
+
+    #[test]
+    fn test_refusal_reason_for_private_ptr_and_types() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_refusal_reason_for_private_ptr_and_types");
+
+        let config = refusal_config();
+
+        // RFC 1918、RFC 4193 与链路本地地址的 PTR 查询被拒绝
+        for ip in ["10.1.2.3", "172.16.0.1", "172.31.255.255", "192.168.1.10", "169.254.1.1", "fd12:3456::1", "fe80::1"] {
+            assert_eq!(refusal_reason(&config, &ptr_query(ip)), Some(RefusalReason::PrivatePtr), "{}", ip);
+        }
+        // 公网地址与私有地址段外的相邻地址正常转发
+        for ip in ["8.8.8.8", "172.32.0.1", "192.169.0.1", "2001:db8::1"] {
+            assert_eq!(refusal_reason(&config, &ptr_query(ip)), None, "{}", ip);
+        }
+
+        // 只包含地址前缀的反向解析名称：前缀整体位于私有地址段内时拒绝
+        let prefix = |name: &str| Query::query(Name::from_ascii(name).unwrap(), RecordType::PTR);
+        assert_eq!(refusal_reason(&config, &prefix("168.192.in-addr.arpa.")), Some(RefusalReason::PrivatePtr));
+        assert_eq!(refusal_reason(&config, &prefix("172.in-addr.arpa.")), None);
+        assert_eq!(refusal_reason(&config, &prefix("in-addr.arpa.")), None);
+
+        // 非 PTR 查询与关闭开关时不拒绝
+        let a_query = Query::query(Name::from(Ipv4Addr::new(10, 0, 0, 1)), RecordType::A);
+        assert_eq!(refusal_reason(&config, &a_query), None);
+        let mut disabled = refusal_config();
+        disabled.refused_types_for_private_ips = false;
+        assert_eq!(refusal_reason(&disabled, &ptr_query("10.1.2.3")), None);
+
+        // 指定的记录类型无条件拒绝（类型名称不区分大小写）
+        let zone = Name::from_ascii("example.com.").unwrap();
+        assert_eq!(refusal_reason(&config, &Query::query(zone.clone(), RecordType::AXFR)), Some(RefusalReason::QueryType(RecordType::AXFR)));
+        assert_eq!(refusal_reason(&config, &Query::query(zone.clone(), RecordType::IXFR)), Some(RefusalReason::QueryType(RecordType::IXFR)));
+        assert_eq!(refusal_reason(&config, &Query::query(zone, RecordType::SOA)), None);
+
+        // 默认不拒绝任何查询
+        let defaults = ServerConfig::default().dns.upstream;
+        assert!(!defaults.refused_types_for_private_ips);
+        assert!(defaults.refused_types.is_empty());
+        assert_eq!(refusal_reason(&defaults, &ptr_query("10.1.2.3")), None);
+
+        info!("Test completed: test_refusal_reason_for_private_ptr_and_types");
+    }
+
+    #[tokio::test]
+    async fn test_refused_queries_are_not_forwarded() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_refused_queries_are_not_forwarded");
+
+        let mock = MockServer::start().await;
+        mount_doh_answer(&mock, Ipv4Addr::new(192, 0, 2, 1)).await;
+        let mut config = mock_upstream_config(8053, &mock.uri());
+        config.dns.upstream = UpstreamConfig {
+            resolvers: config.dns.upstream.resolvers.clone(),
+            ..refusal_config()
+        };
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        let private_before = METRICS.private_ptr_refused_total().get();
+        let axfr_before = METRICS.query_type_refused_total().with_label_values(&["AXFR"]).get();
+
+        // 私有地址的 PTR 查询直接返回 REFUSED
+        let query = query_message(Name::from(Ipv4Addr::new(192, 168, 0, 1)), RecordType::PTR);
+        let response = send_query(&app, &query).await;
+        assert_eq!(response.response_code(), ResponseCode::Refused);
+        assert_eq!(response.id(), 4321);
+        assert_eq!(response.queries(), query.queries());
+        assert!(response.answers().is_empty());
+
+        // 指定的记录类型直接返回 REFUSED
+        let query = query_message(Name::from_ascii("example.com.").unwrap(), RecordType::AXFR);
+        assert_eq!(send_query(&app, &query).await.response_code(), ResponseCode::Refused);
+
+        assert!(mock.received_requests().await.unwrap().is_empty(), "Refused queries must not reach the upstream");
+        assert!(METRICS.private_ptr_refused_total().get() > private_before);
+        assert!(METRICS.query_type_refused_total().with_label_values(&["AXFR"]).get() > axfr_before);
+
+        // 其他查询正常转发
+        let query = query_message(Name::from_ascii("example.com.").unwrap(), RecordType::A);
+        assert_eq!(send_query(&app, &query).await.response_code(), ResponseCode::NoError);
+        assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+
+        info!("Test completed: test_refused_queries_are_not_forwarded");
+    }
+
+    #[test]
+    fn test_refusal_config_parsing_and_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_refusal_config_parsing_and_validation");
+
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "https://1.1.1.1/dns-query"
+                protocol: doh
+            refused_types_for_private_ips: true
+            refused_types: [AXFR, IXFR, "255"]
+        "#;
+        let config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        assert!(config.dns.upstream.refused_types_for_private_ips);
+        assert_eq!(
+            config.dns.upstream.parsed_refused_types().unwrap(),
+            vec![RecordType::AXFR, RecordType::IXFR, RecordType::ANY]
+        );
+        assert!(config.test().is_ok());
+
+        // 未知的记录类型被拒绝
+        let invalid: ServerConfig = serde_yaml::from_str(&config_str.replace("IXFR", "BOGUS")).unwrap();
+        assert!(invalid.test().is_err());
+
+        info!("Test completed: test_refusal_config_parsing_and_validation");
+    }
+}
@@ -0,0 +1,157 @@
+// tests/server/request_dedup_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+    use futures::future::join_all;
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::doh_handler::{PendingRequest, PendingRequests};
+    use oxide_wdns::server::metrics::METRICS;
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use oxide_wdns::server::config::ErrorMode;
+    use crate::server::test_helpers::{test_with_server, test_with_server_config};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const CONCURRENT_REQUESTS: usize = 5;
+    const UPSTREAM_DELAY: Duration = Duration::from_millis(800);
+
+    // 上游延迟应答，使并发请求在解析完成前到达
+    async fn mount_slow_upstream(mock: &MockServer, status: u16) {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(move |request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                ResponseTemplate::new(status)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(create_test_response(&query, ANSWER_IP).to_vec().unwrap())
+                    .set_delay(UPSTREAM_DELAY)
+            })
+            .mount(mock)
+            .await;
+    }
+
+    // 并发发送逐字节相同的 POST 请求，返回各自的 HTTP 响应
+    async fn send_identical(server_addr: &str, query: &Message) -> Vec<reqwest::Response> {
+        let client = reqwest::Client::new();
+        let body = query.to_vec().unwrap();
+        let requests = (0..CONCURRENT_REQUESTS).map(|_| {
+            client.post(format!("{}/dns-query", server_addr))
+                .header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                .body(body.clone())
+                .send()
+        });
+        join_all(requests).await.into_iter().map(|response| response.unwrap()).collect()
+    }
+
+    // 并发发送逐字节相同的 POST 请求，返回各自的 DNS 响应
+    async fn post_identical(server_addr: &str, query: &Message) -> Vec<Message> {
+        let mut responses = Vec::new();
+        for response in send_identical(server_addr, query).await {
+            assert_eq!(response.status(), 200);
+            responses.push(Message::from_vec(&response.bytes().await.unwrap()).unwrap());
+        }
+        responses
+    }
+
+    #[tokio::test]
+    async fn test_identical_requests_share_resolution() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_identical_requests_share_resolution");
+
+        test_with_server(|server_addr, mock| async move {
+            mount_slow_upstream(&mock, 200).await;
+            let hits_before = METRICS.request_deduplication_hits_total().get();
+
+            let query = create_test_query("dedup.example.com", RecordType::A);
+            let responses = post_identical(&server_addr, &query).await;
+
+            // 上游只收到一次查询，所有请求得到相同的应答
+            assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+            for response in &responses {
+                assert_eq!(response.id(), query.id());
+                assert_eq!(response.response_code(), ResponseCode::NoError);
+                assert_eq!(response.answers(), responses[0].answers());
+            }
+            assert!(METRICS.request_deduplication_hits_total().get() >= hits_before + CONCURRENT_REQUESTS as u64 - 1);
+        }).await;
+
+        info!("Test completed: test_identical_requests_share_resolution");
+    }
+
+    #[tokio::test]
+    async fn test_shared_resolution_failure_returns_servfail() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_shared_resolution_failure_returns_servfail");
+
+        test_with_server(|server_addr, mock| async move {
+            mount_slow_upstream(&mock, 500).await;
+
+            // 上游失败时负责解析的请求与所有等待者都返回 SERVFAIL
+            let query = create_test_query("fail.example.com", RecordType::A);
+            for response in post_identical(&server_addr, &query).await {
+                assert_eq!(response.id(), query.id());
+                assert_eq!(response.response_code(), ResponseCode::ServFail);
+            }
+        }).await;
+
+        info!("Test completed: test_shared_resolution_failure_returns_servfail");
+    }
+
+    #[tokio::test]
+    async fn test_shared_resolution_failure_follows_error_mode() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_shared_resolution_failure_follows_error_mode");
+
+        test_with_server_config(|config| config.http.error_mode = ErrorMode::HttpStatus, |server_addr, mock| async move {
+            mount_slow_upstream(&mock, 500).await;
+
+            // 等待者与负责解析的请求一样返回 HTTP 错误状态，而不是 200 SERVFAIL
+            let query = create_test_query("fail-status.example.com", RecordType::A);
+            for response in send_identical(&server_addr, &query).await {
+                assert_eq!(response.status(), 502);
+            }
+            assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+        }).await;
+
+        info!("Test completed: test_shared_resolution_failure_follows_error_mode");
+    }
+
+    #[tokio::test]
+    async fn test_pending_requests_keys_and_failure_signal() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_pending_requests_keys_and_failure_signal");
+
+        let pending = PendingRequests::default();
+        let wire = create_test_query("keys.example.com", RecordType::A).to_vec().unwrap();
+        let client: IpAddr = "198.51.100.1".parse().unwrap();
+        let other_client: IpAddr = "198.51.100.2".parse().unwrap();
+
+        let PendingRequest::Leader(leader) = pending.join(&wire, Some(client), None) else {
+            panic!("first request should lead the resolution");
+        };
+        let PendingRequest::Waiter(mut waiter) = pending.join(&wire, Some(client), None) else {
+            panic!("identical request should wait for the leader");
+        };
+
+        // 客户端或上游组不同的请求不共享解析
+        assert!(matches!(pending.join(&wire, Some(other_client), None), PendingRequest::Leader(_)));
+        assert!(matches!(pending.join(&wire, Some(client), Some("office")), PendingRequest::Leader(_)));
+        assert_eq!(pending.len(), 1);
+
+        // 未广播结果就结束的解析向等待者广播错误
+        drop(leader);
+        assert!(waiter.recv().await.unwrap().is_err());
+        assert!(pending.is_empty());
+
+        info!("Test completed: test_pending_requests_keys_and_failure_signal");
+    }
+}
@@ -0,0 +1,229 @@
+// tests/server/request_id_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{header, Request, StatusCode};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 创建使用模拟 DoH 上游的服务器配置
+    fn request_id_config(upstream_uri: &str, extra_http: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          rate_limit:
+            enabled: false
+          {}
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: false
+        "#, extra_http, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 构建带可选请求 ID 头的 DoH POST 请求
+    fn dns_post_request(request_id: Option<(&str, &str)>) -> Request<Body> {
+        let query = create_test_query("example.com", RecordType::A);
+        let mut builder = Request::post("/dns-query")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE);
+        if let Some((name, value)) = request_id {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::from(query.to_vec().unwrap())).unwrap()
+    }
+
+    // 构建来自指定连接源地址、带请求 ID 头的 DoH POST 请求
+    fn dns_post_request_from(peer: &str, request_id: &str) -> Request<Body> {
+        let mut request = dns_post_request(Some(("X-Request-ID", request_id)));
+        request.extensions_mut().insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        request
+    }
+
+    // 获取模拟上游收到的所有请求中的指定头
+    async fn upstream_header_values(mock_server: &MockServer, name: &str) -> Vec<Option<String>> {
+        mock_server.received_requests().await.unwrap()
+            .iter()
+            .map(|request| request.headers.get(name).map(|v| v.to_str().unwrap().to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_request_id_echoed_and_propagated_upstream() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_request_id_echoed_and_propagated_upstream");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = request_id_config(&mock_server.uri(), "propagate_request_id_upstream: true");
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        // 客户端提供的请求 ID 应原样回显并透传给上游
+        let response = app.clone()
+            .oneshot(dns_post_request(Some(("X-Request-ID", "trace-abc-123"))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("x-request-id").unwrap(), "trace-abc-123");
+        assert_eq!(
+            upstream_header_values(&mock_server, "x-request-id").await,
+            vec![Some("trace-abc-123".to_string())]
+        );
+
+        // 未提供请求 ID 时生成 UUID v4，上游收到相同的 ID
+        let response = app.oneshot(dns_post_request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let generated = response.headers().get("x-request-id").unwrap().to_str().unwrap().to_string();
+        let uuid = uuid::Uuid::parse_str(&generated).unwrap();
+        assert_eq!(uuid.get_version_num(), 4);
+        assert_eq!(upstream_header_values(&mock_server, "x-request-id").await[1], Some(generated));
+
+        info!("Test completed: test_request_id_echoed_and_propagated_upstream");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_not_propagated_by_default() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_request_id_not_propagated_by_default");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = request_id_config(&mock_server.uri(), "");
+        assert_eq!(config.http.request_id_header, "X-Request-ID");
+        assert!(!config.http.propagate_request_id_upstream);
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        // 响应仍回显请求 ID，但不发送给上游
+        let response = app.oneshot(dns_post_request(Some(("X-Request-ID", "trace-abc-123")))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("x-request-id").unwrap(), "trace-abc-123");
+        assert_eq!(upstream_header_values(&mock_server, "x-request-id").await, vec![None]);
+
+        info!("Test completed: test_request_id_not_propagated_by_default");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_custom_header_and_invalid_value() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_request_id_custom_header_and_invalid_value");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = request_id_config(
+            &mock_server.uri(),
+            "request_id_header: \"X-Trace-ID\"\n          propagate_request_id_upstream: true",
+        );
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        // 使用自定义头名称读取、回显并透传
+        let response = app.clone().oneshot(dns_post_request(Some(("X-Trace-ID", "custom-1")))).await.unwrap();
+        assert_eq!(response.headers().get("x-trace-id").unwrap(), "custom-1");
+        assert!(response.headers().get("x-request-id").is_none());
+        assert_eq!(upstream_header_values(&mock_server, "x-trace-id").await, vec![Some("custom-1".to_string())]);
+
+        // 超长的请求 ID 被替换为新生成的 ID
+        let too_long = "a".repeat(200);
+        let response = app.oneshot(dns_post_request(Some(("X-Trace-ID", &too_long)))).await.unwrap();
+        let replaced = response.headers().get("x-trace-id").unwrap().to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(replaced).is_ok());
+
+        info!("Test completed: test_request_id_custom_header_and_invalid_value");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_honored_only_from_trusted_proxies() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_request_id_honored_only_from_trusted_proxies");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = request_id_config(
+            &mock_server.uri(),
+            "request_id_trusted_proxies: [\"10.0.0.0/8\"]\n          propagate_request_id_upstream: true",
+        );
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        // 受信任代理提供的请求 ID 被沿用
+        let response = app.clone().oneshot(dns_post_request_from("10.1.1.1:40000", "proxy-id-1")).await.unwrap();
+        assert_eq!(response.headers().get("x-request-id").unwrap(), "proxy-id-1");
+
+        // 其他来源提供的请求 ID 被替换，上游收到替换后的 ID
+        let response = app.clone().oneshot(dns_post_request_from("192.0.2.5:40000", "spoofed-id")).await.unwrap();
+        let replaced = response.headers().get("x-request-id").unwrap().to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&replaced).is_ok());
+
+        // 无法确定连接源地址时同样不受信任
+        let response = app.oneshot(dns_post_request(Some(("X-Request-ID", "unknown-peer")))).await.unwrap();
+        assert_ne!(response.headers().get("x-request-id").unwrap(), "unknown-peer");
+
+        let upstream_ids = upstream_header_values(&mock_server, "x-request-id").await;
+        assert_eq!(upstream_ids[0], Some("proxy-id-1".to_string()));
+        assert_eq!(upstream_ids[1], Some(replaced));
+
+        info!("Test completed: test_request_id_honored_only_from_trusted_proxies");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_propagated_from_background_miss_resolution() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_request_id_propagated_from_background_miss_resolution");
+
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let mut config = request_id_config(&mock_server.uri(), "propagate_request_id_upstream: true");
+        config.dns.cache.enabled = true;
+        config.dns.cache.async_miss_resolution = true;
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        // 后台任务解析缓存未命中时，上游请求仍携带触发请求的 ID
+        let response = app.oneshot(dns_post_request(Some(("X-Request-ID", "background-1")))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            upstream_header_values(&mock_server, "x-request-id").await,
+            vec![Some("background-1".to_string())]
+        );
+
+        info!("Test completed: test_request_id_propagated_from_background_miss_resolution");
+    }
+
+    #[test]
+    fn test_request_id_header_validation() {
+        let mut config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          request_id_header: "X-Correlation-ID"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+        assert!(config.test().is_ok());
+
+        config.http.request_id_header = "Invalid Header".to_string();
+        assert!(config.test().is_err());
+
+        config.http.request_id_header = String::new();
+        assert!(config.test().is_err());
+
+        config.http.request_id_header = "X-Request-ID".to_string();
+        config.http.request_id_trusted_proxies = vec!["10.0.0.0/8".to_string(), "2001:db8::1".to_string()];
+        assert!(config.test().is_ok());
+        config.http.request_id_trusted_proxies = vec!["not-a-network".to_string()];
+        let err = config.test().unwrap_err();
+        assert!(err.to_string().contains("request_id_trusted_proxies"), "Unexpected error: {}", err);
+    }
+}
@@ -0,0 +1,108 @@
+// tests/server/resolve_api_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::{RData, RecordType};
+    use hickory_proto::rr::rdata::A;
+    use tracing::info;
+    use oxide_wdns::server::config::ServerConfig;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+    use crate::server::test_helpers::server_state;
+
+    const DEFAULT_UPSTREAM_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const OFFICE_GROUP_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 2);
+
+    // 默认上游与 office 上游组分别指向不同的模拟服务器，10.8.0.0/16 的客户端路由到 office
+    fn routing_config(default_uri: &str, office_uri: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: true
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "office"
+                resolvers:
+                  - address: "{}/dns-query"
+                    protocol: doh
+            rules:
+              - match:
+                  type: client_ip
+                  values: ["10.8.0.0/16"]
+                upstream_group: "office"
+        "#, default_uri, office_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 提取响应中的第一个 A 记录
+    fn answer_ip(message: &Message) -> Ipv4Addr {
+        match message.answers()[0].data() {
+            Some(RData::A(A(ip))) => *ip,
+            other => panic!("unexpected answer: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_runs_full_pipeline() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_resolve_runs_full_pipeline");
+
+        let (default_server, default_count) = setup_mock_doh_server(DEFAULT_UPSTREAM_IP).await;
+        let (office_server, office_count) = setup_mock_doh_server(OFFICE_GROUP_IP).await;
+        let state = server_state(routing_config(&default_server.uri(), &office_server.uri())).await;
+
+        // 不经过 HTTP 层直接解析，响应保留查询 ID
+        let mut query = create_test_query("example.com", RecordType::A);
+        query.set_id(4321);
+        let response = state.resolve(query.clone(), None).await;
+        assert_eq!(response.id(), 4321);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(answer_ip(&response), DEFAULT_UPSTREAM_IP);
+
+        // 第二次查询命中缓存，使用新的查询 ID
+        query.set_id(8765);
+        let response = state.resolve(query, None).await;
+        assert_eq!(response.id(), 8765);
+        assert_eq!(*default_count.lock().unwrap(), 1);
+
+        // 客户端 IP 参与 client_ip 路由规则匹配
+        let query = create_test_query("office.example.com", RecordType::A);
+        let client_ip = IpAddr::V4(Ipv4Addr::new(10, 8, 1, 1));
+        let response = state.resolve(query, Some(client_ip)).await;
+        assert_eq!(answer_ip(&response), OFFICE_GROUP_IP);
+        assert_eq!(*office_count.lock().unwrap(), 1);
+
+        info!("Test completed: test_resolve_runs_full_pipeline");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_returns_servfail_on_failure() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_resolve_returns_servfail_on_failure");
+
+        // 上游不可达时返回 SERVFAIL，而不是错误
+        let state = server_state(routing_config("http://127.0.0.1:1", "http://127.0.0.1:1")).await;
+        let mut query = create_test_query("example.com", RecordType::A);
+        query.set_id(99);
+        let response = state.resolve(query, None).await;
+        assert_eq!(response.id(), 99);
+        assert_eq!(response.response_code(), ResponseCode::ServFail);
+
+        // 空问题部分同样返回 SERVFAIL
+        let response = state.resolve(Message::new(), None).await;
+        assert_eq!(response.response_code(), ResponseCode::ServFail);
+
+        info!("Test completed: test_resolve_returns_servfail_on_failure");
+    }
+}
@@ -0,0 +1,209 @@
+// tests/server/response_validation_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use hickory_proto::op::{Message, MessageType, ResponseCode};
+    use hickory_proto::rr::rdata::{A, CNAME, SOA};
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use tracing::info;
+    use url::Url;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::error::ServerError;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::response_validation::{validate_doh_origin, validate_response};
+    use oxide_wdns::server::upstream::{TransportFuture, UpstreamManager, UpstreamSelection, UpstreamTransport};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+
+    const GOOD_URL: &str = "https://good.example.net/dns-query";
+    const POISONED_URL: &str = "https://poisoned.example.net/dns-query";
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const FORGED_IP: Ipv4Addr = Ipv4Addr::new(203, 0, 113, 66);
+
+    fn name(value: &str) -> Name {
+        Name::from_str(value).unwrap()
+    }
+
+    fn a_record(owner: &str, ip: Ipv4Addr) -> Record {
+        Record::from_rdata(name(owner), 300, RData::A(A(ip)))
+    }
+
+    fn cname_record(owner: &str, target: &str) -> Record {
+        Record::from_rdata(name(owner), 300, RData::CNAME(CNAME(name(target))))
+    }
+
+    fn soa_record(zone: &str) -> Record {
+        let soa = SOA::new(name(&format!("ns.{}", zone)), name(&format!("admin.{}", zone)), 1, 3600, 600, 86400, 300);
+        Record::from_rdata(name(zone), 300, RData::SOA(soa))
+    }
+
+    // 仅包含问题部分的空响应
+    fn empty_response(query: &Message) -> Message {
+        let mut response = create_test_response(query, ANSWER_IP);
+        response.take_answers();
+        response
+    }
+
+    // 传输替身：被投毒的上游返回不属于查询名称的应答
+    struct PoisonTransport {
+        poisoned: HashSet<&'static str>,
+    }
+
+    impl UpstreamTransport for PoisonTransport {
+        fn exchange<'a>(&'a self, upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+            let poisoned = self.poisoned.contains(upstream);
+            Box::pin(async move {
+                if !poisoned {
+                    return Ok(create_test_response(query, ANSWER_IP));
+                }
+                let mut response = empty_response(query);
+                response.add_answer(a_record("attacker.example.org.", FORGED_IP));
+                Ok(response)
+            })
+        }
+    }
+
+    async fn build_manager(strict: bool, poisoned: &[&'static str]) -> UpstreamManager {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            strict_response_validation: {}
+            resolvers:
+              - address: "{}"
+                protocol: doh
+              - address: "{}"
+                protocol: doh
+        "#, strict, POISONED_URL, GOOD_URL);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        let transport = Arc::new(PoisonTransport { poisoned: poisoned.iter().copied().collect() });
+        UpstreamManager::with_transport(Arc::new(config), transport).await.unwrap()
+    }
+
+    #[test]
+    fn test_validate_response_answer_names() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_validate_response_answer_names");
+
+        let query = create_test_query("www.example.com", RecordType::A);
+        assert!(validate_response(&query, &create_test_response(&query, ANSWER_IP)).is_ok());
+
+        // 查询名称大小写不同仍视为同一名称
+        let mut response = empty_response(&query);
+        response.add_answer(a_record("WWW.Example.COM.", ANSWER_IP));
+        assert!(validate_response(&query, &response).is_ok());
+
+        // CNAME 链（乱序出现）上的记录都属于查询
+        let mut response = empty_response(&query);
+        response.add_answer(a_record("edge.cdn.example.net.", ANSWER_IP));
+        response.add_answer(cname_record("cdn.example.net.", "edge.cdn.example.net."));
+        response.add_answer(cname_record("www.example.com.", "cdn.example.net."));
+        assert!(validate_response(&query, &response).is_ok());
+
+        // 不属于查询名称或 CNAME 链的应答
+        let mut response = empty_response(&query);
+        response.add_answer(a_record("www.example.com.", ANSWER_IP));
+        response.add_answer(a_record("bank.example.org.", FORGED_IP));
+        assert!(validate_response(&query, &response).is_err());
+
+        // 问题部分与查询不一致
+        let other = create_test_query("other.example.com", RecordType::A);
+        assert!(validate_response(&query, &create_test_response(&other, ANSWER_IP)).is_err());
+        let aaaa = create_test_query("www.example.com", RecordType::AAAA);
+        assert!(validate_response(&query, &create_test_response(&aaaa, ANSWER_IP)).is_err());
+
+        // 不是响应消息
+        let mut response = create_test_response(&query, ANSWER_IP);
+        response.set_message_type(MessageType::Query);
+        assert!(validate_response(&query, &response).is_err());
+
+        info!("Test completed: test_validate_response_answer_names");
+    }
+
+    #[test]
+    fn test_validate_response_authoritative_negative() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_validate_response_authoritative_negative");
+
+        let query = create_test_query("missing.example.com", RecordType::A);
+
+        // 非权威的否定应答无需 SOA
+        let mut response = empty_response(&query);
+        response.set_response_code(ResponseCode::NXDomain);
+        assert!(validate_response(&query, &response).is_ok());
+
+        // 权威的 NXDOMAIN 与 NODATA 必须携带所在区的 SOA
+        response.set_authoritative(true);
+        assert!(validate_response(&query, &response).is_err());
+        response.add_name_server(soa_record("example.com."));
+        assert!(validate_response(&query, &response).is_ok());
+
+        let mut response = empty_response(&query);
+        response.set_authoritative(true);
+        assert!(validate_response(&query, &response).is_err());
+        response.add_name_server(soa_record("example.org."));
+        assert!(validate_response(&query, &response).is_err());
+        response.add_name_server(soa_record("example.com."));
+        assert!(validate_response(&query, &response).is_ok());
+
+        info!("Test completed: test_validate_response_authoritative_negative");
+    }
+
+    #[test]
+    fn test_validate_doh_origin() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_validate_doh_origin");
+
+        let url = |value: &str| Url::parse(value).unwrap();
+
+        assert!(validate_doh_origin(GOOD_URL, &url("https://GOOD.example.net/dns-query"), None).is_ok());
+        // 重定向到其他主机或端口
+        assert!(validate_doh_origin(GOOD_URL, &url("https://evil.example.net/dns-query"), None).is_err());
+        assert!(validate_doh_origin(GOOD_URL, &url("https://good.example.net:8443/dns-query"), None).is_err());
+
+        // IP 地址形式的上游须与对端地址一致
+        let ip_url = "https://192.0.2.53/dns-query";
+        assert!(validate_doh_origin(ip_url, &url(ip_url), Some("192.0.2.53".parse().unwrap())).is_ok());
+        assert!(validate_doh_origin(ip_url, &url(ip_url), Some("198.51.100.7".parse().unwrap())).is_err());
+
+        info!("Test completed: test_validate_doh_origin");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_discards_poisoned_response() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_discards_poisoned_response");
+
+        assert!(ServerConfig::default().dns.upstream.strict_response_validation);
+        let query = create_test_query("www.example.com", RecordType::A);
+        let failures = METRICS.upstream_validation_failures_total().with_label_values(&[POISONED_URL, "global"]);
+
+        // 第一个上游的响应被丢弃，改用下一个上游
+        let before = failures.get();
+        let manager = build_manager(true, &[POISONED_URL]).await;
+        let response = manager.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(response.answers()[0].data(), Some(&RData::A(A(ANSWER_IP))));
+        assert_eq!(failures.get(), before + 1);
+
+        // 所有上游都未通过校验时按上游不可用处理
+        let manager = build_manager(true, &[POISONED_URL, GOOD_URL]).await;
+        let result = manager.resolve(&query, UpstreamSelection::Global, None, None).await;
+        assert!(matches!(result, Err(ServerError::UpstreamUnavailable(_))));
+
+        // 关闭严格校验时直接使用第一个上游的响应
+        let manager = build_manager(false, &[POISONED_URL]).await;
+        let response = manager.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+        assert_eq!(response.answers()[0].data(), Some(&RData::A(A(FORGED_IP))));
+
+        info!("Test completed: test_upstream_discards_poisoned_response");
+    }
+}
@@ -0,0 +1,81 @@
+// tests/server/root_info_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::test_helpers::mock_upstream_config;
+
+    async fn get_root(app: Router, accept: Option<&str>) -> (StatusCode, Option<String>, String) {
+        let mut request = Request::get("/");
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let content_type = response.headers().get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_root_info_html_and_json() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_root_info_html_and_json");
+
+        let mut config = mock_upstream_config(8053, "http://127.0.0.1:9");
+        config.http.doh_paths = vec!["/dns-query".to_string(), "/custom-dns".to_string()];
+        config.dns.cache.enabled = true;
+        config.dns.routing.enabled = false;
+        config.http.websocket_enabled = true;
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        // 默认返回简短的 HTML 页面
+        let (status, content_type, body) = get_root(app.clone(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(content_type.unwrap().starts_with("text/html"));
+        assert!(body.contains(env!("CARGO_PKG_VERSION")));
+        assert!(body.contains("<code>/custom-dns</code>"));
+
+        // Accept: application/json 时返回服务器信息
+        let (status, content_type, body) = get_root(app, Some("application/json")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.as_deref(), Some("application/json"));
+        let info: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!info["git_commit"].as_str().unwrap().is_empty());
+        assert_eq!(info["features"]["cache"], true);
+        assert_eq!(info["features"]["routing"], false);
+        assert_eq!(info["features"]["dnssec"], false);
+        assert_eq!(info["features"]["websocket"], true);
+        assert_eq!(info["doh_paths"], serde_json::json!(["/dns-query", "/custom-dns"]));
+        assert_eq!(info["json_api_path"], "/resolve");
+        assert_eq!(info["websocket_path"], "/dns-ws");
+        assert!(info.get("odoh_path").is_none());
+        assert!(info["uptime_secs"].as_u64().unwrap() < 60);
+
+        info!("Test completed: test_root_info_html_and_json");
+    }
+
+    #[tokio::test]
+    async fn test_root_info_can_be_disabled() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_root_info_can_be_disabled");
+
+        let mut config = mock_upstream_config(8053, "http://127.0.0.1:9");
+        assert!(config.http.root_info_enabled);
+        config.http.root_info_enabled = false;
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+        let (status, _, _) = get_root(app, Some("application/json")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        info!("Test completed: test_root_info_can_be_disabled");
+    }
+}
@@ -0,0 +1,202 @@
+// tests/server/route_test_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::net::IpAddr;
+    use clap::Parser;
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::server::args::{CliArgs, Command};
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::route_test::{build_router, read_domains, route_test, skipped_rule_sources};
+    use oxide_wdns::server::routing::RouteDecision;
+
+    // 创建包含精确、通配符、黑洞与客户端 IP 规则的配置；上游地址不可达，路由测试不应访问它们
+    fn route_test_config(default_group: Option<&str>) -> ServerConfig {
+        let default_line = default_group
+            .map(|group| format!("default_upstream_group: \"{}\"", group))
+            .unwrap_or_default();
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "192.0.2.1:53"
+                protocol: udp
+          routing:
+            enabled: true
+            {}
+            upstream_groups:
+              - name: "internal"
+                resolvers:
+                  - address: "192.0.2.2:53"
+                    protocol: udp
+              - name: "office"
+                resolvers:
+                  - address: "192.0.2.3:53"
+                    protocol: udp
+            rules:
+              - match:
+                  type: exact
+                  values: ["intranet.example.com"]
+                upstream_group: "internal"
+              - match:
+                  type: wildcard
+                  values: ["*.ads.example"]
+                upstream_group: "__blackhole__"
+              - match:
+                  type: client_ip
+                  values: ["10.8.0.0/16"]
+                upstream_group: "office"
+        "#, default_line);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        config.test().unwrap();
+        config
+    }
+
+    #[tokio::test]
+    async fn test_route_test_reports_rule_and_group() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_route_test_reports_rule_and_group");
+
+        let router = build_router(&route_test_config(None)).await.unwrap();
+
+        // 精确规则，域名被规范化
+        let result = route_test(&router, "Intranet.Example.com.", None).await;
+        assert_eq!(result.domain, "intranet.example.com");
+        assert_eq!(result.decision, RouteDecision::UseGroup("internal".to_string()));
+        assert_eq!(result.rule.as_deref(), Some("exact:intranet.example.com"));
+        assert_eq!(result.to_string(), "intranet.example.com\tupstream_group=internal\trule=exact:intranet.example.com");
+
+        // 黑洞规则
+        let result = route_test(&router, "tracker.ads.example", None).await;
+        assert_eq!(result.decision, RouteDecision::Blackhole);
+        assert_eq!(result.target(), "__blackhole__");
+        assert!(result.rule.unwrap().starts_with("wildcard:"));
+
+        // 客户端 IP 规则只在指定客户端 IP 时命中
+        let client_ip: IpAddr = "10.8.1.1".parse().unwrap();
+        let result = route_test(&router, "www.example.org", Some(client_ip)).await;
+        assert_eq!(result.target(), "office");
+        assert_eq!(result.rule.as_deref(), Some("client_ip:10.8.1.1"));
+
+        // 未命中任何规则时使用全局上游
+        let result = route_test(&router, "www.example.org", None).await;
+        assert_eq!(result.decision, RouteDecision::UseGlobal);
+        assert_eq!(result.target(), "__default__");
+        assert!(result.rule.is_none());
+        assert_eq!(result.to_string(), "www.example.org\tupstream_group=__default__\trule=-");
+
+        // 配置了默认上游组时未命中的域名使用默认组
+        let router = build_router(&route_test_config(Some("internal"))).await.unwrap();
+        let result = route_test(&router, "www.example.org", None).await;
+        assert_eq!(result.target(), "internal");
+        assert_eq!(result.rule.as_deref(), Some("default"));
+
+        info!("Test completed: test_route_test_reports_rule_and_group");
+    }
+
+    #[tokio::test]
+    async fn test_route_test_skips_url_rule_sources() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_route_test_skips_url_rule_sources");
+
+        // 规则来源均可正常下载，路由测试不应请求它们
+        let mock = MockServer::start().await;
+        Mock::given(matchers::method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("remote.example.com\n"))
+            .mount(&mock)
+            .await;
+
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "192.0.2.1:53"
+                protocol: udp
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "internal"
+                resolvers:
+                  - address: "192.0.2.2:53"
+                    protocol: udp
+            rules_url: "{uri}/rules.yaml"
+            rules:
+              - match:
+                  type: exact
+                  values: ["intranet.example.com"]
+                upstream_group: "internal"
+              - match:
+                  type: url
+                  url: "{uri}/domains.txt"
+                upstream_group: "internal"
+              - match:
+                  type: client_ip
+                  values: ["10.8.0.0/16"]
+                  url: "{uri}/networks.txt"
+                upstream_group: "internal"
+        "#, uri = mock.uri());
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        config.test().unwrap();
+
+        assert_eq!(skipped_rule_sources(&config), vec![
+            format!("{}/domains.txt", mock.uri()),
+            format!("{}/networks.txt", mock.uri()),
+            format!("{}/rules.yaml", mock.uri()),
+        ]);
+
+        let router = build_router(&config).await.unwrap();
+
+        // URL 规则未加载，域名使用全局上游；内联规则与客户端 IP 的静态网段仍然生效
+        let result = route_test(&router, "remote.example.com", None).await;
+        assert_eq!(result.decision, RouteDecision::UseGlobal);
+        let result = route_test(&router, "intranet.example.com", None).await;
+        assert_eq!(result.target(), "internal");
+        let client_ip: IpAddr = "10.8.1.1".parse().unwrap();
+        let result = route_test(&router, "www.example.org", Some(client_ip)).await;
+        assert_eq!(result.target(), "internal");
+        router.stop_background_tasks();
+
+        assert!(mock.received_requests().await.unwrap().is_empty(), "route-test should not download rule sources");
+
+        info!("Test completed: test_route_test_skips_url_rule_sources");
+    }
+
+    #[test]
+    fn test_route_test_reads_domains_and_parses_args() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_route_test_reads_domains_and_parses_args");
+
+        // 每行一个域名，忽略空行与注释
+        let input = "example.com\n\n# comment\n  intranet.example.com  \n";
+        assert_eq!(read_domains(Cursor::new(input)).unwrap(), vec!["example.com", "intranet.example.com"]);
+
+        let args = CliArgs::try_parse_from([
+            "owdns", "-c", "config.yaml", "route-test", "a.example", "b.example", "--client-ip", "10.8.0.1",
+        ]).unwrap();
+        match args.command {
+            Some(Command::RouteTest { domains, client_ip }) => {
+                assert_eq!(domains, vec!["a.example", "b.example"]);
+                assert_eq!(client_ip, Some("10.8.0.1".parse().unwrap()));
+            },
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        // 未指定子命令时启动服务器
+        let args = CliArgs::try_parse_from(["owdns", "-c", "config.yaml"]).unwrap();
+        assert!(args.command.is_none());
+
+        // 客户端 IP 必须是有效地址
+        assert!(CliArgs::try_parse_from(["owdns", "route-test", "a.example", "--client-ip", "bogus"]).is_err());
+
+        info!("Test completed: test_route_test_reads_domains_and_parses_args");
+    }
+}
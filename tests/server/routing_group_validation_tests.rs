@@ -0,0 +1,143 @@
+// tests/server/routing_group_validation_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use tracing::info;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::route_test::route_test;
+    use oxide_wdns::server::routing::{RouteDecision, Router};
+
+    // 创建包含 internal 上游组与指定规则的配置，上游地址不可达，测试不应访问它们
+    fn routing_config(rules: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "192.0.2.1:53"
+                protocol: udp
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "internal"
+                resolvers:
+                  - address: "192.0.2.2:53"
+                    protocol: udp
+            rules:
+{}
+        "#, rules);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_router_rejects_dangling_group_reference() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_router_rejects_dangling_group_reference");
+
+        let config = routing_config(r#"
+              - match:
+                  type: exact
+                  values: ["intranet.example.com"]
+                upstream_group: "internal"
+              - match:
+                  type: wildcard
+                  values: ["*.corp.example", "*.lab.example", "*.dev.example", "*.qa.example"]
+                upstream_group: "corp""#);
+
+        // 构建路由器时直接返回配置错误，指明规则序号、规则内容与未定义的上游组
+        let error = Router::new(config.dns.routing.clone(), None).await.err()
+            .expect("dangling group reference must fail")
+            .to_string();
+        assert!(error.contains("Rule #2"), "{}", error);
+        assert!(error.contains("wildcard: *.corp.example, *.lab.example, *.dev.example, ..."), "{}", error);
+        assert!(error.contains("unknown upstream group: corp"), "{}", error);
+
+        // 配置校验给出相同的错误
+        let error = config.test().unwrap_err().to_string();
+        assert!(error.contains("Rule #2 (wildcard: *.corp.example"), "{}", error);
+        assert!(error.contains("corp"), "{}", error);
+
+        // 回退链中未定义的上游组同样被拒绝
+        let config = routing_config(r#"
+              - match:
+                  type: exact
+                  values: ["intranet.example.com"]
+                upstream_group: "internal"
+                failover_chain: ["backup"]"#);
+        let error = Router::new(config.dns.routing.clone(), None).await.err().unwrap().to_string();
+        assert!(error.contains("Rule #1 (exact: intranet.example.com)"), "{}", error);
+        assert!(error.contains("backup"), "{}", error);
+
+        // 未定义的默认上游组
+        let mut config = routing_config(r#"
+              - match:
+                  type: exact
+                  values: ["intranet.example.com"]
+                upstream_group: "internal""#);
+        config.dns.routing.default_upstream_group = Some("missing".to_string());
+        let error = Router::new(config.dns.routing.clone(), None).await.err().unwrap().to_string();
+        assert!(error.contains("missing"), "{}", error);
+
+        info!("Test completed: test_router_rejects_dangling_group_reference");
+    }
+
+    #[tokio::test]
+    async fn test_router_accepts_special_route_targets() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_router_accepts_special_route_targets");
+
+        // __default__ 使用全局上游，可用于从更宽泛的规则中排除部分域名
+        let config = routing_config(r#"
+              - match:
+                  type: exact
+                  values: ["public.corp.example"]
+                upstream_group: "__default__"
+              - match:
+                  type: exact
+                  values: ["ads.corp.example"]
+                upstream_group: "__blackhole__"
+              - match:
+                  type: wildcard
+                  values: ["*.corp.example"]
+                upstream_group: "internal""#);
+        assert!(config.test().is_ok());
+        let router = Router::new(config.dns.routing.clone(), None).await.unwrap();
+
+        assert_eq!(route_test(&router, "public.corp.example", None).await.decision, RouteDecision::UseGlobal);
+        assert_eq!(route_test(&router, "ads.corp.example", None).await.decision, RouteDecision::Blackhole);
+        assert_eq!(
+            route_test(&router, "wiki.corp.example", None).await.decision,
+            RouteDecision::UseGroup("internal".to_string())
+        );
+
+        // 特殊目标不能出现在上游组列表或回退链中
+        for rules in [
+            r#"
+              - match:
+                  type: exact
+                  values: ["a.example"]
+                upstream_groups: ["internal", "__default__"]"#,
+            r#"
+              - match:
+                  type: exact
+                  values: ["a.example"]
+                upstream_group: "internal"
+                failover_chain: ["__default__"]"#,
+            r#"
+              - match:
+                  type: exact
+                  values: ["a.example"]
+                upstream_group: "__default__"
+                failover_chain: ["internal"]"#,
+        ] {
+            let config = routing_config(rules);
+            assert!(config.test().is_err(), "{}", rules);
+            assert!(Router::new(config.dns.routing.clone(), None).await.is_err(), "{}", rules);
+        }
+
+        info!("Test completed: test_router_accepts_special_route_targets");
+    }
+}
@@ -16,7 +16,7 @@ mod tests {
     use hickory_proto::op::{Message, MessageType, OpCode};
     use hickory_proto::rr::{Name, RecordType};
     use wiremock::{Mock, MockServer, ResponseTemplate};
-    use wiremock::matchers::{method, path};
+    use wiremock::matchers::{header, method, path};
     
     use oxide_wdns::server::config::ServerConfig;
     use oxide_wdns::server::routing::{Router, RouteDecision};
@@ -1094,4 +1094,509 @@ dns_resolver:
         
         info!("Test completed: test_url_rule_global_routing_disabled");
     }
-} 
\ No newline at end of file
+    
+    // 创建使用远程规则集的配置
+    fn remote_rules_config(rules_url: &str, interval_secs: u64) -> ServerConfig {
+        let config_content = format!(r#"
+http_server:
+  listen_addr: "127.0.0.1:8053"
+dns_resolver:
+  upstream:
+    resolvers:
+      - address: "8.8.8.8:53"
+        protocol: udp
+  routing:
+    enabled: true
+    upstream_groups:
+      - name: "group_a"
+        resolvers:
+          - address: "1.1.1.1:53"
+            protocol: udp
+      - name: "group_b"
+        resolvers:
+          - address: "9.9.9.9:53"
+            protocol: udp
+    rules:
+      - match:
+          type: exact
+          values: ["local.example.com"]
+        upstream_group: "group_b"
+    rules_url: "{}"
+    rules_reload_interval_secs: {}
+"#, rules_url, interval_secs);
+        serde_yaml::from_str(&config_content).unwrap()
+    }
+    
+    // 挂载返回远程规则集的模拟响应
+    async fn mount_remote_rules(mock_server: &MockServer, upstream_group: &str, etag: &str) {
+        let rules = format!(r#"
+- match:
+    type: wildcard
+    values: ["*.remote.example.com"]
+  upstream_group: "{}"
+- match:
+    type: exact
+    values: ["local.example.com"]
+  upstream_group: "group_a"
+"#, upstream_group);
+        
+        // 携带当前 ETag 的请求返回 304
+        Mock::given(method("GET"))
+            .and(path("/rules.yaml"))
+            .and(header("If-None-Match", etag))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(mock_server)
+            .await;
+        
+        Mock::given(method("GET"))
+            .and(path("/rules.yaml"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("ETag", etag)
+                .set_body_string(rules))
+            .mount(mock_server)
+            .await;
+    }
+    
+    #[tokio::test]
+    async fn test_remote_rules_reload_with_etag() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_remote_rules_reload_with_etag");
+        
+        let mock_server = MockServer::start().await;
+        mount_remote_rules(&mock_server, "group_a", "\"v1\"").await;
+        let rules_url = format!("{}/rules.yaml", mock_server.uri());
+        
+        // 启动时即加载远程规则
+        let router = Router::new(remote_rules_config(&rules_url, 1).dns.routing, Some(Client::new())).await.unwrap();
+        assert_eq!(router.match_domain("www.remote.example.com").await, RouteDecision::UseGroup("group_a".to_string()));
+        
+        // 本地规则优先于远程规则
+        assert_eq!(router.match_domain("local.example.com").await, RouteDecision::UseGroup("group_b".to_string()));
+        
+        // 内容未变化时应携带 If-None-Match 并收到 304，规则保持不变
+        sleep(Duration::from_millis(1500)).await;
+        let requests = mock_server.received_requests().await.unwrap();
+        assert!(requests.len() >= 2, "Rules should be reloaded periodically");
+        assert!(requests[1..].iter().all(|r| r.headers.get("If-None-Match").map(|v| v.as_bytes()) == Some(b"\"v1\"")));
+        assert_eq!(router.match_domain("www.remote.example.com").await, RouteDecision::UseGroup("group_a".to_string()));
+        
+        // 内容更新后应替换规则
+        info!("Publishing updated remote rules");
+        mock_server.reset().await;
+        mount_remote_rules(&mock_server, "group_b", "\"v2\"").await;
+        sleep(Duration::from_millis(1500)).await;
+        assert_eq!(router.match_domain("www.remote.example.com").await, RouteDecision::UseGroup("group_b".to_string()));
+        
+        // 下载失败时保留当前规则
+        info!("Simulating remote rules outage");
+        mock_server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/rules.yaml"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+        sleep(Duration::from_millis(1500)).await;
+        assert_eq!(router.match_domain("www.remote.example.com").await, RouteDecision::UseGroup("group_b".to_string()));
+        
+        info!("Test completed: test_remote_rules_reload_with_etag");
+    }
+    
+    #[tokio::test]
+    async fn test_remote_rules_rejects_invalid_rule_set() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_remote_rules_rejects_invalid_rule_set");
+        
+        // 引用不存在的上游组时整个规则集被拒绝
+        let mock_server = MockServer::start().await;
+        mount_remote_rules(&mock_server, "missing_group", "\"v1\"").await;
+        let rules_url = format!("{}/rules.yaml", mock_server.uri());
+        
+        let router = Router::new(remote_rules_config(&rules_url, 0).dns.routing, Some(Client::new())).await.unwrap();
+        assert_eq!(router.match_domain("www.remote.example.com").await, RouteDecision::UseGlobal);
+        
+        info!("Test completed: test_remote_rules_rejects_invalid_rule_set");
+    }
+    
+    #[test]
+    fn test_remote_rules_config_validation() {
+        let config = remote_rules_config("https://rules.example.com/rules.yaml", 300);
+        assert!(config.test().is_ok());
+        
+        // 0 表示仅在启动时加载
+        let config = remote_rules_config("https://rules.example.com/rules.yaml", 0);
+        assert!(config.test().is_ok());
+        
+        // 间隔过短应被拒绝
+        let config = remote_rules_config("https://rules.example.com/rules.yaml", 5);
+        assert!(config.test().is_err());
+        
+        // 仅支持 http/https 地址
+        let config = remote_rules_config("ftp://rules.example.com/rules.yaml", 300);
+        assert!(config.test().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_routing_client_ip_match() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_routing_client_ip_match");
+        
+        // 网段文件：包含注释、单个地址与重叠网段
+        let temp_dir = TempDir::new().unwrap();
+        let ip_file = temp_dir.path().join("cn_ips.txt");
+        std::fs::write(&ip_file, "# CN ranges\n203.0.113.0/24\n203.0.113.128/25\n198.51.100.7\n2001:db8::/32 # v6\n").unwrap();
+        
+        // 网段 URL
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/office.txt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("192.0.2.0/28\n"))
+            .mount(&mock_server)
+            .await;
+        
+        let config_content = format!(r#"
+http_server:
+  listen_addr: "127.0.0.1:8053"
+dns_resolver:
+  upstream:
+    resolvers:
+      - address: "8.8.8.8:53"
+        protocol: udp
+  routing:
+    enabled: true
+    upstream_groups:
+      - name: "cn_group"
+        resolvers:
+          - address: "223.5.5.5:53"
+            protocol: udp
+      - name: "office_group"
+        resolvers:
+          - address: "10.0.0.53:53"
+            protocol: udp
+      - name: "name_group"
+        resolvers:
+          - address: "1.1.1.1:53"
+            protocol: udp
+    rules:
+      - match:
+          type: client_ip
+          values: ["10.8.0.0/16"]
+          path: "{}"
+        upstream_group: "cn_group"
+      - match:
+          type: client_ip
+          url: "{}/office.txt"
+        upstream_group: "office_group"
+      - match:
+          type: exact
+          values: ["named.example.com"]
+        upstream_group: "name_group"
+      - match:
+          type: wildcard
+          values: ["*.ads.example.com"]
+        upstream_group: "__blackhole__"
+    default_upstream_group: "name_group"
+"#, ip_file.display(), mock_server.uri());
+        let config: ServerConfig = serde_yaml::from_str(&config_content).unwrap();
+        assert!(config.test().is_ok());
+        
+        let router = Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap();
+        let cn_client = "203.0.113.200".parse().ok();
+        let other_client = "8.8.4.4".parse().ok();
+        
+        // 内联网段、文件网段（含单个地址与 IPv6）与 URL 网段
+        for ip in ["10.8.1.1", "203.0.113.5", "198.51.100.7", "2001:db8::1", "::ffff:203.0.113.9"] {
+            assert_eq!(
+                router.match_query("www.example.org", ip.parse().ok()).await,
+                RouteDecision::UseGroup("cn_group".to_string()),
+                "client {} should use cn_group", ip
+            );
+        }
+        assert_eq!(
+            router.match_query("www.example.org", "192.0.2.15".parse().ok()).await,
+            RouteDecision::UseGroup("office_group".to_string())
+        );
+        
+        // 网段之外的客户端回退到默认上游组
+        assert_eq!(router.match_query("www.example.org", other_client).await, RouteDecision::UseGroup("name_group".to_string()));
+        assert_eq!(router.match_query("www.example.org", "198.51.100.8".parse().ok()).await, RouteDecision::UseGroup("name_group".to_string()));
+        assert_eq!(router.match_query("www.example.org", "192.0.2.16".parse().ok()).await, RouteDecision::UseGroup("name_group".to_string()));
+        
+        // 优先级：域名规则先于客户端 IP 规则
+        assert_eq!(router.match_query("named.example.com", cn_client).await, RouteDecision::UseGroup("name_group".to_string()));
+        assert_eq!(router.match_query("tracker.ads.example.com", cn_client).await, RouteDecision::Blackhole);
+        
+        // 不带客户端 IP 的匹配只考虑域名规则
+        assert_eq!(router.match_domain("www.example.org").await, RouteDecision::UseGroup("name_group".to_string()));
+        
+        info!("Test completed: test_routing_client_ip_match");
+    }
+    
+    #[test]
+    fn test_routing_client_ip_config_validation() {
+        let config_with_rule = |match_body: &str| -> ServerConfig {
+            serde_yaml::from_str(&format!(r#"
+http_server:
+  listen_addr: "127.0.0.1:8053"
+dns_resolver:
+  upstream:
+    resolvers:
+      - address: "8.8.8.8:53"
+        protocol: udp
+  routing:
+    enabled: true
+    upstream_groups:
+      - name: "cn_group"
+        resolvers:
+          - address: "223.5.5.5:53"
+            protocol: udp
+    rules:
+      - match:
+          type: client_ip
+{}
+        upstream_group: "cn_group"
+"#, match_body)).unwrap()
+        };
+        
+        assert!(config_with_rule("          values: [\"10.0.0.0/8\", \"2001:db8::1\"]").test().is_ok());
+        
+        // 无效网段、缺少网段来源、文件不存在
+        assert!(config_with_rule("          values: [\"10.0.0.0/33\"]").test().is_err());
+        assert!(config_with_rule("          values: [\"not-an-ip\"]").test().is_err());
+        assert!(config_with_rule("          periodic:\n            enabled: false\n            interval_secs: 3600").test().is_err());
+        assert!(config_with_rule("          path: \"/nonexistent/ips.txt\"").test().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_routing_upstream_groups_fallthrough() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_routing_upstream_groups_fallthrough");
+        
+        let config_with_target = |target: &str| -> ServerConfig {
+            serde_yaml::from_str(&format!(r#"
+http_server:
+  listen_addr: "127.0.0.1:8053"
+dns_resolver:
+  upstream:
+    resolvers:
+      - address: "8.8.8.8:53"
+        protocol: udp
+  routing:
+    enabled: true
+    upstream_groups:
+      - name: "primary_group"
+        resolvers:
+          - address: "1.1.1.1:53"
+            protocol: udp
+      - name: "fallback_group"
+        resolvers:
+          - address: "9.9.9.9:53"
+            protocol: udp
+    rules:
+      - match:
+          type: exact
+          values: ["example.com"]
+{}
+"#, target)).unwrap()
+        };
+        
+        // 规则按顺序列出多个上游组
+        let config = config_with_target("        upstream_groups: [\"primary_group\", \"fallback_group\"]");
+        assert!(config.test().is_ok());
+        let router = Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap();
+        let decision = router.match_domain("example.com").await;
+        assert_eq!(decision, RouteDecision::UseGroups(vec!["primary_group".to_string(), "fallback_group".to_string()]));
+        
+        // 单个组的列表等同于 upstream_group
+        let config = config_with_target("        upstream_groups: [\"fallback_group\"]");
+        assert!(config.test().is_ok());
+        let router = Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap();
+        assert_eq!(router.match_domain("example.com").await, RouteDecision::UseGroup("fallback_group".to_string()));
+        
+        // 未知组、重复组、黑洞、同时设置两种写法、未设置目标组均无效
+        assert!(config_with_target("        upstream_groups: [\"primary_group\", \"missing_group\"]").test().is_err());
+        assert!(config_with_target("        upstream_groups: [\"primary_group\", \"primary_group\"]").test().is_err());
+        assert!(config_with_target("        upstream_groups: [\"primary_group\", \"__blackhole__\"]").test().is_err());
+        assert!(config_with_target("        upstream_group: \"primary_group\"\n        upstream_groups: [\"fallback_group\"]").test().is_err());
+        assert!(config_with_target("        upstream_groups: []").test().is_err());
+        
+        // 回退链长度有上限
+        let mut config = config_with_target("        upstream_groups: [\"primary_group\", \"fallback_group\"]");
+        for i in 0..3 {
+            let mut group = config.dns.routing.upstream_groups[0].clone();
+            group.name = format!("extra_group_{}", i);
+            config.dns.routing.rules[0].upstream_groups.push(group.name.clone());
+            config.dns.routing.upstream_groups.push(group);
+        }
+        assert!(config.test().is_err());
+        
+        info!("Test completed: test_routing_upstream_groups_fallthrough");
+    }
+    
+    // gzip 压缩文本
+    fn gzip(content: &str) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+    
+    // 创建指向 URL 规则列表的黑洞路由器
+    async fn create_url_rule_router(url: &str) -> Router {
+        let config_content = format!(r#"
+http_server:
+  listen_addr: "127.0.0.1:8053"
+dns_resolver:
+  upstream:
+    resolvers:
+      - address: "8.8.8.8:53"
+        protocol: udp
+  routing:
+    enabled: true
+    rules:
+      - match:
+          type: url
+          url: "{}"
+          periodic:
+            enabled: true
+            interval_secs: 30
+        upstream_group: "__blackhole__"
+"#, url);
+        let (_temp_dir, config_path) = create_temp_config_file(&config_content);
+        let config = ServerConfig::from_file(&config_path).unwrap();
+        let router = Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap();
+        
+        // 等待URL规则加载完成
+        sleep(Duration::from_millis(500)).await;
+        router
+    }
+    
+    #[tokio::test]
+    async fn test_routing_gzip_url_rules() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_routing_gzip_url_rules");
+        
+        let domains_content = "ads.example.com\nwildcard:*.tracker.test\nregex:evil\\d+\\.example\\.biz\n";
+        let mock_server = MockServer::start().await;
+        
+        // 通过 .gz 后缀识别的压缩列表
+        Mock::given(method("GET"))
+            .and(path("/domains.txt.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(gzip(domains_content)))
+            .mount(&mock_server)
+            .await;
+        
+        // 通过 Content-Encoding 识别的压缩列表
+        Mock::given(method("GET"))
+            .and(path("/domains.txt"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("Content-Encoding", "gzip")
+                .set_body_bytes(gzip(domains_content)))
+            .mount(&mock_server)
+            .await;
+        
+        // 声明为 gzip 但内容损坏的列表
+        Mock::given(method("GET"))
+            .and(path("/broken.txt.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ads.example.com\n"))
+            .mount(&mock_server)
+            .await;
+        
+        for list_path in ["/domains.txt.gz", "/domains.txt"] {
+            let router = create_url_rule_router(&format!("{}{}", mock_server.uri(), list_path)).await;
+            for domain in ["ads.example.com", "a.tracker.test", "evil42.example.biz"] {
+                assert_eq!(router.match_domain(domain).await, RouteDecision::Blackhole,
+                           "{} should be blackholed by {}", domain, list_path);
+            }
+            assert_eq!(router.match_domain("example.com").await, RouteDecision::UseGlobal);
+        }
+        
+        // 解压失败时不加载任何规则，路由器仍可正常使用
+        let router = create_url_rule_router(&format!("{}/broken.txt.gz", mock_server.uri())).await;
+        assert_eq!(router.match_domain("ads.example.com").await, RouteDecision::UseGlobal);
+        
+        info!("Test completed: test_routing_gzip_url_rules");
+    }
+    
+    #[tokio::test]
+    async fn test_routing_normalizes_rule_domains() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_routing_normalizes_rule_domains");
+        
+        let config_with_rules = |rules: &str| -> ServerConfig {
+            serde_yaml::from_str(&format!(r#"
+http_server:
+  listen_addr: "127.0.0.1:8053"
+dns_resolver:
+  upstream:
+    resolvers:
+      - address: "8.8.8.8:53"
+        protocol: udp
+  routing:
+    enabled: true
+    upstream_groups:
+      - name: "idn_group"
+        resolvers:
+          - address: "1.1.1.1:53"
+            protocol: udp
+    rules:
+{}
+"#, rules)).unwrap()
+        };
+        
+        // 大写、末尾的点与国际化域名在加载时被规范化
+        let config = config_with_rules(r#"
+      - match:
+          type: exact
+          values: ["例え.jp", "Example.COM.", "_dmarc.example.org"]
+        upstream_group: "idn_group"
+      - match:
+          type: wildcard
+          values: ["*.テスト.jp.", "*.Example.NET"]
+        upstream_group: "idn_group"
+"#);
+        assert!(config.test().is_ok());
+        let router = Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap();
+        let idn_group = RouteDecision::UseGroup("idn_group".to_string());
+        
+        // 查询中的国际化域名以 punycode 形式出现
+        assert_eq!(router.match_domain("xn--r8jz45g.jp").await, idn_group);
+        assert_eq!(router.match_domain("xn--r8jz45g.jp.").await, idn_group);
+        assert_eq!(router.match_domain("example.com").await, idn_group);
+        assert_eq!(router.match_domain("_dmarc.example.org").await, idn_group);
+        assert_eq!(router.match_domain("www.xn--zckzah.jp").await, idn_group);
+        assert_eq!(router.match_domain("api.example.net").await, idn_group);
+        assert_eq!(router.match_domain("other.jp").await, RouteDecision::UseGlobal);
+        
+        // 无效的域名与正则表达式在加载时报错，并指出出错的规则
+        let invalid_rules = [
+            ("exact", r#"["example.com", "bad..example.com"]"#, "Rule [2]"),
+            ("exact", r#"["  "]"#, "Rule [2]"),
+            ("wildcard", r#"["*.exa mple.com"]"#, "Rule [2]"),
+            ("regex", r#"["(unclosed"]"#, "Rule [2]"),
+        ];
+        for (type_, values, expected) in invalid_rules {
+            let config = config_with_rules(&format!(r#"
+      - match:
+          type: exact
+          values: ["ok.example.com"]
+        upstream_group: "idn_group"
+      - match:
+          type: {}
+          values: {}
+        upstream_group: "idn_group"
+"#, type_, values));
+            let config_error = config.test().unwrap_err().to_string();
+            assert!(config_error.contains(expected), "unexpected config error: {}", config_error);
+            let router_error = Router::new(config.dns.routing.clone(), Some(Client::new())).await.err().unwrap().to_string();
+            assert!(router_error.contains(expected), "unexpected router error: {}", router_error);
+        }
+        
+        info!("Test completed: test_routing_normalizes_rule_domains");
+    }
+}
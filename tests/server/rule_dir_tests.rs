@@ -0,0 +1,168 @@
+// tests/server/rule_dir_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+    use tracing::info;
+    use oxide_wdns::server::config::{RoutingConfig, ServerConfig};
+    use oxide_wdns::server::routing::{RouteDecision, Router};
+
+    // 目录规则：ads 文件映射到黑洞，china 文件映射到 domestic，其他文件使用规则的目标组 clean
+    fn dir_rule_config(dir: &Path) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "https://dns.example.com/dns-query"
+                protocol: doh
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "domestic"
+                resolvers:
+                  - address: "https://domestic.example.com/dns-query"
+                    protocol: doh
+              - name: "clean"
+                resolvers:
+                  - address: "https://clean.example.com/dns-query"
+                    protocol: doh
+            rules:
+              - match:
+                  type: dir
+                  source:
+                    dir: "{}"
+                    format: domain_list
+                    groups:
+                      ads: "__blackhole__"
+                      china: "domestic"
+                upstream_group: "clean"
+        "#, dir.display());
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    async fn dir_router(dir: &Path) -> Router {
+        let routing: RoutingConfig = dir_rule_config(dir).dns.routing;
+        Router::new(routing, None).await.unwrap()
+    }
+
+    fn use_group(group: &str) -> RouteDecision {
+        RouteDecision::UseGroup(group.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_rule_dir_merges_files_into_groups() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_rule_dir_merges_files_into_groups");
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("ads.txt"), "ads.example.com\nwildcard:*.tracker.net\n").unwrap();
+        fs::write(dir.path().join("china.txt"), "# 国内域名\nbaidu.com\nregex:^.*\\.cn$\n").unwrap();
+        fs::write(dir.path().join("other.list"), "github.com\n").unwrap();
+        // 隐藏文件与子目录被忽略
+        fs::write(dir.path().join(".ignored.txt"), "hidden.example.com\n").unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("nested").join("china.txt"), "nested.example.com\n").unwrap();
+
+        let router = dir_router(dir.path()).await;
+        assert_eq!(router.match_domain("ads.example.com").await, RouteDecision::Blackhole);
+        assert_eq!(router.match_domain("a.tracker.net").await, RouteDecision::Blackhole);
+        assert_eq!(router.match_domain("baidu.com.").await, use_group("domestic"));
+        assert_eq!(router.match_domain("www.gov.cn").await, use_group("domestic"));
+        assert_eq!(router.match_domain("GitHub.com").await, use_group("clean"));
+        assert_eq!(router.match_domain("hidden.example.com").await, RouteDecision::UseGlobal);
+        assert_eq!(router.match_domain("nested.example.com").await, RouteDecision::UseGlobal);
+
+        info!("Test completed: test_rule_dir_merges_files_into_groups");
+    }
+
+    #[tokio::test]
+    async fn test_rule_dir_skips_invalid_files() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_rule_dir_skips_invalid_files");
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("china.txt"), "baidu.com\n").unwrap();
+        // 非法正则与非 UTF-8 内容只会跳过对应文件，不影响其他文件
+        fs::write(dir.path().join("broken.txt"), "good.example.com\nregex:([\n").unwrap();
+        fs::write(dir.path().join("binary.txt"), [0xff, 0xfe, 0x00, 0x0a]).unwrap();
+
+        let router = dir_router(dir.path()).await;
+        assert_eq!(router.match_domain("baidu.com").await, use_group("domestic"));
+        assert_eq!(router.match_domain("good.example.com").await, RouteDecision::UseGlobal);
+
+        // 启动时目录不存在则加载失败
+        let missing = dir.path().join("missing");
+        let routing = dir_rule_config(&missing).dns.routing;
+        assert!(Router::new(routing, None).await.is_err());
+
+        info!("Test completed: test_rule_dir_skips_invalid_files");
+    }
+
+    #[tokio::test]
+    async fn test_rule_dir_reload_applies_changes() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_rule_dir_reload_applies_changes");
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("china.txt"), "baidu.com\n").unwrap();
+        fs::write(dir.path().join("old.txt"), "old.example.com\n").unwrap();
+
+        let router = dir_router(dir.path()).await;
+        assert_eq!(router.match_domain("old.example.com").await, use_group("clean"));
+
+        // 新增、删除与修改文件后重新扫描
+        fs::write(dir.path().join("china.txt"), "qq.com\n").unwrap();
+        fs::remove_file(dir.path().join("old.txt")).unwrap();
+        fs::write(dir.path().join("ads.txt"), "ads.example.com\n").unwrap();
+        router.reload_rule_dirs().await;
+
+        assert_eq!(router.match_domain("baidu.com").await, RouteDecision::UseGlobal);
+        assert_eq!(router.match_domain("qq.com").await, use_group("domestic"));
+        assert_eq!(router.match_domain("old.example.com").await, RouteDecision::UseGlobal);
+        assert_eq!(router.match_domain("ads.example.com").await, RouteDecision::Blackhole);
+
+        // 目录无法读取时保留当前规则
+        let path = dir.path().to_path_buf();
+        drop(dir);
+        assert!(!path.exists());
+        router.reload_rule_dirs().await;
+        assert_eq!(router.match_domain("qq.com").await, use_group("domestic"));
+
+        info!("Test completed: test_rule_dir_reload_applies_changes");
+    }
+
+    #[test]
+    fn test_rule_dir_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_rule_dir_config_validation");
+
+        let dir = TempDir::new().unwrap();
+        let config = dir_rule_config(dir.path());
+        assert!(config.test().is_ok());
+
+        // 目录必须存在
+        let config = dir_rule_config(&dir.path().join("missing"));
+        assert!(config.test().is_err());
+
+        // 映射的上游组必须存在
+        let mut config = dir_rule_config(dir.path());
+        config.dns.routing.rules[0].match_.source.as_mut().unwrap()
+            .groups.insert("misc".to_string(), "missing_group".to_string());
+        assert!(config.test().is_err());
+
+        // dir 类型必须配置 source
+        let mut config = dir_rule_config(dir.path());
+        config.dns.routing.rules[0].match_.source = None;
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_rule_dir_config_validation");
+    }
+}
@@ -19,10 +19,8 @@ mod tests {
     use futures::future;
     
     // 项目内部导入
-    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_MESSAGE, CONTENT_TYPE_DNS_JSON};
-    use oxide_wdns::server::cache::DnsCache;
-    use oxide_wdns::server::upstream::UpstreamManager;
-    use oxide_wdns::server::routing::Router;
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_MESSAGE, CONTENT_TYPE_DNS_JSON, DOH_FORMAT_WIRE};
+    use oxide_wdns::server::metrics::METRICS;
     use oxide_wdns::server::doh_handler::ServerState;
     use oxide_wdns::server::config::ServerConfig;
     
@@ -30,6 +28,7 @@ mod tests {
     
     // 导入公共测试工具
     use crate::server::mock_http_server::{find_free_port, create_test_query, create_test_response};
+    use crate::server::test_helpers::{extract_ip_addresses, server_state};
     
     // === 辅助函数 ===
 
@@ -72,17 +71,7 @@ mod tests {
     // 创建服务器状态
     async fn create_server_state(port: u16, rate_limit_enabled: bool, cache_enabled: bool) -> ServerState {
         let config = build_test_config(port, rate_limit_enabled, cache_enabled);
-        let router = Arc::new(Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap());
-        let http_client = Client::new();
-        let upstream = Arc::new(UpstreamManager::new(Arc::new(config.clone()), http_client).await.unwrap());
-        let cache = Arc::new(DnsCache::new(config.dns.cache.clone()));
-        
-        ServerState {
-            config, 
-            upstream, 
-            cache, 
-            router,
-        }
+        server_state(config).await
     }
 
     // 创建一个DNS查询Message
@@ -98,7 +87,7 @@ mod tests {
 
     // 在后台启动测试服务器
     async fn start_test_server(server_state: ServerState) -> (String, oneshot::Sender<()>) {
-        let addr_str = server_state.config.http.listen_addr;
+        let addr_str = server_state.config.http.listen_addr.primary();
         let addr = format!("http://{}", addr_str);
         
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
@@ -201,21 +190,15 @@ mod tests {
             oxide_wdns::server::config::ResolverConfig {
                 address: format!("{}/dns-query", mock_upstream.uri()),
                 protocol: oxide_wdns::server::config::ResolverProtocol::Doh,
+                path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                ca_cert_path: None,
             }
         ];
         
         // 3. 创建服务器状态与组件
-        let router = Arc::new(Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap());
-        let http_client = Client::new();
-        let cache = Arc::new(DnsCache::new(config.dns.cache.clone()));
-        let upstream = Arc::new(UpstreamManager::new(Arc::new(config.clone()), http_client).await.unwrap());
-        
-        let server_state = ServerState {
-            config,
-            upstream,
-            cache,
-            router,
-        };
+        let server_state = server_state(config).await;
         
         // 4. 启动测试服务器
         let (server_addr, shutdown_tx) = start_test_server(server_state).await;
@@ -359,17 +342,7 @@ mod tests {
         
         // 3. 创建服务器状态并启动服务器
         info!("Creating server state with DNS routing configuration...");
-        let router = Arc::new(Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap());
-        let http_client = Client::new();
-        let cache = Arc::new(DnsCache::new(config.dns.cache.clone()));
-        let upstream = Arc::new(UpstreamManager::new(Arc::new(config.clone()), http_client).await.unwrap());
-        
-        let server_state = ServerState {
-            config,
-            upstream,
-            cache,
-            router,
-        };
+        let server_state = server_state(config).await;
         
         // 启动服务器
         info!("Starting test server with DNS routing...");
@@ -430,20 +403,6 @@ mod tests {
         Message::from_vec(&response_bytes).expect("Failed to parse DNS response")
     }
     
-    // 辅助函数：从DNS响应中提取IP地址
-    fn extract_ip_addresses(message: &Message) -> Vec<String> {
-        message.answers()
-            .iter()
-            .filter_map(|answer| {
-                if let Some(hickory_proto::rr::RData::A(ipv4)) = answer.data() {
-                    Some(ipv4.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect()
-    }
-
     // === 其余原始测试保持不变 ===
     
     #[tokio::test]
@@ -741,12 +700,14 @@ mod tests {
         // 短暂等待，确保缓存有机会生效（理论上不需要，但增加稳定性）
         tokio_sleep(Duration::from_millis(100)).await;
 
-        // 5. 立即再次发送相同的DoH查询
-        info!("Sending second (cached) DoH request...");
+        // 5. 立即再次发送相同的DoH查询，但使用不同的消息ID（每个客户端自行选择ID）
+        info!("Sending second (cached) DoH request with a different message ID...");
+        let mut second_query = query.clone();
+        second_query.set_id(query.id().wrapping_add(1));
         let second_response = client
             .post(format!("{}/dns-query", server_addr))
             .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
-            .body(query_bytes)
+            .body(second_query.to_vec().unwrap())
             .send()
             .await
             .expect("Second DoH request failed");
@@ -763,18 +724,21 @@ mod tests {
             .expect("Failed to read second response body");
         info!("Received second response body ({} bytes)", second_body.len());
 
-        // 确保两个响应的消息ID相同（因为缓存会保留原始消息）
         let second_dns_message =
             Message::from_vec(&second_body).expect("Failed to parse second DNS response");
         info!("Parsed second DNS response, ID: {}", second_dns_message.id());
 
-        // 比较消息ID，如果相同则表明是缓存的响应
+        // 每个响应都回显当前请求的消息ID，而不是首个请求方的ID
+        assert_eq!(first_dns_message.id(), query.id(), "First response should echo the first request ID");
         assert_eq!(
-            first_dns_message.id(),
             second_dns_message.id(),
-            "Cache should return the same DNS message ID"
+            second_query.id(),
+            "Cached response should echo the requesting client's message ID"
         );
-        info!("Verified that response was served from cache (matching IDs).");
+        
+        // 缓存的响应内容与首次响应一致
+        assert_eq!(first_dns_message.answers(), second_dns_message.answers());
+        info!("Verified that the cached response carries the requesting client's ID.");
 
         // 6. 清理：关闭服务器
         info!("Shutting down server...");
@@ -829,18 +793,224 @@ mod tests {
         // 8. 断言：收到 200 OK 响应
         assert_eq!(status, StatusCode::OK);
 
-        // 9. 断言：响应体是有效的 DNS 消息
+        // 9. 断言：成功的 GET 响应带有 max-age
+        let cache_control = response.headers().get("cache-control").and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+        info!("DoH GET response Cache-Control: {}", cache_control);
+        assert!(cache_control.starts_with("max-age="), "unexpected Cache-Control: {}", cache_control);
+
+        // 10. 断言：响应体是有效的 DNS 消息
         let response_bytes = response.bytes().await.expect("Failed to read response body");
         info!("Received response body ({} bytes)", response_bytes.len());
         let dns_response = Message::from_vec(&response_bytes).expect("Invalid DNS message format in response");
         info!("Successfully parsed DNS response from GET request");
         assert_eq!(dns_response.message_type(), MessageType::Response);
+        let min_ttl = dns_response.answers().iter().map(|r| r.ttl()).min().unwrap_or(0);
+        assert_eq!(cache_control, format!("max-age={}", min_ttl));
 
-        // 10. 清理：关闭服务器
+        // 11. 清理：关闭服务器
         info!("Shutting down server...");
         let _ = shutdown_tx.send(());
         info!("Test completed: test_server_doh_get_request");
     }
+
+    // 创建使用模拟 DoH 上游并启用缓存的服务器状态
+    async fn create_mock_upstream_state(port: u16, upstream_uri: &str, emit_cache_headers: bool) -> ServerState {
+        let mut config = build_test_config(port, false, true);
+        config.dns.upstream.resolvers = serde_yaml::from_str(&format!(
+            "[{{ address: \"{}/dns-query\", protocol: doh }}]", upstream_uri
+        )).unwrap();
+        config.http.emit_cache_headers = emit_cache_headers;
+
+        server_state(config).await
+    }
+
+    #[tokio::test]
+    async fn test_server_doh_cache_headers() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_server_doh_cache_headers");
+
+        // 模拟上游返回 TTL 为 300 的 A 记录；另一个上游始终返回 503
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let response = create_test_response(&query, std::net::Ipv4Addr::new(192, 0, 2, 70));
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(&mock_server)
+            .await;
+        let failing_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&failing_server)
+            .await;
+
+        let port = find_free_port().await;
+        let (server_addr, shutdown_tx) = start_test_server(create_mock_upstream_state(port, &mock_server.uri(), true).await).await;
+        let client = Client::new();
+        let query = create_test_query("headers.example.com", RecordType::A);
+        let get_url = format!("{}/dns-query?dns={}", server_addr, BASE64_ENGINE.encode(query.to_vec().unwrap()));
+
+        // 首次查询来自上游：max-age 等于最小 TTL
+        let response = client.get(&get_url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("cache-control").unwrap(), "max-age=300");
+        assert!(response.headers().get("age").is_none());
+
+        // 再次查询命中 DNS 缓存：max-age 按条目存在时间递减
+        tokio_sleep(Duration::from_millis(1100)).await;
+        let response = client.get(&get_url).send().await.unwrap();
+        let cache_control = response.headers().get("cache-control").unwrap().to_str().unwrap().to_string();
+        let max_age: u64 = cache_control.strip_prefix("max-age=").unwrap().parse().unwrap();
+        assert!((295..300).contains(&max_age), "unexpected Cache-Control: {}", cache_control);
+        assert!(response.headers().get("age").is_none());
+
+        // POST 响应不可被 HTTP 缓存复用
+        let response = client.post(format!("{}/dns-query", server_addr))
+            .header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+            .body(query.to_vec().unwrap())
+            .send().await.unwrap();
+        assert_eq!(response.headers().get("cache-control").unwrap(), "max-age=0, no-store");
+        assert!(response.headers().get("age").is_none());
+        let _ = shutdown_tx.send(());
+
+        // SERVFAIL 等错误响应不可缓存
+        let port = find_free_port().await;
+        let (server_addr, shutdown_tx) = start_test_server(create_mock_upstream_state(port, &failing_server.uri(), true).await).await;
+        let response = client.get(format!("{}/dns-query?dns={}", server_addr, BASE64_ENGINE.encode(query.to_vec().unwrap())))
+            .send().await.unwrap();
+        assert_eq!(response.headers().get("cache-control").unwrap(), "max-age=0, no-store");
+        let dns_response = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+        assert_eq!(dns_response.response_code(), hickory_proto::op::ResponseCode::ServFail);
+        let _ = shutdown_tx.send(());
+
+        // 关闭后不添加缓存头
+        let port = find_free_port().await;
+        let (server_addr, shutdown_tx) = start_test_server(create_mock_upstream_state(port, &mock_server.uri(), false).await).await;
+        let response = client.get(format!("{}/dns-query?dns={}", server_addr, BASE64_ENGINE.encode(query.to_vec().unwrap())))
+            .send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("cache-control").is_none());
+        let _ = shutdown_tx.send(());
+
+        info!("Test completed: test_server_doh_cache_headers");
+    }
+
+    #[tokio::test]
+    async fn test_server_doh_max_age_matches_min_answer_ttl() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_server_doh_max_age_matches_min_answer_ttl");
+
+        // 模拟上游：A 查询返回 TTL 分别为 300 与 120 的两条记录，AAAA 查询返回无记录，其他域名返回 NXDOMAIN
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let question = query.queries()[0].clone();
+                let mut response = create_test_response(&query, std::net::Ipv4Addr::new(192, 0, 2, 71));
+                if question.name().to_ascii() != "ttl.example.com." {
+                    response.set_response_code(hickory_proto::op::ResponseCode::NXDomain);
+                } else if question.query_type() == RecordType::A {
+                    let mut second = response.answers()[0].clone();
+                    second.set_ttl(120).set_data(Some(hickory_proto::rr::RData::A(
+                        hickory_proto::rr::rdata::A(std::net::Ipv4Addr::new(192, 0, 2, 72))
+                    )));
+                    response.add_answer(second);
+                }
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(&mock_server)
+            .await;
+
+        let port = find_free_port().await;
+        let (server_addr, shutdown_tx) = start_test_server(create_mock_upstream_state(port, &mock_server.uri(), true).await).await;
+        let client = Client::new();
+        let get = |domain: &str, record_type: RecordType| {
+            let query = create_test_query(domain, record_type);
+            client.get(format!("{}/dns-query?dns={}", server_addr, BASE64_ENGINE.encode(query.to_vec().unwrap()))).send()
+        };
+
+        // max-age 等于应答记录的最小 TTL
+        let response = get("ttl.example.com", RecordType::A).await.unwrap();
+        let cache_control = response.headers().get("cache-control").unwrap().to_str().unwrap().to_string();
+        let message = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+        let min_ttl = message.answers().iter().map(|record| record.ttl()).min().unwrap();
+        assert_eq!(message.answers().len(), 2);
+        assert_eq!(min_ttl, 120);
+        assert_eq!(cache_control, format!("max-age={}", min_ttl));
+
+        // 负响应（无记录与 NXDOMAIN）不可缓存
+        let response = get("ttl.example.com", RecordType::AAAA).await.unwrap();
+        assert_eq!(response.headers().get("cache-control").unwrap(), "max-age=0, no-store");
+        let response = get("missing.example.com", RecordType::A).await.unwrap();
+        assert_eq!(response.headers().get("cache-control").unwrap(), "max-age=0, no-store");
+
+        let _ = shutdown_tx.send(());
+        info!("Test completed: test_server_doh_max_age_matches_min_answer_ttl");
+    }
+
+    #[tokio::test]
+    async fn test_server_dns_size_histograms() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_server_dns_size_histograms");
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let response = create_test_response(&query, std::net::Ipv4Addr::new(192, 0, 2, 71));
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(&mock_server)
+            .await;
+
+        // 设置较小的告警阈值，确保大响应告警路径被执行
+        let port = find_free_port().await;
+        let mut state = create_mock_upstream_state(port, &mock_server.uri(), true).await;
+        state.config.http.alert_on_response_larger_than_bytes = Some(16);
+        let (server_addr, shutdown_tx) = start_test_server(state).await;
+
+        let request_hist = METRICS.dns_request_size_bytes().with_label_values(&[DOH_FORMAT_WIRE]);
+        let response_hist = METRICS.dns_response_size_bytes().with_label_values(&[DOH_FORMAT_WIRE]);
+        let requests_before = request_hist.get_sample_count();
+        let responses_before = response_hist.get_sample_count();
+        let response_bytes_before = response_hist.get_sample_sum();
+
+        let query = create_test_query("size.example.com", RecordType::A);
+        let query_bytes = query.to_vec().unwrap();
+        let response = Client::new().post(format!("{}/dns-query", server_addr))
+            .header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+            .body(query_bytes.clone())
+            .send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.bytes().await.unwrap();
+
+        // 查询与响应大小均被记录到直方图
+        assert!(request_hist.get_sample_count() > requests_before);
+        assert!(response_hist.get_sample_count() > responses_before);
+        assert!(response_hist.get_sample_sum() - response_bytes_before >= body.len() as f64);
+        let _ = shutdown_tx.send(());
+
+        // 阈值为 0 的配置无效
+        let mut config = build_test_config(port, false, true);
+        assert!(config.test().is_ok());
+        config.http.alert_on_response_larger_than_bytes = Some(0);
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_server_dns_size_histograms");
+    }
     
     #[tokio::test]
     async fn test_server_rejects_invalid_content_type() {
@@ -867,14 +1037,11 @@ mod tests {
         let client = Client::new();
 
         // 6. 发送带有错误 Content-Type 的 POST 请求
-        info!(
-            "Sending DoH POST request with invalid Content-Type: {}",
-            CONTENT_TYPE_DNS_JSON
-        );
+        info!("Sending DoH POST request with invalid Content-Type: text/plain");
         let response = client
             .post(format!("{}/dns-query", server_addr))
-            .header("Content-Type", HeaderValue::from_static(CONTENT_TYPE_DNS_JSON)) // 修复 Content-Type 设置
-            .body(fake_body)
+            .header("Content-Type", HeaderValue::from_static("text/plain"))
+            .body(fake_body.clone())
             .send()
             .await
             .expect("Request with invalid content type failed");
@@ -884,6 +1051,21 @@ mod tests {
         // 7. 断言：收到 415 Unsupported Media Type 响应
         assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
 
+        // application/dns-json 现已被接受，格式错误的 JSON 请求体应返回 400
+        info!(
+            "Sending DoH POST request with malformed {} body",
+            CONTENT_TYPE_DNS_JSON
+        );
+        let response = client
+            .post(format!("{}/dns-query", server_addr))
+            .header("Content-Type", HeaderValue::from_static(CONTENT_TYPE_DNS_JSON))
+            .body(fake_body)
+            .send()
+            .await
+            .expect("Request with malformed JSON body failed");
+        info!("Response status for malformed JSON body: {}", response.status());
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
         // 8. 清理：关闭服务器
         info!("Shutting down server...");
         let _ = shutdown_tx.send(());
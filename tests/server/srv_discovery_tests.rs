@@ -0,0 +1,161 @@
+// tests/server/srv_discovery_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use hickory_proto::op::{Message, MessageType, ResponseCode};
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use hickory_proto::rr::rdata::SRV;
+    use reqwest::Client;
+    use tracing::info;
+    use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::upstream::{order_srv_records, UpstreamManager};
+
+    const SRV_NAME: &str = "_dns-query._tcp.example.com.";
+
+    // 创建 SRV 记录数据
+    fn srv(priority: u16, weight: u16, port: u16, target: &str) -> SRV {
+        SRV::new(priority, weight, port, Name::from_ascii(target).unwrap())
+    }
+
+    // 启动返回当前 SRV 记录集的模拟 DoH 引导上游
+    async fn setup_srv_doh_server(records: Arc<Mutex<Vec<SRV>>>) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/dns-query"))
+            .respond_with(move |request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let mut response = Message::new();
+                response.set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .set_response_code(ResponseCode::NoError)
+                    .add_queries(query.queries().to_vec());
+                for record in records.lock().unwrap().iter() {
+                    let name = query.queries()[0].name().clone();
+                    response.add_answer(Record::from_rdata(name, 300, RData::SRV(record.clone())));
+                }
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    // 创建启用 SRV 发现的服务器配置
+    fn srv_server_config(bootstrap: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}"
+                protocol: doh
+            query_timeout: 3
+            discover_via_srv: true
+            srv_name: "{}"
+        "#, bootstrap, SRV_NAME);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    #[test]
+    fn test_order_srv_records_by_priority_and_weight() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_order_srv_records_by_priority_and_weight");
+
+        let records = vec![
+            srv(20, 0, 443, "backup.example.com."),
+            srv(10, 1, 443, "light.example.com."),
+            srv(10, 99, 443, "heavy.example.com."),
+            srv(5, 0, 8443, "primary.example.com."),
+        ];
+
+        // 优先级总是升序，同一优先级内权重高的记录更常排在前面
+        let mut heavy_first = 0;
+        for _ in 0..200 {
+            let ordered = order_srv_records(records.clone());
+            let priorities = ordered.iter().map(|srv| srv.priority()).collect::<Vec<_>>();
+            assert_eq!(priorities, vec![5, 10, 10, 20]);
+            if ordered[1].target().to_ascii() == "heavy.example.com." {
+                heavy_first += 1;
+            }
+        }
+        assert!(heavy_first > 150, "heavy record ordered first {} times", heavy_first);
+
+        info!("Test completed: test_order_srv_records_by_priority_and_weight");
+    }
+
+    #[tokio::test]
+    async fn test_srv_discovery_adds_and_withdraws_upstreams() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_srv_discovery_adds_and_withdraws_upstreams");
+
+        let records = Arc::new(Mutex::new(vec![
+            srv(20, 10, 443, "doh2.example.com."),
+            srv(10, 10, 8443, "doh1.example.com."),
+            // 目标为 "." 的记录表示服务不可用，被忽略
+            srv(30, 10, 443, "."),
+        ]));
+        let mock_server = setup_srv_doh_server(records.clone()).await;
+        let config = srv_server_config(&format!("{}/dns-query", mock_server.uri()));
+        let upstream = Arc::new(UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap());
+
+        // 启动时同步发现，按优先级排序
+        upstream.start_srv_discovery().await;
+        assert_eq!(upstream.discovered_resolvers(), vec![
+            "https://doh1.example.com:8443/dns-query".to_string(),
+            "https://doh2.example.com:443/dns-query".to_string(),
+        ]);
+
+        // SRV 查询通过引导上游发出
+        let requests = mock_server.received_requests().await.unwrap();
+        let query = Message::from_vec(&requests[0].body).unwrap();
+        assert_eq!(query.queries()[0].query_type(), RecordType::SRV);
+        assert_eq!(query.queries()[0].name().to_ascii(), SRV_NAME);
+
+        // 撤回的端点在刷新后被移除，新增的端点被加入
+        *records.lock().unwrap() = vec![srv(10, 10, 443, "doh3.example.com.")];
+        assert_eq!(upstream.refresh_srv_upstreams().await.unwrap(), 1);
+        assert_eq!(upstream.discovered_resolvers(), vec!["https://doh3.example.com:443/dns-query".to_string()]);
+
+        // 没有可用记录时保留当前端点
+        records.lock().unwrap().clear();
+        assert!(upstream.refresh_srv_upstreams().await.is_err());
+        assert_eq!(upstream.discovered_resolvers(), vec!["https://doh3.example.com:443/dns-query".to_string()]);
+
+        info!("Test completed: test_srv_discovery_adds_and_withdraws_upstreams");
+    }
+
+    #[test]
+    fn test_srv_discovery_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_srv_discovery_config_validation");
+
+        let mut config = srv_server_config("https://1.1.1.1/dns-query");
+        assert_eq!(config.dns.upstream.srv_refresh_interval_secs, 300);
+        assert!(config.test().is_ok());
+
+        // 必须指定 SRV 名称
+        config.dns.upstream.srv_name = String::new();
+        assert!(config.test().is_err());
+        config.dns.upstream.srv_name = SRV_NAME.to_string();
+
+        // 刷新间隔不能过短
+        config.dns.upstream.srv_refresh_interval_secs = 1;
+        assert!(config.test().is_err());
+        config.dns.upstream.srv_refresh_interval_secs = 60;
+
+        // 需要静态引导解析器
+        config.dns.upstream.resolvers.clear();
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_srv_discovery_config_validation");
+    }
+}
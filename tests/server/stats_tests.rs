@@ -0,0 +1,256 @@
+// tests/server/stats_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use hickory_proto::op::ResponseCode;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::{ServerConfig, StatsConfig};
+    use oxide_wdns::server::stats::{stats_routes, CacheTopEntry, CacheWindow, QueryStats, TopDomainsResponse};
+
+    // 创建指定窗口大小的统计配置
+    fn create_stats_config(window_secs: u64) -> StatsConfig {
+        StatsConfig {
+            enabled: true,
+            top_domains_window_secs: window_secs,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats_aggregates_per_domain() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_stats_aggregates_per_domain");
+
+        let stats = QueryStats::new(&create_stats_config(300));
+
+        // 记录查询：域名大小写和末尾的点应被归一化
+        stats.record("Example.com.", false, Duration::from_millis(20), ResponseCode::NoError);
+        stats.record("example.com", true, Duration::from_millis(10), ResponseCode::NoError);
+        stats.record("example.com.", true, Duration::from_millis(0), ResponseCode::NXDomain);
+        stats.record("other.org.", false, Duration::from_millis(5), ResponseCode::ServFail);
+        assert_eq!(stats.len(), 2);
+
+        let top = stats.top_domains(10);
+        info!(?top, "Top domains snapshot");
+        assert_eq!(top.len(), 2);
+
+        let first = &top[0];
+        assert_eq!(first.domain, "example.com");
+        assert_eq!(first.total_queries, 3);
+        assert_eq!(first.cache_hits, 2);
+        assert_eq!(first.cache_misses, 1);
+        assert!((first.avg_latency_ms - 10.0).abs() < 0.001);
+        assert_eq!(first.response_codes.get("NoError"), Some(&2));
+        assert_eq!(first.response_codes.get("NXDomain"), Some(&1));
+
+        assert_eq!(top[1].domain, "other.org");
+        assert_eq!(top[1].response_codes.get("ServFail"), Some(&1));
+
+        // limit 应截断结果
+        assert_eq!(stats.top_domains(1).len(), 1);
+
+        info!("Test completed: test_stats_aggregates_per_domain");
+    }
+
+    #[tokio::test]
+    async fn test_stats_expires_window_but_keeps_cache_analytics() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_stats_expires_window_but_keeps_cache_analytics");
+
+        // 使用 1 秒的窗口以便快速过期
+        let stats = QueryStats::new(&create_stats_config(1));
+        stats.record("stale.example.com", false, Duration::from_millis(1), ResponseCode::NoError);
+        assert_eq!(stats.purge_stale(), 0, "Fresh entries should not be purged");
+
+        tokio::time::sleep(Duration::from_millis(2100)).await;
+
+        assert!(stats.top_domains(10).is_empty(), "Expired buckets should not be reported");
+        // 缓存分析的分钟桶仍覆盖该查询，域名不应被清理
+        assert_eq!(stats.purge_stale(), 0, "Domains within the cache analytics window should be kept");
+        assert_eq!(stats.len(), 1);
+        let cache_top = stats.cache_top(CacheWindow::OneHour, 10);
+        assert_eq!(cache_top.len(), 1);
+        assert_eq!(cache_top[0].domain, "stale.example.com");
+
+        info!("Test completed: test_stats_expires_window_but_keeps_cache_analytics");
+    }
+
+    #[tokio::test]
+    async fn test_top_domains_endpoint() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_top_domains_endpoint");
+
+        let stats = Arc::new(QueryStats::new(&create_stats_config(300)));
+        for _ in 0..3 {
+            stats.record("a.example.com", false, Duration::from_millis(1), ResponseCode::NoError);
+        }
+        stats.record("b.example.com", false, Duration::from_millis(1), ResponseCode::NoError);
+
+        let request = Request::builder()
+            .uri("/admin/stats/top-domains?limit=1")
+            .body(Body::empty())
+            .unwrap();
+        let response = stats_routes(stats).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let top: TopDomainsResponse = serde_json::from_slice(&body).unwrap();
+        info!(?top, "Received top domains response");
+        assert_eq!(top.window_secs, 300);
+        assert_eq!(top.domains.len(), 1);
+        assert_eq!(top.domains[0].domain, "a.example.com");
+        assert_eq!(top.domains[0].total_queries, 3);
+
+        info!("Test completed: test_top_domains_endpoint");
+    }
+
+    #[tokio::test]
+    async fn test_cache_top_reports_hit_rate_per_window() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cache_top_reports_hit_rate_per_window");
+
+        // 缓存分析数据保留 1 小时，与热门域名统计窗口无关
+        let stats = QueryStats::new(&create_stats_config(10));
+        stats.record("Cached.example.com.", false, Duration::from_millis(30), ResponseCode::NoError);
+        for _ in 0..3 {
+            stats.record("cached.example.com", true, Duration::from_millis(2), ResponseCode::NoError);
+        }
+        stats.record("miss.example.com", false, Duration::from_millis(8), ResponseCode::NXDomain);
+
+        for window in [CacheWindow::OneMinute, CacheWindow::FiveMinutes, CacheWindow::FifteenMinutes, CacheWindow::OneHour] {
+            let top = stats.cache_top(window, 10);
+            assert_eq!(top.len(), 2, "{:?}", window);
+            assert_eq!(top[0].domain, "cached.example.com");
+            assert_eq!(top[0].query_count, 4);
+            assert_eq!(top[0].cache_hit_count, 3);
+            assert_eq!(top[0].cache_miss_count, 1);
+            assert!((top[0].hit_rate - 0.75).abs() < 0.001);
+            assert!((top[0].avg_latency_ms - 9.0).abs() < 0.001);
+            assert_eq!(top[1].domain, "miss.example.com");
+            assert_eq!(top[1].hit_rate, 0.0);
+        }
+        assert_eq!(stats.cache_top(CacheWindow::OneHour, 1).len(), 1);
+
+        // 窗口参数只接受固定取值
+        assert_eq!("1m".parse::<CacheWindow>(), Ok(CacheWindow::OneMinute));
+        assert_eq!("1h".parse::<CacheWindow>().unwrap().minutes(), 60);
+        assert!("10m".parse::<CacheWindow>().is_err());
+        assert_eq!(CacheWindow::default(), CacheWindow::FiveMinutes);
+
+        info!("Test completed: test_cache_top_reports_hit_rate_per_window");
+    }
+
+    #[tokio::test]
+    async fn test_stats_max_tracked_domains() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_stats_max_tracked_domains");
+
+        let mut config = create_stats_config(300);
+        config.max_tracked_domains = 2;
+        let stats = QueryStats::new(&config);
+        stats.record("a.example.com", false, Duration::from_millis(1), ResponseCode::NoError);
+        stats.record("b.example.com", false, Duration::from_millis(1), ResponseCode::NoError);
+        // 达到上限后不再跟踪新域名，已跟踪的域名继续计数
+        stats.record("c.example.com", false, Duration::from_millis(1), ResponseCode::NoError);
+        stats.record("a.example.com", true, Duration::from_millis(1), ResponseCode::NoError);
+
+        assert_eq!(stats.len(), 2);
+        let domains = stats.top_domains(10).into_iter().map(|s| s.domain).collect::<Vec<_>>();
+        assert_eq!(domains, vec!["a.example.com", "b.example.com"]);
+        let top = stats.cache_top(CacheWindow::FiveMinutes, 10);
+        assert_eq!(top.iter().map(|e| e.domain.as_str()).collect::<Vec<_>>(), vec!["a.example.com", "b.example.com"]);
+        assert_eq!(top[0].cache_hit_count, 1);
+
+        info!("Test completed: test_stats_max_tracked_domains");
+    }
+
+    #[tokio::test]
+    async fn test_cache_top_endpoint() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_cache_top_endpoint");
+
+        let stats = Arc::new(QueryStats::new(&create_stats_config(300)));
+        for _ in 0..3 {
+            stats.record("a.example.com", true, Duration::from_millis(1), ResponseCode::NoError);
+        }
+        stats.record("b.example.com", false, Duration::from_millis(1), ResponseCode::NoError);
+
+        let request = Request::builder()
+            .uri("/admin/cache/top?limit=1&window=15m")
+            .body(Body::empty())
+            .unwrap();
+        let response = stats_routes(stats.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let top: Vec<CacheTopEntry> = serde_json::from_slice(&body).unwrap();
+        info!(?top, "Received cache top response");
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].domain, "a.example.com");
+        assert_eq!(top[0].query_count, 3);
+        assert_eq!(top[0].hit_rate, 1.0);
+
+        // 默认窗口与数量
+        let request = Request::builder().uri("/admin/cache/top").body(Body::empty()).unwrap();
+        let response = stats_routes(stats.clone()).oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let top: Vec<CacheTopEntry> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(top.len(), 2);
+
+        // 不支持的窗口返回 400
+        let request = Request::builder().uri("/admin/cache/top?window=2h").body(Body::empty()).unwrap();
+        let response = stats_routes(stats).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        info!("Test completed: test_cache_top_endpoint");
+    }
+
+    #[test]
+    fn test_stats_config_parsing_and_validation() {
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        stats:
+          enabled: true
+          top_domains_window_secs: 600
+        "#;
+        let config: ServerConfig = serde_yaml::from_str(config_str).unwrap();
+        assert!(config.stats.enabled);
+        assert_eq!(config.stats.top_domains_window_secs, 600);
+        assert!(config.test().is_ok());
+
+        // 默认关闭，窗口为 5 分钟
+        let default_config: ServerConfig = serde_yaml::from_str(&config_str.replace(
+            "stats:\n          enabled: true\n          top_domains_window_secs: 600",
+            "",
+        )).unwrap();
+        assert!(!default_config.stats.enabled);
+        assert_eq!(default_config.stats.top_domains_window_secs, 300);
+        assert_eq!(default_config.stats.max_tracked_domains, 10000);
+
+        // 窗口过小应被拒绝
+        let invalid: ServerConfig = serde_yaml::from_str(&config_str.replace("600", "1")).unwrap();
+        assert!(invalid.test().is_err());
+
+        // 最多跟踪的域名数必须大于 0
+        let invalid: ServerConfig = serde_yaml::from_str(&config_str.replace("600", "600\n          max_tracked_domains: 0")).unwrap();
+        assert_eq!(invalid.stats.max_tracked_domains, 0);
+        assert!(invalid.test().is_err());
+    }
+}
@@ -0,0 +1,165 @@
+// tests/server/svcb_hint_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::rdata::svcb::{SvcParamKey, SvcParamValue};
+    use hickory_proto::rr::{RData, RecordType};
+    use hickory_proto::serialize::binary::{BinDecodable, BinEncodable};
+    use tracing::info;
+    use oxide_wdns::server::config::{ServerConfig, SvcbHintConfig};
+    use oxide_wdns::server::svcb::{svcb_rdata, synthesize_svcb_response};
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{extract_ip_addresses, mount_doh_answer, test_with_server_config};
+
+    const UPSTREAM_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 10);
+    const HINT_IPV4: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const HINT_IPV6: Ipv6Addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+    fn full_hint() -> SvcbHintConfig {
+        SvcbHintConfig {
+            alpn: vec!["h3".to_string(), "h2".to_string()],
+            port: Some(443),
+            ipv4hint: vec![HINT_IPV4],
+            ipv6hint: vec![HINT_IPV6],
+            ttl: 120,
+        }
+    }
+
+    fn hints() -> HashMap<String, SvcbHintConfig> {
+        HashMap::from([("www.example.com".to_string(), full_hint())])
+    }
+
+    async fn query(server_addr: &str, name: &str, query_type: RecordType) -> Message {
+        let query = create_test_query(name, query_type);
+        let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let body = reqwest::get(url).await.unwrap().bytes().await.unwrap();
+        Message::from_vec(&body).unwrap()
+    }
+
+    #[test]
+    fn test_svcb_hint_params_wire_format() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_svcb_hint_params_wire_format");
+
+        let svcb = svcb_rdata(&full_hint());
+        let mut expected = vec![
+            0x00, 0x01,                                     // SvcPriority = 1 (ServiceMode)
+            0x00,                                           // TargetName = "."
+            0x00, 0x01, 0x00, 0x06, 2, b'h', b'3', 2, b'h', b'2', // alpn = h3,h2
+            0x00, 0x03, 0x00, 0x02, 0x01, 0xbb,             // port = 443
+            0x00, 0x04, 0x00, 0x04, 192, 0, 2, 1,           // ipv4hint = 192.0.2.1
+            0x00, 0x06, 0x00, 0x10,                         // ipv6hint = 2001:db8::1
+        ];
+        expected.extend_from_slice(&HINT_IPV6.octets());
+        assert_eq!(svcb.to_bytes().unwrap(), expected);
+
+        // 只设置部分参数时仅输出已设置的键
+        let alpn_only = SvcbHintConfig { alpn: vec!["h3".to_string()], ..Default::default() };
+        let keys: Vec<SvcParamKey> = svcb_rdata(&alpn_only).svc_params().iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec![SvcParamKey::Alpn]);
+
+        // 整条应答可以正确编解码
+        let query = create_test_query("WWW.Example.com", RecordType::HTTPS);
+        let response = synthesize_svcb_response(&hints(), &query).expect("HTTPS query should be answered");
+        let decoded = Message::from_bytes(&response.to_vec().unwrap()).unwrap();
+        assert_eq!(decoded.id(), query.id());
+        assert_eq!(decoded.response_code(), ResponseCode::NoError);
+        assert_eq!(decoded.answers().len(), 1);
+        assert_eq!(decoded.answers()[0].ttl(), 120);
+        let Some(RData::HTTPS(https)) = decoded.answers()[0].data() else {
+            panic!("Expected HTTPS record, got {:?}", decoded.answers()[0]);
+        };
+        assert_eq!(https.0, svcb);
+        let port = https.svc_params().iter().find_map(|(_, value)| match value {
+            SvcParamValue::Port(port) => Some(*port),
+            _ => None,
+        });
+        assert_eq!(port, Some(443));
+
+        // SVCB 查询返回 SVCB 类型记录，其他类型与未配置的名称不处理
+        let svcb_query = create_test_query("www.example.com.", RecordType::SVCB);
+        let response = synthesize_svcb_response(&hints(), &svcb_query).unwrap();
+        assert!(matches!(response.answers()[0].data(), Some(RData::SVCB(record)) if *record == svcb));
+        assert!(synthesize_svcb_response(&hints(), &create_test_query("www.example.com", RecordType::A)).is_none());
+        assert!(synthesize_svcb_response(&hints(), &create_test_query("other.example.com", RecordType::HTTPS)).is_none());
+
+        info!("Test completed: test_svcb_hint_params_wire_format");
+    }
+
+    #[tokio::test]
+    async fn test_svcb_hints_answered_locally() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_svcb_hints_answered_locally");
+
+        test_with_server_config(|config| config.dns.svcb_hints = hints(), |server_addr, mock| async move {
+            mount_doh_answer(&mock, UPSTREAM_IP).await;
+
+            // 配置名称的 HTTPS 查询在本地应答，不访问上游
+            let response = query(&server_addr, "www.example.com", RecordType::HTTPS).await;
+            assert_eq!(response.answers().len(), 1);
+            assert_eq!(response.answers()[0].record_type(), RecordType::HTTPS);
+            assert!(mock.received_requests().await.unwrap().is_empty());
+
+            // 同一名称的 A 查询与未配置名称的 HTTPS 查询仍转发上游
+            let response = query(&server_addr, "www.example.com", RecordType::A).await;
+            assert_eq!(extract_ip_addresses(&response), vec![UPSTREAM_IP.to_string()]);
+            query(&server_addr, "api.example.com", RecordType::HTTPS).await;
+            assert_eq!(mock.received_requests().await.unwrap().len(), 2);
+        }).await;
+
+        info!("Test completed: test_svcb_hints_answered_locally");
+    }
+
+    #[test]
+    fn test_svcb_hints_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_svcb_hints_config_validation");
+
+        let parse = |hints: &str| -> ServerConfig {
+            serde_yaml::from_str(&format!(r#"
+            http_server:
+              listen_addr: "127.0.0.1:8053"
+            dns_resolver:
+              upstream:
+                resolvers:
+                  - address: "8.8.8.8:53"
+                    protocol: udp
+              svcb_hints:
+{}
+            "#, hints)).unwrap()
+        };
+
+        let config = parse(r#"
+                "www.example.com":
+                  alpn: ["h3", "h2"]
+                  port: 8443
+                  ipv4hint: ["192.0.2.1"]
+                  ipv6hint: ["2001:db8::1"]"#);
+        assert!(config.test().is_ok());
+        let hint = &config.dns.svcb_hints["www.example.com"];
+        assert_eq!(hint.port, Some(8443));
+        assert_eq!(hint.ttl, 300);
+
+        // 空的 ALPN 标识
+        let config = parse(r#"
+                "www.example.com":
+                  alpn: [""]"#);
+        let err = config.test().unwrap_err();
+        assert!(err.to_string().contains("alpn"), "Unexpected error: {}", err);
+
+        // 无效的名称
+        let config = parse(r#"
+                "bad..name":
+                  alpn: ["h2"]"#);
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_svcb_hints_config_validation");
+    }
+}
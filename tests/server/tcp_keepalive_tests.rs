@@ -0,0 +1,173 @@
+// tests/server/tcp_keepalive_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use hickory_proto::op::{Edns, Message};
+    use hickory_proto::rr::RecordType;
+    use hickory_proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tracing::info;
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_MESSAGE, EDNS_TCP_KEEPALIVE_OPTION_CODE};
+    use oxide_wdns::server::config::ServerConfig;
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::{extract_ip_addresses, test_with_server_config};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 74);
+
+    // 模拟 TCP 上游的统计
+    #[derive(Default)]
+    struct UpstreamStats {
+        // 接受的连接数
+        connections: AtomicUsize,
+        // 收到的查询数
+        queries: AtomicUsize,
+        // 携带 Keepalive 选项的查询数
+        keepalive_queries: AtomicUsize,
+    }
+
+    // 启动模拟 TCP 上游：每个连接上循环应答查询，advertise 为响应中通告的超时（单位 100 毫秒）
+    async fn spawn_tcp_upstream(advertise: Option<u16>) -> (SocketAddr, Arc<UpstreamStats>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stats = Arc::new(UpstreamStats::default());
+        let server_stats = stats.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                server_stats.connections.fetch_add(1, Ordering::SeqCst);
+                let stats = server_stats.clone();
+                tokio::spawn(async move {
+                    while let Ok(len) = stream.read_u16().await {
+                        let mut buf = vec![0u8; len as usize];
+                        if stream.read_exact(&mut buf).await.is_err() {
+                            break;
+                        }
+                        let query = Message::from_vec(&buf).unwrap();
+                        stats.queries.fetch_add(1, Ordering::SeqCst);
+                        let keepalive = query.extensions().as_ref()
+                            .and_then(|edns| edns.option(EdnsCode::from(EDNS_TCP_KEEPALIVE_OPTION_CODE)))
+                            .is_some();
+                        if keepalive {
+                            stats.keepalive_queries.fetch_add(1, Ordering::SeqCst);
+                        }
+
+                        let mut response = create_test_response(&query, ANSWER_IP);
+                        if let Some(timeout) = advertise {
+                            let mut edns = Edns::new();
+                            edns.options_mut().insert(EdnsOption::Unknown(EDNS_TCP_KEEPALIVE_OPTION_CODE, timeout.to_be_bytes().to_vec()));
+                            response.set_edns(edns);
+                        }
+                        let wire = response.to_vec().unwrap();
+                        let mut framed = (wire.len() as u16).to_be_bytes().to_vec();
+                        framed.extend_from_slice(&wire);
+                        if stream.write_all(&framed).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (addr, stats)
+    }
+
+    fn use_tcp_upstream(config: &mut ServerConfig, addr: SocketAddr) {
+        config.dns.upstream.resolvers = vec![serde_yaml::from_str(&format!(r#"
+        address: "{}"
+        protocol: tcp
+        "#, addr)).unwrap()];
+        config.dns.upstream.tcp_keepalive = true;
+        config.dns.cache.enabled = false;
+    }
+
+    // 通过 DoH POST 发送查询并返回响应
+    async fn post_query(client: &reqwest::Client, server_addr: &str, name: &str) -> Message {
+        let query = create_test_query(name, RecordType::A);
+        let response = client.post(format!("{}/dns-query", server_addr))
+            .header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+            .body(query.to_vec().unwrap())
+            .send().await.unwrap();
+        assert!(response.status().is_success());
+        Message::from_vec(&response.bytes().await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tcp_keepalive_option_sent_and_connection_reused() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_tcp_keepalive_option_sent_and_connection_reused");
+
+        // 上游通告 30 秒的空闲超时
+        let (upstream_addr, stats) = spawn_tcp_upstream(Some(300)).await;
+        test_with_server_config(|config| use_tcp_upstream(config, upstream_addr), |server_addr, _mock| async move {
+            let client = reqwest::Client::new();
+            for name in ["first.example.com", "second.example.com", "third.example.com"] {
+                let response = post_query(&client, &server_addr, name).await;
+                assert_eq!(extract_ip_addresses(&response), vec![ANSWER_IP.to_string()]);
+                // Keepalive 选项只作用于上游连接，不返回给客户端
+                let keepalive = response.extensions().as_ref()
+                    .and_then(|edns| edns.option(EdnsCode::from(EDNS_TCP_KEEPALIVE_OPTION_CODE)));
+                assert!(keepalive.is_none());
+            }
+
+            assert_eq!(stats.queries.load(Ordering::SeqCst), 3);
+            assert_eq!(stats.keepalive_queries.load(Ordering::SeqCst), 3);
+            assert_eq!(stats.connections.load(Ordering::SeqCst), 1);
+        }).await;
+
+        info!("Test completed: test_tcp_keepalive_option_sent_and_connection_reused");
+    }
+
+    #[tokio::test]
+    async fn test_tcp_keepalive_timeout_handling() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_tcp_keepalive_timeout_handling");
+
+        // 未通告超时时使用连接池的 idle_timeout，连接仍被复用
+        let (upstream_addr, stats) = spawn_tcp_upstream(None).await;
+        test_with_server_config(|config| use_tcp_upstream(config, upstream_addr), |server_addr, _mock| async move {
+            let client = reqwest::Client::new();
+            post_query(&client, &server_addr, "fallback-1.example.com").await;
+            post_query(&client, &server_addr, "fallback-2.example.com").await;
+            assert_eq!(stats.keepalive_queries.load(Ordering::SeqCst), 2);
+            assert_eq!(stats.connections.load(Ordering::SeqCst), 1);
+        }).await;
+
+        // 通告超时为 0 时每次查询后关闭连接
+        let (upstream_addr, stats) = spawn_tcp_upstream(Some(0)).await;
+        test_with_server_config(|config| use_tcp_upstream(config, upstream_addr), |server_addr, _mock| async move {
+            let client = reqwest::Client::new();
+            post_query(&client, &server_addr, "close-1.example.com").await;
+            post_query(&client, &server_addr, "close-2.example.com").await;
+            assert_eq!(stats.connections.load(Ordering::SeqCst), 2);
+        }).await;
+
+        info!("Test completed: test_tcp_keepalive_timeout_handling");
+    }
+
+    #[test]
+    fn test_tcp_keepalive_config_default() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_tcp_keepalive_config_default");
+
+        assert!(!ServerConfig::default().dns.upstream.tcp_keepalive);
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            tcp_keepalive: true
+            resolvers:
+              - address: "dns.quad9.net@9.9.9.9:853"
+                protocol: dot
+        "#).unwrap();
+        assert!(config.dns.upstream.tcp_keepalive);
+        assert!(config.test().is_ok());
+
+        info!("Test completed: test_tcp_keepalive_config_default");
+    }
+}
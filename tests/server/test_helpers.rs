@@ -0,0 +1,182 @@
+// tests/server/test_helpers.rs
+
+// 集成测试脚手架：启动模拟上游与 oxide-wdns 测试服务器，运行测试后关闭服务器。
+
+use std::fs;
+use std::future::Future;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use hickory_proto::op::Message;
+use hickory_proto::rr::{RData, RecordType};
+use tokio::sync::oneshot;
+use tower::util::ServiceExt;
+use tracing::info;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+use reqwest::Client;
+use oxide_wdns::server::cache::DnsCache;
+use oxide_wdns::server::config::ServerConfig;
+use oxide_wdns::server::doh_handler::{doh_routes, ServerState};
+use oxide_wdns::server::routing::Router;
+use oxide_wdns::server::upstream::UpstreamManager;
+use oxide_wdns::server::DoHServer;
+use crate::server::mock_http_server::{create_test_query, create_test_response, find_free_port};
+
+// 测试服务器启动后等待其开始接受连接的时间
+const SERVER_STARTUP_DELAY: Duration = Duration::from_millis(500);
+
+// 以模拟上游为唯一 DoH 上游的测试配置
+pub fn mock_upstream_config(port: u16, upstream_uri: &str) -> ServerConfig {
+    let config_str = format!(r#"
+    http_server:
+      listen_addr: "127.0.0.1:{}"
+    dns_resolver:
+      upstream:
+        resolvers:
+          - address: "{}/dns-query"
+            protocol: doh
+        query_timeout: 3
+    "#, port, upstream_uri);
+    serde_yaml::from_str(&config_str).expect("Failed to parse test configuration")
+}
+
+// 按配置构建 DoH 处理器的服务器状态，不启用统计、查询日志与调试抓包
+pub async fn server_state(config: ServerConfig) -> ServerState {
+    let router = Arc::new(Router::new(config.dns.routing.clone(), Some(Client::new())).await.unwrap());
+    let upstream = Arc::new(UpstreamManager::new(Arc::new(config.clone()), Client::new()).await.unwrap());
+    let cache = Arc::new(DnsCache::new(config.dns.cache.clone()));
+    ServerState { config, upstream, router, cache, stats: None, query_log: None, capture: None, pending_requests: Default::default(), started_at: tokio::time::Instant::now() }
+}
+
+// 在模拟上游上挂载 DoH 应答：回显查询并对 A 查询返回指定地址
+pub async fn mount_doh_answer(mock: &MockServer, ip: Ipv4Addr) {
+    mount_doh_answer_with_delay(mock, ip, Duration::ZERO).await;
+}
+
+// 与 mount_doh_answer 相同，但每个应答固定延迟 delay 后返回，用于模拟慢上游
+pub async fn mount_doh_answer_with_delay(mock: &MockServer, ip: Ipv4Addr, delay: Duration) {
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/dns-query"))
+        .respond_with(move |request: &wiremock::Request| {
+            let query = Message::from_vec(&request.body).unwrap();
+            ResponseTemplate::new(200)
+                .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                .set_body_bytes(create_test_response(&query, ip).to_vec().unwrap())
+                .set_delay(delay)
+        })
+        .mount(mock)
+        .await;
+}
+
+// 以指定查询 ID 通过 DoH POST 查询 A 记录，返回解析后的响应
+pub async fn post_query(state: ServerState, domain: &str, id: u16) -> Message {
+    let mut query = create_test_query(domain, RecordType::A);
+    query.set_id(id);
+    let response = doh_routes(state)
+        .oneshot(
+            Request::post("/dns-query")
+                .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+                .body(Body::from(query.to_vec().unwrap()))
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    Message::from_vec(&body).unwrap()
+}
+
+// 生成 localhost 的自签名证书，返回 (证书 PEM, 私钥 PEM)
+pub fn generate_cert_pem() -> (String, String) {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    (certified.cert.pem(), certified.key_pair.serialize_pem())
+}
+
+// 生成自签名证书并写入目录下的 cert.pem 与 key.pem，返回证书 PEM
+pub fn write_self_signed_cert(dir: &Path) -> String {
+    let (cert_pem, key_pem) = generate_cert_pem();
+    fs::write(dir.join("cert.pem"), &cert_pem).unwrap();
+    fs::write(dir.join("key.pem"), key_pem).unwrap();
+    cert_pem
+}
+
+// 提取响应应答部分的记录数据，便于断言
+//
+// A/AAAA 为地址，MX 为 "优先级 交换器"，SRV 为 "优先级 权重 端口 目标"，
+// CNAME 为目标名称，TXT 为文本内容；其他类型被忽略。
+pub fn extract_ip_addresses(message: &Message) -> Vec<String> {
+    message.answers()
+        .iter()
+        .filter_map(|answer| match answer.data()? {
+            RData::A(ipv4) => Some(ipv4.to_string()),
+            RData::AAAA(ipv6) => Some(ipv6.to_string()),
+            RData::MX(mx) => Some(format!("{} {}", mx.preference(), mx.exchange())),
+            RData::SRV(srv) => Some(format!("{} {} {} {}", srv.priority(), srv.weight(), srv.port(), srv.target())),
+            RData::CNAME(cname) => Some(cname.0.to_string()),
+            RData::TXT(txt) => Some(txt.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+// 测试结束（包括断言失败导致的 panic）时关闭测试服务器
+struct ServerGuard {
+    shutdown_tx: Option<oneshot::Sender<()>>,
+}
+
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            let _ = shutdown_tx.send(());
+        }
+    }
+}
+
+// 使用默认测试配置运行测试，参见 test_with_server_config
+pub async fn test_with_server<F, Fut>(test: F)
+where
+    F: FnOnce(String, MockServer) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    test_with_server_config(|_| {}, test).await;
+}
+
+// 启动模拟上游与测试服务器，以 (服务器地址, 模拟上游) 运行测试，结束后关闭服务器
+pub async fn test_with_server_config<C, F, Fut>(customize: C, test: F)
+where
+    C: FnOnce(&mut ServerConfig),
+    F: FnOnce(String, MockServer) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mock = MockServer::start().await;
+    let port = find_free_port().await;
+    let mut config = mock_upstream_config(port, &mock.uri());
+    customize(&mut config);
+
+    let addr: SocketAddr = config.http.listen_addr.primary();
+    let components = DoHServer::new(config, false)
+        .build_application_components()
+        .await
+        .expect("Failed to build test server");
+    let listener = tokio::net::TcpListener::bind(addr).await.expect("Failed to bind test server");
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let _guard = ServerGuard { shutdown_tx: Some(shutdown_tx) };
+    tokio::spawn(async move {
+        axum::serve(listener, components.app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+    tokio::time::sleep(SERVER_STARTUP_DELAY).await;
+
+    let server_addr = format!("http://{}", addr);
+    info!(server_addr, upstream = mock.uri(), "Test server started");
+    test(server_addr, mock).await;
+}
@@ -0,0 +1,155 @@
+// tests/server/test_helpers_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::{RData, RecordType};
+    use reqwest::{header, Client, StatusCode};
+    use tracing::info;
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    use oxide_wdns::common::consts::{CONTENT_TYPE_DNS_JSON, CONTENT_TYPE_DNS_MESSAGE};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::{mount_doh_answer, test_with_server, test_with_server_config};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 10);
+
+    fn get_query_url(server_addr: &str, query: &Message) -> String {
+        format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()))
+    }
+
+    fn first_answer_ip(response: &Message) -> Option<Ipv4Addr> {
+        response.answers().first().and_then(|record| match record.data() {
+            Some(RData::A(a)) => Some(a.0),
+            _ => None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_helper_get_query() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_helper_get_query");
+
+        test_with_server(|server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+
+            let query = create_test_query("get.example.com", RecordType::A);
+            let response = reqwest::get(get_query_url(&server_addr, &query)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), CONTENT_TYPE_DNS_MESSAGE);
+
+            let answer = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+            assert_eq!(answer.id(), query.id());
+            assert_eq!(first_answer_ip(&answer), Some(ANSWER_IP));
+        }).await;
+
+        info!("Test completed: test_helper_get_query");
+    }
+
+    #[tokio::test]
+    async fn test_helper_post_query() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_helper_post_query");
+
+        test_with_server(|server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+
+            let query = create_test_query("post.example.com", RecordType::A);
+            let response = Client::new()
+                .post(format!("{}/dns-query", server_addr))
+                .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+                .body(query.to_vec().unwrap())
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let answer = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+            assert_eq!(first_answer_ip(&answer), Some(ANSWER_IP));
+        }).await;
+
+        info!("Test completed: test_helper_post_query");
+    }
+
+    #[tokio::test]
+    async fn test_helper_json_api() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_helper_json_api");
+
+        test_with_server(|server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+
+            let response = reqwest::get(format!("{}/resolve?name=json.example.com&type=A", server_addr)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(response.headers().get(header::CONTENT_TYPE).unwrap().to_str().unwrap().starts_with(CONTENT_TYPE_DNS_JSON));
+
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["Status"], 0);
+            assert_eq!(body["Answer"][0]["data"], ANSWER_IP.to_string());
+        }).await;
+
+        info!("Test completed: test_helper_json_api");
+    }
+
+    #[tokio::test]
+    async fn test_helper_upstream_failure() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_helper_upstream_failure");
+
+        test_with_server(|server_addr, mock| async move {
+            Mock::given(matchers::method("POST"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&mock)
+                .await;
+
+            // 上游失败时返回 SERVFAIL 并提示客户端退避重试
+            let query = create_test_query("fail.example.com", RecordType::A);
+            let response = reqwest::get(get_query_url(&server_addr, &query)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+            let answer = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+            assert_eq!(answer.response_code(), ResponseCode::ServFail);
+        }).await;
+
+        info!("Test completed: test_helper_upstream_failure");
+    }
+
+    #[tokio::test]
+    async fn test_helper_custom_config() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_helper_custom_config");
+
+        // 调整配置：启用缓存，重复查询只发往上游一次
+        test_with_server_config(|config| config.dns.cache.enabled = true, |server_addr, mock| async move {
+            Mock::given(matchers::method("POST"))
+                .and(matchers::path("/dns-query"))
+                .respond_with(|request: &wiremock::Request| {
+                    let query = Message::from_vec(&request.body).unwrap();
+                    ResponseTemplate::new(200)
+                        .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                        .set_body_bytes(create_test_response(&query, ANSWER_IP).to_vec().unwrap())
+                })
+                .expect(1)
+                .mount(&mock)
+                .await;
+
+            let query = create_test_query("cached.example.com", RecordType::A);
+            for _ in 0..3 {
+                let response = reqwest::get(get_query_url(&server_addr, &query)).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let answer = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+                assert_eq!(first_answer_ip(&answer), Some(ANSWER_IP));
+            }
+            mock.verify().await;
+        }).await;
+
+        info!("Test completed: test_helper_custom_config");
+    }
+}
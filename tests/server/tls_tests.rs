@@ -0,0 +1,243 @@
+// tests/server/tls_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::net::SocketAddr;
+    use std::path::Path;
+    use std::sync::Arc;
+    use axum::{routing::get, Router};
+    use reqwest::{Client, StatusCode};
+    use tempfile::TempDir;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::rustls::crypto::ring::default_provider;
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+    use tokio_rustls::rustls::version::{TLS12, TLS13};
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore, SupportedProtocolVersion};
+    use tokio_rustls::TlsConnector;
+    use tracing::info;
+    use oxide_wdns::server::config::{ServerConfig, TlsConfig, TlsMinVersion};
+    use oxide_wdns::server::http_conn::ConnectionLimits;
+    use oxide_wdns::server::tls::{serve_tls, validate_tls_protocol, TlsContext};
+    use crate::server::test_helpers::generate_cert_pem;
+
+    // 将证书与私钥写入临时目录并返回 TLS 配置
+    fn write_tls_files(dir: &Path, cert_pem: &str, key_pem: &str) -> TlsConfig {
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        fs::write(&cert, cert_pem).unwrap();
+        fs::write(&key, key_pem).unwrap();
+        TlsConfig { cert: Some(cert), key: Some(key), acme: Default::default(), client_auth: None, min_version: Default::default(), cipher_suites: None, alpn: None }
+    }
+
+    // 使用指定协议版本与 ALPN 完成握手，返回协商的 ALPN 协议
+    async fn handshake(
+        addr: SocketAddr,
+        cert_pem: &str,
+        versions: &[&'static SupportedProtocolVersion],
+        alpn: &[&[u8]],
+    ) -> std::io::Result<Option<Vec<u8>>> {
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from_pem_slice(cert_pem.as_bytes()).unwrap()).unwrap();
+        let mut client_config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_protocol_versions(versions)
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client_config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+
+        let stream = TcpStream::connect(addr).await?;
+        let tls_stream = TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await?;
+        Ok(tls_stream.get_ref().1.alpn_protocol().map(|p| p.to_vec()))
+    }
+
+    // 以指定配置启动 TLS 监听
+    async fn start_tls_listener(tls_config: &TlsConfig) -> SocketAddr {
+        let tls = Arc::new(TlsContext::new(tls_config).unwrap());
+        let app = Router::new().route("/health", get(|| async { "ok" }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_tls(listener, app, tls, ConnectionLimits::default(), None));
+        addr
+    }
+
+    #[test]
+    fn test_tls_rejects_mismatched_key() {
+        let dir = TempDir::new().unwrap();
+        let (cert_pem, _) = generate_cert_pem();
+        let (_, other_key_pem) = generate_cert_pem();
+        let tls_config = write_tls_files(dir.path(), &cert_pem, &other_key_pem);
+
+        // 私钥与证书不匹配时应给出可读的错误
+        let err = TlsContext::new(&tls_config).err().expect("Mismatched key should be rejected");
+        assert!(err.to_string().contains("does not match"), "Unexpected error: {}", err);
+
+        // 配置校验同样应失败
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          tls:
+            cert: "{}"
+            key: "{}"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#, tls_config.cert_path().display(), tls_config.key_path().display());
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        assert!(config.test().is_err());
+
+        // 文件不存在时也应失败
+        let missing = TlsConfig {
+            cert: Some(dir.path().join("missing.pem")),
+            key: tls_config.key.clone(),
+            acme: Default::default(),
+            client_auth: None,
+            min_version: Default::default(),
+            cipher_suites: None,
+            alpn: None,
+        };
+        assert!(TlsContext::new(&missing).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tls_serves_https_and_rejects_plain_http() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_tls_serves_https_and_rejects_plain_http");
+
+        let dir = TempDir::new().unwrap();
+        let (cert_pem, key_pem) = generate_cert_pem();
+        let tls_config = write_tls_files(dir.path(), &cert_pem, &key_pem);
+        let tls = Arc::new(TlsContext::new(&tls_config).unwrap());
+
+        let app = Router::new().route("/health", get(|| async { "ok" }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_tls(listener, app, tls, ConnectionLimits::default(), None));
+
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+
+        // HTTPS 请求应成功
+        info!("Sending HTTPS request to {}", addr);
+        let response = client.get(format!("https://localhost:{}/health", addr.port()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        // 明文 HTTP 请求应得到 400 响应而不是连接错误
+        info!("Sending plain HTTP request to {}", addr);
+        let response = client.get(format!("http://{}/health", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.text().await.unwrap().contains("HTTPS"));
+
+        info!("Test completed: test_tls_serves_https_and_rejects_plain_http");
+    }
+
+    #[test]
+    fn test_tls_reload_swaps_certificate() {
+        let dir = TempDir::new().unwrap();
+        let (cert_pem, key_pem) = generate_cert_pem();
+        let tls_config = write_tls_files(dir.path(), &cert_pem, &key_pem);
+        let tls = TlsContext::new(&tls_config).unwrap();
+        let original = tls.current_certificate().unwrap();
+
+        // 替换为新证书后重新加载
+        let (new_cert_pem, new_key_pem) = generate_cert_pem();
+        write_tls_files(dir.path(), &new_cert_pem, &new_key_pem);
+        tls.reload().unwrap();
+        let reloaded = tls.current_certificate().unwrap();
+        assert_ne!(original, reloaded);
+
+        // 写入不匹配的证书与私钥，重新加载失败时应保留当前证书
+        write_tls_files(dir.path(), &cert_pem, &new_key_pem);
+        assert!(tls.reload().is_err());
+        assert_eq!(tls.current_certificate().unwrap(), reloaded);
+    }
+
+    #[tokio::test]
+    async fn test_tls_min_version_and_alpn() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_tls_min_version_and_alpn");
+
+        let dir = TempDir::new().unwrap();
+        let (cert_pem, key_pem) = generate_cert_pem();
+        let tls_config = write_tls_files(dir.path(), &cert_pem, &key_pem);
+
+        // 默认同时接受 TLS 1.2 与 TLS 1.3，优先协商 h2
+        let addr = start_tls_listener(&tls_config).await;
+        assert!(handshake(addr, &cert_pem, &[&TLS12], &[b"h2"]).await.is_ok());
+        let alpn = handshake(addr, &cert_pem, &[&TLS13], &[b"http/1.1", b"h2"]).await.unwrap();
+        assert_eq!(alpn.as_deref(), Some(&b"h2"[..]));
+
+        // 最低版本为 1.3 时拒绝 TLS 1.2 握手；限制 ALPN 后只协商列出的协议
+        let strict = TlsConfig {
+            min_version: TlsMinVersion::Tls13,
+            alpn: Some(vec!["http/1.1".to_string()]),
+            ..tls_config
+        };
+        let addr = start_tls_listener(&strict).await;
+        assert!(handshake(addr, &cert_pem, &[&TLS12], &[b"http/1.1"]).await.is_err());
+        let alpn = handshake(addr, &cert_pem, &[&TLS13], &[b"h2", b"http/1.1"]).await.unwrap();
+        assert_eq!(alpn.as_deref(), Some(&b"http/1.1"[..]));
+
+        info!("Test completed: test_tls_min_version_and_alpn");
+    }
+
+    #[test]
+    fn test_tls_protocol_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_tls_protocol_validation");
+
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          tls:
+            cert: "cert.pem"
+            key: "key.pem"
+            min_version: "1.3"
+            cipher_suites: ["TLS13_AES_256_GCM_SHA384", "tls13_chacha20_poly1305_sha256"]
+            alpn: ["h2"]
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+        "#).unwrap();
+        let tls = config.http.tls.unwrap();
+        assert_eq!(tls.min_version, TlsMinVersion::Tls13);
+        assert!(validate_tls_protocol(&tls, true).is_ok());
+
+        // 未知套件或 ALPN 协议
+        let unknown_suite = TlsConfig { cipher_suites: Some(vec!["TLS_RSA_WITH_RC4_128_MD5".to_string()]), ..tls.clone() };
+        assert!(validate_tls_protocol(&unknown_suite, false).is_err());
+        let unknown_alpn = TlsConfig { alpn: Some(vec!["h3".to_string()]), ..tls.clone() };
+        assert!(validate_tls_protocol(&unknown_alpn, false).is_err());
+        let empty_alpn = TlsConfig { alpn: Some(Vec::new()), ..tls.clone() };
+        assert!(validate_tls_protocol(&empty_alpn, false).is_err());
+
+        // 只有 TLS 1.2 套件时不能要求 TLS 1.3，也不能启用 HTTP/3
+        let tls12_suites = Some(vec!["TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256".to_string()]);
+        let tls13_only = TlsConfig { cipher_suites: tls12_suites.clone(), ..tls.clone() };
+        assert!(validate_tls_protocol(&tls13_only, false).is_err());
+        let tls12 = TlsConfig { cipher_suites: tls12_suites, min_version: TlsMinVersion::Tls12, ..tls };
+        assert!(validate_tls_protocol(&tls12, false).is_ok());
+        assert!(validate_tls_protocol(&tls12, true).is_err());
+
+        info!("Test completed: test_tls_protocol_validation");
+    }
+}
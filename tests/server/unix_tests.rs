@@ -0,0 +1,149 @@
+// tests/server/unix_tests.rs
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::net::Ipv4Addr;
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use std::path::Path;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_ENGINE};
+    use hickory_proto::rr::RecordType;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixStream;
+    use tracing::info;
+    use oxide_wdns::server::config::{ServerConfig, UnixSocketConfig};
+    use oxide_wdns::server::http_conn::ConnectionLimits;
+    use oxide_wdns::server::unix::{bind_unix, serve_unix};
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 创建监听 Unix 域套接字的服务器配置
+    fn unix_server_config(upstream_uri: &str, socket: &Path, unix_rate_limit: bool) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          listen_unix: "{}"
+          unix_socket:
+            rate_limit: {}
+          rate_limit:
+            enabled: true
+            per_ip_rate: 1
+            per_ip_concurrent: 1
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+        "#, socket.display(), unix_rate_limit, upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 通过 Unix 域套接字发送 HTTP/1.1 请求，返回状态码
+    async fn unix_http_get(socket: &Path, uri: &str) -> u16 {
+        let mut stream = UnixStream::connect(socket).await.unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nAccept: application/dns-message\r\nConnection: close\r\n\r\n",
+            uri
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        let status_line = String::from_utf8_lossy(&response).lines().next().unwrap_or_default().to_string();
+        status_line.split_whitespace().nth(1).unwrap().parse().unwrap()
+    }
+
+    // 启动 Unix 域套接字上的服务器，返回套接字路径所在的临时目录
+    async fn start_unix_server(unix_rate_limit: bool) -> (TempDir, std::path::PathBuf) {
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 80)).await;
+        let dir = TempDir::new().unwrap();
+        let socket = dir.path().join("run").join("doh.sock");
+        let config = unix_server_config(&mock_server.uri(), &socket, unix_rate_limit);
+
+        let components = DoHServer::new(config.clone(), false).build_application_components().await.unwrap();
+        let listener = bind_unix(&socket, &config.http.unix_socket).unwrap();
+        let unix_app = components.unix_app.expect("unix app should be built when listen_unix is set");
+        tokio::spawn(async move {
+            // 保持模拟上游存活直到服务器任务结束
+            let _mock_server = mock_server;
+            let _ = serve_unix(listener, unix_app, ConnectionLimits::default()).await;
+        });
+        (dir, socket)
+    }
+
+    #[test]
+    fn test_bind_unix_replaces_stale_socket() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_bind_unix_replaces_stale_socket");
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        let dir = TempDir::new().unwrap();
+        let socket = dir.path().join("doh.sock");
+        let config = UnixSocketConfig { mode: "0600".to_string(), ..UnixSocketConfig::default() };
+
+        // 遗留的套接字文件被移除后重新绑定，并应用配置的权限
+        let stale = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        drop(stale);
+        assert!(fs::metadata(&socket).unwrap().file_type().is_socket());
+        let listener = bind_unix(&socket, &config).unwrap();
+        assert_eq!(fs::metadata(&socket).unwrap().permissions().mode() & 0o777, 0o600);
+        drop(listener);
+
+        // 路径上存在普通文件时拒绝覆盖
+        let regular = dir.path().join("regular");
+        fs::write(&regular, b"keep me").unwrap();
+        let err = bind_unix(&regular, &config).unwrap_err();
+        assert!(err.to_string().contains("not a socket"), "{}", err);
+        assert_eq!(fs::read(&regular).unwrap(), b"keep me");
+
+        info!("Test completed: test_bind_unix_replaces_stale_socket");
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_serves_doh_without_rate_limit() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_unix_socket_serves_doh_without_rate_limit");
+
+        let query = create_test_query("unix.example.com", RecordType::A);
+        let uri = format!("/dns-query?dns={}", BASE64_ENGINE.encode(query.to_vec().unwrap()));
+
+        // 默认不对 Unix 域套接字限速
+        let (_dir, socket) = start_unix_server(false).await;
+        assert_eq!(unix_http_get(&socket, "/health").await, 200);
+        for _ in 0..3 {
+            assert_eq!(unix_http_get(&socket, &uri).await, 200);
+        }
+
+        // 启用后所有连接共享同一个限速键
+        let (_dir, socket) = start_unix_server(true).await;
+        let statuses = [
+            unix_http_get(&socket, &uri).await,
+            unix_http_get(&socket, &uri).await,
+            unix_http_get(&socket, &uri).await,
+        ];
+        assert_eq!(statuses[0], 200);
+        assert!(statuses.contains(&429), "{:?}", statuses);
+
+        info!("Test completed: test_unix_socket_serves_doh_without_rate_limit");
+    }
+
+    #[test]
+    fn test_unix_socket_config_validation() {
+        let mut config = unix_server_config("https://dns.example.com", Path::new("/run/owdns/doh.sock"), false);
+        assert_eq!(config.http.unix_socket.mode, "0660");
+        assert_eq!(config.http.unix_socket.parsed_mode().unwrap(), 0o660);
+        assert!(config.test().is_ok());
+
+        // 权限必须是合法的八进制值
+        for mode in ["0999", "rw-rw----", "01777"] {
+            config.http.unix_socket.mode = mode.to_string();
+            assert!(config.test().is_err(), "mode {} should be rejected", mode);
+        }
+        config.http.unix_socket.mode = "0o640".to_string();
+        assert_eq!(config.http.unix_socket.parsed_mode().unwrap(), 0o640);
+    }
+}
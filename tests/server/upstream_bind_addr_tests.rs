@@ -0,0 +1,193 @@
+// tests/server/upstream_bind_addr_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::sync::{Arc, Mutex};
+    use axum::body::Bytes;
+    use axum::extract::{ConnectInfo, State};
+    use axum::routing::post;
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, UdpSocket};
+    use tracing::info;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::upstream::{UpstreamManager, UpstreamSelection};
+    use oxide_wdns::server::create_upstream_http_client;
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    // 回环网段内的第二个地址，用于区分源地址是否生效
+    const BIND_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+
+    // 记录最近一次查询的源地址
+    type SeenSource = Arc<Mutex<Option<IpAddr>>>;
+
+    fn bind_addr_config(address: &str, protocol: &str, bind_addr: Option<IpAddr>) -> ServerConfig {
+        let mut config: ServerConfig = serde_yaml::from_str(&format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}"
+                protocol: {}
+            query_timeout: 3
+        "#, address, protocol)).unwrap();
+        config.dns.upstream.bind_addr = bind_addr;
+        config
+    }
+
+    async fn resolve(config: ServerConfig) -> Message {
+        let client = create_upstream_http_client(&config, &config.dns.http_client.pool, &config.dns.upstream).unwrap();
+        let upstream = UpstreamManager::new(Arc::new(config), client).await.unwrap();
+        let query = create_test_query("example.com", RecordType::A);
+        upstream.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap()
+    }
+
+    // 启动只应答一次的 UDP DNS 服务器
+    async fn start_udp_server(seen: SeenSource) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+            *seen.lock().unwrap() = Some(peer.ip());
+            let query = Message::from_vec(&buf[..len]).unwrap();
+            let response = create_test_response(&query, ANSWER_IP);
+            socket.send_to(&response.to_vec().unwrap(), peer).await.unwrap();
+        });
+        addr
+    }
+
+    // 启动只应答一次的 TCP DNS 服务器（两字节长度前缀）
+    async fn start_tcp_server(seen: SeenSource) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, peer) = listener.accept().await.unwrap();
+            *seen.lock().unwrap() = Some(peer.ip());
+            let len = stream.read_u16().await.unwrap() as usize;
+            let mut buf = vec![0u8; len];
+            stream.read_exact(&mut buf).await.unwrap();
+            let query = Message::from_vec(&buf).unwrap();
+            let response = create_test_response(&query, ANSWER_IP).to_vec().unwrap();
+            stream.write_u16(response.len() as u16).await.unwrap();
+            stream.write_all(&response).await.unwrap();
+        });
+        addr
+    }
+
+    async fn handle_doh(
+        State(seen): State<SeenSource>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        body: Bytes,
+    ) -> ([(&'static str, &'static str); 1], Vec<u8>) {
+        *seen.lock().unwrap() = Some(peer.ip());
+        let query = Message::from_vec(&body).unwrap();
+        let response = create_test_response(&query, ANSWER_IP);
+        ([("content-type", CONTENT_TYPE_DNS_MESSAGE)], response.to_vec().unwrap())
+    }
+
+    // 启动记录连接源地址的 DoH 服务器
+    async fn start_doh_server(seen: SeenSource) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = axum::Router::new().route("/dns-query", post(handle_doh)).with_state(seen);
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_upstream_bind_addr_udp() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_bind_addr_udp");
+
+        let seen = SeenSource::default();
+        let server_addr = start_udp_server(seen.clone()).await;
+        let config = bind_addr_config(&server_addr.to_string(), "udp", Some(BIND_IP));
+        assert!(config.test().is_ok());
+
+        let response = resolve(config).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(*seen.lock().unwrap(), Some(BIND_IP));
+
+        info!("Test completed: test_upstream_bind_addr_udp");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_bind_addr_tcp() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_bind_addr_tcp");
+
+        let seen = SeenSource::default();
+        let server_addr = start_tcp_server(seen.clone()).await;
+        let config = bind_addr_config(&server_addr.to_string(), "tcp", Some(BIND_IP));
+
+        let response = resolve(config).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(*seen.lock().unwrap(), Some(BIND_IP));
+
+        info!("Test completed: test_upstream_bind_addr_tcp");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_bind_addr_doh() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_bind_addr_doh");
+
+        let seen = SeenSource::default();
+        let server_addr = start_doh_server(seen.clone()).await;
+        let url = format!("http://{}/dns-query", server_addr);
+
+        let response = resolve(bind_addr_config(&url, "doh", Some(BIND_IP))).await;
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(*seen.lock().unwrap(), Some(BIND_IP));
+
+        // 未配置源地址时由系统选择
+        let response = resolve(bind_addr_config(&url, "doh", None)).await;
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(*seen.lock().unwrap(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+
+        info!("Test completed: test_upstream_bind_addr_doh");
+    }
+
+    #[test]
+    fn test_upstream_bind_addr_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_bind_addr_validation");
+
+        assert!(ServerConfig::default().dns.upstream.bind_addr.is_none());
+        assert!(bind_addr_config("8.8.8.8:53", "udp", Some(BIND_IP)).test().is_ok());
+        assert!(bind_addr_config("https://dns.example.com/dns-query", "doh", Some(BIND_IP)).test().is_ok());
+
+        // 非本机地址无法绑定
+        assert!(bind_addr_config("8.8.8.8:53", "udp", Some("192.0.2.123".parse().unwrap())).test().is_err());
+        // 地址族与上游不一致
+        assert!(bind_addr_config("8.8.8.8:53", "udp", Some("::1".parse().unwrap())).test().is_err());
+        assert!(bind_addr_config("[2001:4860:4860::8888]:53", "tcp", Some(BIND_IP)).test().is_err());
+
+        // YAML 中按 IP 地址解析
+        let config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+            bind_addr: "127.0.0.2"
+        "#).unwrap();
+        assert_eq!(config.dns.upstream.bind_addr, Some(BIND_IP));
+
+        info!("Test completed: test_upstream_bind_addr_validation");
+    }
+}
@@ -0,0 +1,98 @@
+// tests/server/upstream_http2_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::rr::RecordType;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::metrics::METRICS;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+
+    // 使用明文 HTTP 的 DoH 上游，关闭缓存确保每次查询都发往上游
+    fn http2_config(upstream_uri: &str, prefer_http2: bool) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+            prefer_http2: {}
+          cache:
+            enabled: false
+        "#, upstream_uri, prefer_http2);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    fn version_count(version: &str) -> u64 {
+        METRICS.upstream_http_version_total().with_label_values(&[version]).get()
+    }
+
+    // 通过完整应用发送一次查询，返回上游响应使用的 HTTP 版本计数变化
+    async fn query_and_count(prefer_http2: bool, version: &str) -> u64 {
+        let (mock_server, _) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 1)).await;
+        let config = http2_config(&mock_server.uri(), prefer_http2);
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+
+        let before = version_count(version);
+        let query = create_test_query("example.com", RecordType::A);
+        let uri = format!("/dns-query?dns={}", URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        let response = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        version_count(version) - before
+    }
+
+    #[tokio::test]
+    async fn test_upstream_prefers_http2() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_prefers_http2");
+
+        // 明文上游在 prefer_http2 时直接使用 HTTP/2
+        assert!(query_and_count(true, "h2").await >= 1);
+
+        info!("Test completed: test_upstream_prefers_http2");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_http1_when_http2_disabled() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_http1_when_http2_disabled");
+
+        assert!(query_and_count(false, "h1").await >= 1);
+
+        info!("Test completed: test_upstream_http1_when_http2_disabled");
+    }
+
+    #[test]
+    fn test_upstream_http2_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_http2_config_validation");
+
+        let mut config = ServerConfig::default();
+        assert!(config.dns.upstream.prefer_http2);
+        assert_eq!(config.dns.upstream.http2_keepalive_interval_secs, 30);
+
+        config.dns.upstream.http2_keepalive_interval_secs = 0;
+        assert!(config.test().is_ok());
+        config.dns.upstream.http2_keepalive_interval_secs = 3601;
+        assert!(config.test().is_err());
+
+        // 省略时使用默认值
+        let config = http2_config("http://127.0.0.1:8080", false);
+        assert!(!config.dns.upstream.prefer_http2);
+        assert_eq!(config.dns.upstream.http2_keepalive_interval_secs, 30);
+
+        info!("Test completed: test_upstream_http2_config_validation");
+    }
+}
@@ -0,0 +1,252 @@
+// tests/server/upstream_mtls_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use axum::body::Bytes;
+    use axum::http::header;
+    use axum::{routing::post, Router};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::RecordType;
+    use rcgen::{BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+    use reqwest::Client;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tracing::info;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::{ClientAuthConfig, ClientAuthMode, ResolverConfig, ResolverProtocol, ServerConfig, TlsConfig};
+    use oxide_wdns::server::http_conn::ConnectionLimits;
+    use oxide_wdns::server::tls::{serve_tls, TlsContext};
+    use oxide_wdns::server::upstream::{UpstreamManager, UpstreamSelection};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+    use crate::server::test_helpers::extract_ip_addresses;
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 73);
+
+    // 测试 CA：签发服务端与客户端证书
+    struct TestCa {
+        cert: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    impl TestCa {
+        fn new(name: &str) -> Self {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(Vec::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params.distinguished_name.push(DnType::CommonName, name);
+            let cert = params.self_signed(&key).unwrap();
+            Self { cert, key }
+        }
+
+        // 签发证书并写入目录，返回 (证书路径, 私钥路径)
+        fn issue(&self, dir: &Path, name: &str, purpose: ExtendedKeyUsagePurpose) -> (PathBuf, PathBuf) {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+            params.distinguished_name.push(DnType::CommonName, name);
+            params.extended_key_usages = vec![purpose];
+            let cert = params.signed_by(&key, &self.cert, &self.key).unwrap();
+            let cert_path = dir.join(format!("{}.pem", name));
+            let key_path = dir.join(format!("{}-key.pem", name));
+            fs::write(&cert_path, cert.pem()).unwrap();
+            fs::write(&key_path, key.serialize_pem()).unwrap();
+            (cert_path, key_path)
+        }
+
+        fn write(&self, dir: &Path, name: &str) -> PathBuf {
+            let path = dir.join(format!("{}.pem", name));
+            fs::write(&path, self.cert.pem()).unwrap();
+            path
+        }
+    }
+
+    // 双向 TLS 测试环境：服务端 CA、客户端证书以及要求客户端证书的服务端 TLS 上下文
+    struct MtlsFixture {
+        _dir: TempDir,
+        server_ca: PathBuf,
+        client_cert: PathBuf,
+        client_key: PathBuf,
+        tls: Arc<TlsContext>,
+    }
+
+    impl MtlsFixture {
+        fn new() -> Self {
+            let dir = TempDir::new().unwrap();
+            let server_ca = TestCa::new("Upstream Server CA");
+            let client_ca = TestCa::new("Upstream Client CA");
+            let (server_cert, server_key) = server_ca.issue(dir.path(), "server", ExtendedKeyUsagePurpose::ServerAuth);
+            let (client_cert, client_key) = client_ca.issue(dir.path(), "client", ExtendedKeyUsagePurpose::ClientAuth);
+            let tls_config = TlsConfig {
+                cert: Some(server_cert),
+                key: Some(server_key),
+                acme: Default::default(),
+                client_auth: Some(ClientAuthConfig { ca_cert: client_ca.write(dir.path(), "client-ca"), mode: ClientAuthMode::Required }),
+                min_version: Default::default(),
+                cipher_suites: None,
+                alpn: None,
+            };
+            Self {
+                server_ca: server_ca.write(dir.path(), "server-ca"),
+                client_cert,
+                client_key,
+                tls: Arc::new(TlsContext::new(&tls_config).unwrap()),
+                _dir: dir,
+            }
+        }
+
+        // 信任服务端 CA 的解析器配置，with_client_cert 决定是否出示客户端证书
+        fn resolver(&self, address: String, protocol: ResolverProtocol, with_client_cert: bool) -> ResolverConfig {
+            ResolverConfig {
+                address,
+                protocol,
+                path: None,
+                client_cert_path: with_client_cert.then(|| self.client_cert.clone()),
+                client_key_path: with_client_cert.then(|| self.client_key.clone()),
+                ca_cert_path: Some(self.server_ca.clone()),
+            }
+        }
+    }
+
+    fn answer(body: &[u8]) -> Vec<u8> {
+        let query = Message::from_vec(body).unwrap();
+        create_test_response(&query, ANSWER_IP).to_vec().unwrap()
+    }
+
+    // 要求客户端证书的 DoH 上游
+    async fn start_doh_upstream(tls: Arc<TlsContext>) -> SocketAddr {
+        let app = Router::new().route("/dns-query", post(|body: Bytes| async move {
+            ([(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)], answer(&body))
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_tls(listener, app, tls, ConnectionLimits::default(), None));
+        addr
+    }
+
+    // 要求客户端证书的 DoT 上游，每个连接应答一个查询
+    async fn start_dot_upstream(tls: Arc<TlsContext>) -> SocketAddr {
+        let acceptor = tls.dot_acceptor().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(mut stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let len = stream.read_u16().await.unwrap();
+                    let mut query = vec![0u8; len as usize];
+                    stream.read_exact(&mut query).await.unwrap();
+                    let response = answer(&query);
+                    stream.write_all(&(response.len() as u16).to_be_bytes()).await.unwrap();
+                    stream.write_all(&response).await.unwrap();
+                    stream.shutdown().await.unwrap();
+                });
+            }
+        });
+        addr
+    }
+
+    fn config_with(resolver: ResolverConfig) -> ServerConfig {
+        let mut config: ServerConfig = serde_yaml::from_str(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+            query_timeout: 5
+        "#).unwrap();
+        config.dns.upstream.resolvers = vec![resolver];
+        config
+    }
+
+    async fn resolve_via(resolver: ResolverConfig) -> oxide_wdns::server::error::Result<Message> {
+        let config = config_with(resolver);
+        config.test()?;
+        let manager = UpstreamManager::new(Arc::new(config), Client::new()).await?;
+        let query = create_test_query("mtls.example.com", RecordType::A);
+        manager.resolve(&query, UpstreamSelection::Global, None, None).await
+    }
+
+    #[tokio::test]
+    async fn test_upstream_doh_presents_client_certificate() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_doh_presents_client_certificate");
+
+        let fixture = MtlsFixture::new();
+        let addr = start_doh_upstream(fixture.tls.clone()).await;
+        let url = format!("https://localhost:{}/dns-query", addr.port());
+
+        // 出示客户端证书时查询成功
+        let response = resolve_via(fixture.resolver(url.clone(), ResolverProtocol::Doh, true)).await.unwrap();
+        assert_eq!(extract_ip_addresses(&response), vec![ANSWER_IP.to_string()]);
+
+        // 未出示客户端证书时握手被上游拒绝
+        assert!(resolve_via(fixture.resolver(url, ResolverProtocol::Doh, false)).await.is_err());
+
+        info!("Test completed: test_upstream_doh_presents_client_certificate");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_dot_presents_client_certificate() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_dot_presents_client_certificate");
+
+        let fixture = MtlsFixture::new();
+        let addr = start_dot_upstream(fixture.tls.clone()).await;
+        let address = format!("localhost@{}", addr);
+
+        // 出示客户端证书时查询成功
+        let response = resolve_via(fixture.resolver(address.clone(), ResolverProtocol::Dot, true)).await.unwrap();
+        assert_eq!(extract_ip_addresses(&response), vec![ANSWER_IP.to_string()]);
+
+        // 未出示客户端证书时握手被上游拒绝
+        assert!(resolve_via(fixture.resolver(address, ResolverProtocol::Dot, false)).await.is_err());
+
+        info!("Test completed: test_upstream_dot_presents_client_certificate");
+    }
+
+    #[test]
+    fn test_upstream_client_certificate_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_client_certificate_validation");
+
+        let fixture = MtlsFixture::new();
+        let doh = || fixture.resolver("https://doh.internal.example/dns-query".to_string(), ResolverProtocol::Doh, true);
+        assert!(config_with(doh()).test().is_ok());
+
+        // 缺少私钥配置
+        let mut missing_key = doh();
+        missing_key.client_key_path = None;
+        let err = config_with(missing_key).test().unwrap_err();
+        assert!(err.to_string().contains("client_key_path"), "Unexpected error: {}", err);
+
+        // 证书文件不存在
+        let mut missing_file = doh();
+        missing_file.client_cert_path = Some(PathBuf::from("/nonexistent/client.pem"));
+        let err = config_with(missing_file).test().unwrap_err();
+        assert!(err.to_string().contains("Failed to read client certificate"), "Unexpected error: {}", err);
+
+        // 私钥与证书不匹配
+        let mut mismatched = doh();
+        mismatched.client_key_path = Some(fixture.server_ca.with_file_name("server-key.pem"));
+        let err = config_with(mismatched).test().unwrap_err();
+        assert!(err.to_string().contains("does not match"), "Unexpected error: {}", err);
+
+        // UDP/TCP 解析器不支持 TLS 客户端设置
+        let udp = fixture.resolver("8.8.8.8:53".to_string(), ResolverProtocol::Udp, true);
+        assert!(config_with(udp).test().is_err());
+
+        info!("Test completed: test_upstream_client_certificate_validation");
+    }
+}
@@ -0,0 +1,210 @@
+// tests/server/upstream_override_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use axum::body::{to_bytes, Body};
+    use axum::extract::ConnectInfo;
+    use axum::http::{header, Request, StatusCode};
+    use hickory_proto::op::Message;
+    use hickory_proto::rr::{RData, RecordType};
+    use hickory_proto::rr::rdata::A;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::doh_handler::{doh_routes, ServerState};
+    use oxide_wdns::server::DoHServer;
+    use crate::server::mock_http_server::{create_test_query, setup_mock_doh_server};
+    use crate::server::test_helpers::server_state;
+
+    const DEFAULT_UPSTREAM_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const SECURE_GROUP_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 2);
+
+    // 创建包含 secure_group 上游组的配置，默认上游与 secure_group 分别指向不同的模拟服务器
+    fn override_config(default_uri: &str, secure_uri: &str, trusted_ips: &str) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          trusted_override_ips: {}
+          rate_limit:
+            enabled: false
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+          cache:
+            enabled: true
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "secure_group"
+                resolvers:
+                  - address: "{}/dns-query"
+                    protocol: doh
+        "#, trusted_ips, default_uri, secure_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 构造来自指定连接源地址的 wireformat 查询，可选携带转发头与上游组覆盖头
+    fn query_request(peer_ip: &str, forwarded_for: Option<&str>, group: Option<&str>) -> Request<Body> {
+        let query = create_test_query("example.com", RecordType::A);
+        let mut builder = Request::post("/dns-query")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE_DNS_MESSAGE)
+            .extension(ConnectInfo(SocketAddr::new(peer_ip.parse().unwrap(), 40000)));
+        if let Some(forwarded_for) = forwarded_for {
+            builder = builder.header("X-Forwarded-For", forwarded_for);
+        }
+        if let Some(group) = group {
+            builder = builder.header("X-Upstream-Group", group);
+        }
+        builder.body(Body::from(query.to_vec().unwrap())).unwrap()
+    }
+
+    // 以指定客户端 IP 直连发送 wireformat 查询，可选携带上游组覆盖头
+    async fn post_query(state: &ServerState, client_ip: &str, group: Option<&str>) -> (StatusCode, Vec<u8>) {
+        let response = doh_routes(state.clone())
+            .oneshot(query_request(client_ip, None, group))
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, body.to_vec())
+    }
+
+    // 提取响应中的第一个 A 记录
+    fn answer_ip(body: &[u8]) -> Ipv4Addr {
+        let message = Message::from_vec(body).unwrap();
+        match message.answers()[0].data() {
+            Some(RData::A(A(ip))) => *ip,
+            other => panic!("unexpected answer: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upstream_override_honored_for_trusted_client() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_override_honored_for_trusted_client");
+
+        let (default_server, _) = setup_mock_doh_server(DEFAULT_UPSTREAM_IP).await;
+        let (secure_server, secure_count) = setup_mock_doh_server(SECURE_GROUP_IP).await;
+        let config = override_config(&default_server.uri(), &secure_server.uri(), r#"["10.0.0.0/8"]"#);
+        let state = server_state(config).await;
+
+        // 受信任的客户端直接路由到指定的上游组
+        let (status, body) = post_query(&state, "10.1.2.3", Some("secure_group")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(answer_ip(&body), SECURE_GROUP_IP);
+        assert_eq!(*secure_count.lock().unwrap(), 1);
+
+        // 覆盖查询的结果不写入缓存，普通查询仍使用默认上游
+        let (status, body) = post_query(&state, "10.1.2.3", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(answer_ip(&body), DEFAULT_UPSTREAM_IP);
+
+        // 覆盖查询跳过缓存，每次都发送到指定的上游组
+        let (_, body) = post_query(&state, "10.1.2.3", Some("secure_group")).await;
+        assert_eq!(answer_ip(&body), SECURE_GROUP_IP);
+        assert_eq!(*secure_count.lock().unwrap(), 2);
+
+        // 指定不存在的上游组时返回 400
+        let (status, body) = post_query(&state, "10.1.2.3", Some("missing_group")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(&body[..], b"Unknown upstream group");
+
+        info!("Test completed: test_upstream_override_honored_for_trusted_client");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_override_ignored_for_untrusted_client() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_override_ignored_for_untrusted_client");
+
+        let (default_server, _) = setup_mock_doh_server(DEFAULT_UPSTREAM_IP).await;
+        let (secure_server, secure_count) = setup_mock_doh_server(SECURE_GROUP_IP).await;
+
+        // 不在受信任列表中的客户端，覆盖头被忽略，即使指定的组不存在也不会返回 400
+        let config = override_config(&default_server.uri(), &secure_server.uri(), r#"["10.0.0.0/8"]"#);
+        let state = server_state(config).await;
+        let (status, body) = post_query(&state, "203.0.113.5", Some("secure_group")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(answer_ip(&body), DEFAULT_UPSTREAM_IP);
+        let (status, _) = post_query(&state, "203.0.113.5", Some("missing_group")).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // 默认不信任任何客户端
+        let config = override_config(&default_server.uri(), &secure_server.uri(), "[]");
+        let state = server_state(config).await;
+        let (status, body) = post_query(&state, "10.1.2.3", Some("secure_group")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(answer_ip(&body), DEFAULT_UPSTREAM_IP);
+
+        assert_eq!(*secure_count.lock().unwrap(), 0);
+
+        info!("Test completed: test_upstream_override_ignored_for_untrusted_client");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_override_uses_verified_client_ip() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_override_uses_verified_client_ip");
+
+        let (default_server, _) = setup_mock_doh_server(DEFAULT_UPSTREAM_IP).await;
+        let (secure_server, secure_count) = setup_mock_doh_server(SECURE_GROUP_IP).await;
+        let mut config = override_config(&default_server.uri(), &secure_server.uri(), r#"["10.0.0.0/8"]"#);
+        config.http.trusted_proxies = vec!["192.0.2.100".to_string()];
+        let app = DoHServer::new(config, false).build_application_components().await.unwrap().app;
+        let send = |peer_ip: &'static str, forwarded_for: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(query_request(peer_ip, Some(forwarded_for), Some("secure_group"))).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                answer_ip(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+            }
+        };
+
+        // 不受信任的连接伪造受信任客户端的转发头，覆盖头被忽略
+        assert_eq!(send("203.0.113.5", "10.1.2.3").await, DEFAULT_UPSTREAM_IP);
+        assert_eq!(*secure_count.lock().unwrap(), 0);
+
+        // 受信任代理转发的受信任客户端可以指定上游组
+        assert_eq!(send("192.0.2.100", "10.1.2.3").await, SECURE_GROUP_IP);
+        assert_eq!(*secure_count.lock().unwrap(), 1);
+
+        info!("Test completed: test_upstream_override_uses_verified_client_ip");
+    }
+
+    #[test]
+    fn test_upstream_override_config_validation() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_override_config_validation");
+
+        let default_config = ServerConfig::default();
+        assert!(default_config.http.trusted_override_ips.is_empty());
+        assert_eq!(default_config.http.upstream_override_header, "X-Upstream-Group");
+
+        let mut config = override_config("https://a.example.com", "https://b.example.com", r#"["10.0.0.0/8", "2001:db8::1"]"#);
+        assert!(config.test().is_ok());
+        assert!(config.is_trusted_override_ip("10.20.30.40".parse().unwrap()));
+        assert!(config.is_trusted_override_ip("::ffff:10.0.0.1".parse().unwrap()));
+        assert!(config.is_trusted_override_ip("2001:db8::1".parse().unwrap()));
+        assert!(!config.is_trusted_override_ip("192.168.1.1".parse().unwrap()));
+
+        // 列表项必须是 IP 地址或 CIDR 网段
+        config.http.trusted_override_ips.push("not-an-ip".to_string());
+        assert!(config.test().is_err());
+        config.http.trusted_override_ips.pop();
+
+        // 请求头名称必须合法
+        config.http.upstream_override_header = "X Upstream".to_string();
+        assert!(config.test().is_err());
+
+        info!("Test completed: test_upstream_override_config_validation");
+    }
+}
@@ -0,0 +1,116 @@
+// tests/server/upstream_parse_error_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use reqwest::StatusCode;
+    use tracing::info;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::metrics::METRICS;
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{extract_ip_addresses, mount_doh_answer, test_with_server_config};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 80);
+
+    // 返回 HTML 错误页面的上游（如配置错误的 DoH URL）
+    async fn html_upstream() -> MockServer {
+        let mock = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_raw("<html><body><h1>Welcome to nginx!</h1></body></html>", "text/html; charset=utf-8"))
+            .mount(&mock)
+            .await;
+        mock
+    }
+
+    // 内容类型正确但响应体损坏的上游
+    async fn corrupt_upstream() -> MockServer {
+        let mock = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                .set_body_bytes(vec![0x12, 0x34, 0x81]))
+            .mount(&mock)
+            .await;
+        mock
+    }
+
+    // 将指定上游按顺序放在默认模拟上游之前
+    fn prepend_resolvers(config: &mut ServerConfig, uris: &[String]) {
+        let template = config.dns.upstream.resolvers[0].clone();
+        for (index, uri) in uris.iter().enumerate() {
+            let mut resolver = template.clone();
+            resolver.address = format!("{}/dns-query", uri);
+            config.dns.upstream.resolvers.insert(index, resolver);
+        }
+    }
+
+    async fn query(server_addr: &str) -> reqwest::Response {
+        let query = create_test_query("parse.example.com", RecordType::A);
+        let url = format!("{}/dns-query?dns={}", server_addr, URL_SAFE_NO_PAD.encode(query.to_vec().unwrap()));
+        reqwest::get(url).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unparsable_upstream_response_fails_over() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_unparsable_upstream_response_fails_over");
+
+        let html = html_upstream().await;
+        let corrupt = corrupt_upstream().await;
+        let html_url = format!("{}/dns-query", html.uri());
+        let corrupt_url = format!("{}/dns-query", corrupt.uri());
+        let html_errors = METRICS.upstream_parse_errors_total().with_label_values(&[&html_url]);
+        let corrupt_errors = METRICS.upstream_parse_errors_total().with_label_values(&[&corrupt_url]);
+        // 模拟服务器的端口可能被其他测试复用，按增量断言
+        let before = (html_errors.get(), corrupt_errors.get());
+
+        let uris = vec![html.uri(), corrupt.uri()];
+        test_with_server_config(move |config| prepend_resolvers(config, &uris), |server_addr, mock| async move {
+            mount_doh_answer(&mock, ANSWER_IP).await;
+
+            // HTML 页面与损坏的响应体均被丢弃，由下一个上游应答
+            let response = query(&server_addr).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let message = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+            assert_eq!(message.response_code(), ResponseCode::NoError);
+            assert_eq!(extract_ip_addresses(&message), vec![ANSWER_IP.to_string()]);
+
+            assert_eq!(html.received_requests().await.unwrap().len(), 1);
+            assert_eq!(corrupt.received_requests().await.unwrap().len(), 1);
+            assert_eq!(html_errors.get(), before.0 + 1);
+            assert_eq!(corrupt_errors.get(), before.1 + 1);
+        }).await;
+
+        info!("Test completed: test_unparsable_upstream_response_fails_over");
+    }
+
+    #[tokio::test]
+    async fn test_all_upstreams_unparsable_returns_servfail() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_all_upstreams_unparsable_returns_servfail");
+
+        test_with_server_config(|_| {}, |server_addr, mock| async move {
+            Mock::given(matchers::method("POST"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_raw("<html>Bad Gateway</html>", "text/html"))
+                .mount(&mock)
+                .await;
+
+            // 没有可用的应答时返回 SERVFAIL，而不是 HTTP 500
+            let response = query(&server_addr).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let message = Message::from_vec(&response.bytes().await.unwrap()).unwrap();
+            assert_eq!(message.response_code(), ResponseCode::ServFail);
+        }).await;
+
+        info!("Test completed: test_all_upstreams_unparsable_returns_servfail");
+    }
+}
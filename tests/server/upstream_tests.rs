@@ -2,17 +2,22 @@
 
 #[cfg(test)]
 mod tests {
-    use std::net::Ipv4Addr;
+    use std::net::{Ipv4Addr, SocketAddr};
     use std::sync::Arc;
     
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, UdpSocket};
+    
     use tracing::info;
-    use hickory_proto::op::ResponseCode;
+    use hickory_proto::op::{Message, ResponseCode};
     use hickory_proto::rr::RecordType;
     use reqwest::Client;
     
     use oxide_wdns::server::config::{ResolverConfig, ResolverProtocol, ServerConfig};
     use oxide_wdns::server::upstream::{UpstreamManager, UpstreamSelection};
     use oxide_wdns::server::routing::Router;
+    use oxide_wdns::server::error::ServerError;
+    use oxide_wdns::server::metrics::METRICS;
     use oxide_wdns::common::consts::CONTENT_TYPE_DNS_MESSAGE;
     
     // 引入 wiremock 库和公共测试模块
@@ -20,7 +25,7 @@ mod tests {
     use wiremock::matchers::{method, path};
     
     // 导入公共测试工具
-    use crate::server::mock_http_server::{create_test_query, create_test_response, setup_mock_doh_server};
+    use crate::server::mock_http_server::{create_test_query, create_test_response, find_free_port, setup_mock_doh_server};
     
     // 创建简单的ServerConfig用于测试
     fn create_test_config() -> ServerConfig {
@@ -69,6 +74,10 @@ mod tests {
             ResolverConfig {
                 address: format!("{}/dns-query", mock_server.uri()),
                 protocol: ResolverProtocol::Doh,
+                path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                ca_cert_path: None,
             }
         ];
 
@@ -137,6 +146,10 @@ mod tests {
             ResolverConfig {
                 address: format!("{}/dns-query", mock_server.uri()),
                 protocol: ResolverProtocol::Doh,
+                path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                ca_cert_path: None,
             }
         ];
         
@@ -155,4 +168,535 @@ mod tests {
         
         info!("Test completed: test_upstream_resolve_doh_get");
     }
-} 
\ No newline at end of file
+    
+    // 在 IPv6 回环地址上启动模拟 UDP DNS 服务器
+    async fn start_ipv6_udp_dns_server(response_ip: Ipv4Addr) -> SocketAddr {
+        let socket = UdpSocket::bind("[::1]:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let query = Message::from_vec(&buf[..len]).unwrap();
+                let response = create_test_response(&query, response_ip).to_vec().unwrap();
+                let _ = socket.send_to(&response, peer).await;
+            }
+        });
+        addr
+    }
+    
+    // 在 IPv6 回环地址上启动模拟 TCP DNS 服务器
+    async fn start_ipv6_tcp_dns_server(response_ip: Ipv4Addr) -> SocketAddr {
+        let listener = TcpListener::bind("[::1]:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    // DNS over TCP 使用 2 字节长度前缀
+                    while let Ok(len) = stream.read_u16().await {
+                        let mut buf = vec![0u8; len as usize];
+                        if stream.read_exact(&mut buf).await.is_err() {
+                            break;
+                        }
+                        let query = Message::from_vec(&buf).unwrap();
+                        let response = create_test_response(&query, response_ip).to_vec().unwrap();
+                        let _ = stream.write_u16(response.len() as u16).await;
+                        let _ = stream.write_all(&response).await;
+                    }
+                });
+            }
+        });
+        addr
+    }
+    
+    #[tokio::test]
+    async fn test_upstream_resolve_ipv6_udp_and_tcp() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_resolve_ipv6_udp_and_tcp");
+        
+        let udp_addr = start_ipv6_udp_dns_server(Ipv4Addr::new(192, 0, 2, 6)).await;
+        let tcp_addr = start_ipv6_tcp_dns_server(Ipv4Addr::new(192, 0, 2, 7)).await;
+        
+        for (address, protocol, expected) in [
+            (udp_addr.to_string(), ResolverProtocol::Udp, Ipv4Addr::new(192, 0, 2, 6)),
+            (tcp_addr.to_string(), ResolverProtocol::Tcp, Ipv4Addr::new(192, 0, 2, 7)),
+        ] {
+            // 地址形如 [::1]:端口
+            info!("Resolving via IPv6 {:?} upstream {}", protocol, address);
+            assert!(address.starts_with("[::1]:"));
+            
+            let mut config = create_test_config();
+            config.dns.upstream.resolvers = vec![ResolverConfig {
+                address,
+                protocol,
+                path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                ca_cert_path: None,
+            }];
+            assert!(config.test().is_ok());
+            
+            let upstream_manager = UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap();
+            let query = create_test_query("example.com", RecordType::A);
+            let response = upstream_manager.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+            
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+            let answer = response.answers().first().expect("Response should contain an answer");
+            assert_eq!(answer.data().and_then(|d| d.as_a()).map(|a| a.0), Some(expected));
+        }
+        
+        info!("Test completed: test_upstream_resolve_ipv6_udp_and_tcp");
+    }
+    
+    #[tokio::test]
+    async fn test_upstream_ipv6_address_parsing() {
+        let resolver = |address: &str, protocol: ResolverProtocol| ResolverConfig {
+            address: address.to_string(),
+            protocol,
+            path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            ca_cert_path: None,
+        };
+        
+        // 各协议的 IPv6 地址均应被接受
+        let valid = [
+            resolver("[2001:4860:4860::8888]:53", ResolverProtocol::Udp),
+            resolver("[2001:4860:4860::8844]:53", ResolverProtocol::Tcp),
+            resolver("dns.google@[2001:4860:4860::8888]:853", ResolverProtocol::Dot),
+            resolver("https://[2001:4860:4860::8888]/dns-query", ResolverProtocol::Doh),
+        ];
+        
+        let (tls_name, addr) = valid[2].parse_socket_addr().unwrap();
+        assert_eq!(tls_name.as_deref(), Some("dns.google"));
+        assert_eq!(addr, "[2001:4860:4860::8888]:853".parse::<SocketAddr>().unwrap());
+        
+        let mut config = create_test_config();
+        config.dns.upstream.resolvers = valid.to_vec();
+        assert!(config.test().is_ok());
+        assert!(UpstreamManager::new(Arc::new(config), Client::new()).await.is_ok());
+        
+        // 格式错误的地址应在加载配置时给出明确提示
+        let invalid = [
+            (resolver("2001:4860:4860::8888:53", ResolverProtocol::Udp), "bracketed"),
+            (resolver("[2001:4860:4860::8888]", ResolverProtocol::Tcp), "missing a port"),
+            (resolver("8.8.8.8", ResolverProtocol::Udp), "missing a port"),
+            (resolver("[2001:4860:4860::8888]:853", ResolverProtocol::Dot), "domain@"),
+            (resolver("dns.google@2001:4860:4860::8888:853", ResolverProtocol::Dot), "bracketed"),
+            (resolver("@[2001:4860:4860::8888]:853", ResolverProtocol::Dot), "TLS server name"),
+        ];
+        for (resolver, expected) in invalid {
+            let mut config = create_test_config();
+            config.dns.upstream.resolvers = vec![resolver.clone()];
+            let err = config.test().expect_err(&format!("'{}' should be rejected", resolver.address));
+            assert!(err.to_string().contains(expected), "Unexpected error for '{}': {}", resolver.address, err);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_upstream_doh_resolver_path() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_doh_resolver_path");
+        
+        let resolver = |address: &str, resolver_path: Option<&str>| ResolverConfig {
+            address: address.to_string(),
+            protocol: ResolverProtocol::Doh,
+            path: resolver_path.map(str::to_string),
+            client_cert_path: None,
+            client_key_path: None,
+            ca_cert_path: None,
+        };
+        
+        // 主机与路径分开配置时组合为完整 URL，完整 URL 形式保持不变
+        assert_eq!(resolver("cloudflare-dns.com", Some("/dns-query")).doh_url(), "https://cloudflare-dns.com/dns-query");
+        assert_eq!(resolver("cloudflare-dns.com", None).doh_url(), "https://cloudflare-dns.com/dns-query");
+        assert_eq!(resolver("https://dns.example.com/", Some("/custom")).doh_url(), "https://dns.example.com/custom");
+        assert_eq!(resolver("https://dns.google/dns-query", None).doh_url(), "https://dns.google/dns-query");
+        
+        let mut config = create_test_config();
+        config.dns.upstream.resolvers = vec![
+            resolver("cloudflare-dns.com", Some("/dns-query")),
+            resolver("https://dns.google/dns-query", None),
+        ];
+        assert!(config.test().is_ok());
+        
+        // 路径格式错误、与 address 中的路径重复或用于非 DoH 解析器时拒绝
+        let mut udp_with_path = resolver("8.8.8.8:53", Some("/dns-query"));
+        udp_with_path.protocol = ResolverProtocol::Udp;
+        for invalid in [
+            resolver("cloudflare-dns.com", Some("dns-query")),
+            resolver("cloudflare-dns.com", Some("/dns-query?x=1")),
+            resolver("https://dns.google/dns-query", Some("/dns-query")),
+            resolver("http://dns.example.com", Some("/dns-query")),
+            udp_with_path,
+        ] {
+            let mut config = create_test_config();
+            config.dns.upstream.resolvers = vec![invalid.clone()];
+            assert!(config.test().is_err(), "{:?} should be rejected", invalid);
+        }
+        
+        // 查询发送到 address 与 path 组合后的 URL
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/custom-doh"))
+            .respond_with(|request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(create_test_response(&query, Ipv4Addr::new(192, 0, 2, 8)).to_vec().unwrap())
+            })
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        
+        let mut config = create_test_config();
+        config.dns.upstream.resolvers = vec![resolver(&mock_server.uri(), Some("/custom-doh"))];
+        let upstream_manager = UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap();
+        let query = create_test_query("example.com", RecordType::A);
+        let response = upstream_manager.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        
+        info!("Test completed: test_upstream_doh_resolver_path");
+    }
+    
+    #[tokio::test]
+    async fn test_upstream_doh_hung_tls_handshake_fails_fast() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_doh_hung_tls_handshake_fails_fast");
+        
+        // 接受 TCP 连接但从不完成 TLS 握手的上游
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                held.push(stream);
+            }
+        });
+        
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "https://{}/dns-query"
+                protocol: doh
+            query_timeout: 30
+          http_client:
+            timeout: 30
+            connect_timeout: 1
+            tls_handshake_allowance: 1
+        "#, upstream_addr);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        assert!(config.test().is_ok());
+        
+        let http_client = oxide_wdns::server::create_http_client(&config).unwrap();
+        let upstream_manager = UpstreamManager::new(Arc::new(config), http_client).await.unwrap();
+        
+        // 连接阶段超时应远早于请求总超时触发
+        let query = create_test_query("example.com", RecordType::A);
+        let started = std::time::Instant::now();
+        let result = upstream_manager.resolve(&query, UpstreamSelection::Global, None, None).await;
+        let elapsed = started.elapsed();
+        info!("Hung TLS handshake failed after {:?}", elapsed);
+        
+        assert!(result.is_err());
+        assert!(elapsed < std::time::Duration::from_secs(10), "Connection setup took {:?}", elapsed);
+        
+        info!("Test completed: test_upstream_doh_hung_tls_handshake_fails_fast");
+    }
+    
+    #[test]
+    fn test_http_client_timeout_validation() {
+        let mut config = create_test_config();
+        assert_eq!(config.dns.http_client.connect_timeout, 2);
+        assert_eq!(config.dns.http_client.tls_handshake_allowance, 3);
+        assert_eq!(config.http_client_connect_timeout(), std::time::Duration::from_secs(5));
+        assert!(config.test().is_ok());
+        
+        // 连接超时不能超过请求总超时
+        config.dns.http_client.timeout = 5;
+        config.dns.http_client.connect_timeout = 6;
+        assert!(config.test().is_err());
+        
+        config.dns.http_client.connect_timeout = 0;
+        assert!(config.test().is_err());
+        
+        // 建立连接的总时限（connect_timeout + tls_handshake_allowance）同样不能超过请求总超时
+        config.dns.http_client.connect_timeout = 2;
+        config.dns.http_client.tls_handshake_allowance = 4;
+        assert!(config.test().is_err());
+        
+        config.dns.http_client.tls_handshake_allowance = 3;
+        assert!(config.test().is_ok());
+        
+        // 不为 TLS 握手额外预留时间也是合法的
+        config.dns.http_client.tls_handshake_allowance = 0;
+        assert!(config.test().is_ok());
+    }
+    
+    // 创建带有按状态码重试配置的 DoH 上游管理器
+    async fn create_retry_upstream_manager(mock_server: &MockServer, retry_status: Vec<u16>, max_retries: u32) -> UpstreamManager {
+        let mut config = create_test_config();
+        config.dns.upstream.resolvers = vec![
+            ResolverConfig {
+                address: format!("{}/dns-query", mock_server.uri()),
+                protocol: ResolverProtocol::Doh,
+                path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                ca_cert_path: None,
+            }
+        ];
+        config.dns.http_client.retry_status = retry_status;
+        config.dns.http_client.max_retries = max_retries;
+        UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap()
+    }
+    
+    // 挂载前 n 次返回指定状态码的模拟响应
+    async fn mount_error_status(mock_server: &MockServer, status: u16, retry_after: Option<&str>, times: u64) {
+        let mut template = ResponseTemplate::new(status);
+        if let Some(retry_after) = retry_after {
+            template = template.insert_header("Retry-After", retry_after);
+        }
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .respond_with(template)
+            .up_to_n_times(times)
+            .mount(mock_server)
+            .await;
+    }
+    
+    #[tokio::test]
+    async fn test_upstream_doh_retry_on_status() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_doh_retry_on_status");
+        
+        let query = create_test_query("retry.example.com", RecordType::A);
+        
+        // 503 与 429 各出现一次后成功，留在同一上游上完成查询
+        let mock_server = MockServer::start().await;
+        mount_error_status(&mock_server, 503, None, 1).await;
+        mount_error_status(&mock_server, 429, Some("0"), 1).await;
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .respond_with(move |request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(create_test_response(&query, Ipv4Addr::new(192, 0, 2, 70)).to_vec().unwrap())
+            })
+            .mount(&mock_server)
+            .await;
+        
+        let upstream = create_retry_upstream_manager(&mock_server, vec![429, 502, 503], 2).await;
+        let response = upstream.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 3);
+        
+        // 重试次数用尽后返回错误
+        let mock_server = MockServer::start().await;
+        mount_error_status(&mock_server, 503, None, 10).await;
+        let upstream = create_retry_upstream_manager(&mock_server, vec![503], 2).await;
+        assert!(upstream.resolve(&query, UpstreamSelection::Global, None, None).await.is_err());
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 3);
+        
+        // 未配置的状态码以及未启用重试时不重试
+        let mock_server = MockServer::start().await;
+        mount_error_status(&mock_server, 500, None, 10).await;
+        let upstream = create_retry_upstream_manager(&mock_server, vec![503], 2).await;
+        assert!(upstream.resolve(&query, UpstreamSelection::Global, None, None).await.is_err());
+        let upstream = create_retry_upstream_manager(&mock_server, Vec::new(), 2).await;
+        assert!(upstream.resolve(&query, UpstreamSelection::Global, None, None).await.is_err());
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 2);
+        
+        info!("Test completed: test_upstream_doh_retry_on_status");
+    }
+    
+    #[tokio::test]
+    async fn test_upstream_doh_retry_after_capped_by_query_timeout() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_doh_retry_after_capped_by_query_timeout");
+        
+        let query = create_test_query("busy.example.com", RecordType::A);
+        
+        // Retry-After 超出 query_timeout（3 秒）时立即失败，不再等待
+        let mock_server = MockServer::start().await;
+        mount_error_status(&mock_server, 503, Some("10"), 10).await;
+        let upstream = create_retry_upstream_manager(&mock_server, vec![503], 2).await;
+        let start = std::time::Instant::now();
+        assert!(upstream.resolve(&query, UpstreamSelection::Global, None, None).await.is_err());
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+        
+        // Retry-After 在时间上限内时等待指定时间后重试
+        let mock_server = MockServer::start().await;
+        mount_error_status(&mock_server, 503, Some("1"), 1).await;
+        mount_error_status(&mock_server, 418, None, 1).await;
+        let upstream = create_retry_upstream_manager(&mock_server, vec![503], 2).await;
+        let start = std::time::Instant::now();
+        assert!(upstream.resolve(&query, UpstreamSelection::Global, None, None).await.is_err());
+        assert!(start.elapsed() >= std::time::Duration::from_secs(1));
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 2);
+        
+        info!("Test completed: test_upstream_doh_retry_after_capped_by_query_timeout");
+    }
+    
+    #[test]
+    fn test_http_client_retry_validation() {
+        let mut config = create_test_config();
+        assert!(config.dns.http_client.retry_status.is_empty());
+        assert_eq!(config.dns.http_client.max_retries, 2);
+        
+        config.dns.http_client.retry_status = vec![429, 502, 503];
+        assert!(config.test().is_ok());
+        
+        // 只允许错误状态码
+        config.dns.http_client.retry_status = vec![200];
+        assert!(config.test().is_err());
+        config.dns.http_client.retry_status = vec![503];
+        
+        // 重试次数有上限
+        config.dns.http_client.max_retries = 11;
+        assert!(config.test().is_err());
+    }
+    
+    // 挂载始终返回指定响应码的模拟 DoH 上游
+    async fn mount_rcode_response(mock_server: &MockServer, rcode: ResponseCode) {
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .respond_with(move |request: &wiremock::Request| {
+                let query = Message::from_vec(&request.body).unwrap();
+                let mut response = create_test_response(&query, Ipv4Addr::new(192, 0, 2, 80));
+                if rcode != ResponseCode::NoError {
+                    response.take_answers();
+                    response.set_response_code(rcode);
+                }
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", CONTENT_TYPE_DNS_MESSAGE)
+                    .set_body_bytes(response.to_vec().unwrap())
+            })
+            .mount(mock_server)
+            .await;
+    }
+    
+    #[tokio::test]
+    async fn test_upstream_group_fallthrough() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_group_fallthrough");
+        
+        let failing_server = MockServer::start().await;
+        mount_error_status(&failing_server, 503, None, 100).await;
+        let nxdomain_server = MockServer::start().await;
+        mount_rcode_response(&nxdomain_server, ResponseCode::NXDomain).await;
+        let ok_server = MockServer::start().await;
+        mount_rcode_response(&ok_server, ResponseCode::NoError).await;
+        
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "8.8.8.8:53"
+                protocol: udp
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "failing_group"
+                resolvers:
+                  - address: "{}/dns-query"
+                    protocol: doh
+              - name: "nxdomain_group"
+                resolvers:
+                  - address: "{}/dns-query"
+                    protocol: doh
+              - name: "ok_group"
+                resolvers:
+                  - address: "{}/dns-query"
+                    protocol: doh
+        "#, failing_server.uri(), nxdomain_server.uri(), ok_server.uri());
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        let upstream = UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap();
+        let query = create_test_query("fallthrough.example.com", RecordType::A);
+        let groups = |names: &[&str]| UpstreamSelection::Groups(names.iter().map(|n| n.to_string()).collect());
+        
+        // 首个组不可达时回退到下一组
+        let fallthrough = METRICS.upstream_group_fallthrough_total().with_label_values(&["failing_group", "ok_group"]);
+        let before = fallthrough.get();
+        let response = upstream.resolve(&query, groups(&["failing_group", "ok_group"]), None, None).await.unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(fallthrough.get(), before + 1);
+        assert_eq!(ok_server.received_requests().await.unwrap().len(), 1);
+        
+        // NXDOMAIN 是有效的负响应，不触发回退
+        let response = upstream.resolve(&query, groups(&["nxdomain_group", "ok_group"]), None, None).await.unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert_eq!(ok_server.received_requests().await.unwrap().len(), 1);
+        
+        // 所有组都不可达时返回最后一个错误
+        let result = upstream.resolve(&query, groups(&["failing_group", "failing_group"]), None, None).await;
+        assert!(matches!(result, Err(ServerError::UpstreamUnavailable(_))));
+        
+        info!("Test completed: test_upstream_group_fallthrough");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_doh_resolver_failover_within_group() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_upstream_doh_resolver_failover_within_group");
+        
+        // 第一个解析器的端口上没有监听，连接被拒绝
+        let dead_url = format!("http://127.0.0.1:{}/dns-query", find_free_port().await);
+        let (healthy_server, healthy_count) = setup_mock_doh_server(Ipv4Addr::new(192, 0, 2, 91)).await;
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}"
+                protocol: doh
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 3
+        "#, dead_url, healthy_server.uri());
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        let upstream = UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap();
+        let query = create_test_query("failover.example.com", RecordType::A);
+        
+        // 不可达的解析器被跳过，由组内下一个解析器应答
+        let response = upstream.resolve(&query, UpstreamSelection::Global, None, None).await.unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(*healthy_count.lock().unwrap(), 1);
+        
+        // 组内所有解析器都不可达时返回最后一个错误
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}"
+                protocol: doh
+              - address: "{}"
+                protocol: doh
+            query_timeout: 3
+        "#, dead_url, dead_url);
+        let config: ServerConfig = serde_yaml::from_str(&config_str).unwrap();
+        let upstream = UpstreamManager::new(Arc::new(config), Client::new()).await.unwrap();
+        let result = upstream.resolve(&query, UpstreamSelection::Global, None, None).await;
+        assert!(matches!(result, Err(ServerError::UpstreamUnavailable(_))), "unexpected result: {:?}", result);
+        
+        info!("Test completed: test_upstream_doh_resolver_failover_within_group");
+    }
+}
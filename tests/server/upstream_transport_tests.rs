@@ -0,0 +1,167 @@
+// tests/server/upstream_transport_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+    use std::sync::{Arc, Mutex};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use tracing::info;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::error::ServerError;
+    use oxide_wdns::server::upstream::{TransportFuture, UpstreamManager, UpstreamSelection, UpstreamTransport};
+    use crate::server::mock_http_server::{create_test_query, create_test_response};
+
+    const ANSWER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+
+    // 模拟上游的行为
+    #[derive(Clone, Copy)]
+    enum Behavior {
+        // 返回 A 记录
+        Answer,
+        // 返回指定响应码的空响应
+        Rcode(ResponseCode),
+        // 上游不可达
+        Unavailable,
+    }
+
+    // 传输替身：按上游地址返回预设结果，并记录每次查询的上游
+    #[derive(Default)]
+    struct MockTransport {
+        behaviors: Mutex<HashMap<String, Behavior>>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl MockTransport {
+        fn with(behaviors: &[(&str, Behavior)]) -> Arc<Self> {
+            let transport = Self::default();
+            for (upstream, behavior) in behaviors {
+                transport.set(upstream, *behavior);
+            }
+            Arc::new(transport)
+        }
+
+        fn set(&self, upstream: &str, behavior: Behavior) {
+            self.behaviors.lock().unwrap().insert(upstream.to_string(), behavior);
+        }
+
+        // 取出并清空已记录的查询
+        fn take_calls(&self) -> Vec<String> {
+            std::mem::take(&mut *self.calls.lock().unwrap())
+        }
+    }
+
+    impl UpstreamTransport for MockTransport {
+        fn exchange<'a>(&'a self, upstream: &'a str, query: &'a Message) -> TransportFuture<'a> {
+            self.calls.lock().unwrap().push(upstream.to_string());
+            let behavior = self.behaviors.lock().unwrap().get(upstream).copied().unwrap_or(Behavior::Unavailable);
+            Box::pin(async move {
+                match behavior {
+                    Behavior::Answer => Ok(create_test_response(query, ANSWER_IP)),
+                    Behavior::Rcode(rcode) => {
+                        let mut response = create_test_response(query, ANSWER_IP);
+                        response.take_answers();
+                        response.set_response_code(rcode);
+                        Ok(response)
+                    },
+                    Behavior::Unavailable => Err(ServerError::UpstreamUnavailable(format!("{} is down", upstream))),
+                }
+            })
+        }
+    }
+
+    // 全局上游有两个 DoH 上游并跟踪失败率；primary 组使用 DoH，backup 组使用 UDP
+    fn transport_config() -> ServerConfig {
+        let config_str = r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "https://first.example.com/dns-query"
+                protocol: doh
+              - address: "https://second.example.com/dns-query"
+                protocol: doh
+            failure_ratio_threshold: 0.5
+            rolling_window_secs: 60
+          routing:
+            enabled: true
+            upstream_groups:
+              - name: "primary"
+                resolvers:
+                  - address: "https://primary.example.com/dns-query"
+                    protocol: doh
+              - name: "backup"
+                resolvers:
+                  - address: "192.0.2.53:53"
+                    protocol: udp
+        "#;
+        serde_yaml::from_str(config_str).unwrap()
+    }
+
+    async fn resolve(upstream: &UpstreamManager, selection: UpstreamSelection) -> Result<Message, ServerError> {
+        let query = create_test_query("example.com", RecordType::A);
+        upstream.resolve(&query, selection, None, None).await
+    }
+
+    fn groups(names: &[&str]) -> UpstreamSelection {
+        UpstreamSelection::Groups(names.iter().map(|name| name.to_string()).collect())
+    }
+
+    #[tokio::test]
+    async fn test_injected_transport_group_fallthrough() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_injected_transport_group_fallthrough");
+
+        let transport = MockTransport::with(&[("192.0.2.53:53", Behavior::Answer)]);
+        let upstream = UpstreamManager::with_transport(Arc::new(transport_config()), transport.clone()).await.unwrap();
+
+        // primary 不可达时回退到 backup，非 DoH 上游组同样通过注入的传输发送
+        let response = resolve(&upstream, groups(&["primary", "backup"])).await.unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(transport.take_calls(), vec!["https://primary.example.com/dns-query", "192.0.2.53:53"]);
+
+        // 负响应是有效结果，不触发回退
+        transport.set("https://primary.example.com/dns-query", Behavior::Rcode(ResponseCode::NXDomain));
+        let response = resolve(&upstream, groups(&["primary", "backup"])).await.unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert_eq!(transport.take_calls(), vec!["https://primary.example.com/dns-query"]);
+
+        // 所有组都不可达时返回最后一个组的错误
+        transport.set("https://primary.example.com/dns-query", Behavior::Unavailable);
+        transport.set("192.0.2.53:53", Behavior::Unavailable);
+        let result = resolve(&upstream, groups(&["primary", "backup"])).await;
+        assert!(matches!(result, Err(ServerError::UpstreamUnavailable(reason)) if reason.contains("192.0.2.53:53")));
+
+        info!("Test completed: test_injected_transport_group_fallthrough");
+    }
+
+    #[tokio::test]
+    async fn test_injected_transport_degrades_failing_upstream() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_injected_transport_degrades_failing_upstream");
+
+        let transport = MockTransport::with(&[
+            ("https://first.example.com/dns-query", Behavior::Rcode(ResponseCode::ServFail)),
+            ("https://second.example.com/dns-query", Behavior::Answer),
+        ]);
+        let upstream = UpstreamManager::with_transport(Arc::new(transport_config()), transport.clone()).await.unwrap();
+
+        // 样本数不足前，失败的上游仍是首选
+        for _ in 0..10 {
+            let response = resolve(&upstream, UpstreamSelection::Global).await.unwrap();
+            assert_eq!(response.response_code(), ResponseCode::ServFail);
+        }
+        assert!(transport.take_calls().iter().all(|call| call == "https://first.example.com/dns-query"));
+
+        // 失败率超过阈值后被降级，查询转向第二个上游
+        let response = resolve(&upstream, UpstreamSelection::Global).await.unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(transport.take_calls(), vec!["https://second.example.com/dns-query"]);
+
+        info!("Test completed: test_injected_transport_degrades_failing_upstream");
+    }
+}
@@ -0,0 +1,68 @@
+// tests/server/version_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use tokio::time::Instant;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use oxide_wdns::server::version::version_routes;
+    use oxide_wdns::server::DoHServer;
+    use crate::server::test_helpers::mock_upstream_config;
+
+    async fn get_version(app: Router) -> (StatusCode, serde_json::Value) {
+        let request = Request::get("/version").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_version_endpoint_reports_build_and_uptime() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_version_endpoint_reports_build_and_uptime");
+
+        let started_at = Instant::now() - Duration::from_secs(42);
+        let (status, body) = get_version(version_routes(started_at)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!body["git_commit"].as_str().unwrap().is_empty());
+        assert!(body["uptime_secs"].as_u64().unwrap() >= 42);
+
+        // 构建时间为 RFC 3339 UTC 格式，如 2024-01-02T03:04:05Z
+        let timestamp = body["build_timestamp"].as_str().unwrap();
+        assert_eq!(timestamp.len(), 20);
+        assert_eq!(&timestamp[10..11], "T");
+        assert!(timestamp.ends_with('Z'));
+
+        info!("Test completed: test_version_endpoint_reports_build_and_uptime");
+    }
+
+    #[tokio::test]
+    async fn test_version_endpoint_on_admin_router() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_version_endpoint_on_admin_router");
+
+        // 未配置管理监听时与健康检查一同挂载在 DoH 监听上
+        let config = mock_upstream_config(8053, "http://127.0.0.1:9");
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        let (status, body) = get_version(components.app).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["uptime_secs"].as_u64().unwrap() < 60);
+
+        // 配置了管理监听时只在管理路由上提供
+        let mut config = mock_upstream_config(8053, "http://127.0.0.1:9");
+        config.http.admin_listen_addr = Some("127.0.0.1:9090".parse().unwrap());
+        let components = DoHServer::new(config, false).build_application_components().await.unwrap();
+        assert_eq!(get_version(components.app).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get_version(components.admin_app.unwrap()).await.0, StatusCode::OK);
+
+        info!("Test completed: test_version_endpoint_on_admin_router");
+    }
+}
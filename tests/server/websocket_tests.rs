@@ -0,0 +1,179 @@
+// tests/server/websocket_tests.rs
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::net::Ipv4Addr;
+    use std::time::{Duration, Instant};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use futures::{SinkExt, StreamExt};
+    use hickory_proto::op::{Message, ResponseCode};
+    use hickory_proto::rr::RecordType;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+    use tower::util::ServiceExt;
+    use tracing::info;
+    use wiremock::MockServer;
+    use oxide_wdns::server::config::ServerConfig;
+    use oxide_wdns::server::doh_handler::{doh_routes, ServerState};
+    use crate::server::mock_http_server::create_test_query;
+    use crate::server::test_helpers::{mount_doh_answer_with_delay, server_state};
+
+    const UPSTREAM_DELAY: Duration = Duration::from_millis(200);
+
+    fn websocket_config(upstream_uri: &str, websocket_enabled: bool, per_ip_concurrent: Option<u32>) -> ServerConfig {
+        let config_str = format!(r#"
+        http_server:
+          listen_addr: "127.0.0.1:8053"
+          websocket_enabled: {}
+          rate_limit:
+            enabled: {}
+            per_ip_concurrent: {}
+        dns_resolver:
+          upstream:
+            resolvers:
+              - address: "{}/dns-query"
+                protocol: doh
+            query_timeout: 5
+          cache:
+            enabled: false
+        "#, websocket_enabled, per_ip_concurrent.is_some(), per_ip_concurrent.unwrap_or(10), upstream_uri);
+        serde_yaml::from_str(&config_str).unwrap()
+    }
+
+    // 在随机端口上提供 DoH 路由，返回 WebSocket 端点 URL
+    async fn start_server(state: ServerState) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, doh_routes(state)).await.unwrap();
+        });
+        format!("ws://{}/dns-ws", addr)
+    }
+
+    // 构造指定 ID 的查询
+    fn query_with_id(index: u16) -> Message {
+        let mut query = create_test_query(&format!("host{}.example.com", index), RecordType::A);
+        query.set_id(index);
+        query
+    }
+
+    // 在同一连接上一次性发送全部查询，再收取全部响应，返回响应 ID 集合与总耗时
+    async fn exchange_concurrently(url: &str, count: u16) -> (HashSet<u16>, Duration) {
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let started = Instant::now();
+        for index in 1..=count {
+            let bytes = query_with_id(index).to_vec().unwrap();
+            socket.send(WsMessage::Binary(bytes.into())).await.unwrap();
+        }
+
+        let mut ids = HashSet::new();
+        while ids.len() < count as usize {
+            let frame = tokio::time::timeout(Duration::from_secs(5), socket.next()).await
+                .expect("timed out waiting for a response")
+                .unwrap()
+                .unwrap();
+            let WsMessage::Binary(bytes) = frame else {
+                panic!("unexpected frame: {:?}", frame);
+            };
+            let response = Message::from_vec(&bytes).unwrap();
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+            assert_eq!(response.answers().len(), 1);
+            // 问题部分与消息 ID 对应
+            assert_eq!(response.queries()[0].name().to_ascii(), format!("host{}.example.com.", response.id()));
+            assert!(ids.insert(response.id()), "duplicate response for id {}", response.id());
+        }
+        (ids, started.elapsed())
+    }
+
+    #[tokio::test]
+    async fn test_websocket_concurrent_queries() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_websocket_concurrent_queries");
+
+        let mock_server = MockServer::start().await;
+        mount_doh_answer_with_delay(&mock_server, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let url = start_server(server_state(websocket_config(&mock_server.uri(), true, None)).await).await;
+
+        // 10 个查询在同一连接上并发处理，所有响应都按 ID 返回
+        let (ids, elapsed) = exchange_concurrently(&url, 10).await;
+        assert_eq!(ids, (1..=10).collect::<HashSet<u16>>());
+        assert!(elapsed < UPSTREAM_DELAY * 5, "queries were not processed concurrently: {:?}", elapsed);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 10);
+
+        info!("Test completed: test_websocket_concurrent_queries");
+    }
+
+    #[tokio::test]
+    async fn test_websocket_in_flight_limit() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_websocket_in_flight_limit");
+
+        let mock_server = MockServer::start().await;
+        mount_doh_answer_with_delay(&mock_server, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let url = start_server(server_state(websocket_config(&mock_server.uri(), true, Some(2))).await).await;
+
+        // 每个连接同时最多处理 2 个查询，6 个查询至少需要 3 轮上游往返
+        let (ids, elapsed) = exchange_concurrently(&url, 6).await;
+        assert_eq!(ids.len(), 6);
+        assert!(elapsed >= UPSTREAM_DELAY * 3, "in-flight limit was not applied: {:?}", elapsed);
+
+        info!("Test completed: test_websocket_in_flight_limit");
+    }
+
+    #[tokio::test]
+    async fn test_websocket_text_frames_and_duplicate_ids() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_websocket_text_frames_and_duplicate_ids");
+
+        let mock_server = MockServer::start().await;
+        mount_doh_answer_with_delay(&mock_server, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let url = start_server(server_state(websocket_config(&mock_server.uri(), true, None)).await).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+
+        // 文本帧承载 base64url 编码的查询，第二个查询与在途查询 ID 相同，立即被拒绝
+        let encoded = URL_SAFE_NO_PAD.encode(query_with_id(7).to_vec().unwrap());
+        socket.send(WsMessage::Text(encoded.clone().into())).await.unwrap();
+        socket.send(WsMessage::Text(encoded.into())).await.unwrap();
+
+        let mut rcodes = Vec::new();
+        for _ in 0..2 {
+            let frame = socket.next().await.unwrap().unwrap();
+            let WsMessage::Text(text) = frame else {
+                panic!("unexpected frame: {:?}", frame);
+            };
+            let response = Message::from_vec(&URL_SAFE_NO_PAD.decode(text.as_str()).unwrap()).unwrap();
+            assert_eq!(response.id(), 7);
+            rcodes.push(response.response_code());
+        }
+        assert_eq!(rcodes, vec![ResponseCode::Refused, ResponseCode::NoError]);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+
+        info!("Test completed: test_websocket_text_frames_and_duplicate_ids");
+    }
+
+    #[tokio::test]
+    async fn test_websocket_disabled_by_default() {
+        // 启用 tracing 日志
+        let _ = tracing_subscriber::fmt().with_env_filter("debug").try_init();
+        info!("Starting test: test_websocket_disabled_by_default");
+
+        assert!(!ServerConfig::default().http.websocket_enabled);
+
+        let mock_server = MockServer::start().await;
+        mount_doh_answer_with_delay(&mock_server, Ipv4Addr::new(192, 0, 2, 1), UPSTREAM_DELAY).await;
+        let app = doh_routes(server_state(websocket_config(&mock_server.uri(), false, None)).await);
+        let response = app
+            .oneshot(Request::get("/dns-ws").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        info!("Test completed: test_websocket_disabled_by_default");
+    }
+}